@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// Errors returned by [`Client`](crate::Client) calls.
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or the response body not read, after retries.
+    Transport(reqwest::Error),
+    /// The API answered with an error status; `message` is the server's explanation.
+    Api { status: u16, message: String },
+    /// The response body did not have the expected shape.
+    Decode(String),
+}
+
+impl Error {
+    /// HTTP status of an API error, if the server answered.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Api { status, .. } => Some(*status),
+            Self::Transport(e) => e.status().map(|s| s.as_u16()),
+            Self::Decode(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(e) => write!(f, "transport error: {e}"),
+            Self::Api { status, message } => write!(f, "API error {status}: {message}"),
+            Self::Decode(msg) => write!(f, "unexpected response: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Transport(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
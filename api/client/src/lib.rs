@@ -0,0 +1,754 @@
+//! Async client for the GeoPop API.
+//!
+//! One method per endpoint, typed with the server's own request and response
+//! structs from `geopop-types` (re-exported as [`types`]). Transient failures —
+//! connection errors, timeouts, 429 and 502–504 — are retried with exponential
+//! backoff, honouring `Retry-After`. Job submission is only retried when the
+//! request never reached the server, so a retry cannot queue a job twice.
+//!
+//! ```no_run
+//! # async fn demo() -> geopop_client::Result<()> {
+//! use geopop_client::{types::ExposureQuery, Client, Include};
+//!
+//! let client = Client::builder("http://localhost:8080").api_key("secret").build()?;
+//! let query = ExposureQuery {
+//!     lat: 6.9271, lon: 79.8612, radius: 10.0,
+//!     decay: None, mode: None, coast_km: None, max_elevation_m: None, rings: None, histogram: None,
+//!     top_cells: None, land_only: None, places_limit: None, places_offset: None, sort: None, order: None,
+//!     min_place_population: None, group_by: None, dataset: None, year: None,
+//! };
+//! let exposure = client.exposure(&query, &[Include::Uncertainty]).await?;
+//! println!("{} people within 10 km", exposure.total_population);
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use error::{Error, Result};
+pub use geopop_types as types;
+
+use types::{
+    AdminHierarchyPayload, AdminStatsPayload, Aggregation, AirportsPayload, AirportsQuery,
+    AnalysePayload, BatchPayload, BatchQuery, CacheFlushPayload, CacheFlushQuery, CellIndexQuery,
+    CellPayload, CitySearchPayload, CitySearchQuery, ComparePayload, CompareQuery,
+    ContinentListPayload, ContinentQuery, CountryDetailPayload, CountryListPayload, CountryPageV2,
+    CountryPayload, CountryPopulationPayload, CyclonePayload, CycloneQuery, DensityRankPayload,
+    DistancePayload, DistanceQuery, EezPayload, EpicentreEvent, ExposurePayload,
+    ExposurePlacesPayload, ExposurePlacesQuery, ExposureQuery, FacilitiesPayload, FacilitiesQuery,
+    FacilitiesWithinPayload, FacilitiesWithinQuery, FloodPayload, FloodQuery, H3CellPayload,
+    HealthPayload, HealthQuery, HexGridPayload, IsochronePayload, IsochroneQuery, JobPayload,
+    JobSubmission, LandCheckPayload, LangQuery, MaxDistanceQuery, NearbyCitiesPayload,
+    NearbyCountriesPayload, NearestPlace, PageQuery, PointPayload, PointQuery,
+    PopulationBboxPayload, PopulationChangePayload, PopulationChangeQuery, PopulationGridPayload,
+    PopulationGridQuery, PopulationQuery, ProbeEvent, ReverseMode, ReverseModeQuery, ReversePayload,
+    ReversePayloadV2, RootPayload, RouteSamplePayload, RouteSampleQuery, S2CellPayload,
+    S2GridPayload, StreamError, TagPoint, TagStreamLine, YearQuery,
+};
+
+const API_PREFIX: &str = "/api/v1";
+const API_V2_PREFIX: &str = "/api/v2";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// An optional response section requested via `include=`. Each endpoint
+/// documents which sections it honours; others are rejected with a 400.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Include {
+    Uncertainty,
+    Places,
+    Sources,
+    Timings,
+    Geometry,
+    Hull,
+    Settlement,
+    BuiltUp,
+    NightLights,
+    LandCover,
+}
+
+impl Include {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Uncertainty => "uncertainty",
+            Self::Places => "places",
+            Self::Sources => "sources",
+            Self::Timings => "timings",
+            Self::Geometry => "geometry",
+            Self::Hull => "hull",
+            Self::Settlement => "settlement",
+            Self::BuiltUp => "built_up",
+            Self::NightLights => "night_lights",
+            Self::LandCover => "land_cover",
+        }
+    }
+}
+
+/// One event of [`Client::analyse_stream`].
+#[derive(Debug, Clone)]
+pub enum AnalyseEvent {
+    Country(CountryPayload),
+    NearestPlace(NearestPlace),
+    Epicentre(EpicentreEvent),
+    Probe(ProbeEvent),
+    Summary(Box<AnalysePayload>),
+    Error(StreamError),
+}
+
+/// Builder for [`Client`].
+pub struct ClientBuilder {
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl ClientBuilder {
+    /// Value for the `X-API-Key` header, required when the server sets `API_KEY`.
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Per-attempt request timeout (default 60 s).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retries after the first attempt for transient failures (default 3; 0 disables).
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry; doubles on each further retry (default 250 ms).
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut headers = HeaderMap::new();
+        if let Some(key) = &self.api_key {
+            let mut value = HeaderValue::from_str(key)
+                .map_err(|_| Error::Decode("API key is not a valid header value".into()))?;
+            value.set_sensitive(true);
+            headers.insert("X-API-Key", value);
+        }
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .build()?;
+        Ok(Client {
+            http,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            max_retries: self.max_retries,
+            backoff: self.backoff,
+        })
+    }
+}
+
+/// GeoPop API client. Cheap to clone; clones share the connection pool.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+/// Success and error bodies share this envelope.
+#[derive(Deserialize)]
+struct Envelope<T> {
+    message: String,
+    payload: Option<T>,
+}
+
+#[derive(Serialize)]
+struct Boundaries {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    boundaries: Option<i32>,
+}
+
+impl Client {
+    /// Client with default settings and no API key.
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    /// `base_url` is the server root, e.g. `http://localhost:8080`.
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            api_key: None,
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+        }
+    }
+
+    /// `GET /` — service status and table row counts.
+    pub async fn root(&self) -> Result<RootPayload> {
+        let req = self.http.get(format!("{}/", self.base_url));
+        self.payload(req, true).await
+    }
+
+    /// `GET /health`
+    pub async fn health(&self) -> Result<HealthPayload> {
+        self.payload(self.get("/health"), true).await
+    }
+
+    /// `GET /health?detail=true`: adds pool usage and database status; needs
+    /// the client's API key when the server sets `API_KEY`.
+    pub async fn health_detail(&self) -> Result<HealthPayload> {
+        self.payload(self.get("/health").query(&HealthQuery { detail: true }), true).await
+    }
+
+    /// `GET /admin/stats` with the server's `ADMIN_API_KEY`.
+    pub async fn admin_stats(&self, admin_key: &str) -> Result<AdminStatsPayload> {
+        self.payload(self.get("/admin/stats").header("X-Admin-Key", admin_key), true).await
+    }
+
+    /// `POST /admin/cache/flush`, limited to paths under `prefix` when given.
+    pub async fn flush_caches(&self, admin_key: &str, query: &CacheFlushQuery) -> Result<CacheFlushPayload> {
+        let req = self.post("/admin/cache/flush").header("X-Admin-Key", admin_key).query(query);
+        self.payload(req, true).await
+    }
+
+    /// `GET /population` for a single grid cell.
+    pub async fn population(&self, query: &PointQuery, include: &[Include]) -> Result<PointPayload> {
+        let req = self.get("/population").query(query);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /population` with a `radius`: every populated cell around the point.
+    pub async fn population_grid(
+        &self,
+        query: &PopulationQuery,
+        include: &[Include],
+    ) -> Result<PopulationGridPayload> {
+        let req = self.get("/population").query(query);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /population/grid` — populated cells of a viewport, aggregated into
+    /// blocks when there are more than `max_cells`.
+    pub async fn population_bbox(&self, query: &PopulationGridQuery) -> Result<PopulationBboxPayload> {
+        self.payload(self.get("/population/grid").query(query), true).await
+    }
+
+    /// `GET /population` with a `radius` and `aggregation=h3`: the cells around
+    /// the point summed into H3 hexagons. `query.aggregation` is overridden.
+    pub async fn population_hexagons(&self, query: &PopulationQuery) -> Result<HexGridPayload> {
+        let query = PopulationQuery { aggregation: Some(Aggregation::H3), ..query.clone() };
+        self.payload(self.get("/population").query(&query), true).await
+    }
+
+    /// `GET /population/grid` with `aggregation=h3`: a viewport's cells summed
+    /// into H3 hexagons. `query.aggregation` is overridden.
+    pub async fn population_bbox_hexagons(&self, query: &PopulationGridQuery) -> Result<HexGridPayload> {
+        let query = PopulationGridQuery { aggregation: Some(Aggregation::H3), ..query.clone() };
+        self.payload(self.get("/population/grid").query(&query), true).await
+    }
+
+    /// `GET /population` with a `radius` and `aggregation=s2`: the cells around
+    /// the point summed into S2 cells. `query.aggregation` is overridden.
+    pub async fn population_s2_cells(&self, query: &PopulationQuery) -> Result<S2GridPayload> {
+        let query = PopulationQuery { aggregation: Some(Aggregation::S2), ..query.clone() };
+        self.payload(self.get("/population").query(&query), true).await
+    }
+
+    /// `GET /population/grid` with `aggregation=s2`: a viewport's cells summed
+    /// into S2 cells. `query.aggregation` is overridden.
+    pub async fn population_bbox_s2_cells(&self, query: &PopulationGridQuery) -> Result<S2GridPayload> {
+        let query = PopulationGridQuery { aggregation: Some(Aggregation::S2), ..query.clone() };
+        self.payload(self.get("/population/grid").query(&query), true).await
+    }
+
+    /// `GET /population/s2/{cell}` — population of one S2 cell, by token or id.
+    pub async fn population_s2(&self, cell: &str, query: &CellIndexQuery) -> Result<S2CellPayload> {
+        self.payload(self.get(&format!("/population/s2/{cell}")).query(query), true).await
+    }
+
+    /// `GET /population/h3/{index}` — population of one H3 hexagon.
+    pub async fn population_h3(&self, index: &str, query: &CellIndexQuery) -> Result<H3CellPayload> {
+        self.payload(self.get(&format!("/population/h3/{index}")).query(query), true).await
+    }
+
+    /// `GET /population/change` — change between two grid years at a point, or
+    /// per cell within a radius.
+    pub async fn population_change(&self, query: &PopulationChangeQuery) -> Result<PopulationChangePayload> {
+        self.payload(self.get("/population/change").query(query), true).await
+    }
+
+    /// `POST /population/batch`
+    pub async fn batch_population(&self, body: &BatchQuery) -> Result<BatchPayload> {
+        self.payload(self.post("/population/batch").json(body), true).await
+    }
+
+    /// `GET /population/rank`
+    pub async fn population_rank(&self, query: &PointQuery) -> Result<DensityRankPayload> {
+        self.payload(self.get("/population/rank").query(query), true).await
+    }
+
+    /// `GET /cell/{cell_id}`
+    pub async fn cell(&self, cell_id: i64, boundaries: Option<i32>) -> Result<CellPayload> {
+        let req = self.get(&format!("/cell/{cell_id}")).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /reverse`
+    pub async fn reverse(&self, query: &PointQuery, boundaries: Option<i32>) -> Result<ReversePayload> {
+        let req = self.get("/reverse").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /reverse` with `display_name` and `address` in the languages of `lang`.
+    pub async fn reverse_localised(&self, query: &PointQuery, lang: &LangQuery) -> Result<ReversePayload> {
+        self.payload(self.get("/reverse").query(query).query(lang), true).await
+    }
+
+    /// `GET /reverse` limited to places within `cap.max_distance_km`; a further
+    /// nearest place is a 404 [`Error::Api`].
+    pub async fn reverse_within(&self, query: &PointQuery, cap: &MaxDistanceQuery) -> Result<ReversePayload> {
+        self.payload(self.get("/reverse").query(query).query(cap), true).await
+    }
+
+    /// `GET /reverse?mode=contains`: regions from the boundary polygons
+    /// containing the point rather than from the nearest place.
+    pub async fn reverse_contained(&self, query: &PointQuery) -> Result<ReversePayload> {
+        let mode = ReverseModeQuery { mode: ReverseMode::Contains };
+        self.payload(self.get("/reverse").query(query).query(&mode), true).await
+    }
+
+    /// `GET /admin` — the country and admin units whose boundaries contain
+    /// the point, named in the languages of `lang`.
+    pub async fn admin_hierarchy(
+        &self,
+        query: &PointQuery,
+        lang: &LangQuery,
+        boundaries: Option<i32>,
+    ) -> Result<AdminHierarchyPayload> {
+        let req = self.get("/admin").query(query).query(lang).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /geocoding/nearby-countries`
+    pub async fn nearby_countries(
+        &self,
+        query: &ExposureQuery,
+        boundaries: Option<i32>,
+    ) -> Result<NearbyCountriesPayload> {
+        let req = self.get("/geocoding/nearby-countries").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /geocoding/nearby-cities`
+    pub async fn nearby_cities(&self, query: &ExposurePlacesQuery) -> Result<NearbyCitiesPayload> {
+        self.payload(self.get("/geocoding/nearby-cities").query(query), true).await
+    }
+
+    /// `GET /geocoding/land-check`
+    pub async fn land_check(&self, query: &PointQuery, boundaries: Option<i32>) -> Result<LandCheckPayload> {
+        let req = self.get("/geocoding/land-check").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /cities/search`
+    pub async fn search_cities(&self, query: &CitySearchQuery) -> Result<CitySearchPayload> {
+        self.payload(self.get("/cities/search").query(query), true).await
+    }
+
+    /// `GET /facilities`
+    pub async fn facilities(&self, query: &FacilitiesWithinQuery) -> Result<FacilitiesWithinPayload> {
+        self.payload(self.get("/facilities").query(query), true).await
+    }
+
+    /// `GET /facilities/nearest`
+    pub async fn nearest_facilities(&self, query: &FacilitiesQuery) -> Result<FacilitiesPayload> {
+        self.payload(self.get("/facilities/nearest").query(query), true).await
+    }
+
+    /// `GET /airports/nearest`
+    pub async fn nearest_airports(&self, query: &AirportsQuery) -> Result<AirportsPayload> {
+        self.payload(self.get("/airports/nearest").query(query), true).await
+    }
+
+    /// `GET /distance`
+    pub async fn distance(&self, query: &DistanceQuery) -> Result<DistancePayload> {
+        self.payload(self.get("/distance").query(query), true).await
+    }
+
+    /// `GET /exposure`
+    pub async fn exposure(&self, query: &ExposureQuery, include: &[Include]) -> Result<ExposurePayload> {
+        let req = self.get("/exposure").query(query);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /exposure/places`
+    pub async fn exposure_places(&self, query: &ExposurePlacesQuery) -> Result<ExposurePlacesPayload> {
+        self.payload(self.get("/exposure/places").query(query), true).await
+    }
+
+    /// `GET /compare`
+    pub async fn compare(&self, query: &CompareQuery, include: &[Include]) -> Result<ComparePayload> {
+        let req = self.get("/compare").query(query);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /route/sample`
+    pub async fn route_sample(
+        &self,
+        query: &RouteSampleQuery,
+        boundaries: Option<i32>,
+    ) -> Result<RouteSamplePayload> {
+        let req = self.get("/route/sample").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `POST /exposure/cyclone`
+    pub async fn cyclone_exposure(&self, body: &CycloneQuery, include: &[Include]) -> Result<CyclonePayload> {
+        let req = self.post("/exposure/cyclone").json(body);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `POST /exposure/flood`
+    pub async fn flood_exposure(&self, body: &FloodQuery, include: &[Include]) -> Result<FloodPayload> {
+        let req = self.post("/exposure/flood").json(body);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /exposure/isochrone`
+    pub async fn isochrone_exposure(
+        &self,
+        query: &IsochroneQuery,
+        include: &[Include],
+    ) -> Result<IsochronePayload> {
+        let req = self.get("/exposure/isochrone").query(query);
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /analyse`
+    pub async fn analyse(
+        &self,
+        query: &PointQuery,
+        boundaries: Option<i32>,
+        year: Option<i32>,
+        include: &[Include],
+    ) -> Result<AnalysePayload> {
+        let req = self
+            .get("/analyse")
+            .query(query)
+            .query(&Boundaries { boundaries })
+            .query(&YearQuery { year });
+        self.payload(with_include(req, include), true).await
+    }
+
+    /// `GET /analyse/stream` — the analysis with its progress events, returned
+    /// once the stream has ended. The last event is `Summary` or `Error`.
+    pub async fn analyse_stream(
+        &self,
+        query: &PointQuery,
+        boundaries: Option<i32>,
+        year: Option<i32>,
+        include: &[Include],
+    ) -> Result<Vec<AnalyseEvent>> {
+        let req = self
+            .get("/analyse/stream")
+            .query(query)
+            .query(&Boundaries { boundaries })
+            .query(&YearQuery { year });
+        let body = self.send(with_include(req, include), true).await?.text().await?;
+        parse_events(&body)
+    }
+
+    /// `GET /country` — country containing the point, or the nearest one at sea.
+    pub async fn country(&self, query: &PointQuery, boundaries: Option<i32>) -> Result<CountryPayload> {
+        let req = self.get("/country").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /country/{iso3}`
+    pub async fn country_by_iso3(&self, iso3: &str, boundaries: Option<i32>) -> Result<CountryDetailPayload> {
+        let req = self.get(&format!("/country/{iso3}")).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /country/iso2/{code}`
+    pub async fn country_by_iso2(&self, iso2: &str, boundaries: Option<i32>) -> Result<CountryDetailPayload> {
+        let req = self.get(&format!("/country/iso2/{iso2}")).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /country/numeric/{code}`
+    pub async fn country_by_numeric(&self, code: u16, boundaries: Option<i32>) -> Result<CountryDetailPayload> {
+        let req = self.get(&format!("/country/numeric/{code:03}")).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /country/{iso3}/population`
+    pub async fn country_population(
+        &self,
+        iso3: &str,
+        boundaries: Option<i32>,
+    ) -> Result<CountryPopulationPayload> {
+        let req = self.get(&format!("/country/{iso3}/population")).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /countries`
+    pub async fn countries(&self, query: &ContinentQuery, boundaries: Option<i32>) -> Result<CountryListPayload> {
+        let req = self.get("/countries").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /continents`
+    pub async fn continents(&self, boundaries: Option<i32>) -> Result<ContinentListPayload> {
+        let req = self.get("/continents").query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /eez`
+    pub async fn eez(&self, query: &PointQuery, boundaries: Option<i32>) -> Result<EezPayload> {
+        let req = self.get("/eez").query(query).query(&Boundaries { boundaries });
+        self.payload(req, true).await
+    }
+
+    /// `GET /tiles/countries/{z}/{x}/{y}.mvt` — the raw Mapbox vector tile.
+    pub async fn country_tile(&self, z: u32, x: u32, y: u32, boundaries: Option<i32>) -> Result<Vec<u8>> {
+        let req = self.get(&format!("/tiles/countries/{z}/{x}/{y}.mvt")).query(&Boundaries { boundaries });
+        Ok(self.send(req, true).await?.bytes().await?.to_vec())
+    }
+
+    /// `POST /tag/stream` — tags `points` in order. Lines the server could not
+    /// tag come back as [`TagStreamLine::Error`].
+    pub async fn tag_points(&self, points: &[TagPoint], boundaries: Option<i32>) -> Result<Vec<TagStreamLine>> {
+        let mut body = Vec::new();
+        for point in points {
+            serde_json::to_writer(&mut body, point).map_err(|e| Error::Decode(e.to_string()))?;
+            body.push(b'\n');
+        }
+        let req = self
+            .post("/tag/stream")
+            .query(&Boundaries { boundaries })
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body);
+        let text = self.send(req, true).await?.text().await?;
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str(l).map_err(|e| Error::Decode(e.to_string())))
+            .collect()
+    }
+
+    /// `POST /jobs` — queues a background job. Not retried once the request
+    /// may have reached the server.
+    pub async fn submit_job(&self, body: &JobSubmission) -> Result<JobPayload> {
+        self.payload(self.post("/jobs").json(body), false).await
+    }
+
+    /// `GET /jobs/{id}`
+    pub async fn get_job(&self, id: Uuid) -> Result<JobPayload> {
+        self.payload(self.get(&format!("/jobs/{id}")), true).await
+    }
+
+    /// `POST /graphql` — returns the raw GraphQL response (`data` and/or `errors`).
+    pub async fn graphql(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let res = self.send(self.post("/graphql").json(&body), true).await?;
+        res.json().await.map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    /// `GET /api/v2/reverse` — the revised contract: bare payload, numeric coordinates.
+    pub async fn reverse_v2(&self, query: &PointQuery) -> Result<ReversePayloadV2> {
+        self.bare(self.get_v2("/reverse").query(query)).await
+    }
+
+    /// `GET /api/v2/countries` — one page of countries.
+    pub async fn countries_v2(
+        &self,
+        query: &ContinentQuery,
+        page: &PageQuery,
+        boundaries: Option<i32>,
+    ) -> Result<CountryPageV2> {
+        let req = self.get_v2("/countries").query(query).query(page).query(&Boundaries { boundaries });
+        self.bare(req).await
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.http.get(format!("{}{API_PREFIX}{path}", self.base_url))
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.http.post(format!("{}{API_PREFIX}{path}", self.base_url))
+    }
+
+    fn get_v2(&self, path: &str) -> RequestBuilder {
+        self.http.get(format!("{}{API_V2_PREFIX}{path}", self.base_url))
+    }
+
+    /// Decodes a v2 response, which carries no envelope.
+    async fn bare<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T> {
+        let res = self.send(req, true).await?;
+        res.json().await.map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    async fn payload<T: DeserializeOwned>(&self, req: RequestBuilder, idempotent: bool) -> Result<T> {
+        let res = self.send(req, idempotent).await?;
+        let envelope: Envelope<T> = res.json().await.map_err(|e| Error::Decode(e.to_string()))?;
+        envelope
+            .payload
+            .ok_or_else(|| Error::Decode(format!("response has no payload ({})", envelope.message)))
+    }
+
+    /// Sends with retries and maps error statuses to [`Error::Api`].
+    async fn send(&self, req: RequestBuilder, idempotent: bool) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let this_try = req
+                .try_clone()
+                .ok_or_else(|| Error::Decode("request body cannot be retried".into()))?;
+            let last = attempt >= self.max_retries;
+            match this_try.send().await {
+                Ok(res) if res.status().is_success() => return Ok(res),
+                Ok(res) if !last && idempotent && is_retryable(res.status()) => {
+                    let wait = retry_after(&res).unwrap_or_else(|| backoff_delay(self.backoff, attempt));
+                    log::debug!("geopop: {} from {}, retrying in {wait:?}", res.status(), res.url());
+                    tokio::time::sleep(wait).await;
+                }
+                Ok(res) => return Err(api_error(res).await),
+                Err(e) if !last && (e.is_connect() || (idempotent && e.is_timeout())) => {
+                    let wait = backoff_delay(self.backoff, attempt);
+                    log::debug!("geopop: {e}, retrying in {wait:?}");
+                    tokio::time::sleep(wait).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+            attempt += 1;
+        }
+    }
+}
+
+fn with_include(req: RequestBuilder, include: &[Include]) -> RequestBuilder {
+    if include.is_empty() {
+        return req;
+    }
+    let list: Vec<&str> = include.iter().map(|i| i.as_str()).collect();
+    req.query(&[("include", list.join(","))])
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 502 | 503 | 504)
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    let secs: u64 = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs).min(MAX_BACKOFF))
+}
+
+/// `base * 2^attempt`, capped at `MAX_BACKOFF`.
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(1 << attempt.min(16)).min(MAX_BACKOFF)
+}
+
+async fn api_error(res: Response) -> Error {
+    let status = res.status();
+    let message = match res.json::<Envelope<serde_json::Value>>().await {
+        Ok(body) => body.message,
+        Err(_) => status.canonical_reason().unwrap_or("error").to_string(),
+    };
+    Error::Api { status: status.as_u16(), message }
+}
+
+/// Parses a complete `text/event-stream` body into analysis events.
+fn parse_events(body: &str) -> Result<Vec<AnalyseEvent>> {
+    let decode = |e: serde_json::Error| Error::Decode(e.to_string());
+    let mut events = Vec::new();
+    for block in body.split("\n\n").filter(|b| !b.trim().is_empty()) {
+        let (mut name, mut data) = ("", String::new());
+        for line in block.lines() {
+            if let Some(v) = line.strip_prefix("event:") {
+                name = v.trim();
+            } else if let Some(v) = line.strip_prefix("data:") {
+                data.push_str(v.trim_start());
+            }
+        }
+        let event = match name {
+            "country" => AnalyseEvent::Country(serde_json::from_str(&data).map_err(decode)?),
+            "nearest_place" => AnalyseEvent::NearestPlace(serde_json::from_str(&data).map_err(decode)?),
+            "epicentre" => AnalyseEvent::Epicentre(serde_json::from_str(&data).map_err(decode)?),
+            "probe" => AnalyseEvent::Probe(serde_json::from_str(&data).map_err(decode)?),
+            "summary" => AnalyseEvent::Summary(Box::new(serde_json::from_str(&data).map_err(decode)?)),
+            "error" => AnalyseEvent::Error(serde_json::from_str(&data).map_err(decode)?),
+            // Unknown events are skipped so newer servers stay compatible.
+            _ => continue,
+        };
+        events.push(event);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        let base = Duration::from_millis(250);
+        assert_eq!(backoff_delay(base, 0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(base, 2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(base, 10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(base, 40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn query_omits_unset_options() {
+        let client = Client::new("http://localhost:8080/").unwrap();
+        let query = ExposureQuery {
+            lat: 6.5, lon: 79.25, radius: 10.0,
+            decay: None, mode: None, coast_km: None, max_elevation_m: None, rings: None, histogram: None,
+            top_cells: None, land_only: None, places_limit: None, places_offset: None, sort: None, order: None,
+            min_place_population: None, group_by: None, dataset: None, year: None,
+        };
+        let req = client.get("/exposure").query(&query).query(&Boundaries { boundaries: None });
+        let req = with_include(req, &[Include::Uncertainty, Include::Places]).build().unwrap();
+        assert_eq!(
+            req.url().as_str(),
+            "http://localhost:8080/api/v1/exposure?lat=6.5&lon=79.25&radius=10.0&include=uncertainty%2Cplaces"
+        );
+    }
+
+    #[test]
+    fn v2_requests_use_v2_prefix() {
+        let client = Client::new("http://localhost:8080").unwrap();
+        let query = ContinentQuery { continent: Some("asia".into()), region: None, subregion: None, sovereign: None };
+        let page = PageQuery { page: 2, per_page: 50 };
+        let req = client.get_v2("/countries").query(&query).query(&page).build().unwrap();
+        assert_eq!(
+            req.url().as_str(),
+            "http://localhost:8080/api/v2/countries?continent=asia&page=2&per_page=50"
+        );
+    }
+
+    #[test]
+    fn parses_event_stream() {
+        let body = "event: probe\ndata: {\"radius_km\":5.0,\"populated\":false}\n\n\
+                    event: heartbeat\ndata: {}\n\n\
+                    event: error\ndata: {\"message\":\"database connection error\"}\n\n";
+        let events = parse_events(body).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], AnalyseEvent::Probe(p) if p.radius_km == 5.0 && !p.populated));
+        assert!(matches!(&events[1], AnalyseEvent::Error(e) if e.message == "database connection error"));
+    }
+}
@@ -0,0 +1,8 @@
+-- ── ISO 3166-1 numeric codes ──
+-- Natural Earth ISO_N3_EH, zero-padded to three digits, for
+-- /country/numeric/{code}. Filled in when a vintage is (re)loaded with
+-- ingestion/ingest_naturalearth.py; NULL for earlier loads.
+
+ALTER TABLE countries ADD COLUMN IF NOT EXISTS iso_n3 CHAR(3);
+
+CREATE INDEX IF NOT EXISTS idx_countries_iso_n3 ON countries (iso_n3);
@@ -0,0 +1,21 @@
+-- ── Country land area ──
+-- Geodesic area of each country polygon in km², for the `area_km2` and
+-- `density_per_km2` fields of the country payloads. Computing it per request
+-- is too slow for listings, so a trigger fills it whenever a row is loaded or
+-- its geometry changes, and existing rows are backfilled here.
+
+ALTER TABLE countries ADD COLUMN IF NOT EXISTS area_km2 DOUBLE PRECISION;
+
+CREATE OR REPLACE FUNCTION set_country_area() RETURNS trigger AS $$
+BEGIN
+    NEW.area_km2 := ST_Area(NEW.geom::geography) / 1e6;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS countries_area ON countries;
+CREATE TRIGGER countries_area
+    BEFORE INSERT OR UPDATE OF geom ON countries
+    FOR EACH ROW EXECUTE FUNCTION set_country_area();
+
+UPDATE countries SET area_km2 = ST_Area(geom::geography) / 1e6 WHERE area_km2 IS NULL;
@@ -0,0 +1,11 @@
+-- ── WorldPop constrained 1km population grid ──
+-- The constrained grid places people only in cells with mapped building
+-- footprints, so rural and unsettled land reads as zero instead of a thin
+-- spread. Same cell_id scheme as `population`; loaded by
+-- `ingestion/ingest.py --dataset constrained` and read by /population and
+-- /exposure with `dataset=constrained`. Optional: empty means not loaded.
+
+CREATE TABLE IF NOT EXISTS population_constrained (
+    cell_id INTEGER PRIMARY KEY,
+    pop     REAL    NOT NULL
+);
@@ -0,0 +1,31 @@
+-- ── Population grid years ──
+-- Which WorldPop years are loaded per dataset. The latest year of each dataset
+-- lives in its main table (`population` / `population_constrained`), where the
+-- uncertainty layer, density percentiles and country totals are derived from;
+-- earlier years live in population_history and are read with `year=`.
+-- ingestion/ingest.py maintains both.
+
+CREATE TABLE IF NOT EXISTS population_years (
+    dataset   TEXT        NOT NULL CHECK (dataset IN ('unconstrained', 'constrained')),
+    year      INTEGER     NOT NULL,
+    loaded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (dataset, year)
+);
+
+CREATE TABLE IF NOT EXISTS population_history (
+    dataset TEXT    NOT NULL,
+    year    INTEGER NOT NULL,
+    cell_id INTEGER NOT NULL,
+    pop     REAL    NOT NULL,
+    PRIMARY KEY (dataset, year, cell_id),
+    FOREIGN KEY (dataset, year) REFERENCES population_years (dataset, year) ON DELETE CASCADE
+);
+
+-- Grids loaded before this migration are the R2024B 2025 release.
+INSERT INTO population_years (dataset, year)
+SELECT 'unconstrained', 2025 WHERE EXISTS (SELECT 1 FROM population)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO population_years (dataset, year)
+SELECT 'constrained', 2025 WHERE EXISTS (SELECT 1 FROM population_constrained)
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,96 @@
+-- ── Population raster tiles ──
+-- The latest year of each WorldPop grid as 240×240-cell (2°) raster tiles, for
+-- EXPOSURE_BACKEND=raster: large-radius exposure sums clip the few tiles under
+-- the circle instead of enumerating every candidate cell. Built from the cell
+-- tables by refresh_population_raster(dataset), so both backends read the same
+-- numbers. Optional: empty means not built, and exposure falls back to the grid.
+--
+-- Skipped entirely where the postgis_raster extension is not installed on the
+-- server; the grid backend needs none of it. Install the extension and run
+-- this file with psql to add the raster later.
+
+DO $migration$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'postgis_raster') THEN
+        RAISE NOTICE 'postgis_raster is not available; skipping population_raster';
+        RETURN;
+    END IF;
+
+    CREATE EXTENSION IF NOT EXISTS postgis_raster;
+
+    CREATE TABLE IF NOT EXISTS population_raster (
+        dataset  TEXT    NOT NULL CHECK (dataset IN ('unconstrained', 'constrained')),
+        tile_row INTEGER NOT NULL,
+        tile_col INTEGER NOT NULL,
+        rast     raster  NOT NULL,
+        PRIMARY KEY (dataset, tile_row, tile_col)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_population_raster_hull
+        ON population_raster USING gist (ST_ConvexHull(rast));
+
+    -- One 32-bit float band, nodata -1 so clipped-away pixels drop out of
+    -- ST_SummaryStats. Tiles without a populated cell are not stored. Each tile
+    -- reads its cells with one index range scan per grid row.
+    CREATE OR REPLACE FUNCTION refresh_population_raster(p_dataset TEXT)
+    RETURNS INTEGER LANGUAGE plpgsql AS $fn$
+    DECLARE
+        grid TEXT := CASE p_dataset
+            WHEN 'unconstrained' THEN 'population'
+            WHEN 'constrained' THEN 'population_constrained'
+        END;
+        tile RECORD;
+        vals DOUBLE PRECISION[][];
+        tiles INTEGER := 0;
+    BEGIN
+        IF grid IS NULL THEN
+            RAISE EXCEPTION 'unknown population dataset: %', p_dataset;
+        END IF;
+        DELETE FROM population_raster WHERE dataset = p_dataset;
+
+        FOR tile IN EXECUTE format(
+            'SELECT DISTINCT cell_id / 43200 / 240 AS tile_row, cell_id %% 43200 / 240 AS tile_col FROM %I',
+            grid
+        ) LOOP
+            EXECUTE format($sql$
+                SELECT array_agg(vals ORDER BY r)
+                FROM (
+                    SELECT r.r, array_agg(COALESCE(c.pop, 0)::float8 ORDER BY col.col) AS vals
+                    FROM generate_series(0, 239) r (r)
+                    CROSS JOIN generate_series(0, 239) col (col)
+                    LEFT JOIN (
+                        SELECT p.cell_id, p.pop
+                        FROM generate_series(0, 239) rr (rr)
+                        CROSS JOIN LATERAL (
+                            SELECT cell_id, pop FROM %I
+                            WHERE cell_id BETWEEN ($1 * 240 + rr.rr) * 43200 + $2 * 240
+                                              AND ($1 * 240 + rr.rr) * 43200 + $2 * 240 + 239
+                        ) p
+                    ) c ON c.cell_id = ($1 * 240 + r.r) * 43200 + $2 * 240 + col.col
+                    GROUP BY r.r
+                ) t
+            $sql$, grid) INTO vals USING tile.tile_row, tile.tile_col;
+
+            INSERT INTO population_raster (dataset, tile_row, tile_col, rast)
+            VALUES (
+                p_dataset, tile.tile_row, tile.tile_col,
+                ST_SetValues(
+                    ST_AddBand(
+                        ST_MakeEmptyRaster(
+                            240, 240, tile.tile_col * 2 - 180.0, 90.0 - tile.tile_row * 2,
+                            1 / 120.0, -1 / 120.0, 0, 0, 4326
+                        ),
+                        '32BF'::text, 0, -1
+                    ),
+                    1, 1, 1, vals
+                )
+            );
+            tiles := tiles + 1;
+        END LOOP;
+
+        ANALYZE population_raster;
+        RETURN tiles;
+    END
+    $fn$;
+END
+$migration$;
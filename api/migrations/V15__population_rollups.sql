@@ -0,0 +1,55 @@
+-- ── Population rollup pyramid ──
+-- Population summed over square blocks of the latest year of each grid, at
+-- three resolutions: 12 (0.1°), 60 (0.5°) and 120 (1°) cells per side. Large
+-- exposure sums read the blocks lying wholly inside the circle from here and
+-- only enumerate the grid cells along its edge. Blocks are aligned to the
+-- global grid (block_row = row / block_size) and only populated ones are kept.
+-- Rebuilt by refresh_population_rollups(dataset), which the WorldPop loader
+-- calls after loading a dataset's latest year. Empty means not built yet, and
+-- exposure enumerates the grid throughout.
+
+CREATE TABLE IF NOT EXISTS population_rollups (
+    dataset    TEXT             NOT NULL CHECK (dataset IN ('unconstrained', 'constrained')),
+    block_size SMALLINT         NOT NULL CHECK (block_size IN (12, 60, 120)),
+    block_row  INTEGER          NOT NULL,
+    block_col  INTEGER          NOT NULL,
+    pop        DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (dataset, block_size, block_row, block_col)
+);
+
+CREATE OR REPLACE FUNCTION refresh_population_rollups(p_dataset TEXT)
+RETURNS void LANGUAGE plpgsql AS $$
+DECLARE
+    grid TEXT := CASE p_dataset
+        WHEN 'unconstrained' THEN 'population'
+        WHEN 'constrained' THEN 'population_constrained'
+    END;
+BEGIN
+    IF grid IS NULL THEN
+        RAISE EXCEPTION 'unknown population dataset: %', p_dataset;
+    END IF;
+    DELETE FROM population_rollups WHERE dataset = p_dataset;
+
+    -- 0.1° blocks from the cells, then each coarser level from the finer one.
+    EXECUTE format($sql$
+        INSERT INTO population_rollups (dataset, block_size, block_row, block_col, pop)
+        SELECT $1, 12, cell_id / 43200 / 12, cell_id %% 43200 / 12, SUM(pop::float8)
+        FROM %I
+        GROUP BY 3, 4
+    $sql$, grid) USING p_dataset;
+
+    INSERT INTO population_rollups (dataset, block_size, block_row, block_col, pop)
+    SELECT p_dataset, 60, block_row / 5, block_col / 5, SUM(pop)
+    FROM population_rollups
+    WHERE dataset = p_dataset AND block_size = 12
+    GROUP BY 3, 4;
+
+    INSERT INTO population_rollups (dataset, block_size, block_row, block_col, pop)
+    SELECT p_dataset, 120, block_row / 2, block_col / 2, SUM(pop)
+    FROM population_rollups
+    WHERE dataset = p_dataset AND block_size = 60
+    GROUP BY 3, 4;
+
+    ANALYZE population_rollups;
+END
+$$;
@@ -0,0 +1,21 @@
+-- ── Request audit log ──
+-- One row per API request when AUDIT_LOG=true, written in batches by a
+-- background task on each API instance. `params_hash` is the SHA-256 of the
+-- query string, so a request for known coordinates can be matched without the
+-- log holding them in the clear; `api_key` is a fingerprint (the first 16 hex
+-- digits of the key's SHA-256), never the key itself. Rows are kept until an
+-- operator removes them.
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          BIGINT      GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    method      TEXT        NOT NULL,
+    path        TEXT        NOT NULL,
+    params_hash TEXT        NOT NULL,
+    api_key     TEXT,
+    status      SMALLINT    NOT NULL,
+    latency_ms  REAL        NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log (at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_api_key ON audit_log (api_key, at);
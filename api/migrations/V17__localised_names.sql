@@ -0,0 +1,22 @@
+-- ── Localised place names ──
+-- GeoNames ids of the admin regions and countries, so addresses can name them
+-- from alternate_names in the client's language. Filled by
+-- ingestion/ingest_geonames.py, which now also keeps the alternate names of
+-- these features.
+
+ALTER TABLE admin1_codes ADD COLUMN IF NOT EXISTS geonameid INTEGER;
+ALTER TABLE admin2_codes ADD COLUMN IF NOT EXISTS geonameid INTEGER;
+ALTER TABLE country_info ADD COLUMN IF NOT EXISTS geonameid INTEGER;
+
+-- Name of feature `id` in the first of `langs` (lowercase ISO 639 codes,
+-- optionally with a region: `ta`, `zh-tw`) that has one, skipping historic and
+-- colloquial names; preferred, then short names first. NULL when none does.
+CREATE OR REPLACE FUNCTION localised_name(id INTEGER, langs TEXT[])
+RETURNS TEXT LANGUAGE sql STABLE PARALLEL SAFE AS $$
+    SELECT name FROM alternate_names
+    WHERE geonameid = id
+      AND lower(isolanguage) = ANY(langs)
+      AND NOT is_historic AND NOT is_colloquial
+    ORDER BY array_position(langs, lower(isolanguage)), is_preferred DESC, is_short DESC, alternatenameid
+    LIMIT 1
+$$;
@@ -0,0 +1,24 @@
+-- ── Admin boundary polygons ──
+-- First- and second-level administrative areas, loaded by
+-- ingestion/ingest_admin_boundaries.py, for `/reverse?mode=contains`: the
+-- regions of a point are the polygons containing it rather than those of the
+-- nearest place, which is often across a border. `code` is the GeoNames admin
+-- code (`LK.36`, `LK.36.1234`) so names come from admin1_codes / admin2_codes
+-- and their alternate names when it matches; `name` is the layer's own name.
+-- Like countries, every load is tagged with a boundary vintage so `boundaries=`
+-- pins the regions as well as the country.
+
+CREATE TABLE IF NOT EXISTS admin_boundaries (
+    gid          SERIAL   PRIMARY KEY,
+    vintage      INTEGER  NOT NULL REFERENCES boundary_vintages (vintage),
+    level        SMALLINT NOT NULL CHECK (level IN (1, 2)),
+    country_code CHAR(2),
+    code         TEXT,
+    name         TEXT     NOT NULL,
+    source       TEXT     NOT NULL,
+    geom         GEOMETRY(MultiPolygon, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_boundaries_geom  ON admin_boundaries USING GiST (geom);
+CREATE INDEX IF NOT EXISTS idx_admin_boundaries_level ON admin_boundaries (vintage, level);
+CREATE INDEX IF NOT EXISTS idx_admin_boundaries_code  ON admin_boundaries (vintage, code);
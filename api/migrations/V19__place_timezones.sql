@@ -0,0 +1,6 @@
+-- ── Place timezones ──
+-- IANA timezone of each GeoNames place (`Asia/Colombo`), so /analyse can state
+-- the local time at an epicentre. Filled by ingestion/ingest_geonames.py; NULL
+-- until the places are re-ingested.
+
+ALTER TABLE geonames ADD COLUMN IF NOT EXISTS timezone TEXT;
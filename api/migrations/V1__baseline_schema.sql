@@ -0,0 +1,171 @@
+-- Baseline GeoPop schema: WorldPop population grid + GeoNames places + Natural Earth countries.
+--
+-- Every statement is idempotent so this also adopts databases that were created
+-- by the old docker/init.sql + docker/migrate.sql scripts.
+CREATE EXTENSION IF NOT EXISTS postgis;
+-- pg_trgm powers fuzzy / prefix / substring matching on place names for /cities/search
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+-- unaccent lets us match "Sao Paulo" against "São Paulo" transparently
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- ── WorldPop 1km population grid ──
+-- Each cell is a 30 arc-second (~1km) grid cell identified by a canonical cell_id.
+-- cell_id = row * 43200 + col, where:
+--   row = floor((90 - lat) * 120)
+--   col = floor((lon + 180) * 120)
+
+CREATE TABLE IF NOT EXISTS population (
+    cell_id INTEGER PRIMARY KEY,
+    pop     REAL    NOT NULL
+);
+
+CREATE OR REPLACE FUNCTION get_population(lat DOUBLE PRECISION, lon DOUBLE PRECISION)
+RETURNS REAL AS $$
+DECLARE
+    cid INTEGER;
+    result REAL;
+BEGIN
+    cid := (FLOOR((90.0 - lat) * 120.0))::INTEGER * 43200
+         + (FLOOR((lon + 180.0) * 120.0))::INTEGER;
+    SELECT pop INTO result FROM population WHERE cell_id = cid;
+    RETURN COALESCE(result, 0.0);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- ── WorldPop uncertainty (95% credible interval per cell) ──
+-- Only populated for countries where WorldPop publishes uncertainty rasters.
+
+CREATE TABLE IF NOT EXISTS population_uncertainty (
+    cell_id   INTEGER PRIMARY KEY,
+    pop_lower REAL NOT NULL,
+    pop_upper REAL NOT NULL
+);
+
+-- ── Natural Earth 10m country boundaries ──
+-- Boundaries are versioned: each load is tagged with a vintage (release year) so
+-- historical analyses can be regenerated against the boundaries they used.
+
+CREATE TABLE IF NOT EXISTS boundary_vintages (
+    vintage    INTEGER PRIMARY KEY,
+    source     TEXT NOT NULL,
+    is_current BOOLEAN NOT NULL DEFAULT false,
+    loaded_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- At most one vintage is "current" (the default for unpinned queries).
+CREATE UNIQUE INDEX IF NOT EXISTS idx_boundary_vintages_current
+    ON boundary_vintages (is_current) WHERE is_current;
+
+CREATE OR REPLACE FUNCTION current_boundary_vintage()
+RETURNS INTEGER AS $$
+    SELECT vintage FROM boundary_vintages WHERE is_current
+$$ LANGUAGE sql STABLE;
+
+CREATE TABLE IF NOT EXISTS countries (
+    gid         SERIAL PRIMARY KEY,
+    vintage     INTEGER NOT NULL REFERENCES boundary_vintages (vintage),
+    iso_a2      CHAR(2),
+    iso_a3      CHAR(3),
+    name        TEXT NOT NULL,
+    formal_name TEXT,
+    continent   TEXT NOT NULL,
+    region_un   TEXT,
+    subregion   TEXT,
+    type        TEXT,
+    sovereign   BOOLEAN NOT NULL DEFAULT true,
+    pop_est     BIGINT,
+    geom        GEOMETRY(MultiPolygon, 4326) NOT NULL
+);
+
+-- Pre-versioning databases: tag the existing boundaries with the current year.
+ALTER TABLE countries ADD COLUMN IF NOT EXISTS vintage INTEGER;
+INSERT INTO boundary_vintages (vintage, source, is_current)
+SELECT EXTRACT(YEAR FROM now())::int, 'Natural Earth 10m (pre-versioning load)', true
+WHERE EXISTS (SELECT 1 FROM countries WHERE vintage IS NULL)
+  AND NOT EXISTS (SELECT 1 FROM boundary_vintages)
+ON CONFLICT DO NOTHING;
+UPDATE countries SET vintage = current_boundary_vintage() WHERE vintage IS NULL;
+ALTER TABLE countries ALTER COLUMN vintage SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_countries_geom      ON countries USING GiST (geom);
+CREATE INDEX IF NOT EXISTS idx_countries_iso_a2    ON countries (iso_a2);
+CREATE INDEX IF NOT EXISTS idx_countries_iso_a3    ON countries (iso_a3);
+CREATE INDEX IF NOT EXISTS idx_countries_continent ON countries (LOWER(continent));
+CREATE INDEX IF NOT EXISTS idx_countries_region_un ON countries (LOWER(region_un));
+CREATE INDEX IF NOT EXISTS idx_countries_vintage   ON countries (vintage);
+
+-- ── GeoNames reverse geocoding ──
+
+CREATE TABLE IF NOT EXISTS admin1_codes (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS admin2_codes (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS geonames (
+    geonameid    INTEGER PRIMARY KEY,
+    name         TEXT NOT NULL,
+    latitude     DOUBLE PRECISION NOT NULL,
+    longitude    DOUBLE PRECISION NOT NULL,
+    feature_code TEXT,
+    country_code CHAR(2),
+    admin1_code  TEXT,
+    admin2_code  TEXT,
+    population   BIGINT,
+    geom         GEOMETRY(Point, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_geonames_geom ON geonames USING GiST (geom);
+CREATE INDEX IF NOT EXISTS idx_geonames_geog ON geonames USING GiST ((geom::geography));
+
+-- ── City search indexes ──
+-- Trigram GIN index powers fuzzy search (% operator, similarity(), ILIKE '%foo%').
+CREATE INDEX IF NOT EXISTS idx_geonames_name_trgm
+    ON geonames USING GIN (name gin_trgm_ops);
+
+-- Prefix index for the fast-path "starts-with" branch of autocomplete.
+CREATE INDEX IF NOT EXISTS idx_geonames_name_lower
+    ON geonames (LOWER(name) text_pattern_ops);
+
+-- Country scoping for /cities/search?country=XX
+CREATE INDEX IF NOT EXISTS idx_geonames_country_code
+    ON geonames (country_code);
+
+-- Lets "rank by population within a country" sort use the index.
+CREATE INDEX IF NOT EXISTS idx_geonames_country_pop
+    ON geonames (country_code, population DESC);
+
+-- Feature code filter speeds up the "cities only" subset (PPL*, excluding hamlets/farms).
+CREATE INDEX IF NOT EXISTS idx_geonames_feature_code
+    ON geonames (feature_code);
+
+-- ── GeoNames alternate names (localised / historic / short names) ──
+-- Loaded from alternateNamesV2.zip, restricted to places present in `geonames`.
+
+CREATE TABLE IF NOT EXISTS alternate_names (
+    alternatenameid INTEGER PRIMARY KEY,
+    geonameid       INTEGER NOT NULL,
+    isolanguage     TEXT,
+    name            TEXT NOT NULL,
+    is_preferred    BOOLEAN NOT NULL DEFAULT false,
+    is_short        BOOLEAN NOT NULL DEFAULT false,
+    is_colloquial   BOOLEAN NOT NULL DEFAULT false,
+    is_historic     BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE INDEX IF NOT EXISTS idx_alternate_names_geonameid
+    ON alternate_names (geonameid, isolanguage);
+
+-- ── GeoNames incremental update bookkeeping ──
+-- One row per daily modification/deletion file applied by `ingest_geonames.py update`.
+
+CREATE TABLE IF NOT EXISTS geonames_update_log (
+    update_date DATE PRIMARY KEY,
+    modified    INTEGER NOT NULL,
+    deleted     INTEGER NOT NULL,
+    applied_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
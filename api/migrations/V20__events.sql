@@ -0,0 +1,33 @@
+-- ── Disaster events from the GDACS feed ──
+-- Recorded by the optional GDACS poller (GDACS_POLL_SECS), which queues an
+-- analyse job for each event the first time it sees it. The job's outcome is
+-- copied onto the event once finished, so it outlives the job's retention;
+-- until then GET /events reads it through job_id.
+
+CREATE TABLE IF NOT EXISTS events (
+    event_type    TEXT             NOT NULL,
+    event_id      BIGINT           NOT NULL,
+    episode_id    BIGINT,
+    name          TEXT             NOT NULL,
+    alert_level   TEXT             NOT NULL CHECK (alert_level IN ('green', 'orange', 'red')),
+    severity      DOUBLE PRECISION,
+    severity_text TEXT,
+    lat           DOUBLE PRECISION NOT NULL,
+    lon           DOUBLE PRECISION NOT NULL,
+    country       TEXT,
+    report_url    TEXT,
+    started_at    TIMESTAMPTZ,
+    ended_at      TIMESTAMPTZ,
+    job_id        UUID             REFERENCES jobs (id) ON DELETE SET NULL,
+    status        TEXT             NOT NULL DEFAULT 'queued'
+                  CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+    result        JSONB,
+    error         TEXT,
+    first_seen_at TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    updated_at    TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    PRIMARY KEY (event_type, event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_recent ON events (started_at DESC NULLS LAST);
+CREATE INDEX IF NOT EXISTS idx_events_pending ON events (job_id)
+    WHERE status IN ('queued', 'running');
@@ -0,0 +1,31 @@
+-- ── Monitored locations ──
+-- Points (centre + radius) or polygons registered via /api/v1/monitors. The
+-- API's background scheduler claims monitors whose next_run_at has passed with
+-- FOR UPDATE SKIP LOCKED, recomputes their exposure and appends a snapshot, so
+-- the history shows how the population around an asset changes.
+
+CREATE TABLE IF NOT EXISTS monitors (
+    id              UUID             PRIMARY KEY DEFAULT gen_random_uuid(),
+    name            TEXT             NOT NULL,
+    lat             DOUBLE PRECISION,
+    lon             DOUBLE PRECISION,
+    radius_km       DOUBLE PRECISION,
+    polygon         JSONB,
+    interval_hours  INTEGER          NOT NULL,
+    created_at      TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    updated_at      TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    next_run_at     TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    last_run_at     TIMESTAMPTZ,
+    last_population DOUBLE PRECISION,
+    last_error      TEXT,
+    CHECK ((polygon IS NULL) <> (lat IS NULL AND lon IS NULL AND radius_km IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_monitors_due ON monitors (next_run_at);
+
+CREATE TABLE IF NOT EXISTS monitor_snapshots (
+    monitor_id       UUID             NOT NULL REFERENCES monitors (id) ON DELETE CASCADE,
+    taken_at         TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    total_population DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (monitor_id, taken_at)
+);
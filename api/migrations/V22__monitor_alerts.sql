@@ -0,0 +1,29 @@
+-- ── Monitor alert thresholds ──
+-- Each monitor may define thresholds on its snapshots or on the exposure
+-- around GDACS events recorded inside its area. A breach is stored in
+-- monitor_alerts and, when the monitor has a webhook_url, POSTed there with
+-- the same signing and retries as job callbacks. events_checked_at is the
+-- first_seen_at of the newest event already checked against the monitor.
+
+ALTER TABLE monitors ADD COLUMN IF NOT EXISTS thresholds JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE monitors ADD COLUMN IF NOT EXISTS webhook_url TEXT;
+ALTER TABLE monitors ADD COLUMN IF NOT EXISTS events_checked_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE TABLE IF NOT EXISTS monitor_alerts (
+    id                UUID             PRIMARY KEY DEFAULT gen_random_uuid(),
+    monitor_id        UUID             NOT NULL REFERENCES monitors (id) ON DELETE CASCADE,
+    threshold         JSONB            NOT NULL,
+    value             DOUBLE PRECISION NOT NULL,
+    event_type        TEXT,
+    event_id          BIGINT,
+    created_at        TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    webhook_url       TEXT,
+    delivery_status   TEXT             CHECK (delivery_status IN ('pending', 'delivered', 'failed')),
+    delivery_attempts SMALLINT         NOT NULL DEFAULT 0,
+    delivery_next_at  TIMESTAMPTZ,
+    delivery_error    TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_monitor_alerts_monitor ON monitor_alerts (monitor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_monitor_alerts_due ON monitor_alerts (delivery_next_at)
+    WHERE delivery_status = 'pending';
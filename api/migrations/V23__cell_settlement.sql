@@ -0,0 +1,10 @@
+-- ── Settlement class of populated cells ──
+-- GHSL Settlement Model (GHS-SMOD) level-2 code for every cell in the population
+-- grid, loaded by ingestion/ingest_settlement.py: 30 urban centre, 21–23 urban
+-- cluster, 11–13 rural, 10 water. Backs the urban/rural split of /population,
+-- /exposure and /analyse; unpopulated cells are not stored.
+
+CREATE TABLE IF NOT EXISTS cell_settlement (
+    cell_id INTEGER  PRIMARY KEY,
+    smod    SMALLINT NOT NULL
+);
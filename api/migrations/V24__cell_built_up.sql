@@ -0,0 +1,11 @@
+-- ── Built-up surface ──
+-- GHSL built-up surface (GHS-BUILT-S) in m² per 30 arc-second cell, loaded by
+-- ingestion/ingest_built_up.py. Backs `built_up_m2` on /population and
+-- `include=built_up` on /exposure. Unlike the other per-cell layers it keeps
+-- every built cell, populated or not — depots, ports and industrial estates
+-- hold infrastructure but few residents. Cells without buildings are not stored.
+
+CREATE TABLE IF NOT EXISTS cell_built_up (
+    cell_id  INTEGER PRIMARY KEY,
+    built_m2 REAL    NOT NULL
+);
@@ -0,0 +1,10 @@
+-- ── Nighttime lights ──
+-- VIIRS annual nighttime-lights radiance (nW/cm²/sr) averaged onto the 30
+-- arc-second population grid, loaded by ingestion/ingest_night_lights.py.
+-- Backs `night_light_radiance` on /population and `include=night_lights` on
+-- /exposure. Only lit cells (radiance above zero) are stored.
+
+CREATE TABLE IF NOT EXISTS cell_night_lights (
+    cell_id  INTEGER PRIMARY KEY,
+    radiance REAL    NOT NULL
+);
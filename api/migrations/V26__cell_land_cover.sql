@@ -0,0 +1,11 @@
+-- ── Land cover of populated cells ──
+-- Dominant ESA WorldCover class of every cell in the population grid, loaded by
+-- ingestion/ingest_land_cover.py: the WorldCover code (10 tree cover … 100 moss
+-- and lichen) most of the cell's 10 m pixels carry. Backs `land_cover` on
+-- /population and `include=land_cover` on /exposure; unpopulated cells are not
+-- stored.
+
+CREATE TABLE IF NOT EXISTS cell_land_cover (
+    cell_id INTEGER  PRIMARY KEY,
+    class   SMALLINT NOT NULL
+);
@@ -0,0 +1,18 @@
+-- ── Critical facilities ──
+-- GeoNames S-class (spot, building, farm) features that matter in a disaster:
+-- hospitals (HSP), airports (AIRP), ports (PRT) and schools (SCH). Kept apart
+-- from `geonames`, which holds populated places only, so place lookups stay
+-- unaffected. Loaded alongside places by ingestion/ingest_geonames.py and read
+-- by /facilities/nearest.
+
+CREATE TABLE IF NOT EXISTS facilities (
+    geonameid    INTEGER PRIMARY KEY,
+    name         TEXT NOT NULL,
+    latitude     DOUBLE PRECISION NOT NULL,
+    longitude    DOUBLE PRECISION NOT NULL,
+    feature_code TEXT NOT NULL,
+    country_code CHAR(2),
+    geom         GEOMETRY(Point, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_facilities_geom ON facilities USING GiST (geom);
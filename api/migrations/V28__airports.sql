@@ -0,0 +1,36 @@
+-- ── Airports and runways ──
+-- OurAirports (public domain) airports with their ICAO / IATA codes and the
+-- runways of each, loaded by ingestion/ingest_airports.py and read by
+-- /airports/nearest. Closed airports and closed runways are not stored.
+-- Runway dimensions stay in feet as published; the API converts to metres.
+
+CREATE TABLE IF NOT EXISTS airports (
+    airport_id        INTEGER PRIMARY KEY,
+    ident             TEXT NOT NULL,
+    airport_type      TEXT NOT NULL,
+    name              TEXT NOT NULL,
+    latitude          DOUBLE PRECISION NOT NULL,
+    longitude         DOUBLE PRECISION NOT NULL,
+    elevation_ft      INTEGER,
+    country_code      CHAR(2),
+    municipality      TEXT,
+    scheduled_service BOOLEAN NOT NULL DEFAULT false,
+    icao_code         TEXT,
+    iata_code         TEXT,
+    geom              GEOMETRY(Point, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_airports_geom ON airports USING GiST (geom);
+
+CREATE TABLE IF NOT EXISTS airport_runways (
+    runway_id  INTEGER PRIMARY KEY,
+    airport_id INTEGER NOT NULL REFERENCES airports (airport_id) ON DELETE CASCADE,
+    length_ft  INTEGER,
+    width_ft   INTEGER,
+    surface    TEXT,
+    lighted    BOOLEAN NOT NULL DEFAULT false,
+    le_ident   TEXT,
+    he_ident   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_airport_runways_airport ON airport_runways (airport_id);
@@ -0,0 +1,6 @@
+-- ── Facilities radius search ──
+-- /facilities lists every facility within a radius with ST_DWithin on
+-- geography, which needs the same expression index `geonames` has; the plain
+-- geometry index only serves nearest-neighbour ordering.
+
+CREATE INDEX IF NOT EXISTS idx_facilities_geog ON facilities USING GiST ((geom::geography));
@@ -0,0 +1,11 @@
+-- ── Coastal terrain (per 30 arc-second cell) ──
+-- Elevation and distance to the nearest coastline for low-lying coastal cells,
+-- loaded by ingestion/ingest_terrain.py. Only cells within 100 km of the coast
+-- and at most 100 m above sea level are stored; /exposure?mode=coastal joins
+-- against this table, so cells absent from it are never counted.
+
+CREATE TABLE IF NOT EXISTS cell_terrain (
+    cell_id     INTEGER PRIMARY KEY,
+    elevation_m REAL    NOT NULL,
+    coast_km    REAL    NOT NULL
+);
@@ -0,0 +1,9 @@
+-- ── Elevation of populated cells ──
+-- Surface elevation (metres) for every cell in the population grid, loaded by
+-- ingestion/ingest_terrain.py. Backs POST /exposure/flood; unpopulated cells are
+-- not stored since they never contribute to a population sum.
+
+CREATE TABLE IF NOT EXISTS cell_elevation (
+    cell_id     INTEGER PRIMARY KEY,
+    elevation_m REAL    NOT NULL
+);
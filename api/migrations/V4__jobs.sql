@@ -0,0 +1,25 @@
+-- ── Asynchronous analysis jobs ──
+-- Submitted via POST /api/v1/jobs and executed by the API's background workers.
+-- Workers claim queued jobs with FOR UPDATE SKIP LOCKED, so any number of API
+-- replicas can share the queue. A job left `running` past the job timeout (its
+-- worker died) is reclaimed until it has used up its attempts.
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id          UUID        PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind        TEXT        NOT NULL,
+    params      JSONB       NOT NULL,
+    include     TEXT,
+    status      TEXT        NOT NULL DEFAULT 'queued'
+                CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+    attempts    SMALLINT    NOT NULL DEFAULT 0,
+    result      JSONB,
+    error       TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    started_at  TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_pending ON jobs (created_at)
+    WHERE status IN ('queued', 'running');
+CREATE INDEX IF NOT EXISTS idx_jobs_finished ON jobs (finished_at)
+    WHERE finished_at IS NOT NULL;
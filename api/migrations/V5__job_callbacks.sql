@@ -0,0 +1,14 @@
+-- ── Webhook callbacks for finished jobs ──
+-- When a job with a callback_url finishes, its outcome is POSTed there. Delivery
+-- state lives on the job row so retries survive restarts: callback_next_at is
+-- the next attempt (or the lease expiry of an attempt in flight).
+
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_url TEXT;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_status TEXT
+    CHECK (callback_status IN ('pending', 'delivered', 'failed'));
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_attempts SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_next_at TIMESTAMPTZ;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS callback_error TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_callback_due ON jobs (callback_next_at)
+    WHERE callback_status = 'pending';
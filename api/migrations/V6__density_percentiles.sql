@@ -0,0 +1,56 @@
+-- ── Population density percentiles ──
+-- Density distribution (people per km²) of populated cells, for /population/rank.
+-- scope is 'WORLD' or a country's ISO3 code under the current boundary vintage;
+-- row p holds the p-th percentile density. Recomputed by
+-- refresh_density_percentiles(), which the WorldPop loader calls after each load.
+
+CREATE TABLE IF NOT EXISTS density_percentiles (
+    scope      TEXT     NOT NULL,
+    percentile SMALLINT NOT NULL CHECK (percentile BETWEEN 1 AND 100),
+    density    REAL     NOT NULL,
+    PRIMARY KEY (scope, percentile)
+);
+
+-- Area of a 30 arc-second cell in km², from the latitude of its row centre.
+CREATE OR REPLACE FUNCTION cell_area_km2(cell_id INTEGER)
+RETURNS DOUBLE PRECISION LANGUAGE sql IMMUTABLE PARALLEL SAFE AS $$
+    SELECT (111.32 / 120.0) ^ 2 * cos(radians(90.0 - (cell_id / 43200 + 0.5) / 120.0))
+$$;
+
+-- Cells are assigned to the country containing their centre. A full-world run
+-- scans every populated cell once per scope, so expect it to take a while.
+CREATE OR REPLACE FUNCTION refresh_density_percentiles()
+RETURNS void LANGUAGE sql AS $$
+    DELETE FROM density_percentiles;
+
+    WITH cells AS (
+        SELECT pop / cell_area_km2(cell_id) AS density,
+               ST_SetSRID(ST_MakePoint(
+                   (cell_id % 43200 + 0.5) / 120.0 - 180.0,
+                   90.0 - (cell_id / 43200 + 0.5) / 120.0
+               ), 4326) AS centre
+        FROM population
+        WHERE pop > 0
+    ),
+    distributions AS (
+        SELECT 'WORLD' AS scope,
+               percentile_cont(ARRAY(SELECT generate_series(1, 100) / 100.0)::float8[])
+                   WITHIN GROUP (ORDER BY density) AS densities
+        FROM cells
+        HAVING COUNT(*) > 0
+        UNION ALL
+        SELECT c.iso_a3,
+               percentile_cont(ARRAY(SELECT generate_series(1, 100) / 100.0)::float8[])
+                   WITHIN GROUP (ORDER BY cells.density)
+        FROM cells
+        JOIN countries c
+          ON c.vintage = current_boundary_vintage()
+         AND ST_Contains(c.geom, cells.centre)
+        WHERE c.iso_a3 ~ '^[A-Z]{3}$'
+        GROUP BY c.iso_a3
+    )
+    INSERT INTO density_percentiles (scope, percentile, density)
+    SELECT d.scope, q.percentile, q.density
+    FROM distributions d
+    CROSS JOIN LATERAL unnest(d.densities) WITH ORDINALITY AS q (density, percentile);
+$$;
@@ -0,0 +1,50 @@
+-- ── Country grid population rollup ──
+-- WorldPop grid summed within each country polygon, per boundary vintage, for
+-- /country/{iso3}/population. Intersecting the grid with a polygon at request
+-- time is far too slow, so refresh_country_population() precomputes it; the
+-- WorldPop and Natural Earth loaders call it after each load.
+
+CREATE TABLE IF NOT EXISTS country_population (
+    vintage         INTEGER          NOT NULL REFERENCES boundary_vintages (vintage) ON DELETE CASCADE,
+    iso_a3          TEXT             NOT NULL,
+    grid_population DOUBLE PRECISION NOT NULL,
+    populated_cells BIGINT           NOT NULL,
+    computed_at     TIMESTAMPTZ      NOT NULL DEFAULT now(),
+    PRIMARY KEY (vintage, iso_a3)
+);
+
+-- Centre point of a 30 arc-second cell.
+CREATE OR REPLACE FUNCTION cell_centre(cell_id INTEGER)
+RETURNS geometry LANGUAGE sql IMMUTABLE PARALLEL SAFE AS $$
+    SELECT ST_SetSRID(ST_MakePoint(
+        (cell_id % 43200 + 0.5) / 120.0 - 180.0,
+        90.0 - (cell_id / 43200 + 0.5) / 120.0
+    ), 4326)
+$$;
+
+-- Recomputes one vintage, or every vintage when called without an argument.
+-- Cells count towards the country containing their centre; countries without
+-- populated cells get a zero row so "not computed" stays distinguishable.
+CREATE OR REPLACE FUNCTION refresh_country_population(p_vintage INTEGER DEFAULT NULL)
+RETURNS void LANGUAGE sql AS $$
+    DELETE FROM country_population WHERE p_vintage IS NULL OR vintage = p_vintage;
+
+    WITH sums AS (
+        SELECT c.vintage, UPPER(TRIM(c.iso_a3)) AS iso_a3,
+               SUM(p.pop)::float8 AS grid_population, COUNT(*) AS populated_cells
+        FROM population p
+        JOIN countries c
+          ON (p_vintage IS NULL OR c.vintage = p_vintage)
+         AND ST_Contains(c.geom, cell_centre(p.cell_id))
+        WHERE p.pop > 0 AND c.iso_a3 IS NOT NULL
+        GROUP BY 1, 2
+    )
+    INSERT INTO country_population (vintage, iso_a3, grid_population, populated_cells)
+    SELECT k.vintage, k.iso_a3, COALESCE(s.grid_population, 0), COALESCE(s.populated_cells, 0)
+    FROM (
+        SELECT DISTINCT vintage, UPPER(TRIM(iso_a3)) AS iso_a3
+        FROM countries
+        WHERE iso_a3 IS NOT NULL AND (p_vintage IS NULL OR vintage = p_vintage)
+    ) k
+    LEFT JOIN sums s USING (vintage, iso_a3);
+$$;
@@ -0,0 +1,19 @@
+-- ── Maritime boundaries (Exclusive Economic Zones) ──
+-- Marine Regions World EEZ polygons, loaded by ingestion/ingest_eez.py, for
+-- /eez. A zone has several claimants when it is an overlapping claim or a
+-- joint regime; the claimant arrays are parallel, one element per claimant,
+-- with NULL where the source has no ISO code.
+
+CREATE TABLE IF NOT EXISTS eez (
+    mrgid          INTEGER PRIMARY KEY,
+    name           TEXT    NOT NULL,
+    pol_type       TEXT    NOT NULL,
+    territories    TEXT[]  NOT NULL,
+    territory_iso3 TEXT[]  NOT NULL,
+    sovereigns     TEXT[]  NOT NULL,
+    sovereign_iso3 TEXT[]  NOT NULL,
+    area_km2       DOUBLE PRECISION,
+    geom           GEOMETRY(MultiPolygon, 4326) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_eez_geom ON eez USING GiST (geom);
@@ -0,0 +1,16 @@
+-- ── Country facts (GeoNames countryInfo.txt) ──
+-- Capital, currency, languages, calling code and TLD per ISO country, loaded
+-- by ingestion/ingest_geonames.py and joined onto /country/{iso3}. Keyed by
+-- ISO alpha-3 so it applies to every boundary vintage.
+
+CREATE TABLE IF NOT EXISTS country_info (
+    iso_a3        TEXT PRIMARY KEY,
+    iso_a2        TEXT   NOT NULL,
+    name          TEXT   NOT NULL,
+    capital       TEXT,
+    currency_code TEXT,
+    currency_name TEXT,
+    calling_code  TEXT,
+    tld           TEXT,
+    languages     TEXT[] NOT NULL DEFAULT '{}'
+);
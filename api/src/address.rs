@@ -0,0 +1,100 @@
+//! `display_name` formatting by country, after the per-country templates of
+//! OpenCage's address-formatting project.
+//!
+//! Addresses are written smallest unit first in most countries, but largest
+//! first in East Asia and Hungary, and some countries leave a level out. Each
+//! template lists the components in the order the country writes them; a
+//! component repeating the previous one (Tokyo, Tokyo; Singapore, Singapore)
+//! is dropped.
+
+/// One level of a place's address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Component {
+    Place,
+    District,
+    State,
+    Country,
+}
+
+use Component::*;
+
+/// Smallest first: place, district, state, country.
+const DEFAULT: &[Component] = &[Place, District, State, Country];
+
+/// Countries that do not follow [`DEFAULT`], by ISO 3166-1 alpha-2 code.
+const TEMPLATES: &[(&[&str], &[Component])] = &[
+    // Largest first.
+    (&["CN", "HU", "JP", "KP", "KR", "TW"], &[Country, State, District, Place]),
+    // Counties are not part of a postal address.
+    (&["AU", "CA", "US"], &[Place, State, Country]),
+];
+
+/// Names of a place's address components, each in the response language.
+#[derive(Debug, Default)]
+pub struct AddressParts<'a> {
+    pub place: &'a str,
+    pub district: Option<&'a str>,
+    pub state: Option<&'a str>,
+    pub country: Option<&'a str>,
+}
+
+impl AddressParts<'_> {
+    fn get(&self, component: Component) -> Option<&str> {
+        match component {
+            Place => Some(self.place),
+            District => self.district,
+            State => self.state,
+            Country => self.country,
+        }
+    }
+}
+
+/// `parts` joined in the order of the template for `country_code`.
+pub fn display_name(country_code: &str, parts: &AddressParts) -> String {
+    let template = TEMPLATES
+        .iter()
+        .find(|(codes, _)| codes.iter().any(|c| c.eq_ignore_ascii_case(country_code.trim())))
+        .map_or(DEFAULT, |(_, template)| template);
+
+    let mut names: Vec<&str> = Vec::with_capacity(template.len());
+    for name in template.iter().filter_map(|&c| parts.get(c)) {
+        if !name.is_empty() && names.last() != Some(&name) {
+            names.push(name);
+        }
+    }
+    names.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts<'a>(place: &'a str, district: &'a str, state: &'a str, country: &'a str) -> AddressParts<'a> {
+        AddressParts { place, district: Some(district), state: Some(state), country: Some(country) }
+    }
+
+    #[test]
+    fn orders_components_by_country() {
+        let lk = parts("Colombo", "Colombo District", "Western", "Sri Lanka");
+        assert_eq!(display_name("LK", &lk), "Colombo, Colombo District, Western, Sri Lanka");
+
+        let jp = parts("Shinjuku", "Shinjuku-ku", "Tokyo", "Japan");
+        assert_eq!(display_name("jp", &jp), "Japan, Tokyo, Shinjuku-ku, Shinjuku");
+        let hu = parts("Szentendre", "Szentendrei járás", "Pest", "Hungary");
+        assert_eq!(display_name("HU", &hu), "Hungary, Pest, Szentendrei járás, Szentendre");
+
+        let us = parts("Austin", "Travis County", "Texas", "United States");
+        assert_eq!(display_name("US", &us), "Austin, Texas, United States");
+    }
+
+    #[test]
+    fn skips_missing_and_repeated_components() {
+        let sg = AddressParts { place: "Singapore", country: Some("Singapore"), ..Default::default() };
+        assert_eq!(display_name("SG", &sg), "Singapore");
+
+        let jp = AddressParts { place: "Tokyo", state: Some("Tokyo"), country: Some("Japan"), ..Default::default() };
+        assert_eq!(display_name("JP", &jp), "Japan, Tokyo");
+
+        assert_eq!(display_name("", &AddressParts { place: "Nowhere", ..Default::default() }), "Nowhere");
+    }
+}
@@ -0,0 +1,203 @@
+//! Request audit trail in the `audit_log` table (`AUDIT_LOG=true`).
+//!
+//! The middleware records every request outside the public allowlist —
+//! including those rejected for a missing API key — with its path, a hash of
+//! the query string, a fingerprint of the presented key, the status and the
+//! latency. Records go through a bounded channel to one background writer per
+//! instance, which inserts them in batches on the primary, so a slow database
+//! never holds up responses. When the buffer is full, records are dropped and
+//! counted rather than queued without limit.
+
+use std::future::{ready, Ready};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use deadpool_postgres::Pool;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::auth;
+use crate::repositories::{AuditRecord, AuditRepository};
+
+/// Records buffered between requests and the writer.
+const BUFFER: usize = 10_000;
+/// Most records per INSERT.
+const BATCH: usize = 500;
+
+/// Hex SHA-256 of the query string.
+fn params_hash(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+/// First 16 hex digits of the key's SHA-256: enough to tell keys apart
+/// without storing one.
+fn key_fingerprint(key: &str) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..8])
+}
+
+/// Handle to the audit writer; disabled, the middleware passes requests
+/// straight through.
+#[derive(Clone, Default)]
+pub(crate) struct AuditLog {
+    tx: Option<mpsc::Sender<AuditRecord>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AuditLog {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Starts the background writer inserting into `pool`.
+    pub fn spawn(pool: Pool) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER);
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(write(pool, rx, dropped.clone()));
+        log::info!("Audit logging enabled");
+        Self { tx: Some(tx), dropped }
+    }
+
+    fn record(&self, record: AuditRecord) {
+        if let Some(tx) = &self.tx {
+            if tx.try_send(record).is_err() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+async fn write(pool: Pool, mut rx: mpsc::Receiver<AuditRecord>, dropped: Arc<AtomicU64>) {
+    let mut batch = Vec::with_capacity(BATCH);
+    while rx.recv_many(&mut batch, BATCH).await > 0 {
+        let result = match pool.get().await {
+            Ok(client) => AuditRepository::insert(&client, &batch).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to write {} audit record(s): {e}", batch.len());
+        }
+        batch.clear();
+        let lost = dropped.swap(0, Ordering::Relaxed);
+        if lost > 0 {
+            log::warn!("Audit buffer full; dropped {lost} record(s)");
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AuditLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuditLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuditLogMiddleware { service, audit: self.clone() }))
+    }
+}
+
+pub(crate) struct AuditLogMiddleware<S> {
+    service: S,
+    audit: AuditLog,
+}
+
+impl<S, B> Service<ServiceRequest> for AuditLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if self.audit.tx.is_none() || auth::is_public_path(req.path()) {
+            return Box::pin(self.service.call(req));
+        }
+
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let params_hash = params_hash(req.query_string());
+        let api_key = req
+            .headers()
+            .get(auth::API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|key| !key.is_empty())
+            .map(key_fingerprint);
+        let fut = self.service.call(req);
+        let audit = self.audit.clone();
+        Box::pin(async move {
+            let res = fut.await;
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            audit.record(AuditRecord {
+                method,
+                path,
+                params_hash,
+                api_key,
+                status: status.as_u16() as i16,
+                latency_ms: started.elapsed().as_secs_f32() * 1000.0,
+            });
+            res
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_hide_params_and_keys() {
+        assert_eq!(params_hash(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_ne!(params_hash("lat=6.9271&lon=79.8612"), params_hash("lat=6.9271&lon=79.8613"));
+        let fingerprint = key_fingerprint("secret");
+        assert_eq!(fingerprint.len(), 16);
+        assert!(!fingerprint.contains("secret"));
+    }
+
+    #[actix_web::test]
+    async fn full_buffer_drops_records() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let audit = AuditLog { tx: Some(tx), dropped: Arc::default() };
+        let app = test::init_service(
+            App::new()
+                .wrap(audit.clone())
+                .route("/api/v1/population", web::get().to(HttpResponse::Ok))
+                .route("/api/v1/health", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/population?lat=1&lon=2")
+            .insert_header((auth::API_KEY_HEADER, "secret"));
+        test::call_service(&app, req.to_request()).await;
+        test::call_service(&app, test::TestRequest::get().uri("/api/v1/health").to_request()).await;
+        test::call_service(&app, test::TestRequest::get().uri("/api/v1/population").to_request()).await;
+
+        let record = rx.recv().await.unwrap();
+        assert_eq!(record.path, "/api/v1/population");
+        assert_eq!(record.params_hash, params_hash("lat=1&lon=2"));
+        assert_eq!(record.api_key, Some(key_fingerprint("secret")));
+        assert_eq!(record.status, 200);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(audit.dropped.load(Ordering::Relaxed), 1);
+    }
+}
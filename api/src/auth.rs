@@ -16,6 +16,8 @@ use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Tr
 use actix_web::{Error, HttpResponse};
 use serde::Serialize;
 
+pub(crate) const API_KEY_HEADER: &str = "X-API-Key";
+
 #[derive(Clone)]
 pub(crate) struct ApiKeyAuth {
     pub expected_key: String,
@@ -31,13 +33,13 @@ impl ApiKeyAuth {
 ///
 /// Keep this list in sync with the public endpoints defined in `main.rs`.
 /// Everything else requires a valid `X-API-Key` header.
-fn is_public_path(path: &str) -> bool {
+pub(crate) fn is_public_path(path: &str) -> bool {
     // Root is public so uptime checks can hit `GET /` without credentials.
     if path == "/" {
         return true;
     }
     // Health check + OpenAPI JSON + Swagger UI static assets.
-    if path == "/api/v1/health" || path == "/api/v1/openapi.json" {
+    if matches!(path, "/api/v1/health" | "/api/v2/health" | "/api/v1/openapi.json") {
         return true;
     }
     if path.starts_with("/api/v1/docs") {
@@ -109,7 +111,7 @@ where
 
         let presented = req
             .headers()
-            .get("X-API-Key")
+            .get(API_KEY_HEADER)
             .and_then(|v| v.to_str().ok())
             .unwrap_or("");
 
@@ -150,6 +152,7 @@ mod tests {
     fn public_paths_bypass_auth() {
         assert!(is_public_path("/"));
         assert!(is_public_path("/api/v1/health"));
+        assert!(is_public_path("/api/v2/health"));
         assert!(is_public_path("/api/v1/openapi.json"));
         assert!(is_public_path("/api/v1/docs/"));
         assert!(is_public_path("/api/v1/docs/index.html"));
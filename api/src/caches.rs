@@ -0,0 +1,15 @@
+//! In-process caches that `/admin/cache/flush` can clear.
+//!
+//! Each cache registers itself in `AppState::caches` when the server starts.
+//! A flush reaches only the instance that serves it, so behind a load balancer
+//! every instance has to be flushed (or left to expire).
+
+/// A cache of responses or query results, keyed by request path.
+pub(crate) trait ResponseCache: Send + Sync {
+    /// Name reported by `/admin/cache/flush`.
+    fn name(&self) -> &'static str;
+
+    /// Drops the entries for paths starting with `prefix` (relative to
+    /// `API_PREFIX`), or every entry when `None`, and returns how many.
+    fn invalidate(&self, prefix: Option<&str>) -> usize;
+}
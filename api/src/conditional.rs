@@ -0,0 +1,286 @@
+//! Conditional GETs for country and dataset-derived responses.
+//!
+//! Responses of the routes in [`CONDITIONAL_PATHS`] only change when the data
+//! behind them is reloaded, so they carry an `ETag` hashed from the body. The
+//! tag is remembered per request (path, query and envelope choice) for
+//! `ETAG_TTL_SECS`; a repeat request whose `If-None-Match` names the
+//! remembered tag is answered 304 straight from that cache, without reaching
+//! the handler or the database. A request that misses the cache still gets a
+//! 304 when the freshly computed tag matches, saving the transfer. One
+//! [`TagCache`] is shared by every worker; `/admin/cache/flush` clears it
+//! after a data reload.
+
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderValue, ETAG, IF_NONE_MATCH};
+use actix_web::http::{Method, StatusCode};
+use actix_web::{error, Error, HttpResponse};
+use sha2::{Digest, Sha256};
+
+use crate::caches::ResponseCache;
+use crate::config;
+use crate::envelope::ENVELOPE_HEADER;
+
+/// Routes (relative to `API_PREFIX`) whose responses get an `ETag`; a
+/// trailing `*` matches any suffix.
+const CONDITIONAL_PATHS: &[&str] = &[
+    "/country*",
+    "/countries",
+    "/continents",
+    "/eez",
+    "/population/grid",
+    "/population/h3/*",
+    "/population/s2/*",
+    "/cell/*",
+    "/tiles/*",
+];
+
+/// Requests whose tag is remembered; the cache is emptied when it fills up.
+const MAX_ENTRIES: usize = 10_000;
+
+fn is_conditional(path: &str) -> bool {
+    CONDITIONAL_PATHS.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == *pattern,
+    })
+}
+
+/// Quoted strong entity tag of a response body. `variant` separates
+/// representations of one resource, such as the bare and enveloped bodies.
+fn entity_tag(variant: &str, body: &[u8]) -> String {
+    let digest = Sha256::new().chain_update(variant).chain_update(body).finalize();
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether an `If-None-Match` value names `etag`. Weak comparison, as RFC
+/// 9110 prescribes for `If-None-Match`.
+fn none_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// Entity tags of recent responses, keyed by request.
+#[derive(Default)]
+pub(crate) struct TagCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl TagCache {
+    fn get(&self, key: &str, ttl: Duration) -> Option<String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|(_, stored)| stored.elapsed() < ttl)
+            .map(|(etag, _)| etag.clone())
+    }
+
+    fn insert(&self, key: String, etag: String, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (_, stored)| stored.elapsed() < ttl);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (etag, Instant::now()));
+    }
+}
+
+impl ResponseCache for TagCache {
+    fn name(&self) -> &'static str {
+        "etag"
+    }
+
+    fn invalidate(&self, prefix: Option<&str>) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        match prefix {
+            Some(prefix) => entries.retain(|key, _| !config::api_path(key).starts_with(prefix)),
+            None => entries.clear(),
+        }
+        before - entries.len()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ConditionalGet {
+    ttl: Duration,
+    cache: Arc<TagCache>,
+}
+
+impl ConditionalGet {
+    /// Remember tags in `cache` for `ttl`; zero still tags responses but
+    /// never answers from the cache.
+    pub fn new(ttl: Duration, cache: Arc<TagCache>) -> Self {
+        Self { ttl, cache }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalGet
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ConditionalGetMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConditionalGetMiddleware { service, ttl: self.ttl, cache: self.cache.clone() }))
+    }
+}
+
+pub(crate) struct ConditionalGetMiddleware<S> {
+    service: S,
+    ttl: Duration,
+    cache: Arc<TagCache>,
+}
+
+fn not_modified(req: ServiceRequest, etag: &str) -> ServiceResponse<BoxBody> {
+    req.into_response(HttpResponse::NotModified().insert_header((ETAG, etag)).finish())
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalGetMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let path = config::api_path(req.path());
+        if req.method() != Method::GET || !is_conditional(path) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { Ok(fut.await?.map_into_boxed_body()) });
+        }
+
+        let variant = req
+            .headers()
+            .get(ENVELOPE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let key = format!("{}?{}#{variant}", req.path(), req.query_string());
+        let if_none_match =
+            req.headers().get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()).map(str::to_string);
+        if let Some(header) = &if_none_match {
+            if let Some(etag) = self.cache.get(&key, self.ttl).filter(|etag| none_match(header, etag)) {
+                return Box::pin(ready(Ok(not_modified(req, &etag))));
+            }
+        }
+
+        let fut = self.service.call(req);
+        let (ttl, cache) = (self.ttl, self.cache.clone());
+        Box::pin(async move {
+            let res = fut.await?;
+            if res.status() != StatusCode::OK {
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let (mut head, body) = res.into_parts();
+            let bytes = body::to_bytes(body)
+                .await
+                .map_err(|e| error::ErrorInternalServerError(e.into()))?;
+            let etag = entity_tag(&key, &bytes);
+            if !ttl.is_zero() {
+                cache.insert(key, etag.clone(), ttl);
+            }
+            if if_none_match.is_some_and(|header| none_match(&header, &etag)) {
+                return Ok(ServiceResponse::new(
+                    req,
+                    HttpResponse::NotModified().insert_header((ETAG, etag)).finish(),
+                ));
+            }
+            head.headers_mut().insert(ETAG, HeaderValue::from_str(&etag).expect("hex etag"));
+            Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::API_PREFIX;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn matches_conditional_routes() {
+        assert!(is_conditional("/country/LKA"));
+        assert!(is_conditional("/countries"));
+        assert!(is_conditional("/population/grid"));
+        assert!(!is_conditional("/population"));
+        assert!(!is_conditional("/exposure"));
+    }
+
+    #[test]
+    fn if_none_match_lists() {
+        let etag = entity_tag("", b"{}");
+        assert!(none_match(&etag, &etag));
+        assert!(none_match(&format!("\"x\", W/{etag}"), &etag));
+        assert!(none_match("*", &etag));
+        assert!(!none_match("\"x\"", &etag));
+        assert_ne!(entity_tag("false", b"{}"), etag);
+    }
+
+    #[actix_web::test]
+    async fn repeat_requests_skip_the_handler() {
+        use actix_web::{test, web, App};
+
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let cache = Arc::new(TagCache::default());
+        let app = test::init_service(
+            App::new().app_data(calls.clone()).service(
+                web::scope(API_PREFIX).wrap(ConditionalGet::new(Duration::from_secs(60), cache.clone())).route(
+                    "/country/{iso3}",
+                    web::get().to(|calls: web::Data<AtomicUsize>| async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        HttpResponse::Ok().body("{\"iso_a3\":\"LKA\"}")
+                    }),
+                ),
+            ),
+        )
+        .await;
+        let uri = format!("{API_PREFIX}/country/LKA");
+
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(ETAG).unwrap().to_str().unwrap().to_string();
+
+        let req = test::TestRequest::get().uri(&uri).insert_header((IF_NONE_MATCH, etag.as_str()));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(ETAG).unwrap(), etag.as_str());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let req = test::TestRequest::get().uri(&uri).insert_header((IF_NONE_MATCH, "\"stale\""));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Once flushed, the remembered tag no longer short-circuits.
+        assert_eq!(cache.invalidate(Some("/population")), 0);
+        assert_eq!(cache.invalidate(Some("/country")), 1);
+        let req = test::TestRequest::get().uri(&uri).insert_header((IF_NONE_MATCH, etag.as_str()));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
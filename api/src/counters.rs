@@ -0,0 +1,171 @@
+//! Per-route request counters since the instance started, for `/admin/stats`.
+//!
+//! Requests are keyed by method and the pattern of the route that took them
+//! (`/api/v1/country/{iso3}`), so the table stays as small as the route list
+//! however many distinct paths clients send.
+
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::Error;
+use chrono::{DateTime, Utc};
+
+use crate::models::EndpointCounter;
+
+#[derive(Default)]
+struct Counter {
+    requests: u64,
+    client_errors: u64,
+    server_errors: u64,
+    latency_ms: f64,
+}
+
+/// Shared counters, read by the admin handler and updated by [`CountRequests`].
+pub(crate) struct RequestCounters {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    routes: Mutex<HashMap<(String, String), Counter>>,
+}
+
+impl Default for RequestCounters {
+    fn default() -> Self {
+        Self { started_at: Utc::now(), started: Instant::now(), routes: Mutex::default() }
+    }
+}
+
+impl RequestCounters {
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn uptime_secs(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    fn record(&self, method: &str, route: &str, status: StatusCode, latency_ms: f64) {
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let counter = routes.entry((method.to_string(), route.to_string())).or_default();
+        counter.requests += 1;
+        counter.client_errors += u64::from(status.is_client_error());
+        counter.server_errors += u64::from(status.is_server_error());
+        counter.latency_ms += latency_ms;
+    }
+
+    /// Every route seen so far, busiest first.
+    pub fn snapshot(&self) -> Vec<EndpointCounter> {
+        let routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut endpoints: Vec<EndpointCounter> = routes
+            .iter()
+            .map(|((method, route), c)| EndpointCounter {
+                method: method.clone(),
+                route: route.clone(),
+                requests: c.requests,
+                client_errors: c.client_errors,
+                server_errors: c.server_errors,
+                mean_latency_ms: (c.latency_ms / c.requests as f64 * 10.0).round() / 10.0,
+            })
+            .collect();
+        endpoints.sort_by(|a, b| {
+            b.requests.cmp(&a.requests).then_with(|| (&a.route, &a.method).cmp(&(&b.route, &b.method)))
+        });
+        endpoints
+    }
+}
+
+/// Counts every response into the shared [`RequestCounters`].
+#[derive(Clone)]
+pub(crate) struct CountRequests {
+    counters: Arc<RequestCounters>,
+}
+
+impl CountRequests {
+    pub fn new(counters: Arc<RequestCounters>) -> Self {
+        Self { counters }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CountRequests
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CountRequestsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CountRequestsMiddleware { service, counters: self.counters.clone() }))
+    }
+}
+
+pub(crate) struct CountRequestsMiddleware<S> {
+    service: S,
+    counters: Arc<RequestCounters>,
+}
+
+impl<S, B> Service<ServiceRequest> for CountRequestsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let fut = self.service.call(req);
+        let counters = self.counters.clone();
+        Box::pin(async move {
+            let res = fut.await?;
+            let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".into());
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+            counters.record(&method, &route, res.status(), latency_ms);
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn counts_by_route_pattern() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let counters = Arc::new(RequestCounters::default());
+        let app = test::init_service(
+            App::new()
+                .wrap(CountRequests::new(counters.clone()))
+                .route("/country/{iso3}", web::get().to(HttpResponse::Ok))
+                .route("/fail", web::get().to(HttpResponse::InternalServerError)),
+        )
+        .await;
+
+        for uri in ["/country/LKA", "/country/IND", "/fail", "/missing"] {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let endpoints = counters.snapshot();
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(endpoints[0].route, "/country/{iso3}");
+        assert_eq!(endpoints[0].requests, 2);
+        let fail = endpoints.iter().find(|e| e.route == "/fail").unwrap();
+        assert_eq!((fail.requests, fail.server_errors), (1, 1));
+        let missing = endpoints.iter().find(|e| e.route == "unmatched").unwrap();
+        assert_eq!(missing.client_errors, 1);
+    }
+}
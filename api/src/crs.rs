@@ -0,0 +1,350 @@
+//! Projected coordinate input.
+//!
+//! GIS clients often hold coordinates in Web Mercator or a UTM zone rather
+//! than WGS84. Sending them as `lat`/`lon` gives silently wrong answers, so
+//! any endpoint under the API scope also accepts `x`, `y` and `crs=EPSG:<code>`.
+//! This middleware converts the point to WGS84, rewrites the query to the
+//! `lat`/`lon` the handlers expect, and echoes both in the payload as
+//! `input_coordinate`.
+//!
+//! Supported: EPSG:4326, EPSG:3857 and the WGS84 UTM zones (EPSG:32601–32660
+//! north, EPSG:32701–32760 south). The projections are evaluated directly
+//! (Krüger series for UTM, accurate to well under a millimetre within a zone)
+//! rather than through PROJ, which keeps the request path free of native
+//! dependencies.
+
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::future::{ready, Ready};
+use std::pin::Pin;
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::Uri;
+use actix_web::web::{Bytes, Query};
+use actix_web::{error, Error};
+use serde_json::Value;
+
+use crate::errors::AppError;
+use crate::models::InputCoordinate;
+
+/// WGS84 semi-major axis in metres, shared by Web Mercator and UTM.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const UTM_K0: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+/// Half the width of the Web Mercator square.
+const MERCATOR_MAX: f64 = PI * WGS84_A;
+
+/// A coordinate reference system accepted for `x`/`y` input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Crs {
+    Wgs84,
+    WebMercator,
+    Utm { zone: u8, south: bool },
+}
+
+impl Crs {
+    /// Parses `EPSG:<code>` (case-insensitive).
+    pub fn parse(raw: &str) -> Result<Self, AppError> {
+        let unsupported = || {
+            AppError::Validation(format!(
+                "unsupported crs `{raw}`; use EPSG:4326, EPSG:3857 or a WGS84 UTM zone (EPSG:326xx / EPSG:327xx)"
+            ))
+        };
+        let code: u32 = raw
+            .trim()
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("EPSG:"))
+            .and_then(|_| raw.trim()[5..].parse().ok())
+            .ok_or_else(unsupported)?;
+        match code {
+            4326 => Ok(Self::Wgs84),
+            3857 => Ok(Self::WebMercator),
+            32601..=32660 => Ok(Self::Utm { zone: (code - 32600) as u8, south: false }),
+            32701..=32760 => Ok(Self::Utm { zone: (code - 32700) as u8, south: true }),
+            _ => Err(unsupported()),
+        }
+    }
+
+    pub fn code(self) -> String {
+        match self {
+            Self::Wgs84 => "EPSG:4326".into(),
+            Self::WebMercator => "EPSG:3857".into(),
+            Self::Utm { zone, south } => format!("EPSG:{}", if south { 32700 } else { 32600 } + zone as u32),
+        }
+    }
+
+    /// Converts `x`/`y` (easting/northing, or lon/lat for EPSG:4326) to WGS84 `(lat, lon)`.
+    pub fn to_wgs84(self, x: f64, y: f64) -> Result<(f64, f64), AppError> {
+        if !x.is_finite() || !y.is_finite() {
+            return Err(AppError::Validation("x and y must be finite numbers".into()));
+        }
+        let (lat, lon) = match self {
+            Self::Wgs84 => (y, x),
+            Self::WebMercator => {
+                if x.abs() > MERCATOR_MAX || y.abs() > MERCATOR_MAX {
+                    return Err(AppError::Validation(format!(
+                        "x and y must lie within ±{MERCATOR_MAX:.2} m for EPSG:3857"
+                    )));
+                }
+                let lat = 2.0 * (y / WGS84_A).exp().atan() - FRAC_PI_2;
+                (lat.to_degrees(), (x / WGS84_A).to_degrees())
+            }
+            Self::Utm { zone, south } => utm_to_wgs84(zone, south, x, y),
+        };
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(AppError::Validation(format!(
+                "x={x}, y={y} in {} lies outside WGS84 bounds",
+                self.code()
+            )));
+        }
+        Ok((round7(lat), round7(lon)))
+    }
+}
+
+#[inline]
+fn round7(v: f64) -> f64 {
+    (v * 1e7).round() / 1e7
+}
+
+/// Inverse transverse Mercator by Krüger's series to third order in `n`.
+fn utm_to_wgs84(zone: u8, south: bool, easting: f64, northing: f64) -> (f64, f64) {
+    let n = WGS84_F / (2.0 - WGS84_F);
+    let (n2, n3) = (n * n, n * n * n);
+    let a = WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0);
+    let beta = [
+        n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+        n2 / 48.0 + n3 / 15.0,
+        17.0 * n3 / 480.0,
+    ];
+    let delta = [
+        2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+        7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+        56.0 * n3 / 15.0,
+    ];
+
+    let northing = if south { northing - UTM_FALSE_NORTHING_SOUTH } else { northing };
+    let xi = northing / (UTM_K0 * a);
+    let eta = (easting - UTM_FALSE_EASTING) / (UTM_K0 * a);
+    let (mut xi_p, mut eta_p) = (xi, eta);
+    for (j, b) in beta.iter().enumerate() {
+        let k = 2.0 * (j + 1) as f64;
+        xi_p -= b * (k * xi).sin() * (k * eta).cosh();
+        eta_p -= b * (k * xi).cos() * (k * eta).sinh();
+    }
+    let chi = (xi_p.sin() / eta_p.cosh()).asin();
+    let lat = chi + delta.iter().enumerate().map(|(j, d)| d * (2.0 * (j + 1) as f64 * chi).sin()).sum::<f64>();
+
+    let central_meridian = (zone as f64 * 6.0 - 183.0).to_radians();
+    let mut lon = (central_meridian + eta_p.sinh().atan2(xi_p.cos())).to_degrees();
+    if lon > 180.0 {
+        lon -= 360.0;
+    } else if lon < -180.0 {
+        lon += 360.0;
+    }
+    (lat.to_degrees(), lon)
+}
+
+/// A query rewritten for the handlers, with the converted point to echo back
+/// (`None` when the query was already in WGS84 `lat`/`lon`).
+struct Rewrite {
+    query: String,
+    input: Option<InputCoordinate>,
+}
+
+/// Replaces `x`/`y`/`crs` in the query with the WGS84 `lat`/`lon` of the
+/// point; `None` if the query has no `crs`.
+fn rewrite(query: &str) -> Result<Option<Rewrite>, AppError> {
+    let pairs = Query::<Vec<(String, String)>>::from_query(query)
+        .map_err(|e| AppError::Validation(format!("invalid query string: {e}")))?
+        .into_inner();
+    let get = |name: &str| pairs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+    let Some(crs) = get("crs") else {
+        return Ok(None);
+    };
+    let crs = Crs::parse(crs)?;
+
+    let number = |name: &str| -> Result<Option<f64>, AppError> {
+        get(name)
+            .map(|v| v.trim().parse::<f64>().map_err(|_| AppError::Validation(format!("{name} must be a number"))))
+            .transpose()
+    };
+    let point = match (number("x")?, number("y")?) {
+        (Some(x), Some(y)) if get("lat").is_none() && get("lon").is_none() => Some((x, y)),
+        (Some(_), Some(_)) => {
+            return Err(AppError::Validation("send either x/y with crs, or lat/lon, not both".into()))
+        }
+        (None, None) if crs == Crs::Wgs84 => None,
+        _ => {
+            return Err(AppError::Validation(format!(
+                "crs={} needs the coordinate as x (easting) and y (northing)",
+                crs.code()
+            )))
+        }
+    };
+
+    let kept = query
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split_once('=').map_or(*pair, |(k, _)| k);
+            !matches!(key, "crs" | "x" | "y")
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    let Some((x, y)) = point else {
+        return Ok(Some(Rewrite { query: kept, input: None }));
+    };
+
+    let (lat, lon) = crs.to_wgs84(x, y)?;
+    let query = if kept.is_empty() {
+        format!("lat={lat}&lon={lon}")
+    } else {
+        format!("{kept}&lat={lat}&lon={lon}")
+    };
+    Ok(Some(Rewrite { query, input: Some(InputCoordinate { crs: crs.code(), x, y, lat, lon }) }))
+}
+
+/// Adds `input_coordinate` to the `payload` of an enveloped JSON body.
+fn echo(body: &[u8], input: &InputCoordinate) -> Option<Bytes> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let payload = value.get_mut("payload")?.as_object_mut()?;
+    payload.insert("input_coordinate".into(), serde_json::to_value(input).ok()?);
+    serde_json::to_vec(&value).ok().map(Bytes::from)
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct CrsInput;
+
+impl<S, B> Transform<S, ServiceRequest> for CrsInput
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CrsInputMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CrsInputMiddleware { service }))
+    }
+}
+
+pub(crate) struct CrsInputMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for CrsInputMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let input = match rewrite(req.query_string()) {
+            Ok(None) => None,
+            Ok(Some(Rewrite { query, input })) => {
+                let path_and_query = if query.is_empty() {
+                    req.path().to_string()
+                } else {
+                    format!("{}?{query}", req.path())
+                };
+                let mut parts = req.uri().clone().into_parts();
+                parts.path_and_query = path_and_query.parse().ok();
+                if let Ok(uri) = Uri::from_parts(parts) {
+                    req.match_info_mut().get_mut().update(&uri);
+                    req.head_mut().uri = uri;
+                }
+                input
+            }
+            Err(e) => {
+                return Box::pin(ready(Ok(req.error_response(e).map_into_boxed_body())));
+            }
+        };
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            let is_json = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("application/json"));
+            let Some(input) = input.filter(|_| is_json && res.status().is_success()) else {
+                return Ok(res.map_into_boxed_body());
+            };
+
+            let (req, res) = res.into_parts();
+            let (head, body) = res.into_parts();
+            let bytes = body::to_bytes(body)
+                .await
+                .map_err(|e| error::ErrorInternalServerError(e.into()))?;
+            let bytes = echo(&bytes, &input).unwrap_or(bytes);
+            Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn parses_supported_codes() {
+        assert_eq!(Crs::parse("EPSG:3857").unwrap(), Crs::WebMercator);
+        assert_eq!(Crs::parse("epsg:4326").unwrap(), Crs::Wgs84);
+        assert_eq!(Crs::parse("EPSG:32644").unwrap(), Crs::Utm { zone: 44, south: false });
+        assert_eq!(Crs::parse("EPSG:32756").unwrap().code(), "EPSG:32756");
+        assert!(Crs::parse("EPSG:32661").is_err());
+        assert!(Crs::parse("EPSG:27700").is_err());
+        assert!(Crs::parse("3857").is_err());
+    }
+
+    #[test]
+    fn converts_to_wgs84() {
+        let mercator = Crs::WebMercator;
+        assert_close(mercator.to_wgs84(8_890_108.118, 773_006.716).unwrap(), (6.9271, 79.8612));
+        assert!(mercator.to_wgs84(3e7, 0.0).is_err());
+
+        let colombo = Crs::Utm { zone: 44, south: false };
+        assert_close(colombo.to_wgs84(374190.8539, 765840.8426).unwrap(), (6.9271, 79.8612));
+        let sydney = Crs::Utm { zone: 56, south: true };
+        assert_close(sydney.to_wgs84(334368.6336, 6250948.3453).unwrap(), (-33.8688, 151.2093));
+        assert_close(Crs::Utm { zone: 31, south: false }.to_wgs84(500_000.0, 0.0).unwrap(), (0.0, 3.0));
+    }
+
+    #[test]
+    fn rewrites_query() {
+        assert!(rewrite("lat=6.9&lon=79.8&radius=5").unwrap().is_none());
+
+        let mercator = rewrite("x=8890108.118&y=773006.716&crs=EPSG%3A3857&radius=5").unwrap().unwrap();
+        assert_eq!(mercator.query, "radius=5&lat=6.9271&lon=79.8612");
+        let input = mercator.input.unwrap();
+        assert_eq!(input.crs, "EPSG:3857");
+        assert_eq!(input.x, 8_890_108.118);
+
+        let wgs84 = rewrite("lat=6.9&lon=79.8&crs=EPSG:4326").unwrap().unwrap();
+        assert_eq!(wgs84.query, "lat=6.9&lon=79.8");
+        assert!(wgs84.input.is_none());
+
+        assert!(rewrite("lat=6.9&lon=79.8&crs=EPSG:3857").is_err());
+        assert!(rewrite("x=1&y=2&lat=6.9&crs=EPSG:3857").is_err());
+    }
+}
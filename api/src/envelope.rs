@@ -0,0 +1,138 @@
+//! Envelope opt-out middleware.
+//!
+//! Every JSON response is wrapped as `{success, message, payload}` (see
+//! [`ApiResponse`](crate::response::ApiResponse)). Clients that expect the
+//! documented schema objects at the top level — GIS tools, generated clients —
+//! can send `?envelope=false` or `X-GeoPop-Envelope: false` to receive just the
+//! `payload`. Under `/api/v2` the payload is always returned bare. Error
+//! responses keep the envelope so the message is not lost; streaming responses
+//! are never touched.
+
+use std::future::{ready, Ready};
+use std::pin::Pin;
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, CONTENT_TYPE};
+use actix_web::web::Bytes;
+use actix_web::{error, Error};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::config;
+
+pub(crate) const ENVELOPE_HEADER: &str = "X-GeoPop-Envelope";
+
+#[derive(Clone, Default)]
+pub(crate) struct EnvelopeOptOut;
+
+/// Whether the request asked for the bare payload.
+fn wants_bare(query: &str, headers: &HeaderMap) -> bool {
+    let in_query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| key == "envelope" && value.eq_ignore_ascii_case("false"));
+    let in_header = headers
+        .get(ENVELOPE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("false"));
+    in_query || in_header
+}
+
+#[derive(Deserialize)]
+struct Enveloped<'a> {
+    #[allow(dead_code)]
+    success: bool,
+    #[serde(borrow)]
+    payload: &'a RawValue,
+}
+
+/// The `payload` of an enveloped body, or `None` if `body` is not one.
+fn strip(body: &[u8]) -> Option<Bytes> {
+    let enveloped: Enveloped = serde_json::from_slice(body).ok()?;
+    Some(Bytes::copy_from_slice(enveloped.payload.get().as_bytes()))
+}
+
+impl<S, B> Transform<S, ServiceRequest> for EnvelopeOptOut
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = EnvelopeOptOutMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(EnvelopeOptOutMiddleware { service }))
+    }
+}
+
+pub(crate) struct EnvelopeOptOutMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for EnvelopeOptOutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let bare = config::is_v2(req.path()) || wants_bare(req.query_string(), req.headers());
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            let is_json = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("application/json"));
+            if !bare || !is_json || !res.status().is_success() {
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let (head, body) = res.into_parts();
+            let bytes = body::to_bytes(body)
+                .await
+                .map_err(|e| error::ErrorInternalServerError(e.into()))?;
+            let bytes = strip(&bytes).unwrap_or(bytes);
+            Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    #[test]
+    fn opt_out_by_query_or_header() {
+        let none = HeaderMap::new();
+        assert!(wants_bare("lat=1&envelope=false", &none));
+        assert!(wants_bare("envelope=FALSE", &none));
+        assert!(!wants_bare("envelope=true", &none));
+        assert!(!wants_bare("lat=1&lon=2", &none));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ENVELOPE_HEADER.try_into().unwrap(), HeaderValue::from_static("false"));
+        assert!(wants_bare("", &headers));
+    }
+
+    #[test]
+    fn strips_only_enveloped_bodies() {
+        let body = br#"{"success":true,"message":"success","payload":{"population":12.5}}"#;
+        assert_eq!(strip(body).unwrap(), Bytes::from_static(br#"{"population":12.5}"#));
+        assert!(strip(br#"{"openapi":"3.1.0"}"#).is_none());
+    }
+}
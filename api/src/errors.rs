@@ -1,4 +1,7 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use tokio_postgres::error::SqlState;
 use serde::Serialize;
 use std::fmt;
 
@@ -6,7 +9,26 @@ use std::fmt;
 pub(crate) enum AppError {
     Validation(String),
     Database(String),
+    /// A database failure that is likely gone on the next attempt: a broken
+    /// connection, a serialization failure or a cancelled standby query.
+    Transient(String),
     NotFound(String),
+    /// Missing or wrong credentials for a protected route.
+    Unauthorized(String),
+    /// An external service (e.g. the routing engine) failed or is unreachable.
+    Upstream(String),
+    /// The database circuit breaker is open; retry after this many seconds.
+    Unavailable(u64),
+    /// Work that ran past its time limit, e.g. a background job.
+    Timeout(String),
+    /// A server-wide concurrency limit is reached (e.g. open `/tag/stream`
+    /// bodies); the request can be repeated later.
+    Busy(String),
+    /// A request body over the configured limit of this many bytes.
+    PayloadTooLarge(usize),
+    /// Nothing within the requested `max_km`; the nearest candidate is
+    /// `nearest_km` away.
+    OutOfRange { max_km: f64, nearest_km: f64 },
 }
 
 impl fmt::Display for AppError {
@@ -14,36 +36,110 @@ impl fmt::Display for AppError {
         match self {
             Self::Validation(msg) => write!(f, "validation error: {msg}"),
             Self::Database(msg) => write!(f, "database error: {msg}"),
+            Self::Transient(msg) => write!(f, "transient database error: {msg}"),
             Self::NotFound(msg) => write!(f, "not found: {msg}"),
+            Self::Unauthorized(msg) => write!(f, "unauthorized: {msg}"),
+            Self::Upstream(msg) => write!(f, "upstream error: {msg}"),
+            Self::Unavailable(secs) => write!(f, "database unavailable, retry after {secs}s"),
+            Self::Timeout(msg) => write!(f, "timeout: {msg}"),
+            Self::Busy(msg) => write!(f, "busy: {msg}"),
+            Self::PayloadTooLarge(limit) => write!(f, "request body exceeds {limit} bytes"),
+            Self::OutOfRange { max_km, nearest_km } => {
+                write!(f, "nothing within {max_km} km, nearest at {nearest_km} km")
+            }
+        }
+    }
+}
+
+impl AppError {
+    /// Message safe to show to API clients; database and upstream details are
+    /// only logged.
+    pub fn public_message(&self) -> &str {
+        match self {
+            Self::Validation(msg)
+            | Self::NotFound(msg)
+            | Self::Unauthorized(msg)
+            | Self::Timeout(msg)
+            | Self::Busy(msg) => msg,
+            Self::Database(_) => "database connection error",
+            Self::Upstream(_) => "upstream service unavailable",
+            Self::Transient(_) | Self::Unavailable(_) => "database temporarily unavailable",
+            Self::PayloadTooLarge(_) => "request body too large",
+            Self::OutOfRange { .. } => "No place within max_distance_km",
         }
     }
 }
 
 impl ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
+        let body = ErrorBody {
+            success: false,
+            message: self.public_message(),
+            payload: None::<()>,
+        };
         match self {
-            Self::Validation(msg) => HttpResponse::BadRequest().json(ErrorBody {
-                success: false,
-                message: msg,
-                payload: None::<()>,
-            }),
+            Self::Validation(_) => HttpResponse::BadRequest().json(body),
             Self::Database(msg) => {
                 log::error!("Database error: {msg}");
-                HttpResponse::InternalServerError().json(ErrorBody {
-                    success: false,
-                    message: "database connection error",
-                    payload: None::<()>,
-                })
+                HttpResponse::InternalServerError().json(body)
+            }
+            Self::Transient(msg) => {
+                log::error!("Transient database error: {msg}");
+                HttpResponse::ServiceUnavailable().json(body)
+            }
+            Self::NotFound(_) => HttpResponse::NotFound().json(body),
+            Self::Unauthorized(_) => HttpResponse::Unauthorized().json(body),
+            Self::Upstream(msg) => {
+                log::error!("Upstream error: {msg}");
+                HttpResponse::BadGateway().json(body)
             }
-            Self::NotFound(msg) => HttpResponse::NotFound().json(ErrorBody {
+            Self::Unavailable(secs) => HttpResponse::ServiceUnavailable()
+                .insert_header((header::RETRY_AFTER, secs.to_string()))
+                .json(body),
+            Self::Timeout(msg) => {
+                log::warn!("Timeout: {msg}");
+                HttpResponse::GatewayTimeout().json(body)
+            }
+            Self::Busy(msg) => {
+                log::warn!("Busy: {msg}");
+                HttpResponse::ServiceUnavailable().json(body)
+            }
+            Self::PayloadTooLarge(limit) => HttpResponse::PayloadTooLarge().json(ErrorBody {
+                success: false,
+                message: &format!("Request body exceeds the limit of {limit} bytes"),
+                payload: Some(PayloadLimit { max_bytes: *limit }),
+            }),
+            Self::OutOfRange { max_km, nearest_km } => HttpResponse::NotFound().json(ErrorBody {
                 success: false,
-                message: msg,
-                payload: None::<()>,
+                message: &format!("No place within {max_km} km; the nearest is {nearest_km} km away"),
+                payload: Some(DistanceLimit { max_distance_km: *max_km, nearest_distance_km: *nearest_km }),
             }),
         }
     }
 }
 
+/// Whether a query may succeed when simply run again: the connection broke
+/// (I/O error, closed, or terminated by an administrator or a failover), the
+/// server is not accepting connections yet, or the query lost a
+/// serialization, deadlock or standby recovery conflict (40001, 40P01).
+fn is_transient(err: &tokio_postgres::Error) -> bool {
+    match err.as_db_error() {
+        Some(db_err) => {
+            let code = db_err.code();
+            code.code().starts_with("08")
+                || [
+                    SqlState::T_R_SERIALIZATION_FAILURE,
+                    SqlState::T_R_DEADLOCK_DETECTED,
+                    SqlState::ADMIN_SHUTDOWN,
+                    SqlState::CRASH_SHUTDOWN,
+                    SqlState::CANNOT_CONNECT_NOW,
+                ]
+                .contains(code)
+        }
+        None => err.is_closed() || std::error::Error::source(err).is_some_and(|e| e.is::<std::io::Error>()),
+    }
+}
+
 impl From<tokio_postgres::Error> for AppError {
     fn from(err: tokio_postgres::Error) -> Self {
         let msg = if let Some(db_err) = err.as_db_error() {
@@ -56,19 +152,110 @@ impl From<tokio_postgres::Error> for AppError {
         } else {
             err.to_string()
         };
-        Self::Database(msg)
+        if is_transient(&err) {
+            Self::Transient(msg)
+        } else {
+            Self::Database(msg)
+        }
     }
 }
 
 impl From<deadpool_postgres::PoolError> for AppError {
     fn from(err: deadpool_postgres::PoolError) -> Self {
-        Self::Database(err.to_string())
+        match err {
+            deadpool_postgres::PoolError::Backend(e) => e.into(),
+            e => Self::Database(e.to_string()),
+        }
     }
 }
 
+/// `JsonConfig` error handler: answers rejected JSON bodies in the error
+/// envelope instead of actix's plain-text message, with 413 and the allowed
+/// maximum for oversized ones.
+pub(crate) fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let app_err = match &err {
+        JsonPayloadError::OverflowKnownLength { limit, .. } | JsonPayloadError::Overflow { limit } => {
+            AppError::PayloadTooLarge(*limit)
+        }
+        JsonPayloadError::ContentType => {
+            AppError::Validation("Content-Type must be application/json".into())
+        }
+        JsonPayloadError::Deserialize(e) => AppError::Validation(format!("Invalid JSON body: {e}")),
+        other => AppError::Validation(format!("Invalid request body: {other}")),
+    };
+    InternalError::from_response(err, app_err.error_response()).into()
+}
+
+/// Payload of a 413 response.
+#[derive(Serialize)]
+struct PayloadLimit {
+    max_bytes: usize,
+}
+
+/// Payload of a 404 for a lookup capped by distance.
+#[derive(Serialize)]
+struct DistanceLimit {
+    max_distance_km: f64,
+    nearest_distance_km: f64,
+}
+
 #[derive(Serialize)]
 struct ErrorBody<'a, T: Serialize> {
     success: bool,
     message: &'a str,
     payload: Option<T>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn oversized_json_gets_a_structured_413() {
+        use actix_web::{test, web, App};
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().limit(32).error_handler(json_error))
+                .route("/batch", web::post().to(|body: web::Json<Vec<f64>>| async move {
+                    HttpResponse::Ok().json(body.len())
+                })),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/batch").set_json([1.0; 20]).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["success"], false);
+        assert_eq!(body["payload"]["max_bytes"], 32);
+        assert!(body["message"].as_str().unwrap().contains("32 bytes"));
+
+        let req = test::TestRequest::post().uri("/batch").set_json("[1,").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert!(body["message"].as_str().unwrap().starts_with("Invalid JSON body"));
+    }
+
+    #[actix_web::test]
+    async fn out_of_range_reports_the_nearest_distance() {
+        let err = AppError::OutOfRange { max_km: 10.0, nearest_km: 94.6 };
+        let res = err.error_response();
+        assert_eq!(res.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["payload"]["max_distance_km"], 10.0);
+        assert_eq!(body["payload"]["nearest_distance_km"], 94.6);
+        assert!(body["message"].as_str().unwrap().contains("94.6 km away"));
+    }
+
+    #[actix_web::test]
+    async fn busy_is_a_503_with_its_message() {
+        let res = AppError::Busy("8 tag streams are already open; retry later".into()).error_response();
+        assert_eq!(res.status(), actix_web::http::StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["message"], "8 tag streams are already open; retry later");
+    }
+}
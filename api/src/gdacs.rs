@@ -0,0 +1,218 @@
+//! Optional poller for the GDACS disaster event feed behind `/events`.
+//!
+//! Every `GDACS_POLL_SECS` the feed at `GDACS_URL` is read and each event at
+//! `GDACS_MIN_ALERT` or above is recorded in the `events` table. An event seen
+//! for the first time gets an analyse job for its location, run by the regular
+//! job workers; earthquakes within the `earthquake` hazard template's range are
+//! analysed with it, everything else with the auto-expanding search. Later
+//! episodes update the event but keep its first analysis.
+//!
+//! Recording is idempotent, so several instances may poll the same database,
+//! though one poller is enough.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use deadpool_postgres::Pool;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::config::{Config, Limits};
+use crate::errors::AppError;
+use crate::jobs::{JobQueue, JobRequest};
+use crate::models::{AlertLevel, JobKind};
+use crate::repositories::{EventRecord, EventRepository};
+use crate::validation::EVENT_TYPES;
+
+const GDACS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the feed and records its events.
+struct Poller {
+    http: reqwest::Client,
+    url: String,
+    min_alert: AlertLevel,
+    limits: Limits,
+    queue: JobQueue,
+}
+
+/// Starts the poller when `GDACS_POLL_SECS` is set.
+pub(crate) fn spawn_poller(pool: Pool, config: &Config, queue: JobQueue) {
+    if config.gdacs_poll_secs == 0 {
+        return;
+    }
+    let http = match reqwest::Client::builder().timeout(GDACS_TIMEOUT).build() {
+        Ok(http) => http,
+        Err(e) => {
+            log::error!("GDACS polling disabled: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+    let poller = Poller {
+        http,
+        url: config.gdacs_url.clone(),
+        min_alert: config.gdacs_min_alert,
+        limits: config.limits.clone(),
+        queue,
+    };
+    let interval = Duration::from_secs(config.gdacs_poll_secs);
+    log::info!(
+        "Polling GDACS every {}s for {} alerts and above",
+        config.gdacs_poll_secs,
+        config.gdacs_min_alert.as_str()
+    );
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = poller.poll(&pool).await {
+                log::warn!("GDACS poll failed: {e}");
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+impl Poller {
+    async fn poll(&self, pool: &Pool) -> Result<(), AppError> {
+        let response = self
+            .http
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("GDACS: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::Upstream(format!("GDACS returned {status}")));
+        }
+        let feed: Value = response.json().await.map_err(|e| AppError::Upstream(format!("GDACS: {e}")))?;
+
+        let client = pool.get().await?;
+        let mut queued = 0;
+        for event in parse_feed(&feed).into_iter().filter(|e| e.alert_level >= self.min_alert) {
+            let params = analyse_params(&event, &self.limits);
+            if EventRepository::upsert(&client, &event, &params).await? {
+                log::info!("GDACS event {}{} ({}) recorded", event.event_type, event.event_id, event.name);
+                queued += 1;
+            }
+        }
+        if queued > 0 {
+            self.queue.wake();
+        }
+        EventRepository::store_results(&client).await?;
+        Ok(())
+    }
+}
+
+/// The point events of a GDACS GeoJSON feed, once each. Features without a
+/// point geometry, a known type or an alert level are skipped.
+fn parse_feed(feed: &Value) -> Vec<EventRecord> {
+    let mut seen = HashSet::new();
+    let Some(features) = feed["features"].as_array() else {
+        return Vec::new();
+    };
+    features
+        .iter()
+        .filter_map(|feature| {
+            let p = &feature["properties"];
+            let geometry = &feature["geometry"];
+            if geometry["type"] != "Point" {
+                return None;
+            }
+            let lon = geometry["coordinates"][0].as_f64()?;
+            let lat = geometry["coordinates"][1].as_f64()?;
+            let event_type = p["eventtype"].as_str()?.to_ascii_uppercase();
+            if !EVENT_TYPES.contains(&event_type.as_str()) {
+                return None;
+            }
+            let event_id = p["eventid"].as_i64()?;
+            if !seen.insert((event_type.clone(), event_id)) {
+                return None;
+            }
+            let text = |v: &Value| v.as_str().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+            Some(EventRecord {
+                event_id,
+                episode_id: p["episodeid"].as_i64(),
+                name: text(&p["name"]).unwrap_or_else(|| format!("{event_type} {event_id}")),
+                alert_level: AlertLevel::parse(p["alertlevel"].as_str()?)?,
+                severity: p["severitydata"]["severity"].as_f64(),
+                severity_text: text(&p["severitydata"]["severitytext"]),
+                lat,
+                lon,
+                country: text(&p["country"]),
+                report_url: text(&p["url"]["report"]),
+                started_at: timestamp(&p["fromdate"]),
+                ended_at: timestamp(&p["todate"]),
+                event_type,
+            })
+        })
+        .collect()
+}
+
+/// GDACS dates are UTC without an offset: `2024-01-01T07:10:09`.
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    let raw = value.as_str()?;
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S")
+        .map(|t| t.and_utc())
+        .or_else(|_| raw.parse::<DateTime<Utc>>())
+        .ok()
+}
+
+/// `/analyse` parameters for an event: the earthquake template for
+/// earthquakes it accepts, the auto-expanding search otherwise.
+fn analyse_params(event: &EventRecord, limits: &Limits) -> Value {
+    if let (Some(magnitude), "EQ") = (event.severity, event.event_type.as_str()) {
+        let quake = json!({ "lat": event.lat, "lon": event.lon, "hazard": "earthquake", "magnitude": magnitude });
+        if JobRequest::parse(JobKind::Analyse, &quake, None, limits).is_ok() {
+            return quake;
+        }
+    }
+    json!({ "lat": event.lat, "lon": event.lon })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed() -> Value {
+        let quake = json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [142.4, 37.5] },
+            "properties": {
+                "eventtype": "EQ", "eventid": 1402359, "episodeid": 1559580,
+                "name": "Earthquake in Japan", "alertlevel": "Orange", "country": "Japan",
+                "fromdate": "2024-01-01T07:10:09", "todate": "2024-01-01T07:10:09",
+                "severitydata": { "severity": 7.5, "severitytext": "Magnitude 7.5M, Depth:10km" },
+                "url": { "report": "https://www.gdacs.org/report.aspx?eventid=1402359" }
+            }
+        });
+        let mut area = quake.clone();
+        area["geometry"] = json!({ "type": "Polygon", "coordinates": [] });
+        let cyclone = json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [80.1, 12.0] },
+            "properties": { "eventtype": "TC", "eventid": 1001, "name": " ", "alertlevel": "Red" }
+        });
+        json!({ "type": "FeatureCollection", "features": [area, quake.clone(), quake, cyclone] })
+    }
+
+    #[test]
+    fn parses_point_events_once() {
+        let events = parse_feed(&feed());
+        assert_eq!(events.len(), 2);
+        let quake = &events[0];
+        assert_eq!((quake.event_type.as_str(), quake.event_id), ("EQ", 1402359));
+        assert_eq!((quake.lat, quake.lon), (37.5, 142.4));
+        assert_eq!(quake.alert_level, AlertLevel::Orange);
+        assert_eq!(quake.severity, Some(7.5));
+        assert_eq!(quake.started_at.unwrap().to_rfc3339(), "2024-01-01T07:10:09+00:00");
+        assert_eq!(events[1].name, "TC 1001");
+        assert!(parse_feed(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn analyses_earthquakes_with_the_hazard_template() {
+        let limits = Limits::default();
+        let mut events = parse_feed(&feed());
+        assert_eq!(analyse_params(&events[0], &limits)["hazard"], "earthquake");
+        assert_eq!(analyse_params(&events[1], &limits), json!({ "lat": 12.0, "lon": 80.1 }));
+        events[0].severity = Some(2.0);
+        assert_eq!(analyse_params(&events[0], &limits).get("hazard"), None);
+    }
+}
@@ -0,0 +1,436 @@
+//! GeoJSON outlines of analysed areas, so clients can shade exactly what the
+//! server evaluated, and point-to-point distance and bearing.
+
+use serde_json::{json, Value};
+
+const KM_PER_DEG: f64 = 111.32;
+/// IUGG mean Earth radius.
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+/// WGS84 semi-major axis (m) and flattening.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const CIRCLE_VERTICES: usize = 64;
+
+/// Outline of an exposure search area as the population queries evaluate it.
+///
+/// Cells are counted when their centre lies within `radius_km` under an
+/// equirectangular distance (111.32 km per degree, longitude scaled by
+/// `cos(lat)`), so the true boundary is an ellipse in lon/lat rather than a
+/// geodesic circle. The ring is counter-clockwise per RFC 7946.
+pub fn search_area(lat: f64, lon: f64, radius_km: f64) -> Value {
+    let dlat = radius_km / KM_PER_DEG;
+    let dlon = radius_km / (KM_PER_DEG * lat.to_radians().cos().max(0.01));
+
+    let mut ring: Vec<[f64; 2]> = (0..CIRCLE_VERTICES)
+        .map(|i| {
+            let theta = std::f64::consts::TAU * i as f64 / CIRCLE_VERTICES as f64;
+            [
+                round6(lon + dlon * theta.cos()),
+                round6((lat + dlat * theta.sin()).clamp(-90.0, 90.0)),
+            ]
+        })
+        .collect();
+    ring.push(ring[0]);
+
+    json!({ "type": "Polygon", "coordinates": [ring] })
+}
+
+/// Wraps geometries into a FeatureCollection, tagging each with a `kind` property.
+pub fn feature_collection(features: Vec<(&str, Value)>) -> Value {
+    let features: Vec<Value> = features
+        .into_iter()
+        .map(|(kind, geometry)| {
+            json!({ "type": "Feature", "properties": { "kind": kind }, "geometry": geometry })
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Bounding box `[min_lon, min_lat, max_lon, max_lat]` of a GeoJSON Polygon,
+/// MultiPolygon or GeometryCollection of them, checking ring closure and
+/// coordinate ranges on the way.
+pub fn polygon_bounds(geometry: &Value) -> Result<[f64; 4], String> {
+    let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+    for polygon in polygon_parts(geometry)? {
+        let [x0, y0, x1, y1] = part_bounds(polygon)?;
+        bounds = [bounds[0].min(x0), bounds[1].min(y0), bounds[2].max(x1), bounds[3].max(y1)];
+    }
+    Ok(bounds)
+}
+
+/// The polygons of a GeoJSON Polygon, MultiPolygon or GeometryCollection of
+/// them (nested collections included) as their ring arrays, in input order.
+pub fn polygon_parts(geometry: &Value) -> Result<Vec<&Value>, String> {
+    let parts: Vec<&Value> = match geometry["type"].as_str() {
+        Some("Polygon") => vec![&geometry["coordinates"]],
+        Some("MultiPolygon") => geometry["coordinates"]
+            .as_array()
+            .ok_or("MultiPolygon coordinates must be an array")?
+            .iter()
+            .collect(),
+        Some("GeometryCollection") => {
+            let mut parts = Vec::new();
+            for member in geometry["geometries"].as_array().ok_or("GeometryCollection geometries must be an array")? {
+                parts.extend(polygon_parts(member)?);
+            }
+            parts
+        }
+        _ => {
+            return Err("polygon must be a GeoJSON Polygon, MultiPolygon or GeometryCollection of them".into())
+        }
+    };
+    if parts.is_empty() {
+        return Err("polygon must hold at least one polygon".into());
+    }
+    Ok(parts)
+}
+
+/// One MultiPolygon of polygon ring arrays, as taken apart by [`polygon_parts`].
+pub fn multi_polygon(parts: &[&Value]) -> Value {
+    json!({ "type": "MultiPolygon", "coordinates": parts })
+}
+
+/// Bounding box of one polygon's ring array, checking ring closure and
+/// coordinate ranges.
+pub fn part_bounds(polygon: &Value) -> Result<[f64; 4], String> {
+    let rings = polygon
+        .as_array()
+        .filter(|rings| !rings.is_empty())
+        .ok_or("each polygon needs at least one ring")?;
+    let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+    for ring in rings {
+        let positions: Vec<Vec<f64>> = serde_json::from_value(ring.clone())
+            .map_err(|_| "ring positions must be [lon, lat] number pairs")?;
+        if positions.len() < 4 || positions.first() != positions.last() {
+            return Err("each ring must be closed and have at least 4 positions".into());
+        }
+        for position in &positions {
+            let (lon, lat) = match position.as_slice() {
+                [lon, lat, ..] => (*lon, *lat),
+                _ => return Err("ring positions must be [lon, lat] number pairs".into()),
+            };
+            if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+                return Err(format!("position [{lon}, {lat}] is out of range"));
+            }
+            bounds = [bounds[0].min(lon), bounds[1].min(lat), bounds[2].max(lon), bounds[3].max(lat)];
+        }
+    }
+    Ok(bounds)
+}
+
+/// Bounding box of a GeoJSON Point, MultiPoint, LineString, MultiLineString,
+/// Polygon or MultiPolygon geometry, checking coordinate ranges on the way.
+/// Polygons go through [`polygon_bounds`] and its ring checks.
+pub fn geometry_bounds(geometry: &Value) -> Result<[f64; 4], String> {
+    let coordinates = &geometry["coordinates"];
+    let positions: Vec<Vec<f64>> = match geometry["type"].as_str() {
+        Some("Polygon" | "MultiPolygon") => return polygon_bounds(geometry),
+        Some("Point") => serde_json::from_value(coordinates.clone()).map(|p| vec![p]),
+        Some("MultiPoint" | "LineString") => serde_json::from_value(coordinates.clone()),
+        Some("MultiLineString") => {
+            serde_json::from_value::<Vec<Vec<Vec<f64>>>>(coordinates.clone()).map(|lines| lines.concat())
+        }
+        _ => {
+            return Err("geometry must be a GeoJSON Point, MultiPoint, LineString, MultiLineString, \
+                Polygon or MultiPolygon"
+                .into())
+        }
+    }
+    .map_err(|_| "positions must be [lon, lat] number pairs")?;
+    if positions.is_empty() {
+        return Err("geometry needs at least one position".into());
+    }
+
+    let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+    for position in &positions {
+        let (lon, lat) = match position.as_slice() {
+            [lon, lat, ..] => (*lon, *lat),
+            _ => return Err("positions must be [lon, lat] number pairs".into()),
+        };
+        if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+            return Err(format!("position [{lon}, {lat}] is out of range"));
+        }
+        bounds = [bounds[0].min(lon), bounds[1].min(lat), bounds[2].max(lon), bounds[3].max(lat)];
+    }
+    Ok(bounds)
+}
+
+/// `bounds` grown by `km` on every side, with longitude scaled at the latitude
+/// furthest from the equator. Not clamped, so callers can spot a box that
+/// crosses the antimeridian or a pole.
+pub fn grow_bounds(bounds: [f64; 4], km: f64) -> [f64; 4] {
+    let dlat = km / KM_PER_DEG;
+    let widest = bounds[1].abs().max(bounds[3].abs()) + dlat;
+    let dlon = km / (KM_PER_DEG * widest.min(90.0).to_radians().cos().max(0.01));
+    [
+        round6(bounds[0] - dlon),
+        round6(bounds[1] - dlat),
+        round6(bounds[2] + dlon),
+        round6(bounds[3] + dlat),
+    ]
+}
+
+/// Every polygon ring of a GeoJSON geometry as `[lon, lat]` pairs, outer rings
+/// and holes alike. Geometry collections are searched; points and lines are
+/// skipped, as are malformed positions.
+pub fn rings(geometry: &Value) -> Vec<Vec<[f64; 2]>> {
+    let to_ring = |ring: &Value| -> Vec<[f64; 2]> {
+        ring.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|p| Some([p.get(0)?.as_f64()?, p.get(1)?.as_f64()?]))
+            .collect()
+    };
+    let polygon = |coordinates: &Value| -> Vec<Vec<[f64; 2]>> {
+        coordinates.as_array().into_iter().flatten().map(to_ring).collect()
+    };
+    match geometry["type"].as_str() {
+        Some("Polygon") => polygon(&geometry["coordinates"]),
+        Some("MultiPolygon") => geometry["coordinates"].as_array().into_iter().flatten().flat_map(polygon).collect(),
+        Some("GeometryCollection") => geometry["geometries"].as_array().into_iter().flatten().flat_map(rings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Longer side in km of a `[min_lon, min_lat, max_lon, max_lat]` box, with
+/// longitude scaled at its middle latitude.
+pub fn bbox_span_km(bounds: &[f64; 4]) -> f64 {
+    let mid_lat = (bounds[1] + bounds[3]) / 2.0;
+    let height_km = (bounds[3] - bounds[1]) * KM_PER_DEG;
+    let width_km = (bounds[2] - bounds[0]) * KM_PER_DEG * mid_lat.to_radians().cos();
+    height_km.max(width_km)
+}
+
+/// Great-circle (haversine) distance between two points in km.
+pub fn great_circle_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = phi2 - phi1;
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Ellipsoidal (WGS84) distance between two points in km, by Vincenty's inverse
+/// formula. `None` for nearly antipodal points, where the iteration does not converge.
+pub fn vincenty_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Option<f64> {
+    let b = WGS84_A * (1.0 - WGS84_F);
+    let l = (lon2 - lon1).to_radians();
+    let (sin_u1, cos_u1) = ((1.0 - WGS84_F) * lat1.to_radians().tan()).atan().sin_cos();
+    let (sin_u2, cos_u2) = ((1.0 - WGS84_F) * lat2.to_radians().tan()).atan().sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            return Some(0.0);
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // Zero on the equator, where cos²α vanishes.
+        let cos_2sm = if cos2_alpha == 0.0 { 0.0 } else { cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha };
+        let c = WGS84_F / 16.0 * cos2_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l + (1.0 - c) * WGS84_F * sin_alpha
+            * (sigma + c * sin_sigma * (cos_2sm + c * cos_sigma * (2.0 * cos_2sm * cos_2sm - 1.0)));
+
+        if (lambda - previous).abs() < 1e-12 {
+            let u2 = cos2_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+            let big_a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+            let big_b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+            let delta_sigma = big_b * sin_sigma
+                * (cos_2sm + big_b / 4.0
+                    * (cos_sigma * (2.0 * cos_2sm * cos_2sm - 1.0)
+                        - big_b / 6.0 * cos_2sm * (4.0 * sin_sigma * sin_sigma - 3.0)
+                            * (4.0 * cos_2sm * cos_2sm - 3.0)));
+            return Some(b * big_a * (sigma - delta_sigma) / 1000.0);
+        }
+    }
+    None
+}
+
+/// Point a `fraction` (0–1) of the way along the great circle from point 1 to
+/// point 2. `None` for antipodal points, which no single great circle joins.
+pub fn great_circle_point(
+    lat1: f64,
+    lon1: f64,
+    lat2: f64,
+    lon2: f64,
+    fraction: f64,
+) -> Option<(f64, f64)> {
+    let delta = great_circle_km(lat1, lon1, lat2, lon2) / EARTH_RADIUS_KM;
+    if delta == 0.0 {
+        return Some((lat1, lon1));
+    }
+    if delta.sin() < 1e-9 {
+        return None;
+    }
+    let a = ((1.0 - fraction) * delta).sin() / delta.sin();
+    let b = (fraction * delta).sin() / delta.sin();
+    let (phi1, lambda1, phi2, lambda2) =
+        (lat1.to_radians(), lon1.to_radians(), lat2.to_radians(), lon2.to_radians());
+    let x = a * phi1.cos() * lambda1.cos() + b * phi2.cos() * lambda2.cos();
+    let y = a * phi1.cos() * lambda1.sin() + b * phi2.cos() * lambda2.sin();
+    let z = a * phi1.sin() + b * phi2.sin();
+    // Keep longitudes in [-180, 180) like every other coordinate the API accepts.
+    let lon = round6(y.atan2(x).to_degrees());
+    Some((round6(z.atan2(x.hypot(y)).to_degrees()), if lon >= 180.0 { lon - 360.0 } else { lon }))
+}
+
+/// Compute initial bearing (forward azimuth) from point 1 to point 2 in degrees (0–360).
+pub fn bearing_deg(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lon = (lon2 - lon1).to_radians();
+    let x = d_lon.sin() * lat2.cos();
+    let y = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+    (x.atan2(y).to_degrees() + 360.0) % 360.0
+}
+
+/// Convert a bearing in degrees to an 8-point compass direction.
+pub fn compass_direction(deg: f64) -> String {
+    const DIRS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    DIRS[((deg + 22.5) % 360.0 / 45.0) as usize].into()
+}
+
+#[inline]
+fn round6(v: f64) -> f64 {
+    (v * 1e6).round() / 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(polygon: &Value) -> Vec<[f64; 2]> {
+        serde_json::from_value(polygon["coordinates"][0].clone()).unwrap()
+    }
+
+    #[test]
+    fn ring_is_closed() {
+        let ring = ring(&search_area(6.9271, 79.8612, 10.0));
+        assert_eq!(ring.len(), CIRCLE_VERTICES + 1);
+        assert_eq!(ring.first(), ring.last());
+    }
+
+    #[test]
+    fn extents_match_search_distance() {
+        let ring = ring(&search_area(60.0, 10.0, 111.32));
+        let max_lat = ring.iter().map(|p| p[1]).fold(f64::MIN, f64::max);
+        let max_lon = ring.iter().map(|p| p[0]).fold(f64::MIN, f64::max);
+        assert!((max_lat - 61.0).abs() < 1e-6);
+        // cos(60°) = 0.5 → one degree of latitude spans two of longitude.
+        assert!((max_lon - 12.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clamps_at_pole() {
+        let ring = ring(&search_area(89.9, 0.0, 50.0));
+        assert!(ring.iter().all(|p| p[1] <= 90.0));
+    }
+
+    #[test]
+    fn colombo_to_kandy() {
+        let km = great_circle_km(6.9271, 79.8612, 7.2906, 80.6337);
+        assert!((km - 94.34).abs() < 0.01, "{km}");
+        let bearing = bearing_deg(6.9271, 79.8612, 7.2906, 80.6337);
+        assert_eq!(compass_direction(bearing), "NE");
+        assert_eq!(great_circle_km(10.0, 20.0, 10.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn great_circle_midpoint_crosses_the_antimeridian() {
+        assert_eq!(great_circle_point(0.0, 170.0, 0.0, -170.0, 0.5), Some((0.0, -180.0)));
+        assert_eq!(great_circle_point(6.9271, 79.8612, 7.2906, 80.6337, 0.0), Some((6.9271, 79.8612)));
+        let (lat, _) = great_circle_point(10.0, 0.0, 10.0, 90.0, 0.5).unwrap();
+        assert!(lat > 10.0, "great circle bulges poleward: {lat}");
+        assert!(great_circle_point(0.0, 0.0, 0.0, 180.0, 0.5).is_none());
+    }
+
+    #[test]
+    fn vincenty_matches_reference_geodesic() {
+        // Flinders Peak to Buninyong, Vincenty's (1975) worked example: 54 972.271 m.
+        let km = vincenty_km(-37.951_033_42, 144.424_867_89, -37.652_821_14, 143.926_495_53).unwrap();
+        assert!((km - 54.972_271).abs() < 1e-3, "{km}");
+        assert_eq!(vincenty_km(10.0, 20.0, 10.0, 20.0), Some(0.0));
+        assert!(vincenty_km(0.0, 0.0, 0.5, 179.7).is_none());
+    }
+
+    #[test]
+    fn rings_of_nested_geometries() {
+        let collection = json!({"type": "GeometryCollection", "geometries": [
+            {"type": "MultiPolygon", "coordinates": [
+                [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]], [[0.2, 0.1], [0.8, 0.1], [0.8, 0.7], [0.2, 0.1]]],
+                [[[5.0, 5.0], [6.0, 5.0], [6.0, 6.0], [5.0, 5.0]]]
+            ]},
+            {"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]]}
+        ]});
+        let rings = rings(&collection);
+        assert_eq!(rings.len(), 3);
+        assert_eq!(rings[2][0], [5.0, 5.0]);
+        assert!(super::rings(&json!({"type": "Point", "coordinates": [0.0, 0.0]})).is_empty());
+    }
+
+    #[test]
+    fn polygon_bounds_cover_every_ring() {
+        let multi = json!({"type": "MultiPolygon", "coordinates": [
+            [[[90.0, 23.0], [91.0, 23.0], [91.0, 24.0], [90.0, 23.0]]],
+            [[[89.5, 22.5, 4.0], [89.8, 22.5, 4.0], [89.8, 22.9, 4.0], [89.5, 22.5, 4.0]]]
+        ]});
+        assert_eq!(polygon_bounds(&multi).unwrap(), [89.5, 22.5, 91.0, 24.0]);
+    }
+
+    #[test]
+    fn polygon_bounds_rejects_invalid_geometry() {
+        let open = json!({"type": "Polygon", "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]]});
+        assert!(polygon_bounds(&open).is_err());
+        assert!(polygon_bounds(&json!({"type": "Point", "coordinates": [0.0, 0.0]})).is_err());
+        let out_of_range = json!({"type": "Polygon", "coordinates": [[[0.0, 0.0], [200.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]});
+        assert!(polygon_bounds(&out_of_range).is_err());
+    }
+
+    #[test]
+    fn geometry_collections_are_taken_apart() {
+        let square = |x: f64| json!([[[x, 0.0], [x + 1.0, 0.0], [x + 1.0, 1.0], [x, 0.0]]]);
+        let collection = json!({"type": "GeometryCollection", "geometries": [
+            {"type": "Polygon", "coordinates": square(0.0)},
+            {"type": "GeometryCollection", "geometries": [{"type": "MultiPolygon", "coordinates": [square(2.0), square(4.0)]}]}
+        ]});
+        let parts = polygon_parts(&collection).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(part_bounds(parts[1]).unwrap(), [2.0, 0.0, 3.0, 1.0]);
+        assert_eq!(polygon_bounds(&collection).unwrap(), [0.0, 0.0, 5.0, 1.0]);
+        assert_eq!(multi_polygon(&parts)["coordinates"][2], square(4.0));
+
+        let with_line = json!({"type": "GeometryCollection", "geometries": [
+            {"type": "Polygon", "coordinates": square(0.0)},
+            {"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]]}
+        ]});
+        assert!(polygon_parts(&with_line).is_err());
+        assert!(polygon_parts(&json!({"type": "GeometryCollection", "geometries": []})).is_err());
+    }
+
+    #[test]
+    fn geometry_bounds_of_points_and_lines() {
+        let line = json!({"type": "LineString", "coordinates": [[79.85, 6.90], [79.90, 6.95], [80.00, 7.00]]});
+        assert_eq!(geometry_bounds(&line).unwrap(), [79.85, 6.90, 80.00, 7.00]);
+        let point = json!({"type": "Point", "coordinates": [79.8612, 6.9271]});
+        assert_eq!(geometry_bounds(&point).unwrap(), [79.8612, 6.9271, 79.8612, 6.9271]);
+        let lines = json!({"type": "MultiLineString", "coordinates": [[[0.0, 0.0], [1.0, 1.0]], [[-2.0, 3.0], [0.0, 0.0]]]});
+        assert_eq!(geometry_bounds(&lines).unwrap(), [-2.0, 0.0, 1.0, 3.0]);
+        assert!(geometry_bounds(&json!({"type": "LineString", "coordinates": []})).is_err());
+        assert!(geometry_bounds(&json!({"type": "Point", "coordinates": [0.0, 95.0]})).is_err());
+        assert!(geometry_bounds(&json!({"type": "GeometryCollection", "geometries": []})).is_err());
+    }
+
+    #[test]
+    fn grown_bounds_cover_the_buffer() {
+        let grown = grow_bounds([79.8612, 6.9271, 79.8612, 6.9271], 11.132);
+        assert!((grown[3] - grown[1] - 0.2).abs() < 1e-6);
+        assert!(grown[2] - grown[0] > 0.2);
+        assert!(grow_bounds([179.9, 0.0, 179.9, 0.0], 50.0)[2] > 180.0);
+    }
+}
@@ -0,0 +1,366 @@
+//! OGC GeoPackage files of `/export/geopackage`: the population grid, named
+//! places and country boundaries of a bounding box as feature tables in one
+//! SQLite file that QGIS, ArcGIS and GDAL open directly.
+//!
+//! The file holds the three required metadata tables with the definitions
+//! from the specification (version 1.3) and one feature table per layer, all
+//! in WGS 84. Geometries are stored as GeoPackage binary: a small header with
+//! the SRS and an optional envelope, followed by standard little-endian WKB.
+
+use chrono::{DateTime, Utc};
+
+use crate::errors::AppError;
+use crate::models::GridCell;
+use crate::sqlite::{self, Table, Value};
+
+pub(crate) const CONTENT_TYPE: &str = "application/geopackage+sqlite3";
+
+/// `application_id` of a GeoPackage: "GPKG" in ASCII.
+const APPLICATION_ID: u32 = 0x4750_4B47;
+/// `user_version` of GeoPackage 1.3.0.
+const USER_VERSION: u32 = 10_300;
+const WGS84: i64 = 4326;
+
+const WKB_POINT: u32 = 1;
+const WKB_POLYGON: u32 = 3;
+
+const SPATIAL_REF_SYS_SQL: &str = "CREATE TABLE gpkg_spatial_ref_sys (srs_name TEXT NOT NULL, \
+    srs_id INTEGER NOT NULL PRIMARY KEY, organization TEXT NOT NULL, organization_coordsys_id INTEGER NOT NULL, \
+    definition TEXT NOT NULL, description TEXT)";
+const CONTENTS_SQL: &str = "CREATE TABLE gpkg_contents (table_name TEXT NOT NULL PRIMARY KEY, \
+    data_type TEXT NOT NULL, identifier TEXT UNIQUE, description TEXT DEFAULT '', \
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')), min_x DOUBLE, min_y DOUBLE, \
+    max_x DOUBLE, max_y DOUBLE, srs_id INTEGER, \
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id))";
+const GEOMETRY_COLUMNS_SQL: &str = "CREATE TABLE gpkg_geometry_columns (table_name TEXT NOT NULL, \
+    column_name TEXT NOT NULL, geometry_type_name TEXT NOT NULL, srs_id INTEGER NOT NULL, z TINYINT NOT NULL, \
+    m TINYINT NOT NULL, CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name), \
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name), \
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name), \
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id))";
+
+const WGS84_WKT: &str = "GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,\
+    AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],\
+    UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],AUTHORITY[\"EPSG\",\"4326\"]]";
+
+/// A named place of the `places` layer.
+pub(crate) struct PlaceFeature {
+    pub geonameid: i32,
+    pub name: String,
+    pub feature_code: Option<String>,
+    pub country_code: Option<String>,
+    pub population: i64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// A country of the `countries` layer, its boundary clipped to the box as
+/// little-endian WKB.
+pub(crate) struct CountryFeature {
+    pub iso_a2: Option<String>,
+    pub iso_a3: Option<String>,
+    pub name: String,
+    pub continent: Option<String>,
+    pub wkb: Vec<u8>,
+    /// `[min_lon, min_lat, max_lon, max_lat]` of the clipped boundary.
+    pub envelope: [f64; 4],
+}
+
+/// A feature table under construction, with its `gpkg_contents` and
+/// `gpkg_geometry_columns` entries.
+pub(crate) struct Layer {
+    table: Table,
+    name: &'static str,
+    description: &'static str,
+    geometry_type: &'static str,
+    next_fid: i64,
+}
+
+impl Layer {
+    pub fn population(cells: &[GridCell]) -> Self {
+        let mut layer = Self::new(
+            "population",
+            "WorldPop 1 km grid cells with their estimated population",
+            "POLYGON",
+            "population REAL, lat REAL, lon REAL",
+        );
+        for cell in cells {
+            let b = &cell.bounds;
+            let mut wkb = wkb_header(WKB_POLYGON);
+            wkb.extend(1u32.to_le_bytes());
+            wkb.extend(5u32.to_le_bytes());
+            for [x, y] in [
+                [b.min_lon, b.min_lat],
+                [b.max_lon, b.min_lat],
+                [b.max_lon, b.max_lat],
+                [b.min_lon, b.max_lat],
+                [b.min_lon, b.min_lat],
+            ] {
+                wkb.extend(x.to_le_bytes());
+                wkb.extend(y.to_le_bytes());
+            }
+            let envelope = [b.min_lon, b.min_lat, b.max_lon, b.max_lat];
+            layer.push(geometry(&wkb, Some(envelope)), vec![
+                (cell.population as f64).into(),
+                cell.lat.into(),
+                cell.lon.into(),
+            ]);
+        }
+        layer
+    }
+
+    pub fn places(places: Vec<PlaceFeature>) -> Self {
+        let mut layer = Self::new(
+            "places",
+            "GeoNames populated places",
+            "POINT",
+            "geonameid INTEGER, name TEXT, feature_code TEXT, country_code TEXT, population INTEGER",
+        );
+        for place in places {
+            let mut wkb = wkb_header(WKB_POINT);
+            wkb.extend(place.lon.to_le_bytes());
+            wkb.extend(place.lat.to_le_bytes());
+            layer.push(geometry(&wkb, None), vec![
+                (place.geonameid as i64).into(),
+                place.name.into(),
+                place.feature_code.into(),
+                place.country_code.into(),
+                place.population.into(),
+            ]);
+        }
+        layer
+    }
+
+    pub fn countries(countries: Vec<CountryFeature>) -> Self {
+        let mut layer = Self::new(
+            "countries",
+            "Natural Earth country boundaries clipped to the bounding box",
+            "MULTIPOLYGON",
+            "iso_a2 TEXT, iso_a3 TEXT, name TEXT, continent TEXT",
+        );
+        for country in countries {
+            layer.push(geometry(&country.wkb, Some(country.envelope)), vec![
+                country.iso_a2.into(),
+                country.iso_a3.into(),
+                country.name.into(),
+                country.continent.into(),
+            ]);
+        }
+        layer
+    }
+
+    fn new(name: &'static str, description: &'static str, geometry_type: &'static str, columns: &str) -> Self {
+        let sql = format!("CREATE TABLE \"{name}\" (fid INTEGER PRIMARY KEY, geom {geometry_type}, {columns})");
+        Self { table: Table::new(name, sql), name, description, geometry_type, next_fid: 1 }
+    }
+
+    fn push(&mut self, geometry: Vec<u8>, attributes: Vec<Value>) {
+        let mut values = vec![Value::Null, Value::Blob(geometry)];
+        values.extend(attributes);
+        self.table.row(self.next_fid, values);
+        self.next_fid += 1;
+    }
+}
+
+/// A GeoPackage holding `layers`, each declared with `bbox` as its extent.
+pub(crate) fn write(layers: Vec<Layer>, bbox: [f64; 4], created: DateTime<Utc>) -> Result<Vec<u8>, AppError> {
+    let mut srs = Table::new("gpkg_spatial_ref_sys", SPATIAL_REF_SYS_SQL);
+    srs.row(-1, vec![
+        "Undefined cartesian SRS".into(),
+        Value::Null,
+        "NONE".into(),
+        Value::Integer(-1),
+        "undefined".into(),
+        "undefined cartesian coordinate reference system".into(),
+    ]);
+    srs.row(0, vec![
+        "Undefined geographic SRS".into(),
+        Value::Null,
+        "NONE".into(),
+        Value::Integer(0),
+        "undefined".into(),
+        "undefined geographic coordinate reference system".into(),
+    ]);
+    srs.row(WGS84, vec![
+        "WGS 84 geodetic".into(),
+        Value::Null,
+        "EPSG".into(),
+        Value::Integer(WGS84),
+        WGS84_WKT.into(),
+        "longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid".into(),
+    ]);
+
+    let last_change = created.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    let [min_x, min_y, max_x, max_y] = bbox;
+    let mut contents = Table::new("gpkg_contents", CONTENTS_SQL).autoindex(&[0]).autoindex(&[2]);
+    let mut columns = Table::new("gpkg_geometry_columns", GEOMETRY_COLUMNS_SQL).autoindex(&[0, 1]).autoindex(&[0]);
+    let mut tables = Vec::with_capacity(layers.len() + 3);
+    for (layer, rowid) in layers.into_iter().zip(1..) {
+        contents.row(rowid, vec![
+            layer.name.into(),
+            "features".into(),
+            layer.name.into(),
+            layer.description.into(),
+            last_change.as_str().into(),
+            min_x.into(),
+            min_y.into(),
+            max_x.into(),
+            max_y.into(),
+            Value::Integer(WGS84),
+        ]);
+        columns.row(rowid, vec![
+            layer.name.into(),
+            "geom".into(),
+            layer.geometry_type.into(),
+            Value::Integer(WGS84),
+            Value::Integer(0),
+            Value::Integer(0),
+        ]);
+        tables.push(layer.table);
+    }
+    tables.splice(0..0, [srs, contents, columns]);
+    sqlite::write(tables, APPLICATION_ID, USER_VERSION)
+}
+
+fn wkb_header(kind: u32) -> Vec<u8> {
+    let mut wkb = vec![1];
+    wkb.extend(kind.to_le_bytes());
+    wkb
+}
+
+/// GeoPackage binary of a WGS 84 geometry: magic, version, flags (little
+/// endian, envelope kind), SRS id and envelope, then the WKB.
+fn geometry(wkb: &[u8], envelope: Option<[f64; 4]>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + 32 + wkb.len());
+    out.extend(b"GP");
+    out.push(0);
+    out.push(if envelope.is_some() { 0b0000_0011 } else { 0b0000_0001 });
+    out.extend((WGS84 as i32).to_le_bytes());
+    if let Some([min_x, min_y, max_x, max_y]) = envelope {
+        for v in [min_x, max_x, min_y, max_y] {
+            out.extend(v.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(wkb);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CellBounds;
+
+    #[test]
+    fn geometry_header_orders_the_envelope_x_first() {
+        let blob = geometry(&[1, 1, 0, 0, 0], Some([79.8, 6.8, 79.9, 6.9]));
+        assert_eq!(&blob[..4], b"GP\x00\x03");
+        assert_eq!(i32::from_le_bytes(blob[4..8].try_into().unwrap()), 4326);
+        let x_max = f64::from_le_bytes(blob[16..24].try_into().unwrap());
+        assert_eq!(x_max, 79.9);
+        assert_eq!(blob.len(), 8 + 32 + 5);
+        assert_eq!(&geometry(&[1], None)[..4], b"GP\x00\x01");
+    }
+
+    #[test]
+    fn package_is_marked_as_geopackage() {
+        let cell = GridCell {
+            lat: 6.925,
+            lon: 79.858,
+            population: 2_200.0,
+            bounds: CellBounds { min_lat: 6.9208, max_lat: 6.9292, min_lon: 79.8542, max_lon: 79.8625 },
+            uncertainty: None,
+        };
+        let file = write(vec![Layer::population(&[cell])], [79.8, 6.8, 79.9, 7.0], Utc::now()).unwrap();
+        assert_eq!(&file[68..72], b"GPKG");
+        assert_eq!(u32::from_be_bytes(file[60..64].try_into().unwrap()), 10_300);
+        let text = String::from_utf8_lossy(&file);
+        assert!(text.contains("CREATE TABLE \"population\" (fid INTEGER PRIMARY KEY, geom POLYGON"));
+        assert!(text.contains("sqlite_autoindex_gpkg_geometry_columns_2"));
+    }
+
+    /// Opens a package spanning many b-tree pages with SQLite itself, so the
+    /// hand-written file format is checked by a real reader.
+    #[test]
+    #[ignore = "requires the sqlite3 CLI"]
+    fn sqlite_reads_the_package() {
+        let cells: Vec<GridCell> = (0u16..5_000)
+            .map(|i| {
+                let lat = 6.0 + f64::from(i / 100) / 120.0;
+                let lon = 79.5 + f64::from(i % 100) / 120.0;
+                GridCell {
+                    lat,
+                    lon,
+                    population: f32::from(i),
+                    bounds: CellBounds {
+                        min_lat: lat - 1.0 / 240.0,
+                        max_lat: lat + 1.0 / 240.0,
+                        min_lon: lon - 1.0 / 240.0,
+                        max_lon: lon + 1.0 / 240.0,
+                    },
+                    uncertainty: None,
+                }
+            })
+            .collect();
+        let places = vec![PlaceFeature {
+            geonameid: 1_248_991,
+            name: "Colombo".into(),
+            feature_code: Some("PPLC".into()),
+            country_code: Some("LK".into()),
+            population: 648_034,
+            lat: 6.93548,
+            lon: 79.84868,
+        }];
+        let mut wkb = wkb_header(6); // MultiPolygon
+        wkb.extend(1u32.to_le_bytes());
+        wkb.extend(wkb_header(WKB_POLYGON));
+        wkb.extend(1u32.to_le_bytes());
+        wkb.extend(4u32.to_le_bytes());
+        for [x, y] in [[79.6, 6.0], [81.9, 6.0], [81.9, 9.8], [79.6, 6.0]] {
+            wkb.extend(f64::to_le_bytes(x));
+            wkb.extend(f64::to_le_bytes(y));
+        }
+        let countries = vec![CountryFeature {
+            iso_a2: Some("LK".into()),
+            iso_a3: Some("LKA".into()),
+            name: "Sri Lanka".into(),
+            continent: None,
+            wkb,
+            envelope: [79.6, 6.0, 81.9, 9.8],
+        }];
+        let layers = vec![Layer::population(&cells), Layer::places(places), Layer::countries(countries)];
+        let file = write(layers, [79.5, 6.0, 82.0, 10.0], Utc::now()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("geopop-{}.gpkg", std::process::id()));
+        std::fs::write(&path, file).unwrap();
+        let output = std::process::Command::new("sqlite3")
+            .arg("-bail")
+            .arg(&path)
+            .arg(
+                "PRAGMA integrity_check; PRAGMA foreign_key_check; PRAGMA application_id; PRAGMA user_version; \
+                 SELECT count(*) FROM gpkg_spatial_ref_sys; \
+                 SELECT group_concat(table_name || ':' || data_type || ':' || srs_id) FROM gpkg_contents; \
+                 SELECT group_concat(table_name || ':' || geometry_type_name) FROM gpkg_geometry_columns; \
+                 SELECT count(*), sum(population), hex(substr(min(geom), 1, 4)) FROM population; \
+                 SELECT name, population FROM places WHERE geonameid = 1248991; \
+                 SELECT iso_a3, length(geom) FROM countries;",
+            )
+            .output()
+            .expect("sqlite3 is installed");
+        std::fs::remove_file(&path).unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), [
+            "ok",
+            "1196444487",
+            "10300",
+            "3",
+            "population:features:4326,places:features:4326,countries:features:4326",
+            "population:POLYGON,places:POINT,countries:MULTIPOLYGON",
+            "5000|12497500.0|47500003",
+            "Colombo|648034",
+            "LKA|126",
+        ]);
+    }
+}
+
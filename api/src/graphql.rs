@@ -0,0 +1,301 @@
+//! GraphQL schema over the same repositories as the REST routes.
+//!
+//! The REST endpoints each answer one question; GraphQL lets a client combine
+//! them — country, nearest place and exposure for a point — in one round-trip
+//! and receive only the fields it selects. Every repository call takes its own
+//! pooled connection, so sibling fields of `point` run concurrently.
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, ErrorExtensions, Object, Result, Schema,
+};
+use validator::Validate;
+
+use crate::config::{Features, Limits};
+use crate::errors::AppError;
+use crate::include::Includes;
+use crate::models::{
+    BoundaryQuery, CityHit, CitySearchQuery, ContinentQuery, CountryDetailPayload, CountryPayload,
+    ExposureQuery, ExposurePayload, NearestPlace, PointQuery, ReverseMode,
+    ReversePayload,
+};
+use crate::repositories::{CountryCode, Repositories};
+use crate::routes::exposure;
+use crate::validation::{validate_country_filter, validate_iso3};
+
+pub(crate) type GeoPopSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Maximum nesting depth of a query.
+const MAX_DEPTH: usize = 8;
+/// Complexity budget per query; each `exposure` costs `EXPOSURE_COST`, so one
+/// request can ask for a handful of radii but not hundreds.
+const MAX_COMPLEXITY: usize = 250;
+const EXPOSURE_COST: usize = 40;
+
+pub(crate) fn build_schema(repos: Repositories, limits: Limits, features: Features) -> GeoPopSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(repos)
+        .data(limits)
+        .data(features)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Maps an `AppError` to a GraphQL error with the REST status in
+/// `extensions.code`. Database details are logged, not returned.
+fn gql_error(err: AppError) -> Error {
+    let code = match &err {
+        AppError::Validation(_) => "BAD_REQUEST",
+        AppError::NotFound(_) | AppError::OutOfRange { .. } => "NOT_FOUND",
+        AppError::Unauthorized(_) => "UNAUTHORIZED",
+        AppError::Database(msg) => {
+            log::error!("Database error: {msg}");
+            "INTERNAL_SERVER_ERROR"
+        }
+        AppError::Upstream(msg) => {
+            log::error!("Upstream error: {msg}");
+            "BAD_GATEWAY"
+        }
+        AppError::Transient(msg) => {
+            log::error!("Transient database error: {msg}");
+            "SERVICE_UNAVAILABLE"
+        }
+        AppError::Unavailable(_) => "SERVICE_UNAVAILABLE",
+        AppError::Timeout(msg) => {
+            log::warn!("Timeout: {msg}");
+            "GATEWAY_TIMEOUT"
+        }
+        AppError::Busy(msg) => {
+            log::warn!("Busy: {msg}");
+            "SERVICE_UNAVAILABLE"
+        }
+        AppError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+    };
+    Error::new(err.public_message()).extend_with(|_, e| e.set("code", code))
+}
+
+fn validate(input: &impl Validate) -> Result<()> {
+    input
+        .validate()
+        .map_err(|e| gql_error(AppError::Validation(format!("Validation failed: {e}"))))
+}
+
+/// `exposure` is left out of introspection, and refused, when `ENABLE_EXPOSURE`
+/// is off.
+fn exposure_enabled(ctx: &Context<'_>) -> bool {
+    ctx.data_opt::<Features>().is_none_or(|f| f.exposure)
+}
+
+fn repos<'a>(ctx: &Context<'a>) -> &'a Repositories {
+    ctx.data_unchecked::<Repositories>()
+}
+
+pub(crate) struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A coordinate to look up; select the facts you need about it.
+    async fn point(
+        &self,
+        #[graphql(desc = "Latitude in decimal degrees")] lat: f64,
+        #[graphql(desc = "Longitude in decimal degrees")] lon: f64,
+        #[graphql(desc = "Boundary dataset vintage for country fields (default: current)")]
+        boundaries: Option<i32>,
+    ) -> Result<Point> {
+        validate(&PointQuery { lat, lon })?;
+        validate(&BoundaryQuery { boundaries })?;
+        Ok(Point { lat, lon, boundaries })
+    }
+
+    /// Country by ISO 3166-1 alpha-3 code.
+    async fn country(
+        &self,
+        ctx: &Context<'_>,
+        iso3: String,
+        boundaries: Option<i32>,
+    ) -> Result<CountryDetailPayload> {
+        let iso3 = validate_iso3(&iso3).map_err(gql_error)?;
+        validate(&BoundaryQuery { boundaries })?;
+        let vintage = repos(ctx).countries.resolve_vintage(boundaries).await.map_err(gql_error)?;
+        repos(ctx).countries.get_by_code(&CountryCode::Alpha3(iso3), vintage).await.map_err(gql_error)
+    }
+
+    /// Sovereign countries of a continent (`asia`, `europe`, `africa`, `oceania`,
+    /// `americas`, `north-america`, `south-america`), UN region or UN subregion,
+    /// as `/countries`.
+    async fn countries(
+        &self,
+        ctx: &Context<'_>,
+        continent: Option<String>,
+        region: Option<String>,
+        subregion: Option<String>,
+        boundaries: Option<i32>,
+    ) -> Result<Vec<CountryPayload>> {
+        validate(&BoundaryQuery { boundaries })?;
+        let filter =
+            validate_country_filter(&ContinentQuery { continent, region, subregion, sovereign: None }).map_err(gql_error)?;
+        let vintage = repos(ctx).countries.resolve_vintage(boundaries).await.map_err(gql_error)?;
+        repos(ctx).countries.get_countries(&filter, vintage).await.map_err(gql_error)
+    }
+
+    /// Fuzzy city search, as `/cities/search`.
+    async fn cities(
+        &self,
+        ctx: &Context<'_>,
+        q: String,
+        #[graphql(desc = "ISO 3166-1 alpha-2 country filter")] country: Option<String>,
+        #[graphql(default = 10)] limit: i64,
+        #[graphql(default = 0)] min_population: i64,
+        #[graphql(desc = "Boundary dataset vintage for country names (default: current)")]
+        boundaries: Option<i32>,
+    ) -> Result<Vec<CityHit>> {
+        let query = CitySearchQuery { q, country, limit, min_population };
+        validate(&query)?;
+        validate(&BoundaryQuery { boundaries })?;
+        let country = query.country.as_ref().map(|c| c.to_uppercase());
+        let vintage = repos(ctx).countries.resolve_vintage(boundaries).await.map_err(gql_error)?;
+        repos(ctx)
+            .geocoding
+            .search_cities(query.q.trim(), country.as_deref(), query.limit, query.min_population, vintage)
+            .await
+            .map_err(gql_error)
+    }
+}
+
+/// A validated coordinate. Its fields are resolved lazily, only when selected.
+pub(crate) struct Point {
+    lat: f64,
+    lon: f64,
+    boundaries: Option<i32>,
+}
+
+#[Object]
+impl Point {
+    async fn lat(&self) -> f64 {
+        self.lat
+    }
+
+    async fn lon(&self) -> f64 {
+        self.lon
+    }
+
+    /// Population of the 1 km grid cell containing the point.
+    async fn population(&self, ctx: &Context<'_>) -> Result<f32> {
+        repos(ctx).population.get_population(self.lat, self.lon).await.map_err(gql_error)
+    }
+
+    /// Whether the point lies inside a country boundary.
+    async fn is_land(&self, ctx: &Context<'_>) -> Result<bool> {
+        let vintage = repos(ctx).countries.resolve_vintage(self.boundaries).await.map_err(gql_error)?;
+        repos(ctx).countries.is_land(self.lat, self.lon, vintage).await.map_err(gql_error)
+    }
+
+    /// Country containing the point, or the nearest one at sea.
+    async fn country(&self, ctx: &Context<'_>) -> Result<CountryPayload> {
+        let vintage = repos(ctx).countries.resolve_vintage(self.boundaries).await.map_err(gql_error)?;
+        repos(ctx).countries.get_by_coordinate(self.lat, self.lon, vintage)
+            .await
+            .map_err(gql_error)
+    }
+
+    /// Nearest named place with distance and direction from the point.
+    async fn nearest_place(&self, ctx: &Context<'_>) -> Result<NearestPlace> {
+        let vintage = repos(ctx).countries.resolve_vintage(self.boundaries).await.map_err(gql_error)?;
+        repos(ctx).geocoding.find_nearest_place(self.lat, self.lon, vintage).await.map_err(gql_error)
+    }
+
+    /// Reverse geocode: the nearest named place with its address.
+    async fn reverse(&self, ctx: &Context<'_>) -> Result<ReversePayload> {
+        let vintage = repos(ctx).countries.resolve_vintage(self.boundaries).await.map_err(gql_error)?;
+        repos(ctx)
+            .geocoding
+            .reverse_geocode(self.lat, self.lon, &[], None, ReverseMode::Nearest, vintage)
+            .await
+            .map_err(gql_error)
+    }
+
+    /// Population exposure within `radius` km, as `/exposure`. `uncertainty` and
+    /// `places` are only computed when selected.
+    #[graphql(complexity = "EXPOSURE_COST + child_complexity", visible = "exposure_enabled")]
+    async fn exposure(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Search radius in kilometres (server maximum, 500 by default)")] radius: f64,
+    ) -> Result<ExposurePayload> {
+        if !exposure_enabled(ctx) {
+            return Err(gql_error(AppError::NotFound("Exposure is disabled on this server".into())));
+        }
+        let query = ExposureQuery {
+            lat: self.lat,
+            lon: self.lon,
+            radius,
+            decay: None,
+            mode: None,
+            coast_km: None,
+            max_elevation_m: None,
+            rings: None,
+            histogram: None,
+            top_cells: None,
+            land_only: None,
+            places_limit: None,
+            places_offset: None,
+            sort: None,
+            order: None,
+            min_place_population: None,
+            group_by: None,
+            dataset: None,
+            year: None,
+        };
+        let look_ahead = ctx.look_ahead();
+        let sections: Vec<&str> = ["uncertainty", "places"]
+            .into_iter()
+            .filter(|field| look_ahead.field(field).exists())
+            .collect();
+        let includes = Includes::from_list(&sections.join(",")).map_err(gql_error)?;
+        exposure::check(&query, includes, ctx.data_unchecked::<Limits>()).map_err(gql_error)?;
+        let repos = repos(ctx);
+        let vintage = repos.countries.resolve_vintage(self.boundaries).await.map_err(gql_error)?;
+        exposure::run(&*repos.population, &*repos.geocoding, &query, includes, vintage)
+            .await
+            .map_err(gql_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> GeoPopSchema {
+        Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .limit_depth(MAX_DEPTH)
+            .limit_complexity(MAX_COMPLEXITY)
+            .finish()
+    }
+
+    #[tokio::test]
+    async fn invalid_coordinate_is_bad_request() {
+        let res = schema().execute("{ point(lat: 91, lon: 0) { lat } }").await;
+        let err = &res.errors[0];
+        let code = err.extensions.as_ref().and_then(|e| e.get("code")).cloned();
+        assert_eq!(code, Some(async_graphql::Value::from("BAD_REQUEST")));
+    }
+
+    #[tokio::test]
+    async fn exposure_fanout_exceeds_complexity() {
+        let fields: String = (1..=8).map(|i| format!("e{i}: exposure(radius: {i}) {{ placeCount }} ")).collect();
+        let res = schema().execute(format!("{{ point(lat: 0, lon: 0) {{ {fields} }} }}")).await;
+        assert!(res.errors[0].message.contains("complex"));
+    }
+
+    #[tokio::test]
+    async fn disabled_exposure_is_unknown() {
+        let features = Features { exposure: false, ..Features::default() };
+        let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription).data(features).finish();
+        let res = schema.execute("{ point(lat: 0, lon: 0) { exposure(radius: 1) { placeCount } } }").await;
+        let code = res.errors[0].extensions.as_ref().and_then(|e| e.get("code")).cloned();
+        assert_eq!(code, Some(async_graphql::Value::from("NOT_FOUND")));
+        let res = schema.execute(r#"{ __type(name: "Point") { fields { name } } }"#).await;
+        let fields = res.data.into_json().unwrap().to_string();
+        assert!(fields.contains("\"population\"") && !fields.contains("\"exposure\""), "{fields}");
+    }
+}
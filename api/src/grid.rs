@@ -19,13 +19,21 @@ pub fn cell_id(lat: f64, lon: f64) -> Option<i32> {
     let row = ((90.0 - lat) * 120.0).floor() as i64;
     let col = ((lon + 180.0) * 120.0).floor() as i64;
 
-    if row < 0 || row >= NROWS || col < 0 || col >= NCOLS {
+    if !(0..NROWS).contains(&row) || !(0..NCOLS).contains(&col) {
         return None;
     }
 
     Some((row * NCOLS + col) as i32)
 }
 
+/// Row and column of a cell_id — the inverse of [`cell_id`].
+///
+/// Returns `None` if the id lies outside the grid.
+#[inline]
+pub fn row_col(cell_id: i64) -> Option<(i64, i64)> {
+    (0..NROWS * NCOLS).contains(&cell_id).then_some((cell_id / NCOLS, cell_id % NCOLS))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,6 +49,15 @@ mod tests {
         assert_eq!(id, 4619 * 43200 + 21584);
     }
 
+    #[test]
+    fn row_col_inverts_cell_id() {
+        let id = cell_id(51.5074, -0.1278).unwrap();
+        assert_eq!(row_col(id as i64), Some((4619, 21584)));
+        assert_eq!(row_col(NROWS * NCOLS - 1), Some((NROWS - 1, NCOLS - 1)));
+        assert_eq!(row_col(NROWS * NCOLS), None);
+        assert_eq!(row_col(-1), None);
+    }
+
     #[test]
     fn out_of_bounds() {
         assert_eq!(cell_id(91.0, 0.0), None);
@@ -0,0 +1,156 @@
+//! Hazard templates behind `/analyse?hazard=`: how an event's magnitude maps
+//! to the radius and distance decay of the analysis, so callers need not guess
+//! a km value.
+//!
+//! `earthquake` and `volcano` are built in. `HAZARD_TEMPLATES` names a JSON
+//! file holding an array of further templates; one with a built-in's name
+//! replaces it.
+
+use std::{env, fs};
+
+use crate::errors::AppError;
+use crate::models::{DecayModel, HazardQuery, HazardScenario, HazardTemplate};
+use crate::validation::MAX_RADIUS_KM;
+
+/// The templates a deployment offers.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HazardTemplates(Vec<HazardTemplate>);
+
+impl Default for HazardTemplates {
+    fn default() -> Self {
+        Self(vec![
+            // Radius of strong shaking: 5 km at M5, 40 km at M6.8, 160 km at M8.
+            HazardTemplate {
+                name: "earthquake".into(),
+                a: -1.8,
+                b: 0.5,
+                min_magnitude: 3.0,
+                max_magnitude: 9.5,
+                min_radius_km: 5.0,
+                max_radius_km: MAX_RADIUS_KM,
+                decay: Some(DecayModel::Linear),
+            },
+            // Volcanic Explosivity Index: 5 km at VEI 0, 25 km at VEI 4, 125 km at VEI 8.
+            HazardTemplate {
+                name: "volcano".into(),
+                a: 0.7,
+                b: 0.175,
+                min_magnitude: 0.0,
+                max_magnitude: 8.0,
+                min_radius_km: 5.0,
+                max_radius_km: 200.0,
+                decay: Some(DecayModel::Linear),
+            },
+        ])
+    }
+}
+
+impl HazardTemplates {
+    /// The built-in templates plus those in the `HAZARD_TEMPLATES` file. An
+    /// unreadable file or invalid template is logged and left out.
+    pub fn from_env() -> Self {
+        let mut templates = Self::default();
+        let Ok(path) = env::var("HAZARD_TEMPLATES") else {
+            return templates;
+        };
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_str::<Vec<HazardTemplate>>(&raw).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(extra) => templates.extend(extra),
+            Err(e) => log::warn!("Ignoring HAZARD_TEMPLATES file {path}: {e}"),
+        }
+        templates
+    }
+
+    /// Adds `extra`, replacing templates of the same name.
+    fn extend(&mut self, extra: Vec<HazardTemplate>) {
+        for template in extra {
+            if let Err(e) = check(&template) {
+                log::warn!("Ignoring hazard template `{}`: {e}", template.name);
+                continue;
+            }
+            self.0.retain(|t| !t.name.eq_ignore_ascii_case(&template.name));
+            self.0.push(template);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HazardTemplate> {
+        self.0.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The scenario `query` describes, its radius rounded to 0.1 km, or `None`
+    /// without `hazard`. `weighted_population` is left for the caller.
+    pub fn resolve(&self, query: &HazardQuery) -> Result<Option<HazardScenario>, AppError> {
+        let (name, magnitude) = match (&query.hazard, query.magnitude) {
+            (None, None) => return Ok(None),
+            (Some(name), Some(magnitude)) => (name, magnitude),
+            _ => return Err(AppError::Validation("hazard and magnitude must be given together".into())),
+        };
+        let template = self.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.0.iter().map(|t| t.name.as_str()).collect();
+            AppError::Validation(format!("Unknown hazard `{name}`; expected one of: {}", names.join(", ")))
+        })?;
+        if !(template.min_magnitude..=template.max_magnitude).contains(&magnitude) {
+            return Err(AppError::Validation(format!(
+                "{} magnitude must be between {} and {}",
+                template.name, template.min_magnitude, template.max_magnitude
+            )));
+        }
+        Ok(Some(HazardScenario {
+            template: template.clone(),
+            magnitude,
+            radius_km: (template.radius_km(magnitude) * 10.0).round() / 10.0,
+            weighted_population: None,
+        }))
+    }
+}
+
+fn check(t: &HazardTemplate) -> Result<(), String> {
+    if t.name.trim().is_empty() {
+        return Err("name is empty".into());
+    }
+    if !(t.a.is_finite() && t.b.is_finite() && t.min_magnitude <= t.max_magnitude) {
+        return Err("a, b and the magnitude range must be finite, with min_magnitude ≤ max_magnitude".into());
+    }
+    if !(t.min_radius_km > 0.0 && t.min_radius_km <= t.max_radius_km && t.max_radius_km <= MAX_RADIUS_KM) {
+        return Err(format!("radius bounds must satisfy 0 < min_radius_km ≤ max_radius_km ≤ {MAX_RADIUS_KM}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(hazard: &str, magnitude: f64) -> HazardQuery {
+        HazardQuery { hazard: Some(hazard.into()), magnitude: Some(magnitude) }
+    }
+
+    #[test]
+    fn derives_radius_from_magnitude() {
+        let templates = HazardTemplates::default();
+        let scenario = templates.resolve(&query("Earthquake", 6.8)).unwrap().unwrap();
+        assert_eq!(scenario.template.name, "earthquake");
+        assert_eq!(scenario.radius_km, 39.8);
+        assert_eq!(templates.resolve(&query("earthquake", 3.0)).unwrap().unwrap().radius_km, 5.0);
+        assert_eq!(templates.resolve(&query("volcano", 8.0)).unwrap().unwrap().radius_km, 125.9);
+
+        assert!(templates.resolve(&HazardQuery::default()).unwrap().is_none());
+        assert!(templates.resolve(&query("earthquake", 9.8)).is_err());
+        assert!(templates.resolve(&query("meteor", 5.0)).is_err());
+        assert!(templates.resolve(&HazardQuery { magnitude: Some(6.0), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn configured_templates_replace_builtins() {
+        let mut templates = HazardTemplates::default();
+        let flood = HazardTemplate { name: "flood".into(), max_radius_km: 50.0, decay: None, ..templates.0[0].clone() };
+        let bad = HazardTemplate { name: "volcano".into(), max_radius_km: 5000.0, ..templates.0[1].clone() };
+        let quake = HazardTemplate { max_radius_km: 100.0, ..templates.0[0].clone() };
+        templates.extend(vec![flood, bad, quake]);
+        assert_eq!(templates.get("flood").unwrap().max_radius_km, 50.0);
+        assert_eq!(templates.get("volcano").unwrap().max_radius_km, 200.0);
+        assert_eq!(templates.get("earthquake").unwrap().radius_km(9.0), 100.0);
+    }
+}
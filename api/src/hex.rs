@@ -0,0 +1,141 @@
+//! H3 hexagons over the WorldPop grid.
+//!
+//! A grid cell belongs to the hexagon its centre falls in, the same rule the
+//! polygon sums use, so summing cells by hexagon never splits a cell and the
+//! hexagons of one resolution add up to the cells they were built from.
+
+use std::collections::HashMap;
+
+use h3o::{CellIndex, LatLng, Resolution};
+
+use crate::models::{GridCell, HexCell};
+
+/// Coarsest resolution `/population/h3/{index}` sums: a resolution-3 hexagon
+/// spans about 1.5°, coarser ones would read tens of thousands of cells.
+pub(crate) const MIN_LOOKUP_RESOLUTION: u8 = 3;
+
+pub(crate) fn resolution(value: u8) -> Resolution {
+    Resolution::try_from(value.min(15)).expect("resolutions 0-15 are valid")
+}
+
+/// Hexagon containing a point, `None` for coordinates H3 rejects.
+fn hexagon(lat: f64, lon: f64, resolution: Resolution) -> Option<CellIndex> {
+    LatLng::new(lat, lon).ok().map(|ll| ll.to_cell(resolution))
+}
+
+/// Hexagon vertices as `[lon, lat]` pairs.
+pub(crate) fn boundary(cell: CellIndex) -> Vec<[f64; 2]> {
+    cell.boundary().iter().map(|v| [v.lng(), v.lat()]).collect()
+}
+
+/// `[min_lon, min_lat, max_lon, max_lat]` of a hexagon padded by one grid cell,
+/// since its edges bow slightly past the vertices. `None` when it straddles
+/// the antimeridian.
+pub(crate) fn bounds(cell: CellIndex) -> Option<[f64; 4]> {
+    let vertices = boundary(cell);
+    let (mut min_lon, mut min_lat, mut max_lon, mut max_lat) = (180.0f64, 90.0f64, -180.0f64, -90.0f64);
+    for [lon, lat] in vertices {
+        min_lon = min_lon.min(lon);
+        max_lon = max_lon.max(lon);
+        min_lat = min_lat.min(lat);
+        max_lat = max_lat.max(lat);
+    }
+    if max_lon - min_lon > 180.0 {
+        return None;
+    }
+    let pad = 1.0 / 120.0;
+    Some([
+        (min_lon - pad).max(-180.0),
+        (min_lat - pad).max(-90.0),
+        (max_lon + pad).min(180.0 - 1e-9),
+        (max_lat + pad).min(90.0),
+    ])
+}
+
+/// Population and count of the cells whose centre falls in `cell`.
+pub(crate) fn population_in(cell: CellIndex, cells: &[GridCell]) -> (f64, i64) {
+    cells
+        .iter()
+        .filter(|c| hexagon(c.lat, c.lon, cell.resolution()) == Some(cell))
+        .fold((0.0, 0), |(pop, n), c| (pop + c.population as f64, n + 1))
+}
+
+/// Sum grid cells into the hexagons of `resolution`, most populous first.
+pub(crate) fn aggregate(cells: &[GridCell], resolution: Resolution) -> Vec<HexCell> {
+    let mut sums: HashMap<CellIndex, (f64, i64)> = HashMap::new();
+    for c in cells {
+        if let Some(hex) = hexagon(c.lat, c.lon, resolution) {
+            let entry = sums.entry(hex).or_default();
+            entry.0 += c.population as f64;
+            entry.1 += 1;
+        }
+    }
+
+    let mut hexes: Vec<HexCell> = sums
+        .into_iter()
+        .map(|(hex, (population, cell_count))| {
+            let centre = LatLng::from(hex);
+            HexCell {
+                h3: hex.to_string(),
+                lat: centre.lat(),
+                lon: centre.lng(),
+                population: (population * 10.0).round() / 10.0,
+                cell_count,
+                boundary: boundary(hex),
+            }
+        })
+        .collect();
+    hexes.sort_by(|a, b| b.population.total_cmp(&a.population).then_with(|| a.h3.cmp(&b.h3)));
+    hexes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CellBounds;
+
+    fn cell(lat: f64, lon: f64, population: f32) -> GridCell {
+        GridCell {
+            lat,
+            lon,
+            population,
+            bounds: CellBounds { min_lat: lat, max_lat: lat, min_lon: lon, max_lon: lon },
+            uncertainty: None,
+        }
+    }
+
+    #[test]
+    fn aggregation_keeps_every_cell_once() {
+        let cells: Vec<GridCell> = (0..10)
+            .flat_map(|r| (0..10).map(move |c| (r, c)))
+            .map(|(r, c)| cell(6.9 + (r as f64 + 0.5) / 120.0, 79.8 + (c as f64 + 0.5) / 120.0, 10.0))
+            .collect();
+
+        for res in [5, 7, 9] {
+            let hexes = aggregate(&cells, resolution(res));
+            assert_eq!(hexes.iter().map(|h| h.cell_count).sum::<i64>(), 100, "res {res}");
+            assert!((hexes.iter().map(|h| h.population).sum::<f64>() - 1000.0).abs() < 1e-6);
+            assert!(hexes.windows(2).all(|w| w[0].population >= w[1].population));
+            for hex in &hexes {
+                let index: CellIndex = hex.h3.parse().unwrap();
+                assert_eq!(u8::from(index.resolution()), res);
+                assert_eq!(population_in(index, &cells), (hex.population, hex.cell_count));
+                assert_eq!(hex.boundary.len(), 6);
+            }
+        }
+        // Coarser hexagons hold more cells each.
+        assert!(aggregate(&cells, resolution(5)).len() < aggregate(&cells, resolution(9)).len());
+    }
+
+    #[test]
+    fn bounds_cover_the_hexagon() {
+        let hex = hexagon(6.9271, 79.8612, resolution(7)).unwrap();
+        let [min_lon, min_lat, max_lon, max_lat] = bounds(hex).unwrap();
+        assert!(min_lon < 79.8612 && 79.8612 < max_lon);
+        assert!(min_lat < 6.9271 && 6.9271 < max_lat);
+        assert!(max_lon - min_lon < 0.1);
+
+        let straddling = hexagon(0.0, 179.999, resolution(0)).unwrap();
+        assert_eq!(bounds(straddling), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Shared `include=` expansion parameter.
+//!
+//! Optional response sections are requested with a comma-separated list
+//! (`include=uncertainty,timings`) or, equivalently, one boolean flag per section
+//! (`include_geometry=true`). Both forms are parsed once by the [`Includes`]
+//! extractor; each endpoint declares the sections it honours, and the same
+//! table drives the `include` parameter documented in the OpenAPI spec.
+
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use std::time::Instant;
+use utoipa::openapi::path::{ParameterBuilder, ParameterIn};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::{OpenApi, Required};
+use utoipa::Modify;
+
+use crate::errors::AppError;
+use crate::models::{StageTiming, Timings};
+
+/// An optional response section that can be requested via `include=`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Section {
+    /// WorldPop 95% credible intervals
+    Uncertainty,
+    /// Named places inside the analysed area
+    Places,
+    /// Datasets (and versions) the response was computed from
+    Sources,
+    /// Server-side timing breakdown per processing stage
+    Timings,
+    /// GeoJSON outline of the analysed area
+    Geometry,
+    /// GeoJSON convex hull of the populated cells (implies `geometry`)
+    Hull,
+    /// Population split by GHS-SMOD settlement class
+    Settlement,
+    /// GHSL built-up surface of the area
+    BuiltUp,
+    /// VIIRS nighttime-lights radiance of the area
+    NightLights,
+    /// Population by ESA WorldCover land-cover class
+    LandCover,
+}
+
+impl Section {
+    const ALL: [Section; 10] = [
+        Self::Uncertainty,
+        Self::Places,
+        Self::Sources,
+        Self::Timings,
+        Self::Geometry,
+        Self::Hull,
+        Self::Settlement,
+        Self::BuiltUp,
+        Self::NightLights,
+        Self::LandCover,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Uncertainty => "uncertainty",
+            Self::Places => "places",
+            Self::Sources => "sources",
+            Self::Timings => "timings",
+            Self::Geometry => "geometry",
+            Self::Hull => "hull",
+            Self::Settlement => "settlement",
+            Self::BuiltUp => "built_up",
+            Self::NightLights => "night_lights",
+            Self::LandCover => "land_cover",
+        }
+    }
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+
+    fn lookup(token: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(token))
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "Unknown include section '{token}'. Valid values: {}",
+                    names(&Self::ALL)
+                ))
+            })
+    }
+}
+
+/// Sections honoured by `GET /population`.
+pub(crate) const POPULATION: &[Section] = &[Section::Uncertainty, Section::Sources, Section::Timings];
+/// Sections honoured by `GET /exposure`.
+pub(crate) const EXPOSURE: &[Section] = &[
+    Section::Uncertainty,
+    Section::Places,
+    Section::Sources,
+    Section::Timings,
+    Section::Geometry,
+    Section::Hull,
+    Section::Settlement,
+    Section::BuiltUp,
+    Section::NightLights,
+    Section::LandCover,
+];
+/// Sections honoured by `GET /analyse`.
+pub(crate) const ANALYSE: &[Section] = &[
+    Section::Uncertainty,
+    Section::Places,
+    Section::Sources,
+    Section::Timings,
+    Section::Settlement,
+];
+
+/// Sections honoured by `POST /exposure/cyclone`.
+pub(crate) const CYCLONE: &[Section] = &[Section::Geometry, Section::Sources, Section::Timings];
+
+/// Sections honoured by `POST /exposure/buffer`.
+pub(crate) const BUFFER: &[Section] = &[Section::Sources, Section::Timings];
+
+/// Sections honoured by `POST /exposure/flood`.
+pub(crate) const FLOOD: &[Section] = &[Section::Sources, Section::Timings];
+
+/// Sections honoured by `GET /exposure/isochrone`.
+pub(crate) const ISOCHRONE: &[Section] = &[Section::Sources, Section::Timings];
+
+/// Sections honoured by `POST /exposure/shakemap`.
+pub(crate) const SHAKEMAP: &[Section] = &[Section::Sources, Section::Timings];
+
+/// Endpoints that accept `include=`, keyed by OpenAPI path.
+const ENDPOINTS: &[(&str, &[Section])] = &[
+    ("/population", POPULATION),
+    ("/exposure", EXPOSURE),
+    ("/analyse", ANALYSE),
+    ("/analyse/stream", ANALYSE),
+    ("/exposure/cyclone", CYCLONE),
+    ("/exposure/buffer", BUFFER),
+    ("/exposure/flood", FLOOD),
+    ("/exposure/isochrone", ISOCHRONE),
+    ("/exposure/shakemap", SHAKEMAP),
+];
+
+/// Number of places embedded by `include=places` unless the endpoint pages them.
+pub(crate) const MAX_INCLUDED_PLACES: i64 = 20;
+
+/// The parsed `include=` list of a request.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Includes(u16);
+
+impl Includes {
+    /// Builds the set from query pairs: `include=a,b` lists and `include_<section>=true`
+    /// flags. Other parameters are ignored.
+    fn from_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self, AppError> {
+        let mut set = 0;
+        for (key, value) in pairs {
+            if key == "include" {
+                for token in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                    set |= Section::lookup(token)?.bit();
+                }
+            } else if let Some(token) = key.strip_prefix("include_") {
+                let section = Section::lookup(token)?;
+                match value.to_ascii_lowercase().as_str() {
+                    "true" | "1" | "yes" => set |= section.bit(),
+                    "false" | "0" | "no" | "" => {}
+                    _ => {
+                        return Err(AppError::Validation(format!(
+                            "Invalid value '{value}' for {key}: expected true or false"
+                        )))
+                    }
+                }
+            }
+        }
+        if set & Section::Hull.bit() != 0 {
+            set |= Section::Geometry.bit();
+        }
+        Ok(Self(set))
+    }
+
+    /// Parses a bare comma-separated list, as carried by job submissions.
+    pub fn from_list(raw: &str) -> Result<Self, AppError> {
+        Self::from_pairs([("include", raw)])
+    }
+
+    pub fn has(&self, section: Section) -> bool {
+        self.0 & section.bit() != 0
+    }
+
+    /// Rejects sections the endpoint does not honour, so a typo or unsupported
+    /// expansion fails loudly instead of being silently dropped.
+    pub fn ensure_supported(&self, supported: &[Section]) -> Result<(), AppError> {
+        match Section::ALL.into_iter().find(|&s| self.has(s) && !supported.contains(&s)) {
+            Some(s) => Err(AppError::Validation(format!(
+                "include={} is not supported here. Valid values: {}",
+                s.name(),
+                names(supported)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The same set with `section` added, for endpoints that need a section
+    /// whatever the caller asked for.
+    pub fn with(self, section: Section) -> Self {
+        Self(self.0 | section.bit())
+    }
+
+    /// Starts a stage timer that only records when `include=timings` was requested.
+    pub fn stopwatch(&self) -> Stopwatch {
+        Stopwatch::new(self.has(Section::Timings))
+    }
+}
+
+fn names(sections: &[Section]) -> String {
+    sections.iter().map(|s| s.name()).collect::<Vec<_>>().join(", ")
+}
+
+impl FromRequest for Includes {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let pairs = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+            .map(web::Query::into_inner)
+            .unwrap_or_default();
+        ready(Self::from_pairs(pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))))
+    }
+}
+
+/// Records elapsed time per processing stage for `include=timings`.
+pub(crate) struct Stopwatch {
+    enabled: bool,
+    start: Instant,
+    last: Instant,
+    stages: Vec<StageTiming>,
+}
+
+impl Stopwatch {
+    fn new(enabled: bool) -> Self {
+        let now = Instant::now();
+        Self { enabled, start: now, last: now, stages: Vec::new() }
+    }
+
+    /// Closes the current stage under `stage` and starts the next one.
+    pub fn mark(&mut self, stage: &'static str) {
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        self.stages.push(StageTiming { stage: stage.into(), ms: millis(now - self.last) });
+        self.last = now;
+    }
+
+    pub fn finish(self) -> Option<Timings> {
+        self.enabled.then(|| Timings { total_ms: millis(self.start.elapsed()), stages: self.stages })
+    }
+}
+
+fn millis(d: std::time::Duration) -> f64 {
+    (d.as_secs_f64() * 1e5).round() / 100.0
+}
+
+/// Datasets attributed by `include=sources`.
+pub(crate) mod sources {
+    use crate::models::{DataSource, Dataset};
+
+    pub fn worldpop() -> DataSource {
+        DataSource {
+            name: "WorldPop".into(),
+            version: "R2024B 2025 unconstrained UN-adjusted, 1 km".into(),
+            url: "https://www.worldpop.org/".into(),
+        }
+    }
+
+    /// The WorldPop grid a `dataset` / `year` pair selects.
+    pub fn worldpop_grid(dataset: Option<Dataset>, year: i32) -> DataSource {
+        let dataset = dataset.unwrap_or_default().as_str();
+        DataSource {
+            name: "WorldPop".into(),
+            version: format!("R2024B {year} {dataset} UN-adjusted, 1 km"),
+            url: "https://www.worldpop.org/".into(),
+        }
+    }
+
+    pub fn worldpop_uncertainty() -> DataSource {
+        DataSource {
+            name: "WorldPop uncertainty".into(),
+            version: "95% credible intervals, 1 km (where published)".into(),
+            url: "https://www.worldpop.org/".into(),
+        }
+    }
+
+    pub fn geonames() -> DataSource {
+        DataSource {
+            name: "GeoNames".into(),
+            version: "allCountries, populated places".into(),
+            url: "https://www.geonames.org/".into(),
+        }
+    }
+
+    pub fn terrain() -> DataSource {
+        DataSource {
+            name: "Terrain".into(),
+            version: "30 arc-second DEM: cell elevation and distance to coast".into(),
+            url: "https://www.ncei.noaa.gov/products/etopo-global-relief-model".into(),
+        }
+    }
+
+    pub fn valhalla() -> DataSource {
+        DataSource {
+            name: "Valhalla".into(),
+            version: "Isochrones from the configured routing engine's road network".into(),
+            url: "https://github.com/valhalla/valhalla".into(),
+        }
+    }
+
+    pub fn usgs_shakemap() -> DataSource {
+        DataSource {
+            name: "USGS ShakeMap".into(),
+            version: "Modified Mercalli intensity contours (cont_mmi.json)".into(),
+            url: "https://earthquake.usgs.gov/data/shakemap/".into(),
+        }
+    }
+
+    pub fn ghsl_smod() -> DataSource {
+        DataSource {
+            name: "GHSL Settlement Model".into(),
+            version: "GHS-SMOD degree of urbanisation, 1 km".into(),
+            url: "https://ghsl.jrc.ec.europa.eu/".into(),
+        }
+    }
+
+    pub fn ghsl_built_up() -> DataSource {
+        DataSource {
+            name: "GHSL Built-up Surface".into(),
+            version: "GHS-BUILT-S built-up surface, 30 arc-second".into(),
+            url: "https://ghsl.jrc.ec.europa.eu/".into(),
+        }
+    }
+
+    pub fn viirs_night_lights() -> DataSource {
+        DataSource {
+            name: "VIIRS nighttime lights".into(),
+            version: "Annual VNL composite, averaged to 30 arc-second".into(),
+            url: "https://eogdata.mines.edu/products/vnl/".into(),
+        }
+    }
+
+    pub fn esa_worldcover() -> DataSource {
+        DataSource {
+            name: "ESA WorldCover".into(),
+            version: "10 m land cover, dominant class per 30 arc-second cell".into(),
+            url: "https://esa-worldcover.org/".into(),
+        }
+    }
+
+    pub fn natural_earth(vintage: i32) -> DataSource {
+        DataSource {
+            name: "Natural Earth".into(),
+            version: format!("Admin 0 countries 10m, vintage {vintage}"),
+            url: "https://www.naturalearthdata.com/".into(),
+        }
+    }
+}
+
+/// Adds the shared `include` query parameter to every endpoint in [`ENDPOINTS`],
+/// listing exactly the sections that endpoint honours.
+pub(crate) struct IncludeDocs;
+
+impl Modify for IncludeDocs {
+    fn modify(&self, openapi: &mut OpenApi) {
+        for &(path, sections) in ENDPOINTS {
+            let Some(op) = openapi
+                .paths
+                .paths
+                .get_mut(path)
+                .and_then(|item| item.get.as_mut().or(item.post.as_mut()))
+            else {
+                continue;
+            };
+            let values = names(sections);
+            let param = ParameterBuilder::new()
+                .name("include")
+                .parameter_in(ParameterIn::Query)
+                .required(Required::False)
+                .description(Some(format!(
+                    "Comma-separated optional response sections. Supported here: {values}. \
+                     Each section can also be requested as `include_<section>=true`. \
+                     Unknown or unsupported sections are rejected with 400."
+                )))
+                .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+                .example(Some(serde_json::Value::String(
+                    sections.iter().take(2).map(|s| s.name()).collect::<Vec<_>>().join(","),
+                )))
+                .build();
+            op.parameters.get_or_insert_with(Vec::new).push(param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: Option<&str>) -> Result<Includes, AppError> {
+        raw.map_or(Ok(Includes::default()), Includes::from_list)
+    }
+
+    #[test]
+    fn parses_case_insensitive_list() {
+        let inc = parse(Some(" Uncertainty ,timings,,")).unwrap();
+        assert!(inc.has(Section::Uncertainty));
+        assert!(inc.has(Section::Timings));
+        assert!(!inc.has(Section::Places));
+    }
+
+    #[test]
+    fn empty_list_includes_nothing() {
+        let inc = parse(None).unwrap();
+        assert!(Section::ALL.into_iter().all(|s| !inc.has(s)));
+    }
+
+    #[test]
+    fn rejects_unknown_section() {
+        assert!(parse(Some("uncertainty,bogus")).is_err());
+    }
+
+    #[test]
+    fn accepts_boolean_flags() {
+        let inc = Includes::from_pairs([("include_geometry", "true"), ("include_places", "false")]).unwrap();
+        assert!(inc.has(Section::Geometry));
+        assert!(!inc.has(Section::Places));
+        assert!(Includes::from_pairs([("include_geometry", "maybe")]).is_err());
+    }
+
+    #[test]
+    fn hull_implies_geometry() {
+        assert!(parse(Some("hull")).unwrap().has(Section::Geometry));
+    }
+
+    #[test]
+    fn rejects_unsupported_section() {
+        let inc = parse(Some("places")).unwrap();
+        assert!(inc.ensure_supported(POPULATION).is_err());
+        assert!(inc.ensure_supported(EXPOSURE).is_ok());
+    }
+}
@@ -0,0 +1,394 @@
+//! Background execution of `/jobs` submissions.
+//!
+//! Jobs live in the `jobs` table, so they survive restarts and are shared by
+//! every API instance on the same database. Each instance runs a small pool of
+//! workers that claim jobs one at a time; a submission wakes a local worker
+//! immediately, and workers also poll so jobs submitted elsewhere are picked up.
+//!
+//! Jobs submitted with a `callback_url` are POSTed there once finished. The
+//! body is the job as `GET /jobs/{id}` returns it, signed with HMAC-SHA256 over
+//! `"{timestamp}.{body}"` using `WEBHOOK_SECRET`; failed deliveries are retried
+//! with exponential backoff. Callback URLs must pass `Webhooks::check`
+//! on submission and again before each attempt.
+
+use deadpool_postgres::Pool;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use validator::Validate;
+
+use crate::config::{ExposureBackend, Limits};
+use crate::errors::AppError;
+use crate::hazard::HazardTemplates;
+use crate::include::Includes;
+use crate::models::{
+    BoundaryQuery, ExposureQuery, HazardQuery, JobKind, JobPayload, PlaceFilterQuery, PointQuery,
+    SearchRadiusQuery, YearQuery,
+};
+use crate::repositories::{ClaimedJob, JobRepository, Repositories};
+use crate::routes::{analyse, exposure};
+use crate::webhook::{self, Webhooks};
+
+/// How often idle workers look for jobs submitted to other instances.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Attempts before a job whose worker keeps dying is marked failed.
+const MAX_ATTEMPTS: i16 = 3;
+/// Finished jobs are deleted after this many hours.
+const RETENTION_HOURS: i32 = 7 * 24;
+const PURGE_EVERY: Duration = Duration::from_secs(3600);
+
+/// Webhook delivery attempts before giving up (~1 h of retries in total).
+pub(crate) const MAX_CALLBACK_ATTEMPTS: i16 = 8;
+const CALLBACK_BASE_DELAY_SECS: f64 = 30.0;
+const CALLBACK_MAX_DELAY_SECS: f64 = 3600.0;
+pub(crate) const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// A delivery not recorded within this window (sender crashed) is retried.
+pub(crate) const CALLBACK_LEASE_SECS: f64 = 60.0;
+pub(crate) const CALLBACK_BATCH: i64 = 16;
+
+/// A validated job request, ready to run.
+pub(crate) enum JobRequest {
+    Exposure(ExposureQuery, BoundaryQuery, Includes),
+    Analyse(analyse::AnalyseRequest, Includes),
+}
+
+impl JobRequest {
+    /// Parses and validates submission parameters with the same rules as the
+    /// synchronous endpoint.
+    pub fn parse(
+        kind: JobKind,
+        params: &Value,
+        include: Option<&str>,
+        limits: &Limits,
+    ) -> Result<Self, AppError> {
+        let includes = Includes::from_list(include.unwrap_or_default())?;
+        let invalid = |e: serde_json::Error| AppError::Validation(format!("Invalid params: {e}"));
+        match kind {
+            JobKind::Exposure => {
+                let query: ExposureQuery = serde_json::from_value(params.clone()).map_err(invalid)?;
+                let boundaries: BoundaryQuery =
+                    serde_json::from_value(params.clone()).map_err(invalid)?;
+                exposure::check(&query, includes, limits)?;
+                boundaries.validate().map_err(|e| {
+                    AppError::Validation(format!("Validation failed: {e}"))
+                })?;
+                Ok(Self::Exposure(query, boundaries, includes))
+            }
+            JobKind::Analyse => {
+                let point: PointQuery = serde_json::from_value(params.clone()).map_err(invalid)?;
+                let boundaries: BoundaryQuery =
+                    serde_json::from_value(params.clone()).map_err(invalid)?;
+                let places: PlaceFilterQuery =
+                    serde_json::from_value(params.clone()).map_err(invalid)?;
+                let year: YearQuery = serde_json::from_value(params.clone()).map_err(invalid)?;
+                let search: SearchRadiusQuery =
+                    serde_json::from_value(params.clone()).map_err(invalid)?;
+                let hazard: HazardQuery = serde_json::from_value(params.clone()).map_err(invalid)?;
+                let request = analyse::AnalyseRequest { point, boundaries, places, year, search, hazard };
+                analyse::check(&request, includes, &limits.hazards)?;
+                Ok(Self::Analyse(request, includes))
+            }
+        }
+    }
+
+    async fn run(&self, repos: &Repositories, hazards: &HazardTemplates) -> Result<Value, AppError> {
+        let payload = match self {
+            Self::Exposure(query, boundaries, includes) => serde_json::to_value(
+                exposure::run(
+                    &**repos.population_for(query.dataset),
+                    &*repos.geocoding,
+                    query,
+                    *includes,
+                    repos.countries.resolve_vintage(boundaries.boundaries).await?,
+                )
+                .await?,
+            ),
+            Self::Analyse(request, includes) => serde_json::to_value(
+                analyse::run(repos, request, hazards, *includes, &analyse::Progress::default()).await?,
+            ),
+        };
+        payload.map_err(|e| AppError::Database(format!("failed to encode job result: {e}")))
+    }
+}
+
+/// Handle used by the submit endpoint to wake an idle local worker.
+#[derive(Clone)]
+pub(crate) struct JobQueue {
+    notify: Arc<Notify>,
+    webhooks: Webhooks,
+}
+
+impl JobQueue {
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Whether `callback_url` can be honoured (a webhook secret is configured).
+    pub fn callbacks_enabled(&self) -> bool {
+        self.webhooks.enabled()
+    }
+
+    /// Webhook settings callback and alert URLs are checked against.
+    pub fn webhooks(&self) -> &Webhooks {
+        &self.webhooks
+    }
+}
+
+/// Starts `workers` background workers (plus a webhook sender when a secret is
+/// configured) and returns the queue handle.
+pub(crate) fn spawn_workers(
+    pool: Pool,
+    read_pool: Pool,
+    workers: usize,
+    timeout_secs: u32,
+    webhooks: Webhooks,
+    exposure: ExposureBackend,
+    limits: Limits,
+) -> JobQueue {
+    let queue = JobQueue {
+        notify: Arc::new(Notify::new()),
+        webhooks,
+    };
+    for id in 0..workers {
+        // Job queries may run up to the job timeout rather than the request one.
+        let repos = Repositories::postgres(&read_pool, timeout_secs, exposure);
+        tokio::spawn(worker(id, pool.clone(), repos, queue.notify.clone(), timeout_secs, limits.clone()));
+    }
+    if workers > 0 {
+        log::info!("Started {workers} job worker(s), timeout {timeout_secs}s");
+        if queue.callbacks_enabled() {
+            tokio::spawn(deliver_callbacks(pool, queue.webhooks.clone()));
+        }
+    }
+    queue
+}
+
+async fn worker(
+    id: usize,
+    pool: Pool,
+    repos: Repositories,
+    notify: Arc<Notify>,
+    timeout_secs: u32,
+    limits: Limits,
+) {
+    // A job is only considered abandoned well after it should have timed out.
+    let stale_after_secs = f64::from(timeout_secs) * 2.0;
+    let mut last_purge: Option<Instant> = None;
+
+    loop {
+        if id == 0 && last_purge.is_none_or(|t| t.elapsed() >= PURGE_EVERY) {
+            housekeeping(&pool, stale_after_secs).await;
+            last_purge = Some(Instant::now());
+        }
+
+        let claimed = match pool.get().await {
+            Ok(client) => JobRepository::claim(&client, stale_after_secs, MAX_ATTEMPTS).await,
+            Err(e) => Err(AppError::from(e)),
+        };
+        match claimed {
+            Ok(Some(job)) => execute(&pool, &repos, &limits, job, timeout_secs).await,
+            Ok(None) => {
+                tokio::select! {
+                    _ = notify.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+            Err(e) => {
+                log::error!("job worker {id}: failed to claim job: {e}");
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn execute(
+    pool: &Pool,
+    repos: &Repositories,
+    limits: &Limits,
+    job: ClaimedJob,
+    timeout_secs: u32,
+) {
+    let started = Instant::now();
+    let outcome = match JobRequest::parse(job.kind, &job.params, job.include.as_deref(), limits) {
+        Ok(request) => {
+            let limit = Duration::from_secs(u64::from(timeout_secs));
+            match tokio::time::timeout(limit, request.run(repos, &limits.hazards)).await {
+                Ok(result) => result,
+                Err(_) => Err(AppError::Timeout(format!("job exceeded {timeout_secs}s"))),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    let recorded = match pool.get().await {
+        Ok(client) => match &outcome {
+            Ok(result) => JobRepository::succeed(&client, job.id, result).await,
+            Err(e) => {
+                match e {
+                    AppError::Database(msg) | AppError::Transient(msg) => log::error!("job {}: {msg}", job.id),
+                    AppError::Timeout(msg) => log::warn!("job {}: {msg}", job.id),
+                    _ => {}
+                }
+                JobRepository::fail(&client, job.id, e.public_message()).await
+            }
+        },
+        Err(e) => Err(AppError::from(e)),
+    };
+    match recorded {
+        Ok(()) => log::info!(
+            "job {} ({}) {} in {:.1}s",
+            job.id,
+            job.kind.as_str(),
+            if outcome.is_ok() { "succeeded" } else { "failed" },
+            started.elapsed().as_secs_f64()
+        ),
+        Err(e) => log::error!("job {}: failed to record outcome: {e}", job.id),
+    }
+}
+
+async fn housekeeping(pool: &Pool, stale_after_secs: f64) {
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("job housekeeping skipped: {e}");
+            return;
+        }
+    };
+    match JobRepository::fail_exhausted(&client, stale_after_secs, MAX_ATTEMPTS).await {
+        Ok(0) => {}
+        Ok(n) => log::warn!("marked {n} abandoned job(s) as failed"),
+        Err(e) => log::warn!("job housekeeping failed: {e}"),
+    }
+    match JobRepository::purge_finished(&client, RETENTION_HOURS).await {
+        Ok(0) => {}
+        Ok(n) => log::info!("purged {n} finished job(s)"),
+        Err(e) => log::warn!("job purge failed: {e}"),
+    }
+}
+
+/// Sends due webhook callbacks until the process exits.
+async fn deliver_callbacks(pool: Pool, webhooks: Webhooks) {
+    let http = match webhook::client(CALLBACK_TIMEOUT) {
+        Ok(http) => http,
+        Err(e) => {
+            log::error!("job callbacks disabled: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+
+    loop {
+        let due = match pool.get().await {
+            Ok(client) => {
+                JobRepository::claim_callbacks(&client, CALLBACK_LEASE_SECS, CALLBACK_BATCH).await
+            }
+            Err(e) => Err(AppError::from(e)),
+        };
+        let due = match due {
+            Ok(due) => due,
+            Err(e) => {
+                log::error!("job callbacks: failed to claim deliveries: {e}");
+                Vec::new()
+            }
+        };
+        if due.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        for (job, url) in due {
+            let attempts = job.callback.as_ref().map_or(1, |c| c.attempts);
+            let id = job.id;
+            let outcome = match webhooks.check(&url).await {
+                Ok(()) => send_callback(&http, webhooks.secret(), &url, job).await,
+                Err(e) => Err(format!("callback_url rejected: {e}")),
+            };
+            let recorded = match pool.get().await {
+                Ok(client) => match &outcome {
+                    Ok(()) => JobRepository::callback_delivered(&client, id).await,
+                    Err(error) => {
+                        let retry = (attempts < MAX_CALLBACK_ATTEMPTS).then(|| retry_delay_secs(attempts));
+                        log::warn!(
+                            "job {id}: callback attempt {attempts} failed ({error}){}",
+                            if retry.is_some() { ", will retry" } else { ", giving up" }
+                        );
+                        JobRepository::callback_failed(&client, id, error, retry).await
+                    }
+                },
+                Err(e) => Err(AppError::from(e)),
+            };
+            if let Err(e) = recorded {
+                log::error!("job {id}: failed to record callback outcome: {e}");
+            }
+        }
+    }
+}
+
+async fn send_callback(
+    http: &reqwest::Client,
+    secret: &str,
+    url: &str,
+    mut job: JobPayload,
+) -> Result<(), String> {
+    job.callback = None;
+    let body = serde_json::to_vec(&job).map_err(|e| e.to_string())?;
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let response = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-GeoPop-Event", "job.finished")
+        .header("X-GeoPop-Job-Id", job.id.to_string())
+        .header("X-GeoPop-Timestamp", timestamp.to_string())
+        .header("X-GeoPop-Signature", sign(secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("receiver answered {}", response.status()))
+    }
+}
+
+/// `sha256=<hex>` HMAC of `"{timestamp}.{body}"`. Receivers recompute it with
+/// the shared secret and should reject stale timestamps to prevent replays.
+pub(crate) fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    // HMAC accepts keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Exponential backoff after the `attempt`-th failure: 30 s, 1 min, 2 min, …, capped at 1 h.
+pub(crate) fn retry_delay_secs(attempt: i16) -> f64 {
+    let exponent = i32::from(attempt.max(1) - 1).min(16);
+    (CALLBACK_BASE_DELAY_SECS * 2f64.powi(exponent)).min(CALLBACK_MAX_DELAY_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_matches_reference_hmac() {
+        // echo -n '1700000000.{}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", 1_700_000_000, b"{}"),
+            "sha256=b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_cap() {
+        assert_eq!(retry_delay_secs(1), 30.0);
+        assert_eq!(retry_delay_secs(2), 60.0);
+        assert_eq!(retry_delay_secs(4), 240.0);
+        assert_eq!(retry_delay_secs(20), CALLBACK_MAX_DELAY_SECS);
+    }
+}
@@ -0,0 +1,198 @@
+//! KML export of `/exposure` and `/population?radius=`, for responders who
+//! work in Google Earth rather than with JSON.
+//!
+//! A document holds up to three folders: the search area as a polygon with
+//! its centre, the grid cells as rectangles shaded by population, and the
+//! named places as points. Figures go into each placemark's `ExtendedData`
+//! so they show in the balloon and survive a conversion to other formats.
+
+use std::fmt::Write;
+
+use crate::geometry;
+use crate::models::{ExposedPlace, GridCell};
+
+/// Content type of the documents written here.
+pub(crate) const CONTENT_TYPE: &str = "application/vnd.google-earth.kml+xml";
+
+/// Cell fill colours (KML `aabbggrr`) from sparse to dense, with the lower
+/// population bound of each.
+const CELL_STYLES: [(&str, f32); 4] = [
+    ("7fb2ffff", 0.0),
+    ("7f4cb2fe", 100.0),
+    ("7f3c8dfd", 1_000.0),
+    ("7f2600bd", 10_000.0),
+];
+
+/// A KML document under construction.
+pub(crate) struct KmlDocument {
+    name: String,
+    lat: f64,
+    lon: f64,
+    radius_km: f64,
+    cells: Vec<GridCell>,
+    places: Vec<ExposedPlace>,
+}
+
+impl KmlDocument {
+    /// A document for the circle of `radius_km` around `lat`/`lon`.
+    pub fn new(name: impl Into<String>, lat: f64, lon: f64, radius_km: f64) -> Self {
+        Self { name: name.into(), lat, lon, radius_km, cells: Vec::new(), places: Vec::new() }
+    }
+
+    pub fn cells(mut self, cells: Vec<GridCell>) -> Self {
+        self.cells = cells;
+        self
+    }
+
+    pub fn places(mut self, places: Vec<ExposedPlace>) -> Self {
+        self.places = places;
+        self
+    }
+
+    /// Writes the document, with `summary` as the search area's
+    /// `ExtendedData` fields.
+    pub fn render(&self, summary: &[(&str, String)]) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+        let _ = writeln!(out, "<name>{}</name>", escape(&self.name));
+        out.push_str(
+            "<Style id=\"area\"><LineStyle><color>ff0000ff</color><width>2</width></LineStyle>\
+             <PolyStyle><color>1a0000ff</color></PolyStyle></Style>\n",
+        );
+        for (i, (colour, _)) in CELL_STYLES.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "<Style id=\"cell{i}\"><LineStyle><width>0</width></LineStyle>\
+                 <PolyStyle><color>{colour}</color><outline>0</outline></PolyStyle></Style>"
+            );
+        }
+        out.push_str(
+            "<Style id=\"place\"><IconStyle><scale>0.8</scale><Icon>\
+             <href>http://maps.google.com/mapfiles/kml/paddle/wht-circle.png</href></Icon></IconStyle></Style>\n",
+        );
+
+        out.push_str("<Folder>\n<name>Search area</name>\n");
+        let area = geometry::rings(&geometry::search_area(self.lat, self.lon, self.radius_km));
+        let _ = writeln!(out, "<Placemark>\n<name>{} km radius</name>\n<styleUrl>#area</styleUrl>", self.radius_km);
+        extended_data(&mut out, summary);
+        if let Some(ring) = area.first() {
+            polygon(&mut out, ring);
+        }
+        out.push_str("</Placemark>\n");
+        let _ = writeln!(
+            out,
+            "<Placemark>\n<name>Centre</name>\n<Point><coordinates>{},{}</coordinates></Point>\n</Placemark>",
+            self.lon, self.lat
+        );
+        out.push_str("</Folder>\n");
+
+        if !self.cells.is_empty() {
+            out.push_str("<Folder>\n<name>Grid cells</name>\n");
+            for cell in &self.cells {
+                let b = &cell.bounds;
+                let _ = writeln!(
+                    out,
+                    "<Placemark>\n<name>{}</name>\n<styleUrl>#cell{}</styleUrl>",
+                    cell.population.round(),
+                    cell_style(cell.population)
+                );
+                extended_data(&mut out, &[("population", format!("{:.1}", cell.population))]);
+                polygon(
+                    &mut out,
+                    &[
+                        [b.min_lon, b.min_lat],
+                        [b.max_lon, b.min_lat],
+                        [b.max_lon, b.max_lat],
+                        [b.min_lon, b.max_lat],
+                        [b.min_lon, b.min_lat],
+                    ],
+                );
+                out.push_str("</Placemark>\n");
+            }
+            out.push_str("</Folder>\n");
+        }
+
+        if !self.places.is_empty() {
+            out.push_str("<Folder>\n<name>Places</name>\n");
+            for place in &self.places {
+                let _ = writeln!(out, "<Placemark>\n<name>{}</name>\n<styleUrl>#place</styleUrl>", escape(&place.name));
+                extended_data(
+                    &mut out,
+                    &[
+                        ("population", place.population.to_string()),
+                        ("distance_km", place.distance_km.to_string()),
+                        ("direction", place.direction.clone()),
+                    ],
+                );
+                let _ = writeln!(out, "<Point><coordinates>{},{}</coordinates></Point>\n</Placemark>", place.lon, place.lat);
+            }
+            out.push_str("</Folder>\n");
+        }
+
+        out.push_str("</Document>\n</kml>\n");
+        out
+    }
+}
+
+fn cell_style(population: f32) -> usize {
+    CELL_STYLES.iter().rposition(|(_, min)| population >= *min).unwrap_or(0)
+}
+
+fn extended_data(out: &mut String, fields: &[(&str, String)]) {
+    out.push_str("<ExtendedData>");
+    for (name, value) in fields {
+        let _ = write!(out, "<Data name=\"{name}\"><value>{}</value></Data>", escape(value));
+    }
+    out.push_str("</ExtendedData>\n");
+}
+
+fn polygon(out: &mut String, ring: &[[f64; 2]]) {
+    out.push_str("<Polygon><outerBoundaryIs><LinearRing><coordinates>");
+    for (i, [lon, lat]) in ring.iter().enumerate() {
+        let _ = write!(out, "{}{lon},{lat}", if i == 0 { "" } else { " " });
+    }
+    out.push_str("</coordinates></LinearRing></outerBoundaryIs></Polygon>\n");
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CellBounds;
+
+    fn cell(population: f32) -> GridCell {
+        GridCell {
+            lat: 6.925,
+            lon: 79.858,
+            population,
+            bounds: CellBounds { min_lat: 6.9208, max_lat: 6.9292, min_lon: 79.8542, max_lon: 79.8625 },
+            uncertainty: None,
+        }
+    }
+
+    #[test]
+    fn cells_are_shaded_by_population() {
+        assert_eq!(cell_style(0.0), 0);
+        assert_eq!(cell_style(99.9), 0);
+        assert_eq!(cell_style(2_200.0), 2);
+        assert_eq!(cell_style(50_000.0), 3);
+    }
+
+    #[test]
+    fn document_has_area_and_cell_folders() {
+        let kml = KmlDocument::new("Exposure <Colombo>", 6.9271, 79.8612, 5.0)
+            .cells(vec![cell(2_200.0)])
+            .render(&[("total_population", "55000".into())]);
+        assert!(kml.starts_with("<?xml"));
+        assert!(kml.contains("<name>Exposure &lt;Colombo&gt;</name>"));
+        assert!(kml.contains("<Data name=\"total_population\"><value>55000</value></Data>"));
+        assert!(kml.contains("<styleUrl>#cell2</styleUrl>"));
+        assert!(kml.contains("79.8542,6.9208 79.8625,6.9208"));
+        assert!(!kml.contains("<name>Places</name>"));
+        assert_eq!(kml.matches("<Placemark>").count(), kml.matches("</Placemark>").count());
+        assert!(kml.trim_end().ends_with("</kml>"));
+    }
+}
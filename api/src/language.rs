@@ -0,0 +1,82 @@
+//! Language negotiation for localised place names.
+//!
+//! `/reverse` names the place, its regions and its country in the client's
+//! language when GeoNames has an alternate name for it, falling back to the
+//! default GeoNames name (English for countries and most regions) otherwise.
+
+use actix_web::http::header::{HeaderValue, ACCEPT_LANGUAGE, VARY};
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::validation::{is_language_tag, parse_languages, MAX_LANGUAGES};
+
+/// Languages to name places in, most preferred first: the `lang` parameter
+/// when given, else `Accept-Language` by q-value (`*`, malformed tags and
+/// `q=0` skipped). A tag with a region is followed by its bare language, so
+/// `pt-BR` still finds Portuguese names. Empty means the default names.
+pub(crate) fn preferred(lang: Option<&str>, req: &HttpRequest) -> Vec<String> {
+    let tags = match lang.and_then(parse_languages) {
+        Some(tags) => tags,
+        None => req
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .map(accept_language)
+            .unwrap_or_default(),
+    };
+    let mut languages = Vec::with_capacity(tags.len() * 2);
+    for tag in tags {
+        let primary = tag.split('-').next().unwrap_or_default().to_string();
+        for candidate in [tag, primary] {
+            if !languages.contains(&candidate) {
+                languages.push(candidate);
+            }
+        }
+    }
+    languages
+}
+
+/// The tags of an `Accept-Language` value, lowercased, by descending q-value.
+fn accept_language(header: &str) -> Vec<String> {
+    let mut weighted: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let tag = parts.next()?.to_ascii_lowercase();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            (q > 0.0 && is_language_tag(&tag)).then_some((tag, q))
+        })
+        .take(MAX_LANGUAGES)
+        .collect();
+    // Stable, so equal weights keep the client's order.
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Marks `res` as depending on `Accept-Language`, for shared caches.
+pub(crate) fn vary(mut res: HttpResponse) -> HttpResponse {
+    res.headers_mut().append(VARY, HeaderValue::from_static("Accept-Language"));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn orders_accept_language_by_weight() {
+        assert_eq!(accept_language("en;q=0.5, ta, si;q=0.8"), ["ta", "si", "en"]);
+        assert_eq!(accept_language("*, fr;q=0, de-CH;q=x, it"), ["it"]);
+        assert!(accept_language("").is_empty());
+    }
+
+    #[test]
+    fn lang_parameter_overrides_header() {
+        let req = TestRequest::default().insert_header((ACCEPT_LANGUAGE, "pt-BR, en;q=0.9")).to_http_request();
+        assert_eq!(preferred(None, &req), ["pt-br", "pt", "en"]);
+        assert_eq!(preferred(Some("ta"), &req), ["ta"]);
+        assert!(preferred(None, &TestRequest::default().to_http_request()).is_empty());
+    }
+}
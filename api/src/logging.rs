@@ -0,0 +1,276 @@
+//! Log output and the per-request access log.
+//!
+//! `LOG_FORMAT=json` writes every record as one JSON object per line, with
+//! the record's key-values as top-level fields, for log pipelines (Loki, ELK)
+//! that cannot parse free-form lines; the default `text` keeps the human
+//! format with the key-values appended as `key=value`. [`AccessLog`] emits
+//! one record per request carrying the method, path, status, latency, the
+//! request ID (echoed as `X-Request-Id`) and the time spent holding database
+//! connections.
+
+use std::cell::Cell;
+use std::env;
+use std::future::{ready, Ready};
+use std::io::Write;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue, USER_AGENT};
+use actix_web::Error;
+use env_logger::Env;
+use log::kv::{self, Key, Value, VisitSource};
+use log::Record;
+use serde_json::{Map, Number};
+use uuid::Uuid;
+
+pub(crate) const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Target of the access log records, so `RUST_LOG` can silence them.
+const ACCESS_TARGET: &str = "access";
+
+/// Paths left out of the access log: probes would drown everything else.
+const QUIET_PATHS: &[&str] = &["/api/v1/health", "/api/v2/health"];
+
+/// Longest client-supplied request ID that is kept; longer or non-printable
+/// ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format `{other}`")),
+        }
+    }
+}
+
+/// Installs the global logger: `RUST_LOG` filters (default `info`), and
+/// `LOG_FORMAT` picks the output. Runs before `Config::from_env` so the
+/// configuration can log what it ignores.
+pub(crate) fn init() {
+    let format = env::var("LOG_FORMAT").ok().and_then(|v| v.parse().ok()).unwrap_or_default();
+    let mut builder = env_logger::Builder::from_env(Env::default().default_filter_or("info"));
+    match format {
+        LogFormat::Text => builder.format_timestamp_secs(),
+        LogFormat::Json => builder.format(|buf, record| writeln!(buf, "{}", json_line(record))),
+    };
+    builder.init();
+}
+
+/// A record as a JSON object: timestamp, level, target and message, then the
+/// key-values.
+fn json_line(record: &Record<'_>) -> serde_json::Value {
+    let mut fields = Map::new();
+    fields.insert("ts".into(), chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true).into());
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+    serde_json::Value::Object(fields)
+}
+
+struct JsonFields<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let json = if let Some(n) = value.to_u64() {
+            n.into()
+        } else if let Some(n) = value.to_i64() {
+            n.into()
+        } else if let Some(b) = value.to_bool() {
+            b.into()
+        } else if let Some(n) = value.to_f64().and_then(Number::from_f64) {
+            n.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.as_str().to_string(), json);
+        Ok(())
+    }
+}
+
+tokio::task_local! {
+    /// Time the request being handled has held database connections.
+    static DB_TIME: Cell<Duration>;
+}
+
+/// Adds `elapsed` to the database time of the current request; a no-op
+/// outside [`AccessLog`] (jobs, streamed bodies).
+pub(crate) fn record_db_time(elapsed: Duration) {
+    let _ = DB_TIME.try_with(|total| total.set(total.get() + elapsed));
+}
+
+/// The request's `X-Request-Id` when it is usable, else a fresh UUID.
+fn request_id(req: &ServiceRequest) -> String {
+    req.headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string())
+}
+
+/// Access log middleware, replacing actix's `Logger`.
+pub(crate) struct AccessLog;
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogMiddleware { service }))
+    }
+}
+
+pub(crate) struct AccessLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let id = request_id(&req);
+        let quiet = QUIET_PATHS.contains(&req.path());
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let query = req.query_string().to_string();
+        let peer = req.connection_info().realip_remote_addr().unwrap_or("-").to_string();
+        let user_agent =
+            req.headers().get(USER_AGENT).and_then(|v| v.to_str().ok()).unwrap_or("-").to_string();
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let (res, db_time) = DB_TIME
+                .scope(Cell::new(Duration::ZERO), async move {
+                    let res = fut.await;
+                    (res, DB_TIME.with(Cell::get))
+                })
+                .await;
+            let (status, bytes) = match &res {
+                Ok(res) => (res.status(), match res.response().body().size() {
+                    BodySize::Sized(n) => Some(n),
+                    _ => None,
+                }),
+                Err(e) => (e.as_response_error().status_code(), None),
+            };
+            if !quiet {
+                log::info!(
+                    target: ACCESS_TARGET,
+                    method = method.as_str(),
+                    path = path.as_str(),
+                    query = query.as_str(),
+                    status = status.as_u16(),
+                    latency_ms = millis(started.elapsed()),
+                    db_ms = millis(db_time),
+                    bytes = bytes,
+                    request_id = id.as_str(),
+                    peer = peer.as_str(),
+                    user_agent = user_agent.as_str();
+                    "{method} {path} {}", status.as_u16()
+                );
+            }
+            let mut res = res?;
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                res.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Milliseconds to a tenth.
+fn millis(d: Duration) -> f64 {
+    (d.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines_carry_key_values() {
+        let status = 200u16;
+        let kvs: &[(&str, Value<'_>)] =
+            &[("status", Value::from(status)), ("latency_ms", Value::from(1.5)), ("path", Value::from("/api/v1/population"))];
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("GET /api/v1/population 200"))
+                .level(log::Level::Info)
+                .target(ACCESS_TARGET)
+                .key_values(&kvs)
+                .build(),
+        );
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "access");
+        assert_eq!(line["message"], "GET /api/v1/population 200");
+        assert_eq!(line["status"], 200);
+        assert_eq!(line["latency_ms"], 1.5);
+        assert_eq!(line["path"], "/api/v1/population");
+        assert!(line["ts"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn parses_log_formats() {
+        assert_eq!("JSON".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("text".parse::<LogFormat>(), Ok(LogFormat::Text));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[actix_web::test]
+    async fn echoes_or_assigns_request_ids() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let app = test::init_service(App::new().wrap(AccessLog).route(
+            "/",
+            web::get().to(|| async {
+                record_db_time(Duration::from_millis(3));
+                HttpResponse::Ok().finish()
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/").insert_header((REQUEST_ID_HEADER, "abc-123"));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.headers().get(&REQUEST_ID_HEADER).unwrap(), "abc-123");
+
+        let req = test::TestRequest::get().uri("/").insert_header((REQUEST_ID_HEADER, "bad id"));
+        let res = test::call_service(&app, req.to_request()).await;
+        let id = res.headers().get(&REQUEST_ID_HEADER).unwrap().to_str().unwrap();
+        assert_eq!(id.len(), 32);
+    }
+}
@@ -1,28 +1,74 @@
+mod address;
+mod audit;
 mod auth;
+mod caches;
+mod conditional;
 mod config;
+mod counters;
+mod crs;
+mod envelope;
 mod errors;
+mod gdacs;
+mod geopackage;
+mod geometry;
+mod graphql;
 mod grid;
+mod hazard;
+mod hex;
+mod include;
+mod jobs;
+mod kml;
+mod language;
+mod logging;
+mod monitors;
+mod migrations;
 mod models;
+mod pyramid;
+mod report;
 mod repositories;
 mod response;
 mod routes;
+mod routing;
+mod shakemap;
+mod s2;
+mod shapefile;
+mod sqlite;
+mod state;
+mod statement_timeout;
 mod validation;
+mod webhook;
+mod windfield;
+
+#[cfg(test)]
+mod tests;
 
 use actix_cors::Cors;
-use actix_web::{middleware::Logger, web, App, HttpServer};
+use actix_web::{web, App, HttpServer};
 
+use crate::audit::AuditLog;
 use crate::auth::ApiKeyAuth;
-use deadpool_postgres::{Config as PgConfig, ManagerConfig, PoolConfig, RecyclingMethod, Runtime, Timeouts};
-use env_logger::Env;
+use crate::conditional::{ConditionalGet, TagCache};
+use crate::counters::{CountRequests, RequestCounters};
+use crate::crs::CrsInput;
+use crate::statement_timeout::StatementTimeout;
+use crate::envelope::EnvelopeOptOut;
+use crate::include::IncludeDocs;
+use crate::logging::AccessLog;
+use crate::repositories::Repositories;
+use crate::state::AppState;
+use crate::webhook::Webhooks;
+use deadpool_postgres::{Config as PgConfig, ManagerConfig, Pool, PoolConfig, RecyclingMethod, Runtime, Timeouts};
 use native_tls::{Certificate, TlsConnector};
 use postgres_native_tls::MakeTlsConnector;
+use std::sync::Arc;
 use std::{env, fs};
+use tokio::sync::Semaphore;
 use tokio_postgres::NoTls;
 use utoipa::openapi::Server;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::config::API_PREFIX;
+use crate::config::{ExposureBackend, API_PREFIX, API_V2_PREFIX};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -30,109 +76,176 @@ use crate::config::API_PREFIX;
         title = "GeoPop API",
         description = "High-performance API for global population lookups, reverse geocoding, \
             country boundaries, and disaster risk exposure assessment.\n\n\
-            Data sources: WorldPop 1km grid, Natural Earth boundaries, GeoNames places.",
+            Data sources: WorldPop 1km grid, Natural Earth boundaries, GeoNames places.\n\n\
+            Successful JSON responses are wrapped as `{success, message, payload}`. Add \
+            `envelope=false` to the query (or send `X-GeoPop-Envelope: false`) to receive \
+            the payload schema at the top level instead.\n\n\
+            `lat`/`lon` values may carry a hemisphere letter or use degrees-minutes-seconds \
+            (`6°55'38\"N`), and `from`/`to` pairs may also be written `6.9271N 79.8612E`.\n\n\
+            Endpoints taking `lat`/`lon` also accept a projected point as `x`, `y` and \
+            `crs=EPSG:3857` (or a WGS84 UTM zone, `EPSG:326xx` / `EPSG:327xx`). It is converted \
+            to WGS84 before the lookup and echoed as `input_coordinate` in the payload.",
         version = "1.0.0"
     ),
     paths(
         routes::root::root,
         routes::health::health,
+        routes::admin::admin_stats,
+        routes::admin::flush_caches,
         routes::population::get_population,
         routes::population::batch_population,
+        routes::population::population_rank,
+        routes::population::population_grid,
+        routes::population::h3_population,
+        routes::population::s2_population,
+        routes::population::population_change,
+        routes::cell::cell,
         routes::geocoding::reverse_geocode,
+        routes::geocoding::admin_hierarchy,
         routes::geocoding::nearby_countries,
         routes::geocoding::nearby_cities,
         routes::geocoding::land_check,
         routes::geocoding::search_cities,
+        routes::geocoding::facilities_within,
+        routes::geocoding::nearest_facilities,
+        routes::geocoding::nearest_airports,
+        routes::distance::distance,
         routes::exposure::exposure,
         routes::exposure::exposure_places,
+        routes::exposure::exposure_admin,
+        routes::compare::compare,
+        routes::route_sample::route_sample,
+        routes::cyclone::cyclone_exposure,
+        routes::buffer::buffer_exposure,
+        routes::flood::flood_exposure,
+        routes::isochrone::isochrone_exposure,
+        routes::shakemap::shakemap_exposure,
         routes::analyse::analyse,
+        routes::analyse::analyse_stream,
+        routes::analyse::analyse_report,
         routes::country::country_lookup,
         routes::country::country_by_iso3,
+        routes::country::country_by_iso2,
+        routes::country::country_by_numeric,
+        routes::country::country_population,
         routes::country::countries_by_continent,
+        routes::country::continents,
+        routes::country::eez_lookup,
+        routes::export::export_geopackage,
+        routes::tiles::country_tile,
+        routes::tagging::tag_stream,
+        routes::jobs::submit_job,
+        routes::jobs::get_job,
+        routes::events::list_events,
+        routes::monitors::create_monitor,
+        routes::monitors::list_monitors,
+        routes::monitors::get_monitor,
+        routes::monitors::update_monitor,
+        routes::monitors::delete_monitor,
+        routes::monitors::monitor_snapshots,
+        routes::monitors::monitor_alerts,
+        routes::graphql::graphql,
     ),
     components(schemas(
         models::RootPayload, models::TableRowCount,
         models::PointQuery, models::PopulationQuery, models::PointPayload,
-        models::BatchQuery, models::BatchPayload,
+        models::BatchQuery, models::BatchPayload, models::DensityRankPayload,
         models::PopulationGridPayload, models::GridCell, models::CellBounds,
-        models::HealthPayload, models::ReversePayload,
-        models::ExposureQuery, models::ExposurePayload,
+        models::PopulationGridQuery, models::PopulationBboxPayload, models::CellPayload,
+        models::Aggregation, models::CellIndexQuery, models::HexCell, models::HexGridPayload,
+        models::H3CellPayload, models::S2Cell, models::S2GridPayload, models::S2CellPayload,
+        models::PopulationChangeQuery, models::PopulationChangePayload, models::CellChange,
+        models::PopulationInterval, models::AggregateInterval,
+        models::IncludedMeta, models::DataSource, models::Timings, models::StageTiming,
+        models::HealthPayload, models::PoolStatus, models::DatabaseStatus, models::ReversePayload, models::ReverseMode,
+        models::AdminStatsPayload, models::CacheHitRates, models::TableUsage, models::IndexUsage,
+        models::EndpointCounter, models::CacheFlushPayload, models::CacheFlushed,
+        models::ExposureQuery, models::ExposurePayload, models::DecayModel,
+        models::ExposureMode, models::Dataset, models::CoastalExposure, models::DistanceRing,
+        models::DensityBucket, models::CountryExposure,
         models::ExposurePlacesQuery, models::ExposurePlacesPayload,
-        models::ExposedPlace, models::CoordinateInfo,
+        models::CompareQuery, models::ComparePayload,
+        models::PlaceSort, models::SortOrder, models::PlaceFilterQuery, models::SearchRadiusQuery, models::LocalTime,
+        models::EpicentreTerrain, models::TerrainClass, models::SettlementClass, models::SettlementExposure, models::BuiltUpExposure, models::NightLightsExposure, models::LandCoverClass, models::LandCoverShare,
+        models::HazardQuery, models::HazardTemplate, models::HazardScenario,
+        models::AdminLevel, models::AdminGroup,
+        models::ExposedPlace, models::CoordinateInfo, models::InputCoordinate,
+        models::CycloneQuery, models::WindRadii, models::QuadrantRadii,
+        models::CyclonePayload, models::WindSwath,
+        models::AdminHierarchyPayload, models::AdminCountry, models::AdminArea,
+        models::AdminExposureQuery, models::AdminExposurePayload, models::AdminPlace,
+        models::BufferQuery, models::BufferPayload,
+        models::FloodQuery, models::FloodPayload, models::FloodPartExposure,
+        models::IsochroneQuery, models::IsochronePayload, models::TravelMode,
+        models::ReportFormat, models::ExportFormat, models::ExportLayer, models::GeoPackageQuery,
+        models::ShakeMapQuery, models::ShakeMapPayload, models::MmiContourExposure,
+        models::JobSubmission, models::JobKind, models::JobPayload, models::JobStatus,
+        models::JobCallback, models::CallbackStatus,
+        models::EventsQuery, models::AlertLevel, models::EventPayload, models::EventListPayload,
+        models::MonitorRequest, models::SnapshotQuery, models::MonitorPayload, models::MonitorListPayload,
+        models::MonitorSnapshot, models::MonitorSnapshotsPayload,
+        models::AlertThreshold, models::ThresholdMetric, models::MonitorAlert, models::MonitorAlertsPayload,
+        models::EpicentreEvent, models::ProbeEvent, models::StreamError,
         models::AnalysePayload, models::NearestPlace, models::PopulationSummary,
         models::NearbyCountryEntry, models::NearbyCountriesPayload,
         models::LandCheckPayload, models::NearbyCitiesPayload,
-        models::CountryPayload, models::CountryDetailPayload,
-        models::ContinentQuery, models::CountryListPayload,
+        models::CountryPayload, models::CountryDetailPayload, models::CountryPopulationPayload,
+        models::EezPayload, models::EezZone, models::EezClaimant,
+        models::ContinentQuery, models::Sovereignty, models::CountryListPayload, models::BoundaryQuery, models::YearQuery,
+        models::ContinentStats, models::ContinentListPayload,
         models::CitySearchQuery, models::CitySearchPayload, models::CityHit,
+        models::FacilitiesQuery, models::FacilityType, models::FacilitiesPayload, models::Facility,
+        models::FacilitiesWithinQuery, models::FacilitiesWithinPayload, models::FacilityGroup,
+        models::AirportsQuery, models::AirportsPayload, models::Airport, models::AirportType, models::Runway,
+        models::DistanceQuery, models::DistancePayload,
+        models::RouteSampleQuery, models::RouteSamplePayload, models::RouteSample,
+        models::TagPoint, models::TaggedPoint, models::TagLineError, models::TagStreamLine,
     )),
+    modifiers(&IncludeDocs),
     tags(
         (name = "System", description = "Health and status"),
         (name = "Population", description = "WorldPop 1km grid lookups"),
         (name = "Geocoding", description = "Reverse geocoding via GeoNames"),
         (name = "Risk Assessment", description = "Population exposure analysis"),
         (name = "Country", description = "Country lookup via Natural Earth"),
+        (name = "Export", description = "Layers of an area in GIS file formats"),
+        (name = "Enrichment", description = "Streaming coordinate tagging for telemetry pipelines"),
+        (name = "Jobs", description = "Background execution of long-running analyses"),
+        (name = "Events", description = "Disaster events from the GDACS feed, analysed as they arrive"),
+        (name = "Monitors", description = "Locations whose exposure is recomputed periodically"),
+        (name = "GraphQL", description = "Combined lookups with client-selected fields"),
     )
 )]
 struct ApiDoc;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info"))
-        .format_timestamp_secs()
-        .init();
+    logging::init();
     let cfg = config::Config::from_env();
 
-    let pg_config: tokio_postgres::Config = cfg.database_url
-        .parse()
-        .expect("invalid DATABASE_URL");
-
-    let mut pool_cfg = PgConfig::new();
-    if let Some(host) = pg_config.get_hosts().first() {
-        match host {
-            tokio_postgres::config::Host::Tcp(h) => pool_cfg.host = Some(h.clone()),
-            #[cfg(unix)]
-            tokio_postgres::config::Host::Unix(p) => pool_cfg.host = Some(p.to_string_lossy().into()),
-        }
+    let command = env::args().nth(1);
+    if let Some(cmd) = command.as_deref().filter(|c| *c != "migrate") {
+        eprintln!("unknown command `{cmd}`\n\nUsage: geopop-api [migrate]");
+        std::process::exit(2);
     }
-    if let Some(port) = pg_config.get_ports().first() { pool_cfg.port = Some(*port); }
-    if let Some(user) = pg_config.get_user() { pool_cfg.user = Some(user.into()); }
-    if let Some(pw) = pg_config.get_password() { pool_cfg.password = Some(String::from_utf8_lossy(pw).into()); }
-    if let Some(db) = pg_config.get_dbname() { pool_cfg.dbname = Some(db.into()); }
 
-    pool_cfg.manager = Some(ManagerConfig { recycling_method: RecyclingMethod::Fast });
-    let mut pool_config = PoolConfig::new(cfg.pool_size);
-    pool_config.timeouts = Timeouts {
-        wait: Some(std::time::Duration::from_secs(5)),
-        create: Some(std::time::Duration::from_secs(5)),
-        recycle: Some(std::time::Duration::from_secs(5)),
+    let pool = create_pool(&cfg.database_url, cfg.pool_size, "DATABASE_URL");
+    let read_pool = if cfg.database_read_url.is_empty() {
+        pool.clone()
+    } else {
+        log::info!("Routing repository reads to DATABASE_READ_URL");
+        create_pool(&cfg.database_read_url, cfg.pool_size, "DATABASE_READ_URL")
     };
-    pool_cfg.pool = Some(pool_config);
 
-    let ssl_mode = DbSslMode::from_database_url(&cfg.database_url);
-    let pool = if ssl_mode == DbSslMode::Disable {
-        log::warn!("Database TLS mode: disabled (sslmode=disable)");
-        pool_cfg
-            .create_pool(Some(Runtime::Tokio1), NoTls)
-            .expect("failed to create database connection pool")
-    } else {
-        let mut tls_builder = TlsConnector::builder();
-        if matches!(ssl_mode, DbSslMode::Require | DbSslMode::Prefer) {
-            // Match libpq `sslmode=require`: encrypt traffic but skip cert/hostname checks.
-            tls_builder.danger_accept_invalid_certs(true);
-            tls_builder.danger_accept_invalid_hostnames(true);
+    if command.is_some() || cfg.migrate_on_start {
+        if let Err(e) = migrations::run(&pool).await {
+            log::error!("{e}");
+            std::process::exit(1);
         }
-        add_ssl_root_cert_if_present(&cfg.database_url, &mut tls_builder);
-
-        let native_tls = tls_builder
-            .build()
-            .expect("failed to initialize TLS connector");
-        let tls = MakeTlsConnector::new(native_tls);
-        log::info!("Database TLS mode: {}", ssl_mode.as_str());
-        pool_cfg
-            .create_pool(Some(Runtime::Tokio1), tls)
-            .expect("failed to create TLS database connection pool")
-    };
+    }
+    if command.is_some() {
+        return Ok(());
+    }
 
     let bind = format!("{}:{}", cfg.host, cfg.port);
     log::info!("Starting GeoPop API on {bind}");
@@ -151,42 +264,97 @@ async fn main() -> std::io::Result<()> {
 
     let mut openapi = ApiDoc::openapi();
     openapi.servers = Some(vec![Server::new("/"), Server::new(API_PREFIX)]);
+    cfg.features.prune(&mut openapi);
+    let disabled = cfg.features.disabled();
+    if !disabled.is_empty() {
+        log::info!("Disabled route groups: {}", disabled.join(", "));
+    }
 
     let openapi_url: &'static str = Box::leak(format!("{API_PREFIX}/openapi.json").into_boxed_str());
     let docs_path: &'static str = Box::leak(format!("{API_PREFIX}/docs/{{_:.*}}").into_boxed_str());
 
     let api_key = cfg.api_key.clone();
+    let job_queue = jobs::spawn_workers(
+        pool.clone(),
+        read_pool.clone(),
+        cfg.job_workers,
+        cfg.job_timeout_secs,
+        Webhooks::new(&cfg.webhook_secret, cfg.webhook_allowed_hosts.clone()),
+        cfg.exposure_backend,
+        cfg.limits.clone(),
+    );
+    gdacs::spawn_poller(pool.clone(), &cfg, job_queue.clone());
+    if cfg.job_workers > 0 {
+        let scheduled = Repositories::postgres(&read_pool, cfg.job_timeout_secs, cfg.exposure_backend);
+        monitors::spawn_scheduler(pool.clone(), scheduled.population, job_queue.webhooks().clone());
+    }
+
+    let repos = Repositories::postgres(&read_pool, cfg.limits.request_timeout_secs, cfg.exposure_backend);
+    let schema = graphql::build_schema(repos.clone(), cfg.limits.clone(), cfg.features);
+    let routing = routing::RoutingEngine::new(&cfg.routing_url);
+    if routing.is_some() {
+        log::info!("Routing engine: {}", cfg.routing_url);
+    }
+    let shakemap = shakemap::ShakeMapSource::new(&cfg.shakemap_url);
+    if cfg.exposure_backend == ExposureBackend::Raster {
+        log::info!("Exposure backend: raster tiles for large radii");
+    }
+    let audit = if cfg.audit_log { AuditLog::spawn(pool.clone()) } else { AuditLog::disabled() };
+    let counters = Arc::new(RequestCounters::default());
+    let etags = Arc::new(TagCache::default());
+    let tag_streams = Arc::new(Semaphore::new(cfg.limits.max_tag_streams));
+    let state = web::Data::new(AppState {
+        pool: pool.clone(),
+        read_pool,
+        config: cfg,
+        jobs: job_queue,
+        routing,
+        shakemap,
+        counters: counters.clone(),
+        caches: vec![etags.clone()],
+        tag_streams,
+    });
 
     HttpServer::new(move || {
         App::new()
-            .wrap(
-                Logger::new(r#"%a "%r" %s %b %Dms "%{User-Agent}i""#)
-                    .exclude("/api/v1/health"),
-            )
+            .wrap(EnvelopeOptOut)
+            .wrap(CountRequests::new(counters.clone()))
+            .wrap(AccessLog)
             .wrap(Cors::permissive())
             // API key auth: runs AFTER logger/CORS so rejected requests are still
             // logged and CORS preflight keeps working for browsers. The middleware
             // has a built-in allowlist for root, health, docs, and openapi.json.
             .wrap(ApiKeyAuth::new(api_key.clone()))
-            .app_data(web::Data::new(pool.clone()))
+            // Outermost, so requests the key check rejects are audited too.
+            .wrap(audit.clone())
+            .app_data(state.clone())
+            .app_data(web::Data::new(repos.clone()))
+            .app_data(web::Data::from(repos.population.clone()))
+            .app_data(web::Data::from(repos.geocoding.clone()))
+            .app_data(web::Data::from(repos.countries.clone()))
+            .app_data(web::Data::new(schema.clone()))
+            .app_data(state.config.limits.json_config())
             .route("/", web::get().to(routes::root::root))
             .service(SwaggerUi::new(docs_path).url(openapi_url, openapi.clone()))
             .service(
                 web::scope(API_PREFIX)
-                    .route("/health", web::get().to(routes::health::health))
-                    .route("/population", web::get().to(routes::population::get_population))
-                    .route("/population/batch", web::post().to(routes::population::batch_population))
-                    .route("/reverse", web::get().to(routes::geocoding::reverse_geocode))
-                    .route("/geocoding/nearby-countries", web::get().to(routes::geocoding::nearby_countries))
-                    .route("/geocoding/nearby-cities", web::get().to(routes::geocoding::nearby_cities))
-                    .route("/geocoding/land-check", web::get().to(routes::geocoding::land_check))
-                    .route("/cities/search", web::get().to(routes::geocoding::search_cities))
-                    .route("/exposure/places", web::get().to(routes::exposure::exposure_places))
-                    .route("/exposure", web::get().to(routes::exposure::exposure))
-                    .route("/analyse", web::get().to(routes::analyse::analyse))
-                    .route("/country", web::get().to(routes::country::country_lookup))
-                    .route("/country/{iso3}", web::get().to(routes::country::country_by_iso3))
-                    .route("/countries", web::get().to(routes::country::countries_by_continent))
+                    .wrap(CrsInput)
+                    .wrap(StatementTimeout::new(state.config.limits.clone()))
+                    .wrap(ConditionalGet::new(
+                        std::time::Duration::from_secs(state.config.etag_ttl_secs),
+                        etags.clone(),
+                    ))
+                    .configure(|cfg| routes::configure(cfg, &state.config.features)),
+            )
+            .service(
+                web::scope(API_V2_PREFIX)
+                    .wrap(CrsInput)
+                    .wrap(StatementTimeout::new(state.config.limits.clone()))
+                    .wrap(ConditionalGet::new(
+                        std::time::Duration::from_secs(state.config.etag_ttl_secs),
+                        etags.clone(),
+                    ))
+                    .configure(|cfg| routes::v2::configure(cfg, &state.config.features)),
             )
     })
     .bind(&bind)?
@@ -242,6 +410,61 @@ fn extract_query_param(database_url: &str, key: &str) -> Option<String> {
     })
 }
 
+/// Connection pool for the database at `database_url`; `var` names the
+/// setting it came from in log and panic messages.
+fn create_pool(database_url: &str, pool_size: usize, var: &str) -> Pool {
+    let pg_config: tokio_postgres::Config = database_url
+        .parse()
+        .unwrap_or_else(|e| panic!("invalid {var}: {e}"));
+
+    let mut pool_cfg = PgConfig::new();
+    if let Some(host) = pg_config.get_hosts().first() {
+        match host {
+            tokio_postgres::config::Host::Tcp(h) => pool_cfg.host = Some(h.clone()),
+            #[cfg(unix)]
+            tokio_postgres::config::Host::Unix(p) => pool_cfg.host = Some(p.to_string_lossy().into()),
+        }
+    }
+    if let Some(port) = pg_config.get_ports().first() { pool_cfg.port = Some(*port); }
+    if let Some(user) = pg_config.get_user() { pool_cfg.user = Some(user.into()); }
+    if let Some(pw) = pg_config.get_password() { pool_cfg.password = Some(String::from_utf8_lossy(pw).into()); }
+    if let Some(db) = pg_config.get_dbname() { pool_cfg.dbname = Some(db.into()); }
+
+    pool_cfg.manager = Some(ManagerConfig { recycling_method: RecyclingMethod::Fast });
+    let mut pool_config = PoolConfig::new(pool_size);
+    pool_config.timeouts = Timeouts {
+        wait: Some(std::time::Duration::from_secs(5)),
+        create: Some(std::time::Duration::from_secs(5)),
+        recycle: Some(std::time::Duration::from_secs(5)),
+    };
+    pool_cfg.pool = Some(pool_config);
+
+    let ssl_mode = DbSslMode::from_database_url(database_url);
+    if ssl_mode == DbSslMode::Disable {
+        log::warn!("Database TLS mode for {var}: disabled (sslmode=disable)");
+        pool_cfg
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .expect("failed to create database connection pool")
+    } else {
+        let mut tls_builder = TlsConnector::builder();
+        if matches!(ssl_mode, DbSslMode::Require | DbSslMode::Prefer) {
+            // Match libpq `sslmode=require`: encrypt traffic but skip cert/hostname checks.
+            tls_builder.danger_accept_invalid_certs(true);
+            tls_builder.danger_accept_invalid_hostnames(true);
+        }
+        add_ssl_root_cert_if_present(database_url, &mut tls_builder);
+
+        let native_tls = tls_builder
+            .build()
+            .expect("failed to initialize TLS connector");
+        let tls = MakeTlsConnector::new(native_tls);
+        log::info!("Database TLS mode for {var}: {}", ssl_mode.as_str());
+        pool_cfg
+            .create_pool(Some(Runtime::Tokio1), tls)
+            .expect("failed to create TLS database connection pool")
+    }
+}
+
 fn add_ssl_root_cert_if_present(database_url: &str, tls_builder: &mut native_tls::TlsConnectorBuilder) {
     let cert_path = extract_query_param(database_url, "sslrootcert")
         .or_else(|| env::var("PGSSLROOTCERT").ok())
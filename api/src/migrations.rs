@@ -0,0 +1,28 @@
+use deadpool_postgres::Pool;
+
+mod embedded {
+    // Compiles every `api/migrations/V{n}__{name}.sql` into the binary.
+    refinery::embed_migrations!("migrations");
+}
+
+/// Applies any pending embedded migrations. Progress is tracked by refinery in
+/// `refinery_schema_history`, so this is a no-op on an up-to-date database.
+pub(crate) async fn run(pool: &Pool) -> Result<(), String> {
+    let mut client = pool
+        .get()
+        .await
+        .map_err(|e| format!("failed to get a database connection: {e}"))?;
+    let report = embedded::migrations::runner()
+        .run_async(&mut **client)
+        .await
+        .map_err(|e| format!("migration failed: {e}"))?;
+
+    let applied = report.applied_migrations();
+    if applied.is_empty() {
+        log::info!("Database schema is up to date");
+    }
+    for migration in applied {
+        log::info!("Applied migration {migration}");
+    }
+    Ok(())
+}
@@ -1,5 +1,4 @@
-pub mod requests;
-pub mod responses;
+//! Request and response types live in the `geopop-types` crate so the client
+//! shares them; re-exported here under their historical path.
 
-pub use requests::*;
-pub use responses::*;
+pub use geopop_types::{requests::*, responses::*};
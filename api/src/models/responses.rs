@@ -1,510 +0,0 @@
-use serde::Serialize;
-use std::collections::HashMap;
-use utoipa::ToSchema;
-
-/// Health check status.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({"status": "ok"}))]
-pub struct HealthPayload {
-    /// Service status indicator
-    #[schema(example = "ok")]
-    pub status: String,
-}
-
-/// Population data for a single coordinate.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "population": 28534.0, "resolution_km": 1.0}))]
-pub struct PointPayload {
-    /// Queried latitude
-    #[schema(example = 6.9271)]
-    pub lat: f64,
-    /// Queried longitude
-    #[schema(example = 79.8612)]
-    pub lon: f64,
-    /// Estimated population within the grid cell
-    #[schema(example = 28534.0)]
-    pub population: f32,
-    /// Grid cell resolution in kilometres (always 1.0 for WorldPop data)
-    #[schema(example = 1.0)]
-    pub resolution_km: f32,
-}
-
-/// Batch population results for multiple coordinates.
-#[derive(Serialize, ToSchema)]
-pub struct BatchPayload {
-    /// Array of population results for each queried point
-    pub results: Vec<PointPayload>,
-}
-
-/// Bounding box of a single population grid cell.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({"min_lat": 20.4583, "max_lat": 20.4667, "min_lon": 93.9500, "max_lon": 93.9583}))]
-pub struct CellBounds {
-    /// Southern edge latitude
-    #[schema(example = 20.4583)]
-    pub min_lat: f64,
-    /// Northern edge latitude
-    #[schema(example = 20.4667)]
-    pub max_lat: f64,
-    /// Western edge longitude
-    #[schema(example = 93.9500)]
-    pub min_lon: f64,
-    /// Eastern edge longitude
-    #[schema(example = 93.9583)]
-    pub max_lon: f64,
-}
-
-/// A single 1 km² population grid cell with its bounds for map rendering.
-#[derive(Serialize, ToSchema)]
-pub struct GridCell {
-    /// Centre latitude of the grid cell
-    #[schema(example = 20.4625)]
-    pub lat: f64,
-    /// Centre longitude of the grid cell
-    #[schema(example = 93.9542)]
-    pub lon: f64,
-    /// Estimated population within this cell
-    #[schema(example = 5.16)]
-    pub population: f32,
-    /// Geographic bounds of the cell (for rendering as a rectangle on a map)
-    pub bounds: CellBounds,
-}
-
-/// Population grid data within a radius, suitable for map visualisation.
-#[derive(Serialize, ToSchema)]
-pub struct PopulationGridPayload {
-    /// Centre coordinate of the query
-    pub coordinate: CoordinateInfo,
-    /// Search radius in kilometres
-    #[schema(example = 5.0)]
-    pub radius_km: f64,
-    /// Total population across all cells within the radius
-    #[schema(example = 1653.2)]
-    pub total_population: f64,
-    /// Number of non-empty grid cells returned
-    #[schema(example = 42)]
-    pub cell_count: usize,
-    /// Individual grid cells with population > 0
-    pub cells: Vec<GridCell>,
-}
-
-/// Reverse geocoding result — nearest named place to the queried coordinate.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({
-    "place_id": 1234,
-    "lat": "6.9271",
-    "lon": "79.8612",
-    "name": "Colombo",
-    "display_name": "Colombo, Western Province, Sri Lanka",
-    "address": {"city": "Colombo", "state": "Western Province", "country": "Sri Lanka"}
-}))]
-pub struct ReversePayload {
-    /// GeoNames place identifier
-    #[schema(example = 1234)]
-    pub place_id: i32,
-    /// Latitude of the matched place
-    #[schema(example = "6.9271")]
-    pub lat: String,
-    /// Longitude of the matched place
-    #[schema(example = "79.8612")]
-    pub lon: String,
-    /// Place name
-    #[schema(example = "Colombo")]
-    pub name: String,
-    /// Full display name including administrative hierarchy
-    #[schema(example = "Colombo, Western Province, Sri Lanka")]
-    pub display_name: String,
-    /// Structured address components (city, state, country, etc.)
-    pub address: HashMap<String, String>,
-}
-
-/// A named place within the exposure search radius.
-#[derive(Serialize, ToSchema)]
-pub struct ExposedPlace {
-    /// GeoNames place identifier
-    #[schema(example = 1234)]
-    pub place_id: i32,
-    /// Latitude of the place
-    #[schema(example = "6.9271")]
-    pub lat: String,
-    /// Longitude of the place
-    #[schema(example = "79.8612")]
-    pub lon: String,
-    /// Place name
-    #[schema(example = "Colombo")]
-    pub name: String,
-    /// Full display name
-    #[schema(example = "Colombo, Western Province, Sri Lanka")]
-    pub display_name: String,
-    /// Structured address components (city, district, state, country, country_code)
-    pub address: HashMap<String, String>,
-    /// Distance from the epicentre in kilometres
-    #[schema(example = 3.2)]
-    pub distance_km: f64,
-    /// Compass direction from the epicentre (N, NE, E, SE, S, SW, W, NW)
-    #[schema(example = "SW")]
-    pub direction: String,
-    /// Bearing from the epicentre in degrees (0 = North, 90 = East, 180 = South, 270 = West)
-    #[schema(example = 225.3)]
-    pub bearing_deg: f64,
-}
-
-/// Coordinate pair used in exposure results.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({"lat": 6.9271, "lon": 79.8612}))]
-pub struct CoordinateInfo {
-    /// Latitude
-    #[schema(example = 6.9271)]
-    pub lat: f64,
-    /// Longitude
-    #[schema(example = 79.8612)]
-    pub lon: f64,
-}
-
-/// Comprehensive population exposure analysis for a circular area.
-#[derive(Serialize, ToSchema)]
-pub struct ExposurePayload {
-    /// Centre coordinate of the analysis area
-    pub coordinate: CoordinateInfo,
-    /// Search radius in kilometres
-    #[schema(example = 10.0)]
-    pub radius_km: f64,
-    /// Total estimated population within the radius
-    #[schema(example = 456789.0)]
-    pub total_population: f64,
-    /// Area of the search circle in km²
-    #[schema(example = 314.16)]
-    pub area_km2: f64,
-    /// Average population density (people/km²) within the radius
-    #[schema(example = 1454.1)]
-    pub density_per_km2: f64,
-    /// Population in the 1km grid cell at the centre coordinate
-    #[schema(example = 28534.0)]
-    pub cell_population: f32,
-    /// Area of the centre grid cell in km²
-    #[schema(example = 0.77)]
-    pub cell_area_km2: f64,
-    /// Population density of the centre grid cell (people/km²)
-    #[schema(example = 37057.1)]
-    pub cell_density_per_km2: f64,
-    /// Number of named places within the search radius (use /exposure/places for details)
-    #[schema(example = 121)]
-    pub place_count: i64,
-}
-
-/// Paginated list of named places within an exposure radius.
-#[derive(Serialize, ToSchema)]
-pub struct ExposurePlacesPayload {
-    /// Centre coordinate of the search
-    pub coordinate: CoordinateInfo,
-    /// Search radius in kilometres
-    #[schema(example = 10.0)]
-    pub radius_km: f64,
-    /// Total number of places within the radius
-    #[schema(example = 121)]
-    pub total_places: i64,
-    /// Current page number (1-indexed)
-    #[schema(example = 1)]
-    pub page: i64,
-    /// Number of results per page
-    #[schema(example = 20)]
-    pub per_page: i64,
-    /// Places on this page, ordered by distance from centre
-    pub places: Vec<ExposedPlace>,
-}
-
-/// Country information from Natural Earth boundaries.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({
-    "iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka",
-    "formal_name": "Democratic Socialist Republic of Sri Lanka",
-    "continent": "Asia", "region": "Asia", "subregion": "Southern Asia"
-}))]
-pub struct CountryPayload {
-    /// ISO 3166-1 alpha-2 code
-    #[schema(example = "LK")]
-    pub iso_a2: Option<String>,
-    /// ISO 3166-1 alpha-3 code
-    #[schema(example = "LKA")]
-    pub iso_a3: Option<String>,
-    /// Country common name
-    #[schema(example = "Sri Lanka")]
-    pub name: String,
-    /// Country formal/official name
-    #[schema(example = "Democratic Socialist Republic of Sri Lanka")]
-    pub formal_name: Option<String>,
-    /// Continent
-    #[schema(example = "Asia")]
-    pub continent: String,
-    /// World region
-    #[schema(example = "Asia")]
-    pub region: Option<String>,
-    /// World sub-region
-    #[schema(example = "Southern Asia")]
-    pub subregion: Option<String>,
-}
-
-/// Detailed country information including population estimate and bounding box.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({
-    "iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka",
-    "formal_name": "Democratic Socialist Republic of Sri Lanka",
-    "continent": "Asia", "region": "Asia", "subregion": "Southern Asia",
-    "pop_est": 21670000, "bbox": [79.6952, 5.9169, 81.8813, 9.8354]
-}))]
-pub struct CountryDetailPayload {
-    /// ISO 3166-1 alpha-2 code
-    #[schema(example = "LK")]
-    pub iso_a2: Option<String>,
-    /// ISO 3166-1 alpha-3 code
-    #[schema(example = "LKA")]
-    pub iso_a3: Option<String>,
-    /// Country common name
-    #[schema(example = "Sri Lanka")]
-    pub name: String,
-    /// Country formal/official name
-    #[schema(example = "Democratic Socialist Republic of Sri Lanka")]
-    pub formal_name: Option<String>,
-    /// Continent
-    #[schema(example = "Asia")]
-    pub continent: String,
-    /// World region
-    #[schema(example = "Asia")]
-    pub region: Option<String>,
-    /// World sub-region
-    #[schema(example = "Southern Asia")]
-    pub subregion: Option<String>,
-    /// Estimated population
-    #[schema(example = 21670000)]
-    pub pop_est: Option<i64>,
-    /// Bounding box [min_lon, min_lat, max_lon, max_lat]
-    #[schema(example = json!([79.6952, 5.9169, 81.8813, 9.8354]))]
-    pub bbox: [f64; 4],
-}
-
-/// A country entry with distance from a search coordinate.
-#[derive(Serialize, ToSchema)]
-pub struct NearbyCountryEntry {
-    #[serde(flatten)]
-    pub country: CountryPayload,
-    /// Distance from the search coordinate to the nearest country border in km (0 if inside)
-    #[schema(example = 0.0)]
-    pub distance_km: f64,
-}
-
-/// Countries found within a radius of a coordinate.
-#[derive(Serialize, ToSchema)]
-pub struct NearbyCountriesPayload {
-    pub coordinate: CoordinateInfo,
-    #[schema(example = 50.0)]
-    pub radius_km: f64,
-    /// Whether the coordinate itself is on land (inside a country polygon)
-    #[schema(example = true)]
-    pub is_land: bool,
-    pub countries: Vec<NearbyCountryEntry>,
-}
-
-/// Land/sea check result for a coordinate.
-#[derive(Serialize, ToSchema)]
-pub struct LandCheckPayload {
-    pub coordinate: CoordinateInfo,
-    /// true if the coordinate is on land, false if at sea
-    #[schema(example = true)]
-    pub is_land: bool,
-    /// The country containing the coordinate (null if at sea)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub country: Option<CountryPayload>,
-}
-
-/// Paginated list of nearby cities/places within a radius.
-#[derive(Serialize, ToSchema)]
-pub struct NearbyCitiesPayload {
-    pub coordinate: CoordinateInfo,
-    #[schema(example = 10.0)]
-    pub radius_km: f64,
-    #[schema(example = 158)]
-    pub total_places: i64,
-    #[schema(example = 1)]
-    pub page: i64,
-    #[schema(example = 20)]
-    pub per_page: i64,
-    pub places: Vec<ExposedPlace>,
-}
-
-/// List of countries belonging to a continent.
-#[derive(Serialize, ToSchema)]
-pub struct CountryListPayload {
-    /// Queried continent name
-    #[schema(example = "asia")]
-    pub continent: String,
-    /// Number of countries returned
-    #[schema(example = 49)]
-    pub count: usize,
-    /// Country list
-    pub countries: Vec<CountryPayload>,
-}
-
-/// Nearest named place to the epicentre with distance and direction.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({
-    "place_id": 1325189, "name": "Hetsaw",
-    "display_name": "Hetsaw, Kyaunkpyu District, Rakhine, Myanmar",
-    "address": {"city": "Hetsaw", "district": "Kyaunkpyu District", "state": "Rakhine", "country": "Myanmar", "country_code": "mm"},
-    "distance_km": 4.69, "direction": "SW", "bearing_deg": 233.3
-}))]
-pub struct NearestPlace {
-    /// GeoNames place identifier
-    #[schema(example = 1325189)]
-    pub place_id: i32,
-    /// Place name
-    #[schema(example = "Hetsaw")]
-    pub name: String,
-    /// Full display name including administrative hierarchy
-    #[schema(example = "Hetsaw, Kyaunkpyu District, Rakhine, Myanmar")]
-    pub display_name: String,
-    /// Structured address components (city, district, state, country, country_code)
-    pub address: HashMap<String, String>,
-    /// Distance from the epicentre in kilometres
-    #[schema(example = 4.69)]
-    pub distance_km: f64,
-    /// Compass direction from the epicentre (N, NE, E, SE, S, SW, W, NW)
-    #[schema(example = "SW")]
-    pub direction: String,
-    /// Bearing from the epicentre in degrees (0 = North, 90 = East)
-    #[schema(example = 233.3)]
-    pub bearing_deg: f64,
-}
-
-/// Population summary found via auto-expanding radius search.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({"search_radius_km": 5.0, "total_population": 426.0, "area_km2": 78.54, "density_per_km2": 5.4, "epicentre_population": 5.16}))]
-pub struct PopulationSummary {
-    /// Radius (km) at which population was found (indicates remoteness)
-    #[schema(example = 5.0)]
-    pub search_radius_km: f64,
-    /// Total population within the search radius
-    #[schema(example = 426.0)]
-    pub total_population: f64,
-    /// Area of the search circle in km²
-    #[schema(example = 78.54)]
-    pub area_km2: f64,
-    /// Average population density (people/km²) within the search radius
-    #[schema(example = 5.4)]
-    pub density_per_km2: f64,
-    /// Population at the exact epicentre grid cell (0 if ocean/desert)
-    #[schema(example = 5.16)]
-    pub epicentre_population: f32,
-}
-
-/// Comprehensive disaster impact analysis for a coordinate.
-#[derive(Serialize, ToSchema)]
-pub struct AnalysePayload {
-    /// Epicentre coordinate
-    pub coordinate: CoordinateInfo,
-    /// Whether the coordinate is on land (true) or at sea (false)
-    #[schema(example = true)]
-    pub is_land: bool,
-    /// Country the epicentre is in (or nearest to, if in ocean)
-    pub country: CountryPayload,
-    /// Nearest named place from GeoNames with distance and direction
-    pub nearest_place: NearestPlace,
-    /// Population summary from auto-expanding radius search
-    pub population: PopulationSummary,
-}
-
-/// A single city search hit returned by /cities/search.
-#[derive(Serialize, ToSchema)]
-#[schema(example = json!({
-    "place_id": 1248991,
-    "name": "Colombo",
-    "display_name": "Colombo, Western Province, Sri Lanka",
-    "country_code": "LK",
-    "country": "Sri Lanka",
-    "admin1": "Western Province",
-    "admin2": "Colombo District",
-    "feature_code": "PPLC",
-    "lat": 6.9319,
-    "lon": 79.8478,
-    "population": 648034,
-    "score": 1.0,
-    "bbox": [79.8028, 6.8819, 79.8928, 6.9819]
-}))]
-pub struct CityHit {
-    /// GeoNames place identifier
-    #[schema(example = 1248991)]
-    pub place_id: i32,
-    /// Matched place name
-    #[schema(example = "Colombo")]
-    pub name: String,
-    /// Full display name including administrative hierarchy
-    #[schema(example = "Colombo, Western Province, Sri Lanka")]
-    pub display_name: String,
-    /// ISO 3166-1 alpha-2 country code
-    #[schema(example = "LK")]
-    pub country_code: Option<String>,
-    /// Country common name
-    #[schema(example = "Sri Lanka")]
-    pub country: Option<String>,
-    /// First-order administrative division (state / province / region)
-    #[schema(example = "Western Province")]
-    pub admin1: Option<String>,
-    /// Second-order administrative division (district / county)
-    #[schema(example = "Colombo District")]
-    pub admin2: Option<String>,
-    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
-    #[schema(example = "PPLC")]
-    pub feature_code: Option<String>,
-    /// Latitude of the place centroid
-    #[schema(example = 6.9319)]
-    pub lat: f64,
-    /// Longitude of the place centroid
-    #[schema(example = 79.8478)]
-    pub lon: f64,
-    /// Population estimate from GeoNames (0 if unknown)
-    #[schema(example = 648034)]
-    pub population: i64,
-    /// Match quality score in [0, 1]. 1.0 = exact, 0.9 = prefix, <0.9 = fuzzy similarity.
-    #[schema(example = 1.0)]
-    pub score: f64,
-    /// Synthesized bounding box [min_lon, min_lat, max_lon, max_lat] scaled from population.
-    /// Use this as a fallback when no true boundary polygon is available.
-    #[schema(example = json!([79.8028, 6.8819, 79.8928, 6.9819]))]
-    pub bbox: [f64; 4],
-}
-
-/// City search result set.
-#[derive(Serialize, ToSchema)]
-pub struct CitySearchPayload {
-    /// Echoed search term
-    #[schema(example = "colom")]
-    pub query: String,
-    /// Echoed country filter (uppercase, if provided)
-    #[schema(example = "LK")]
-    pub country: Option<String>,
-    /// Number of results returned
-    #[schema(example = 3)]
-    pub count: usize,
-    /// Matching cities, ordered by score then population
-    pub results: Vec<CityHit>,
-}
-
-/// Root endpoint payload: health, docs link, and database stats.
-#[derive(Serialize, ToSchema)]
-pub struct RootPayload {
-    #[schema(example = "tg-geo-pop")]
-    pub service: String,
-    #[schema(example = "ok")]
-    pub status: String,
-    #[schema(example = "/api/v1/docs/")]
-    pub docs_url: String,
-    #[schema(example = "/api/v1/health")]
-    pub health_url: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tables: Option<Vec<TableRowCount>>,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct TableRowCount {
-    pub name: String,
-    pub estimated_rows: i64,
-}
@@ -0,0 +1,284 @@
+//! Periodic exposure snapshots of `/monitors`.
+//!
+//! Every instance with job workers also runs one scheduler. It claims monitors
+//! whose next snapshot is due from the `monitors` table, sums the population
+//! in their area with the job query timeout, and appends the result to
+//! `monitor_snapshots`. A failed run is retried after a short delay instead of
+//! waiting a whole interval.
+//!
+//! Each snapshot is compared with the monitor's `population` and `change`
+//! thresholds; GDACS events recorded inside a monitored area are checked
+//! against its `event_exposure` thresholds as they arrive. Breaches are
+//! stored in `monitor_alerts` and, when a webhook secret is configured,
+//! POSTed to the monitor's `webhook_url` like job callbacks.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool_postgres::{Object, Pool};
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::jobs::{self, CALLBACK_BATCH, CALLBACK_LEASE_SECS, CALLBACK_TIMEOUT, MAX_CALLBACK_ATTEMPTS};
+use crate::models::{AlertThreshold, MonitorAlert, ThresholdMetric};
+use crate::repositories::{
+    AlertRepository, DueMonitor, EventCheck, MonitorArea, MonitorRepository, PopulationRepository,
+};
+use crate::webhook::{self, Webhooks};
+
+/// How often the scheduler looks for due monitors.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Monitors claimed per round.
+const BATCH: i64 = 8;
+/// Delay before a failed run is retried.
+const RETRY_SECS: f64 = 900.0;
+
+/// Starts the snapshot scheduler, plus an alert sender when a webhook secret
+/// is configured.
+pub(crate) fn spawn_scheduler(pool: Pool, population: Arc<dyn PopulationRepository>, webhooks: Webhooks) {
+    if webhooks.enabled() {
+        tokio::spawn(deliver_alerts(pool.clone(), webhooks));
+    }
+    tokio::spawn(async move {
+        loop {
+            check_events(&pool, &*population).await;
+            let due = match pool.get().await {
+                Ok(client) => MonitorRepository::claim_due(&client, BATCH).await,
+                Err(e) => Err(AppError::from(e)),
+            };
+            let due = match due {
+                Ok(due) => due,
+                Err(e) => {
+                    log::error!("monitor scheduler: failed to claim monitors: {e}");
+                    Vec::new()
+                }
+            };
+            if due.is_empty() {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            for monitor in due {
+                snapshot(&pool, &*population, monitor).await;
+            }
+        }
+    });
+}
+
+async fn snapshot(pool: &Pool, population: &dyn PopulationRepository, monitor: DueMonitor) {
+    let outcome = population_in(population, &monitor.area).await;
+    let recorded = match pool.get().await {
+        Ok(client) => match &outcome {
+            Ok(total) => {
+                let recorded = MonitorRepository::record(&client, monitor.id, *total).await;
+                for (threshold, value) in breaches(&monitor.thresholds, monitor.previous, *total) {
+                    if let Err(e) = AlertRepository::create(&client, monitor.id, threshold, value, None).await {
+                        log::error!("monitor {}: failed to record alert: {e}", monitor.id);
+                    }
+                }
+                recorded
+            }
+            Err(e) => {
+                log::warn!("monitor {}: snapshot failed: {e}", monitor.id);
+                MonitorRepository::fail(&client, monitor.id, e.public_message(), RETRY_SECS).await
+            }
+        },
+        Err(e) => Err(AppError::from(e)),
+    };
+    if let Err(e) = recorded {
+        log::error!("monitor {}: failed to record snapshot: {e}", monitor.id);
+    }
+}
+
+/// Snapshot thresholds breached by `current`, each with the value that
+/// breached it. `population` fires when a snapshot first exceeds the value,
+/// `change` whenever the snapshot moved by more than it either way.
+fn breaches(thresholds: &[AlertThreshold], previous: Option<f64>, current: f64) -> Vec<(&AlertThreshold, f64)> {
+    thresholds
+        .iter()
+        .filter_map(|t| match t.metric {
+            ThresholdMetric::Population => {
+                (current > t.above && previous.is_none_or(|p| p <= t.above)).then_some((t, current))
+            }
+            ThresholdMetric::Change => {
+                let change = current - previous?;
+                (change.abs() > t.above).then_some((t, (change * 10.0).round() / 10.0))
+            }
+            ThresholdMetric::EventExposure => None,
+        })
+        .collect()
+}
+
+/// Checks events recorded since the last round against `event_exposure`
+/// thresholds of the monitors they fall in.
+async fn check_events(pool: &Pool, population: &dyn PopulationRepository) {
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("monitor scheduler: failed to check events: {e}");
+            return;
+        }
+    };
+    let checks = match MonitorRepository::claim_event_checks(&client, BATCH).await {
+        Ok(checks) => checks,
+        Err(e) => {
+            log::error!("monitor scheduler: failed to claim event checks: {e}");
+            return;
+        }
+    };
+    for check in checks {
+        if let Err(e) = check_monitor_events(&client, population, &check).await {
+            log::warn!("monitor {}: event check failed: {e}", check.id);
+        }
+    }
+}
+
+async fn check_monitor_events(
+    client: &Object,
+    population: &dyn PopulationRepository,
+    check: &EventCheck,
+) -> Result<(), AppError> {
+    let events = MonitorRepository::events_in(client, &check.area, check.since).await?;
+    for event in &events {
+        for threshold in check.thresholds.iter().filter(|t| t.metric == ThresholdMetric::EventExposure) {
+            let exposed = population.get_exposure_population(event.lat, event.lon, threshold.event_radius_km()).await?;
+            let exposed = (exposed * 10.0).round() / 10.0;
+            if exposed > threshold.above {
+                let key = (event.event_type.as_str(), event.event_id);
+                AlertRepository::create(client, check.id, threshold, exposed, Some(key)).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Population inside a monitor's area, rounded to 0.1.
+async fn population_in(
+    population: &dyn PopulationRepository,
+    area: &MonitorArea,
+) -> Result<f64, AppError> {
+    let total = match area {
+        MonitorArea::Circle { lat, lon, radius_km } => {
+            population.get_exposure_population(*lat, *lon, *radius_km).await?
+        }
+        MonitorArea::Polygon(polygon) => {
+            let bounds = geometry::polygon_bounds(polygon).map_err(AppError::Validation)?;
+            population.get_polygon_population(&polygon.to_string(), bounds).await?.0
+        }
+    };
+    Ok((total * 10.0).round() / 10.0)
+}
+
+/// Sends due alert webhooks until the process exits, with the signing and
+/// backoff of job callbacks, after the same target checks.
+async fn deliver_alerts(pool: Pool, webhooks: Webhooks) {
+    let http = match webhook::client(CALLBACK_TIMEOUT) {
+        Ok(http) => http,
+        Err(e) => {
+            log::error!("monitor alerts disabled: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+
+    loop {
+        let due = match pool.get().await {
+            Ok(client) => AlertRepository::claim_deliveries(&client, CALLBACK_LEASE_SECS, CALLBACK_BATCH).await,
+            Err(e) => Err(AppError::from(e)),
+        };
+        let due = match due {
+            Ok(due) => due,
+            Err(e) => {
+                log::error!("monitor alerts: failed to claim deliveries: {e}");
+                Vec::new()
+            }
+        };
+        if due.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        for (alert, url) in due {
+            let attempts = alert.delivery.as_ref().map_or(1, |d| d.attempts);
+            let id = alert.id;
+            let outcome = match webhooks.check(&url).await {
+                Ok(()) => send_alert(&http, webhooks.secret(), &url, alert).await,
+                Err(e) => Err(format!("webhook_url rejected: {e}")),
+            };
+            let recorded = match pool.get().await {
+                Ok(client) => match &outcome {
+                    Ok(()) => AlertRepository::delivered(&client, id).await,
+                    Err(error) => {
+                        let retry = (attempts < MAX_CALLBACK_ATTEMPTS).then(|| jobs::retry_delay_secs(attempts));
+                        log::warn!(
+                            "alert {id}: delivery attempt {attempts} failed ({error}){}",
+                            if retry.is_some() { ", will retry" } else { ", giving up" }
+                        );
+                        AlertRepository::delivery_failed(&client, id, error, retry).await
+                    }
+                },
+                Err(e) => Err(AppError::from(e)),
+            };
+            if let Err(e) = recorded {
+                log::error!("alert {id}: failed to record delivery outcome: {e}");
+            }
+        }
+    }
+}
+
+async fn send_alert(
+    http: &reqwest::Client,
+    secret: &str,
+    url: &str,
+    mut alert: MonitorAlert,
+) -> Result<(), String> {
+    alert.delivery = None;
+    let body = serde_json::to_vec(&alert).map_err(|e| e.to_string())?;
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let response = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-GeoPop-Event", "monitor.alert")
+        .header("X-GeoPop-Alert-Id", alert.id.to_string())
+        .header("X-GeoPop-Timestamp", timestamp.to_string())
+        .header("X-GeoPop-Signature", jobs::sign(secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("receiver answered {}", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(metric: ThresholdMetric, above: f64) -> AlertThreshold {
+        AlertThreshold { metric, above, radius_km: None }
+    }
+
+    #[test]
+    fn population_threshold_fires_once_when_crossed() {
+        let thresholds = [threshold(ThresholdMetric::Population, 50_000.0)];
+        assert_eq!(breaches(&thresholds, None, 55_000.0).len(), 1);
+        assert_eq!(breaches(&thresholds, Some(49_000.0), 55_000.0)[0].1, 55_000.0);
+        assert!(breaches(&thresholds, Some(52_000.0), 55_000.0).is_empty());
+        assert!(breaches(&thresholds, None, 40_000.0).is_empty());
+    }
+
+    #[test]
+    fn change_threshold_needs_a_previous_snapshot() {
+        let thresholds = [
+            threshold(ThresholdMetric::Change, 1_000.0),
+            threshold(ThresholdMetric::EventExposure, 0.0),
+        ];
+        assert!(breaches(&thresholds, None, 55_000.0).is_empty());
+        assert!(breaches(&thresholds, Some(54_500.0), 55_000.0).is_empty());
+        let fired = breaches(&thresholds, Some(57_000.0), 55_000.0);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].1, -2_000.0);
+    }
+}
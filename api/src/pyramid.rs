@@ -0,0 +1,183 @@
+//! Decomposition of an exposure circle over the rollup pyramid.
+//!
+//! `population_rollups` holds the grid summed over 1°, 0.5° and 0.1° blocks.
+//! A circle is covered from the coarsest level down: blocks whose cells all
+//! lie inside are taken whole, blocks on the edge are split into the next
+//! level, and edge blocks of the finest level leave their cells to be tested
+//! one by one. A 500 km circle comes down to a few hundred blocks and a few
+//! tens of thousands of cells instead of about a million cells.
+//!
+//! Distances use the same equirectangular approximation as the grid queries
+//! (cell centres, km per degree 111.32, longitude scaled by the cosine of the
+//! centre latitude), and the edge cells are tested with the expression their
+//! shared `cells_within` scan uses, so the pyramid counts exactly the cells
+//! they count.
+
+use crate::grid::{NCOLS, NROWS};
+
+const KM_PER_DEG: f64 = 111.32;
+
+/// Block sides in cells, coarsest first; each divides the one before.
+pub const BLOCK_SIZES: [i64; 3] = [120, 60, 12];
+
+/// A circle covered by whole blocks plus the rows of edge cells still to test.
+#[derive(Debug, Default, PartialEq)]
+pub struct Cover {
+    /// `(block_size, block_row, block_col)` of blocks wholly inside.
+    pub blocks: Vec<(i32, i32, i32)>,
+    /// `(row, first_col, last_col)` runs of cells that may be inside.
+    pub spans: Vec<(i32, i32, i32)>,
+}
+
+struct Circle {
+    lat: f64,
+    lon: f64,
+    radius_km: f64,
+    cos_lat: f64,
+}
+
+impl Circle {
+    /// Distance in km from the centre to the point (`lat`, `lon`).
+    fn distance(&self, lat: f64, lon: f64) -> f64 {
+        KM_PER_DEG * ((lat - self.lat).powi(2) + ((lon - self.lon) * self.cos_lat).powi(2)).sqrt()
+    }
+
+    /// Whether every, or no, cell centre of the rows and columns lies inside.
+    fn classify(&self, rows: (i64, i64), cols: (i64, i64)) -> Option<bool> {
+        let (north, south) = (centre_lat(rows.0), centre_lat(rows.1));
+        let (west, east) = (centre_lon(cols.0), centre_lon(cols.1));
+        let corners = [(north, west), (north, east), (south, west), (south, east)];
+        if corners.iter().all(|&(lat, lon)| self.distance(lat, lon) <= self.radius_km) {
+            return Some(true);
+        }
+        let nearest = self.distance(self.lat.clamp(south, north), self.lon.clamp(west, east));
+        (nearest > self.radius_km).then_some(false)
+    }
+}
+
+fn centre_lat(row: i64) -> f64 {
+    90.0 - (row as f64 + 0.5) / 120.0
+}
+
+fn centre_lon(col: i64) -> f64 {
+    (col as f64 + 0.5) / 120.0 - 180.0
+}
+
+/// Covers the cells within `radius_km` of (`lat`, `lon`) — the cells the grid
+/// exposure query counts — with pyramid blocks and edge spans.
+pub fn cover(lat: f64, lon: f64, radius_km: f64) -> Cover {
+    let circle = Circle { lat, lon, radius_km, cos_lat: lat.to_radians().cos().max(0.01) };
+    let dlat = radius_km / KM_PER_DEG;
+    let dlon = radius_km / (KM_PER_DEG * circle.cos_lat);
+    let rows = (row_of(lat + dlat), row_of(lat - dlat));
+    let cols = (col_of(lon - dlon), col_of(lon + dlon));
+
+    let mut cover = Cover::default();
+    let size = BLOCK_SIZES[0];
+    for block_row in rows.0 / size..=rows.1 / size {
+        for block_col in cols.0 / size..=cols.1 / size {
+            split(&circle, 0, block_row, block_col, &mut cover);
+        }
+    }
+    cover.spans.sort_unstable();
+    cover.spans.dedup_by(|next, run| {
+        let joins = next.0 == run.0 && next.1 == run.2 + 1;
+        if joins {
+            run.2 = next.2;
+        }
+        joins
+    });
+    cover
+}
+
+fn row_of(lat: f64) -> i64 {
+    (((90.0 - lat) * 120.0).floor() as i64).clamp(0, NROWS - 1)
+}
+
+fn col_of(lon: f64) -> i64 {
+    (((lon + 180.0) * 120.0).floor() as i64).clamp(0, NCOLS - 1)
+}
+
+fn split(circle: &Circle, level: usize, block_row: i64, block_col: i64, cover: &mut Cover) {
+    let size = BLOCK_SIZES[level];
+    let rows = (block_row * size, block_row * size + size - 1);
+    let cols = (block_col * size, block_col * size + size - 1);
+    match circle.classify(rows, cols) {
+        Some(true) => cover.blocks.push((size as i32, block_row as i32, block_col as i32)),
+        Some(false) => {}
+        None => match BLOCK_SIZES.get(level + 1) {
+            Some(&child) => {
+                let per_side = size / child;
+                for r in 0..per_side {
+                    for c in 0..per_side {
+                        split(circle, level + 1, block_row * per_side + r, block_col * per_side + c, cover);
+                    }
+                }
+            }
+            None => {
+                for row in rows.0..=rows.1 {
+                    cover.spans.push((row as i32, cols.0 as i32, cols.1 as i32));
+                }
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cells counted by the grid query, by brute force.
+    fn cells_within(lat: f64, lon: f64, radius_km: f64) -> Vec<(i64, i64)> {
+        let circle = Circle { lat, lon, radius_km, cos_lat: lat.to_radians().cos().max(0.01) };
+        let rows = (row_of(lat + radius_km / 100.0), row_of(lat - radius_km / 100.0));
+        let cols = (col_of(lon - radius_km / 50.0), col_of(lon + radius_km / 50.0));
+        (rows.0..=rows.1)
+            .flat_map(|r| (cols.0..=cols.1).map(move |c| (r, c)))
+            .filter(|&(r, c)| circle.distance(centre_lat(r), centre_lon(c)) <= radius_km)
+            .collect()
+    }
+
+    /// Cells of the whole blocks plus the span cells that pass the distance
+    /// test the SQL applies to them.
+    fn covered(cover: &Cover, lat: f64, lon: f64, radius_km: f64) -> Vec<(i64, i64)> {
+        let circle = Circle { lat, lon, radius_km, cos_lat: lat.to_radians().cos().max(0.01) };
+        let block_cells = cover.blocks.iter().flat_map(|&(size, br, bc)| {
+            let (size, br, bc) = (i64::from(size), i64::from(br), i64::from(bc));
+            (0..size).flat_map(move |r| (0..size).map(move |c| (br * size + r, bc * size + c)))
+        });
+        let span_cells = cover
+            .spans
+            .iter()
+            .flat_map(|&(row, c0, c1)| (i64::from(c0)..=i64::from(c1)).map(move |c| (i64::from(row), c)))
+            .filter(|&(r, c)| circle.distance(centre_lat(r), centre_lon(c)) <= radius_km);
+        let mut cells: Vec<(i64, i64)> = block_cells.chain(span_cells).collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn covers_exactly_the_cells_within_the_radius() {
+        for (lat, lon, radius_km) in [(6.9271, 79.8612, 60.0), (51.5074, -0.1278, 150.0), (-33.87, 151.21, 25.0)] {
+            let cover = cover(lat, lon, radius_km);
+            assert_eq!(covered(&cover, lat, lon, radius_km), cells_within(lat, lon, radius_km), "{lat},{lon}");
+        }
+    }
+
+    #[test]
+    fn large_circles_need_few_cells() {
+        let cover = cover(6.9271, 79.8612, 500.0);
+        let levels: Vec<i32> = BLOCK_SIZES.iter().map(|&s| s as i32).collect();
+        assert!(cover.blocks.iter().any(|b| b.0 == levels[0]));
+        let edge_cells: i64 = cover.spans.iter().map(|&(_, c0, c1)| i64::from(c1 - c0 + 1)).sum();
+        assert!(edge_cells < 100_000, "{edge_cells}");
+        assert!(cover.blocks.len() < 1_000, "{}", cover.blocks.len());
+    }
+
+    #[test]
+    fn small_circles_are_all_edge() {
+        let cover = cover(6.9271, 79.8612, 2.0);
+        assert!(cover.blocks.is_empty());
+        assert!(!cover.spans.is_empty());
+    }
+}
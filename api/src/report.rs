@@ -0,0 +1,592 @@
+//! Situational reports of `/analyse/report`: an analysis laid out for
+//! responders to attach to briefings instead of pasting JSON by hand.
+//!
+//! Both formats carry the same content, built once as a list of tables: a
+//! map of the boundaries around the epicentre with the search radius, the
+//! exposure figures, the nearest places and facts about the country. HTML is
+//! a self-contained page with an inline SVG map. The PDF is written directly
+//! with the standard Helvetica fonts, which cover Latin-1 only, so other
+//! characters print as `?`.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::geometry;
+use crate::models::{AnalysePayload, CoordinateInfo, CountryDetailPayload, SettlementClass, TerrainClass};
+
+const KM_PER_DEG: f64 = 111.32;
+/// The map shows the search radius with this margin, and at least this many
+/// km around the epicentre.
+const MAP_MARGIN: f64 = 1.4;
+const MAP_MIN_HALF_KM: f64 = 10.0;
+
+/// A4 portrait in points, and the page margin.
+const PAGE_W: f64 = 595.0;
+const PAGE_H: f64 = 842.0;
+const PAGE_MARGIN: f64 = 50.0;
+const FONT_SIZE: f64 = 9.0;
+const LINE_H: f64 = 13.0;
+
+/// Map of the boundaries around an epicentre, in pixels of a
+/// [`WIDTH`](Self::WIDTH) × [`HEIGHT`](Self::HEIGHT) frame.
+pub(crate) struct MapThumbnail {
+    bbox: [f64; 4],
+    outlines: Vec<Vec<[f64; 2]>>,
+    area: Vec<[f64; 2]>,
+    centre: [f64; 2],
+}
+
+impl MapThumbnail {
+    pub const WIDTH: f64 = 480.0;
+    pub const HEIGHT: f64 = 300.0;
+
+    /// `[min_lon, min_lat, max_lon, max_lat]` the map of a search around
+    /// `lat`/`lon` covers, with longitude stretched so that a km is as wide
+    /// as it is tall.
+    pub fn bbox(lat: f64, lon: f64, radius_km: f64) -> [f64; 4] {
+        let half_lat = (radius_km * MAP_MARGIN).max(MAP_MIN_HALF_KM) / KM_PER_DEG;
+        let half_lon = half_lat * Self::WIDTH / Self::HEIGHT / lat.to_radians().cos().max(0.01);
+        [
+            (lon - half_lon).max(-180.0),
+            (lat - half_lat).max(-90.0),
+            (lon + half_lon).min(180.0),
+            (lat + half_lat).min(90.0),
+        ]
+    }
+
+    /// Simplification tolerance of about one pixel of the map of `bbox`.
+    pub fn tolerance_deg(bbox: &[f64; 4]) -> f64 {
+        (bbox[3] - bbox[1]) / Self::HEIGHT
+    }
+
+    /// The map of a search of `radius_km` around `lat`/`lon` over boundary
+    /// `outlines` clipped to [`bbox`](Self::bbox).
+    pub fn new(lat: f64, lon: f64, radius_km: f64, outlines: Vec<Vec<[f64; 2]>>) -> Self {
+        let area = geometry::rings(&geometry::search_area(lat, lon, radius_km)).swap_remove(0);
+        Self { bbox: Self::bbox(lat, lon, radius_km), outlines, area, centre: [lon, lat] }
+    }
+
+    /// Pixel position of a lon/lat pair, y pointing down.
+    fn project(&self, [lon, lat]: [f64; 2]) -> (f64, f64) {
+        let [min_lon, min_lat, max_lon, max_lat] = self.bbox;
+        (
+            (lon - min_lon) / (max_lon - min_lon) * Self::WIDTH,
+            (max_lat - lat) / (max_lat - min_lat) * Self::HEIGHT,
+        )
+    }
+
+    fn svg_path(&self, rings: &[Vec<[f64; 2]>]) -> String {
+        let mut d = String::new();
+        for ring in rings.iter().filter(|r| r.len() > 2) {
+            for (i, &point) in ring.iter().enumerate() {
+                let (x, y) = self.project(point);
+                let _ = write!(d, "{}{x:.1} {y:.1}", if i == 0 { "M" } else { "L" });
+            }
+            d.push('Z');
+        }
+        d
+    }
+
+    fn svg(&self) -> String {
+        let (cx, cy) = self.project(self.centre);
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\" \
+             role=\"img\" aria-label=\"Map of the search area\">\
+             <rect width=\"{w}\" height=\"{h}\" fill=\"#dbe9f6\"/>\
+             <path d=\"{land}\" fill=\"#f4f1ea\" stroke=\"#8a8a8a\" stroke-width=\"0.8\" fill-rule=\"evenodd\"/>\
+             <path d=\"{area}\" fill=\"#c0392b\" fill-opacity=\"0.12\" stroke=\"#c0392b\" stroke-width=\"1.5\"/>\
+             <circle cx=\"{cx:.1}\" cy=\"{cy:.1}\" r=\"4\" fill=\"#c0392b\"/></svg>",
+            w = Self::WIDTH,
+            h = Self::HEIGHT,
+            land = self.svg_path(&self.outlines),
+            area = self.svg_path(std::slice::from_ref(&self.area)),
+        )
+    }
+
+    /// PDF path operators for `rings`, with the map's top left at `(left, top)`.
+    fn pdf_path(&self, rings: &[Vec<[f64; 2]>], left: f64, top: f64) -> String {
+        let mut ops = String::new();
+        for ring in rings.iter().filter(|r| r.len() > 2) {
+            for (i, &point) in ring.iter().enumerate() {
+                let (x, y) = self.project(point);
+                let _ = writeln!(ops, "{:.1} {:.1} {}", left + x, top - y, if i == 0 { "m" } else { "l" });
+            }
+            ops.push_str("h\n");
+        }
+        ops
+    }
+
+    fn pdf(&self, left: f64, top: f64) -> String {
+        let (w, h) = (Self::WIDTH, Self::HEIGHT);
+        let (cx, cy) = self.project(self.centre);
+        let mut ops = format!("q\n{left} {} {w} {h} re W n\n0.859 0.914 0.965 rg\n{left} {} {w} {h} re f\n", top - h, top - h);
+        ops.push_str("0.957 0.945 0.918 rg 0.541 0.541 0.541 RG 0.8 w\n");
+        ops.push_str(&self.pdf_path(&self.outlines, left, top));
+        ops.push_str("B*\n0.753 0.224 0.169 RG 0.753 0.224 0.169 rg 1.5 w\n");
+        ops.push_str(&self.pdf_path(std::slice::from_ref(&self.area), left, top));
+        ops.push_str("S\n");
+        // A 4 pt dot of four Bézier quarter circles, counter-clockwise from the east.
+        let (x, y, r) = (left + cx, top - cy, 4.0);
+        let k = r * 0.5523;
+        let _ = writeln!(ops, "{:.1} {y:.1} m", x + r);
+        for [(x1, y1), (x2, y2), (x3, y3)] in [
+            [(x + r, y + k), (x + k, y + r), (x, y + r)],
+            [(x - k, y + r), (x - r, y + k), (x - r, y)],
+            [(x - r, y - k), (x - k, y - r), (x, y - r)],
+            [(x + k, y - r), (x + r, y - k), (x + r, y)],
+        ] {
+            let _ = writeln!(ops, "{x1:.1} {y1:.1} {x2:.1} {y2:.1} {x3:.1} {y3:.1} c");
+        }
+        ops.push_str("f\n");
+        ops.push_str("0.541 0.541 0.541 RG 0.5 w\n");
+        let _ = writeln!(ops, "{left} {} {w} {h} re S\nQ", top - h);
+        ops
+    }
+}
+
+/// A titled table of the report; label/value facts when `header` is empty.
+struct Table {
+    title: &'static str,
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Everything a situational report shows.
+pub(crate) struct Report {
+    pub analysis: AnalysePayload,
+    /// Country details, `None` when the country has no ISO code to look them up by.
+    pub country: Option<CountryDetailPayload>,
+    pub map: MapThumbnail,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Report {
+    fn title(&self) -> String {
+        format!("{}, {}", self.analysis.nearest_place.name, self.analysis.country.name)
+    }
+
+    fn subtitle(&self) -> String {
+        let CoordinateInfo { lat, lon } = self.analysis.coordinate;
+        format!(
+            "Epicentre {lat:.4}, {lon:.4} · generated {}",
+            self.generated_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    }
+
+    fn attribution(&self) -> String {
+        format!(
+            "Population: WorldPop {}. Places: GeoNames. Boundaries: Natural Earth {}.",
+            self.analysis.year, self.analysis.country.boundary_vintage
+        )
+    }
+
+    fn tables(&self) -> Vec<Table> {
+        let a = &self.analysis;
+        let p = &a.population;
+        let mut exposure = vec![
+            fact("Search radius", format!("{} km", p.search_radius_km)),
+            fact("Population within radius", thousands(p.total_population)),
+            fact("Area", format!("{} km²", thousands(p.area_km2))),
+            fact("Density", format!("{} per km²", p.density_per_km2)),
+            fact("Epicentre cell population", thousands(f64::from(p.epicentre_population))),
+            fact("On land", if a.is_land { "yes" } else { "no (nearest country shown)" }.to_string()),
+        ];
+        if let Some(hazard) = &a.hazard {
+            exposure.push(fact("Hazard", format!("{} magnitude {}", hazard.template.name, hazard.magnitude)));
+            if let Some(weighted) = hazard.weighted_population {
+                exposure.push(fact("Distance-weighted population", thousands(weighted)));
+            }
+        }
+        if let Some(terrain) = &a.terrain {
+            let class = match terrain.class {
+                TerrainClass::Coastal => "coastal",
+                TerrainClass::Lowland => "lowland",
+                TerrainClass::Highland => "highland",
+            };
+            exposure.push(fact("Terrain", format!("{class}, {} m elevation", terrain.elevation_m)));
+        }
+        if let Some(class) = a.settlement_class {
+            let class = match class {
+                SettlementClass::UrbanCentre => "urban centre",
+                SettlementClass::UrbanCluster => "urban cluster",
+                SettlementClass::Rural => "rural",
+            };
+            exposure.push(fact("Settlement", class.to_string()));
+        }
+        if let Some(time) = &a.local_time {
+            exposure.push(fact("Local time", format!("{} ({}, UTC{})", time.local_time, time.timezone, time.utc_offset)));
+        }
+
+        let nearest = a.nearest_places.as_deref().unwrap_or(std::slice::from_ref(&a.nearest_place));
+        let places = nearest
+            .iter()
+            .map(|place| {
+                vec![
+                    place.name.clone(),
+                    format!("{} km {}", place.distance_km, place.direction),
+                    if place.population > 0 { thousands(place.population as f64) } else { "—".into() },
+                ]
+            })
+            .collect();
+
+        let mut tables = vec![
+            Table { title: "Exposure", header: Vec::new(), rows: exposure },
+            Table { title: "Nearest places", header: vec!["Place", "Distance", "Population"], rows: places },
+            Table { title: "Country", header: Vec::new(), rows: self.country_facts() },
+        ];
+        if !a.nearby_countries.is_empty() {
+            let rows = a
+                .nearby_countries
+                .iter()
+                .map(|c| vec![c.country.name.clone(), format!("{} km", c.distance_km)])
+                .collect();
+            tables.push(Table { title: "Countries within 200 km", header: vec!["Country", "Border distance"], rows });
+        }
+        tables
+    }
+
+    fn country_facts(&self) -> Vec<Vec<String>> {
+        let c = &self.analysis.country;
+        let codes = [c.iso_a2.as_deref(), c.iso_a3.as_deref()].into_iter().flatten().collect::<Vec<_>>();
+        let mut rows = vec![fact("Name", c.formal_name.clone().unwrap_or_else(|| c.name.clone()))];
+        if !codes.is_empty() {
+            rows.push(fact("ISO codes", codes.join(" / ")));
+        }
+        rows.push(fact(
+            "Region",
+            [Some(c.continent.as_str()), c.subregion.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(", "),
+        ));
+        if let Some(detail) = &self.country {
+            if let Some(capital) = &detail.capital {
+                rows.push(fact("Capital", capital.clone()));
+            }
+            if let Some(pop) = detail.pop_est {
+                rows.push(fact("Population estimate", thousands(pop as f64)));
+            }
+            if !detail.languages.is_empty() {
+                rows.push(fact("Languages", detail.languages.join(", ")));
+            }
+            if let Some(currency) = &detail.currency_code {
+                rows.push(fact("Currency", currency.clone()));
+            }
+            if let Some(code) = &detail.calling_code {
+                rows.push(fact("Calling code", code.clone()));
+            }
+        }
+        if let Some(area) = c.area_km2 {
+            rows.push(fact("Land area", format!("{} km²", thousands(area))));
+        }
+        if let Some(density) = c.density_per_km2 {
+            rows.push(fact("Density", format!("{density} per km²")));
+        }
+        rows
+    }
+
+    /// The report as a self-contained HTML page.
+    pub fn html(&self) -> String {
+        let mut body = String::new();
+        for table in self.tables() {
+            let _ = write!(body, "<h2>{}</h2><table>", escape(table.title));
+            if !table.header.is_empty() {
+                body.push_str("<tr>");
+                for cell in &table.header {
+                    let _ = write!(body, "<th>{}</th>", escape(cell));
+                }
+                body.push_str("</tr>");
+            }
+            for row in &table.rows {
+                body.push_str("<tr>");
+                for (i, cell) in row.iter().enumerate() {
+                    let tag = if table.header.is_empty() && i == 0 { "th" } else { "td" };
+                    let _ = write!(body, "<{tag}>{}</{tag}>", escape(cell));
+                }
+                body.push_str("</tr>");
+            }
+            body.push_str("</table>");
+        }
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+             <title>Situational report: {title}</title><style>\
+             body{{font-family:Helvetica,Arial,sans-serif;font-size:14px;color:#222;max-width:800px;margin:2em auto;padding:0 1em}}\
+             h1{{font-size:24px;margin-bottom:0}}h2{{font-size:16px;margin:1.5em 0 .4em;border-bottom:1px solid #ccc}}\
+             table{{border-collapse:collapse;width:100%}}th,td{{text-align:left;padding:3px 8px 3px 0;vertical-align:top}}\
+             th{{font-weight:600;width:40%}}.meta{{color:#666}}svg{{max-width:100%;height:auto;margin-top:1em}}\
+             </style></head><body><h1>{title}</h1><p class=\"meta\">{subtitle}</p>{map}{body}\
+             <p class=\"meta\">{attribution}</p></body></html>\n",
+            title = escape(&self.title()),
+            subtitle = escape(&self.subtitle()),
+            map = self.map.svg(),
+            attribution = escape(&self.attribution()),
+        )
+    }
+
+    /// The report as an A4 PDF, continued on further pages when the tables
+    /// outgrow the first.
+    pub fn pdf(&self) -> Vec<u8> {
+        let mut pages = Vec::new();
+        let mut page = String::new();
+        let mut y = PAGE_H - PAGE_MARGIN;
+        text(&mut page, "F2", 18.0, PAGE_MARGIN, y - 18.0, &self.title());
+        y -= 36.0;
+        text(&mut page, "F1", FONT_SIZE, PAGE_MARGIN, y, &self.subtitle());
+        y -= 12.0;
+        let left = (PAGE_W - MapThumbnail::WIDTH) / 2.0;
+        page.push_str(&self.map.pdf(left, y));
+        y -= MapThumbnail::HEIGHT + 10.0;
+
+        let width = PAGE_W - 2.0 * PAGE_MARGIN;
+        for table in self.tables() {
+            let columns = if table.header.is_empty() { 2 } else { table.header.len() };
+            // The first column is as wide as the others together.
+            let first = if columns > 1 { width / 2.0 } else { width };
+            let others = (width - first) / (columns.max(2) - 1) as f64;
+            let x_of = |i: usize| PAGE_MARGIN + if i == 0 { 0.0 } else { first + others * (i - 1) as f64 };
+            // Helvetica averages about half an em per character.
+            let max_chars = |i: usize| {
+                let column = if i == 0 { first } else { others };
+                (column / (FONT_SIZE * 0.5)) as usize
+            };
+
+            let mut lines: Vec<(&str, Vec<&str>)> = Vec::new();
+            if !table.header.is_empty() {
+                lines.push(("F2", table.header.clone()));
+            }
+            lines.extend(table.rows.iter().map(|row| ("F1", row.iter().map(String::as_str).collect())));
+
+            // Keep a title with at least its first line.
+            if y - 2.0 * LINE_H - 8.0 < PAGE_MARGIN {
+                pages.push(std::mem::take(&mut page));
+                y = PAGE_H - PAGE_MARGIN;
+            }
+            y -= LINE_H + 8.0;
+            text(&mut page, "F2", 11.0, PAGE_MARGIN, y, table.title);
+            for (font, cells) in lines {
+                if y - LINE_H < PAGE_MARGIN {
+                    pages.push(std::mem::take(&mut page));
+                    y = PAGE_H - PAGE_MARGIN;
+                }
+                y -= LINE_H;
+                for (i, cell) in cells.iter().enumerate() {
+                    let font = if table.header.is_empty() && i == 0 { "F2" } else { font };
+                    text(&mut page, font, FONT_SIZE, x_of(i), y, &truncate(cell, max_chars(i)));
+                }
+            }
+        }
+        if y - 2.0 * LINE_H < PAGE_MARGIN {
+            pages.push(std::mem::take(&mut page));
+            y = PAGE_H - PAGE_MARGIN;
+        }
+        text(&mut page, "F1", 8.0, PAGE_MARGIN, y - 2.0 * LINE_H, &self.attribution());
+        pages.push(page);
+        pdf_document(&self.title(), &pages)
+    }
+}
+
+fn fact(label: &str, value: String) -> Vec<String> {
+    vec![label.to_string(), value]
+}
+
+/// `v` rounded to a whole number with thousands separators.
+fn thousands(v: f64) -> String {
+    let digits = format!("{:.0}", v.abs());
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if v.round() < 0.0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// Appends a line of text at `(x, y)` to a page content stream.
+fn text(page: &mut String, font: &str, size: f64, x: f64, y: f64, s: &str) {
+    let _ = writeln!(page, "BT /{font} {size} Tf {x:.1} {y:.1} Td ({}) Tj ET", pdf_string(s));
+}
+
+/// `s` as the body of a PDF literal string in WinAnsiEncoding. The content
+/// stream is kept ASCII: other bytes are written as octal escapes.
+fn pdf_string(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+                continue;
+            }
+            ' '..='~' => {
+                out.push(c);
+                continue;
+            }
+            '…' => 0x85,
+            '—' => 0x97,
+            '–' => 0x96,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            _ => b'?',
+        };
+        if byte == b'?' {
+            out.push('?');
+        } else {
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    }
+    out
+}
+
+/// A PDF 1.4 file of A4 `pages` (content streams) using Helvetica as `F1`
+/// and Helvetica-Bold as `F2`.
+fn pdf_document(title: &str, pages: &[String]) -> Vec<u8> {
+    // 1 catalog, 2 page tree, 3–4 fonts, 5 info, then a page and its content per page.
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len()).map(|i| format!("{} 0 R", 6 + 2 * i)).collect::<Vec<_>>().join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+        format!("<< /Title ({}) /Producer (GeoPop) >>", pdf_string(&format!("Situational report: {title}"))),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_W} {PAGE_H}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            7 + 2 * i
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{content}endstream", content.len()));
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref = out.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    out.extend_from_slice(trailer.as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn report(nearby: usize) -> Report {
+        let nearby: Vec<_> = (0..nearby)
+            .map(|i| json!({"iso_a2": "IN", "iso_a3": "IND", "name": format!("Country {i}"),
+                "formal_name": null, "continent": "Asia", "region": "Asia", "subregion": "Southern Asia",
+                "boundary_vintage": 2025, "sovereign": true, "area_km2": null, "density_per_km2": null,
+                "distance_km": 30.0 + i as f64}))
+            .collect();
+        let analysis = serde_json::from_value(json!({
+            "coordinate": {"lat": 6.9271, "lon": 79.8612},
+            "is_land": true,
+            "country": {"iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka", "formal_name": null,
+                "continent": "Asia", "region": "Asia", "subregion": "Southern Asia", "boundary_vintage": 2025,
+                "sovereign": true, "area_km2": 66177.6, "density_per_km2": 327.5},
+            "nearest_place": {"place_id": 1, "name": "Colombo <Fort>", "display_name": "Colombo", "address": {},
+                "distance_km": 0.5, "direction": "N", "bearing_deg": 2.0, "population": 648034},
+            "nearby_countries": nearby,
+            "local_time": null,
+            "terrain": null,
+            "population": {"search_radius_km": 5.0, "total_population": 55000.0, "area_km2": 78.54,
+                "density_per_km2": 700.3, "epicentre_population": 2200.0},
+            "year": 2025
+        }))
+        .unwrap();
+        let square = vec![[79.0, 6.0], [81.0, 6.0], [81.0, 8.0], [79.0, 8.0], [79.0, 6.0]];
+        Report {
+            analysis,
+            country: None,
+            map: MapThumbnail::new(6.9271, 79.8612, 5.0, vec![square]),
+            generated_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn thousands_groups_digits() {
+        assert_eq!(thousands(0.0), "0");
+        assert_eq!(thousands(999.4), "999");
+        assert_eq!(thousands(55000.0), "55,000");
+        assert_eq!(thousands(1_234_567.8), "1,234,568");
+        assert_eq!(thousands(-52800.0), "-52,800");
+    }
+
+    #[test]
+    fn pdf_strings_are_escaped_and_latin1() {
+        assert_eq!(pdf_string("a (b) \\"), "a \\(b\\) \\\\");
+        assert_eq!(pdf_string("km²"), "km\\262");
+        assert_eq!(pdf_string("Kraków — 東京"), "Krak\\363w \\227 ??");
+    }
+
+    #[test]
+    fn map_keeps_the_search_area_inside() {
+        let map = report(0).map;
+        let (x, y) = map.project(map.centre);
+        assert!((x - MapThumbnail::WIDTH / 2.0).abs() < 1e-9 && (y - MapThumbnail::HEIGHT / 2.0).abs() < 1e-9);
+        for &point in &map.area {
+            let (x, y) = map.project(point);
+            assert!((0.0..=MapThumbnail::WIDTH).contains(&x) && (0.0..=MapThumbnail::HEIGHT).contains(&y));
+        }
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let html = report(0).html();
+        assert!(html.contains("<h1>Colombo &lt;Fort&gt;, Sri Lanka</h1>"));
+        assert!(html.contains("<td>55,000</td>"));
+        assert!(html.contains("<svg"));
+    }
+
+    #[test]
+    fn pdf_xref_points_at_each_object() {
+        for (nearby, pages) in [(0, 1), (60, 2)] {
+            let pdf = report(nearby).pdf();
+            let text = String::from_utf8_lossy(&pdf);
+            assert!(pdf.starts_with(b"%PDF-1.4"));
+            assert!(text.contains(&format!("/Count {pages}")), "{nearby} nearby countries");
+            let startxref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+            assert!(pdf[startxref..].starts_with(b"xref\n"));
+            let offsets: Vec<usize> = std::str::from_utf8(&pdf[startxref..])
+                .unwrap()
+                .lines()
+                .skip(3)
+                .take_while(|l| l.ends_with(" n "))
+                .map(|l| l[..10].parse().unwrap())
+                .collect();
+            assert_eq!(offsets.len(), 5 + 2 * pages);
+            for (i, offset) in offsets.into_iter().enumerate() {
+                assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::errors::AppError;
+use crate::models::{AlertThreshold, CallbackStatus, JobCallback, MonitorAlert};
+use deadpool_postgres::Object;
+use serde_json::Value;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+const ALERT_COLUMNS: &str = "a.id, a.monitor_id, m.name, a.threshold, a.value, a.event_type, a.event_id, \
+    a.created_at, a.webhook_url, a.delivery_status, a.delivery_attempts, a.delivery_error";
+
+/// The event that breached an `event_exposure` threshold: type and GDACS ID.
+pub(crate) type EventKey<'a> = (&'a str, i64);
+
+pub(crate) struct AlertRepository;
+
+impl AlertRepository {
+    /// Records a breach of `threshold`, due for delivery at once when the
+    /// monitor has a webhook.
+    pub async fn create(
+        client: &Object,
+        monitor_id: Uuid,
+        threshold: &AlertThreshold,
+        value: f64,
+        event: Option<EventKey<'_>>,
+    ) -> Result<(), AppError> {
+        let threshold = serde_json::to_value(threshold)
+            .map_err(|e| AppError::Database(format!("failed to encode threshold: {e}")))?;
+        client
+            .execute(
+                "INSERT INTO monitor_alerts (monitor_id, threshold, value, event_type, event_id, webhook_url,
+                                             delivery_status, delivery_next_at)
+                 SELECT id, $2::jsonb, $3::float8, $4::text, $5::bigint, webhook_url,
+                        CASE WHEN webhook_url IS NULL THEN NULL ELSE 'pending' END,
+                        CASE WHEN webhook_url IS NULL THEN NULL ELSE now() END
+                 FROM monitors WHERE id = $1",
+                &[&monitor_id, &threshold, &value, &event.map(|e| e.0), &event.map(|e| e.1)],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Up to `limit` alerts of a monitor, most recent first.
+    pub async fn list(client: &Object, monitor_id: Uuid, limit: i64) -> Result<Vec<MonitorAlert>, AppError> {
+        let sql = format!(
+            "SELECT {ALERT_COLUMNS} FROM monitor_alerts a JOIN monitors m ON m.id = a.monitor_id
+             WHERE a.monitor_id = $1 ORDER BY a.created_at DESC LIMIT $2"
+        );
+        client.query(&sql, &[&monitor_id, &limit]).await?.iter().map(to_alert).collect()
+    }
+
+    /// Leases up to `limit` deliveries that are due, as
+    /// [`JobRepository::claim_callbacks`](super::JobRepository::claim_callbacks)
+    /// does for jobs. Returns each alert with its webhook URL.
+    pub async fn claim_deliveries(
+        client: &Object,
+        lease_secs: f64,
+        limit: i64,
+    ) -> Result<Vec<(MonitorAlert, String)>, AppError> {
+        let sql = format!(
+            "WITH due AS (
+                 UPDATE monitor_alerts SET delivery_next_at = now() + make_interval(secs => $1),
+                                           delivery_attempts = delivery_attempts + 1
+                 WHERE id IN (
+                     SELECT id FROM monitor_alerts
+                     WHERE delivery_status = 'pending' AND delivery_next_at <= now()
+                     ORDER BY delivery_next_at
+                     FOR UPDATE SKIP LOCKED
+                     LIMIT $2
+                 )
+                 RETURNING *
+             )
+             SELECT {ALERT_COLUMNS} FROM due a JOIN monitors m ON m.id = a.monitor_id"
+        );
+        client
+            .query(&sql, &[&lease_secs, &limit])
+            .await?
+            .iter()
+            .map(|row| Ok((to_alert(row)?, row.get(8))))
+            .collect()
+    }
+
+    pub async fn delivered(client: &Object, id: Uuid) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE monitor_alerts SET delivery_status = 'delivered', delivery_next_at = NULL, \
+                 delivery_error = NULL WHERE id = $1",
+                &[&id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Records a failed delivery: retried after `retry_in_secs`, or given up
+    /// for good when `None`.
+    pub async fn delivery_failed(
+        client: &Object,
+        id: Uuid,
+        error: &str,
+        retry_in_secs: Option<f64>,
+    ) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE monitor_alerts SET delivery_error = $2,
+                     delivery_status = CASE WHEN $3::float8 IS NULL THEN 'failed' ELSE 'pending' END,
+                     delivery_next_at = now() + make_interval(secs => $3::float8)
+                 WHERE id = $1",
+                &[&id, &error, &retry_in_secs],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn to_alert(row: &Row) -> Result<MonitorAlert, AppError> {
+    let threshold: Value = row.get(3);
+    let event_type: Option<String> = row.get(5);
+    let event_id: Option<i64> = row.get(6);
+    Ok(MonitorAlert {
+        id: row.get(0),
+        monitor_id: row.get(1),
+        monitor_name: row.get(2),
+        threshold: serde_json::from_value(threshold)
+            .map_err(|e| AppError::Database(format!("invalid stored threshold: {e}")))?,
+        value: row.get(4),
+        event: event_type.zip(event_id).map(|(t, id)| format!("{t}{id}")),
+        created_at: row.get(7),
+        delivery: row.get::<_, Option<String>>(8).map(|url| JobCallback {
+            url,
+            status: row
+                .get::<_, Option<String>>(9)
+                .and_then(|s| CallbackStatus::parse(&s))
+                .unwrap_or(CallbackStatus::Pending),
+            attempts: row.get(10),
+            last_error: row.get(11),
+        }),
+    })
+}
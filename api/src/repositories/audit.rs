@@ -0,0 +1,36 @@
+use crate::errors::AppError;
+use deadpool_postgres::Object;
+
+/// One request as recorded in `audit_log`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AuditRecord {
+    pub method: String,
+    pub path: String,
+    pub params_hash: String,
+    pub api_key: Option<String>,
+    pub status: i16,
+    pub latency_ms: f32,
+}
+
+pub(crate) struct AuditRepository;
+
+impl AuditRepository {
+    /// Inserts a batch of records in one statement.
+    pub async fn insert(client: &Object, records: &[AuditRecord]) -> Result<u64, AppError> {
+        let column = |f: fn(&AuditRecord) -> &str| records.iter().map(f).collect::<Vec<_>>();
+        let methods = column(|r| &r.method);
+        let paths = column(|r| &r.path);
+        let hashes = column(|r| &r.params_hash);
+        let keys: Vec<Option<&str>> = records.iter().map(|r| r.api_key.as_deref()).collect();
+        let statuses: Vec<i16> = records.iter().map(|r| r.status).collect();
+        let latencies: Vec<f32> = records.iter().map(|r| r.latency_ms).collect();
+        let inserted = client
+            .execute(
+                "INSERT INTO audit_log (method, path, params_hash, api_key, status, latency_ms) \
+                 SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::smallint[], $6::real[])",
+                &[&methods, &paths, &hashes, &keys, &statuses, &latencies],
+            )
+            .await?;
+        Ok(inserted)
+    }
+}
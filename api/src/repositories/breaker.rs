@@ -0,0 +1,163 @@
+//! Circuit breaker around database access.
+//!
+//! When Postgres is down, every checkout waits out the pool's 5 s create
+//! timeout, so requests pile up behind a database that cannot answer. After
+//! `FAILURE_THRESHOLD` consecutive connection failures the breaker opens and
+//! `PgDb::conn` fails fast with 503 and `Retry-After` for `OPEN_FOR`. Then
+//! one request is let through as a probe (half-open): its success closes the
+//! breaker, its failure opens it again.
+//!
+//! Only failures of the database itself count — a pool that cannot create or
+//! recycle a connection, or a connection that breaks while in use. Query
+//! errors (bad input, statement timeouts) and waiting for a busy pool do not.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use deadpool_postgres::{PoolError, TimeoutType};
+
+use crate::errors::AppError;
+
+const FAILURE_THRESHOLD: u32 = 5;
+const OPEN_FOR: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// A probe is in flight. A probe that never reports back (its request was
+    /// cancelled) is replaced after `OPEN_FOR`.
+    HalfOpen { since: Instant },
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    state: Mutex<State>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self { state: Mutex::new(State::Closed { failures: 0 }) }
+    }
+}
+
+impl CircuitBreaker {
+    /// Admits a database call, or fails with `AppError::Unavailable` while
+    /// the breaker is open or a probe is in flight.
+    pub fn check(&self) -> Result<(), AppError> {
+        self.check_at(Instant::now())
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now());
+    }
+
+    fn check_at(&self, now: Instant) -> Result<(), AppError> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now < until => Err(AppError::Unavailable(retry_after(until - now))),
+            State::HalfOpen { since } if now < since + OPEN_FOR => {
+                Err(AppError::Unavailable(retry_after(since + OPEN_FOR - now)))
+            }
+            State::Open { .. } | State::HalfOpen { .. } => {
+                log::info!("Database circuit half-open; probing");
+                *state = State::HalfOpen { since: now };
+                Ok(())
+            }
+        }
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            State::Closed { failures } if failures + 1 < FAILURE_THRESHOLD => {
+                State::Closed { failures: failures + 1 }
+            }
+            State::Closed { .. } | State::HalfOpen { .. } => {
+                log::warn!("Database circuit open for {}s", OPEN_FOR.as_secs());
+                State::Open { until: now + OPEN_FOR }
+            }
+            open @ State::Open { .. } => open,
+        };
+    }
+}
+
+/// Whole seconds until the breaker admits calls again, at least one.
+fn retry_after(remaining: Duration) -> u64 {
+    remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+}
+
+/// Whether a checkout failure means the database is unreachable, rather than
+/// every connection being busy.
+pub(super) fn is_outage(err: &PoolError) -> bool {
+    !matches!(err, PoolError::Timeout(TimeoutType::Wait))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tripped(breaker: &CircuitBreaker, now: Instant) {
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.check_at(now).unwrap();
+            breaker.record_failure_at(now);
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            breaker.record_failure_at(now);
+        }
+        breaker.record_success();
+        breaker.record_failure_at(now);
+        assert!(breaker.check_at(now).is_ok());
+
+        breaker.record_success();
+        tripped(&breaker, now);
+        let err = breaker.check_at(now + Duration::from_millis(500)).unwrap_err();
+        assert!(matches!(err, AppError::Unavailable(10)));
+    }
+
+    #[test]
+    fn half_open_admits_one_probe() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        tripped(&breaker, now);
+
+        let later = now + OPEN_FOR;
+        assert!(breaker.check_at(later).is_ok());
+        assert!(breaker.check_at(later).is_err());
+        breaker.record_failure_at(later);
+        assert!(breaker.check_at(later + Duration::from_secs(1)).is_err());
+
+        let probe = later + OPEN_FOR;
+        assert!(breaker.check_at(probe).is_ok());
+        breaker.record_success();
+        assert!(breaker.check_at(probe).is_ok());
+        assert!(breaker.check_at(probe).is_ok());
+    }
+
+    #[test]
+    fn abandoned_probe_is_replaced() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        tripped(&breaker, now);
+        assert!(breaker.check_at(now + OPEN_FOR).is_ok());
+        assert!(breaker.check_at(now + OPEN_FOR * 2).is_ok());
+    }
+
+    #[test]
+    fn busy_pool_is_not_an_outage() {
+        assert!(!is_outage(&PoolError::Timeout(TimeoutType::Wait)));
+        assert!(is_outage(&PoolError::Timeout(TimeoutType::Create)));
+        assert!(is_outage(&PoolError::Closed));
+    }
+}
@@ -1,56 +1,247 @@
+use super::{statements, PgDb};
 use crate::errors::AppError;
-use crate::models::{CountryDetailPayload, CountryPayload, NearbyCountryEntry};
-use deadpool_postgres::Object;
+use crate::geopackage::CountryFeature;
+use crate::models::{
+    ContinentStats, CountryDetailPayload, CountryPayload, CountryPopulationPayload, EezClaimant,
+    EezZone, NearbyCountryEntry,
+};
+use async_trait::async_trait;
 
-pub(crate) struct CountryRepository;
+/// Country boundary lookups.
+#[async_trait]
+pub(crate) trait CountryRepository: Send + Sync {
+    /// Resolve the boundary vintage a request should run against: the pinned
+    /// `boundaries=YYYY` value if it exists, otherwise the current vintage.
+    async fn resolve_vintage(&self, requested: Option<i32>) -> Result<i32, AppError>;
 
-impl CountryRepository {
-    pub async fn is_land(client: &Object, lat: f64, lon: f64) -> Result<bool, AppError> {
-        let sql = r#"
-            SELECT EXISTS(
-                SELECT 1 FROM countries
-                WHERE ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
-            )
-        "#;
-        let row = client.query_one(sql, &[&lon, &lat]).await?;
+    async fn is_land(&self, lat: f64, lon: f64, vintage: i32) -> Result<bool, AppError>;
+
+    async fn get_land_country(
+        &self,
+        lat: f64,
+        lon: f64,
+        vintage: i32,
+    ) -> Result<Option<CountryPayload>, AppError>;
+
+    async fn get_nearby_countries(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        vintage: i32,
+    ) -> Result<Vec<NearbyCountryEntry>, AppError>;
+
+    async fn get_by_coordinate(
+        &self,
+        lat: f64,
+        lon: f64,
+        vintage: i32,
+    ) -> Result<CountryPayload, AppError>;
+
+    async fn get_by_code(
+        &self,
+        code: &CountryCode,
+        vintage: i32,
+    ) -> Result<CountryDetailPayload, AppError>;
+
+    async fn get_countries(
+        &self,
+        filter: &CountryFilter,
+        vintage: i32,
+    ) -> Result<Vec<CountryPayload>, AppError>;
+
+    /// Country count, summed `pop_est`, area and extent of every continent in
+    /// the vintage, ordered by name.
+    async fn get_continent_stats(&self, vintage: i32) -> Result<Vec<ContinentStats>, AppError>;
+
+    /// Precomputed WorldPop grid total for a country (`country_population`).
+    /// Fails with `NotFound` for an unknown country or when the rollup has not
+    /// been computed for the vintage.
+    async fn get_grid_population(
+        &self,
+        iso3: &str,
+        vintage: i32,
+    ) -> Result<CountryPopulationPayload, AppError>;
+
+    /// Exclusive economic zone containing the coordinate, `None` outside every
+    /// zone. Fails with `NotFound` when the EEZ layer has not been loaded.
+    async fn get_eez(&self, lat: f64, lon: f64) -> Result<Option<EezZone>, AppError>;
+
+    /// Mapbox vector tile `z/x/y` (Web Mercator) with a `countries` layer of
+    /// the vintage's boundaries, simplified by `tolerance_m` metres. Empty
+    /// when no country touches the tile.
+    async fn get_boundary_tile(
+        &self,
+        z: u32,
+        x: u32,
+        y: u32,
+        tolerance_m: f64,
+        vintage: i32,
+    ) -> Result<Vec<u8>, AppError>;
+
+    /// Rings (outer and holes alike) of the vintage's boundaries clipped to
+    /// `bbox` (`[min_lon, min_lat, max_lon, max_lat]`) and simplified by
+    /// `tolerance_deg`, as lon/lat pairs. Used to draw report maps.
+    async fn get_outlines(
+        &self,
+        bbox: [f64; 4],
+        tolerance_deg: f64,
+        vintage: i32,
+    ) -> Result<Vec<Vec<[f64; 2]>>, AppError>;
+
+    /// The vintage's countries overlapping `bbox`, their boundaries clipped
+    /// to it, by name.
+    async fn get_clipped_boundaries(&self, bbox: [f64; 4], vintage: i32) -> Result<Vec<CountryFeature>, AppError>;
+}
+
+/// A normalised ISO 3166-1 country code of any of the three kinds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CountryCode {
+    /// Upper-case alpha-2, e.g. `LK`.
+    Alpha2(String),
+    /// Upper-case alpha-3, e.g. `LKA`.
+    Alpha3(String),
+    /// Zero-padded numeric, e.g. `144`.
+    Numeric(String),
+}
+
+impl CountryCode {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Alpha2(_) => "iso_a2",
+            Self::Alpha3(_) => "iso_a3",
+            Self::Numeric(_) => "iso_n3",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Alpha2(code) | Self::Alpha3(code) | Self::Numeric(code) => code,
+        }
+    }
+}
+
+/// Country listing filter; every field set must match. Values are already
+/// normalised: continents lower-case, regions and subregions canonical.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CountryFilter {
+    pub continent: Option<String>,
+    pub region: Option<String>,
+    pub subregion: Option<String>,
+    /// `None` lists sovereign states and dependent territories alike.
+    pub sovereign: Option<bool>,
+}
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// `pop_est` per km² of land, when both are known.
+fn density(pop_est: Option<i64>, area_km2: Option<f64>) -> Option<f64> {
+    match (pop_est, area_km2) {
+        (Some(pop), Some(area)) if area > 0.0 => Some(round1(pop as f64 / area)),
+        _ => None,
+    }
+}
+
+/// `CountryRepository` backed by the Natural Earth tables in PostGIS.
+pub(crate) struct PgCountryRepository {
+    db: PgDb,
+}
+
+impl PgCountryRepository {
+    pub fn new(db: PgDb) -> Self {
+        Self { db }
+    }
+
+    fn build_country_payload(row: &tokio_postgres::Row) -> CountryPayload {
+        CountryPayload {
+            iso_a2: row.get::<_, Option<String>>(0).map(|s| s.trim().to_string()),
+            iso_a3: row.get::<_, Option<String>>(1).map(|s| s.trim().to_string()),
+            name: row.get(2),
+            formal_name: row.get(3),
+            continent: row.get(4),
+            region: row.get(5),
+            subregion: row.get(6),
+            boundary_vintage: row.get(7),
+            sovereign: row.get(8),
+            area_km2: row.get::<_, Option<f64>>(9).map(round1),
+            density_per_km2: density(row.get(10), row.get(9)),
+        }
+    }
+}
+
+#[async_trait]
+impl CountryRepository for PgCountryRepository {
+    async fn resolve_vintage(&self, requested: Option<i32>) -> Result<i32, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::BOUNDARY_VINTAGE).await?;
+        let row = client.query_opt(&stmt, &[&requested]).await?;
+        if let Some(row) = row {
+            return Ok(row.get(0));
+        }
+
+        let Some(year) = requested else {
+            return Err(AppError::NotFound("No boundary dataset has been loaded".into()));
+        };
+        let available: Vec<String> = client
+            .query("SELECT vintage FROM boundary_vintages ORDER BY vintage", &[])
+            .await?
+            .iter()
+            .map(|r| r.get::<_, i32>(0).to_string())
+            .collect();
+        Err(AppError::NotFound(format!(
+            "Unknown boundary vintage {year}. Available: {}",
+            available.join(", ")
+        )))
+    }
+
+    async fn is_land(&self, lat: f64, lon: f64, vintage: i32) -> Result<bool, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::IS_LAND).await?;
+        let row = client.query_one(&stmt, &[&lon, &lat, &vintage]).await?;
         Ok(row.get(0))
     }
 
-    pub async fn get_land_country(
-        client: &Object,
+    async fn get_land_country(
+        &self,
         lat: f64,
         lon: f64,
+        vintage: i32,
     ) -> Result<Option<CountryPayload>, AppError> {
-        let sql = r#"
-            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion
-            FROM countries
-            WHERE ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
-            LIMIT 1
-        "#;
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::COUNTRY_AT_POINT).await?;
         Ok(client
-            .query_opt(sql, &[&lon, &lat])
+            .query_opt(&stmt, &[&lon, &lat, &vintage])
             .await?
             .map(|r| Self::build_country_payload(&r)))
     }
 
-    pub async fn get_nearby_countries(
-        client: &Object,
+    async fn get_nearby_countries(
+        &self,
         lat: f64,
         lon: f64,
         radius_km: f64,
+        vintage: i32,
     ) -> Result<Vec<NearbyCountryEntry>, AppError> {
+        let client = &self.db.conn().await?;
         let sql = r#"
-            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion,
+            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion, vintage, sovereign,
+                   area_km2, pop_est,
                    ST_Distance(geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
             FROM countries
-            WHERE ST_DWithin(geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
+            WHERE vintage = $4
+              AND ST_DWithin(geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
             ORDER BY ST_Distance(geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography)
         "#;
-        let rows = client.query(sql, &[&lon, &lat, &(radius_km * 1000.0)]).await?;
+        let rows = client
+            .query(sql, &[&lon, &lat, &(radius_km * 1000.0), &vintage])
+            .await?;
         Ok(rows
             .iter()
             .map(|r| {
-                let distance_km: f64 = r.get(7);
+                let distance_km: f64 = r.get(11);
                 NearbyCountryEntry {
                     country: Self::build_country_payload(r),
                     distance_km: (distance_km * 100.0).round() / 100.0,
@@ -59,27 +250,26 @@ impl CountryRepository {
             .collect())
     }
 
-    pub async fn get_by_coordinate(
-        client: &Object,
+    async fn get_by_coordinate(
+        &self,
         lat: f64,
         lon: f64,
+        vintage: i32,
     ) -> Result<CountryPayload, AppError> {
-        let sql = r#"
-            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion
-            FROM countries
-            WHERE ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
-            LIMIT 1
-        "#;
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::COUNTRY_AT_POINT).await?;
 
-        let row = match client.query_opt(sql, &[&lon, &lat]).await? {
+        let row = match client.query_opt(&stmt, &[&lon, &lat, &vintage]).await? {
             Some(r) => r,
             None => {
                 let fallback = r#"
-                    SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion
-                    FROM countries ORDER BY geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326) LIMIT 1
+                    SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion, vintage, sovereign,
+                   area_km2, pop_est
+                    FROM countries WHERE vintage = $3
+                    ORDER BY geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326) LIMIT 1
                 "#;
                 client
-                    .query_opt(fallback, &[&lon, &lat])
+                    .query_opt(fallback, &[&lon, &lat, &vintage])
                     .await?
                     .ok_or_else(|| AppError::NotFound("No country found at this coordinate".into()))?
             }
@@ -88,20 +278,31 @@ impl CountryRepository {
         Ok(Self::build_country_payload(&row))
     }
 
-    pub async fn get_by_iso3(
-        client: &Object,
-        iso3: &str,
+    async fn get_by_code(
+        &self,
+        code: &CountryCode,
+        vintage: i32,
     ) -> Result<CountryDetailPayload, AppError> {
-        let sql = r#"
-            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion,
-                   pop_est, ST_XMin(geom), ST_YMin(geom), ST_XMax(geom), ST_YMax(geom)
-            FROM countries WHERE UPPER(iso_a3) = $1 ORDER BY sovereign DESC LIMIT 1
-        "#;
+        let client = &self.db.conn().await?;
+        let sql = format!(
+            r#"
+            SELECT c.iso_a2, c.iso_a3, c.name, c.formal_name, c.continent, c.region_un, c.subregion,
+                   c.vintage, c.pop_est,
+                   ST_XMin(c.geom), ST_YMin(c.geom), ST_XMax(c.geom), ST_YMax(c.geom),
+                   i.capital, i.currency_code, i.languages, i.calling_code, i.tld, c.area_km2
+            FROM countries c
+            LEFT JOIN country_info i ON i.iso_a3 = UPPER(TRIM(c.iso_a3))
+            WHERE UPPER(c.{column}) = $1 AND c.vintage = $2
+            ORDER BY c.sovereign DESC LIMIT 1
+            "#,
+            column = code.column()
+        );
 
+        let value = code.value();
         let row = client
-            .query_opt(sql, &[&iso3])
+            .query_opt(&sql, &[&value, &vintage])
             .await?
-            .ok_or_else(|| AppError::NotFound(format!("Country not found: {iso3}")))?;
+            .ok_or_else(|| AppError::NotFound(format!("Country not found: {value}")))?;
 
         Ok(CountryDetailPayload {
             iso_a2: row.get::<_, Option<String>>(0).map(|s| s.trim().to_string()),
@@ -111,48 +312,259 @@ impl CountryRepository {
             continent: row.get(4),
             region: row.get(5),
             subregion: row.get(6),
-            pop_est: row.get(7),
-            bbox: [row.get(8), row.get(9), row.get(10), row.get(11)],
+            boundary_vintage: row.get(7),
+            pop_est: row.get(8),
+            bbox: [row.get(9), row.get(10), row.get(11), row.get(12)],
+            capital: row.get(13),
+            currency_code: row.get(14),
+            languages: row.get::<_, Option<Vec<String>>>(15).unwrap_or_default(),
+            calling_code: row.get(16),
+            tld: row.get(17),
+            area_km2: row.get::<_, Option<f64>>(18).map(round1),
+            density_per_km2: density(row.get(8), row.get(18)),
         })
     }
 
-    pub async fn get_by_continent(
-        client: &Object,
-        continent: &str,
+    async fn get_countries(
+        &self,
+        filter: &CountryFilter,
+        vintage: i32,
     ) -> Result<Vec<CountryPayload>, AppError> {
-        let base = "SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion \
-                    FROM countries WHERE sovereign = true AND iso_a2 IS NOT NULL AND iso_a3 IS NOT NULL";
-
-        let rows = if continent == "americas" {
-            client
-                .query(&format!("{base} AND LOWER(region_un) = 'americas' ORDER BY name"), &[])
-                .await?
-        } else if continent == "north-america" {
-            client
-                .query(&format!("{base} AND LOWER(continent) = 'north america' ORDER BY name"), &[])
-                .await?
-        } else if continent == "south-america" {
-            client
-                .query(&format!("{base} AND LOWER(continent) = 'south america' ORDER BY name"), &[])
-                .await?
-        } else {
-            client
-                .query(&format!("{base} AND LOWER(region_un) = LOWER($1) ORDER BY name"), &[&continent])
-                .await?
-        };
+        let client = &self.db.conn().await?;
+        // `americas` and the other continents follow the UN region; the two
+        // halves of the Americas only exist as Natural Earth continents.
+        let sql = r#"
+            SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion, vintage, sovereign,
+                   area_km2, pop_est
+            FROM countries
+            WHERE vintage = $1 AND ($5::bool IS NULL OR sovereign = $5::bool)
+              AND iso_a2 IS NOT NULL AND iso_a3 IS NOT NULL
+              AND ($2::text IS NULL OR CASE $2::text
+                      WHEN 'north-america' THEN LOWER(continent) = 'north america'
+                      WHEN 'south-america' THEN LOWER(continent) = 'south america'
+                      ELSE LOWER(region_un) = $2::text
+                  END)
+              AND ($3::text IS NULL OR region_un = $3::text)
+              AND ($4::text IS NULL OR subregion = $4::text)
+            ORDER BY name
+        "#;
+        let rows = client
+            .query(
+                sql,
+                &[&vintage, &filter.continent, &filter.region, &filter.subregion, &filter.sovereign],
+            )
+            .await?;
 
         Ok(rows.iter().map(Self::build_country_payload).collect())
     }
 
-    fn build_country_payload(row: &tokio_postgres::Row) -> CountryPayload {
-        CountryPayload {
-            iso_a2: row.get::<_, Option<String>>(0).map(|s| s.trim().to_string()),
-            iso_a3: row.get::<_, Option<String>>(1).map(|s| s.trim().to_string()),
-            name: row.get(2),
-            formal_name: row.get(3),
-            continent: row.get(4),
-            region: row.get(5),
-            subregion: row.get(6),
-        }
+    async fn get_continent_stats(&self, vintage: i32) -> Result<Vec<ContinentStats>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            WITH stats AS (
+                SELECT continent,
+                       COUNT(*) FILTER (
+                           WHERE sovereign AND iso_a2 IS NOT NULL AND iso_a3 IS NOT NULL
+                       ) AS country_count,
+                       COALESCE(SUM(pop_est), 0) AS pop_est,
+                       COALESCE(SUM(area_km2), 0) AS area_km2,
+                       ST_Extent(geom) AS extent
+                FROM countries
+                WHERE vintage = $1
+                GROUP BY continent
+            )
+            SELECT continent, country_count::int8, pop_est::int8, area_km2::float8,
+                   ST_XMin(extent)::float8, ST_YMin(extent)::float8,
+                   ST_XMax(extent)::float8, ST_YMax(extent)::float8
+            FROM stats
+            ORDER BY continent
+        "#;
+        let rows = client.query(sql, &[&vintage]).await?;
+        Ok(rows
+            .iter()
+            .map(|r| ContinentStats {
+                name: r.get(0),
+                country_count: r.get(1),
+                pop_est: r.get(2),
+                area_km2: round1(r.get(3)),
+                bbox: [r.get(4), r.get(5), r.get(6), r.get(7)].map(|v: f64| (v * 1e4).round() / 1e4),
+            })
+            .collect())
+    }
+
+    async fn get_grid_population(
+        &self,
+        iso3: &str,
+        vintage: i32,
+    ) -> Result<CountryPopulationPayload, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT c.name, c.pop_est, r.grid_population, r.populated_cells, r.computed_at
+            FROM countries c
+            LEFT JOIN country_population r ON r.vintage = c.vintage AND r.iso_a3 = UPPER(TRIM(c.iso_a3))
+            WHERE UPPER(c.iso_a3) = $1 AND c.vintage = $2
+            ORDER BY c.sovereign DESC LIMIT 1
+        "#;
+
+        let row = client
+            .query_opt(sql, &[&iso3, &vintage])
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Country not found: {iso3}")))?;
+        let Some(grid_population) = row.get::<_, Option<f64>>(2) else {
+            return Err(AppError::NotFound(format!(
+                "Grid population for {iso3} has not been computed for vintage {vintage} \
+                 (run `make refresh-country-population`)"
+            )));
+        };
+
+        let pop_est: Option<i64> = row.get(1);
+        let grid_population = (grid_population * 10.0).round() / 10.0;
+        let estimate = pop_est.filter(|&p| p > 0).map(|p| p as f64);
+        Ok(CountryPopulationPayload {
+            iso_a3: iso3.to_string(),
+            name: row.get(0),
+            boundary_vintage: vintage,
+            grid_population,
+            populated_cells: row.get(3),
+            pop_est,
+            difference: estimate.map(|p| ((grid_population - p) * 10.0).round() / 10.0),
+            ratio: estimate.map(|p| (grid_population / p * 1000.0).round() / 1000.0),
+            computed_at: row.get(4),
+        })
+    }
+
+    async fn get_boundary_tile(
+        &self,
+        z: u32,
+        x: u32,
+        y: u32,
+        tolerance_m: f64,
+        vintage: i32,
+    ) -> Result<Vec<u8>, AppError> {
+        let client = &self.db.conn().await?;
+        // Clip in 4326 first: it keeps the transform cheap and drops Antarctica's
+        // pole, which has no Web Mercator coordinate.
+        let sql = r#"
+            WITH tile AS (
+                SELECT ST_TileEnvelope($1, $2, $3) AS env,
+                       ST_Transform(ST_TileEnvelope($1, $2, $3, margin => 64.0 / 4096), 4326) AS clip
+            ),
+            features AS (
+                SELECT ST_AsMVTGeom(
+                           ST_SimplifyPreserveTopology(
+                               ST_Transform(ST_ClipByBox2D(c.geom, tile.clip), 3857), $4
+                           ),
+                           tile.env, 4096, 64, true
+                       ) AS geom,
+                       TRIM(c.iso_a2) AS iso_a2, TRIM(c.iso_a3) AS iso_a3, c.name,
+                       c.continent, c.sovereign
+                FROM countries c, tile
+                WHERE c.vintage = $5 AND c.geom && tile.clip
+            )
+            SELECT ST_AsMVT(features.*, 'countries', 4096, 'geom')
+            FROM features
+            WHERE geom IS NOT NULL
+        "#;
+        let (z, x, y) = (z as i32, x as i32, y as i32);
+        let row = client.query_one(sql, &[&z, &x, &y, &tolerance_m, &vintage]).await?;
+        Ok(row.get::<_, Option<Vec<u8>>>(0).unwrap_or_default())
+    }
+
+    async fn get_outlines(
+        &self,
+        bbox: [f64; 4],
+        tolerance_deg: f64,
+        vintage: i32,
+    ) -> Result<Vec<Vec<[f64; 2]>>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            WITH clip AS (SELECT ST_MakeEnvelope($1, $2, $3, $4, 4326) AS env)
+            SELECT ST_AsGeoJSON(
+                       ST_SimplifyPreserveTopology(ST_ClipByBox2D(c.geom, clip.env), $5), 5
+                   )::json
+            FROM countries c, clip
+            WHERE c.vintage = $6 AND c.geom && clip.env
+        "#;
+        let [min_lon, min_lat, max_lon, max_lat] = bbox;
+        let rows = client
+            .query(sql, &[&min_lon, &min_lat, &max_lon, &max_lat, &tolerance_deg, &vintage])
+            .await?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.get::<_, Option<serde_json::Value>>(0))
+            .flat_map(|geometry| crate::geometry::rings(&geometry))
+            .collect())
+    }
+
+    async fn get_clipped_boundaries(&self, bbox: [f64; 4], vintage: i32) -> Result<Vec<CountryFeature>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            WITH clip AS (SELECT ST_MakeEnvelope($1, $2, $3, $4, 4326) AS env),
+            clipped AS (
+                SELECT TRIM(c.iso_a2) AS iso_a2, TRIM(c.iso_a3) AS iso_a3, c.name, c.continent,
+                       ST_Multi(ST_CollectionExtract(ST_ClipByBox2D(c.geom, clip.env), 3)) AS geom
+                FROM countries c, clip
+                WHERE c.vintage = $5 AND c.geom && clip.env
+            )
+            SELECT iso_a2, iso_a3, name, continent, ST_AsBinary(geom, 'NDR'),
+                   ST_XMin(geom), ST_YMin(geom), ST_XMax(geom), ST_YMax(geom)
+            FROM clipped
+            WHERE NOT ST_IsEmpty(geom)
+            ORDER BY name
+        "#;
+        let [min_lon, min_lat, max_lon, max_lat] = bbox;
+        let rows = client.query(sql, &[&min_lon, &min_lat, &max_lon, &max_lat, &vintage]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| CountryFeature {
+                iso_a2: row.get(0),
+                iso_a3: row.get(1),
+                name: row.get(2),
+                continent: row.get(3),
+                wkb: row.get(4),
+                envelope: [row.get(5), row.get(6), row.get(7), row.get(8)],
+            })
+            .collect())
+    }
+
+    async fn get_eez(&self, lat: f64, lon: f64) -> Result<Option<EezZone>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT mrgid, name, pol_type, territories, territory_iso3, sovereigns, sovereign_iso3, area_km2
+            FROM eez
+            WHERE ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
+            ORDER BY area_km2 NULLS LAST
+            LIMIT 1
+        "#;
+        let Some(row) = client.query_opt(sql, &[&lon, &lat]).await? else {
+            let loaded: bool = client.query_one("SELECT EXISTS (SELECT 1 FROM eez)", &[]).await?.get(0);
+            if !loaded {
+                return Err(AppError::NotFound("EEZ layer is not loaded (run `make ingest-eez`)".into()));
+            }
+            return Ok(None);
+        };
+
+        let territories: Vec<String> = row.get(3);
+        let territory_iso3: Vec<Option<String>> = row.get(4);
+        let sovereigns: Vec<String> = row.get(5);
+        let sovereign_iso3: Vec<Option<String>> = row.get(6);
+        let claimants = territories
+            .into_iter()
+            .zip(territory_iso3)
+            .zip(sovereigns.into_iter().zip(sovereign_iso3))
+            .map(|((territory, territory_iso3), (sovereign, sovereign_iso3))| EezClaimant {
+                territory,
+                territory_iso3,
+                sovereign,
+                sovereign_iso3,
+            })
+            .collect();
+        Ok(Some(EezZone {
+            mrgid: row.get(0),
+            name: row.get(1),
+            kind: row.get(2),
+            claimants,
+            area_km2: row.get(7),
+        }))
     }
 }
@@ -0,0 +1,146 @@
+use crate::errors::AppError;
+use crate::models::{AlertLevel, CoordinateInfo, EventPayload, JobStatus};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Object;
+use serde_json::Value;
+use tokio_postgres::Row;
+
+/// Alert levels from least to most severe, for comparing them in SQL.
+const ALERT_ORDER: &str = "ARRAY['green', 'orange', 'red']";
+
+/// One event as read from the GDACS feed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct EventRecord {
+    pub event_type: String,
+    pub event_id: i64,
+    pub episode_id: Option<i64>,
+    pub name: String,
+    pub alert_level: AlertLevel,
+    pub severity: Option<f64>,
+    pub severity_text: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    pub country: Option<String>,
+    pub report_url: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+pub(crate) struct EventRepository;
+
+impl EventRepository {
+    /// Records `event`. The first time it is seen, an analyse job with `params`
+    /// is queued for it and `true` returned; later episodes only update it.
+    pub async fn upsert(client: &Object, event: &EventRecord, params: &Value) -> Result<bool, AppError> {
+        let sql = r#"
+            WITH job AS (
+                INSERT INTO jobs (kind, params)
+                SELECT 'analyse', $14::jsonb
+                WHERE NOT EXISTS (SELECT 1 FROM events WHERE event_type = $1 AND event_id = $2)
+                RETURNING id
+            )
+            INSERT INTO events (event_type, event_id, episode_id, name, alert_level, severity, severity_text,
+                                lat, lon, country, report_url, started_at, ended_at, job_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, (SELECT id FROM job))
+            ON CONFLICT (event_type, event_id) DO UPDATE SET
+                episode_id = EXCLUDED.episode_id, name = EXCLUDED.name, alert_level = EXCLUDED.alert_level,
+                severity = EXCLUDED.severity, severity_text = EXCLUDED.severity_text,
+                country = EXCLUDED.country, report_url = EXCLUDED.report_url,
+                ended_at = EXCLUDED.ended_at, updated_at = now()
+            WHERE events.episode_id IS DISTINCT FROM EXCLUDED.episode_id
+               OR events.alert_level <> EXCLUDED.alert_level
+            RETURNING xmax = 0
+        "#;
+        let row = client
+            .query_opt(
+                sql,
+                &[
+                    &event.event_type,
+                    &event.event_id,
+                    &event.episode_id,
+                    &event.name,
+                    &event.alert_level.as_str(),
+                    &event.severity,
+                    &event.severity_text,
+                    &event.lat,
+                    &event.lon,
+                    &event.country,
+                    &event.report_url,
+                    &event.started_at,
+                    &event.ended_at,
+                    params,
+                ],
+            )
+            .await?;
+        Ok(row.is_some_and(|row| row.get(0)))
+    }
+
+    /// Copies the outcome of finished jobs onto their events, so it outlives
+    /// the jobs' retention.
+    pub async fn store_results(client: &Object) -> Result<u64, AppError> {
+        Ok(client
+            .execute(
+                "UPDATE events e SET status = j.status, result = j.result, error = j.error
+                 FROM jobs j
+                 WHERE j.id = e.job_id AND e.status IN ('queued', 'running')
+                 AND j.status IN ('succeeded', 'failed')",
+                &[],
+            )
+            .await?)
+    }
+
+    /// Up to `limit` events, most recent first, optionally of one type and at
+    /// `min_alert` or above.
+    pub async fn list(
+        client: &Object,
+        event_type: Option<&str>,
+        min_alert: AlertLevel,
+        limit: i64,
+    ) -> Result<Vec<EventPayload>, AppError> {
+        let sql = format!(
+            "SELECT e.event_type, e.event_id, e.episode_id, e.name, e.alert_level, e.severity,
+                    e.severity_text, e.lat, e.lon, e.country, e.report_url, e.started_at, e.ended_at,
+                    e.first_seen_at, COALESCE(j.status, e.status), COALESCE(e.result, j.result),
+                    COALESCE(e.error, j.error)
+             FROM events e LEFT JOIN jobs j ON j.id = e.job_id
+             WHERE ($1::text IS NULL OR e.event_type = $1)
+             AND array_position({ALERT_ORDER}, e.alert_level) >= array_position({ALERT_ORDER}, $2::text)
+             ORDER BY e.started_at DESC NULLS LAST, e.first_seen_at DESC
+             LIMIT $3"
+        );
+        client
+            .query(&sql, &[&event_type, &min_alert.as_str(), &limit])
+            .await?
+            .iter()
+            .map(to_payload)
+            .collect()
+    }
+}
+
+fn to_payload(row: &Row) -> Result<EventPayload, AppError> {
+    let event_type: String = row.get(0);
+    let event_id: i64 = row.get(1);
+    let alert: String = row.get(4);
+    let status: String = row.get(14);
+    Ok(EventPayload {
+        id: format!("{event_type}{event_id}"),
+        event_type,
+        event_id,
+        episode_id: row.get(2),
+        name: row.get(3),
+        alert_level: AlertLevel::parse(&alert)
+            .ok_or_else(|| AppError::Database(format!("unknown alert level '{alert}'")))?,
+        severity: row.get(5),
+        severity_text: row.get(6),
+        coordinate: CoordinateInfo { lat: row.get(7), lon: row.get(8) },
+        country: row.get(9),
+        report_url: row.get(10),
+        started_at: row.get(11),
+        ended_at: row.get(12),
+        first_seen_at: row.get(13),
+        status: JobStatus::parse(&status)
+            .ok_or_else(|| AppError::Database(format!("unknown job status '{status}'")))?,
+        result: row.get(15),
+        error: row.get(16),
+    })
+}
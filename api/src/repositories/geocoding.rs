@@ -1,36 +1,93 @@
+use super::{statements, PgDb};
+use crate::address::{self, AddressParts};
 use crate::errors::AppError;
-use crate::models::{CityHit, ExposedPlace, NearestPlace, ReversePayload};
-use deadpool_postgres::Object;
+use crate::geometry::{bearing_deg, compass_direction};
+use crate::geopackage::PlaceFeature;
+use crate::models::{
+    AdminArea, AdminCountry, AdminGroup, AdminLevel, AdminPlace, Airport, AirportType, CityHit, ExposedPlace, Facility, FacilityGroup, FacilityType, NearestPlace, PlaceSort, ReverseMode, ReversePayload, Runway, SortOrder,
+};
+use async_trait::async_trait;
 use std::collections::HashMap;
 
-pub(crate) struct GeocodingRepository;
+/// GeoNames feature class of every row in `geonames`: ingestion keeps only
+/// populated places (class `P`), so the class is not stored.
+const POPULATED_PLACE_CLASS: &str = "P";
+
+/// OurAirports publishes elevations and runway dimensions in feet.
+const FEET_TO_METRES: f64 = 0.3048;
+
+/// An administrative unit of `admin_boundaries`, its rows merged.
+pub(crate) struct AdminUnit {
+    pub level: i16,
+    /// GeoNames name of the code, else the boundary layer's own.
+    pub name: String,
+    pub country_code: Option<String>,
+    /// Boundary as a GeoJSON MultiPolygon.
+    pub geojson: String,
+    /// `[min_lon, min_lat, max_lon, max_lat]` of the boundary.
+    pub bounds: [f64; 4],
+}
 
-impl GeocodingRepository {
-    pub async fn reverse_geocode(
-        client: &Object,
-        lat: f64,
-        lon: f64,
-    ) -> Result<ReversePayload, AppError> {
-        let sql = r#"
-            SELECT g.geonameid, g.name, g.latitude, g.longitude,
-                   g.feature_code, g.country_code, g.admin1_code, g.admin2_code,
-                   a1.name, a2.name, c.name
-            FROM geonames g
-            LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
-            LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
-            LEFT JOIN countries c ON c.iso_a2 = g.country_code
-            ORDER BY g.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
-            LIMIT 1
-        "#;
+/// Filter and ordering of the places returned by
+/// [`GeocodingRepository::get_exposed_places`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PlaceOptions {
+    pub sort: PlaceSort,
+    pub order: SortOrder,
+    /// Places with a smaller GeoNames population (unknown counts as 0) are skipped.
+    pub min_population: i64,
+}
 
-        let row = client
-            .query_opt(sql, &[&lon, &lat])
-            .await?
-            .ok_or_else(|| AppError::NotFound("No nearby place found".into()))?;
+impl Default for PlaceOptions {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
 
-        Ok(Self::build_reverse_payload(&row))
+impl PlaceOptions {
+    /// Nearest first unless the request says otherwise; `order` defaults per sort field.
+    pub fn new(sort: Option<PlaceSort>, order: Option<SortOrder>) -> Self {
+        let sort = sort.unwrap_or_default();
+        Self { sort, order: order.unwrap_or(sort.default_order()), min_population: 0 }
     }
 
+    pub fn min_population(self, min_population: Option<i64>) -> Self {
+        Self { min_population: min_population.unwrap_or(0), ..self }
+    }
+
+    /// `ORDER BY` clause; ties fall back to distance so pages are stable.
+    fn sql(self) -> String {
+        let dir = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        match self.sort {
+            PlaceSort::Distance => format!("distance_km {dir}, g.geonameid"),
+            PlaceSort::Population => format!("COALESCE(g.population, 0) {dir}, distance_km, g.geonameid"),
+            PlaceSort::Name => format!("g.name {dir}, distance_km, g.geonameid"),
+        }
+    }
+}
+
+/// Place lookups over GeoNames.
+#[async_trait]
+pub(crate) trait GeocodingRepository: Send + Sync {
+    /// The nearest place, with `display_name` and `address` in the first of
+    /// `languages` GeoNames has names in (default names if none). With
+    /// `max_distance_km`, a nearest place further away is `OutOfRange`.
+    /// `mode` picks whether the address regions are the nearest place's or
+    /// those of the boundary polygons containing the point. Countries come
+    /// from boundary vintage `vintage`.
+    async fn reverse_geocode(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        max_distance_km: Option<f64>,
+        mode: ReverseMode,
+        vintage: i32,
+    ) -> Result<ReversePayload, AppError>;
+
     /// Fuzzy city search for Google-Places-style autocomplete.
     ///
     /// Strategy:
@@ -47,13 +104,244 @@ impl GeocodingRepository {
     ///   London (pop 9M) gets ~+0.75, enough to beat a population-0 village
     ///   that happens to share the exact typed string (e.g. "Londo" or
     ///   "Lononwei" vs "London").
-    pub async fn search_cities(
-        client: &Object,
+    async fn search_cities(
+        &self,
+        query: &str,
+        country: Option<&str>,
+        limit: i64,
+        min_population: i64,
+        vintage: i32,
+    ) -> Result<Vec<CityHit>, AppError>;
+
+    /// Find the single nearest named place globally (KNN, no radius limit) with distance and direction.
+    async fn find_nearest_place(
+        &self,
+        lat: f64,
+        lon: f64,
+        vintage: i32,
+    ) -> Result<NearestPlace, AppError>;
+
+    /// The `limit` nearest named places, nearest first, like `find_nearest_place`.
+    async fn find_nearest_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        limit: i64,
+        vintage: i32,
+    ) -> Result<Vec<NearestPlace>, AppError>;
+
+    /// The `limit` nearest facilities with the given GeoNames feature code,
+    /// nearest first. Empty before facilities are ingested.
+    async fn find_nearest_facilities(
+        &self,
+        lat: f64,
+        lon: f64,
+        feature_code: &str,
+        limit: i64,
+    ) -> Result<Vec<Facility>, AppError>;
+
+    /// Facilities of each of `types` within the radius, one group per type in
+    /// the order given: the full count and the `limit` nearest.
+    async fn find_facilities_within(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        types: &[FacilityType],
+        limit: i64,
+    ) -> Result<Vec<FacilityGroup>, AppError>;
+
+    /// The `limit` nearest airports, nearest first, with their open runways.
+    /// With `min_runway_m`, only airports with a runway at least that long.
+    async fn find_nearest_airports(
+        &self,
+        lat: f64,
+        lon: f64,
+        min_runway_m: Option<f64>,
+        limit: i64,
+    ) -> Result<Vec<Airport>, AppError>;
+
+    /// IANA timezone of the nearest place that has one; `None` before places
+    /// are ingested with timezones.
+    async fn find_timezone(&self, lat: f64, lon: f64) -> Result<Option<String>, AppError>;
+
+    /// Places within the radius with at least `min_population` inhabitants.
+    async fn count_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        min_population: i64,
+    ) -> Result<i64, AppError>;
+
+    /// `count_exposed_places` per GeoNames country code.
+    async fn count_exposed_places_by_country(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        min_population: i64,
+    ) -> Result<HashMap<String, i64>, AppError>;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        options: PlaceOptions,
+        limit: i64,
+        offset: i64,
+        vintage: i32,
+    ) -> Result<Vec<ExposedPlace>, AppError>;
+
+    /// Places within the radius with at least `min_population` inhabitants,
+    /// counted and summed per region at `level`, largest population first.
+    async fn group_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        level: AdminLevel,
+        min_population: i64,
+    ) -> Result<Vec<AdminGroup>, AppError>;
+
+    /// Up to `limit` places inside `bbox` (`[min_lon, min_lat, max_lon, max_lat]`),
+    /// most populous first.
+    async fn get_places_in_bbox(&self, bbox: [f64; 4], limit: i64) -> Result<Vec<PlaceFeature>, AppError>;
+
+    /// The country of boundary vintage `vintage` and admin units whose
+    /// boundaries contain the point, named in the first of `languages`
+    /// GeoNames has a name in.
+    async fn admin_hierarchy(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        vintage: i32,
+    ) -> Result<(Option<AdminCountry>, Option<AdminArea>, Option<AdminArea>), AppError>;
+
+    /// The boundary of the admin unit with GeoNames code `code` in boundary
+    /// vintage `vintage`; `None` when no boundary of that vintage carries it.
+    async fn get_admin_unit(&self, code: &str, vintage: i32) -> Result<Option<AdminUnit>, AppError>;
+
+    /// The `limit` most populous places inside the `vintage` boundary of
+    /// admin unit `code`.
+    async fn get_admin_places(&self, code: &str, limit: i64, vintage: i32) -> Result<Vec<AdminPlace>, AppError>;
+}
+
+/// `GeocodingRepository` backed by the GeoNames tables in PostGIS.
+pub(crate) struct PgGeocodingRepository {
+    db: PgDb,
+}
+
+impl PgGeocodingRepository {
+    pub fn new(db: PgDb) -> Self {
+        Self { db }
+    }
+
+    fn feature_code_to_address_key(code: &str) -> &'static str {
+        match code {
+            "PPLC" | "PPLA" | "PPLA2" | "PPL" => "city",
+            "PPLA3" | "PPLA4" => "town",
+            "PPLX" | "PPLL" | "PPLF" => "village",
+            _ => "municipality",
+        }
+    }
+
+    fn build_address(
+        row: &tokio_postgres::Row,
+        name: &str,
+        fc: &str,
+        cc: &str,
+    ) -> (String, HashMap<String, String>) {
+        let admin1: Option<String> = row.get(8);
+        let admin2: Option<String> = row.get(9);
+        let country: Option<String> = row.get(10);
+
+        let display_name = address::display_name(cc, &AddressParts {
+            place: name,
+            district: admin2.as_deref(),
+            state: admin1.as_deref(),
+            country: country.as_deref(),
+        });
+
+        let mut address = HashMap::with_capacity(5);
+        address.insert(Self::feature_code_to_address_key(fc).into(), name.to_string());
+        if let Some(a2) = admin2 { address.insert("district".into(), a2); }
+        if let Some(a1) = admin1 { address.insert("state".into(), a1); }
+        if let Some(cn) = country { address.insert("country".into(), cn); }
+        if !cc.is_empty() { address.insert("country_code".into(), cc.to_lowercase()); }
+
+        (display_name, address)
+    }
+
+    fn build_reverse_payload(row: &tokio_postgres::Row) -> ReversePayload {
+        let name: String = row.get(1);
+        let local_name = row.get::<_, Option<String>>(12);
+        let fc = row.get::<_, Option<String>>(4).unwrap_or_default();
+        let cc = row.get::<_, Option<String>>(5).unwrap_or_default();
+        let (display_name, address) =
+            Self::build_address(row, local_name.as_deref().unwrap_or(&name), &fc, &cc);
+
+        ReversePayload {
+            place_id: row.get(0),
+            lat: format!("{}", row.get::<_, f64>(2)),
+            lon: format!("{}", row.get::<_, f64>(3)),
+            name,
+            display_name,
+            address,
+            population: row.get(11),
+            feature_class: Self::feature_class(&fc),
+            feature_code: (!fc.is_empty()).then_some(fc),
+        }
+    }
+
+    fn feature_class(fc: &str) -> Option<String> {
+        (!fc.is_empty()).then(|| POPULATED_PLACE_CLASS.into())
+    }
+}
+
+#[async_trait]
+impl GeocodingRepository for PgGeocodingRepository {
+    async fn reverse_geocode(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        max_distance_km: Option<f64>,
+        mode: ReverseMode,
+        vintage: i32,
+    ) -> Result<ReversePayload, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = match mode {
+            ReverseMode::Nearest => statements::REVERSE_GEOCODE,
+            ReverseMode::Contains => statements::REVERSE_GEOCODE_CONTAINED,
+        };
+        let stmt = client.prepare_cached(sql).await?;
+
+        let row = client
+            .query_opt(&stmt, &[&lon, &lat, &languages, &vintage])
+            .await?
+            .ok_or_else(|| AppError::NotFound("No nearby place found".into()))?;
+
+        let distance_km: f64 = row.get(13);
+        if let Some(max_km) = max_distance_km.filter(|&max_km| distance_km > max_km) {
+            return Err(AppError::OutOfRange { max_km, nearest_km: round1(distance_km) });
+        }
+
+        Ok(Self::build_reverse_payload(&row))
+    }
+
+    async fn search_cities(
+        &self,
         query: &str,
         country: Option<&str>,
         limit: i64,
         min_population: i64,
+        vintage: i32,
     ) -> Result<Vec<CityHit>, AppError> {
+        let client = &self.db.conn().await?;
         let use_fuzzy = query.chars().count() >= 4;
 
         if use_fuzzy {
@@ -105,7 +393,7 @@ impl GeocodingRepository {
             FROM geonames g
             LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
             LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
-            LEFT JOIN countries   c  ON c.iso_a2 = g.country_code
+            LEFT JOIN countries   c  ON c.iso_a2 = g.country_code AND c.vintage = $5
             WHERE g.feature_code IN ('PPLC','PPLA','PPLA2','PPLA3','PPLA4','PPL','PPLG')
               AND COALESCE(g.population, 0) >= $4
               AND ($2::char(2) IS NULL OR g.country_code = $2)
@@ -120,7 +408,7 @@ impl GeocodingRepository {
         let rows = client
             .query(
                 sql.as_str(),
-                &[&query, &country_param, &limit, &min_population],
+                &[&query, &country_param, &limit, &min_population, &vintage],
             )
             .await?;
 
@@ -138,10 +426,12 @@ impl GeocodingRepository {
                 let population: i64 = row.get(11);
                 let score: f64 = row.get(12);
 
-                let mut parts = vec![name.clone()];
-                if let Some(ref a1) = admin1 { parts.push(a1.clone()); }
-                if let Some(ref cn) = country_name { parts.push(cn.clone()); }
-                let display_name = parts.join(", ");
+                let display_name = address::display_name(cc.as_deref().unwrap_or_default(), &AddressParts {
+                    place: &name,
+                    state: admin1.as_deref(),
+                    country: country_name.as_deref(),
+                    ..Default::default()
+                });
 
                 let bbox = bbox_from_population(lat, lon, population);
 
@@ -164,88 +454,282 @@ impl GeocodingRepository {
             .collect())
     }
 
-    /// Find the single nearest named place globally (KNN, no radius limit) with distance and direction.
-    pub async fn find_nearest_place(
-        client: &Object,
+    async fn find_nearest_place(
+        &self,
         lat: f64,
         lon: f64,
+        vintage: i32,
     ) -> Result<NearestPlace, AppError> {
-        let sql = r#"
-            SELECT g.geonameid, g.name, g.latitude, g.longitude,
-                   g.feature_code, g.country_code, g.admin1_code, g.admin2_code,
-                   a1.name, a2.name, c.name,
-                   ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
-            FROM geonames g
-            LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
-            LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
-            LEFT JOIN countries c ON c.iso_a2 = g.country_code
-            ORDER BY g.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
-            LIMIT 1
-        "#;
-
-        let row = client
-            .query_opt(sql, &[&lon, &lat])
+        self.find_nearest_places(lat, lon, 1, vintage)
             .await?
-            .ok_or_else(|| AppError::NotFound("No nearby place found".into()))?;
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::NotFound("No nearby place found".into()))
+    }
 
-        let name: String = row.get(1);
-        let place_lat: f64 = row.get(2);
-        let place_lon: f64 = row.get(3);
-        let fc = row.get::<_, Option<String>>(4).unwrap_or_default();
-        let cc = row.get::<_, Option<String>>(5).unwrap_or_default();
-        let (display_name, address) = Self::build_address(&row, &name, &fc, &cc);
-        let bearing = bearing_deg(lat, lon, place_lat, place_lon);
+    async fn find_nearest_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        limit: i64,
+        vintage: i32,
+    ) -> Result<Vec<NearestPlace>, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::NEAREST_PLACES).await?;
+        let rows = client.query(&stmt, &[&lon, &lat, &limit, &vintage]).await?;
 
-        Ok(NearestPlace {
-            place_id: row.get(0),
-            name,
-            display_name,
-            address,
-            distance_km: round2(row.get::<_, f64>(11)),
-            direction: compass_direction(bearing),
-            bearing_deg: round1(bearing),
-        })
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let name: String = row.get(1);
+                let place_lat: f64 = row.get(2);
+                let place_lon: f64 = row.get(3);
+                let fc = row.get::<_, Option<String>>(4).unwrap_or_default();
+                let cc = row.get::<_, Option<String>>(5).unwrap_or_default();
+                let (display_name, address) = Self::build_address(row, &name, &fc, &cc);
+                let bearing = bearing_deg(lat, lon, place_lat, place_lon);
+
+                NearestPlace {
+                    place_id: row.get(0),
+                    name,
+                    display_name,
+                    address,
+                    distance_km: round2(row.get::<_, f64>(11)),
+                    direction: compass_direction(bearing),
+                    bearing_deg: round1(bearing),
+                    population: row.get(12),
+                    feature_class: Self::feature_class(&fc),
+                    feature_code: (!fc.is_empty()).then_some(fc),
+                }
+            })
+            .collect())
     }
 
-    pub async fn count_exposed_places(
-        client: &Object,
+    async fn find_nearest_facilities(
+        &self,
+        lat: f64,
+        lon: f64,
+        feature_code: &str,
+        limit: i64,
+    ) -> Result<Vec<Facility>, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::NEAREST_FACILITIES).await?;
+        let rows = client.query(&stmt, &[&lon, &lat, &feature_code, &limit]).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let facility_lat: f64 = row.get(4);
+                let facility_lon: f64 = row.get(5);
+                let bearing = bearing_deg(lat, lon, facility_lat, facility_lon);
+
+                Facility {
+                    facility_id: row.get(0),
+                    name: row.get(1),
+                    feature_code: row.get(2),
+                    country_code: row.get(3),
+                    lat: facility_lat,
+                    lon: facility_lon,
+                    distance_km: round2(row.get::<_, f64>(6)),
+                    direction: compass_direction(bearing),
+                    bearing_deg: round1(bearing),
+                }
+            })
+            .collect())
+    }
+
+    async fn find_facilities_within(
+        &self,
         lat: f64,
         lon: f64,
         radius_km: f64,
+        types: &[FacilityType],
+        limit: i64,
+    ) -> Result<Vec<FacilityGroup>, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::FACILITIES_WITHIN).await?;
+        let codes: Vec<&str> = types.iter().map(|t| t.feature_code()).collect();
+        let rows = client.query(&stmt, &[&lon, &lat, &radius_km, &codes, &limit]).await?;
+
+        let mut groups: Vec<FacilityGroup> = types
+            .iter()
+            .map(|&facility_type| FacilityGroup { facility_type, count: 0, facilities: Vec::new() })
+            .collect();
+        for row in &rows {
+            let feature_code: String = row.get(2);
+            let Some(group) = groups.iter_mut().find(|g| g.facility_type.feature_code() == feature_code) else {
+                continue;
+            };
+            let facility_lat: f64 = row.get(4);
+            let facility_lon: f64 = row.get(5);
+            let bearing = bearing_deg(lat, lon, facility_lat, facility_lon);
+
+            group.count = row.get(7);
+            group.facilities.push(Facility {
+                facility_id: row.get(0),
+                name: row.get(1),
+                feature_code,
+                country_code: row.get(3),
+                lat: facility_lat,
+                lon: facility_lon,
+                distance_km: round2(row.get::<_, f64>(6)),
+                direction: compass_direction(bearing),
+                bearing_deg: round1(bearing),
+            });
+        }
+        Ok(groups)
+    }
+
+    async fn find_nearest_airports(
+        &self,
+        lat: f64,
+        lon: f64,
+        min_runway_m: Option<f64>,
+        limit: i64,
+    ) -> Result<Vec<Airport>, AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::NEAREST_AIRPORTS).await?;
+        let min_runway_ft = min_runway_m.map(|m| m / FEET_TO_METRES);
+        let rows = client.query(&stmt, &[&lon, &lat, &min_runway_ft, &limit]).await?;
+
+        let mut airports: Vec<Airport> = rows
+            .iter()
+            .filter_map(|row| {
+                let airport_lat: f64 = row.get(8);
+                let airport_lon: f64 = row.get(9);
+                let bearing = bearing_deg(lat, lon, airport_lat, airport_lon);
+
+                Some(Airport {
+                    airport_id: row.get(0),
+                    ident: row.get(1),
+                    airport_type: AirportType::from_ourairports(row.get(2))?,
+                    name: row.get(3),
+                    icao_code: row.get(4),
+                    iata_code: row.get(5),
+                    municipality: row.get(6),
+                    country_code: row.get(7),
+                    lat: airport_lat,
+                    lon: airport_lon,
+                    elevation_m: row.get::<_, Option<i32>>(10).map(feet_to_metres),
+                    scheduled_service: row.get(11),
+                    distance_km: round2(row.get::<_, f64>(12)),
+                    direction: compass_direction(bearing),
+                    bearing_deg: round1(bearing),
+                    longest_runway_m: None,
+                    runways: Vec::new(),
+                })
+            })
+            .collect();
+        if airports.is_empty() {
+            return Ok(airports);
+        }
+
+        let ids: Vec<i32> = airports.iter().map(|a| a.airport_id).collect();
+        let stmt = client.prepare_cached(statements::AIRPORT_RUNWAYS).await?;
+        let mut runways: HashMap<i32, Vec<Runway>> = HashMap::new();
+        for row in client.query(&stmt, &[&ids]).await? {
+            let ends: Vec<String> = [row.get::<_, Option<String>>(1), row.get(2)].into_iter().flatten().collect();
+            runways.entry(row.get(0)).or_default().push(Runway {
+                designator: (!ends.is_empty()).then(|| ends.join("/")),
+                length_m: row.get::<_, Option<i32>>(3).map(feet_to_metres),
+                width_m: row.get::<_, Option<i32>>(4).map(feet_to_metres),
+                surface: row.get(5),
+                lighted: row.get(6),
+            });
+        }
+        for airport in &mut airports {
+            airport.runways = runways.remove(&airport.airport_id).unwrap_or_default();
+            airport.longest_runway_m = airport.runways.iter().filter_map(|r| r.length_m).max();
+        }
+        Ok(airports)
+    }
+
+    async fn count_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        min_population: i64,
     ) -> Result<i64, AppError> {
+        let client = &self.db.conn().await?;
         let sql = r#"
             SELECT COUNT(*)::bigint
             FROM geonames g
             WHERE ST_DWithin(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
+              AND COALESCE(g.population, 0) >= $4
         "#;
-        let row = client.query_one(sql, &[&lon, &lat, &(radius_km * 1000.0)]).await?;
+        let row = client
+            .query_one(sql, &[&lon, &lat, &(radius_km * 1000.0), &min_population])
+            .await?;
         Ok(row.get(0))
     }
 
-    pub async fn get_exposed_places(
-        client: &Object,
+    async fn find_timezone(&self, lat: f64, lon: f64) -> Result<Option<String>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT timezone FROM geonames
+            WHERE timezone IS NOT NULL
+            ORDER BY geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+            LIMIT 1
+        "#;
+        let row = client.query_opt(sql, &[&lon, &lat]).await?;
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    async fn count_exposed_places_by_country(
+        &self,
         lat: f64,
         lon: f64,
         radius_km: f64,
+        min_population: i64,
+    ) -> Result<HashMap<String, i64>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT g.country_code, COUNT(*)::bigint
+            FROM geonames g
+            WHERE ST_DWithin(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
+              AND COALESCE(g.population, 0) >= $4
+              AND g.country_code IS NOT NULL
+            GROUP BY g.country_code
+        "#;
+        let rows = client
+            .query(sql, &[&lon, &lat, &(radius_km * 1000.0), &min_population])
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get::<_, String>(0).trim().to_string(), row.get(1)))
+            .collect())
+    }
+
+    async fn get_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        options: PlaceOptions,
         limit: i64,
         offset: i64,
+        vintage: i32,
     ) -> Result<Vec<ExposedPlace>, AppError> {
-        let sql = r#"
+        let client = &self.db.conn().await?;
+        let sql = format!(r#"
             SELECT g.geonameid, g.name, g.latitude, g.longitude,
                    g.feature_code, g.country_code, g.admin1_code, g.admin2_code,
                    a1.name, a2.name, c.name,
-                   ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
+                   ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0 AS distance_km,
+                   COALESCE(g.population, 0)
             FROM geonames g
             LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
             LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
-            LEFT JOIN countries c ON c.iso_a2 = g.country_code
+            LEFT JOIN countries c ON c.iso_a2 = g.country_code AND c.vintage = $7
             WHERE ST_DWithin(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
-            ORDER BY ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography)
+              AND COALESCE(g.population, 0) >= $6
+            ORDER BY {}
             LIMIT $4 OFFSET $5
-        "#;
+        "#, options.sql());
 
         let rows = client
-            .query(sql, &[&lon, &lat, &(radius_km * 1000.0), &limit, &offset])
+            .query(&sql, &[&lon, &lat, &(radius_km * 1000.0), &limit, &offset, &options.min_population, &vintage])
             .await?;
 
         Ok(rows
@@ -269,63 +753,160 @@ impl GeocodingRepository {
                     distance_km: round2(row.get::<_, f64>(11)),
                     direction: compass_direction(bearing),
                     bearing_deg: round1(bearing),
+                    population: row.get(12),
+                    feature_class: Self::feature_class(&fc),
+                    feature_code: (!fc.is_empty()).then_some(fc),
                 }
             })
             .collect())
     }
 
-    fn feature_code_to_address_key(code: &str) -> &'static str {
-        match code {
-            "PPLC" | "PPLA" | "PPLA2" | "PPL" => "city",
-            "PPLA3" | "PPLA4" => "town",
-            "PPLX" | "PPLL" | "PPLF" => "village",
-            _ => "municipality",
-        }
-    }
+    async fn group_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        level: AdminLevel,
+        min_population: i64,
+    ) -> Result<Vec<AdminGroup>, AppError> {
+        let client = &self.db.conn().await?;
+        let (admin2_cols, admin2_group) = match level {
+            AdminLevel::Admin1 => ("NULL::text, NULL::text", ""),
+            AdminLevel::Admin2 => ("g.admin2_code, a2.name", ", g.admin2_code, a2.name"),
+        };
+        let sql = format!(r#"
+            SELECT g.country_code::text, g.admin1_code, a1.name, {admin2_cols},
+                   COUNT(*)::bigint AS place_count,
+                   SUM(COALESCE(g.population, 0))::bigint AS place_population
+            FROM geonames g
+            LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
+            LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
+            WHERE ST_DWithin(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography, $3)
+              AND COALESCE(g.population, 0) >= $4
+            GROUP BY g.country_code, g.admin1_code, a1.name{admin2_group}
+            ORDER BY place_population DESC, place_count DESC
+        "#);
 
-    fn build_address(
-        row: &tokio_postgres::Row,
-        name: &str,
-        fc: &str,
-        cc: &str,
-    ) -> (String, HashMap<String, String>) {
-        let admin1: Option<String> = row.get(8);
-        let admin2: Option<String> = row.get(9);
-        let country: Option<String> = row.get(10);
+        let rows = client
+            .query(&sql, &[&lon, &lat, &(radius_km * 1000.0), &min_population])
+            .await?;
 
-        let mut parts = vec![name.to_string()];
-        if let Some(ref a2) = admin2 { parts.push(a2.clone()); }
-        if let Some(ref a1) = admin1 { parts.push(a1.clone()); }
-        if let Some(ref cn) = country { parts.push(cn.clone()); }
-        let display_name = parts.join(", ");
+        Ok(rows
+            .iter()
+            .map(|row| AdminGroup {
+                country_code: row.get(0),
+                admin1_code: row.get(1),
+                admin1: row.get(2),
+                admin2_code: row.get(3),
+                admin2: row.get(4),
+                place_count: row.get(5),
+                place_population: row.get(6),
+            })
+            .collect())
+    }
 
-        let mut address = HashMap::with_capacity(5);
-        address.insert(Self::feature_code_to_address_key(fc).into(), name.to_string());
-        if let Some(a2) = admin2 { address.insert("district".into(), a2); }
-        if let Some(a1) = admin1 { address.insert("state".into(), a1); }
-        if let Some(cn) = country { address.insert("country".into(), cn); }
-        if !cc.is_empty() { address.insert("country_code".into(), cc.to_lowercase()); }
+    async fn get_places_in_bbox(&self, bbox: [f64; 4], limit: i64) -> Result<Vec<PlaceFeature>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT geonameid, name, feature_code, TRIM(country_code), COALESCE(population, 0), latitude, longitude
+            FROM geonames
+            WHERE geom && ST_MakeEnvelope($1, $2, $3, $4, 4326)
+            ORDER BY COALESCE(population, 0) DESC, geonameid
+            LIMIT $5
+        "#;
+        let [min_lon, min_lat, max_lon, max_lat] = bbox;
+        let rows = client.query(sql, &[&min_lon, &min_lat, &max_lon, &max_lat, &limit]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| PlaceFeature {
+                geonameid: row.get(0),
+                name: row.get(1),
+                feature_code: row.get(2),
+                country_code: row.get(3),
+                population: row.get(4),
+                lat: row.get(5),
+                lon: row.get(6),
+            })
+            .collect())
+    }
 
-        (display_name, address)
+    async fn admin_hierarchy(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        vintage: i32,
+    ) -> Result<(Option<AdminCountry>, Option<AdminArea>, Option<AdminArea>), AppError> {
+        let client = &self.db.conn().await?;
+        let stmt = client.prepare_cached(statements::ADMIN_HIERARCHY).await?;
+        let row = client.query_one(&stmt, &[&lon, &lat, &languages, &vintage]).await?;
+
+        let country = row.get::<_, Option<String>>(2).map(|name| AdminCountry {
+            iso_a2: row.get(0),
+            iso_a3: row.get(1),
+            name,
+        });
+        let area = |code: usize| {
+            row.get::<_, Option<String>>(code + 1).map(|name| AdminArea { code: row.get(code), name })
+        };
+        Ok((country, area(3), area(5)))
     }
 
-    fn build_reverse_payload(row: &tokio_postgres::Row) -> ReversePayload {
-        let name: String = row.get(1);
-        let fc = row.get::<_, Option<String>>(4).unwrap_or_default();
-        let cc = row.get::<_, Option<String>>(5).unwrap_or_default();
-        let (display_name, address) = Self::build_address(row, &name, &fc, &cc);
+    async fn get_admin_unit(&self, code: &str, vintage: i32) -> Result<Option<AdminUnit>, AppError> {
+        let client = &self.db.conn().await?;
+        // A layer may split a unit over several rows (islands, exclaves).
+        let sql = r#"
+            WITH b AS (
+                SELECT MIN(level) AS level, MIN(country_code) AS country_code, MIN(name) AS name,
+                       ST_Multi(ST_Union(geom)) AS geom
+                FROM admin_boundaries
+                WHERE code = $1 AND vintage = $2
+            )
+            SELECT b.level, COALESCE(a1.name, a2.name, b.name), TRIM(b.country_code), ST_AsGeoJSON(b.geom, 6),
+                   ST_XMin(b.geom), ST_YMin(b.geom), ST_XMax(b.geom), ST_YMax(b.geom)
+            FROM b
+            LEFT JOIN admin1_codes a1 ON b.level = 1 AND a1.code = $1
+            LEFT JOIN admin2_codes a2 ON b.level = 2 AND a2.code = $1
+            WHERE b.geom IS NOT NULL
+        "#;
+        let row = client.query_opt(sql, &[&code, &vintage]).await?;
+        Ok(row.map(|row| AdminUnit {
+            level: row.get(0),
+            name: row.get(1),
+            country_code: row.get(2),
+            geojson: row.get(3),
+            bounds: [row.get(4), row.get(5), row.get(6), row.get(7)],
+        }))
+    }
 
-        ReversePayload {
-            place_id: row.get(0),
-            lat: format!("{}", row.get::<_, f64>(2)),
-            lon: format!("{}", row.get::<_, f64>(3)),
-            name,
-            display_name,
-            address,
-        }
+    async fn get_admin_places(&self, code: &str, limit: i64, vintage: i32) -> Result<Vec<AdminPlace>, AppError> {
+        let client = &self.db.conn().await?;
+        let sql = r#"
+            SELECT g.geonameid, g.name, g.feature_code, COALESCE(g.population, 0), g.latitude, g.longitude
+            FROM geonames g
+            JOIN admin_boundaries b ON b.code = $1 AND b.vintage = $3 AND ST_Contains(b.geom, g.geom)
+            ORDER BY COALESCE(g.population, 0) DESC, g.geonameid
+            LIMIT $2
+        "#;
+        let rows = client.query(sql, &[&code, &limit, &vintage]).await?;
+        Ok(rows
+            .iter()
+            .map(|row| AdminPlace {
+                place_id: row.get(0),
+                name: row.get(1),
+                feature_code: row.get(2),
+                population: row.get(3),
+                lat: row.get(4),
+                lon: row.get(5),
+            })
+            .collect())
     }
 }
 
+fn feet_to_metres(ft: i32) -> i32 {
+    (f64::from(ft) * FEET_TO_METRES).round() as i32
+}
+
 #[inline]
 fn round1(v: f64) -> f64 {
     (v * 10.0).round() / 10.0
@@ -358,17 +939,3 @@ fn bbox_from_population(lat: f64, lon: f64, population: i64) -> [f64; 4] {
     ]
 }
 
-/// Compute initial bearing (forward azimuth) from point 1 to point 2 in degrees (0–360).
-fn bearing_deg(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
-    let d_lon = (lon2 - lon1).to_radians();
-    let x = d_lon.sin() * lat2.cos();
-    let y = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
-    (x.atan2(y).to_degrees() + 360.0) % 360.0
-}
-
-/// Convert a bearing in degrees to an 8-point compass direction.
-fn compass_direction(deg: f64) -> String {
-    const DIRS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
-    DIRS[((deg + 22.5) % 360.0 / 45.0) as usize].into()
-}
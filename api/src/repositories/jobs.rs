@@ -0,0 +1,212 @@
+use crate::errors::AppError;
+use crate::models::{CallbackStatus, JobCallback, JobKind, JobPayload, JobStatus};
+use deadpool_postgres::Object;
+use serde_json::Value;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+const JOB_COLUMNS: &str = "id, kind, status, attempts, created_at, started_at, finished_at, \
+    result, error, callback_url, callback_status, callback_attempts, callback_error";
+
+/// Marks a finished job's webhook as due, if it has one.
+const QUEUE_CALLBACK: &str = "callback_status = CASE WHEN callback_url IS NULL THEN NULL ELSE 'pending' END, \
+    callback_next_at = CASE WHEN callback_url IS NULL THEN NULL ELSE now() END";
+
+/// A job claimed by a worker, with what it needs to run.
+pub(crate) struct ClaimedJob {
+    pub id: Uuid,
+    pub kind: JobKind,
+    pub params: Value,
+    pub include: Option<String>,
+}
+
+pub(crate) struct JobRepository;
+
+impl JobRepository {
+    pub async fn create(
+        client: &Object,
+        kind: JobKind,
+        params: &Value,
+        include: Option<&str>,
+        callback_url: Option<&str>,
+    ) -> Result<JobPayload, AppError> {
+        let sql = format!(
+            "INSERT INTO jobs (kind, params, include, callback_url) VALUES ($1, $2, $3, $4) \
+             RETURNING {JOB_COLUMNS}"
+        );
+        let row = client
+            .query_one(&sql, &[&kind.as_str(), params, &include, &callback_url])
+            .await?;
+        to_payload(&row)
+    }
+
+    pub async fn get(client: &Object, id: Uuid) -> Result<Option<JobPayload>, AppError> {
+        let sql = format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = $1");
+        client.query_opt(&sql, &[&id]).await?.as_ref().map(to_payload).transpose()
+    }
+
+    /// Claims the oldest queued job, or a `running` one whose worker has been
+    /// silent for `stale_after_secs` and still has attempts left. `SKIP LOCKED`
+    /// lets concurrent workers (and replicas) claim different jobs.
+    pub async fn claim(
+        client: &Object,
+        stale_after_secs: f64,
+        max_attempts: i16,
+    ) -> Result<Option<ClaimedJob>, AppError> {
+        let sql = r#"
+            UPDATE jobs SET status = 'running', started_at = now(), attempts = attempts + 1
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE (status = 'queued'
+                       OR (status = 'running' AND started_at < now() - make_interval(secs => $1)))
+                AND attempts < $2
+                ORDER BY created_at
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING id, kind, params, include
+        "#;
+        let Some(row) = client.query_opt(sql, &[&stale_after_secs, &max_attempts]).await? else {
+            return Ok(None);
+        };
+        let kind: String = row.get(1);
+        Ok(Some(ClaimedJob {
+            id: row.get(0),
+            kind: JobKind::parse(&kind)
+                .ok_or_else(|| AppError::Database(format!("unknown job kind '{kind}'")))?,
+            params: row.get(2),
+            include: row.get(3),
+        }))
+    }
+
+    pub async fn succeed(client: &Object, id: Uuid, result: &Value) -> Result<(), AppError> {
+        let sql = format!(
+            "UPDATE jobs SET status = 'succeeded', result = $2, finished_at = now(), {QUEUE_CALLBACK} \
+             WHERE id = $1"
+        );
+        client.execute(&sql, &[&id, result]).await?;
+        Ok(())
+    }
+
+    pub async fn fail(client: &Object, id: Uuid, error: &str) -> Result<(), AppError> {
+        let sql = format!(
+            "UPDATE jobs SET status = 'failed', error = $2, finished_at = now(), {QUEUE_CALLBACK} \
+             WHERE id = $1"
+        );
+        client.execute(&sql, &[&id, &error]).await?;
+        Ok(())
+    }
+
+    /// Leases up to `limit` callbacks that are due, pushing their next attempt
+    /// `lease_secs` out so a crashed sender's deliveries are retried later.
+    /// Returns each job with its callback URL.
+    pub async fn claim_callbacks(
+        client: &Object,
+        lease_secs: f64,
+        limit: i64,
+    ) -> Result<Vec<(JobPayload, String)>, AppError> {
+        let sql = format!(
+            "UPDATE jobs SET callback_next_at = now() + make_interval(secs => $1), \
+                             callback_attempts = callback_attempts + 1
+             WHERE id IN (
+                 SELECT id FROM jobs
+                 WHERE callback_status = 'pending' AND callback_next_at <= now()
+                 ORDER BY callback_next_at
+                 FOR UPDATE SKIP LOCKED
+                 LIMIT $2
+             )
+             RETURNING {JOB_COLUMNS}"
+        );
+        client
+            .query(&sql, &[&lease_secs, &limit])
+            .await?
+            .iter()
+            .map(|row| Ok((to_payload(row)?, row.get(9))))
+            .collect()
+    }
+
+    pub async fn callback_delivered(client: &Object, id: Uuid) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE jobs SET callback_status = 'delivered', callback_next_at = NULL, \
+                 callback_error = NULL WHERE id = $1",
+                &[&id],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Records a failed delivery: retried after `retry_in_secs`, or given up
+    /// for good when `None`.
+    pub async fn callback_failed(
+        client: &Object,
+        id: Uuid,
+        error: &str,
+        retry_in_secs: Option<f64>,
+    ) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE jobs SET callback_error = $2,
+                     callback_status = CASE WHEN $3::float8 IS NULL THEN 'failed' ELSE 'pending' END,
+                     callback_next_at = now() + make_interval(secs => $3::float8)
+                 WHERE id = $1",
+                &[&id, &error, &retry_in_secs],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Fails jobs that were reclaimed after a worker died until they ran out of
+    /// attempts, so they don't stay `running` forever.
+    pub async fn fail_exhausted(
+        client: &Object,
+        stale_after_secs: f64,
+        max_attempts: i16,
+    ) -> Result<u64, AppError> {
+        Ok(client
+            .execute(
+                "UPDATE jobs SET status = 'failed', error = 'job did not complete', finished_at = now()
+                 WHERE status = 'running' AND attempts >= $2
+                 AND started_at < now() - make_interval(secs => $1)",
+                &[&stale_after_secs, &max_attempts],
+            )
+            .await?)
+    }
+
+    /// Deletes finished jobs older than `hours`.
+    pub async fn purge_finished(client: &Object, hours: i32) -> Result<u64, AppError> {
+        Ok(client
+            .execute(
+                "DELETE FROM jobs WHERE finished_at < now() - make_interval(hours => $1)",
+                &[&hours],
+            )
+            .await?)
+    }
+}
+
+fn to_payload(row: &Row) -> Result<JobPayload, AppError> {
+    let kind: String = row.get(1);
+    let status: String = row.get(2);
+    Ok(JobPayload {
+        id: row.get(0),
+        kind: JobKind::parse(&kind)
+            .ok_or_else(|| AppError::Database(format!("unknown job kind '{kind}'")))?,
+        status: JobStatus::parse(&status)
+            .ok_or_else(|| AppError::Database(format!("unknown job status '{status}'")))?,
+        attempts: row.get(3),
+        created_at: row.get(4),
+        started_at: row.get(5),
+        finished_at: row.get(6),
+        result: row.get(7),
+        error: row.get(8),
+        callback: row.get::<_, Option<String>>(9).map(|url| JobCallback {
+            url,
+            status: row
+                .get::<_, Option<String>>(10)
+                .and_then(|s| CallbackStatus::parse(&s))
+                .unwrap_or(CallbackStatus::Pending),
+            attempts: row.get(11),
+            last_error: row.get(12),
+        }),
+    })
+}
@@ -1,9 +1,142 @@
+pub(crate) mod alerts;
+pub(crate) mod audit;
+mod breaker;
 pub(crate) mod country;
+pub(crate) mod events;
 pub(crate) mod geocoding;
+pub(crate) mod jobs;
+pub(crate) mod monitors;
 pub(crate) mod population;
+mod retry;
 pub(crate) mod stats;
+mod statements;
+pub(crate) mod tagging;
 
-pub(crate) use country::CountryRepository;
-pub(crate) use geocoding::GeocodingRepository;
-pub(crate) use population::PopulationRepository;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Instant;
+
+use deadpool_postgres::{Object, Pool};
+
+use crate::config::ExposureBackend;
+use crate::errors::AppError;
+use crate::logging;
+use crate::models::Dataset;
+use crate::statement_timeout::STATEMENT_TIMEOUT;
+use breaker::CircuitBreaker;
+use retry::Retrying;
+
+pub(crate) use alerts::AlertRepository;
+pub(crate) use audit::{AuditRecord, AuditRepository};
+pub(crate) use country::{CountryCode, CountryFilter, CountryRepository, PgCountryRepository};
+pub(crate) use events::{EventRecord, EventRepository};
+pub(crate) use geocoding::{AdminUnit, GeocodingRepository, PgGeocodingRepository, PlaceOptions};
+pub(crate) use jobs::{ClaimedJob, JobRepository};
+pub(crate) use monitors::{DueMonitor, EventCheck, MonitorArea, MonitorRepository};
+pub(crate) use population::{PgPopulationRepository, PopulationRepository, SearchArea};
 pub(crate) use stats::StatsRepository;
+pub(crate) use tagging::TaggingRepository;
+
+/// Connection source for the Postgres repositories. Every connection handed
+/// out has JIT disabled and the statement timeout set — the endpoint's, when
+/// the request scoped one, else `statement_timeout_s` — so a pooled
+/// connection never carries another caller's settings. Checkouts go through
+/// a circuit breaker shared by all clones, which fails fast while the
+/// database is down (see `breaker`).
+#[derive(Clone)]
+pub(crate) struct PgDb {
+    pool: Pool,
+    statement_timeout_s: u32,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl PgDb {
+    pub fn new(pool: Pool, statement_timeout_s: u32) -> Self {
+        Self { pool, statement_timeout_s, breaker: Arc::default() }
+    }
+
+    async fn conn(&self) -> Result<Conn<'_>, AppError> {
+        self.breaker.check()?;
+        let since = Instant::now();
+        let client = match self.pool.get().await {
+            Ok(client) => Conn { client, breaker: &self.breaker, since },
+            Err(e) => {
+                if breaker::is_outage(&e) {
+                    self.breaker.record_failure();
+                }
+                return Err(e.into());
+            }
+        };
+        client
+            .batch_execute(&format!(
+                "SET jit = off; SET statement_timeout = '{}s'",
+                STATEMENT_TIMEOUT.try_with(|secs| *secs).unwrap_or(self.statement_timeout_s)
+            ))
+            .await?;
+        self.breaker.record_success();
+        Ok(client)
+    }
+}
+
+/// A pooled connection checked out through `PgDb::conn`. If the connection
+/// broke while in use, returning it counts as a failure for the breaker.
+/// The time from checkout to return counts towards the request's `db_ms`.
+pub(crate) struct Conn<'a> {
+    client: Object,
+    breaker: &'a CircuitBreaker,
+    since: Instant,
+}
+
+impl Deref for Conn<'_> {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.client
+    }
+}
+
+impl Drop for Conn<'_> {
+    fn drop(&mut self) {
+        if self.client.is_closed() {
+            self.breaker.record_failure();
+        }
+        logging::record_db_time(self.since.elapsed());
+    }
+}
+
+/// The query repositories shared by the REST handlers, GraphQL and jobs.
+#[derive(Clone)]
+pub(crate) struct Repositories {
+    pub population: Arc<dyn PopulationRepository>,
+    pub population_constrained: Arc<dyn PopulationRepository>,
+    pub geocoding: Arc<dyn GeocodingRepository>,
+    pub countries: Arc<dyn CountryRepository>,
+    pub tagging: Arc<TaggingRepository>,
+}
+
+impl Repositories {
+    /// Postgres-backed repositories whose queries are cut off after
+    /// `statement_timeout_s` and retried on transient errors, summing
+    /// exposure with `exposure`.
+    pub fn postgres(pool: &Pool, statement_timeout_s: u32, exposure: ExposureBackend) -> Self {
+        let db = PgDb::new(pool.clone(), statement_timeout_s);
+        let population = |dataset| Arc::new(Retrying(Arc::new(PgPopulationRepository::new(db.clone(), dataset, exposure))));
+        Self {
+            population: population(Dataset::Unconstrained),
+            population_constrained: population(Dataset::Constrained),
+            geocoding: Arc::new(Retrying(Arc::new(PgGeocodingRepository::new(db.clone())))),
+            countries: Arc::new(Retrying(Arc::new(PgCountryRepository::new(db.clone())))),
+            tagging: Arc::new(TaggingRepository::new(db)),
+        }
+    }
+
+    /// The population repository over the latest year of the requested
+    /// WorldPop grid (unconstrained when not given); see
+    /// `PopulationRepository::for_year` for earlier years.
+    pub fn population_for(&self, dataset: Option<Dataset>) -> &Arc<dyn PopulationRepository> {
+        match dataset.unwrap_or_default() {
+            Dataset::Unconstrained => &self.population,
+            Dataset::Constrained => &self.population_constrained,
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crate::errors::AppError;
+use crate::models::{AlertThreshold, CoordinateInfo, MonitorPayload, MonitorRequest, MonitorSnapshot};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Object;
+use serde_json::Value;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+const MONITOR_COLUMNS: &str = "id, name, lat, lon, radius_km, polygon, interval_hours, created_at, \
+    updated_at, next_run_at, last_run_at, last_population, last_error, thresholds, webhook_url";
+
+/// Where a monitor sums population: a centre and radius, or a GeoJSON polygon.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MonitorArea {
+    Circle { lat: f64, lon: f64, radius_km: f64 },
+    Polygon(Value),
+}
+
+impl MonitorArea {
+    fn columns(&self) -> (Option<f64>, Option<f64>, Option<f64>, Option<&Value>) {
+        match self {
+            Self::Circle { lat, lon, radius_km } => (Some(*lat), Some(*lon), Some(*radius_km), None),
+            Self::Polygon(polygon) => (None, None, None, Some(polygon)),
+        }
+    }
+
+    fn from_row(row: &Row, first: usize) -> Result<Self, AppError> {
+        match (row.get(first), row.get(first + 1), row.get(first + 2), row.get::<_, Option<Value>>(first + 3)) {
+            (Some(lat), Some(lon), Some(radius_km), None) => Ok(Self::Circle { lat, lon, radius_km }),
+            (None, None, None, Some(polygon)) => Ok(Self::Polygon(polygon)),
+            _ => Err(AppError::Database("monitor has no area".into())),
+        }
+    }
+}
+
+/// A monitor whose snapshot is due, claimed by the scheduler.
+pub(crate) struct DueMonitor {
+    pub id: Uuid,
+    pub area: MonitorArea,
+    pub thresholds: Vec<AlertThreshold>,
+    /// Population of the latest snapshot before this one.
+    pub previous: Option<f64>,
+}
+
+/// A monitor with `event_exposure` thresholds and events recorded since it
+/// was last checked.
+pub(crate) struct EventCheck {
+    pub id: Uuid,
+    pub area: MonitorArea,
+    pub thresholds: Vec<AlertThreshold>,
+    /// `first_seen_at` of the newest event already checked.
+    pub since: DateTime<Utc>,
+}
+
+/// A GDACS event inside a monitored area.
+pub(crate) struct AreaEvent {
+    pub event_type: String,
+    pub event_id: i64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+pub(crate) struct MonitorRepository;
+
+impl MonitorRepository {
+    /// Registers a monitor; its first snapshot is due immediately.
+    pub async fn create(
+        client: &Object,
+        body: &MonitorRequest,
+        area: &MonitorArea,
+    ) -> Result<MonitorPayload, AppError> {
+        let (lat, lon, radius_km, polygon) = area.columns();
+        let thresholds = encode_thresholds(&body.thresholds)?;
+        let sql = format!(
+            "INSERT INTO monitors (name, lat, lon, radius_km, polygon, interval_hours, thresholds, webhook_url) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {MONITOR_COLUMNS}"
+        );
+        let row = client
+            .query_one(
+                &sql,
+                &[
+                    &body.name.trim(),
+                    &lat,
+                    &lon,
+                    &radius_km,
+                    &polygon,
+                    &body.interval_hours,
+                    &thresholds,
+                    &body.webhook_url,
+                ],
+            )
+            .await?;
+        to_payload(&row)
+    }
+
+    pub async fn get(client: &Object, id: Uuid) -> Result<Option<MonitorPayload>, AppError> {
+        let sql = format!("SELECT {MONITOR_COLUMNS} FROM monitors WHERE id = $1");
+        client.query_opt(&sql, &[&id]).await?.as_ref().map(to_payload).transpose()
+    }
+
+    pub async fn list(client: &Object) -> Result<Vec<MonitorPayload>, AppError> {
+        let sql = format!("SELECT {MONITOR_COLUMNS} FROM monitors ORDER BY created_at, id");
+        client.query(&sql, &[]).await?.iter().map(to_payload).collect()
+    }
+
+    /// Replaces a monitor's settings. A changed area makes the next snapshot
+    /// due immediately; the history is kept either way.
+    pub async fn update(
+        client: &Object,
+        id: Uuid,
+        body: &MonitorRequest,
+        area: &MonitorArea,
+    ) -> Result<Option<MonitorPayload>, AppError> {
+        let (lat, lon, radius_km, polygon) = area.columns();
+        let thresholds = encode_thresholds(&body.thresholds)?;
+        let sql = format!(
+            "UPDATE monitors SET
+                 next_run_at = CASE
+                     WHEN (lat, lon, radius_km) IS DISTINCT FROM ($3::float8, $4::float8, $5::float8)
+                          OR polygon IS DISTINCT FROM $6::jsonb THEN now()
+                     ELSE COALESCE(last_run_at, created_at) + make_interval(hours => $7)
+                 END,
+                 name = $2, lat = $3, lon = $4, radius_km = $5, polygon = $6, interval_hours = $7,
+                 thresholds = $8, webhook_url = $9, updated_at = now()
+             WHERE id = $1
+             RETURNING {MONITOR_COLUMNS}"
+        );
+        let row = client
+            .query_opt(
+                &sql,
+                &[
+                    &id,
+                    &body.name.trim(),
+                    &lat,
+                    &lon,
+                    &radius_km,
+                    &polygon,
+                    &body.interval_hours,
+                    &thresholds,
+                    &body.webhook_url,
+                ],
+            )
+            .await?;
+        row.as_ref().map(to_payload).transpose()
+    }
+
+    /// Deletes a monitor with its snapshots; `false` when there was none.
+    pub async fn delete(client: &Object, id: Uuid) -> Result<bool, AppError> {
+        Ok(client.execute("DELETE FROM monitors WHERE id = $1", &[&id]).await? > 0)
+    }
+
+    /// Up to `limit` snapshots of a monitor, most recent first, each with its
+    /// change from the one before.
+    pub async fn snapshots(client: &Object, id: Uuid, limit: i64) -> Result<Vec<MonitorSnapshot>, AppError> {
+        let rows = client
+            .query(
+                "SELECT taken_at, total_population,
+                        total_population - lag(total_population) OVER (ORDER BY taken_at)
+                 FROM monitor_snapshots WHERE monitor_id = $1
+                 ORDER BY taken_at DESC LIMIT $2",
+                &[&id, &limit],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| MonitorSnapshot { taken_at: row.get(0), total_population: row.get(1), change: row.get(2) })
+            .collect())
+    }
+
+    /// Claims up to `limit` monitors whose snapshot is due, moving their next
+    /// run an interval out so no other scheduler takes them.
+    pub async fn claim_due(client: &Object, limit: i64) -> Result<Vec<DueMonitor>, AppError> {
+        let rows = client
+            .query(
+                "UPDATE monitors SET next_run_at = now() + make_interval(hours => interval_hours)
+                 WHERE id IN (
+                     SELECT id FROM monitors WHERE next_run_at <= now()
+                     ORDER BY next_run_at
+                     FOR UPDATE SKIP LOCKED
+                     LIMIT $1
+                 )
+                 RETURNING id, lat, lon, radius_km, polygon, thresholds, last_population",
+                &[&limit],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(DueMonitor {
+                    id: row.get(0),
+                    area: MonitorArea::from_row(row, 1)?,
+                    thresholds: decode_thresholds(row.get(5))?,
+                    previous: row.get(6),
+                })
+            })
+            .collect()
+    }
+
+    /// Claims up to `limit` monitors with `event_exposure` thresholds that
+    /// have not been checked against the newest recorded event, marking them
+    /// checked up to it.
+    pub async fn claim_event_checks(client: &Object, limit: i64) -> Result<Vec<EventCheck>, AppError> {
+        let rows = client
+            .query(
+                "WITH newest AS (SELECT max(first_seen_at) AS seen FROM events),
+                 due AS (
+                     SELECT id, events_checked_at FROM monitors, newest
+                     WHERE thresholds @> '[{\"metric\": \"event_exposure\"}]' AND events_checked_at < newest.seen
+                     ORDER BY id
+                     FOR UPDATE OF monitors SKIP LOCKED
+                     LIMIT $1
+                 )
+                 UPDATE monitors m SET events_checked_at = newest.seen
+                 FROM due, newest
+                 WHERE m.id = due.id
+                 RETURNING m.id, m.lat, m.lon, m.radius_km, m.polygon, m.thresholds, due.events_checked_at",
+                &[&limit],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(EventCheck {
+                    id: row.get(0),
+                    area: MonitorArea::from_row(row, 1)?,
+                    thresholds: decode_thresholds(row.get(5))?,
+                    since: row.get(6),
+                })
+            })
+            .collect()
+    }
+
+    /// Events first seen after `since` whose location lies inside `area`.
+    pub async fn events_in(
+        client: &Object,
+        area: &MonitorArea,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<AreaEvent>, AppError> {
+        let rows = match area {
+            MonitorArea::Circle { lat, lon, radius_km } => {
+                client
+                    .query(
+                        "SELECT event_type, event_id, lat, lon FROM events
+                         WHERE first_seen_at > $1
+                           AND ST_DWithin(ST_MakePoint(lon, lat)::geography,
+                                          ST_MakePoint($3, $2)::geography, $4::float8 * 1000.0)
+                         ORDER BY first_seen_at",
+                        &[&since, lat, lon, radius_km],
+                    )
+                    .await?
+            }
+            MonitorArea::Polygon(polygon) => {
+                client
+                    .query(
+                        "SELECT event_type, event_id, lat, lon FROM events
+                         WHERE first_seen_at > $1
+                           AND ST_Intersects(ST_SetSRID(ST_GeomFromGeoJSON($2::text), 4326),
+                                             ST_SetSRID(ST_MakePoint(lon, lat), 4326))
+                         ORDER BY first_seen_at",
+                        &[&since, &polygon.to_string()],
+                    )
+                    .await?
+            }
+        };
+        Ok(rows
+            .iter()
+            .map(|row| AreaEvent { event_type: row.get(0), event_id: row.get(1), lat: row.get(2), lon: row.get(3) })
+            .collect())
+    }
+
+    /// Appends a snapshot and records it as the monitor's latest.
+    pub async fn record(client: &Object, id: Uuid, total_population: f64) -> Result<(), AppError> {
+        client
+            .execute(
+                "WITH snapshot AS (
+                     INSERT INTO monitor_snapshots (monitor_id, total_population) VALUES ($1, $2)
+                 )
+                 UPDATE monitors SET last_run_at = now(), last_population = $2, last_error = NULL
+                 WHERE id = $1",
+                &[&id, &total_population],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Records a failed run, retried after `retry_in_secs` unless the regular
+    /// interval comes sooner.
+    pub async fn fail(client: &Object, id: Uuid, error: &str, retry_in_secs: f64) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE monitors SET last_error = $2,
+                     next_run_at = LEAST(next_run_at, now() + make_interval(secs => $3))
+                 WHERE id = $1",
+                &[&id, &error, &retry_in_secs],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn encode_thresholds(thresholds: &[AlertThreshold]) -> Result<Value, AppError> {
+    serde_json::to_value(thresholds).map_err(|e| AppError::Database(format!("failed to encode thresholds: {e}")))
+}
+
+fn decode_thresholds(value: Value) -> Result<Vec<AlertThreshold>, AppError> {
+    serde_json::from_value(value).map_err(|e| AppError::Database(format!("invalid stored thresholds: {e}")))
+}
+
+fn to_payload(row: &Row) -> Result<MonitorPayload, AppError> {
+    let lat: Option<f64> = row.get(2);
+    let lon: Option<f64> = row.get(3);
+    Ok(MonitorPayload {
+        id: row.get(0),
+        name: row.get(1),
+        coordinate: lat.zip(lon).map(|(lat, lon)| CoordinateInfo { lat, lon }),
+        radius_km: row.get(4),
+        polygon: row.get(5),
+        interval_hours: row.get(6),
+        created_at: row.get(7),
+        updated_at: row.get(8),
+        next_run_at: row.get(9),
+        last_run_at: row.get(10),
+        last_population: row.get(11),
+        last_error: row.get(12),
+        thresholds: decode_thresholds(row.get(13))?,
+        webhook_url: row.get(14),
+    })
+}
@@ -1,190 +1,1626 @@
+use super::{statements, PgDb};
+use crate::config::ExposureBackend;
 use crate::errors::AppError;
 use crate::grid;
-use crate::models::{CellBounds, GridCell};
+use crate::pyramid;
+use crate::models::{
+    AggregateInterval, CellBounds, CountryExposure, Dataset, DecayModel, DensityRankPayload, GridCell,
+    LandCoverClass, LandCoverShare, PopulationInterval, SettlementClass, SettlementExposure,
+};
+use async_trait::async_trait;
 use deadpool_postgres::Object;
+use futures_util::future::try_join_all;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::sync::Arc;
+use tokio_postgres::error::SqlState;
 
 const KM_PER_DEG: f64 = 111.32;
 const ROW_MAX: i32 = 21599;
+const COL_MAX: i32 = 43199;
+/// Pooled connections one batch lookup spreads its points over.
+const BATCH_CONNECTIONS: usize = 4;
+/// Smallest radius summed from `population_raster` under
+/// `ExposureBackend::Raster`, or from the rollup pyramid otherwise; below it
+/// the grid enumeration is as fast.
+const LARGE_RADIUS_KM: f64 = 50.0;
 
 fn search_bounds(lat: f64, lon: f64, radius_km: f64) -> (i32, i32, i32, i32) {
     let dlat = radius_km / KM_PER_DEG;
     let cos_lat = lat.to_radians().cos().max(0.01);
     let dlon = radius_km / (KM_PER_DEG * cos_lat);
+    bbox_bounds([lon - dlon, lat - dlat, lon + dlon, lat + dlat])
+}
+
+/// Grid row/column range `(min_row, max_row, min_col, max_col)` covering a
+/// `[min_lon, min_lat, max_lon, max_lat]` box.
+fn bbox_bounds([min_lon, min_lat, max_lon, max_lat]: [f64; 4]) -> (i32, i32, i32, i32) {
     (
-        (((90.0 - (lat + dlat)) * 120.0).floor() as i32).clamp(0, ROW_MAX),
-        (((90.0 - (lat - dlat)) * 120.0).floor() as i32).clamp(0, ROW_MAX),
-        ((lon - dlon + 180.0) * 120.0).floor() as i32,
-        ((lon + dlon + 180.0) * 120.0).floor() as i32,
+        (((90.0 - max_lat) * 120.0).floor() as i32).clamp(0, ROW_MAX),
+        (((90.0 - min_lat) * 120.0).floor() as i32).clamp(0, ROW_MAX),
+        ((min_lon + 180.0) * 120.0).floor() as i32,
+        ((max_lon + 180.0) * 120.0).floor() as i32,
+    )
+}
+
+/// Equirectangular distance in km from (`$1`, `$2`) to the centre of cell
+/// `sub.cell_id`, the test behind every circle query and `pyramid`.
+const CELL_KM: &str = r#"111.32 * sqrt(
+    pow((90.0 - (sub.cell_id / 43200 + 0.5) / 120.0) - $1::float8, 2) +
+    pow(((mod(sub.cell_id, 43200) + 0.5) / 120.0 - 180.0 - $2::float8) * cos(radians($1::float8)), 2)
+)"#;
+
+/// Subquery over the rows of `source` (the grid or a per-cell layer) in grid
+/// rows `$n..$n+1` and columns `$n+2..$n+3`, as from `bbox_bounds`, with
+/// `first_param` as `$n`. `populated` drops empty grid cells inside the scan.
+/// Each row is one `cell_id` index range scan, so run it under
+/// `set_seqscan_off`.
+fn cells_in_box(source: &str, first_param: usize, populated: bool) -> String {
+    let [min_row, max_row, min_col, max_col] = [0, 1, 2, 3].map(|i| first_param + i);
+    let populated = if populated { "AND p.pop > 0" } else { "" };
+    format!(
+        r#"(
+            SELECT sub.*
+            FROM generate_series(${min_row}::int, ${max_row}::int) AS r(r)
+            CROSS JOIN LATERAL (
+                SELECT p.*
+                FROM {source} p
+                WHERE p.cell_id BETWEEN r.r * 43200 + ${min_col}::int AND r.r * 43200 + ${max_col}::int
+                {populated}
+            ) sub
+        )"#
     )
 }
 
-pub(crate) struct PopulationRepository;
+/// Subquery over the rows of `source` whose cell centre lies within `$3` km
+/// of (`$1`, `$2`), with that distance as `km`; `$4..$7` are the box from
+/// `search_bounds`. Every circle query is built on it, so they all count the
+/// same cells.
+fn cells_within(source: &str, populated: bool) -> String {
+    format!(
+        r#"(
+            SELECT sub.*, d.km
+            FROM {cells} sub
+            CROSS JOIN LATERAL (SELECT {CELL_KM} AS km) d
+            WHERE d.km <= $3::float8
+        )"#,
+        cells = cells_in_box(source, 4, populated)
+    )
+}
+
+/// `pyramid_exposure_population`'s query: (`$1`, `$2`) and `$3` are the
+/// circle, `$4` the dataset, `$5..$7` the rollup blocks and `$8..$10` the
+/// edge spans, as arrays from `columns`.
+fn pyramid_sql(grid: &str) -> String {
+    format!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM population_rollups WHERE dataset = $4),
+               (SELECT COALESCE(SUM(r.pop), 0)
+                FROM unnest($5::int[], $6::int[], $7::int[]) AS b (size, brow, bcol)
+                JOIN population_rollups r
+                  ON r.dataset = $4 AND r.block_size = b.size
+                 AND r.block_row = b.brow AND r.block_col = b.bcol)
+             + (SELECT COALESCE(SUM(sub.pop), 0)::float8
+                FROM unnest($8::int[], $9::int[], $10::int[]) AS s (r, c0, c1)
+                CROSS JOIN LATERAL (
+                    SELECT p.pop, p.cell_id
+                    FROM {grid} p
+                    WHERE p.cell_id BETWEEN s.r * 43200 + s.c0 AND s.r * 43200 + s.c1
+                ) sub
+                WHERE {CELL_KM} <= $3::float8)
+        "#
+    )
+}
+
+/// `get_flood_exposure`'s query for the kind of `area`. The elevation limit is
+/// `$8` after a circle's parameters (see `cells_within`) and `$1` before a
+/// polygon's box (`$2..$5`) and GeoJSON (`$6`).
+fn flood_sql(grid: &str, area: &SearchArea<'_>) -> String {
+    let sums = |elevation: &str| {
+        format!(
+            r#"
+            SELECT COALESCE(SUM(c.pop), 0)::float8,
+                   COALESCE(SUM(c.pop) FILTER (WHERE e.elevation_m <= {elevation}::float8), 0)::float8,
+                   COUNT(*) FILTER (WHERE e.elevation_m <= {elevation}::float8)::int8
+            "#
+        )
+    };
+    match area {
+        SearchArea::Circle { .. } => format!(
+            r#"{sums}
+            FROM {cells} c
+            LEFT JOIN cell_elevation e ON e.cell_id = c.cell_id"#,
+            sums = sums("$8"),
+            cells = cells_within(grid, true)
+        ),
+        SearchArea::Polygon { .. } => format!(
+            r#"WITH area AS MATERIALIZED (
+                SELECT ST_SetSRID(ST_GeomFromGeoJSON($6::text), 4326) AS g
+            )
+            {sums}
+            FROM area, {cells} c
+            LEFT JOIN cell_elevation e ON e.cell_id = c.cell_id
+            WHERE ST_Intersects(area.g, ST_SetSRID(ST_MakePoint(
+                (mod(c.cell_id, 43200) + 0.5) / 120.0 - 180.0,
+                90.0 - (c.cell_id / 43200 + 0.5) / 120.0
+            ), 4326))"#,
+            sums = sums("$1"),
+            cells = cells_in_box(grid, 2, true)
+        ),
+    }
+}
+
+/// Area whose cells a query sums, matched by cell centre.
+pub(crate) enum SearchArea<'a> {
+    /// Equirectangular distance from a centre, as in /exposure.
+    Circle { lat: f64, lon: f64, radius_km: f64 },
+    /// GeoJSON (Multi)Polygon and its `[min_lon, min_lat, max_lon, max_lat]` bounds.
+    Polygon { geojson: &'a str, bounds: [f64; 4] },
+}
+
+/// Queries over the 30 arc-second population grid and its optional layers.
+#[async_trait]
+pub(crate) trait PopulationRepository: Send + Sync {
+    async fn get_population(&self, lat: f64, lon: f64) -> Result<f32, AppError>;
+
+    async fn get_batch_population(
+        &self,
+        points: &[(f64, f64)],
+    ) -> Result<Vec<f32>, AppError>;
+
+    async fn get_cell_population(&self, lat: f64, lon: f64) -> Result<f32, AppError>;
+
+    /// Elevation (m) of the cell containing the coordinate and, for low-lying
+    /// coastal cells, its distance to the coast (km). `None` where the terrain
+    /// layers hold no value: unpopulated cells, or layers not loaded.
+    async fn get_cell_terrain(&self, lat: f64, lon: f64) -> Result<Option<(f64, Option<f64>)>, AppError>;
+
+    /// GHS-SMOD settlement class of the cell containing the coordinate. `None`
+    /// for unpopulated or unclassified cells, or when the layer is not loaded.
+    async fn get_cell_settlement(&self, lat: f64, lon: f64) -> Result<Option<SettlementClass>, AppError>;
+
+    /// GHSL built-up surface (m²) of the cell containing the coordinate: 0 for
+    /// an unbuilt cell, `None` when the layer is not loaded.
+    async fn get_cell_built_up(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError>;
+
+    /// VIIRS nighttime-lights radiance (nW/cm²/sr) of the cell containing the
+    /// coordinate: 0 for an unlit cell, `None` when the layer is not loaded.
+    async fn get_cell_night_lights(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError>;
+
+    /// Dominant ESA WorldCover class of the cell containing the coordinate.
+    /// `None` for unpopulated cells, or when the layer is not loaded.
+    async fn get_cell_land_cover(&self, lat: f64, lon: f64) -> Result<Option<LandCoverClass>, AppError>;
+
+    /// Density of the cell containing the coordinate, ranked against the
+    /// precomputed `density_percentiles` of the world and of the country
+    /// containing the coordinate. Fails with `NotFound` when the distribution
+    /// has not been computed yet.
+    async fn get_density_rank(&self, lat: f64, lon: f64) -> Result<DensityRankPayload, AppError>;
+
+    /// Returns all non-empty grid cells within a radius, with their centre coordinates and bounds.
+    async fn get_grid_cells(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Vec<GridCell>, AppError>;
+
+    /// The `limit` most populated cells counted by `get_exposure_population`,
+    /// most populous first.
+    async fn get_top_cells(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        limit: i64,
+    ) -> Result<Vec<GridCell>, AppError>;
+
+    /// Number of populated cells overlapping a `[min_lon, min_lat, max_lon, max_lat]` box.
+    async fn count_bbox_cells(&self, bbox: [f64; 4]) -> Result<i64, AppError>;
+
+    /// Populated cells overlapping a box, with their bounds, most populous first.
+    /// With `aggregation` > 1 cells are summed into `aggregation`×`aggregation`
+    /// blocks aligned to the global grid, so blocks stay put as a viewport pans.
+    async fn get_bbox_grid_cells(
+        &self,
+        bbox: [f64; 4],
+        aggregation: i32,
+    ) -> Result<Vec<GridCell>, AppError>;
+
+    /// WorldPop 95% interval for the cell containing the coordinate, if the
+    /// country is covered by an uncertainty raster.
+    async fn get_cell_uncertainty(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<Option<PopulationInterval>, AppError>;
+
+    /// Attach per-cell intervals to grid cells (in one round-trip) and return
+    /// the aggregate interval across all of them.
+    async fn attach_grid_uncertainty(
+        &self,
+        cells: &mut [GridCell],
+    ) -> Result<AggregateInterval, AppError>;
+
+    /// Aggregate 95% interval within a circular radius, using the same cell
+    /// enumeration as `get_exposure_population`. Cells without uncertainty
+    /// data contribute their point estimate to both bounds.
+    async fn get_exposure_uncertainty(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<AggregateInterval, AppError>;
+
+    /// Sum population within a circular radius.
+    /// LATERAL forces PostgreSQL into nested loop + index scan on every row,
+    /// preventing the planner from choosing a catastrophic hash join on 175M rows.
+    async fn get_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<f64, AppError>;
+
+    /// Population per distance band for ascending ring radii `rings`, in one pass
+    /// over the cells within the outermost ring. Band `i` holds cells whose centre
+    /// lies in `[rings[i-1], rings[i])`; the outermost band also includes its edge,
+    /// matching `get_exposure_population`.
+    async fn get_ring_populations(
+        &self,
+        lat: f64,
+        lon: f64,
+        rings: &[f64],
+    ) -> Result<Vec<f64>, AppError>;
+
+    /// Cell count and population per density bucket over the cells counted by
+    /// `get_exposure_population`, bucketed by people/km² of each cell's own area
+    /// against ascending `edges`: `edges.len() + 1` buckets, the first below
+    /// `edges[0]` and the last at or above the final edge.
+    async fn get_density_histogram(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        edges: &[f64],
+    ) -> Result<Vec<(i64, f64)>, AppError>;
+
+    /// Convex hull (GeoJSON) of the populated cells counted by `get_exposure_population`,
+    /// using full cell extents. `None` when the area holds no population.
+    async fn get_populated_hull(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Option<serde_json::Value>, AppError>;
+
+    /// Distance-weighted population sum over the same cells as `get_exposure_population`.
+    /// Each cell's population is scaled by the decay weight of its centre's distance.
+    async fn get_weighted_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        decay: DecayModel,
+    ) -> Result<f64, AppError>;
+
+    /// Built-up surface (m²) and built cell count inside the radius, by the same
+    /// distance rule as `get_exposure_population` but over every built cell,
+    /// populated or not. Fails with `NotFound` when the layer has not been loaded.
+    async fn get_built_up_exposure(&self, lat: f64, lon: f64, radius_km: f64) -> Result<(f64, i64), AppError>;
+
+    /// Summed radiance, brightest cell's radiance and lit cell count inside the
+    /// radius, by the same distance rule as `get_exposure_population`. Fails with
+    /// `NotFound` when the nighttime-lights layer has not been loaded.
+    async fn get_night_lights_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<(f64, f64, i64), AppError>;
+
+    /// Population and populated-cell count per dominant land-cover class inside
+    /// the radius (counted as `get_exposure_population` does), largest population
+    /// first, with `share_of_population` left at 0. Cells without a class are
+    /// left out. Fails with `NotFound` when the land-cover layer has not been loaded.
+    async fn get_land_cover_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Vec<LandCoverShare>, AppError>;
+
+    /// Population inside the radius (counted as `get_exposure_population` does)
+    /// split by GHS-SMOD settlement class. Fails with `NotFound` when the
+    /// settlement layer has not been loaded.
+    async fn get_settlement_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<SettlementExposure, AppError>;
+
+    /// Population and populated-cell count inside the radius, restricted to cells
+    /// within `coast_km` of the coastline and at or below `max_elevation_m`.
+    /// Fails with `NotFound` when the terrain layer has not been loaded, rather
+    /// than reporting a misleading zero.
+    async fn get_coastal_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        coast_km: f64,
+        max_elevation_m: f64,
+    ) -> Result<(f64, i64), AppError>;
+
+    /// Population of the cells counted by `get_exposure_population` per
+    /// country of vintage `vintage` containing their centre, largest first,
+    /// with `place_count` left at 0. Empty unless the circle crosses more than
+    /// one country, which then all appear, populated or not.
+    async fn get_country_split(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        vintage: i32,
+    ) -> Result<Vec<CountryExposure>, AppError>;
+
+    /// Area in km² of the geodesic circle of `radius_km` that lies on land, by
+    /// the country boundaries of vintage `vintage`.
+    async fn get_land_area(&self, lat: f64, lon: f64, radius_km: f64, vintage: i32) -> Result<f64, AppError>;
+
+    /// Total population in `area`, plus the population and populated-cell count
+    /// of cells whose elevation is at or below `max_elevation_m`. Cells missing
+    /// from the elevation layer count towards the total only.
+    async fn get_flood_exposure(
+        &self,
+        area: &SearchArea<'_>,
+        max_elevation_m: f64,
+    ) -> Result<(f64, f64, i64), AppError>;
+
+    /// [`get_flood_exposure`](Self::get_flood_exposure) of each polygon in
+    /// `parts`, given as GeoJSON Polygon with its bounds, in order.
+    async fn get_flood_exposure_by_part(
+        &self,
+        parts: &[(String, [f64; 4])],
+        max_elevation_m: f64,
+    ) -> Result<Vec<(f64, f64, i64)>, AppError>;
+
+    /// Population and populated-cell count of cells whose centre lies inside a
+    /// GeoJSON (Multi)Polygon with the given bounds, plus the polygon's area in km².
+    async fn get_polygon_population(
+        &self,
+        geojson: &str,
+        bounds: [f64; 4],
+    ) -> Result<(f64, i64, f64), AppError>;
+
+    /// Population and populated-cell count of cells whose centre lies within
+    /// `buffer_km` of a GeoJSON geometry, buffered geodesically. `bounds` must
+    /// cover the buffered area. Also returns its area in km² and its GeoJSON.
+    async fn get_buffer_population(
+        &self,
+        geojson: &str,
+        buffer_km: f64,
+        bounds: [f64; 4],
+    ) -> Result<(f64, i64, f64, String), AppError>;
+
+    /// Population inside each quadrant swath around a centre. Each swath is four
+    /// radii in km (NE, SE, SW, NW); a cell counts when its centre's distance is
+    /// within the radius of the quadrant its bearing falls in. All swaths are
+    /// evaluated in one pass over the cells of the largest one.
+    async fn get_quadrant_swath_populations(
+        &self,
+        lat: f64,
+        lon: f64,
+        swaths: &[[f64; 4]],
+    ) -> Result<Vec<f64>, AppError>;
+
+    /// Fast existence check: is there ANY populated cell within the bounding box?
+    /// LATERAL + LIMIT 1 stops at the very first populated cell found — empty
+    /// ocean rows cost a single B-tree probe that returns nothing.
+    async fn has_population_within(
+        &self,
+        lat: f64,
+        lon: f64,
+        search_km: f64,
+    ) -> Result<bool, AppError>;
+
+    /// This repository's grid as of `year`, or as of the latest year loaded
+    /// when `None`, together with that year. Fails with `NotFound` when the
+    /// grid has not been loaded at all, or not for `year`.
+    async fn for_year(
+        &self,
+        year: Option<i32>,
+    ) -> Result<(i32, Arc<dyn PopulationRepository>), AppError>;
+}
+
+/// `PopulationRepository` backed by the PostGIS population tables.
+pub(crate) struct PgPopulationRepository {
+    db: PgDb,
+    dataset: Dataset,
+    /// Relation the queries read as `p`: the dataset's table for the latest
+    /// year, or a slice of `population_history` for an earlier one.
+    grid: String,
+    /// Whether `grid` is the latest year, the only one with uncertainty data.
+    latest: bool,
+    exposure: ExposureBackend,
+}
+
+impl PgPopulationRepository {
+    /// Repository over the latest year of `dataset`.
+    pub fn new(db: PgDb, dataset: Dataset, exposure: ExposureBackend) -> Self {
+        Self { db, dataset, grid: grid_table(dataset).into(), latest: true, exposure }
+    }
 
-impl PopulationRepository {
-    pub async fn get_population(client: &Object, lat: f64, lon: f64) -> Result<f32, AppError> {
+    /// Population within the radius from the raster tiles of the latest
+    /// year, counting pixels whose centre lies inside the same equirectangular
+    /// ellipse the grid query tests against. `None` when the dataset's raster
+    /// has not been built, or the table does not exist because the server
+    /// lacks `postgis_raster` (V14 skips it there).
+    async fn raster_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Option<f64>, AppError> {
+        let client = &self.db.conn().await?;
+        let row = client
+            .query_one(
+                r#"
+                WITH area AS (
+                    SELECT ST_SetSRID(ST_Translate(ST_Scale(
+                        ST_Buffer(ST_MakePoint(0, 0), 1, 32),
+                        $3::float8 / 111.32 / GREATEST(cos(radians($1::float8)), 0.01),
+                        $3::float8 / 111.32
+                    ), $2::float8, $1::float8), 4326) AS geom
+                )
+                SELECT EXISTS (SELECT 1 FROM population_raster WHERE dataset = $4),
+                       COALESCE(SUM((ST_SummaryStats(ST_Clip(r.rast, a.geom))).sum), 0)::float8
+                FROM area a
+                LEFT JOIN population_raster r
+                  ON r.dataset = $4 AND ST_ConvexHull(r.rast) && a.geom
+                "#,
+                &[&lat, &lon, &radius_km, &self.dataset.as_str()],
+            )
+            .await;
+        match row {
+            Ok(row) => Ok(row.get::<_, bool>(0).then(|| row.get(1))),
+            Err(e) if e.code() == Some(&SqlState::UNDEFINED_TABLE) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Population within the radius from the latest year's rollup blocks
+    /// wholly inside the circle plus the grid cells along its edge (see
+    /// `pyramid`). `None` when the dataset's rollups have not been built.
+    async fn pyramid_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Option<f64>, AppError> {
+        let cover = pyramid::cover(lat, lon, radius_km);
+        let (sizes, block_rows, block_cols) = columns(&cover.blocks);
+        let (rows, first_cols, last_cols) = columns(&cover.spans);
+        let sql = pyramid_sql(&self.grid);
+        let client = &self.db.conn().await?;
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(
+                &sql,
+                &[
+                    &lat,
+                    &lon,
+                    &radius_km,
+                    &self.dataset.as_str(),
+                    &sizes,
+                    &block_rows,
+                    &block_cols,
+                    &rows,
+                    &first_cols,
+                    &last_cols,
+                ],
+            )
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok(row.get::<_, bool>(0).then(|| row.get(1)))
+    }
+
+    /// One connection's share of a batch lookup, its queries pipelined on
+    /// the connection rather than awaited in turn.
+    async fn batch_share(&self, cells: &[i32]) -> Result<Vec<f32>, AppError> {
+        let client = self.db.conn().await?;
+        let stmt = client
+            .prepare_cached(&statements::cell_population(&self.grid))
+            .await?;
+        let lookups = cells.iter().map(|cell| {
+            let (client, stmt) = (&client, &stmt);
+            async move {
+                Ok::<_, AppError>(client.query_opt(stmt, &[cell]).await?.map_or(0.0, |r| r.get::<_, f32>(0)))
+            }
+        });
+        try_join_all(lookups).await
+    }
+
+    fn require_latest(&self) -> Result<(), AppError> {
+        if !self.latest {
+            return Err(AppError::Validation(
+                "include=uncertainty is only available for the latest population year".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Table holding the latest year of a dataset.
+fn grid_table(dataset: Dataset) -> &'static str {
+    match dataset {
+        Dataset::Unconstrained => "population",
+        Dataset::Constrained => "population_constrained",
+    }
+}
+
+#[async_trait]
+impl PopulationRepository for PgPopulationRepository {
+    async fn get_population(&self, lat: f64, lon: f64) -> Result<f32, AppError> {
+        let client = &self.db.conn().await?;
         let cell = grid::cell_id(lat, lon).ok_or_else(|| {
             AppError::Validation("Coordinates out of range. lat: [-90, 90], lon: [-180, 180)".into())
         })?;
 
-        let population = client
-            .query_opt("SELECT pop FROM population WHERE cell_id = $1", &[&cell])
-            .await?
-            .map_or(0.0, |r| r.get::<_, f32>(0));
+        let stmt = client.prepare_cached(&statements::cell_population(&self.grid)).await?;
+        let population = client.query_opt(&stmt, &[&cell]).await?.map_or(0.0, |r| r.get::<_, f32>(0));
 
         Ok(population)
     }
 
-    pub async fn get_batch_population(
-        client: &Object,
+    async fn get_batch_population(
+        &self,
         points: &[(f64, f64)],
     ) -> Result<Vec<f32>, AppError> {
-        let stmt = client
-            .prepare_cached("SELECT pop FROM population WHERE cell_id = $1")
+        // Telemetry batches repeat points heavily: look each cell up once,
+        // then fan the results back out in request order.
+        let cells: Vec<Option<i32>> = points.iter().map(|&(lat, lon)| grid::cell_id(lat, lon)).collect();
+        let mut unique: Vec<i32> = cells.iter().flatten().copied().collect();
+        unique.sort_unstable();
+        unique.dedup();
+
+        // Split the cells across a few pooled connections; `buffered` keeps
+        // the shares in order while they run concurrently.
+        let share = unique.len().div_ceil(BATCH_CONNECTIONS).max(1);
+        let shares: Vec<_> = unique.chunks(share).map(|chunk| self.batch_share(chunk)).collect();
+        let populations: Vec<f32> = stream::iter(shares)
+            .buffered(BATCH_CONNECTIONS)
+            .try_collect::<Vec<Vec<f32>>>()
+            .await?
+            .concat();
+
+        Ok(cells
+            .iter()
+            .map(|cell| cell.and_then(|c| unique.binary_search(&c).ok()).map_or(0.0, |i| populations[i]))
+            .collect())
+    }
+
+    async fn get_cell_population(&self, lat: f64, lon: f64) -> Result<f32, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(0.0);
+        };
+        let stmt = client.prepare_cached(&statements::cell_population(&self.grid)).await?;
+        Ok(client.query_opt(&stmt, &[&cell]).await?.map_or(0.0, |r| r.get(0)))
+    }
+
+    async fn get_cell_terrain(&self, lat: f64, lon: f64) -> Result<Option<(f64, Option<f64>)>, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        let sql = r#"
+            SELECT COALESCE(e.elevation_m, t.elevation_m)::float8, t.coast_km::float8
+            FROM (SELECT $1::int4 AS cell_id) c
+            LEFT JOIN cell_elevation e ON e.cell_id = c.cell_id
+            LEFT JOIN cell_terrain t ON t.cell_id = c.cell_id
+            WHERE e.cell_id IS NOT NULL OR t.cell_id IS NOT NULL
+        "#;
+        Ok(client.query_opt(sql, &[&cell]).await?.map(|r| (r.get(0), r.get(1))))
+    }
+
+    async fn get_cell_settlement(&self, lat: f64, lon: f64) -> Result<Option<SettlementClass>, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        let row = client
+            .query_opt("SELECT smod FROM cell_settlement WHERE cell_id = $1", &[&cell])
             .await?;
+        Ok(row.and_then(|r| SettlementClass::from_smod(r.get(0))))
+    }
 
-        let mut results = Vec::with_capacity(points.len());
-        for &(lat, lon) in points {
-            let population = match grid::cell_id(lat, lon) {
-                Some(cell) => client
-                    .query_opt(&stmt, &[&cell])
-                    .await?
-                    .map_or(0.0, |r| r.get::<_, f32>(0)),
-                None => 0.0,
-            };
-            results.push(population);
-        }
+    async fn get_cell_land_cover(&self, lat: f64, lon: f64) -> Result<Option<LandCoverClass>, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        let row = client
+            .query_opt("SELECT class FROM cell_land_cover WHERE cell_id = $1", &[&cell])
+            .await?;
+        Ok(row.and_then(|r| LandCoverClass::from_worldcover(r.get(0))))
+    }
 
-        Ok(results)
+    async fn get_cell_built_up(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        let sql = r#"
+            SELECT COALESCE((SELECT built_m2 FROM cell_built_up WHERE cell_id = $1), 0)::float8
+            WHERE EXISTS (SELECT 1 FROM cell_built_up)
+        "#;
+        Ok(client.query_opt(sql, &[&cell]).await?.map(|r| r.get(0)))
     }
 
-    pub async fn get_cell_population(client: &Object, lat: f64, lon: f64) -> Result<f32, AppError> {
-        match grid::cell_id(lat, lon) {
-            Some(cell) => Ok(client
-                .query_opt("SELECT pop FROM population WHERE cell_id = $1", &[&cell])
-                .await?
-                .map_or(0.0, |r| r.get(0))),
-            None => Ok(0.0),
-        }
+    async fn get_cell_night_lights(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError> {
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        let sql = r#"
+            SELECT COALESCE((SELECT radiance FROM cell_night_lights WHERE cell_id = $1), 0)::float8
+            WHERE EXISTS (SELECT 1 FROM cell_night_lights)
+        "#;
+        Ok(client.query_opt(sql, &[&cell]).await?.map(|r| r.get(0)))
     }
 
-    /// Returns all non-empty grid cells within a radius, with their centre coordinates and bounds.
-    pub async fn get_grid_cells(
-        client: &Object,
+    async fn get_density_rank(&self, lat: f64, lon: f64) -> Result<DensityRankPayload, AppError> {
+        let client = &self.db.conn().await?;
+        let cell = grid::cell_id(lat, lon).ok_or_else(|| {
+            AppError::Validation("Coordinates out of range. lat: [-90, 90], lon: [-180, 180)".into())
+        })?;
+        // A percentile is the number of the scope's 100 quantiles at or below the
+        // cell's density; HAVING turns a scope without rows into NULL.
+        let sql = r#"
+            WITH cell AS (
+                SELECT COALESCE((SELECT pop FROM population WHERE cell_id = $1), 0)::float8 AS pop,
+                       cell_area_km2($1) AS area
+            ),
+            country AS (
+                SELECT iso_a3
+                FROM countries
+                WHERE vintage = current_boundary_vintage()
+                  AND ST_Contains(geom, ST_SetSRID(ST_MakePoint($3, $2), 4326))
+                LIMIT 1
+            )
+            SELECT cell.pop, cell.area, country.iso_a3,
+                   (SELECT COUNT(*) FILTER (WHERE d.density <= (cell.pop / cell.area)::real)
+                    FROM density_percentiles d
+                    WHERE d.scope = 'WORLD'
+                    HAVING COUNT(*) > 0)::int,
+                   (SELECT COUNT(*) FILTER (WHERE d.density <= (cell.pop / cell.area)::real)
+                    FROM density_percentiles d
+                    WHERE d.scope = country.iso_a3
+                    HAVING COUNT(*) > 0)::int
+            FROM cell
+            LEFT JOIN country ON true
+        "#;
+        let row = client.query_one(sql, &[&cell, &lat, &lon]).await?;
+        let world_percentile: Option<i32> = row.get(3);
+        let world_percentile = world_percentile.ok_or_else(|| {
+            AppError::NotFound(
+                "Density percentiles have not been computed (run `make refresh-density`)".into(),
+            )
+        })?;
+        let population: f64 = row.get(0);
+        let area: f64 = row.get(1);
+
+        Ok(DensityRankPayload {
+            lat,
+            lon,
+            population: population as f32,
+            cell_area_km2: (area * 1000.0).round() / 1000.0,
+            density_per_km2: round1(population / area),
+            iso_a3: row.get(2),
+            country_percentile: row.get(4),
+            world_percentile,
+        })
+    }
+
+    async fn get_grid_cells(
+        &self,
         lat: f64,
         lon: f64,
         radius_km: f64,
     ) -> Result<Vec<GridCell>, AppError> {
-        let sql = r#"
-            SELECT r.r, c.c, p.pop
-            FROM generate_series(
-                GREATEST(FLOOR((90.0 - ($1::float8 + $3::float8/111.32)) * 120.0)::int, 0),
-                LEAST(FLOOR((90.0 - ($1::float8 - $3::float8/111.32)) * 120.0)::int, 21599)
-            ) r,
-            generate_series(
-                FLOOR(($2::float8 - $3::float8/(111.32 * cos(radians($1::float8))) + 180.0) * 120.0)::int,
-                FLOOR(($2::float8 + $3::float8/(111.32 * cos(radians($1::float8))) + 180.0) * 120.0)::int
-            ) c,
-            population p
-            WHERE p.cell_id = r.r * 43200 + c.c
-            AND p.pop > 0
-            AND 111.32 * sqrt(
-                pow((90.0 - (r.r + 0.5) / 120.0) - $1::float8, 2) +
-                pow((((c.c + 0.5) / 120.0 - 180.0) - $2::float8) * cos(radians($1::float8)), 2)
-            ) <= $3::float8
-            ORDER BY p.pop DESC
-        "#;
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT c.cell_id / 43200, mod(c.cell_id, 43200), c.pop
+            FROM {cells} c
+            ORDER BY c.pop DESC
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?
+            .iter()
+            .map(|row| grid_cell(row.get(0), row.get(1), 1, row.get(2)))
+            .collect())
+    }
+
+    async fn get_top_cells(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        limit: i64,
+    ) -> Result<Vec<GridCell>, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT c.cell_id / 43200, mod(c.cell_id, 43200), c.pop
+            FROM {cells} c
+            ORDER BY c.pop DESC, c.cell_id
+            LIMIT $8
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col, &limit])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?
+            .iter()
+            .map(|row| grid_cell(row.get(0), row.get(1), 1, row.get(2)))
+            .collect())
+    }
+
+    async fn count_bbox_cells(&self, bbox: [f64; 4]) -> Result<i64, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = bbox_bounds(bbox);
+        let sql = format!(
+            r#"
+            SELECT COUNT(*)
+            FROM {cells} c
+        "#,
+            cells = cells_in_box(&self.grid, 1, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&min_row, &max_row, &min_col, &max_col.min(COL_MAX)])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?.get(0))
+    }
+
+    async fn get_bbox_grid_cells(
+        &self,
+        bbox: [f64; 4],
+        aggregation: i32,
+    ) -> Result<Vec<GridCell>, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = bbox_bounds(bbox);
+        let sql = format!(
+            r#"
+            SELECT (c.cell_id / 43200) / $5::int, (c.cell_id % 43200) / $5::int, SUM(c.pop)::real
+            FROM {cells} c
+            GROUP BY 1, 2
+            ORDER BY 3 DESC
+        "#,
+            cells = cells_in_box(&self.grid, 1, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(&sql, &[&min_row, &max_row, &min_col, &max_col.min(COL_MAX), &aggregation])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?
+            .iter()
+            .map(|row| grid_cell(row.get(0), row.get(1), aggregation, row.get(2)))
+            .collect())
+    }
 
-        let rows = client.query(sql, &[&lat, &lon, &radius_km]).await?;
-        let step = 1.0 / 120.0;
+    async fn get_cell_uncertainty(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<Option<PopulationInterval>, AppError> {
+        self.require_latest()?;
+        let client = &self.db.conn().await?;
+        let Some(cell) = grid::cell_id(lat, lon) else {
+            return Ok(None);
+        };
+        Ok(client
+            .query_opt(
+                "SELECT pop_lower, pop_upper FROM population_uncertainty WHERE cell_id = $1",
+                &[&cell],
+            )
+            .await?
+            .map(|r| PopulationInterval {
+                lower: r.get::<_, f32>(0) as f64,
+                upper: r.get::<_, f32>(1) as f64,
+            }))
+    }
 
-        Ok(rows
+    async fn attach_grid_uncertainty(
+        &self,
+        cells: &mut [GridCell],
+    ) -> Result<AggregateInterval, AppError> {
+        self.require_latest()?;
+        let client = &self.db.conn().await?;
+        let ids: Vec<i32> = cells
+            .iter()
+            .map(|c| grid::cell_id(c.lat, c.lon).unwrap_or(-1))
+            .collect();
+        let rows = client
+            .query(
+                "SELECT cell_id, pop_lower, pop_upper FROM population_uncertainty WHERE cell_id = ANY($1)",
+                &[&ids],
+            )
+            .await?;
+        let intervals: std::collections::HashMap<i32, PopulationInterval> = rows
             .iter()
-            .map(|row| {
-                let r: i32 = row.get(0);
-                let c: i32 = row.get(1);
-                let pop: f32 = row.get(2);
-                let center_lat = 90.0 - (r as f64 + 0.5) * step;
-                let center_lon = (c as f64 + 0.5) * step - 180.0;
-                let min_lat = 90.0 - (r as f64 + 1.0) * step;
-                let max_lat = 90.0 - r as f64 * step;
-                let min_lon = c as f64 * step - 180.0;
-                let max_lon = (c as f64 + 1.0) * step - 180.0;
-
-                GridCell {
-                    lat: round5(center_lat),
-                    lon: round5(center_lon),
-                    population: pop,
-                    bounds: CellBounds {
-                        min_lat: round5(min_lat),
-                        max_lat: round5(max_lat),
-                        min_lon: round5(min_lon),
-                        max_lon: round5(max_lon),
+            .map(|r| {
+                (
+                    r.get(0),
+                    PopulationInterval {
+                        lower: r.get::<_, f32>(1) as f64,
+                        upper: r.get::<_, f32>(2) as f64,
                     },
-                }
+                )
             })
-            .collect())
+            .collect();
+
+        let mut aggregate = AggregateInterval {
+            lower: 0.0,
+            upper: 0.0,
+            covered_cells: 0,
+            total_cells: cells.len() as i64,
+        };
+        for (cell, id) in cells.iter_mut().zip(ids) {
+            cell.uncertainty = intervals.get(&id).copied();
+            match cell.uncertainty {
+                Some(interval) => {
+                    aggregate.lower += interval.lower;
+                    aggregate.upper += interval.upper;
+                    aggregate.covered_cells += 1;
+                }
+                None => {
+                    aggregate.lower += cell.population as f64;
+                    aggregate.upper += cell.population as f64;
+                }
+            }
+        }
+        aggregate.lower = round1(aggregate.lower);
+        aggregate.upper = round1(aggregate.upper);
+        Ok(aggregate)
     }
 
-    /// Sum population within a circular radius.
-    /// LATERAL forces PostgreSQL into nested loop + index scan on every row,
-    /// preventing the planner from choosing a catastrophic hash join on 175M rows.
-    pub async fn get_exposure_population(
-        client: &Object,
+    async fn get_exposure_uncertainty(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<AggregateInterval, AppError> {
+        self.require_latest()?;
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(COALESCE(u.pop_lower, c.pop)), 0)::float8,
+                   COALESCE(SUM(COALESCE(u.pop_upper, c.pop)), 0)::float8,
+                   COUNT(u.pop_lower)::bigint,
+                   COUNT(*)::bigint
+            FROM {cells} c
+            LEFT JOIN population_uncertainty u ON u.cell_id = c.cell_id
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok(AggregateInterval {
+            lower: round1(row.get(0)),
+            upper: round1(row.get(1)),
+            covered_cells: row.get(2),
+            total_cells: row.get(3),
+        })
+    }
+
+    async fn get_exposure_population(
+        &self,
         lat: f64,
         lon: f64,
         radius_km: f64,
     ) -> Result<f64, AppError> {
+        if self.latest && radius_km >= LARGE_RADIUS_KM {
+            if self.exposure == ExposureBackend::Raster {
+                match self.raster_exposure_population(lat, lon, radius_km).await? {
+                    Some(total) => return Ok(total),
+                    None => log::warn!(
+                        "EXPOSURE_BACKEND=raster but the {} raster is not built; summing the grid",
+                        self.dataset.as_str()
+                    ),
+                }
+            }
+            if let Some(total) = self.pyramid_exposure_population(lat, lon, radius_km).await? {
+                return Ok(total);
+            }
+        }
+        let client = &self.db.conn().await?;
         let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
-        let sql = r#"
-            SELECT COALESCE(SUM(sub.pop), 0)::float8
-            FROM generate_series($4::int, $5::int) AS r(r)
-            CROSS JOIN LATERAL (
-                SELECT p.pop, p.cell_id
-                FROM population p
-                WHERE p.cell_id BETWEEN r.r * 43200 + $6::int AND r.r * 43200 + $7::int
-            ) sub
-            WHERE 111.32 * sqrt(
-                pow((90.0 - (sub.cell_id / 43200 + 0.5) / 120.0) - $1::float8, 2) +
-                pow(((mod(sub.cell_id, 43200) + 0.5) / 120.0 - 180.0 - $2::float8) * cos(radians($1::float8)), 2)
-            ) <= $3::float8
-        "#;
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(c.pop), 0)::float8
+            FROM {cells} c
+        "#,
+            cells = cells_within(&self.grid, false)
+        );
         set_seqscan_off(client).await?;
         let query_result = client
-            .query_one(sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
             .await;
         reset_seqscan(client).await;
         Ok(query_result?.get(0))
     }
 
-    /// Fast existence check: is there ANY populated cell within the bounding box?
-    /// LATERAL + LIMIT 1 stops at the very first populated cell found — empty
-    /// ocean rows cost a single B-tree probe that returns nothing.
-    pub async fn has_population_within(
-        client: &Object,
+    async fn get_ring_populations(
+        &self,
         lat: f64,
         lon: f64,
-        search_km: f64,
-    ) -> Result<bool, AppError> {
-        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, search_km);
-        let sql = r#"
-            SELECT EXISTS(
-                SELECT 1
-                FROM generate_series($1::int, $2::int) AS r(r)
+        rings: &[f64],
+    ) -> Result<Vec<f64>, AppError> {
+        let client = &self.db.conn().await?;
+        let outer_km = rings.last().copied().unwrap_or(0.0);
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, outer_km);
+        let sql = format!(
+            r#"
+            SELECT LEAST(width_bucket(c.km, $8::float8[]), cardinality($8::float8[]) - 1) AS band,
+                   SUM(c.pop)::float8
+            FROM {cells} c
+            GROUP BY band
+        "#,
+            cells = cells_within(&self.grid, false)
+        );
+        let rings = rings.to_vec();
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(
+                &sql,
+                &[&lat, &lon, &outer_km, &min_row, &max_row, &min_col, &max_col, &rings],
+            )
+            .await;
+        reset_seqscan(client).await;
+        let mut bands = vec![0.0; rings.len()];
+        for row in query_result? {
+            let band: i32 = row.get(0);
+            if let Some(slot) = bands.get_mut(band as usize) {
+                *slot = row.get(1);
+            }
+        }
+        Ok(bands)
+    }
+
+    async fn get_density_histogram(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        edges: &[f64],
+    ) -> Result<Vec<(i64, f64)>, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT width_bucket(
+                       c.pop / (pow(111.32 / 120.0, 2)
+                           * cos(radians(90.0 - (c.cell_id / 43200 + 0.5) / 120.0))),
+                       $8::float8[]) AS bucket,
+                   COUNT(*), SUM(c.pop)::float8
+            FROM {cells} c
+            GROUP BY bucket
+        "#,
+            cells = cells_within(&self.grid, false)
+        );
+        let edges = edges.to_vec();
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(
+                &sql,
+                &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col, &edges],
+            )
+            .await;
+        reset_seqscan(client).await;
+        let mut buckets = vec![(0, 0.0); edges.len() + 1];
+        for row in query_result? {
+            let bucket: i32 = row.get(0);
+            if let Some(slot) = buckets.get_mut(bucket as usize) {
+                *slot = (row.get(1), row.get(2));
+            }
+        }
+        Ok(buckets)
+    }
+
+    async fn get_populated_hull(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT ST_AsGeoJSON(ST_ConvexHull(ST_Collect(ST_MakeEnvelope(
+                       (mod(c.cell_id, 43200)) / 120.0 - 180.0,
+                       90.0 - (c.cell_id / 43200 + 1) / 120.0,
+                       (mod(c.cell_id, 43200) + 1) / 120.0 - 180.0,
+                       90.0 - (c.cell_id / 43200) / 120.0,
+                       4326))), 6)
+            FROM {cells} c
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        let geojson: Option<String> = query_result?.get(0);
+        geojson
+            .map(|g| serde_json::from_str(&g))
+            .transpose()
+            .map_err(|e| AppError::Database(format!("invalid hull GeoJSON: {e}")))
+    }
+
+    async fn get_weighted_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        decay: DecayModel,
+    ) -> Result<f64, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(c.pop * CASE $8::text
+                       WHEN 'linear' THEN GREATEST(0.0, 1.0 - c.km / $3::float8)
+                       WHEN 'inverse_square' THEN 1.0 / (1.0 + c.km * c.km)
+                   END), 0)::float8
+            FROM {cells} c
+        "#,
+            cells = cells_within(&self.grid, false)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(
+                &sql,
+                &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col, &decay.as_str()],
+            )
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?.get(0))
+    }
+
+    async fn get_country_split(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        vintage: i32,
+    ) -> Result<Vec<CountryExposure>, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            WITH circle AS (
+                SELECT ST_Buffer(ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography, $3 * 1000.0)::geometry AS g
+            ),
+            cand AS (
+                SELECT c.gid, c.iso_a2, c.iso_a3, c.name, c.geom
+                FROM countries c, circle
+                WHERE c.vintage = $8 AND ST_Intersects(c.geom, circle.g)
+            ),
+            sums AS (
+                SELECT k.gid, SUM(c.pop)::float8 AS pop
+                FROM {cells} c
                 CROSS JOIN LATERAL (
-                    SELECT 1 FROM population p
-                    WHERE p.cell_id BETWEEN r.r * 43200 + $3::int AND r.r * 43200 + $4::int
-                    AND p.pop > 0
+                    SELECT cand.gid FROM cand
+                    WHERE ST_Contains(cand.geom, cell_centre(c.cell_id))
                     LIMIT 1
-                ) sub
+                ) k
+                GROUP BY k.gid
             )
-        "#;
+            SELECT cand.iso_a2, cand.iso_a3, cand.name, COALESCE(sums.pop, 0.0)
+            FROM cand LEFT JOIN sums USING (gid)
+            WHERE (SELECT COUNT(*) FROM cand) > 1
+            ORDER BY 4 DESC, cand.name
+        "#,
+            cells = cells_within(&self.grid, false)
+        );
         set_seqscan_off(client).await?;
         let query_result = client
-            .query_one(sql, &[&min_row, &max_row, &min_col, &max_col])
+            .query(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col, &vintage])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?
+            .iter()
+            .map(|row| CountryExposure {
+                iso_a2: row.get::<_, Option<String>>(0).map(|s| s.trim().to_string()),
+                iso_a3: row.get::<_, Option<String>>(1).map(|s| s.trim().to_string()),
+                name: row.get(2),
+                population: row.get(3),
+                share_of_total: 0.0,
+                place_count: 0,
+            })
+            .collect())
+    }
+
+    async fn get_land_area(&self, lat: f64, lon: f64, radius_km: f64, vintage: i32) -> Result<f64, AppError> {
+        let client = &self.db.conn().await?;
+        let row = client
+            .query_one(
+                r#"
+                WITH circle AS (
+                    SELECT ST_Buffer(ST_SetSRID(ST_MakePoint($2, $1), 4326)::geography, $3 * 1000.0)::geometry AS g
+                )
+                SELECT COALESCE(ST_Area(ST_Union(ST_Intersection(c.geom, circle.g))::geography) / 1e6, 0.0)
+                FROM circle
+                JOIN countries c ON c.vintage = $4 AND ST_Intersects(c.geom, circle.g)
+                "#,
+                &[&lat, &lon, &radius_km, &vintage],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    async fn get_built_up_exposure(&self, lat: f64, lon: f64, radius_km: f64) -> Result<(f64, i64), AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_built_up", "Built-up surface", "ingest-built-up").await?;
+
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(b.built_m2), 0)::float8, COUNT(*)::int8
+            FROM {cells} b
+        "#,
+            cells = cells_within("cell_built_up", false)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1)))
+    }
+
+    async fn get_night_lights_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<(f64, f64, i64), AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_night_lights", "Nighttime lights", "ingest-night-lights").await?;
+
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(n.radiance), 0)::float8, COALESCE(MAX(n.radiance), 0)::float8, COUNT(*)::int8
+            FROM {cells} n
+        "#,
+            cells = cells_within("cell_night_lights", false)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1), row.get(2)))
+    }
+
+    async fn get_land_cover_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Vec<LandCoverShare>, AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_land_cover", "Land cover", "ingest-land-cover").await?;
+
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT l.class, SUM(c.pop)::float8, COUNT(*)::int8
+            FROM {cells} c
+            JOIN cell_land_cover l ON l.cell_id = c.cell_id
+            GROUP BY l.class
+            ORDER BY 2 DESC, l.class
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?
+            .iter()
+            .filter_map(|r| {
+                Some(LandCoverShare {
+                    class: LandCoverClass::from_worldcover(r.get(0))?,
+                    population: r.get(1),
+                    cell_count: r.get(2),
+                    share_of_population: 0.0,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_settlement_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<SettlementExposure, AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_settlement", "Settlement", "ingest-settlement").await?;
+
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(c.pop) FILTER (WHERE s.smod = 30), 0)::float8,
+                   COALESCE(SUM(c.pop) FILTER (WHERE s.smod BETWEEN 21 AND 23), 0)::float8,
+                   COALESCE(SUM(c.pop) FILTER (WHERE s.smod BETWEEN 11 AND 13), 0)::float8,
+                   COALESCE(SUM(c.pop), 0)::float8
+            FROM {cells} c
+            LEFT JOIN cell_settlement s ON s.cell_id = c.cell_id
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        let (urban_centre, urban_cluster, rural, total): (f64, f64, f64, f64) =
+            (row.get(0), row.get(1), row.get(2), row.get(3));
+        Ok(SettlementExposure {
+            urban_centre,
+            urban_cluster,
+            rural,
+            unclassified: (total - urban_centre - urban_cluster - rural).max(0.0),
+        })
+    }
+
+    async fn get_coastal_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        coast_km: f64,
+        max_elevation_m: f64,
+    ) -> Result<(f64, i64), AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_terrain", "Coastal terrain", "ingest-terrain").await?;
+
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+        let sql = format!(
+            r#"
+            SELECT COALESCE(SUM(c.pop), 0)::float8, COUNT(*)::int8
+            FROM {cells} c
+            JOIN cell_terrain t ON t.cell_id = c.cell_id
+            WHERE t.coast_km <= $8::float8
+            AND t.elevation_m <= $9::float8
+        "#,
+            cells = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(
+                &sql,
+                &[
+                    &lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col,
+                    &coast_km, &max_elevation_m,
+                ],
+            )
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1)))
+    }
+
+    async fn get_flood_exposure(
+        &self,
+        area: &SearchArea<'_>,
+        max_elevation_m: f64,
+    ) -> Result<(f64, f64, i64), AppError> {
+        let client = &self.db.conn().await?;
+        require_layer(client, "cell_elevation", "Elevation", "ingest-terrain").await?;
+
+        let sql = flood_sql(&self.grid, area);
+        set_seqscan_off(client).await?;
+        let query_result = match *area {
+            SearchArea::Circle { lat, lon, radius_km } => {
+                let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, radius_km);
+                client
+                    .query_one(
+                        &sql,
+                        &[&lat, &lon, &radius_km, &min_row, &max_row, &min_col, &max_col, &max_elevation_m],
+                    )
+                    .await
+            }
+            SearchArea::Polygon { geojson, bounds } => {
+                let (min_row, max_row, min_col, max_col) = bbox_bounds(bounds);
+                client
+                    .query_one(
+                        &sql,
+                        &[&max_elevation_m, &min_row, &max_row, &min_col, &max_col, &geojson],
+                    )
+                    .await
+            }
+        };
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1), row.get(2)))
+    }
+
+    async fn get_flood_exposure_by_part(
+        &self,
+        parts: &[(String, [f64; 4])],
+        max_elevation_m: f64,
+    ) -> Result<Vec<(f64, f64, i64)>, AppError> {
+        // Each part scans only its own bounds; one query over the union's box
+        // would read every cell between distant islands.
+        let mut exposures = Vec::with_capacity(parts.len());
+        for (geojson, bounds) in parts {
+            let area = SearchArea::Polygon { geojson, bounds: *bounds };
+            exposures.push(self.get_flood_exposure(&area, max_elevation_m).await?);
+        }
+        Ok(exposures)
+    }
+
+    async fn get_polygon_population(
+        &self,
+        geojson: &str,
+        bounds: [f64; 4],
+    ) -> Result<(f64, i64, f64), AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = bbox_bounds(bounds);
+
+        let sql = format!(
+            r#"
+            WITH area AS MATERIALIZED (
+                SELECT ST_SetSRID(ST_GeomFromGeoJSON($5::text), 4326) AS g
+            )
+            SELECT COALESCE(SUM(c.pop), 0)::float8,
+                   COUNT(c.pop)::int8,
+                   (SELECT ST_Area(g::geography) / 1e6 FROM area)::float8
+            FROM area, {cells} c
+            WHERE ST_Intersects(area.g, cell_centre(c.cell_id))
+        "#,
+            cells = cells_in_box(&self.grid, 1, true)
+        );
+
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&min_row, &max_row, &min_col, &max_col, &geojson])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1), row.get(2)))
+    }
+
+    async fn get_buffer_population(
+        &self,
+        geojson: &str,
+        buffer_km: f64,
+        bounds: [f64; 4],
+    ) -> Result<(f64, i64, f64, String), AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = bbox_bounds(bounds);
+
+        let sql = format!(
+            r#"
+            WITH area AS MATERIALIZED (
+                SELECT ST_Buffer(
+                    ST_SetSRID(ST_GeomFromGeoJSON($5::text), 4326)::geography, $6::float8 * 1000
+                )::geometry AS g
+            )
+            SELECT COALESCE(SUM(c.pop), 0)::float8,
+                   COUNT(c.pop)::int8,
+                   (SELECT ST_Area(g::geography) / 1e6 FROM area)::float8,
+                   (SELECT ST_AsGeoJSON(g, 6) FROM area)
+            FROM area, {cells} c
+            WHERE ST_Intersects(area.g, cell_centre(c.cell_id))
+        "#,
+            cells = cells_in_box(&self.grid, 1, true)
+        );
+
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&min_row, &max_row, &min_col, &max_col, &geojson, &buffer_km])
+            .await;
+        reset_seqscan(client).await;
+        let row = query_result?;
+        Ok((row.get(0), row.get(1), row.get(2), row.get(3)))
+    }
+
+    async fn get_quadrant_swath_populations(
+        &self,
+        lat: f64,
+        lon: f64,
+        swaths: &[[f64; 4]],
+    ) -> Result<Vec<f64>, AppError> {
+        let client = &self.db.conn().await?;
+        let max_radius = swaths.iter().flatten().copied().fold(0.0, f64::max);
+        if max_radius <= 0.0 {
+            return Ok(vec![0.0; swaths.len()]);
+        }
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, max_radius);
+        let radii: Vec<f64> = swaths.iter().flatten().copied().collect();
+        let swath_count = swaths.len() as i32;
+
+        let sql = format!(
+            r#"
+            WITH cells AS MATERIALIZED (
+                SELECT w.pop, w.km,
+                       floor((degrees(atan2(
+                           ((mod(w.cell_id, 43200) + 0.5) / 120.0 - 180.0 - $2::float8) * cos(radians($1::float8)),
+                           (90.0 - (w.cell_id / 43200 + 0.5) / 120.0) - $1::float8
+                       )) + 360.0) / 90.0)::int % 4 + 1 AS q
+                FROM {within} w
+            )
+            SELECT COALESCE(SUM(c.pop) FILTER (
+                       WHERE c.km <= ($8::float8[])[(s.i - 1) * 4 + c.q]
+                   ), 0)::float8
+            FROM generate_series(1, $9::int) AS s(i)
+            LEFT JOIN cells c ON true
+            GROUP BY s.i
+            ORDER BY s.i
+        "#,
+            within = cells_within(&self.grid, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query(
+                &sql,
+                &[&lat, &lon, &max_radius, &min_row, &max_row, &min_col, &max_col, &radii, &swath_count],
+            )
+            .await;
+        reset_seqscan(client).await;
+        Ok(query_result?.iter().map(|r| r.get(0)).collect())
+    }
+
+    async fn has_population_within(
+        &self,
+        lat: f64,
+        lon: f64,
+        search_km: f64,
+    ) -> Result<bool, AppError> {
+        let client = &self.db.conn().await?;
+        let (min_row, max_row, min_col, max_col) = search_bounds(lat, lon, search_km);
+        let sql = format!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM {cells} c)
+        "#,
+            cells = cells_in_box(&self.grid, 1, true)
+        );
+        set_seqscan_off(client).await?;
+        let query_result = client
+            .query_one(&sql, &[&min_row, &max_row, &min_col, &max_col])
             .await;
         reset_seqscan(client).await;
         Ok(query_result?.get(0))
     }
+
+    async fn for_year(
+        &self,
+        year: Option<i32>,
+    ) -> Result<(i32, Arc<dyn PopulationRepository>), AppError> {
+        let client = &self.db.conn().await?;
+        let dataset = self.dataset.as_str();
+        let stmt = client.prepare_cached(statements::POPULATION_YEARS).await?;
+        let loaded: Vec<i32> = client
+            .query(&stmt, &[&dataset])
+            .await?
+            .iter()
+            .map(|r| r.get(0))
+            .collect();
+        let Some(&latest) = loaded.last() else {
+            let target = match self.dataset {
+                Dataset::Unconstrained => "ingest-worldpop",
+                Dataset::Constrained => "ingest-worldpop-constrained",
+            };
+            return Err(AppError::NotFound(format!(
+                "The {dataset} population grid is not loaded (run `make {target}`)"
+            )));
+        };
+        let year = year.unwrap_or(latest);
+        if !loaded.contains(&year) {
+            let available: Vec<String> = loaded.iter().map(i32::to_string).collect();
+            return Err(AppError::NotFound(format!(
+                "No {dataset} population grid for {year}. Available: {}",
+                available.join(", ")
+            )));
+        }
+        // `dataset` and `year` are checked against population_years above.
+        let grid = if year == latest {
+            grid_table(self.dataset).to_string()
+        } else {
+            format!(
+                "(SELECT cell_id, pop FROM population_history WHERE dataset = '{dataset}' AND year = {year})"
+            )
+        };
+        let repo = Self {
+            db: self.db.clone(),
+            dataset: self.dataset,
+            grid,
+            latest: year == latest,
+            exposure: self.exposure,
+        };
+        Ok((year, Arc::new(repo)))
+    }
+}
+
+/// The grid cell at row/column `(r, c)`, or with `size` > 1 the
+/// `size`×`size` block of cells at block row/column `(r, c)`.
+fn grid_cell(r: i32, c: i32, size: i32, population: f32) -> GridCell {
+    let step = size as f64 / 120.0;
+    let max_lat = 90.0 - r as f64 * step;
+    let min_lat = (max_lat - step).max(-90.0);
+    let min_lon = c as f64 * step - 180.0;
+    let max_lon = (min_lon + step).min(180.0);
+
+    GridCell {
+        lat: round5((min_lat + max_lat) / 2.0),
+        lon: round5((min_lon + max_lon) / 2.0),
+        population,
+        bounds: CellBounds {
+            min_lat: round5(min_lat),
+            max_lat: round5(max_lat),
+            min_lon: round5(min_lon),
+            max_lon: round5(max_lon),
+        },
+        uncertainty: None,
+    }
+}
+
+/// Fails with `NotFound` when an optional per-cell layer has not been loaded,
+/// so callers don't report a misleading zero. `target` is the make target that
+/// loads it.
+async fn require_layer(client: &Object, table: &str, label: &str, target: &str) -> Result<(), AppError> {
+    let loaded: bool = client
+        .query_one(&format!("SELECT EXISTS (SELECT 1 FROM {table})"), &[])
+        .await?
+        .get(0);
+    if !loaded {
+        return Err(AppError::NotFound(format!(
+            "{label} layer is not loaded (run `make {target}`)"
+        )));
+    }
+    Ok(())
+}
+
+/// The three columns of a list of triples, as arrays for `unnest`.
+fn columns(triples: &[(i32, i32, i32)]) -> (Vec<i32>, Vec<i32>, Vec<i32>) {
+    (
+        triples.iter().map(|t| t.0).collect(),
+        triples.iter().map(|t| t.1).collect(),
+        triples.iter().map(|t| t.2).collect(),
+    )
 }
 
 async fn set_seqscan_off(client: &Object) -> Result<(), AppError> {
@@ -198,7 +1634,71 @@ async fn reset_seqscan(client: &Object) {
     }
 }
 
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
 #[inline]
 fn round5(v: f64) -> f64 {
     (v * 100_000.0).round() / 100_000.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The distinct `$n` placeholders of `sql`, in order.
+    fn placeholders(sql: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = sql
+            .split('$')
+            .skip(1)
+            .filter_map(|rest| {
+                let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok()
+            })
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    #[test]
+    fn pyramid_sql_numbers_the_circle_first() {
+        let sql = pyramid_sql("population");
+        assert_eq!(placeholders(&sql), (1..=10).collect::<Vec<_>>());
+        assert!(sql.contains("- $1::float8, 2)"), "{sql}");
+        assert!(sql.contains("- 180.0 - $2::float8) * cos(radians($1::float8))"), "{sql}");
+        assert!(sql.contains("<= $3::float8"), "{sql}");
+        assert!(sql.contains("dataset = $4)") && sql.contains("r.dataset = $4 AND"), "{sql}");
+        assert!(sql.contains("unnest($5::int[], $6::int[], $7::int[]) AS b (size, brow, bcol)"), "{sql}");
+        assert!(sql.contains("unnest($8::int[], $9::int[], $10::int[]) AS s (r, c0, c1)"), "{sql}");
+    }
+
+    #[test]
+    fn flood_sql_places_the_elevation_limit_by_area() {
+        let circle = flood_sql("population", &SearchArea::Circle { lat: 6.9, lon: 79.9, radius_km: 5.0 });
+        assert_eq!(placeholders(&circle), (1..=8).collect::<Vec<_>>());
+        assert!(circle.contains("d.km <= $3::float8"), "{circle}");
+        assert!(circle.contains("generate_series($4::int, $5::int)"), "{circle}");
+        assert!(circle.contains("r.r * 43200 + $6::int AND r.r * 43200 + $7::int"), "{circle}");
+        assert!(circle.contains("e.elevation_m <= $8::float8"), "{circle}");
+
+        let polygon = flood_sql("population", &SearchArea::Polygon { geojson: "{}", bounds: [79.8, 6.8, 80.0, 7.0] });
+        assert_eq!(placeholders(&polygon), (1..=6).collect::<Vec<_>>());
+        assert!(polygon.contains("e.elevation_m <= $1::float8"), "{polygon}");
+        assert!(polygon.contains("generate_series($2::int, $3::int)"), "{polygon}");
+        assert!(polygon.contains("r.r * 43200 + $4::int AND r.r * 43200 + $5::int"), "{polygon}");
+        assert!(polygon.contains("ST_GeomFromGeoJSON($6::text)"), "{polygon}");
+        assert!(!polygon.contains("d.km"), "{polygon}");
+    }
+
+    #[test]
+    fn populated_scans_filter_inside_the_index_range() {
+        let scan = cells_in_box("population", 1, true);
+        let range = scan.find("BETWEEN").unwrap();
+        let filter = scan.find("AND p.pop > 0").expect("populated scan filters on pop");
+        assert!(range < filter && filter < scan.find(") sub").unwrap(), "{scan}");
+        assert!(!cells_in_box("cell_built_up", 1, false).contains("pop"));
+    }
+}
@@ -0,0 +1,592 @@
+//! Retries for transient database errors.
+//!
+//! A Postgres failover or an administrator terminating backends breaks the
+//! pooled connections mid-flight, and a hot standby cancels queries that
+//! conflict with replay. Each is gone on the next attempt, so the read
+//! repositories are wrapped in [`Retrying`], which re-runs a call that failed
+//! with `AppError::Transient` up to `MAX_ATTEMPTS` times with jittered
+//! exponential backoff. Every query behind these traits is read-only, so
+//! re-running one is always safe. An open circuit breaker is not retried.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{AdminUnit, CountryCode, CountryFilter, CountryRepository, GeocodingRepository, PlaceOptions};
+use super::{PopulationRepository, SearchArea};
+use crate::errors::AppError;
+use crate::geopackage::{CountryFeature, PlaceFeature};
+use crate::models::{
+    AdminArea, AdminCountry, AdminGroup, AdminLevel, AdminPlace, AggregateInterval, Airport, CityHit, ContinentStats, CountryDetailPayload, CountryExposure,
+    CountryPayload, CountryPopulationPayload, DecayModel, DensityRankPayload, EezZone, ExposedPlace, Facility, FacilityGroup, FacilityType, GridCell,
+    LandCoverClass, LandCoverShare, NearbyCountryEntry, NearestPlace, PopulationInterval, ReverseMode, ReversePayload, SettlementClass,
+    SettlementExposure,
+};
+
+const MAX_ATTEMPTS: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Random delay in `[d/2, d]` for `d = BASE_BACKOFF * 2^attempt`, so clients
+/// that failed together do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let full = BASE_BACKOFF * 2u32.pow(attempt);
+    let jitter = RandomState::new().build_hasher().finish() % 1000;
+    full / 2 + full / 2 * jitter as u32 / 1000
+}
+
+/// Awaits `$call` again while it fails with a transient error and attempts
+/// remain. A macro rather than a function so every attempt re-borrows the
+/// arguments, including `&mut` ones.
+macro_rules! retrying {
+    ($call:expr) => {{
+        let mut attempt = 0;
+        loop {
+            match $call.await {
+                Err(AppError::Transient(msg)) if attempt + 1 < MAX_ATTEMPTS => {
+                    let wait = backoff(attempt);
+                    log::warn!("Transient database error, retrying in {wait:?}: {msg}");
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        }
+    }};
+}
+
+/// A repository whose calls are retried on transient database errors.
+pub(crate) struct Retrying<R: ?Sized>(pub Arc<R>);
+
+#[async_trait]
+impl<R: PopulationRepository + ?Sized> PopulationRepository for Retrying<R> {
+    async fn get_population(&self, lat: f64, lon: f64) -> Result<f32, AppError> {
+        retrying!(self.0.get_population(lat, lon))
+    }
+
+    async fn get_batch_population(&self, points: &[(f64, f64)]) -> Result<Vec<f32>, AppError> {
+        retrying!(self.0.get_batch_population(points))
+    }
+
+    async fn get_cell_population(&self, lat: f64, lon: f64) -> Result<f32, AppError> {
+        retrying!(self.0.get_cell_population(lat, lon))
+    }
+
+    async fn get_cell_terrain(&self, lat: f64, lon: f64) -> Result<Option<(f64, Option<f64>)>, AppError> {
+        retrying!(self.0.get_cell_terrain(lat, lon))
+    }
+
+    async fn get_cell_settlement(&self, lat: f64, lon: f64) -> Result<Option<SettlementClass>, AppError> {
+        retrying!(self.0.get_cell_settlement(lat, lon))
+    }
+
+    async fn get_cell_built_up(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError> {
+        retrying!(self.0.get_cell_built_up(lat, lon))
+    }
+
+    async fn get_cell_night_lights(&self, lat: f64, lon: f64) -> Result<Option<f64>, AppError> {
+        retrying!(self.0.get_cell_night_lights(lat, lon))
+    }
+
+    async fn get_cell_land_cover(&self, lat: f64, lon: f64) -> Result<Option<LandCoverClass>, AppError> {
+        retrying!(self.0.get_cell_land_cover(lat, lon))
+    }
+
+    async fn get_density_rank(&self, lat: f64, lon: f64) -> Result<DensityRankPayload, AppError> {
+        retrying!(self.0.get_density_rank(lat, lon))
+    }
+
+    async fn get_grid_cells(&self, lat: f64, lon: f64, radius_km: f64) -> Result<Vec<GridCell>, AppError> {
+        retrying!(self.0.get_grid_cells(lat, lon, radius_km))
+    }
+
+    async fn count_bbox_cells(&self, bbox: [f64; 4]) -> Result<i64, AppError> {
+        retrying!(self.0.count_bbox_cells(bbox))
+    }
+
+    async fn get_bbox_grid_cells(&self, bbox: [f64; 4], aggregation: i32) -> Result<Vec<GridCell>, AppError> {
+        retrying!(self.0.get_bbox_grid_cells(bbox, aggregation))
+    }
+
+    async fn get_cell_uncertainty(&self, lat: f64, lon: f64) -> Result<Option<PopulationInterval>, AppError> {
+        retrying!(self.0.get_cell_uncertainty(lat, lon))
+    }
+
+    async fn attach_grid_uncertainty(&self, cells: &mut [GridCell]) -> Result<AggregateInterval, AppError> {
+        retrying!(self.0.attach_grid_uncertainty(cells))
+    }
+
+    async fn get_exposure_uncertainty(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<AggregateInterval, AppError> {
+        retrying!(self.0.get_exposure_uncertainty(lat, lon, radius_km))
+    }
+
+    async fn get_exposure_population(&self, lat: f64, lon: f64, radius_km: f64) -> Result<f64, AppError> {
+        retrying!(self.0.get_exposure_population(lat, lon, radius_km))
+    }
+
+    async fn get_top_cells(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        limit: i64,
+    ) -> Result<Vec<GridCell>, AppError> {
+        retrying!(self.0.get_top_cells(lat, lon, radius_km, limit))
+    }
+
+    async fn get_density_histogram(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        edges: &[f64],
+    ) -> Result<Vec<(i64, f64)>, AppError> {
+        retrying!(self.0.get_density_histogram(lat, lon, radius_km, edges))
+    }
+
+    async fn get_ring_populations(&self, lat: f64, lon: f64, rings: &[f64]) -> Result<Vec<f64>, AppError> {
+        retrying!(self.0.get_ring_populations(lat, lon, rings))
+    }
+
+    async fn get_populated_hull(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Option<serde_json::Value>, AppError> {
+        retrying!(self.0.get_populated_hull(lat, lon, radius_km))
+    }
+
+    async fn get_weighted_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        decay: DecayModel,
+    ) -> Result<f64, AppError> {
+        retrying!(self.0.get_weighted_exposure_population(lat, lon, radius_km, decay))
+    }
+
+    async fn get_built_up_exposure(&self, lat: f64, lon: f64, radius_km: f64) -> Result<(f64, i64), AppError> {
+        retrying!(self.0.get_built_up_exposure(lat, lon, radius_km))
+    }
+
+    async fn get_night_lights_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<(f64, f64, i64), AppError> {
+        retrying!(self.0.get_night_lights_exposure(lat, lon, radius_km))
+    }
+
+    async fn get_land_cover_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<Vec<LandCoverShare>, AppError> {
+        retrying!(self.0.get_land_cover_exposure(lat, lon, radius_km))
+    }
+
+    async fn get_settlement_exposure(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    ) -> Result<SettlementExposure, AppError> {
+        retrying!(self.0.get_settlement_exposure(lat, lon, radius_km))
+    }
+
+    async fn get_coastal_exposure_population(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        coast_km: f64,
+        max_elevation_m: f64,
+    ) -> Result<(f64, i64), AppError> {
+        retrying!(self.0.get_coastal_exposure_population(lat, lon, radius_km, coast_km, max_elevation_m))
+    }
+
+    async fn get_country_split(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        vintage: i32,
+    ) -> Result<Vec<CountryExposure>, AppError> {
+        retrying!(self.0.get_country_split(lat, lon, radius_km, vintage))
+    }
+
+    async fn get_land_area(&self, lat: f64, lon: f64, radius_km: f64, vintage: i32) -> Result<f64, AppError> {
+        retrying!(self.0.get_land_area(lat, lon, radius_km, vintage))
+    }
+
+    async fn get_flood_exposure(
+        &self,
+        area: &SearchArea<'_>,
+        max_elevation_m: f64,
+    ) -> Result<(f64, f64, i64), AppError> {
+        retrying!(self.0.get_flood_exposure(area, max_elevation_m))
+    }
+
+    async fn get_flood_exposure_by_part(
+        &self,
+        parts: &[(String, [f64; 4])],
+        max_elevation_m: f64,
+    ) -> Result<Vec<(f64, f64, i64)>, AppError> {
+        retrying!(self.0.get_flood_exposure_by_part(parts, max_elevation_m))
+    }
+
+    async fn get_polygon_population(&self, geojson: &str, bounds: [f64; 4]) -> Result<(f64, i64, f64), AppError> {
+        retrying!(self.0.get_polygon_population(geojson, bounds))
+    }
+
+    async fn get_buffer_population(
+        &self,
+        geojson: &str,
+        buffer_km: f64,
+        bounds: [f64; 4],
+    ) -> Result<(f64, i64, f64, String), AppError> {
+        retrying!(self.0.get_buffer_population(geojson, buffer_km, bounds))
+    }
+
+    async fn get_quadrant_swath_populations(
+        &self,
+        lat: f64,
+        lon: f64,
+        swaths: &[[f64; 4]],
+    ) -> Result<Vec<f64>, AppError> {
+        retrying!(self.0.get_quadrant_swath_populations(lat, lon, swaths))
+    }
+
+    async fn has_population_within(&self, lat: f64, lon: f64, search_km: f64) -> Result<bool, AppError> {
+        retrying!(self.0.has_population_within(lat, lon, search_km))
+    }
+
+    async fn for_year(&self, year: Option<i32>) -> Result<(i32, Arc<dyn PopulationRepository>), AppError> {
+        let (year, repo) = retrying!(self.0.for_year(year))?;
+        Ok((year, Arc::new(Retrying(repo))))
+    }
+}
+
+#[async_trait]
+impl<R: GeocodingRepository + ?Sized> GeocodingRepository for Retrying<R> {
+    async fn reverse_geocode(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        max_distance_km: Option<f64>,
+        mode: ReverseMode,
+        vintage: i32,
+    ) -> Result<ReversePayload, AppError> {
+        retrying!(self.0.reverse_geocode(lat, lon, languages, max_distance_km, mode, vintage))
+    }
+
+    async fn search_cities(
+        &self,
+        query: &str,
+        country: Option<&str>,
+        limit: i64,
+        min_population: i64,
+        vintage: i32,
+    ) -> Result<Vec<CityHit>, AppError> {
+        retrying!(self.0.search_cities(query, country, limit, min_population, vintage))
+    }
+
+    async fn find_nearest_place(&self, lat: f64, lon: f64, vintage: i32) -> Result<NearestPlace, AppError> {
+        retrying!(self.0.find_nearest_place(lat, lon, vintage))
+    }
+
+    async fn find_nearest_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        limit: i64,
+        vintage: i32,
+    ) -> Result<Vec<NearestPlace>, AppError> {
+        retrying!(self.0.find_nearest_places(lat, lon, limit, vintage))
+    }
+
+    async fn find_nearest_facilities(
+        &self,
+        lat: f64,
+        lon: f64,
+        feature_code: &str,
+        limit: i64,
+    ) -> Result<Vec<Facility>, AppError> {
+        retrying!(self.0.find_nearest_facilities(lat, lon, feature_code, limit))
+    }
+
+    async fn find_facilities_within(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        types: &[FacilityType],
+        limit: i64,
+    ) -> Result<Vec<FacilityGroup>, AppError> {
+        retrying!(self.0.find_facilities_within(lat, lon, radius_km, types, limit))
+    }
+
+    async fn find_nearest_airports(
+        &self,
+        lat: f64,
+        lon: f64,
+        min_runway_m: Option<f64>,
+        limit: i64,
+    ) -> Result<Vec<Airport>, AppError> {
+        retrying!(self.0.find_nearest_airports(lat, lon, min_runway_m, limit))
+    }
+
+    async fn find_timezone(&self, lat: f64, lon: f64) -> Result<Option<String>, AppError> {
+        retrying!(self.0.find_timezone(lat, lon))
+    }
+
+    async fn count_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        min_population: i64,
+    ) -> Result<i64, AppError> {
+        retrying!(self.0.count_exposed_places(lat, lon, radius_km, min_population))
+    }
+
+    async fn count_exposed_places_by_country(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        min_population: i64,
+    ) -> Result<HashMap<String, i64>, AppError> {
+        retrying!(self.0.count_exposed_places_by_country(lat, lon, radius_km, min_population))
+    }
+
+    async fn get_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        options: PlaceOptions,
+        limit: i64,
+        offset: i64,
+        vintage: i32,
+    ) -> Result<Vec<ExposedPlace>, AppError> {
+        retrying!(self.0.get_exposed_places(lat, lon, radius_km, options, limit, offset, vintage))
+    }
+
+    async fn group_exposed_places(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        level: AdminLevel,
+        min_population: i64,
+    ) -> Result<Vec<AdminGroup>, AppError> {
+        retrying!(self.0.group_exposed_places(lat, lon, radius_km, level, min_population))
+    }
+
+    async fn get_places_in_bbox(&self, bbox: [f64; 4], limit: i64) -> Result<Vec<PlaceFeature>, AppError> {
+        retrying!(self.0.get_places_in_bbox(bbox, limit))
+    }
+
+    async fn admin_hierarchy(
+        &self,
+        lat: f64,
+        lon: f64,
+        languages: &[String],
+        vintage: i32,
+    ) -> Result<(Option<AdminCountry>, Option<AdminArea>, Option<AdminArea>), AppError> {
+        retrying!(self.0.admin_hierarchy(lat, lon, languages, vintage))
+    }
+
+    async fn get_admin_unit(&self, code: &str, vintage: i32) -> Result<Option<AdminUnit>, AppError> {
+        retrying!(self.0.get_admin_unit(code, vintage))
+    }
+
+    async fn get_admin_places(&self, code: &str, limit: i64, vintage: i32) -> Result<Vec<AdminPlace>, AppError> {
+        retrying!(self.0.get_admin_places(code, limit, vintage))
+    }
+}
+
+#[async_trait]
+impl<R: CountryRepository + ?Sized> CountryRepository for Retrying<R> {
+    async fn resolve_vintage(&self, requested: Option<i32>) -> Result<i32, AppError> {
+        retrying!(self.0.resolve_vintage(requested))
+    }
+
+    async fn is_land(&self, lat: f64, lon: f64, vintage: i32) -> Result<bool, AppError> {
+        retrying!(self.0.is_land(lat, lon, vintage))
+    }
+
+    async fn get_land_country(&self, lat: f64, lon: f64, vintage: i32) -> Result<Option<CountryPayload>, AppError> {
+        retrying!(self.0.get_land_country(lat, lon, vintage))
+    }
+
+    async fn get_nearby_countries(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        vintage: i32,
+    ) -> Result<Vec<NearbyCountryEntry>, AppError> {
+        retrying!(self.0.get_nearby_countries(lat, lon, radius_km, vintage))
+    }
+
+    async fn get_by_coordinate(&self, lat: f64, lon: f64, vintage: i32) -> Result<CountryPayload, AppError> {
+        retrying!(self.0.get_by_coordinate(lat, lon, vintage))
+    }
+
+    async fn get_by_code(&self, code: &CountryCode, vintage: i32) -> Result<CountryDetailPayload, AppError> {
+        retrying!(self.0.get_by_code(code, vintage))
+    }
+
+    async fn get_countries(&self, filter: &CountryFilter, vintage: i32) -> Result<Vec<CountryPayload>, AppError> {
+        retrying!(self.0.get_countries(filter, vintage))
+    }
+
+    async fn get_continent_stats(&self, vintage: i32) -> Result<Vec<ContinentStats>, AppError> {
+        retrying!(self.0.get_continent_stats(vintage))
+    }
+
+    async fn get_grid_population(&self, iso3: &str, vintage: i32) -> Result<CountryPopulationPayload, AppError> {
+        retrying!(self.0.get_grid_population(iso3, vintage))
+    }
+
+    async fn get_eez(&self, lat: f64, lon: f64) -> Result<Option<EezZone>, AppError> {
+        retrying!(self.0.get_eez(lat, lon))
+    }
+
+    async fn get_boundary_tile(
+        &self,
+        z: u32,
+        x: u32,
+        y: u32,
+        tolerance_m: f64,
+        vintage: i32,
+    ) -> Result<Vec<u8>, AppError> {
+        retrying!(self.0.get_boundary_tile(z, x, y, tolerance_m, vintage))
+    }
+
+    async fn get_outlines(
+        &self,
+        bbox: [f64; 4],
+        tolerance_deg: f64,
+        vintage: i32,
+    ) -> Result<Vec<Vec<[f64; 2]>>, AppError> {
+        retrying!(self.0.get_outlines(bbox, tolerance_deg, vintage))
+    }
+
+    async fn get_clipped_boundaries(&self, bbox: [f64; 4], vintage: i32) -> Result<Vec<CountryFeature>, AppError> {
+        retrying!(self.0.get_clipped_boundaries(bbox, vintage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct Flaky {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl CountryRepository for Flaky {
+        async fn resolve_vintage(&self, _: Option<i32>) -> Result<i32, AppError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(AppError::Transient("connection reset".into()))
+            } else {
+                Ok(2024)
+            }
+        }
+
+        async fn is_land(&self, _: f64, _: f64, _: i32) -> Result<bool, AppError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::Database("syntax error".into()))
+        }
+
+        async fn get_land_country(&self, _: f64, _: f64, _: i32) -> Result<Option<CountryPayload>, AppError> {
+            Ok(None)
+        }
+        async fn get_nearby_countries(
+            &self,
+            _: f64,
+            _: f64,
+            _: f64,
+            _: i32,
+        ) -> Result<Vec<NearbyCountryEntry>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_by_coordinate(&self, _: f64, _: f64, _: i32) -> Result<CountryPayload, AppError> {
+            Err(AppError::NotFound("No country found".into()))
+        }
+        async fn get_by_code(&self, _: &CountryCode, _: i32) -> Result<CountryDetailPayload, AppError> {
+            Err(AppError::NotFound("No country found".into()))
+        }
+        async fn get_countries(&self, _: &CountryFilter, _: i32) -> Result<Vec<CountryPayload>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_continent_stats(&self, _: i32) -> Result<Vec<ContinentStats>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_grid_population(&self, _: &str, _: i32) -> Result<CountryPopulationPayload, AppError> {
+            Err(AppError::NotFound("No country found".into()))
+        }
+        async fn get_eez(&self, _: f64, _: f64) -> Result<Option<EezZone>, AppError> {
+            Ok(None)
+        }
+        async fn get_boundary_tile(&self, _: u32, _: u32, _: u32, _: f64, _: i32) -> Result<Vec<u8>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_outlines(&self, _: [f64; 4], _: f64, _: i32) -> Result<Vec<Vec<[f64; 2]>>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_clipped_boundaries(&self, _: [f64; 4], _: i32) -> Result<Vec<CountryFeature>, AppError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn flaky(failures: u32) -> Retrying<Flaky> {
+        Retrying(Arc::new(Flaky { failures, calls: AtomicU32::new(0) }))
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_up_to_the_cap() {
+        let repo = flaky(MAX_ATTEMPTS - 1);
+        assert_eq!(repo.resolve_vintage(None).await.unwrap(), 2024);
+        assert_eq!(repo.0.calls.load(Ordering::SeqCst), MAX_ATTEMPTS);
+
+        let repo = flaky(MAX_ATTEMPTS);
+        assert!(matches!(repo.resolve_vintage(None).await, Err(AppError::Transient(_))));
+        assert_eq!(repo.0.calls.load(Ordering::SeqCst), MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        let repo = flaky(0);
+        assert!(matches!(repo.is_land(0.0, 0.0, 2024).await, Err(AppError::Database(_))));
+        assert_eq!(repo.0.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn backoff_grows_with_jitter() {
+        for attempt in 0..3 {
+            let full = BASE_BACKOFF * 2u32.pow(attempt);
+            let wait = backoff(attempt);
+            assert!(wait >= full / 2 && wait <= full, "{wait:?}");
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Registry of the hot queries, prepared once per pooled connection.
+//!
+//! Passing SQL text to `query` makes Postgres parse and plan it on every call.
+//! The statements here run on nearly every request, so the repositories hand
+//! them to `prepare_cached`: deadpool keeps the prepared statement on the
+//! connection, and later uses only bind and execute. Only fixed texts belong
+//! here — SQL built per request with interpolated values would leave a cached
+//! statement behind for every distinct text.
+
+/// Population of one grid cell (`$1` = cell id) in `grid`, a population
+/// table or a year's history subquery. One text per grid, so a handful per
+/// connection.
+pub(super) fn cell_population(grid: &str) -> String {
+    format!("SELECT pop FROM {grid} p WHERE p.cell_id = $1")
+}
+
+/// Grid years loaded for a dataset (`$1`), oldest first.
+pub(super) const POPULATION_YEARS: &str =
+    "SELECT year FROM population_years WHERE dataset = $1 ORDER BY year";
+
+/// The requested boundary vintage (`$1`, NULL for the current one), if loaded.
+pub(super) const BOUNDARY_VINTAGE: &str =
+    "SELECT vintage FROM boundary_vintages WHERE vintage = COALESCE($1::int, current_boundary_vintage())";
+
+/// Whether any country of vintage `$3` contains the point (`$1` lon, `$2` lat).
+pub(super) const IS_LAND: &str = r#"
+    SELECT EXISTS(
+        SELECT 1 FROM countries
+        WHERE vintage = $3 AND ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
+    )
+"#;
+
+/// The country of vintage `$3` containing the point (`$1` lon, `$2` lat), in
+/// the column order of `PgCountryRepository::build_country_payload`.
+pub(super) const COUNTRY_AT_POINT: &str = r#"
+    SELECT iso_a2, iso_a3, name, formal_name, continent, region_un, subregion, vintage, sovereign,
+           area_km2, pop_est
+    FROM countries
+    WHERE vintage = $3 AND ST_Contains(geom, ST_SetSRID(ST_MakePoint($1, $2), 4326))
+    LIMIT 1
+"#;
+
+/// The GeoNames place nearest the point (`$1` lon, `$2` lat) with its admin
+/// names, localised to the first of the languages `$3` that has a name, and
+/// the country name of vintage `$4`, in the column order of
+/// `PgGeocodingRepository::build_reverse_payload`.
+pub(super) const REVERSE_GEOCODE: &str = r#"
+    SELECT g.geonameid, g.name, g.latitude, g.longitude,
+           g.feature_code, g.country_code, g.admin1_code, g.admin2_code,
+           COALESCE(localised_name(a1.geonameid, $3), a1.name),
+           COALESCE(localised_name(a2.geonameid, $3), a2.name),
+           COALESCE(localised_name(ci.geonameid, $3), c.name),
+           COALESCE(g.population, 0),
+           localised_name(g.geonameid, $3),
+           ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
+    FROM geonames g
+    LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
+    LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
+    LEFT JOIN countries c ON c.iso_a2 = g.country_code AND c.vintage = $4
+    LEFT JOIN country_info ci ON ci.iso_a2 = g.country_code
+    ORDER BY g.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+    LIMIT 1
+"#;
+
+/// Like [`REVERSE_GEOCODE`], but with the country and regions of the polygons
+/// containing the point (admin and country boundaries of vintage `$4`). A
+/// level without a containing polygon falls back to the nearest place's, as
+/// long as that place lies in the regions resolved above it.
+pub(super) const REVERSE_GEOCODE_CONTAINED: &str = r#"
+    WITH pt AS (SELECT ST_SetSRID(ST_MakePoint($1, $2), 4326) AS geom),
+    pc AS (
+        SELECT c.iso_a2, c.name FROM countries c, pt
+        WHERE c.vintage = $4 AND ST_Contains(c.geom, pt.geom)
+        ORDER BY ST_Area(c.geom) LIMIT 1
+    ),
+    b1 AS (
+        SELECT b.code, b.name FROM admin_boundaries b, pt
+        WHERE b.vintage = $4 AND b.level = 1 AND ST_Contains(b.geom, pt.geom)
+        ORDER BY ST_Area(b.geom) LIMIT 1
+    ),
+    b2 AS (
+        SELECT b.code, b.name FROM admin_boundaries b, pt
+        WHERE b.vintage = $4 AND b.level = 2 AND ST_Contains(b.geom, pt.geom)
+        ORDER BY ST_Area(b.geom) LIMIT 1
+    )
+    SELECT g.geonameid, g.name, g.latitude, g.longitude,
+           g.feature_code, CASE WHEN pc.name IS NULL THEN g.country_code ELSE pc.iso_a2 END,
+           g.admin1_code, g.admin2_code,
+           CASE WHEN b1.name IS NOT NULL
+                    THEN COALESCE(localised_name(ba1.geonameid, $3), ba1.name, b1.name)
+                WHEN pc.name IS NULL OR pc.iso_a2 = g.country_code
+                    THEN COALESCE(localised_name(a1.geonameid, $3), a1.name)
+           END,
+           CASE WHEN b2.name IS NOT NULL
+                    THEN COALESCE(localised_name(ba2.geonameid, $3), ba2.name, b2.name)
+                WHEN b1.code = a1.code
+                  OR (b1.name IS NULL AND (pc.name IS NULL OR pc.iso_a2 = g.country_code))
+                    THEN COALESCE(localised_name(a2.geonameid, $3), a2.name)
+           END,
+           CASE WHEN pc.name IS NOT NULL
+                    THEN COALESCE(localised_name(pci.geonameid, $3), pc.name)
+                ELSE COALESCE(localised_name(ci.geonameid, $3), c.name)
+           END,
+           COALESCE(g.population, 0),
+           localised_name(g.geonameid, $3),
+           ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
+    FROM geonames g
+    LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
+    LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
+    LEFT JOIN countries c ON c.iso_a2 = g.country_code AND c.vintage = $4
+    LEFT JOIN country_info ci ON ci.iso_a2 = g.country_code
+    LEFT JOIN pc ON true
+    LEFT JOIN b1 ON true
+    LEFT JOIN b2 ON true
+    LEFT JOIN admin1_codes ba1 ON ba1.code = b1.code
+    LEFT JOIN admin2_codes ba2 ON ba2.code = b2.code
+    LEFT JOIN country_info pci ON pci.iso_a2 = pc.iso_a2
+    ORDER BY g.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+    LIMIT 1
+"#;
+
+/// The country and first- and second-level admin polygons of vintage `$4`
+/// containing the point, as one row of nullable codes and names, the names
+/// localised into `$3`.
+pub(super) const ADMIN_HIERARCHY: &str = r#"
+    WITH pt AS (SELECT ST_SetSRID(ST_MakePoint($1, $2), 4326) AS geom),
+    pc AS (
+        SELECT c.iso_a2, c.iso_a3, c.name FROM countries c, pt
+        WHERE c.vintage = $4 AND ST_Contains(c.geom, pt.geom)
+        ORDER BY ST_Area(c.geom) LIMIT 1
+    ),
+    b1 AS (
+        SELECT b.code, b.name FROM admin_boundaries b, pt
+        WHERE b.vintage = $4 AND b.level = 1 AND ST_Contains(b.geom, pt.geom)
+        ORDER BY ST_Area(b.geom) LIMIT 1
+    ),
+    b2 AS (
+        SELECT b.code, b.name FROM admin_boundaries b, pt
+        WHERE b.vintage = $4 AND b.level = 2 AND ST_Contains(b.geom, pt.geom)
+        ORDER BY ST_Area(b.geom) LIMIT 1
+    )
+    SELECT TRIM(pc.iso_a2), TRIM(pc.iso_a3), COALESCE(localised_name(ci.geonameid, $3), pc.name),
+           b1.code, COALESCE(localised_name(ba1.geonameid, $3), ba1.name, b1.name),
+           b2.code, COALESCE(localised_name(ba2.geonameid, $3), ba2.name, b2.name)
+    FROM pt
+    LEFT JOIN pc ON true
+    LEFT JOIN b1 ON true
+    LEFT JOIN b2 ON true
+    LEFT JOIN country_info ci ON ci.iso_a2 = pc.iso_a2
+    LEFT JOIN admin1_codes ba1 ON ba1.code = b1.code
+    LEFT JOIN admin2_codes ba2 ON ba2.code = b2.code
+"#;
+
+/// The `$3` nearest places, like [`REVERSE_GEOCODE`] but with the distance in
+/// km before the population and the country name of vintage `$4`.
+pub(super) const NEAREST_PLACES: &str = r#"
+    SELECT g.geonameid, g.name, g.latitude, g.longitude,
+           g.feature_code, g.country_code, g.admin1_code, g.admin2_code,
+           a1.name, a2.name, c.name,
+           ST_Distance(g.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0,
+           COALESCE(g.population, 0)
+    FROM geonames g
+    LEFT JOIN admin1_codes a1 ON a1.code = g.country_code || '.' || g.admin1_code
+    LEFT JOIN admin2_codes a2 ON a2.code = g.country_code || '.' || g.admin1_code || '.' || g.admin2_code
+    LEFT JOIN countries c ON c.iso_a2 = g.country_code AND c.vintage = $4
+    ORDER BY g.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+    LIMIT $3
+"#;
+
+pub(super) const NEAREST_FACILITIES: &str = r#"
+    SELECT f.geonameid, f.name, f.feature_code, TRIM(f.country_code), f.latitude, f.longitude,
+           ST_Distance(f.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
+    FROM facilities f
+    WHERE f.feature_code = $3
+    ORDER BY f.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+    LIMIT $4
+"#;
+
+/// Facilities of the feature codes `$4` within `$3` km, the nearest `$5` of
+/// each code with the code's full count alongside.
+pub(super) const FACILITIES_WITHIN: &str = r#"
+    SELECT geonameid, name, feature_code, country_code, latitude, longitude, distance_km, total
+    FROM (
+        SELECT f.geonameid, f.name, f.feature_code, TRIM(f.country_code) AS country_code,
+               f.latitude, f.longitude, ST_Distance(f.geom::geography, p.g) / 1000.0 AS distance_km,
+               COUNT(*) OVER (PARTITION BY f.feature_code) AS total,
+               ROW_NUMBER() OVER (
+                   PARTITION BY f.feature_code ORDER BY ST_Distance(f.geom::geography, p.g), f.geonameid
+               ) AS rank
+        FROM facilities f,
+             (SELECT ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography AS g) p
+        WHERE f.feature_code = ANY($4)
+          AND ST_DWithin(f.geom::geography, p.g, $3 * 1000.0)
+    ) ranked
+    WHERE rank <= $5
+    ORDER BY feature_code, distance_km, geonameid
+"#;
+
+/// Airports of the served classes, nearest first. `$3` is the shortest
+/// acceptable runway in feet, or NULL for no runway requirement.
+pub(super) const NEAREST_AIRPORTS: &str = r#"
+    SELECT a.airport_id, a.ident, a.airport_type, a.name, a.icao_code, a.iata_code, a.municipality,
+           TRIM(a.country_code), a.latitude, a.longitude, a.elevation_ft, a.scheduled_service,
+           ST_Distance(a.geom::geography, ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography) / 1000.0
+    FROM airports a
+    WHERE a.airport_type IN ('large_airport', 'medium_airport', 'small_airport')
+      AND ($3::float8 IS NULL OR EXISTS (
+          SELECT 1 FROM airport_runways r WHERE r.airport_id = a.airport_id AND r.length_ft >= $3
+      ))
+    ORDER BY a.geom <-> ST_SetSRID(ST_MakePoint($1, $2), 4326)
+    LIMIT $4
+"#;
+
+pub(super) const AIRPORT_RUNWAYS: &str = r#"
+    SELECT airport_id, le_ident, he_ident, length_ft, width_ft, surface, lighted
+    FROM airport_runways
+    WHERE airport_id = ANY($1)
+    ORDER BY airport_id, length_ft DESC NULLS LAST, runway_id
+"#;
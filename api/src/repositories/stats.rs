@@ -1,6 +1,8 @@
 use deadpool_postgres::Object;
 use serde::Serialize;
 
+use crate::models::{CacheHitRates, IndexUsage, TableUsage};
+
 #[derive(Debug, Serialize)]
 pub(crate) struct TableStats {
     pub name: String,
@@ -24,4 +26,64 @@ impl StatsRepository {
             .await?;
         Ok(rows.iter().map(|r| TableStats { name: r.get(0), estimated_rows: r.get(1) }).collect())
     }
+
+    /// PostGIS library version; one round-trip, so it doubles as the health
+    /// check's latency probe.
+    pub async fn postgis_version(client: &Object) -> Result<String, tokio_postgres::Error> {
+        Ok(client.query_one("SELECT postgis_lib_version()", &[]).await?.get(0))
+    }
+
+    /// Live rows and on-disk size of every user table, largest first.
+    pub async fn table_usage(client: &Object) -> Result<Vec<TableUsage>, tokio_postgres::Error> {
+        let rows = client
+            .query(
+                r#"
+                SELECT relname::text, n_live_tup, pg_total_relation_size(relid)
+                FROM pg_stat_user_tables
+                ORDER BY 3 DESC, 1
+                "#,
+                &[],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|r| TableUsage { name: r.get(0), rows: r.get(1), total_bytes: r.get(2) })
+            .collect())
+    }
+
+    /// Size and scan count of every user index, largest first.
+    pub async fn index_usage(client: &Object) -> Result<Vec<IndexUsage>, tokio_postgres::Error> {
+        let rows = client
+            .query(
+                r#"
+                SELECT indexrelname::text, relname::text, pg_relation_size(indexrelid), idx_scan
+                FROM pg_stat_user_indexes
+                ORDER BY 3 DESC, 1
+                "#,
+                &[],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|r| IndexUsage { name: r.get(0), table: r.get(1), bytes: r.get(2), scans: r.get(3) })
+            .collect())
+    }
+
+    /// Buffer cache hit ratios of user tables and indexes.
+    pub async fn cache_hit_rates(client: &Object) -> Result<CacheHitRates, tokio_postgres::Error> {
+        let row = client
+            .query_one(
+                r#"
+                SELECT
+                    (SELECT sum(heap_blks_hit)::float8 / NULLIF(sum(heap_blks_hit) + sum(heap_blks_read), 0)
+                     FROM pg_statio_user_tables),
+                    (SELECT sum(idx_blks_hit)::float8 / NULLIF(sum(idx_blks_hit) + sum(idx_blks_read), 0)
+                     FROM pg_statio_user_indexes)
+                "#,
+                &[],
+            )
+            .await?;
+        let ratio = |v: Option<f64>| v.map(|r| (r * 1000.0).round() / 1000.0);
+        Ok(CacheHitRates { tables: ratio(row.get(0)), indexes: ratio(row.get(1)) })
+    }
 }
@@ -0,0 +1,61 @@
+use super::PgDb;
+use crate::errors::AppError;
+use crate::grid;
+
+/// Country and cell population of point chunks, for `/tag/stream` and
+/// `/route/sample`. Each call checks out its own connection, so a long-lived
+/// stream holds one only while a chunk is being tagged.
+pub(crate) struct TaggingRepository {
+    db: PgDb,
+}
+
+impl TaggingRepository {
+    pub fn new(db: PgDb) -> Self {
+        Self { db }
+    }
+
+    /// Tags a chunk of points with their containing country and cell population
+    /// in a single round-trip. Results are returned in input order, one per
+    /// point.
+    pub async fn tag_points(
+        &self,
+        points: &[(f64, f64)],
+        vintage: i32,
+    ) -> Result<Vec<(Option<String>, f32)>, AppError> {
+        let lats: Vec<f64> = points.iter().map(|p| p.0).collect();
+        let lons: Vec<f64> = points.iter().map(|p| p.1).collect();
+        let cells: Vec<i32> = points
+            .iter()
+            .map(|&(lat, lon)| grid::cell_id(lat, lon).unwrap_or(-1))
+            .collect();
+
+        let client = &self.db.conn().await?;
+        let stmt = client
+            .prepare_cached(
+                r#"
+                SELECT c.iso_a3, COALESCE(p.pop, 0)::real
+                FROM unnest($1::float8[], $2::float8[], $3::int[])
+                     WITH ORDINALITY AS pt(lat, lon, cell_id, ord)
+                LEFT JOIN population p ON p.cell_id = pt.cell_id
+                LEFT JOIN LATERAL (
+                    SELECT iso_a3 FROM countries
+                    WHERE vintage = $4
+                      AND ST_Contains(geom, ST_SetSRID(ST_MakePoint(pt.lon, pt.lat), 4326))
+                    LIMIT 1
+                ) c ON true
+                ORDER BY pt.ord
+                "#,
+            )
+            .await?;
+
+        let rows = client.query(&stmt, &[&lats, &lons, &cells, &vintage]).await?;
+        if rows.len() != points.len() {
+            return Err(AppError::Database(format!(
+                "tagging returned {} rows for {} points",
+                rows.len(),
+                points.len()
+            )));
+        }
+        Ok(rows.iter().map(|r| (r.get(0), r.get(1))).collect())
+    }
+}
@@ -17,4 +17,26 @@ impl<T: Serialize> ApiResponse<T> {
             payload: Some(payload),
         })
     }
+
+    /// `201 Created` with a `Location` header pointing at the new resource.
+    pub fn created(payload: T, location: &str) -> HttpResponse {
+        HttpResponse::Created()
+            .insert_header((actix_web::http::header::LOCATION, location))
+            .json(Self {
+                success: true,
+                message: "created",
+                payload: Some(payload),
+            })
+    }
+
+    /// `202 Accepted` with a `Location` header pointing at where the result will appear.
+    pub fn accepted(payload: T, location: &str) -> HttpResponse {
+        HttpResponse::Accepted()
+            .insert_header((actix_web::http::header::LOCATION, location))
+            .json(Self {
+                success: true,
+                message: "accepted",
+                payload: Some(payload),
+            })
+    }
 }
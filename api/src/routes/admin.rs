@@ -0,0 +1,117 @@
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::models::{AdminStatsPayload, CacheFlushPayload, CacheFlushQuery, CacheFlushed};
+use crate::repositories::StatsRepository;
+use crate::response::ApiResponse;
+use crate::routes::health::{database_status, pool_status};
+use crate::state::AppState;
+
+pub(crate) const ADMIN_KEY_HEADER: &str = "X-Admin-Key";
+
+/// Admin routes answer only when `ADMIN_API_KEY` is set, and only to
+/// requests presenting it; without it they do not exist.
+fn authorize(req: &HttpRequest, state: &AppState) -> Result<(), AppError> {
+    let expected = &state.config.admin_api_key;
+    if expected.is_empty() {
+        return Err(AppError::NotFound("Admin routes are disabled".into()));
+    }
+    let presented = req.headers().get(ADMIN_KEY_HEADER).and_then(|v| v.to_str().ok());
+    if presented != Some(expected.as_str()) {
+        return Err(AppError::Unauthorized("invalid or missing admin key".into()));
+    }
+    Ok(())
+}
+
+/// Operational statistics of this instance and the primary database.
+#[utoipa::path(
+    get,
+    path = "/admin/stats",
+    tag = "System",
+    summary = "Admin statistics",
+    description = "Reports what operators would otherwise ask the database directly: live rows and size \
+        per table, size and scan count per index, buffer cache hit ratios, connection pool usage, \
+        database round-trip latency and PostGIS version, and the requests this instance served per \
+        route since it started. Table, index and cache figures come from the primary's statistics \
+        views; request counters are per instance.\n\n\
+        Requires the `ADMIN_API_KEY` in `X-Admin-Key` (in addition to `X-API-Key` when `API_KEY` is \
+        set). The route returns 404 when `ADMIN_API_KEY` is not configured.",
+    params(
+        ("X-Admin-Key" = String, Header, description = "The server's `ADMIN_API_KEY`")
+    ),
+    responses(
+        (status = 200, description = "Statistics", body = AdminStatsPayload),
+        (status = 401, description = "Missing or wrong admin key"),
+        (status = 404, description = "Admin routes are disabled")
+    )
+)]
+pub(crate) async fn admin_stats(req: HttpRequest, state: web::Data<AppState>) -> ActixResult<HttpResponse> {
+    authorize(&req, &state)?;
+    let pool = pool_status(&state.pool);
+    let read_pool = (!state.config.database_read_url.is_empty()).then(|| pool_status(&state.read_pool));
+    let database = database_status(&state.pool).await;
+
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let (tables, indexes, cache) = tokio::try_join!(
+        StatsRepository::table_usage(&client),
+        StatsRepository::index_usage(&client),
+        StatsRepository::cache_hit_rates(&client),
+    )
+    .map_err(AppError::from)?;
+
+    Ok(ApiResponse::ok(AdminStatsPayload {
+        started_at: state.counters.started_at(),
+        uptime_secs: state.counters.uptime_secs(),
+        pool,
+        read_pool,
+        database,
+        cache,
+        tables,
+        indexes,
+        endpoints: state.counters.snapshot(),
+    }))
+}
+
+/// Clear the in-process caches, or their entries under one path prefix.
+#[utoipa::path(
+    post,
+    path = "/admin/cache/flush",
+    tag = "System",
+    summary = "Flush response caches",
+    description = "Drops cached entries so the next requests re-read the database, e.g. after a dataset \
+        reload. With `prefix` (relative to `/api/v1`, e.g. `/country`) only entries for paths starting \
+        with it are dropped. Today the only cache is the ETag cache behind `If-None-Match`.\n\n\
+        Each instance caches for itself: behind a load balancer, flush every instance (or wait \
+        `ETAG_TTL_SECS`). Requires `X-Admin-Key`, like `/admin/stats`.",
+    params(
+        ("X-Admin-Key" = String, Header, description = "The server's `ADMIN_API_KEY`"),
+        ("prefix" = Option<String>, Query, description = "Only flush paths starting with this, relative to `/api/v1`", example = "/country")
+    ),
+    responses(
+        (status = 200, description = "Entries dropped per cache", body = CacheFlushPayload),
+        (status = 400, description = "`prefix` does not start with `/`"),
+        (status = 401, description = "Missing or wrong admin key"),
+        (status = 404, description = "Admin routes are disabled")
+    )
+)]
+pub(crate) async fn flush_caches(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<CacheFlushQuery>,
+) -> ActixResult<HttpResponse> {
+    authorize(&req, &state)?;
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let prefix = query.into_inner().prefix;
+
+    let caches: Vec<CacheFlushed> = state
+        .caches
+        .iter()
+        .map(|cache| CacheFlushed { name: cache.name().into(), flushed: cache.invalidate(prefix.as_deref()) })
+        .collect();
+    let flushed = caches.iter().map(|c| c.flushed).sum();
+    log::info!("Flushed {flushed} cache entries under {}", prefix.as_deref().unwrap_or("/"));
+    Ok(ApiResponse::ok(CacheFlushPayload { prefix, flushed, caches }))
+}
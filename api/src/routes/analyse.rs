@@ -1,14 +1,41 @@
-use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::web::Bytes;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, Result as ActixResult};
+use chrono::{DateTime, Offset, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::mpsc;
 use validator::Validate;
 
 use crate::errors::AppError;
-use crate::models::{AnalysePayload, CoordinateInfo, PointQuery, PopulationSummary};
-use crate::repositories::{CountryRepository, GeocodingRepository, PopulationRepository};
+use crate::hazard::HazardTemplates;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{
+    AnalysePayload, BoundaryQuery, CoordinateInfo, EpicentreEvent, EpicentreTerrain, HazardQuery,
+    IncludedMeta, LocalTime, PlaceFilterQuery, PointQuery, PopulationSummary,
+    ProbeEvent, ReportFormat, ReportQuery, SearchRadiusQuery, StreamError, TerrainClass, YearQuery,
+};
+use crate::report::{MapThumbnail, Report};
+use crate::repositories::{CountryCode, PlaceOptions, PopulationRepository, Repositories};
 use crate::response::ApiResponse;
+use crate::routes::exposure;
+use crate::state::AppState;
 
-const STEP_KM: f64 = 5.0;
-const MAX_RADIUS_KM: f64 = 1000.0;
+/// Reach of `nearby_countries`, measured to the nearest border.
+const NEARBY_COUNTRIES_KM: f64 = 200.0;
+/// Terrain class thresholds: low-lying cells this close to the coast are
+/// coastal, cells this high are highland.
+const COASTAL_KM: f64 = 10.0;
+const HIGHLAND_M: f64 = 500.0;
+const DEFAULT_STEP_KM: f64 = 5.0;
+const DEFAULT_MAX_RADIUS_KM: f64 = 1000.0;
+/// Search tiers as multiples of the step: with the default 5 km step, 5, 10,
+/// 25 … 700 km, then the cap.
+const TIER_STEPS: [f64; 8] = [1.0, 2.0, 5.0, 10.0, 20.0, 40.0, 80.0, 140.0];
+/// Nearest places a report lists unless `nearest_places` says otherwise.
+const REPORT_NEAREST_PLACES: i64 = 5;
 
 #[inline]
 fn round1(v: f64) -> f64 {
@@ -29,110 +56,558 @@ fn round2(v: f64) -> f64 {
     description = "Comprehensive disaster impact analysis for a coordinate. Takes only lat/lon — \
         no radius needed.\n\n\
         The endpoint automatically:\n\
-        1. Identifies the country (or nearest country if in ocean)\n\
+        1. Identifies the country (or nearest country if in ocean), and lists the other \
+           countries within 200 km as `nearby_countries`\n\
         2. Finds the nearest named place (city/town/village) with distance and direction\n\
-        3. Checks population at the epicentre grid cell\n\
-        4. If no population at the epicentre, expands the search radius in 5 km increments \
-           (up to 1000 km) until population is found\n\n\
+        3. Reports the epicentre's timezone (that of the nearest place), UTC offset and current \
+           local time as `local_time`\n\
+        4. Checks population, elevation, terrain class (`coastal`, `lowland` or `highland`) \
+           and GHS-SMOD `settlement_class` at the epicentre grid cell\n\
+        5. If no population at the epicentre, expands the search radius in tiers of 1, 2, 5, 10, \
+           20, 40, 80 and 140 times `step_km` (default 5 km) up to `max_radius_km` (default \
+           1000 km) until population is found\n\n\
         The `population.search_radius_km` field indicates how remote the epicentre is — \
         a value of 5 means population was found within 5 km; a value of 500 means \
-        the nearest populated area is ~500 km away.\n\n\
+        the nearest populated area is ~500 km away. A finer `step_km` (down to 1 km) sharpens \
+        that estimate for urban events; a coarser one (up to 100 km) or a lower `max_radius_km` \
+        answers mid-ocean events in fewer probes.\n\n\
+        Optional sections via `include=`: `uncertainty` adds a WorldPop 95% interval to the \
+        population summary; `places` lists the 20 nearest named places within the search radius \
+        (with `min_place_population`, only places at least that large); `settlement` splits the \
+        summary's population into urban centre, urban cluster and rural cells (404 until \
+        `make ingest-settlement` has run); `sources` and `timings` add dataset attribution and a server-side timing breakdown.\n\n\
+        `nearest_places=N` (up to 20) adds `nearest_places`: the N nearest named places, each \
+        with distance, direction and population like `nearest_place`, regardless of the search \
+        radius.\n\n\
+        `hazard` and `magnitude` (`hazard=earthquake&magnitude=6.8`) replace the search with a \
+        hazard template: the radius follows from the magnitude as `10^(a + b·magnitude)` km, \
+        clamped to the template's bounds, and a template with a `decay` adds a distance-weighted \
+        population. `population` then covers that radius, and `hazard` reports the template, \
+        radius and weighted population. `earthquake` (moment magnitude 3-9.5) and `volcano` \
+        (VEI 0-8) are built in; servers can add templates via `HAZARD_TEMPLATES`.\n\n\
+        `year` evaluates an earlier population grid year where one has been loaded, for \
+        retrospective analyses (default: the latest, echoed as `year`); `include=uncertainty` \
+        is only available for the latest year.\n\n\
         Ideal for disaster events where the epicentre may be in ocean, desert, or uninhabited terrain.",
     params(
         ("lat" = f64, Query, description = "Epicentre latitude in decimal degrees", example = 20.4657, minimum = -90, maximum = 90),
-        ("lon" = f64, Query, description = "Epicentre longitude in decimal degrees", example = 93.9572, minimum = -180, maximum = 180)
+        ("lon" = f64, Query, description = "Epicentre longitude in decimal degrees", example = 93.9572, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023),
+        ("min_place_population" = Option<i64>, Query, description = "With `include=places`: only list places with at least this GeoNames population (default: 0)", example = 5000),
+        ("nearest_places" = Option<i64>, Query, description = "Add `nearest_places`, the N nearest named places with distance and direction (max 20)", example = 5),
+        ("step_km" = Option<f64>, Query, description = "Smallest search radius in km; later tiers are multiples of it (1-100, default: 5)", example = 1.0),
+        ("max_radius_km" = Option<f64>, Query, description = "Largest search radius in km, at least `step_km` (max 1000, default: 1000)", example = 200.0),
+        ("hazard" = Option<String>, Query, description = "Hazard template deriving the radius from `magnitude` instead of searching (`earthquake`, `volcano`)", example = "earthquake"),
+        ("magnitude" = Option<f64>, Query, description = "Event magnitude on the hazard template's scale; required with `hazard`", example = 6.8),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020)
     ),
     responses(
         (status = 200, description = "Disaster impact analysis results", body = AnalysePayload),
-        (status = 400, description = "Invalid or out-of-range coordinates")
+        (status = 400, description = "Invalid or out-of-range coordinates, year, search radius or magnitude, unknown `hazard`, `include=uncertainty` with an earlier `year`, or unsupported `include` section"),
+        (status = 404, description = "Unknown boundary vintage, no population grid loaded for `year`, or `include=settlement` without the settlement layer")
     )
 )]
 pub(crate) async fn analyse(
-    pool: web::Data<Pool>,
-    query: web::Query<PointQuery>,
+    repos: web::Data<Repositories>,
+    state: web::Data<AppState>,
+    request: AnalyseRequest,
+    includes: Includes,
 ) -> ActixResult<HttpResponse> {
-    query.validate().map_err(|e| {
-        AppError::Validation(format!("Validation failed: {e}"))
-    })?;
+    let hazards = &state.limits().hazards;
+    check(&request, includes, hazards)?;
+    let payload = run(&repos, &request, hazards, includes, &Progress::default()).await?;
+    Ok(ApiResponse::ok(payload))
+}
 
-    let (lat, lon) = (query.lat, query.lon);
+/// Disaster impact analysis streamed as server-sent events.
+#[utoipa::path(
+    get,
+    path = "/analyse/stream",
+    tag = "Risk Assessment",
+    summary = "Disaster impact analysis (server-sent events)",
+    description = "Runs the same analysis as /analyse but streams its progress as `text/event-stream`, \
+        so a UI can show feedback while a remote-ocean epicentre walks through many radius probes.\n\n\
+        Events, each with a JSON `data` line:\n\
+        - `country` — country containing (or nearest to) the epicentre\n\
+        - `nearest_place` — nearest named place\n\
+        - `epicentre` — `{population, is_land}` at the epicentre cell\n\
+        - `probe` — `{radius_km, populated}` for each radius tier checked, in increasing order\n\
+        - `summary` — the complete /analyse payload; the stream then ends\n\
+        - `error` — `{message}` if the analysis fails; the stream then ends\n\n\
+        `country` and `nearest_place` are resolved concurrently and may arrive in either order. \
+        Invalid parameters are rejected with a plain 400 before the stream starts.",
+    params(
+        ("lat" = f64, Query, description = "Epicentre latitude in decimal degrees", example = 20.4657, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Epicentre longitude in decimal degrees", example = 93.9572, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023),
+        ("min_place_population" = Option<i64>, Query, description = "With `include=places`: only list places with at least this GeoNames population (default: 0)", example = 5000),
+        ("nearest_places" = Option<i64>, Query, description = "Add `nearest_places`, the N nearest named places with distance and direction (max 20)", example = 5),
+        ("step_km" = Option<f64>, Query, description = "Smallest search radius in km; later tiers are multiples of it (1-100, default: 5)", example = 1.0),
+        ("max_radius_km" = Option<f64>, Query, description = "Largest search radius in km, at least `step_km` (max 1000, default: 1000)", example = 200.0),
+        ("hazard" = Option<String>, Query, description = "Hazard template deriving the radius from `magnitude` instead of searching (`earthquake`, `volcano`)", example = "earthquake"),
+        ("magnitude" = Option<f64>, Query, description = "Event magnitude on the hazard template's scale; required with `hazard`", example = 6.8),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020)
+    ),
+    responses(
+        (status = 200, description = "Event stream ending with `summary` (an AnalysePayload) or `error`", body = ProbeEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Invalid or out-of-range coordinates, year, search radius or magnitude, unknown `hazard`, or unsupported `include` section")
+    )
+)]
+pub(crate) async fn analyse_stream(
+    repos: web::Data<Repositories>,
+    state: web::Data<AppState>,
+    request: AnalyseRequest,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    let hazards = state.limits().hazards.clone();
+    check(&request, includes, &hazards)?;
 
-    let (country_res, place_res, epicentre_res, land_res) = tokio::join!(
-        async {
-            let c = pool.get().await.map_err(AppError::from)?;
-            configure_conn(&c).await;
-            CountryRepository::get_by_coordinate(&c, lat, lon).await
-        },
+    let (tx, mut rx) = mpsc::unbounded_channel::<Bytes>();
+    let repos = repos.get_ref().clone();
+    actix_web::rt::spawn(async move {
+        let progress = Progress(Some(tx));
+        match run(&repos, &request, &hazards, includes, &progress).await {
+            Ok(payload) => progress.emit("summary", &payload),
+            Err(e) => {
+                if let AppError::Database(msg) | AppError::Transient(msg) = &e {
+                    log::error!("analyse stream: {msg}");
+                }
+                progress.emit("error", &StreamError { message: e.public_message().into() });
+            }
+        }
+    });
+
+    let body = futures_util::stream::poll_fn(move |cx| {
+        rx.poll_recv(cx).map(|chunk| chunk.map(Ok::<_, actix_web::Error>))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body))
+}
+
+/// Disaster impact analysis as a formatted situational report.
+#[utoipa::path(
+    get,
+    path = "/analyse/report",
+    tag = "Risk Assessment",
+    summary = "Situational report (HTML or PDF)",
+    description = "Runs the same analysis as /analyse and renders it server-side as a situational \
+        report for briefings: a map of the boundaries around the epicentre with the search radius, \
+        the exposure figures, the nearest places (5 unless `nearest_places` is given), facts about \
+        the country and the countries within 200 km.\n\n\
+        `format=html` (default) returns a self-contained page with an inline SVG map; `format=pdf` \
+        an A4 PDF. The PDF uses the standard Helvetica fonts, so characters outside Latin-1 print \
+        as `?`. `include=` is not supported.",
+    params(
+        ("format" = Option<ReportFormat>, Query, description = "Document format: `html` (default) or `pdf`"),
+        ("lat" = f64, Query, description = "Epicentre latitude in decimal degrees", example = 20.4657, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Epicentre longitude in decimal degrees", example = 93.9572, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023),
+        ("nearest_places" = Option<i64>, Query, description = "Nearest named places to list (default: 5, max 20)", example = 5),
+        ("step_km" = Option<f64>, Query, description = "Smallest search radius in km; later tiers are multiples of it (1-100, default: 5)", example = 1.0),
+        ("max_radius_km" = Option<f64>, Query, description = "Largest search radius in km, at least `step_km` (max 1000, default: 1000)", example = 200.0),
+        ("hazard" = Option<String>, Query, description = "Hazard template deriving the radius from `magnitude` instead of searching (`earthquake`, `volcano`)", example = "earthquake"),
+        ("magnitude" = Option<f64>, Query, description = "Event magnitude on the hazard template's scale; required with `hazard`", example = 6.8),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020)
+    ),
+    responses(
+        (status = 200, description = "Situational report", content_type = "text/html"),
+        (status = 200, description = "Situational report", content_type = "application/pdf"),
+        (status = 400, description = "Invalid or out-of-range coordinates, year, search radius or magnitude, unknown `hazard` or `format`, or any `include` section"),
+        (status = 404, description = "Unknown boundary vintage or no population grid loaded for `year`")
+    )
+)]
+pub(crate) async fn analyse_report(
+    repos: web::Data<Repositories>,
+    state: web::Data<AppState>,
+    mut request: AnalyseRequest,
+    includes: Includes,
+    query: web::Query<ReportQuery>,
+) -> ActixResult<HttpResponse> {
+    includes.ensure_supported(&[])?;
+    let hazards = &state.limits().hazards;
+    check(&request, includes, hazards)?;
+    request.places.nearest_places.get_or_insert(REPORT_NEAREST_PLACES);
+    let analysis = run(&repos, &request, hazards, includes, &Progress::default()).await?;
+
+    let CoordinateInfo { lat, lon } = analysis.coordinate;
+    let radius_km = analysis.population.search_radius_km;
+    let vintage = analysis.country.boundary_vintage;
+    let bbox = MapThumbnail::bbox(lat, lon, radius_km);
+    let (outlines, country) = tokio::join!(
+        repos.countries.get_outlines(bbox, MapThumbnail::tolerance_deg(&bbox), vintage),
         async {
-            let c = pool.get().await.map_err(AppError::from)?;
-            configure_conn(&c).await;
-            GeocodingRepository::find_nearest_place(&c, lat, lon).await
+            match analysis.country.iso_a3.clone() {
+                Some(iso3) => repos.countries.get_by_code(&CountryCode::Alpha3(iso3), vintage).await.map(Some),
+                None => Ok(None),
+            }
         },
+    );
+    let country = match country {
+        Ok(country) => country,
+        Err(AppError::NotFound(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let report = Report {
+        map: MapThumbnail::new(lat, lon, radius_km, outlines?),
+        analysis,
+        country,
+        generated_at: Utc::now(),
+    };
+
+    Ok(match query.format {
+        ReportFormat::Html => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(report.html()),
+        ReportFormat::Pdf => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!("inline; filename=\"geopop-report-{lat:.4}_{lon:.4}.pdf\""),
+            ))
+            .body(report.pdf()),
+    })
+}
+
+/// Sink for intermediate analysis steps; a no-op unless streaming.
+#[derive(Default)]
+pub(crate) struct Progress(Option<mpsc::UnboundedSender<Bytes>>);
+
+impl Progress {
+    /// Sends one server-sent event. Errors (client gone) are ignored; the
+    /// analysis simply finishes without an audience.
+    fn emit(&self, event: &str, data: &impl Serialize) {
+        if let Some(tx) = &self.0 {
+            if let Ok(json) = serde_json::to_string(data) {
+                let _ = tx.send(Bytes::from(format!("event: {event}\ndata: {json}\n\n")));
+            }
+        }
+    }
+}
+
+/// The query parameters of one analysis, from the query string or a job's
+/// params.
+pub(crate) struct AnalyseRequest {
+    pub point: PointQuery,
+    pub boundaries: BoundaryQuery,
+    pub places: PlaceFilterQuery,
+    pub year: YearQuery,
+    pub search: SearchRadiusQuery,
+    pub hazard: HazardQuery,
+}
+
+impl FromRequest for AnalyseRequest {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        fn group<T: DeserializeOwned>(query: &str) -> Result<T, AppError> {
+            web::Query::<T>::from_query(query)
+                .map(web::Query::into_inner)
+                .map_err(|e| AppError::Validation(e.to_string()))
+        }
+        let query = req.query_string();
+        ready((|| {
+            Ok(Self {
+                point: group(query)?,
+                boundaries: group(query)?,
+                places: group(query)?,
+                year: group(query)?,
+                search: group(query)?,
+                hazard: group(query)?,
+            })
+        })())
+    }
+}
+
+/// Validates an analyse request; shared with job submission.
+pub(crate) fn check(
+    request: &AnalyseRequest,
+    includes: Includes,
+    hazards: &HazardTemplates,
+) -> Result<(), AppError> {
+    let AnalyseRequest { point, boundaries, places, year, search, hazard } = request;
+    point.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    places.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    year.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    search.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if search_step(search) > search_cap(search) {
+        return Err(AppError::Validation(
+            "max_radius_km must be at least step_km".into(),
+        ));
+    }
+    hazard.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if hazards.resolve(hazard)?.is_some()
+        && (search.step_km.is_some() || search.max_radius_km.is_some())
+    {
+        return Err(AppError::Validation(
+            "step_km and max_radius_km do not apply with hazard".into(),
+        ));
+    }
+    includes.ensure_supported(include::ANALYSE)?;
+    if places.min_place_population.is_some() && !includes.has(Section::Places) {
+        return Err(AppError::Validation(
+            "min_place_population requires include=places".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the analysis for a validated request, reporting intermediate steps to
+/// `progress`. Used by the handlers and by background jobs, which build their
+/// repositories with a longer statement timeout.
+pub(crate) async fn run(
+    repos: &Repositories,
+    request: &AnalyseRequest,
+    hazards: &HazardTemplates,
+    includes: Includes,
+    progress: &Progress,
+) -> Result<AnalysePayload, AppError> {
+    let AnalyseRequest { point, boundaries, places, year, search, hazard } = request;
+    let (lat, lon) = (point.lat, point.lon);
+    let mut watch = includes.stopwatch();
+    let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+    watch.mark("boundary_vintage");
+    let (year, population) = repos.population.for_year(year.year).await?;
+    watch.mark("population_year");
+
+    let (country_res, place_res, epicentre_res, land_res, nearby_res, timezone_res, terrain_res, settlement_res) = tokio::join!(
         async {
-            let c = pool.get().await.map_err(AppError::from)?;
-            configure_conn(&c).await;
-            PopulationRepository::get_cell_population(&c, lat, lon).await
+            let country = repos.countries.get_by_coordinate(lat, lon, vintage).await?;
+            progress.emit("country", &country);
+            Ok::<_, AppError>(country)
         },
         async {
-            let c = pool.get().await.map_err(AppError::from)?;
-            CountryRepository::is_land(&c, lat, lon).await
+            let limit = places.nearest_places.unwrap_or(1);
+            let nearest = repos.geocoding.find_nearest_places(lat, lon, limit, vintage).await?;
+            let place = nearest
+                .first()
+                .cloned()
+                .ok_or_else(|| AppError::NotFound("No nearby place found".into()))?;
+            progress.emit("nearest_place", &place);
+            Ok::<_, AppError>((place, nearest))
         },
+        population.get_cell_population(lat, lon),
+        repos.countries.is_land(lat, lon, vintage),
+        repos.countries.get_nearby_countries(lat, lon, NEARBY_COUNTRIES_KM, vintage),
+        repos.geocoding.find_timezone(lat, lon),
+        repos.population.get_cell_terrain(lat, lon),
+        repos.population.get_cell_settlement(lat, lon),
     );
 
     let country = country_res?;
-    let nearest_place = place_res?;
+    let mut nearby_countries = nearby_res?;
+    nearby_countries.retain(|c| c.country.iso_a3 != country.iso_a3);
+    let local_time = timezone_res?.and_then(|tz| local_time(tz, Utc::now()));
+    let terrain = terrain_res?.map(|(elevation_m, coast_km)| EpicentreTerrain {
+        elevation_m: round1(elevation_m),
+        coast_km: coast_km.map(round1),
+        class: terrain_class(elevation_m, coast_km),
+    });
+    let settlement_class = settlement_res?;
+    let (nearest_place, nearest) = place_res?;
+    let nearest_places = places.nearest_places.is_some().then_some(nearest);
     let is_land = land_res.unwrap_or(false);
     let epicentre_pop = epicentre_res.unwrap_or(0.0);
+    watch.mark("country_place_epicentre");
+    progress.emit("epicentre", &EpicentreEvent { population: epicentre_pop, is_land });
+
+    let population = population.as_ref();
+    let mut scenario = hazards.resolve(hazard)?;
+    let step = search_step(search);
+    let (search_radius, total_pop) = if let Some(radius_km) = scenario.as_ref().map(|s| s.radius_km) {
+        let pop = population.get_exposure_population(lat, lon, radius_km).await?;
+        progress.emit("probe", &ProbeEvent { radius_km, populated: pop > 0.0 });
+        (radius_km, pop)
+    } else if epicentre_pop > 0.0 {
+        progress.emit("probe", &ProbeEvent { radius_km: step, populated: true });
+        let pop = population.get_exposure_population(lat, lon, step).await?;
+        (step, pop)
+    } else {
+        let tiers = search_tiers(step, search_cap(search));
+        find_population_radius(population, lat, lon, &tiers, progress).await?
+    };
+    watch.mark("population");
 
-    // Population radius search on its own connection
-    let client = pool.get().await.map_err(AppError::from)?;
-    configure_conn(&client).await;
+    if let Some(scenario) = &mut scenario {
+        if let Some(decay) = scenario.template.decay {
+            let weighted = population
+                .get_weighted_exposure_population(lat, lon, scenario.radius_km, decay)
+                .await?;
+            scenario.weighted_population = Some(round1(weighted));
+            watch.mark("weighted_population");
+        }
+    }
 
-    let (search_radius, total_pop) = if epicentre_pop > 0.0 {
-        let pop = PopulationRepository::get_exposure_population(&client, lat, lon, STEP_KM).await?;
-        (STEP_KM, pop)
+    let uncertainty = if includes.has(Section::Uncertainty) {
+        let interval = population.get_exposure_uncertainty(lat, lon, search_radius).await?;
+        watch.mark("uncertainty");
+        Some(interval)
     } else {
-        find_population_radius(&client, lat, lon).await?
+        None
     };
+    let settlement = if includes.has(Section::Settlement) {
+        let split = population.get_settlement_exposure(lat, lon, search_radius).await?;
+        watch.mark("settlement");
+        Some(exposure::round_settlement(split))
+    } else {
+        None
+    };
+    let places = if includes.has(Section::Places) {
+        let options = PlaceOptions::default().min_population(places.min_place_population);
+        let places = repos.geocoding.get_exposed_places(
+            lat, lon, search_radius, options, include::MAX_INCLUDED_PLACES, 0, vintage,
+        ).await?;
+        watch.mark("places");
+        Some(places)
+    } else {
+        None
+    };
+    let sources = includes.has(Section::Sources).then(|| {
+        let mut list = vec![sources::worldpop_grid(None, year), sources::geonames(), sources::natural_earth(vintage)];
+        if includes.has(Section::Uncertainty) {
+            list.push(sources::worldpop_uncertainty());
+        }
+        if includes.has(Section::Settlement) {
+            list.push(sources::ghsl_smod());
+        }
+        list
+    });
 
     let area = std::f64::consts::PI * search_radius * search_radius;
     let density = if area > 0.0 { total_pop / area } else { 0.0 };
 
-    Ok(ApiResponse::ok(AnalysePayload {
+    Ok(AnalysePayload {
         coordinate: CoordinateInfo { lat, lon },
         is_land,
         country,
         nearest_place,
+        nearest_places,
+        nearby_countries,
+        local_time,
+        terrain,
+        settlement_class,
+        hazard: scenario,
         population: PopulationSummary {
             search_radius_km: search_radius,
             total_population: round1(total_pop),
             area_km2: round2(area),
             density_per_km2: round1(density),
             epicentre_population: epicentre_pop,
+            uncertainty,
+            settlement,
         },
-    }))
+        year,
+        places,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    })
+}
+
+/// The time `now` in `timezone`; `None` for a name the bundled tz database
+/// does not know.
+fn local_time(timezone: String, now: DateTime<Utc>) -> Option<LocalTime> {
+    let tz: chrono_tz::Tz = timezone.parse().ok()?;
+    let local = now.with_timezone(&tz);
+    Some(LocalTime {
+        timezone,
+        utc_offset: local.offset().fix().to_string(),
+        local_time: local.to_rfc3339_opts(SecondsFormat::Secs, false),
+    })
+}
+
+/// `coast_km` is only known for cells the coastal terrain layer stores: those
+/// at most 100 m above sea level.
+fn terrain_class(elevation_m: f64, coast_km: Option<f64>) -> TerrainClass {
+    match coast_km {
+        Some(km) if km <= COASTAL_KM => TerrainClass::Coastal,
+        _ if elevation_m >= HIGHLAND_M => TerrainClass::Highland,
+        _ => TerrainClass::Lowland,
+    }
+}
+
+fn search_step(search: &SearchRadiusQuery) -> f64 {
+    search.step_km.unwrap_or(DEFAULT_STEP_KM)
+}
+
+fn search_cap(search: &SearchRadiusQuery) -> f64 {
+    search.max_radius_km.unwrap_or(DEFAULT_MAX_RADIUS_KM)
 }
 
-async fn configure_conn(client: &deadpool_postgres::Object) {
-    client.execute("SET jit = off", &[]).await.ok();
-    client.execute("SET statement_timeout = '30s'", &[]).await.ok();
+/// Radii to probe: the [`TIER_STEPS`] multiples of `step` below `cap`, then
+/// `cap` itself.
+fn search_tiers(step: f64, cap: f64) -> Vec<f64> {
+    let mut tiers: Vec<f64> = TIER_STEPS.iter().map(|m| m * step).filter(|&km| km < cap).collect();
+    tiers.push(cap);
+    tiers
 }
 
 /// Tiered existence check: probe expanding tiers until population is found,
 /// then compute exposure at that tier. Each empty-ocean tier costs a single
 /// fast EXISTS query. Worst case (deep ocean): 9 existence checks + 1 sum.
 async fn find_population_radius(
-    client: &deadpool_postgres::Object,
+    population: &dyn PopulationRepository,
     lat: f64,
     lon: f64,
+    tiers: &[f64],
+    progress: &Progress,
 ) -> Result<(f64, f64), AppError> {
-    const TIERS: [f64; 9] = [5.0, 10.0, 25.0, 50.0, 100.0, 200.0, 400.0, 700.0, MAX_RADIUS_KM];
-    for &tier_km in &TIERS {
-        if PopulationRepository::has_population_within(client, lat, lon, tier_km).await? {
-            let pop =
-                PopulationRepository::get_exposure_population(client, lat, lon, tier_km).await?;
+    for &tier_km in tiers {
+        let populated = population.has_population_within(lat, lon, tier_km).await?;
+        progress.emit("probe", &ProbeEvent { radius_km: tier_km, populated });
+        if populated {
+            let pop = population.get_exposure_population(lat, lon, tier_km).await?;
             return Ok((tier_km, pop));
         }
     }
-    Ok((MAX_RADIUS_KM, 0.0))
+    Ok((tiers.last().copied().unwrap_or_default(), 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_scale_with_step_and_stop_at_cap() {
+        assert_eq!(
+            search_tiers(DEFAULT_STEP_KM, DEFAULT_MAX_RADIUS_KM),
+            [5.0, 10.0, 25.0, 50.0, 100.0, 200.0, 400.0, 700.0, 1000.0],
+        );
+        assert_eq!(search_tiers(1.0, 30.0), [1.0, 2.0, 5.0, 10.0, 20.0, 30.0]);
+        assert_eq!(search_tiers(25.0, 1000.0), [25.0, 50.0, 125.0, 250.0, 500.0, 1000.0]);
+        assert_eq!(search_tiers(10.0, 10.0), [10.0]);
+    }
+
+    #[test]
+    fn classifies_terrain() {
+        assert_eq!(terrain_class(4.0, Some(1.0)), TerrainClass::Coastal);
+        assert_eq!(terrain_class(40.0, Some(60.0)), TerrainClass::Lowland);
+        assert_eq!(terrain_class(120.0, None), TerrainClass::Lowland);
+        assert_eq!(terrain_class(1800.0, None), TerrainClass::Highland);
+    }
+
+    #[test]
+    fn local_time_follows_daylight_saving() {
+        let now = DateTime::parse_from_rfc3339("2026-07-01T12:00:00Z").unwrap().to_utc();
+        let colombo = local_time("Asia/Colombo".into(), now).unwrap();
+        assert_eq!(colombo.utc_offset, "+05:30");
+        assert_eq!(colombo.local_time, "2026-07-01T17:30:00+05:30");
+
+        let winter = DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z").unwrap().to_utc();
+        assert_eq!(local_time("America/New_York".into(), now).unwrap().utc_offset, "-04:00");
+        assert_eq!(local_time("America/New_York".into(), winter).unwrap().utc_offset, "-05:00");
+        assert!(local_time("Mars/Olympus_Mons".into(), now).is_none());
+    }
 }
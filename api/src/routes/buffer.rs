@@ -0,0 +1,91 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{BufferPayload, BufferQuery, IncludedMeta};
+use crate::repositories::PopulationRepository;
+use crate::response::ApiResponse;
+use crate::validation::MAX_BUFFER_SPAN_KM;
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Population within a buffer distance of any geometry.
+#[utoipa::path(
+    post,
+    path = "/exposure/buffer",
+    tag = "Risk Assessment",
+    summary = "Population within a buffered geometry",
+    description = "Buffers a GeoJSON `geometry` by `buffer_km` and sums the population of grid \
+        cells whose centre lies inside the result. A point gives a geodesic circle, a line a \
+        corridor (a road, pipeline or storm track) and a polygon a zone widened on every side; \
+        the other exposure endpoints are special cases of this one.\n\n\
+        Buffering is done on the geography type in PostGIS, so distances are metres on the \
+        spheroid rather than degrees. The buffered area's bounding box may span at most 1000 km \
+        per side and must not cross the antimeridian. `buffer_km` may be 0 only for polygons. \
+        The buffered outline is returned so clients can draw exactly what was counted.",
+    request_body(
+        content = BufferQuery,
+        description = "Geometry and buffer distance"
+    ),
+    responses(
+        (status = 200, description = "Population within the buffered geometry", body = BufferPayload),
+        (status = 400, description = "Invalid geometry or buffer distance, buffered area too large, or unsupported `include` section")
+    )
+)]
+pub(crate) async fn buffer_exposure(
+    population: web::Data<dyn PopulationRepository>,
+    body: web::Json<BufferQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    includes.ensure_supported(include::BUFFER)?;
+
+    let bounds = geometry::geometry_bounds(&body.geometry)
+        .map_err(|e| AppError::Validation(format!("Invalid geometry: {e}")))?;
+    let geometry_type = body.geometry["type"].as_str().unwrap_or_default().to_string();
+    if body.buffer_km == 0.0 && !geometry_type.ends_with("Polygon") {
+        return Err(AppError::Validation(format!(
+            "buffer_km must be above 0 for a {geometry_type}"
+        ))
+        .into());
+    }
+    let grown = geometry::grow_bounds(bounds, body.buffer_km);
+    if grown[0] < -180.0 || grown[2] > 180.0 {
+        return Err(AppError::Validation("Buffered geometry must not cross the antimeridian".into()).into());
+    }
+    if geometry::bbox_span_km(&grown) > MAX_BUFFER_SPAN_KM {
+        return Err(AppError::Validation(format!(
+            "Buffered geometry's bounding box must span at most {MAX_BUFFER_SPAN_KM} km per side"
+        ))
+        .into());
+    }
+
+    let mut watch = includes.stopwatch();
+    let (total, cells, area_km2, buffered) = population
+        .get_buffer_population(&body.geometry.to_string(), body.buffer_km, grown)
+        .await?;
+    watch.mark("population");
+    let buffered: serde_json::Value = serde_json::from_str(&buffered)
+        .map_err(|e| AppError::Database(format!("invalid buffered geometry: {e}")))?;
+    let bbox = geometry::polygon_bounds(&buffered).unwrap_or(grown);
+
+    let sources = includes.has(Section::Sources).then(|| vec![sources::worldpop()]);
+
+    Ok(ApiResponse::ok(BufferPayload {
+        geometry_type,
+        buffer_km: body.buffer_km,
+        buffered,
+        bbox,
+        area_km2: round1(area_km2),
+        total_population: round1(total),
+        populated_cell_count: cells,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    }))
+}
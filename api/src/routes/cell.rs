@@ -0,0 +1,95 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::grid::{self, NCOLS, NROWS};
+use crate::models::{BoundaryQuery, CellBounds, CellPayload};
+use crate::repositories::{CountryRepository, GeocodingRepository, PopulationRepository};
+use crate::response::ApiResponse;
+
+const KM_PER_DEG: f64 = 111.32;
+const STEP_DEG: f64 = 1.0 / 120.0;
+
+#[inline]
+fn round6(v: f64) -> f64 {
+    (v * 1e6).round() / 1e6
+}
+
+/// Describe a single grid cell by its id.
+#[utoipa::path(
+    get,
+    path = "/cell/{cell_id}",
+    tag = "Population",
+    summary = "Grid cell introspection",
+    description = "Returns the row, column, bounds, centre, area and population of a 30 \
+        arc-second grid cell (`cell_id = row × 43200 + col`, row counted southwards from 90°N, \
+        column eastwards from 180°W), together with the country containing its centre and the \
+        nearest named place. Handy for debugging grid alignment without re-deriving the \
+        row/column arithmetic.",
+    params(
+        ("cell_id" = i64, Path, description = "Grid cell id (0 to 933119999)", example = 430648783),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for `country` (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Cell details", body = CellPayload),
+        (status = 400, description = "Cell id is not a number or lies outside the grid"),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn cell(
+    population: web::Data<dyn PopulationRepository>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
+    path: web::Path<String>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    let raw = path.into_inner();
+    let (cell_id, (row, col)) = raw
+        .parse::<i64>()
+        .ok()
+        .and_then(|id| Some((id, grid::row_col(id)?)))
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "Invalid cell id '{raw}'; expected 0 to {}",
+                NROWS * NCOLS - 1
+            ))
+        })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let max_lat = 90.0 - row as f64 * STEP_DEG;
+    let min_lon = col as f64 * STEP_DEG - 180.0;
+    let (lat, lon) = (max_lat - STEP_DEG / 2.0, min_lon + STEP_DEG / 2.0);
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let (population_res, country_res, place_res) = tokio::join!(
+        population.get_cell_population(lat, lon),
+        countries.get_land_country(lat, lon, vintage),
+        geocoding.find_nearest_place(lat, lon, vintage),
+    );
+    let nearest_place = match place_res {
+        Ok(place) => Some(place),
+        Err(AppError::NotFound(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let area = (STEP_DEG * KM_PER_DEG).powi(2) * lat.to_radians().cos();
+    Ok(ApiResponse::ok(CellPayload {
+        cell_id,
+        row,
+        col,
+        lat: round6(lat),
+        lon: round6(lon),
+        bounds: CellBounds {
+            min_lat: round6(max_lat - STEP_DEG),
+            max_lat: round6(max_lat),
+            min_lon: round6(min_lon),
+            max_lon: round6(min_lon + STEP_DEG),
+        },
+        area_km2: (area * 1000.0).round() / 1000.0,
+        population: population_res?,
+        country: country_res?,
+        nearest_place,
+    }))
+}
@@ -0,0 +1,100 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry::{bearing_deg, compass_direction, great_circle_km};
+use crate::include::Includes;
+use crate::models::{BoundaryQuery, ComparePayload, CompareQuery, ExposureQuery};
+use crate::repositories::{CountryRepository, GeocodingRepository, PopulationRepository};
+use crate::response::ApiResponse;
+use crate::routes::exposure;
+use crate::state::AppState;
+use crate::validation::parse_location;
+
+/// Compare population exposure around two locations.
+#[utoipa::path(
+    get,
+    path = "/compare",
+    tag = "Risk Assessment",
+    summary = "Compare two locations",
+    description = "Runs the `/exposure` analysis around both `from` and `to` with the same \
+        `radius` and returns the two summaries side by side, together with the great-circle \
+        distance and initial bearing from `from` to `to` and the difference in total population \
+        (`to` minus `from`). Useful for before/after relocation analysis or comparing candidate \
+        facility sites.\n\n\
+        `include=` accepts the same sections as `/exposure` and applies them to both locations.",
+    params(
+        ("from" = String, Query, description = "First location as `lat,lon`", example = "6.9271,79.8612"),
+        ("to" = String, Query, description = "Second location as `lat,lon`", example = "7.2906,80.6337"),
+        ("radius" = Option<f64>, Query, description = "Exposure radius around each location in kilometres (default: 1, max: 500)", example = 10.0),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for both analyses (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Side-by-side exposure with distance and bearing", body = ComparePayload),
+        (status = 400, description = "Malformed `from` / `to`, radius out of range or unsupported `include` section"),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn compare(
+    population: web::Data<dyn PopulationRepository>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
+    state: web::Data<AppState>,
+    query: web::Query<CompareQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let from = exposure_query(&query.from, query.radius)?;
+    let to = exposure_query(&query.to, query.radius)?;
+    exposure::check(&from, includes, state.limits())?;
+    exposure::check(&to, includes, state.limits())?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let (from_exposure, to_exposure) = tokio::try_join!(
+        exposure::run(&**population, &**geocoding, &from, includes, vintage),
+        exposure::run(&**population, &**geocoding, &to, includes, vintage),
+    )?;
+
+    let bearing = bearing_deg(from.lat, from.lon, to.lat, to.lon);
+    let difference = to_exposure.total_population - from_exposure.total_population;
+    Ok(ApiResponse::ok(ComparePayload {
+        distance_km: (great_circle_km(from.lat, from.lon, to.lat, to.lon) * 100.0).round() / 100.0,
+        bearing_deg: (bearing * 10.0).round() / 10.0,
+        direction: compass_direction(bearing),
+        population_difference: (difference * 10.0).round() / 10.0,
+        from: from_exposure,
+        to: to_exposure,
+    }))
+}
+
+/// Plain exposure summary around a `lat,lon` pair.
+fn exposure_query(raw: &str, radius: f64) -> Result<ExposureQuery, AppError> {
+    let (lat, lon) = parse_location(raw)?;
+    Ok(ExposureQuery {
+        lat,
+        lon,
+        radius,
+        decay: None,
+        mode: None,
+        coast_km: None,
+        max_elevation_m: None,
+        rings: None,
+        histogram: None,
+        top_cells: None,
+        land_only: None,
+        places_limit: None,
+        places_offset: None,
+        sort: None,
+        order: None,
+        min_place_population: None,
+        group_by: None,
+        dataset: None,
+        year: None,
+    })
+}
@@ -1,12 +1,14 @@
 use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
 use validator::Validate;
 
 use crate::errors::AppError;
-use crate::models::{ContinentQuery, CountryDetailPayload, CountryListPayload, CountryPayload, PointQuery};
-use crate::repositories::CountryRepository;
+use crate::models::{
+    BoundaryQuery, ContinentListPayload, ContinentQuery, CountryDetailPayload, CountryListPayload,
+    CountryPayload, CountryPopulationPayload, CoordinateInfo, EezPayload, PointQuery, Sovereignty,
+};
+use crate::repositories::{CountryCode, CountryFilter, CountryRepository};
 use crate::response::ApiResponse;
-use crate::validation::validate_continent;
+use crate::validation::validate_country_filter;
 
 /// Identify which country contains a given coordinate.
 #[utoipa::path(
@@ -15,10 +17,13 @@ use crate::validation::validate_continent;
     tag = "Country",
     summary = "Country by coordinate",
     description = "Returns the country that contains the given coordinate using Natural Earth \
-        boundary polygons. Includes ISO codes, formal name, continent, region, and sub-region.",
+        boundary polygons. Includes ISO codes, formal name, continent, region, and sub-region.\n\n\
+        Pass `boundaries=YYYY` to evaluate against an older boundary vintage so historical \
+        results stay reproducible after boundary updates.",
     params(
         ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
-        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180)
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Country found at the given coordinate", body = CountryPayload),
@@ -27,19 +32,111 @@ use crate::validation::validate_continent;
     )
 )]
 pub(crate) async fn country_lookup(
-    pool: web::Data<Pool>,
+    countries: web::Data<dyn CountryRepository>,
     query: web::Query<PointQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
-    let result = CountryRepository::get_by_coordinate(&client, query.lat, query.lon).await?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let result = countries.get_by_coordinate(query.lat, query.lon, vintage).await?;
 
     Ok(ApiResponse::ok(result))
 }
 
+/// List every continent with aggregate country figures.
+#[utoipa::path(
+    get,
+    path = "/continents",
+    tag = "Country",
+    summary = "Continent statistics",
+    description = "Returns each Natural Earth continent with its number of countries, summed \
+        `pop_est`, total land area and bounding box, so clients need not aggregate `/countries` \
+        themselves.\n\n\
+        `country_count` counts the sovereign countries with ISO codes that `/countries` lists; \
+        population, area and extent cover every polygon on the continent, dependencies \
+        included. Continents are Natural Earth's (`Asia`, `North America`, …), not the \
+        UN regions `/countries?continent=asia` filters on, so counts may differ slightly.",
+    params(
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Continents with their aggregate figures", body = ContinentListPayload),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn continents(
+    countries: web::Data<dyn CountryRepository>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let continents = countries.get_continent_stats(vintage).await?;
+
+    Ok(ApiResponse::ok(ContinentListPayload {
+        boundary_vintage: vintage,
+        count: continents.len(),
+        continents,
+    }))
+}
+
+/// Identify the exclusive economic zone containing an offshore coordinate.
+#[utoipa::path(
+    get,
+    path = "/eez",
+    tag = "Country",
+    summary = "Maritime zone by coordinate",
+    description = "Returns the exclusive economic zone (Marine Regions World EEZ) containing the \
+        coordinate, with the territory and sovereign state claiming it — several for overlapping \
+        claims and joint regimes. Unlike `/country`, which falls back to the nearest country for \
+        points at sea, this reflects which state actually has maritime jurisdiction.\n\n\
+        `zone` is null on land and on the high seas; `is_land` tells the two apart, evaluated \
+        against `boundaries`. Needs the optional EEZ layer (`make ingest-eez`) and returns 404 \
+        without it.",
+    params(
+        ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.0, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for `is_land` (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Maritime zone at the coordinate, if any", body = EezPayload),
+        (status = 400, description = "Invalid or out-of-range coordinates"),
+        (status = 404, description = "EEZ layer is not loaded, or unknown boundary vintage")
+    )
+)]
+pub(crate) async fn eez_lookup(
+    countries: web::Data<dyn CountryRepository>,
+    query: web::Query<PointQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let (zone, is_land) = tokio::try_join!(
+        countries.get_eez(query.lat, query.lon),
+        countries.is_land(query.lat, query.lon, vintage),
+    )?;
+
+    Ok(ApiResponse::ok(EezPayload {
+        coordinate: CoordinateInfo { lat: query.lat, lon: query.lon },
+        is_land,
+        zone,
+    }))
+}
+
 /// Look up detailed country information by ISO-3166 alpha-3 code.
 #[utoipa::path(
     get,
@@ -50,58 +147,318 @@ pub(crate) async fn country_lookup(
         geographic bounding box for the given ISO-3166 alpha-3 code.\n\n\
         Examples: `USA`, `GBR`, `LKA`, `IND`, `AUS`",
     params(
-        ("iso3" = String, Path, description = "ISO-3166 alpha-3 country code (3 uppercase letters)", example = "LKA")
+        ("iso3" = String, Path, description = "ISO-3166 alpha-3 country code (3 uppercase letters)", example = "LKA"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Country details found", body = CountryDetailPayload),
         (status = 400, description = "Invalid ISO code format — must be exactly 3 letters"),
-        (status = 404, description = "No country found for the given ISO code")
+        (status = 404, description = "No country found for the given ISO code or boundary vintage")
     )
 )]
 pub(crate) async fn country_by_iso3(
-    pool: web::Data<Pool>,
+    countries: web::Data<dyn CountryRepository>,
     path: web::Path<String>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     let iso3 = crate::validation::validate_iso3(&path.into_inner())?;
+    country_detail(&countries, CountryCode::Alpha3(iso3), &boundaries).await
+}
 
-    let client = pool.get().await.map_err(AppError::from)?;
-    let result = CountryRepository::get_by_iso3(&client, &iso3).await?;
+/// Look up a country by its ISO 3166-1 alpha-2 code.
+#[utoipa::path(
+    get,
+    path = "/country/iso2/{code}",
+    tag = "Country",
+    summary = "Country by ISO alpha-2 code",
+    description = "Same as `/country/{iso3}`, keyed by the two-letter code that feeds such as \
+        GDACS and WHO use. Case-insensitive.",
+    params(
+        ("code" = String, Path, description = "ISO-3166 alpha-2 country code (2 letters)", example = "LK"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Country details found", body = CountryDetailPayload),
+        (status = 400, description = "Invalid ISO code format — must be exactly 2 letters"),
+        (status = 404, description = "No country found for the given ISO code or boundary vintage")
+    )
+)]
+pub(crate) async fn country_by_iso2(
+    countries: web::Data<dyn CountryRepository>,
+    path: web::Path<String>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    let iso2 = crate::validation::validate_iso2(&path.into_inner())?;
+    country_detail(&countries, CountryCode::Alpha2(iso2), &boundaries).await
+}
+
+/// Look up a country by its ISO 3166-1 numeric code.
+#[utoipa::path(
+    get,
+    path = "/country/numeric/{code}",
+    tag = "Country",
+    summary = "Country by ISO numeric code",
+    description = "Same as `/country/{iso3}`, keyed by the ISO 3166-1 numeric (UN M49) code. \
+        Leading zeros are optional: `4` and `004` both resolve to Afghanistan.\n\n\
+        Numeric codes come from Natural Earth and are only known for boundary vintages loaded \
+        after they were added; older vintages answer 404 until reloaded.",
+    params(
+        ("code" = String, Path, description = "ISO-3166 numeric country code (1 to 3 digits)", example = "144"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Country details found", body = CountryDetailPayload),
+        (status = 400, description = "Invalid numeric code — must be 1 to 3 digits"),
+        (status = 404, description = "No country found for the given code or boundary vintage")
+    )
+)]
+pub(crate) async fn country_by_numeric(
+    countries: web::Data<dyn CountryRepository>,
+    path: web::Path<String>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    let numeric = crate::validation::validate_iso_numeric(&path.into_inner())?;
+    country_detail(&countries, CountryCode::Numeric(numeric), &boundaries).await
+}
+
+/// Shared tail of the three code lookups.
+async fn country_detail(
+    countries: &web::Data<dyn CountryRepository>,
+    code: CountryCode,
+    boundaries: &BoundaryQuery,
+) -> ActixResult<HttpResponse> {
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let result = countries.get_by_code(&code, vintage).await?;
+
+    Ok(ApiResponse::ok(result))
+}
+
+/// Compare the WorldPop grid total of a country with its Natural Earth estimate.
+#[utoipa::path(
+    get,
+    path = "/country/{iso3}/population",
+    tag = "Country",
+    summary = "Country grid population",
+    description = "Returns the WorldPop 1 km grid summed within the country polygon next to the \
+        Natural Earth `pop_est`, with their difference and ratio — a quick consistency check \
+        between the two datasets.\n\n\
+        Totals are precomputed per boundary vintage (a cell counts when its centre lies inside \
+        the polygon) and refreshed by the WorldPop and Natural Earth loaders; `computed_at` says \
+        when. Returns 404 if the rollup has not been computed for the vintage yet.",
+    params(
+        ("iso3" = String, Path, description = "ISO-3166 alpha-3 country code (3 uppercase letters)", example = "LKA"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Grid total and Natural Earth estimate", body = CountryPopulationPayload),
+        (status = 400, description = "Invalid ISO code format — must be exactly 3 letters"),
+        (status = 404, description = "Unknown country or vintage, or the rollup has not been computed")
+    )
+)]
+pub(crate) async fn country_population(
+    countries: web::Data<dyn CountryRepository>,
+    path: web::Path<String>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    let iso3 = crate::validation::validate_iso3(&path.into_inner())?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let result = countries.get_grid_population(&iso3, vintage).await?;
 
     Ok(ApiResponse::ok(result))
 }
 
-/// List all countries belonging to a continent.
+/// List the countries of a continent, UN region or UN subregion.
 #[utoipa::path(
     get,
     path = "/countries",
     tag = "Country",
-    summary = "Countries by continent",
-    description = "Returns a list of all countries in the specified continent. \
+    summary = "Countries by continent, region or subregion",
+    description = "Returns the countries matching the given filters; at least one of \
+        `continent`, `region`, `subregion` and `sovereign` is required, and several must all \
+        match. All values are case-insensitive.\n\n\
+        Only sovereign states are listed unless `sovereign=false` (dependent territories such \
+        as Greenland, Puerto Rico or Réunion) or `sovereign=all` is passed. Entries without \
+        ISO codes are never listed.\n\n\
         Valid continent values: `asia`, `europe`, `africa`, `oceania`, `americas`, \
-        `north-america`, `south-america` (case-insensitive).",
+        `north-america`, `south-america`.\n\n\
+        Valid UN M49 region values: `Africa`, `Americas`, `Asia`, `Europe`, `Oceania`.\n\n\
+        Valid UN M49 subregion values: `Australia and New Zealand`, `Caribbean`, \
+        `Central America`, `Central Asia`, `Eastern Africa`, `Eastern Asia`, `Eastern Europe`, \
+        `Melanesia`, `Micronesia`, `Middle Africa`, `Northern Africa`, `Northern America`, \
+        `Northern Europe`, `Polynesia`, `South America`, `South-Eastern Asia`, \
+        `Southern Africa`, `Southern Asia`, `Southern Europe`, `Western Africa`, \
+        `Western Asia`, `Western Europe`.",
     params(
-        ("continent" = String, Query, description = "Continent name", example = "asia")
+        ("continent" = Option<String>, Query, description = "Continent name", example = "asia"),
+        ("region" = Option<String>, Query, description = "UN M49 region", example = "Asia"),
+        ("subregion" = Option<String>, Query, description = "UN M49 subregion", example = "Southern Asia"),
+        ("sovereign" = Option<Sovereignty>, Query, description = "`true` (default), `false` for dependent territories only, or `all`"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
     ),
     responses(
-        (status = 200, description = "List of countries in the continent", body = CountryListPayload),
-        (status = 400, description = "Invalid continent name — see description for valid values")
+        (status = 200, description = "Countries matching every filter", body = CountryListPayload),
+        (status = 400, description = "No filter, or an invalid continent, region, subregion or sovereign value — see description for valid values"),
+        (status = 404, description = "Unknown boundary vintage")
     )
 )]
 pub(crate) async fn countries_by_continent(
-    pool: web::Data<Pool>,
+    countries: web::Data<dyn CountryRepository>,
     query: web::Query<ContinentQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
-    query.validate().map_err(|e| {
+    let (filter, list) = matching_countries(&**countries, &query, &boundaries).await?;
+
+    Ok(ApiResponse::ok(CountryListPayload {
+        continent: filter.continent,
+        region: filter.region,
+        subregion: filter.subregion,
+        count: list.len(),
+        countries: list,
+    }))
+}
+
+/// The validated filter of a `/countries` request and every country matching it.
+pub(super) async fn matching_countries(
+    countries: &dyn CountryRepository,
+    query: &ContinentQuery,
+    boundaries: &BoundaryQuery,
+) -> Result<(CountryFilter, Vec<CountryPayload>), AppError> {
+    boundaries.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
 
-    let continent = validate_continent(&query.continent)?;
-    let client = pool.get().await.map_err(AppError::from)?;
-    let countries = CountryRepository::get_by_continent(&client, &continent).await?;
+    let filter = validate_country_filter(query)?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let list = countries.get_countries(&filter, vintage).await?;
+    Ok((filter, list))
+}
 
-    Ok(ApiResponse::ok(CountryListPayload {
-        continent: query.continent.clone(),
-        count: countries.len(),
-        countries,
-    }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geopackage::CountryFeature;
+    use crate::models::{ContinentStats, EezZone, NearbyCountryEntry};
+    use actix_web::{http::StatusCode, test, App};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    /// Knows a single vintage and no countries.
+    struct EmptyCountries;
+
+    #[async_trait]
+    impl CountryRepository for EmptyCountries {
+        async fn resolve_vintage(&self, requested: Option<i32>) -> Result<i32, AppError> {
+            match requested {
+                None | Some(2025) => Ok(2025),
+                Some(v) => Err(AppError::NotFound(format!("Unknown boundary vintage {v}"))),
+            }
+        }
+        async fn is_land(&self, _: f64, _: f64, _: i32) -> Result<bool, AppError> {
+            Ok(false)
+        }
+        async fn get_land_country(&self, _: f64, _: f64, _: i32) -> Result<Option<CountryPayload>, AppError> {
+            Ok(None)
+        }
+        async fn get_nearby_countries(&self, _: f64, _: f64, _: f64, _: i32) -> Result<Vec<NearbyCountryEntry>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_by_coordinate(&self, _: f64, _: f64, _: i32) -> Result<CountryPayload, AppError> {
+            Err(AppError::NotFound("No country found".into()))
+        }
+        async fn get_by_code(&self, code: &CountryCode, vintage: i32) -> Result<CountryDetailPayload, AppError> {
+            Err(AppError::NotFound(format!("No country {} in {vintage}", code.value())))
+        }
+        async fn get_boundary_tile(&self, _: u32, _: u32, _: u32, _: f64, _: i32) -> Result<Vec<u8>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_countries(&self, _: &CountryFilter, _: i32) -> Result<Vec<CountryPayload>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_continent_stats(&self, _: i32) -> Result<Vec<ContinentStats>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_grid_population(&self, iso3: &str, vintage: i32) -> Result<CountryPopulationPayload, AppError> {
+            Err(AppError::NotFound(format!("No country {iso3} in {vintage}")))
+        }
+        async fn get_eez(&self, _: f64, _: f64) -> Result<Option<EezZone>, AppError> {
+            Ok(None)
+        }
+        async fn get_outlines(&self, _: [f64; 4], _: f64, _: i32) -> Result<Vec<Vec<[f64; 2]>>, AppError> {
+            Ok(Vec::new())
+        }
+        async fn get_clipped_boundaries(&self, _: [f64; 4], _: i32) -> Result<Vec<CountryFeature>, AppError> {
+            Ok(Vec::new())
+        }
+    }
+
+    async fn get(uri: &str) -> (StatusCode, String) {
+        let repo: Arc<dyn CountryRepository> = Arc::new(EmptyCountries);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::from(repo))
+                .route("/country/{iso3}", web::get().to(country_by_iso3))
+                .route("/country/iso2/{code}", web::get().to(country_by_iso2))
+                .route("/country/numeric/{code}", web::get().to(country_by_numeric))
+                .route("/countries", web::get().to(countries_by_continent)),
+        )
+        .await;
+        let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        let status = res.status();
+        let body = test::read_body(res).await;
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[actix_web::test]
+    async fn iso3_is_validated_before_lookup() {
+        let (status, _) = get("/country/LK").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn iso3_lookup_goes_through_repository() {
+        let (status, body) = get("/country/lka").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("No country LKA in 2025"), "{body}");
+
+        let (status, _) = get("/country/LKA?boundaries=2019").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn countries_needs_a_valid_filter() {
+        let (status, body) = get("/countries").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("at least one of continent, region, subregion or sovereign"), "{body}");
+
+        let (status, body) = get("/countries?subregion=Middle%20Earth").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("Western Africa"), "{body}");
+
+        let (status, body) = get("/countries?region=asia&subregion=southern%20asia").await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert!(body.contains(r#""subregion":"Southern Asia""#), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn alpha2_and_numeric_codes_are_normalised() {
+        let (status, body) = get("/country/iso2/lk").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("No country LK in"), "{body}");
+        let (status, body) = get("/country/numeric/4").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("No country 004 in"), "{body}");
+
+        for uri in ["/country/iso2/LKA", "/country/numeric/1000", "/country/numeric/+44", "/country/numeric/0"] {
+            let (status, _) = get(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
 }
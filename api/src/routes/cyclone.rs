@@ -0,0 +1,120 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{
+    CoordinateInfo, CyclonePayload, CycloneQuery, IncludedMeta, QuadrantRadii, WindSwath,
+};
+use crate::repositories::PopulationRepository;
+use crate::response::ApiResponse;
+use crate::validation::MAX_WIND_RADIUS_KM;
+use crate::windfield::{self, THRESHOLDS_KT};
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Population exposed to a tropical cyclone's 34/50/64-kt wind swaths.
+#[utoipa::path(
+    post,
+    path = "/exposure/cyclone",
+    tag = "Risk Assessment",
+    summary = "Cyclone wind-field exposure",
+    description = "Estimates the population inside the 34, 50 and 64 kt sustained-wind swaths of a \
+        tropical cyclone.\n\n\
+        Each swath is described by four quadrant radii (NE, SE, SW, NW) around the storm centre. \
+        Radii given in `wind_radii` (e.g. from an advisory, converted to km) are used as-is; any \
+        threshold without them is modelled from `max_wind_kt` and `rmw_km` with a modified \
+        Rankine vortex `V(r) = Vmax · (RMW / r)^0.5`, capped at 1000 km. Thresholds above the \
+        maximum wind have an empty swath.\n\n\
+        Swaths are nested, so `population` is the number of people exposed to *at least* that \
+        wind speed. Cells are counted by their centre, using the same grid machinery as /exposure.",
+    request_body(
+        content = CycloneQuery,
+        description = "Storm centre, intensity and optional quadrant wind radii"
+    ),
+    responses(
+        (status = 200, description = "Population per wind swath", body = CyclonePayload),
+        (status = 400, description = "Invalid storm parameters, missing `rmw_km`, or unsupported `include` section")
+    )
+)]
+pub(crate) async fn cyclone_exposure(
+    population: web::Data<dyn PopulationRepository>,
+    body: web::Json<CycloneQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    includes.ensure_supported(include::CYCLONE)?;
+
+    let mut watch = includes.stopwatch();
+    let (lat, lon) = (body.lat, body.lon);
+
+    let mut swaths = Vec::with_capacity(THRESHOLDS_KT.len());
+    for kt in THRESHOLDS_KT {
+        let advisory = body.wind_radii.as_ref().and_then(|w| w.for_threshold(kt));
+        let (radii, source) = match (advisory, body.rmw_km) {
+            (Some(radii), _) => (radii, "advisory"),
+            (None, Some(rmw)) => {
+                let r = windfield::rankine_radius(body.max_wind_kt, rmw, kt as f64)
+                    .min(MAX_WIND_RADIUS_KM);
+                (QuadrantRadii::uniform(round1(r)), "rankine")
+            }
+            (None, None) => {
+                return Err(AppError::Validation(format!(
+                    "rmw_km is required unless wind_radii.r{kt} is given"
+                ))
+                .into())
+            }
+        };
+        swaths.push((kt, radii, source));
+    }
+
+
+    let radii: Vec<[f64; 4]> = swaths.iter().map(|(_, r, _)| r.as_array()).collect();
+    let populations =
+        population.get_quadrant_swath_populations(lat, lon, &radii).await?;
+    watch.mark("population");
+
+    let geometry = includes.has(Section::Geometry).then(|| {
+        geometry::feature_collection(
+            swaths
+                .iter()
+                .filter_map(|(kt, r, _)| {
+                    let kind = match kt {
+                        34 => "34kt",
+                        50 => "50kt",
+                        _ => "64kt",
+                    };
+                    windfield::swath_polygon(lat, lon, r.as_array()).map(|g| (kind, g))
+                })
+                .collect(),
+        )
+    });
+    let sources = includes.has(Section::Sources).then(|| vec![sources::worldpop()]);
+
+    let swaths = swaths
+        .into_iter()
+        .zip(populations)
+        .map(|((threshold_kt, radii_km, radii_source), population)| WindSwath {
+            threshold_kt,
+            radii_source: radii_source.into(),
+            radii_km,
+            area_km2: round1(windfield::swath_area_km2(radii_km.as_array())),
+            population: round1(population),
+        })
+        .collect();
+
+    Ok(ApiResponse::ok(CyclonePayload {
+        coordinate: CoordinateInfo { lat, lon },
+        max_wind_kt: body.max_wind_kt,
+        rmw_km: body.rmw_km,
+        swaths,
+        geometry,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    }))
+}
@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry::{bearing_deg, compass_direction, great_circle_km, vincenty_km};
+use crate::models::{CoordinateInfo, DistancePayload, DistanceQuery};
+use crate::response::ApiResponse;
+use crate::validation::parse_location;
+
+#[inline]
+fn round3(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+/// Distance and bearing between two points.
+#[utoipa::path(
+    get,
+    path = "/distance",
+    tag = "Geocoding",
+    summary = "Distance and bearing between two points",
+    description = "Returns the great-circle (haversine) distance on a spherical Earth, the \
+        ellipsoidal WGS84 distance by Vincenty's formula, and the initial bearing and 8-point \
+        compass direction from `from` to `to` — the same calculations the other endpoints use \
+        for their `distance_km` and `direction` fields. Distances are rounded to the metre.\n\n\
+        `geodesic_km` is null for nearly antipodal points, where Vincenty's iteration does not \
+        converge. No database access.",
+    params(
+        ("from" = String, Query, description = "Start point as `lat,lon`", example = "6.9271,79.8612"),
+        ("to" = String, Query, description = "End point as `lat,lon`", example = "7.2906,80.6337")
+    ),
+    responses(
+        (status = 200, description = "Distance and direction", body = DistancePayload),
+        (status = 400, description = "Malformed `from` / `to`")
+    )
+)]
+pub(crate) async fn distance(query: web::Query<DistanceQuery>) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let (from_lat, from_lon) = parse_location(&query.from)?;
+    let (to_lat, to_lon) = parse_location(&query.to)?;
+
+    let bearing = bearing_deg(from_lat, from_lon, to_lat, to_lon);
+    Ok(ApiResponse::ok(DistancePayload {
+        from: CoordinateInfo { lat: from_lat, lon: from_lon },
+        to: CoordinateInfo { lat: to_lat, lon: to_lon },
+        distance_km: round3(great_circle_km(from_lat, from_lon, to_lat, to_lon)),
+        geodesic_km: vincenty_km(from_lat, from_lon, to_lat, to_lon).map(round3),
+        bearing_deg: (bearing * 10.0).round() / 10.0,
+        direction: compass_direction(bearing),
+    }))
+}
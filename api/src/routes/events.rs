@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::models::{AlertLevel, EventListPayload, EventsQuery};
+use crate::repositories::EventRepository;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+
+/// Disaster events recorded from the GDACS feed, with their analyses.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "Events",
+    summary = "List recorded disaster events",
+    description = "Lists the disaster events the GDACS poller has recorded, most recent first. Each \
+        event carries its GDACS type, alert level, severity and location, and the `/analyse` payload \
+        for that location in `result` once its background analysis has finished (`status` tracks it \
+        as for `/jobs`). Earthquakes are analysed with the `earthquake` hazard template at their \
+        magnitude, other events with the auto-expanding search.\n\n\
+        The poller is off unless `GDACS_POLL_SECS` is set, and records only events at \
+        `GDACS_MIN_ALERT` (default `orange`) or above; without it the list stays empty.",
+    params(
+        ("event_type" = Option<String>, Query, description = "GDACS event type: EQ, TC, FL, VO, DR or WF", example = "EQ"),
+        ("min_alert" = Option<String>, Query, description = "Only events at this alert level or above: `green`, `orange` or `red`", example = "orange"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of events (default: 20, max: 100)", example = 20)
+    ),
+    responses(
+        (status = 200, description = "Recorded events", body = EventListPayload),
+        (status = 400, description = "Invalid event type, alert level or limit")
+    )
+)]
+pub(crate) async fn list_events(
+    state: web::Data<AppState>,
+    query: web::Query<EventsQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let event_type = query.event_type.as_deref().map(str::to_ascii_uppercase);
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let events = EventRepository::list(
+        &client,
+        event_type.as_deref(),
+        query.min_alert.unwrap_or(AlertLevel::Green),
+        query.limit,
+    )
+    .await?;
+
+    Ok(ApiResponse::ok(EventListPayload { count: events.len(), events }))
+}
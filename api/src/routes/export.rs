@@ -0,0 +1,96 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use chrono::Utc;
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geopackage::{self, Layer};
+use crate::models::{ExportLayer, GeoPackageQuery};
+use crate::repositories::Repositories;
+use crate::validation::{parse_bbox, parse_layers};
+
+/// Most populated cells one GeoPackage holds.
+const MAX_EXPORT_CELLS: i64 = 250_000;
+/// Most places one GeoPackage holds.
+const MAX_EXPORT_PLACES: i64 = 50_000;
+
+/// Download population, places and boundaries of an area as one GeoPackage.
+#[utoipa::path(
+    get,
+    path = "/export/geopackage",
+    tag = "Export",
+    summary = "GeoPackage of an area",
+    description = "Returns an OGC GeoPackage (SQLite) with the requested `layers` inside the \
+        `bbox` (each side at most 10°), in WGS 84, ready to open in QGIS, ArcGIS or GDAL:\n\n\
+        - `population`: every populated WorldPop 1 km cell overlapping the box as a polygon, with \
+        `population`, `lat` and `lon` (the cell centre); at most 250,000 cells.\n\
+        - `places`: GeoNames places inside the box as points, with `geonameid`, `name`, \
+        `feature_code`, `country_code` and `population`; at most 50,000 places.\n\
+        - `countries`: country boundaries clipped to the box as multipolygons, with `iso_a2`, \
+        `iso_a3`, `name` and `continent`.\n\n\
+        All three layers are included by default. `year` picks the population grid and \
+        `boundaries` the boundary vintage, as elsewhere.",
+    params(
+        ("bbox" = String, Query, description = "Area as `min_lon,min_lat,max_lon,max_lat` (each side at most 10°)", example = "79.80,6.85,79.95,7.00"),
+        ("layers" = Option<String>, Query, description = "Comma-separated layers: `population`, `places`, `countries` (default: all three)", example = "population,places"),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage of `countries` (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "GeoPackage file", content_type = "application/geopackage+sqlite3"),
+        (status = 400, description = "Malformed or oversized `bbox`, unknown or repeated layer, or more cells or places than one file holds"),
+        (status = 404, description = "No grid loaded for `year` or unknown boundary vintage")
+    )
+)]
+pub(crate) async fn export_geopackage(
+    repos: web::Data<Repositories>,
+    query: web::Query<GeoPackageQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let bbox = parse_bbox(&query.bbox)
+        .ok_or_else(|| AppError::Validation("bbox must be min_lon,min_lat,max_lon,max_lat".into()))?;
+    let requested = match query.layers.as_deref() {
+        Some(raw) => parse_layers(raw).unwrap_or_default(),
+        None => ExportLayer::ALL.to_vec(),
+    };
+
+    let mut layers = Vec::with_capacity(requested.len());
+    for layer in requested {
+        layers.push(match layer {
+            ExportLayer::Population => {
+                let (_, repo) = repos.population.for_year(query.year).await?;
+                let populated = repo.count_bbox_cells(bbox).await?;
+                if populated > MAX_EXPORT_CELLS {
+                    return Err(too_many(format!("{populated} populated cells"), MAX_EXPORT_CELLS).into());
+                }
+                Layer::population(&repo.get_bbox_grid_cells(bbox, 1).await?)
+            }
+            ExportLayer::Places => {
+                let places = repos.geocoding.get_places_in_bbox(bbox, MAX_EXPORT_PLACES + 1).await?;
+                if places.len() as i64 > MAX_EXPORT_PLACES {
+                    return Err(too_many(format!("more than {MAX_EXPORT_PLACES} places"), MAX_EXPORT_PLACES).into());
+                }
+                Layer::places(places)
+            }
+            ExportLayer::Countries => {
+                let vintage = repos.countries.resolve_vintage(query.boundaries).await?;
+                Layer::countries(repos.countries.get_clipped_boundaries(bbox, vintage).await?)
+            }
+        });
+    }
+
+    let file = geopackage::write(layers, bbox, Utc::now())?;
+    let [min_lon, min_lat, max_lon, max_lat] = bbox;
+    Ok(HttpResponse::Ok()
+        .content_type(geopackage::CONTENT_TYPE)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"geopop-{min_lon}_{min_lat}_{max_lon}_{max_lat}.gpkg\""),
+        ))
+        .body(file))
+}
+
+fn too_many(found: String, max: i64) -> AppError {
+    AppError::Validation(format!("bbox holds {found}; a GeoPackage layer holds at most {max}, use a smaller bbox"))
+}
@@ -1,15 +1,30 @@
 use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
 use validator::Validate;
 
+use crate::config::Limits;
 use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::kml::{self, KmlDocument};
 use crate::models::{
-    CoordinateInfo, ExposurePayload, ExposurePlacesPayload, ExposurePlacesQuery, ExposureQuery,
+    AdminExposurePayload, AdminExposureQuery, AdminLevel, BoundaryQuery, BuiltUpExposure, CoastalExposure, CoordinateInfo, Dataset, DecayModel, DensityBucket, DistanceRing, ExportFormat, ExportQuery,
+    ExposureMode, ExposurePayload, ExposurePlacesPayload, ExposurePlacesQuery, ExposureQuery, IncludedMeta,
+    NightLightsExposure, PlaceSort, SettlementExposure, SortOrder,
+    DEFAULT_COAST_KM, DEFAULT_MAX_ELEVATION_M,
 };
-use crate::repositories::{GeocodingRepository, PopulationRepository};
+use crate::repositories::{CountryRepository, GeocodingRepository, PlaceOptions, PopulationRepository, Repositories};
 use crate::response::ApiResponse;
+use crate::shapefile;
+use crate::state::AppState;
+use crate::validation::{parse_rings, validate_admin_code, MAX_ADMIN_SPAN_KM, MAX_TOP_CELLS};
 
 const KM_PER_DEG: f64 = 111.32;
+/// Places listed by `/exposure/admin` unless `places` says otherwise.
+const DEFAULT_ADMIN_PLACES: i64 = 10;
+/// Most cells a `format=shapefile` export holds.
+const MAX_SHAPEFILE_CELLS: i64 = 100_000;
+/// Edges of the `histogram` density buckets, people/km².
+const DENSITY_EDGES: [f64; 4] = [10.0, 100.0, 1_000.0, 10_000.0];
 
 #[inline]
 fn round1(v: f64) -> f64 {
@@ -21,6 +36,16 @@ fn round2(v: f64) -> f64 {
     (v * 100.0).round() / 100.0
 }
 
+/// The settlement split rounded like `total_population`; shared with /analyse.
+pub(crate) fn round_settlement(split: SettlementExposure) -> SettlementExposure {
+    SettlementExposure {
+        urban_centre: round1(split.urban_centre),
+        urban_cluster: round1(split.urban_cluster),
+        rural: round1(split.rural),
+        unclassified: round1(split.unclassified),
+    }
+}
+
 /// Analyse population exposure within a circular area around a coordinate.
 #[utoipa::path(
     get,
@@ -30,56 +55,465 @@ fn round2(v: f64) -> f64 {
     description = "Calculates the total estimated population within a circular area of the given \
         radius around the coordinate. Returns population density metrics and a count of named \
         places (use /exposure/places for the full paginated list).\n\n\
-        The analysis uses WorldPop 1 km grid data.",
+        Optional sections via `include=`: `uncertainty` reports `total_population` as a range \
+        using WorldPop 95% credible intervals where available; `places` embeds the nearest named \
+        places, paged with `places_limit` (default 20, max 100) and `places_offset`, and sets \
+        `places_truncated` when more remain, and ordered by `sort=distance|population|name` \
+        and `order=asc|desc` (default: nearest first, largest first for population); \
+        `min_place_population` drops smaller places from `places` and `place_count`; `sources` and `timings` add dataset attribution and a server-side timing \
+        breakdown; `geometry` (or `include_geometry=true`) returns the analysed area as a GeoJSON \
+        FeatureCollection, computed with the same distance rule as the population sum, and \
+        `hull` adds the convex hull of the populated cells that were counted; `settlement` splits \
+        `total_population` into GHS-SMOD urban centre, urban cluster and rural cells (404 until \
+        `make ingest-settlement` has run); `built_up` reports the GHSL built-up surface of every \
+        cell in the radius, populated or not, for infrastructure damage estimates (404 until \
+        `make ingest-built-up` has run); `night_lights` sums VIIRS nighttime-lights radiance over \
+        the radius — a proxy for economic activity and a pre-event baseline for power outages \
+        (404 until `make ingest-night-lights` has run); `land_cover` splits `total_population` by \
+        the dominant ESA WorldCover class of each cell — cropland, tree cover, built-up and so on \
+        — largest first (404 until `make ingest-land-cover` has run).\n\n\
+        Pass `mode=coastal` for a tsunami / storm-surge figure: `coastal.population` counts \
+        only cells within `coast_km` of the coastline (default 10) and at or below \
+        `max_elevation_m` (default 10 m) inside the radius around the epicentre. Coastal mode \
+        needs the optional terrain layer (`make ingest-terrain`) and returns 404 without it.\n\n\
+        Pass `decay=linear` (`w = 1 - d/radius`) or `decay=inverse_square` (`w = 1/(1 + d²)`, \
+        d in km) to also get `weighted_population`, where each cell counts by its distance \
+        from the centre — people 1 km away weigh far more than people 90 km away.\n\n\
+        Pass `rings=5,10,25,50` (ascending, up to 10, none beyond `radius`) for a situation-report \
+        breakdown: `rings` lists the population of each concentric band and the cumulative \
+        \"within N km\" total, computed in a single pass.\n\n\
+        Pass `histogram=true` for `histogram`: the grid cells in the radius and their population \
+        per density bucket (below 10, 10–100, 100–1k, 1k–10k and from 10k people/km²), telling a \
+        single dense city apart from population spread over farmland.\n\n\
+        Pass `land_only=true` to clip the circle to land (the country boundaries of `boundaries`) for \
+        `area_km2` and `density_per_km2`; the whole-circle area understates the density of \
+        coastal cities.\n\n\
+        When the radius crosses a border, `countries` splits the total by country: the population \
+        of the cells whose centre lies in each, its share of `total_population`, and its named \
+        places.\n\n\
+        Pass `top_cells=N` (up to 100) for `top_cells`: the N most populated grid cells in the \
+        radius with their bounds, most populous first, to point at hotspots without fetching the \
+        whole grid from `/population`.\n\n\
+        Pass `group_by=admin1` or `group_by=admin2` for `admin_groups`: the number of named \
+        places and their summed GeoNames population per province or district within the radius, \
+        largest first — the layout of a humanitarian situation report.\n\n\
+        The analysis uses WorldPop 1 km grid data: the unconstrained grid by default, or with \
+        `dataset=constrained` the constrained grid, which places people only in cells with \
+        mapped buildings (loaded by `make ingest-worldpop-constrained`; 404 without it). \
+        `year` picks an earlier grid year where one has been loaded (default: the latest, \
+        echoed as `year`). Uncertainty intervals exist for the latest unconstrained grid only.\n\n\
+        Pass `format=kml` for a KML document to open in Google Earth instead of JSON: the search \
+        area with the totals, the `top_cells` most populated cells (default 100) shaded by \
+        population, and the embedded places (`include=places` is implied).\n\n\
+        Pass `format=shapefile` for a zipped ESRI Shapefile (`.shp`, `.shx`, `.dbf`, `.prj`, WGS 84) \
+        of every populated cell counted in the total — one rectangle per cell with `POPULATION`, \
+        `LAT` and `LON` attributes — for desktop GIS. Exports are limited to 100,000 cells.",
     params(
         ("lat" = f64, Query, description = "Centre latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
         ("lon" = f64, Query, description = "Centre longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
-        ("radius" = Option<f64>, Query, description = "Search radius in kilometres (default: 1, max: 500)", example = 10.0)
+        ("radius" = Option<f64>, Query, description = "Search radius in kilometres (default: 1, max: 500)", example = 10.0),
+        ("decay" = Option<DecayModel>, Query, description = "Distance-decay model for `weighted_population`: `linear` or `inverse_square`", example = "linear"),
+        ("mode" = Option<ExposureMode>, Query, description = "`standard` (default) or `coastal` for low-lying coastal population", example = "coastal"),
+        ("coast_km" = Option<f64>, Query, description = "Coastal mode: maximum distance to the coastline in km (default: 10, max: 100)", example = 5.0),
+        ("max_elevation_m" = Option<f64>, Query, description = "Coastal mode: maximum elevation in metres (default: 10, range: -100 to 100)", example = 10.0),
+        ("rings" = Option<String>, Query, description = "Comma-separated ring radii in km for a per-band breakdown (ascending, max 10, none beyond `radius`)", example = "5,10,25,50"),
+        ("histogram" = Option<bool>, Query, description = "Add `histogram`, cells and population per density bucket", example = true),
+        ("land_only" = Option<bool>, Query, description = "Count only land in `area_km2` and `density_per_km2`", example = true),
+        ("top_cells" = Option<i64>, Query, description = "Add `top_cells`, the N most populated cells with bounds (max 100)", example = 10),
+        ("places_limit" = Option<i64>, Query, description = "With `include=places`: places to embed, nearest first (default: 20, max: 100)", example = 50),
+        ("places_offset" = Option<i64>, Query, description = "With `include=places`: nearest places to skip (default: 0)", example = 50),
+        ("sort" = Option<PlaceSort>, Query, description = "With `include=places`: sort places by `distance` (default), `population` or `name`", example = "population"),
+        ("order" = Option<SortOrder>, Query, description = "With `include=places`: `asc` or `desc` (default: desc for population, asc otherwise)", example = "desc"),
+        ("min_place_population" = Option<i64>, Query, description = "Only count and list places with at least this GeoNames population (default: 0)", example = 5000),
+        ("group_by" = Option<AdminLevel>, Query, description = "Aggregate places per `admin1` or `admin2` region into `admin_groups`", example = "admin1"),
+        ("dataset" = Option<Dataset>, Query, description = "WorldPop grid to sum: `unconstrained` (default) or `constrained`", example = "constrained"),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for `countries`, `land_only` and place country names (default: current)", example = 2023),
+        ("format" = Option<ExportFormat>, Query, description = "`json` (default), `kml` or `shapefile`", example = "kml")
     ),
     responses(
-        (status = 200, description = "Exposure analysis results", body = ExposurePayload),
-        (status = 400, description = "Invalid coordinates or radius out of range (0–500 km)")
+        (status = 200, description = "Exposure analysis results, a KML document with `format=kml` or a zipped Shapefile with `format=shapefile`", content(
+            (ExposurePayload = "application/json"),
+            ("application/vnd.google-earth.kml+xml"),
+            ("application/zip")
+        )),
+        (status = 400, description = "Invalid coordinates, radius out of range (0–500 km), malformed `rings` or a ring beyond `radius`, more than 100,000 cells for `format=shapefile`, coastal parameters without `mode=coastal`, places paging or sorting without `include=places`, `include=uncertainty` with `dataset=constrained` or an earlier `year`, or unsupported `include` section"),
+        (status = 404, description = "`mode=coastal` requested but the terrain layer is not loaded, `include=settlement`, `built_up`, `night_lights` or `land_cover` requested but that layer is not loaded, `dataset=constrained` requested but that grid is not loaded, no grid loaded for `year`, or unknown boundary vintage")
     )
 )]
 pub(crate) async fn exposure(
-    pool: web::Data<Pool>,
+    repos: web::Data<Repositories>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    state: web::Data<AppState>,
     query: web::Query<ExposureQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+    export: web::Query<ExportQuery>,
+    includes: Includes,
 ) -> ActixResult<HttpResponse> {
+    check(&query, includes, state.limits())?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let population = repos.population_for(query.dataset);
+    match export.format {
+        ExportFormat::Json => {
+            let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+            let payload = run(&**population, &**geocoding, &query, includes, vintage).await?;
+            Ok(ApiResponse::ok(payload))
+        }
+        ExportFormat::Kml => {
+            let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+            let mut query = query.into_inner();
+            query.top_cells = query.top_cells.or(Some(MAX_TOP_CELLS));
+            let payload =
+                run(&**population, &**geocoding, &query, includes.with(Section::Places), vintage).await?;
+            Ok(kml_response(payload))
+        }
+        ExportFormat::Shapefile => {
+            let (lat, lon) = (query.lat, query.lon);
+            let (year, population) = population.for_year(query.year).await?;
+            let cells = population.get_top_cells(lat, lon, query.radius, MAX_SHAPEFILE_CELLS + 1).await?;
+            if cells.len() as i64 > MAX_SHAPEFILE_CELLS {
+                return Err(AppError::Validation(format!(
+                    "format=shapefile is limited to {MAX_SHAPEFILE_CELLS} cells; use a smaller radius"
+                ))
+                .into());
+            }
+            let name = format!("geopop-cells-{lat:.4}_{lon:.4}-{year}");
+            let body = shapefile::zipped(&name, &cells, chrono::Utc::now().date_naive())?;
+            Ok(HttpResponse::Ok()
+                .content_type(shapefile::CONTENT_TYPE)
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{name}.zip\"")))
+                .body(body))
+        }
+    }
+}
+
+fn kml_response(payload: ExposurePayload) -> HttpResponse {
+    let CoordinateInfo { lat, lon } = payload.coordinate;
+    let mut summary = vec![
+        ("total_population", payload.total_population.to_string()),
+        ("year", payload.year.to_string()),
+        ("density_per_km2", payload.density_per_km2.to_string()),
+        ("place_count", payload.place_count.to_string()),
+    ];
+    if let Some(weighted) = payload.weighted_population {
+        summary.push(("weighted_population", weighted.to_string()));
+    }
+    let body = KmlDocument::new(format!("GeoPop exposure {lat:.4}, {lon:.4}"), lat, lon, payload.radius_km)
+        .cells(payload.top_cells.unwrap_or_default())
+        .places(payload.places.unwrap_or_default())
+        .render(&summary);
+    HttpResponse::Ok()
+        .content_type(kml::CONTENT_TYPE)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"geopop-exposure-{lat:.4}_{lon:.4}.kml\""),
+        ))
+        .body(body)
+}
+
+/// Validates an exposure request; shared with job submission so bad jobs are
+/// rejected up front.
+pub(crate) fn check(query: &ExposureQuery, includes: Includes, limits: &Limits) -> Result<(), AppError> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    limits.check_radius(query.radius)?;
+    includes.ensure_supported(include::EXPOSURE)?;
+    if query.mode.unwrap_or_default() != ExposureMode::Coastal
+        && (query.coast_km.is_some() || query.max_elevation_m.is_some())
+    {
+        return Err(AppError::Validation(
+            "coast_km and max_elevation_m require mode=coastal".into(),
+        ));
+    }
+    if query.dataset == Some(Dataset::Constrained) && includes.has(Section::Uncertainty) {
+        return Err(AppError::Validation(
+            "include=uncertainty is only available for dataset=unconstrained".into(),
+        ));
+    }
+    let places_options = query.places_limit.is_some()
+        || query.places_offset.is_some()
+        || query.sort.is_some()
+        || query.order.is_some();
+    if !includes.has(Section::Places) && places_options {
+        return Err(AppError::Validation(
+            "places_limit, places_offset, sort and order require include=places".into(),
+        ));
+    }
+    if let Some(outer) = query.rings.as_deref().and_then(parse_rings).and_then(|r| r.last().copied()) {
+        if outer > query.radius {
+            return Err(AppError::Validation(format!(
+                "rings must not extend beyond radius ({outer} km > {} km)",
+                query.radius
+            )));
+        }
+    }
+    Ok(())
+}
 
-    let client = pool.get().await.map_err(AppError::from)?;
-    client.execute("SET jit = off", &[]).await.ok();
-    client.execute("SET statement_timeout = '30s'", &[]).await.ok();
+/// Computes the exposure payload for a validated request against boundary
+/// vintage `vintage`. Used by the handler and by background jobs, whose
+/// repositories allow a longer statement timeout.
+pub(crate) async fn run(
+    population: &dyn PopulationRepository,
+    geocoding: &dyn GeocodingRepository,
+    query: &ExposureQuery,
+    includes: Includes,
+    vintage: i32,
+) -> Result<ExposurePayload, AppError> {
+    let coastal_mode = query.mode.unwrap_or_default() == ExposureMode::Coastal;
+    let mut watch = includes.stopwatch();
 
     let (lat, lon, radius_km) = (query.lat, query.lon, query.radius);
 
-    let total_pop = PopulationRepository::get_exposure_population(&client, lat, lon, radius_km).await?;
-    let place_count = GeocodingRepository::count_exposed_places(&client, lat, lon, radius_km)
+    let (year, population) = population.for_year(query.year).await?;
+    let total_pop = population.get_exposure_population(lat, lon, radius_km).await?;
+    watch.mark("population");
+    let weighted_population = match query.decay {
+        Some(decay) => {
+            let weighted = population
+                .get_weighted_exposure_population(lat, lon, radius_km, decay)
+                .await?;
+            watch.mark("weighted_population");
+            Some(round1(weighted))
+        }
+        None => None,
+    };
+    let coastal = if coastal_mode {
+        let coast_km = query.coast_km.unwrap_or(DEFAULT_COAST_KM);
+        let max_elevation_m = query.max_elevation_m.unwrap_or(DEFAULT_MAX_ELEVATION_M);
+        let (coastal_pop, cell_count) = population
+            .get_coastal_exposure_population(lat, lon, radius_km, coast_km, max_elevation_m)
+            .await?;
+        watch.mark("coastal");
+        Some(CoastalExposure {
+            coast_km,
+            max_elevation_m,
+            population: round1(coastal_pop),
+            cell_count,
+            share_of_total: if total_pop > 0.0 { (coastal_pop / total_pop * 1000.0).round() / 1000.0 } else { 0.0 },
+        })
+    } else {
+        None
+    };
+    let rings = match query.rings.as_deref().and_then(parse_rings) {
+        Some(radii) => {
+            let bands = population.get_ring_populations(lat, lon, &radii).await?;
+            watch.mark("rings");
+            let mut cumulative = 0.0;
+            let mut inner_km = 0.0;
+            Some(
+                radii.iter().zip(bands).map(|(&outer_km, population)| {
+                    cumulative += population;
+                    let ring = DistanceRing {
+                        inner_km,
+                        outer_km,
+                        population: round1(population),
+                        cumulative_population: round1(cumulative),
+                    };
+                    inner_km = outer_km;
+                    ring
+                }).collect(),
+            )
+        }
+        None => None,
+    };
+    let histogram = if query.histogram.unwrap_or(false) {
+        let buckets = population.get_density_histogram(lat, lon, radius_km, &DENSITY_EDGES).await?;
+        watch.mark("histogram");
+        let bounds = std::iter::once(0.0).chain(DENSITY_EDGES).zip(DENSITY_EDGES.map(Some).into_iter().chain([None]));
+        Some(
+            bounds.zip(buckets).map(|((min, max), (cell_count, population))| DensityBucket {
+                min_density_per_km2: min,
+                max_density_per_km2: max,
+                cell_count,
+                population: round1(population),
+                share_of_population: if total_pop > 0.0 { (population / total_pop * 1000.0).round() / 1000.0 } else { 0.0 },
+            }).collect(),
+        )
+    } else {
+        None
+    };
+    let top_cells = match query.top_cells {
+        Some(limit) => {
+            let cells = population.get_top_cells(lat, lon, radius_km, limit).await?;
+            watch.mark("top_cells");
+            Some(cells)
+        }
+        None => None,
+    };
+    let options = PlaceOptions::new(query.sort, query.order).min_population(query.min_place_population);
+    let place_count = geocoding
+        .count_exposed_places(lat, lon, radius_km, options.min_population)
         .await
         .unwrap_or(0);
-    let cell_pop = PopulationRepository::get_cell_population(&client, lat, lon)
-        .await
-        .unwrap_or(0.0);
+    let mut split = population.get_country_split(lat, lon, radius_km, vintage).await?;
+    let countries = if split.is_empty() {
+        None
+    } else {
+        let place_counts = geocoding
+            .count_exposed_places_by_country(lat, lon, radius_km, options.min_population)
+            .await?;
+        for country in &mut split {
+            country.share_of_total =
+                if total_pop > 0.0 { (country.population / total_pop * 1000.0).round() / 1000.0 } else { 0.0 };
+            country.population = round1(country.population);
+            country.place_count =
+                country.iso_a2.as_ref().and_then(|cc| place_counts.get(cc)).copied().unwrap_or(0);
+        }
+        Some(split)
+    };
+    watch.mark("countries");
+    let cell_pop = population.get_cell_population(lat, lon).await.unwrap_or(0.0);
+    watch.mark("places_and_cell");
+    let uncertainty = if includes.has(Section::Uncertainty) {
+        let interval = population.get_exposure_uncertainty(lat, lon, radius_km).await?;
+        watch.mark("uncertainty");
+        Some(interval)
+    } else {
+        None
+    };
+    let settlement = if includes.has(Section::Settlement) {
+        let split = population.get_settlement_exposure(lat, lon, radius_km).await?;
+        watch.mark("settlement");
+        Some(round_settlement(split))
+    } else {
+        None
+    };
+    let land_cover = if includes.has(Section::LandCover) {
+        let mut classes = population.get_land_cover_exposure(lat, lon, radius_km).await?;
+        for class in &mut classes {
+            class.share_of_population =
+                if total_pop > 0.0 { (class.population / total_pop * 1000.0).round() / 1000.0 } else { 0.0 };
+            class.population = round1(class.population);
+        }
+        watch.mark("land_cover");
+        Some(classes)
+    } else {
+        None
+    };
+    let (places, places_truncated) = if includes.has(Section::Places) {
+        let limit = query.places_limit.unwrap_or(include::MAX_INCLUDED_PLACES);
+        let offset = query.places_offset.unwrap_or(0);
+        let places = geocoding.get_exposed_places(lat, lon, radius_km, options, limit, offset, vintage).await?;
+        watch.mark("places");
+        let truncated = offset + (places.len() as i64) < place_count;
+        (Some(places), Some(truncated))
+    } else {
+        (None, None)
+    };
+    let admin_groups = match query.group_by {
+        Some(level) => {
+            let groups = geocoding
+                .group_exposed_places(lat, lon, radius_km, level, options.min_population)
+                .await?;
+            watch.mark("admin_groups");
+            Some(groups)
+        }
+        None => None,
+    };
+    let geometry = if includes.has(Section::Geometry) {
+        let mut features = vec![("search_area", geometry::search_area(lat, lon, radius_km))];
+        if includes.has(Section::Hull) {
+            if let Some(hull) = population.get_populated_hull(lat, lon, radius_km).await? {
+                features.push(("populated_hull", hull));
+            }
+            watch.mark("hull");
+        }
+        Some(geometry::feature_collection(features))
+    } else {
+        None
+    };
+    let sources = includes.has(Section::Sources).then(|| {
+        let mut list = vec![sources::worldpop_grid(query.dataset, year), sources::geonames()];
+        if includes.has(Section::Uncertainty) {
+            list.push(sources::worldpop_uncertainty());
+        }
+        if coastal_mode {
+            list.push(sources::terrain());
+        }
+        if includes.has(Section::Settlement) {
+            list.push(sources::ghsl_smod());
+        }
+        if includes.has(Section::BuiltUp) {
+            list.push(sources::ghsl_built_up());
+        }
+        if includes.has(Section::NightLights) {
+            list.push(sources::viirs_night_lights());
+        }
+        if includes.has(Section::LandCover) {
+            list.push(sources::esa_worldcover());
+        }
+        list
+    });
 
     let deg = 1.0 / 120.0;
     let cell_area = deg * deg * KM_PER_DEG * KM_PER_DEG * lat.to_radians().cos();
     let cell_density = if cell_area > 0.0 { cell_pop as f64 / cell_area } else { 0.0 };
-    let area = std::f64::consts::PI * radius_km * radius_km;
+    let land_only = query.land_only.unwrap_or(false);
+    let area = if land_only {
+        let land = population.get_land_area(lat, lon, radius_km, vintage).await?;
+        watch.mark("land_area");
+        land
+    } else {
+        std::f64::consts::PI * radius_km * radius_km
+    };
     let density = if area > 0.0 { total_pop / area } else { 0.0 };
+    let built_up = if includes.has(Section::BuiltUp) {
+        let (surface_m2, cell_count) = population.get_built_up_exposure(lat, lon, radius_km).await?;
+        watch.mark("built_up");
+        Some(BuiltUpExposure {
+            surface_m2: surface_m2.round(),
+            cell_count,
+            share_of_area: if area > 0.0 { (surface_m2 / (area * 1e6) * 1000.0).round() / 1000.0 } else { 0.0 },
+        })
+    } else {
+        None
+    };
+    let night_lights = if includes.has(Section::NightLights) {
+        let (sum, max, lit_cells) = population.get_night_lights_exposure(lat, lon, radius_km).await?;
+        watch.mark("night_lights");
+        Some(NightLightsExposure {
+            sum_radiance: round1(sum),
+            max_radiance: round1(max),
+            lit_cell_count: lit_cells,
+        })
+    } else {
+        None
+    };
 
-    Ok(ApiResponse::ok(ExposurePayload {
+    Ok(ExposurePayload {
         coordinate: CoordinateInfo { lat, lon },
         radius_km,
         total_population: round1(total_pop),
+        year,
+        decay: query.decay,
+        weighted_population,
+        coastal,
+        rings,
+        histogram,
+        top_cells,
+        countries,
         area_km2: round2(area),
+        land_only,
         density_per_km2: round1(density),
         cell_population: cell_pop,
         cell_area_km2: round2(cell_area),
         cell_density_per_km2: round1(cell_density),
         place_count,
-    }))
+        uncertainty,
+        settlement,
+        built_up,
+        night_lights,
+        land_cover,
+        places,
+        admin_groups,
+        places_truncated,
+        geometry,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    })
 }
 
 /// Paginated list of named places within an exposure radius.
@@ -89,38 +523,47 @@ pub(crate) async fn exposure(
     tag = "Risk Assessment",
     summary = "Places within exposure radius (paginated)",
     description = "Returns a paginated list of named places (from GeoNames) within the given \
-        radius, ordered by distance from the centre coordinate.",
+        radius, ordered by distance from the centre coordinate unless `sort` / `order` say otherwise.",
     params(
         ("lat" = f64, Query, description = "Centre latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
         ("lon" = f64, Query, description = "Centre longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
         ("radius" = Option<f64>, Query, description = "Search radius in kilometres (default: 1, max: 500)", example = 10.0),
         ("page" = Option<i64>, Query, description = "Page number (default: 1)", example = 1),
-        ("per_page" = Option<i64>, Query, description = "Results per page (default: 20, max: 100)", example = 20)
+        ("per_page" = Option<i64>, Query, description = "Results per page (default: 20, max: 100)", example = 20),
+        ("sort" = Option<PlaceSort>, Query, description = "Sort by `distance` (default), `population` or `name`", example = "population"),
+        ("order" = Option<SortOrder>, Query, description = "`asc` or `desc` (default: desc for population, asc otherwise)", example = "desc"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for the country name (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Paginated places list", body = ExposurePlacesPayload),
-        (status = 400, description = "Invalid parameters")
+        (status = 400, description = "Invalid parameters"),
+        (status = 404, description = "Unknown boundary vintage")
     )
 )]
 pub(crate) async fn exposure_places(
-    pool: web::Data<Pool>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
+    state: web::Data<AppState>,
     query: web::Query<ExposurePlacesQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
-
-    let client = pool.get().await.map_err(AppError::from)?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    state.limits().check_radius(query.radius)?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
 
     let (lat, lon, radius_km) = (query.lat, query.lon, query.radius);
     let page = query.page;
     let per_page = query.per_page;
     let offset = (page - 1) * per_page;
 
-    let total_places = GeocodingRepository::count_exposed_places(&client, lat, lon, radius_km)
-        .await
-        .unwrap_or(0);
-    let places = GeocodingRepository::get_exposed_places(&client, lat, lon, radius_km, per_page, offset)
+    let total_places = geocoding.count_exposed_places(lat, lon, radius_km, 0).await.unwrap_or(0);
+    let options = PlaceOptions::new(query.sort, query.order);
+    let places = geocoding.get_exposed_places(lat, lon, radius_km, options, per_page, offset, vintage)
         .await
         .unwrap_or_default();
 
@@ -133,3 +576,73 @@ pub(crate) async fn exposure_places(
         places,
     }))
 }
+
+/// Grid population of an administrative unit's boundary.
+#[utoipa::path(
+    get,
+    path = "/exposure/admin",
+    tag = "Risk Assessment",
+    summary = "Exposure of an administrative unit",
+    description = "Sums the WorldPop grid inside the boundary of a first- or second-level \
+        administrative unit, named by its GeoNames admin code (`LK.36` for Western Province, \
+        `LK.36.1232783` for one of its districts), and lists its most populous places. Response \
+        is often planned by district, which a radius around a point only approximates.\n\n\
+        Boundaries come from `ingest_admin_boundaries.py` (Natural Earth states and provinces by \
+        default) of the current boundary vintage, or the one given by `boundaries`; a code no \
+        boundary of that vintage carries is a 404. A cell counts when its centre lies \
+        inside the boundary. Units whose bounding box spans more than 2500 km, or crosses the \
+        antimeridian, are rejected — use /country/{iso3}/population for those.",
+    params(
+        ("code" = String, Query, description = "GeoNames admin code (`CC.A1` or `CC.A1.A2`)", example = "LK.36"),
+        ("places" = Option<i64>, Query, description = "Number of most populous places to list (default: 10, max: 100)", example = 10),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage of the admin unit (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Population, area and places of the unit", body = AdminExposurePayload),
+        (status = 400, description = "Invalid admin code or `places`, or the unit is too large"),
+        (status = 404, description = "Unknown boundary vintage, or no admin boundary with this code is loaded for it")
+    )
+)]
+pub(crate) async fn exposure_admin(
+    repos: web::Data<Repositories>,
+    query: web::Query<AdminExposureQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let code = validate_admin_code(&query.code)?;
+    let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+
+    let unit = repos.geocoding.get_admin_unit(&code, vintage).await?.ok_or_else(|| {
+        AppError::NotFound(format!("No admin boundary with code {code} is loaded for boundaries {vintage}"))
+    })?;
+    if geometry::bbox_span_km(&unit.bounds) > MAX_ADMIN_SPAN_KM {
+        return Err(AppError::Validation(format!(
+            "Admin unit {code} spans more than {MAX_ADMIN_SPAN_KM} km; use /country/{{iso3}}/population instead"
+        ))
+        .into());
+    }
+
+    let limit = query.places.unwrap_or(DEFAULT_ADMIN_PLACES);
+    let ((total, cells, area_km2), top_places) = tokio::try_join!(
+        repos.population.get_polygon_population(&unit.geojson, unit.bounds),
+        repos.geocoding.get_admin_places(&code, limit, vintage),
+    )?;
+
+    Ok(ApiResponse::ok(AdminExposurePayload {
+        code,
+        level: unit.level,
+        name: unit.name,
+        country_code: unit.country_code,
+        bbox: unit.bounds,
+        area_km2: round1(area_km2),
+        total_population: round1(total),
+        populated_cell_count: cells,
+        density_per_km2: if area_km2 > 0.0 { round1(total / area_km2) } else { 0.0 },
+        top_places,
+    }))
+}
@@ -0,0 +1,157 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use serde_json::json;
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{CoordinateInfo, FloodPartExposure, FloodPayload, FloodQuery, IncludedMeta};
+use crate::repositories::{PopulationRepository, SearchArea};
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use crate::validation::{MAX_FLOOD_SPAN_KM, MAX_POLYGON_PARTS};
+
+const KM_PER_DEG: f64 = 111.32;
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[inline]
+fn round6(v: f64) -> f64 {
+    (v * 1e6).round() / 1e6
+}
+
+/// Population at or below a flood level inside a polygon or circular area.
+#[utoipa::path(
+    post,
+    path = "/exposure/flood",
+    tag = "Risk Assessment",
+    summary = "Flood exposure by elevation threshold",
+    description = "Estimates how many people live in grid cells whose ground elevation is at or \
+        below `max_elevation_m` — a simple bathtub model of a flood or storm surge at that level.\n\n\
+        The area is either a GeoJSON `polygon` (Polygon, MultiPolygon or a GeometryCollection of \
+        them, whose bounding box may span at most 1000 km per side and must not cross the \
+        antimeridian) or `lat`/`lon` with a `radius`, counted with the same distance rule as \
+        /exposure. Cells are matched by their centre, and a cell under overlapping polygons is \
+        counted once.\n\n\
+        With `breakdown`, `parts` reports each polygon on its own — every polygon of a \
+        MultiPolygon or collection, in input order, up to 100 — such as the islands of an \
+        archipelago.\n\n\
+        Needs the optional elevation layer (`make ingest-terrain`) and returns 404 without it. \
+        Populated cells the elevation layer does not cover count towards `total_population` only.",
+    request_body(
+        content = FloodQuery,
+        description = "Flood area and water level"
+    ),
+    responses(
+        (status = 200, description = "Flooded population within the area", body = FloodPayload),
+        (status = 400, description = "Invalid polygon or coordinates, both or neither area forms given, area too large, `breakdown` without a polygon or with too many parts, or unsupported `include` section"),
+        (status = 404, description = "Elevation layer is not loaded")
+    )
+)]
+pub(crate) async fn flood_exposure(
+    population: web::Data<dyn PopulationRepository>,
+    state: web::Data<AppState>,
+    body: web::Json<FloodQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if let Some(radius) = body.radius {
+        state.limits().check_radius(radius)?;
+    }
+    includes.ensure_supported(include::FLOOD)?;
+
+    // Collections are flattened into one MultiPolygon for the totals; parts
+    // keep their own GeoJSON and bounds for the breakdown.
+    let geojson;
+    let mut parts = Vec::new();
+    let (area, bounds, centre) = match (&body.polygon, body.lat, body.lon) {
+        (Some(polygon), None, None) if body.radius.is_none() => {
+            let invalid = |e: String| AppError::Validation(format!("Invalid polygon: {e}"));
+            let polygons = geometry::polygon_parts(polygon).map_err(invalid)?;
+            let bounds = geometry::polygon_bounds(polygon).map_err(invalid)?;
+            if geometry::bbox_span_km(&bounds) > MAX_FLOOD_SPAN_KM {
+                return Err(AppError::Validation(format!(
+                    "Polygon bounding box must span at most {MAX_FLOOD_SPAN_KM} km per side"
+                ))
+                .into());
+            }
+            if body.breakdown {
+                if polygons.len() > MAX_POLYGON_PARTS {
+                    return Err(AppError::Validation(format!(
+                        "breakdown supports at most {MAX_POLYGON_PARTS} polygons, got {}",
+                        polygons.len()
+                    ))
+                    .into());
+                }
+                for part in &polygons {
+                    let part_bounds = geometry::part_bounds(part).map_err(invalid)?;
+                    parts.push((json!({ "type": "Polygon", "coordinates": part }).to_string(), part_bounds));
+                }
+            }
+            geojson = geometry::multi_polygon(&polygons).to_string();
+            (SearchArea::Polygon { geojson: &geojson, bounds }, bounds, None)
+        }
+        (None, Some(_), Some(_)) if body.breakdown => {
+            return Err(AppError::Validation("breakdown needs a `polygon` area".into()).into())
+        }
+        (None, Some(lat), Some(lon)) => {
+            let radius_km = body.radius.unwrap_or(1.0);
+            let dlat = radius_km / KM_PER_DEG;
+            let dlon = radius_km / (KM_PER_DEG * lat.to_radians().cos().max(0.01));
+            let bounds = [
+                round6(lon - dlon),
+                round6((lat - dlat).max(-90.0)),
+                round6(lon + dlon),
+                round6((lat + dlat).min(90.0)),
+            ];
+            (SearchArea::Circle { lat, lon, radius_km }, bounds, Some((lat, lon, radius_km)))
+        }
+        _ => {
+            return Err(AppError::Validation(
+                "Provide either `polygon` or `lat` and `lon` (with optional `radius`), not both".into(),
+            )
+            .into())
+        }
+    };
+
+    let mut watch = includes.stopwatch();
+    let (total, flooded, flooded_cells) =
+        population.get_flood_exposure(&area, body.max_elevation_m).await?;
+    watch.mark("population");
+    let parts = if parts.is_empty() {
+        None
+    } else {
+        let exposures = population.get_flood_exposure_by_part(&parts, body.max_elevation_m).await?;
+        watch.mark("parts");
+        let breakdown = parts.iter().zip(exposures).enumerate().map(|(index, ((_, bbox), (total, flooded, cells)))| {
+            FloodPartExposure {
+                index,
+                bbox: *bbox,
+                total_population: round1(total),
+                flooded_population: round1(flooded),
+                flooded_cell_count: cells,
+            }
+        });
+        Some(breakdown.collect())
+    };
+
+    let sources = includes.has(Section::Sources).then(|| vec![sources::worldpop(), sources::terrain()]);
+
+    Ok(ApiResponse::ok(FloodPayload {
+        coordinate: centre.map(|(lat, lon, _)| CoordinateInfo { lat, lon }),
+        radius_km: centre.map(|(_, _, r)| r),
+        bbox: bounds,
+        max_elevation_m: body.max_elevation_m,
+        total_population: round1(total),
+        flooded_population: round1(flooded),
+        flooded_cell_count: flooded_cells,
+        share_of_total: if total > 0.0 { (flooded / total * 1000.0).round() / 1000.0 } else { 0.0 },
+        parts,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    }))
+}
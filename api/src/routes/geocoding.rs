@@ -1,14 +1,17 @@
-use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
 use validator::Validate;
 
 use crate::errors::AppError;
+use crate::language;
 use crate::models::{
-    CitySearchPayload, CitySearchQuery, CoordinateInfo, ExposurePlacesQuery, ExposureQuery,
-    LandCheckPayload, NearbyCitiesPayload, NearbyCountriesPayload, PointQuery, ReversePayload,
+    AdminHierarchyPayload, AirportsPayload, AirportsQuery, BoundaryQuery, CitySearchPayload, CitySearchQuery, CoordinateInfo, ExposurePlacesQuery, ExposureQuery,
+    FacilitiesPayload, FacilitiesQuery, FacilitiesWithinPayload, FacilitiesWithinQuery, FacilityType, LandCheckPayload, LangQuery, MaxDistanceQuery, NearbyCitiesPayload, NearbyCountriesPayload, PlaceSort, PointQuery,
+    ReverseMode, ReverseModeQuery, ReversePayload, SortOrder,
 };
-use crate::repositories::{CountryRepository, GeocodingRepository};
+use crate::repositories::{CountryRepository, GeocodingRepository, PlaceOptions, Repositories};
 use crate::response::ApiResponse;
+use crate::state::AppState;
+use crate::validation::parse_facility_types;
 
 /// Find the nearest named place for a given coordinate.
 #[utoipa::path(
@@ -18,29 +21,117 @@ use crate::response::ApiResponse;
     summary = "Reverse geocode",
     description = "Returns the nearest named place (city, town, village, etc.) for the given \
         coordinate using the GeoNames gazetteer. The response includes a structured address \
-        with administrative hierarchy (city, state, country).",
+        with administrative hierarchy (city, state, country).\n\n\
+        `display_name` and `address` use the first language of `lang` (or else \
+        `Accept-Language`) that GeoNames has a name in, falling back to the default names; \
+        `name` is always the default GeoNames name.\n\n\
+        With `mode=contains` the country, state and district are those whose boundary polygons \
+        contain the coordinate, which avoids naming the neighbouring region near borders; the \
+        place itself stays the nearest one.",
     params(
         ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
-        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180)
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
+        ("lang" = Option<String>, Query, description = "Language tags for `display_name` and `address`, most preferred first; overrides `Accept-Language`", example = "ta"),
+        ("mode" = Option<ReverseMode>, Query, description = "`nearest` (default): the regions of the nearest place; `contains`: those whose boundary polygons contain the point", example = "contains"),
+        ("max_distance_km" = Option<f64>, Query, description = "Answer 404 when the nearest place is further than this (km); the body states the distance to the nearest one", example = 50.0, maximum = 20000),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for the country and, with `mode=contains`, the regions (default: current)", example = 2023),
+        ("Accept-Language" = Option<String>, Header, description = "Preferred languages for `display_name` and `address`", example = "ta, en;q=0.8")
     ),
     responses(
         (status = 200, description = "Nearest named place found", body = ReversePayload),
         (status = 400, description = "Invalid or out-of-range coordinates"),
-        (status = 404, description = "No named place found near the given coordinate")
+        (status = 404, description = "No named place found, none within `max_distance_km` (`payload` then holds `max_distance_km` and `nearest_distance_km`), or unknown boundary vintage")
     )
 )]
 pub(crate) async fn reverse_geocode(
-    pool: web::Data<Pool>,
+    req: HttpRequest,
+    repos: web::Data<Repositories>,
     query: web::Query<PointQuery>,
+    lang: web::Query<LangQuery>,
+    cap: web::Query<MaxDistanceQuery>,
+    mode: web::Query<ReverseModeQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    lang.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    cap.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let languages = language::preferred(lang.lang.as_deref(), &req);
+    let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+    let result = repos
+        .geocoding
+        .reverse_geocode(query.lat, query.lon, &languages, cap.max_distance_km, mode.mode, vintage)
+        .await?;
+
+    Ok(language::vary(ApiResponse::ok(result)))
+}
+
+/// Find the country and administrative units containing a coordinate.
+#[utoipa::path(
+    get,
+    path = "/admin",
+    tag = "Geocoding",
+    summary = "Administrative hierarchy of a point",
+    description = "Returns the country, first-level unit (state / province) and second-level \
+        unit (district / county) whose boundary polygons contain the coordinate, with their \
+        codes and names, in one call. Unlike `/reverse`, nothing is inferred from the nearest \
+        place, so points near a border get their own regions.\n\n\
+        Countries come from the Natural Earth boundaries of vintage `boundaries` (default: \
+        current) and units from the layers `ingest_admin_boundaries.py` loaded for that vintage. \
+        A level no loaded boundary covers — the sea, or a level that was never loaded for the \
+        vintage — is `null`. Names use the first language of `lang` (or \
+        else `Accept-Language`) that GeoNames has a name in.",
+    params(
+        ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
+        ("lang" = Option<String>, Query, description = "Language tags for the names, most preferred first; overrides `Accept-Language`", example = "ta"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage (default: current)", example = 2023),
+        ("Accept-Language" = Option<String>, Header, description = "Preferred languages for the names", example = "ta, en;q=0.8")
+    ),
+    responses(
+        (status = 200, description = "Containing country and admin units", body = AdminHierarchyPayload),
+        (status = 400, description = "Invalid or out-of-range coordinates, or invalid `lang`"),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn admin_hierarchy(
+    req: HttpRequest,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
+    query: web::Query<PointQuery>,
+    lang: web::Query<LangQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    lang.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
-    let result = GeocodingRepository::reverse_geocode(&client, query.lat, query.lon).await?;
+    let languages = language::preferred(lang.lang.as_deref(), &req);
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let (country, admin1, admin2) =
+        geocoding.admin_hierarchy(query.lat, query.lon, &languages, vintage).await?;
 
-    Ok(ApiResponse::ok(result))
+    Ok(language::vary(ApiResponse::ok(AdminHierarchyPayload {
+        coordinate: CoordinateInfo { lat: query.lat, lon: query.lon },
+        country,
+        admin1,
+        admin2,
+    })))
 }
 
 /// Find all countries within a radius of a coordinate.
@@ -54,7 +145,8 @@ pub(crate) async fn reverse_geocode(
     params(
         ("lat" = f64, Query, description = "Centre latitude", example = 6.9271, minimum = -90, maximum = 90),
         ("lon" = f64, Query, description = "Centre longitude", example = 79.8612, minimum = -180, maximum = 180),
-        ("radius" = Option<f64>, Query, description = "Search radius in km (default: 1, max: 500)", example = 50.0)
+        ("radius" = Option<f64>, Query, description = "Search radius in km (default: 1, max: 500)", example = 50.0),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Countries within radius", body = NearbyCountriesPayload),
@@ -62,18 +154,24 @@ pub(crate) async fn reverse_geocode(
     )
 )]
 pub(crate) async fn nearby_countries(
-    pool: web::Data<Pool>,
+    countries: web::Data<dyn CountryRepository>,
+    state: web::Data<AppState>,
     query: web::Query<ExposureQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    state.limits().check_radius(query.radius)?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
     let (lat, lon, radius_km) = (query.lat, query.lon, query.radius);
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
 
-    let is_land = CountryRepository::is_land(&client, lat, lon).await.unwrap_or(false);
-    let countries = CountryRepository::get_nearby_countries(&client, lat, lon, radius_km).await?;
+    let is_land = countries.is_land(lat, lon, vintage).await.unwrap_or(false);
+    let countries = countries.get_nearby_countries(lat, lon, radius_km, vintage).await?;
 
     Ok(ApiResponse::ok(NearbyCountriesPayload {
         coordinate: CoordinateInfo { lat, lon },
@@ -90,37 +188,47 @@ pub(crate) async fn nearby_countries(
     tag = "Geocoding",
     summary = "Nearby cities (paginated)",
     description = "Returns a paginated list of named places from GeoNames within the given \
-        radius, ordered by distance from the coordinate.",
+        radius, ordered by distance from the coordinate unless `sort` / `order` say otherwise.",
     params(
         ("lat" = f64, Query, description = "Centre latitude", example = 6.9271, minimum = -90, maximum = 90),
         ("lon" = f64, Query, description = "Centre longitude", example = 79.8612, minimum = -180, maximum = 180),
         ("radius" = Option<f64>, Query, description = "Search radius in km (default: 1, max: 500)", example = 10.0),
         ("page" = Option<i64>, Query, description = "Page number (default: 1)", example = 1),
-        ("per_page" = Option<i64>, Query, description = "Results per page (default: 20, max: 100)", example = 20)
+        ("per_page" = Option<i64>, Query, description = "Results per page (default: 20, max: 100)", example = 20),
+        ("sort" = Option<PlaceSort>, Query, description = "Sort by `distance` (default), `population` or `name`", example = "population"),
+        ("order" = Option<SortOrder>, Query, description = "`asc` or `desc` (default: desc for population, asc otherwise)", example = "desc"),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for the country name (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Paginated places list", body = NearbyCitiesPayload),
-        (status = 400, description = "Invalid parameters")
+        (status = 400, description = "Invalid parameters"),
+        (status = 404, description = "Unknown boundary vintage")
     )
 )]
 pub(crate) async fn nearby_cities(
-    pool: web::Data<Pool>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
+    state: web::Data<AppState>,
     query: web::Query<ExposurePlacesQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    state.limits().check_radius(query.radius)?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
     let (lat, lon, radius_km) = (query.lat, query.lon, query.radius);
     let page = query.page;
     let per_page = query.per_page;
     let offset = (page - 1) * per_page;
 
-    let total_places = GeocodingRepository::count_exposed_places(&client, lat, lon, radius_km)
-        .await
-        .unwrap_or(0);
-    let places = GeocodingRepository::get_exposed_places(&client, lat, lon, radius_km, per_page, offset)
+    let total_places = geocoding.count_exposed_places(lat, lon, radius_km, 0).await.unwrap_or(0);
+    let options = PlaceOptions::new(query.sort, query.order);
+    let places = geocoding.get_exposed_places(lat, lon, radius_km, options, per_page, offset, vintage)
         .await
         .unwrap_or_default();
 
@@ -134,6 +242,134 @@ pub(crate) async fn nearby_cities(
     }))
 }
 
+/// Nearest critical facilities of one type.
+#[utoipa::path(
+    get,
+    path = "/facilities/nearest",
+    tag = "Geocoding",
+    summary = "Nearest critical facilities",
+    description = "Returns the `limit` nearest hospitals, airports, ports or schools to the coordinate, \
+        nearest first, each with distance, compass direction and bearing. Facilities are GeoNames \
+        S-class features (`HSP`, `AIRP`, `PRT`, `SCH`); the list is empty until they are ingested \
+        with `make ingest-geonames`.",
+    params(
+        ("lat" = f64, Query, description = "Latitude", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude", example = 79.8612, minimum = -180, maximum = 180),
+        ("type" = FacilityType, Query, description = "`hospital`, `airport`, `port` or `school`", example = "hospital"),
+        ("limit" = Option<i64>, Query, description = "Facilities to return (default: 5, max: 50)", example = 5)
+    ),
+    responses(
+        (status = 200, description = "Nearest facilities", body = FacilitiesPayload),
+        (status = 400, description = "Invalid parameters")
+    )
+)]
+pub(crate) async fn nearest_facilities(
+    geocoding: web::Data<dyn GeocodingRepository>,
+    query: web::Query<FacilitiesQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let (lat, lon) = (query.lat, query.lon);
+    let facilities = geocoding
+        .find_nearest_facilities(lat, lon, query.facility_type.feature_code(), query.limit)
+        .await?;
+
+    Ok(ApiResponse::ok(FacilitiesPayload {
+        coordinate: CoordinateInfo { lat, lon },
+        facility_type: query.facility_type,
+        facilities,
+    }))
+}
+
+/// Critical facilities inside a radius, grouped by type.
+#[utoipa::path(
+    get,
+    path = "/facilities",
+    tag = "Geocoding",
+    summary = "Facilities within a radius",
+    description = "Lists the hospitals, airports, ports and schools inside the radius, one group per \
+        requested type (all four unless `types` narrows them) with its count and its facilities \
+        nearest first. `limit` caps the facilities listed per type, not the count. Facilities are \
+        GeoNames S-class features, as in `/facilities/nearest`.",
+    params(
+        ("lat" = f64, Query, description = "Centre latitude", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Centre longitude", example = 79.8612, minimum = -180, maximum = 180),
+        ("radius" = Option<f64>, Query, description = "Search radius in km (default: 1, max: 500)", example = 25.0),
+        ("types" = Option<String>, Query, description = "Comma-separated `hospital`, `airport`, `port`, `school` (default: all)", example = "hospital,airport"),
+        ("limit" = Option<i64>, Query, description = "Facilities listed per type (default: 100, max: 500)", example = 100)
+    ),
+    responses(
+        (status = 200, description = "Facilities grouped by type", body = FacilitiesWithinPayload),
+        (status = 400, description = "Invalid parameters")
+    )
+)]
+pub(crate) async fn facilities_within(
+    geocoding: web::Data<dyn GeocodingRepository>,
+    state: web::Data<AppState>,
+    query: web::Query<FacilitiesWithinQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    state.limits().check_radius(query.radius)?;
+
+    let (lat, lon, radius_km) = (query.lat, query.lon, query.radius);
+    let types = match query.types.as_deref() {
+        Some(raw) => parse_facility_types(raw).unwrap_or_default(),
+        None => FacilityType::ALL.to_vec(),
+    };
+    let groups = geocoding.find_facilities_within(lat, lon, radius_km, &types, query.limit).await?;
+
+    Ok(ApiResponse::ok(FacilitiesWithinPayload {
+        coordinate: CoordinateInfo { lat, lon },
+        radius_km,
+        total_facilities: groups.iter().map(|g| g.count).sum(),
+        groups,
+    }))
+}
+
+/// Nearest airports with ICAO / IATA codes and runways.
+#[utoipa::path(
+    get,
+    path = "/airports/nearest",
+    tag = "Geocoding",
+    summary = "Nearest airports",
+    description = "Returns the `limit` nearest airports to the coordinate, nearest first, with their \
+        ICAO and IATA codes, open runways (dimensions in metres, longest first) and distance, compass \
+        direction and bearing. `min_runway_m` keeps only airports with an open runway at least that \
+        long, e.g. `1500` for a loaded C-130. Airports come from OurAirports: large, medium and small \
+        airports are served; heliports, seaplane bases and closed airports are not. The list is empty \
+        until `make ingest-airports` has run.",
+    params(
+        ("lat" = f64, Query, description = "Latitude", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude", example = 79.8612, minimum = -180, maximum = 180),
+        ("min_runway_m" = Option<f64>, Query, description = "Shortest acceptable runway in metres (max 6000)", example = 1500.0),
+        ("limit" = Option<i64>, Query, description = "Airports to return (default: 5, max: 50)", example = 5)
+    ),
+    responses(
+        (status = 200, description = "Nearest airports", body = AirportsPayload),
+        (status = 400, description = "Invalid parameters")
+    )
+)]
+pub(crate) async fn nearest_airports(
+    geocoding: web::Data<dyn GeocodingRepository>,
+    query: web::Query<AirportsQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let (lat, lon) = (query.lat, query.lon);
+    let airports = geocoding.find_nearest_airports(lat, lon, query.min_runway_m, query.limit).await?;
+
+    Ok(ApiResponse::ok(AirportsPayload {
+        coordinate: CoordinateInfo { lat, lon },
+        airports,
+    }))
+}
+
 /// Check whether a coordinate is on land or at sea.
 #[utoipa::path(
     get,
@@ -144,7 +380,8 @@ pub(crate) async fn nearby_cities(
         If on land, also returns the containing country.",
     params(
         ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
-        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180)
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Land/sea check result", body = LandCheckPayload),
@@ -152,17 +389,21 @@ pub(crate) async fn nearby_cities(
     )
 )]
 pub(crate) async fn land_check(
-    pool: web::Data<Pool>,
+    countries: web::Data<dyn CountryRepository>,
     query: web::Query<PointQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
     let (lat, lon) = (query.lat, query.lon);
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
 
-    let country = CountryRepository::get_land_country(&client, lat, lon).await?;
+    let country = countries.get_land_country(lat, lon, vintage).await?;
     let is_land = country.is_some();
 
     Ok(ApiResponse::ok(LandCheckPayload {
@@ -199,36 +440,37 @@ pub(crate) async fn land_check(
         ("min_population" = Option<i64>, Query,
             description = "Only return places whose GeoNames population estimate is at least this value. \
                 Default: 0. Useful to hide hamlets — try 1000 or 10000 for a cleaner autocomplete.",
-            example = 1000, minimum = 0)
+            example = 1000, minimum = 0),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for the country name (default: current)", example = 2023)
     ),
     responses(
         (status = 200, description = "Matching cities ordered by score then population",
             body = CitySearchPayload),
-        (status = 400, description = "Invalid query parameters")
+        (status = 400, description = "Invalid query parameters"),
+        (status = 404, description = "Unknown boundary vintage")
     )
 )]
 pub(crate) async fn search_cities(
-    pool: web::Data<Pool>,
+    geocoding: web::Data<dyn GeocodingRepository>,
+    countries: web::Data<dyn CountryRepository>,
     query: web::Query<CitySearchQuery>,
+    boundaries: web::Query<BoundaryQuery>,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
-
-    let client = pool.get().await.map_err(AppError::from)?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
 
     let q = query.q.trim().to_string();
     let country_upper = query.country.as_ref().map(|c| c.to_uppercase());
     let country_ref = country_upper.as_deref();
 
-    let results = GeocodingRepository::search_cities(
-        &client,
-        &q,
-        country_ref,
-        query.limit,
-        query.min_population,
-    )
-    .await?;
+    let results = geocoding
+        .search_cities(&q, country_ref, query.limit, query.min_population, vintage)
+        .await?;
 
     Ok(ApiResponse::ok(CitySearchPayload {
         query: q,
@@ -0,0 +1,42 @@
+use actix_web::{web, HttpResponse};
+use async_graphql::http::GraphiQLSource;
+
+use crate::config::API_PREFIX;
+use crate::graphql::GeoPopSchema;
+
+/// Execute a GraphQL query.
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "GraphQL",
+    summary = "GraphQL query",
+    description = "Standard GraphQL-over-HTTP endpoint (`{\"query\", \"variables\", \"operationName\"}`). \
+        Combines population, geocoding, country and exposure lookups in one round-trip and returns \
+        only the selected fields, e.g.\n\n\
+        `{ point(lat: 6.9271, lon: 79.8612) { country { name } nearestPlace { name distanceKm } \
+        exposure(radius: 10) { totalPopulation placeCount } } }`\n\n\
+        Errors follow the GraphQL convention: HTTP 200 with an `errors` array, each carrying \
+        `extensions.code` (`BAD_REQUEST`, `NOT_FOUND` or `INTERNAL_SERVER_ERROR`). Queries are \
+        limited in depth and cost — each `exposure` field is expensive, so only a few fit in one \
+        request. The schema is available by introspection; `GET /graphql` serves GraphiQL.",
+    request_body(content = Object, description = "GraphQL request", content_type = "application/json",
+        example = json!({"query": "{ point(lat: 6.9271, lon: 79.8612) { population country { name iso_a3: isoA3 } } }"})),
+    responses(
+        (status = 200, description = "GraphQL response with `data` and/or `errors`", body = Object,
+            example = json!({"data": {"point": {"population": 28534.0, "country": {"name": "Sri Lanka", "iso_a3": "LKA"}}}})),
+        (status = 400, description = "Body is not a GraphQL request")
+    )
+)]
+pub(crate) async fn graphql(
+    schema: web::Data<GeoPopSchema>,
+    request: web::Json<async_graphql::Request>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(schema.execute(request.into_inner()).await)
+}
+
+/// GraphiQL explorer for the GraphQL endpoint.
+pub(crate) async fn graphiql() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(GraphiQLSource::build().endpoint(&format!("{API_PREFIX}/graphql")).finish())
+}
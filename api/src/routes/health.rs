@@ -1,7 +1,19 @@
-use actix_web::HttpResponse;
+use std::time::{Duration, Instant};
 
-use crate::models::HealthPayload;
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use deadpool_postgres::Pool;
+
+use crate::auth::API_KEY_HEADER;
+use crate::config::Config;
+use crate::errors::AppError;
+use crate::models::{DatabaseStatus, HealthPayload, HealthQuery, PoolStatus};
+use crate::repositories::StatsRepository;
 use crate::response::ApiResponse;
+use crate::routes::admin::ADMIN_KEY_HEADER;
+use crate::state::AppState;
+
+/// How long the health check waits for a connection and its probe query.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Returns the current health status of the API service.
 #[utoipa::path(
@@ -9,13 +21,105 @@ use crate::response::ApiResponse;
     path = "/health",
     tag = "System",
     summary = "Health check",
-    description = "Returns the current health status of the API. Use this endpoint for uptime monitoring and load-balancer health probes.",
+    description = "Returns the current health status of the API. Use this endpoint for uptime monitoring and load-balancer health probes.\n\n\
+        The check makes one round-trip to the primary database, cut off after 2 s. When the database \
+        cannot be reached `status` is `degraded`; the response is still 200.\n\n\
+        With `detail=true` the response adds `pool` (connection pool usage: `waiting` above zero means \
+        requests are queueing for a connection, which precedes pool timeouts), `database` (round-trip \
+        latency and the PostGIS version) and, when a read replica is configured, `read_pool`. Details \
+        need a valid `X-API-Key` when `API_KEY` is set, or the `X-Admin-Key`.",
+    params(
+        ("detail" = Option<bool>, Query, description = "Add pool usage, database latency and the PostGIS version", example = true)
+    ),
     responses(
-        (status = 200, description = "Service is healthy", body = HealthPayload)
+        (status = 200, description = "Service health", body = HealthPayload),
+        (status = 401, description = "`detail=true` without a valid API or admin key")
     )
 )]
-pub(crate) async fn health() -> HttpResponse {
-    ApiResponse::ok(HealthPayload {
-        status: "ok".into(),
+pub(crate) async fn health(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<HealthQuery>,
+) -> ActixResult<HttpResponse> {
+    if query.detail && !may_see_detail(&req, &state.config) {
+        return Err(AppError::Unauthorized("detail=true needs a valid X-API-Key or X-Admin-Key".into()).into());
+    }
+    let database = database_status(&state.pool).await;
+    let status = if database.reachable { "ok" } else { "degraded" }.into();
+    let payload = if query.detail {
+        HealthPayload {
+            status,
+            pool: Some(pool_status(&state.pool)),
+            read_pool: (!state.config.database_read_url.is_empty()).then(|| pool_status(&state.read_pool)),
+            database: Some(database),
+        }
+    } else {
+        HealthPayload { status, pool: None, read_pool: None, database: None }
+    };
+    Ok(ApiResponse::ok(payload))
+}
+
+/// `/health` itself is public; its details are for callers that may use the
+/// rest of the API (any caller when `API_KEY` is unset) or hold the admin key.
+fn may_see_detail(req: &HttpRequest, config: &Config) -> bool {
+    let presented = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    config.api_key.is_empty()
+        || presented(API_KEY_HEADER) == Some(config.api_key.as_str())
+        || (!config.admin_api_key.is_empty() && presented(ADMIN_KEY_HEADER) == Some(config.admin_api_key.as_str()))
+}
+
+/// Round-trip to the database: a connection checkout plus a trivial query,
+/// cut off after `DB_CHECK_TIMEOUT`.
+pub(crate) async fn database_status(pool: &Pool) -> DatabaseStatus {
+    let started = Instant::now();
+    let probe = tokio::time::timeout(DB_CHECK_TIMEOUT, async {
+        let client = pool.get().await.map_err(|e| e.to_string())?;
+        StatsRepository::postgis_version(&client).await.map_err(|e| e.to_string())
     })
+    .await
+    .unwrap_or_else(|_| Err("timed out".into()));
+
+    match probe {
+        Ok(version) => DatabaseStatus {
+            reachable: true,
+            latency_ms: Some((started.elapsed().as_secs_f64() * 1e4).round() / 10.0),
+            postgis_version: Some(version),
+        },
+        Err(e) => {
+            log::warn!("Health check could not reach the database: {e}");
+            DatabaseStatus { reachable: false, latency_ms: None, postgis_version: None }
+        }
+    }
+}
+
+pub(crate) fn pool_status(pool: &Pool) -> PoolStatus {
+    let status = pool.status();
+    PoolStatus {
+        max_size: status.max_size,
+        size: status.size,
+        available: status.available,
+        waiting: status.waiting,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn detail_follows_the_api_and_admin_keys() {
+        let open = Config { api_key: String::new(), admin_api_key: String::new(), ..Config::from_env() };
+        assert!(may_see_detail(&TestRequest::get().to_http_request(), &open));
+
+        let keyed = Config { api_key: "k".into(), admin_api_key: "a".into(), ..Config::from_env() };
+        let with = |name: &str, value: &str| TestRequest::get().insert_header((name, value)).to_http_request();
+        assert!(!may_see_detail(&TestRequest::get().to_http_request(), &keyed));
+        assert!(may_see_detail(&with(API_KEY_HEADER, "k"), &keyed));
+        assert!(may_see_detail(&with(ADMIN_KEY_HEADER, "a"), &keyed));
+        assert!(!may_see_detail(&with(API_KEY_HEADER, "a"), &keyed));
+
+        let no_admin = Config { admin_api_key: String::new(), ..keyed };
+        assert!(!may_see_detail(&with(ADMIN_KEY_HEADER, ""), &no_admin));
+    }
 }
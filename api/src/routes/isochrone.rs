@@ -0,0 +1,80 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{CoordinateInfo, IncludedMeta, IsochronePayload, IsochroneQuery};
+use crate::repositories::PopulationRepository;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Population reachable within a travel time over the road network.
+#[utoipa::path(
+    get,
+    path = "/exposure/isochrone",
+    tag = "Risk Assessment",
+    summary = "Population within a travel-time isochrone",
+    description = "Asks the configured routing engine for the area reachable from `lat`/`lon` \
+        within `minutes` by `mode`, and sums the population of grid cells whose centre lies \
+        inside it. Road-network reachability reflects evacuation and response times far better \
+        than a straight-line radius.\n\n\
+        Needs a Valhalla server at `ROUTING_URL` and returns 404 when none is configured. A start \
+        point the engine cannot route from (e.g. no road nearby, or outside its road network) is \
+        a 400; an unreachable or failing engine is a 502.",
+    params(
+        ("lat" = f64, Query, description = "Latitude (-90 to 90)", example = 6.9271),
+        ("lon" = f64, Query, description = "Longitude (-180 to 180)", example = 79.8612),
+        ("minutes" = f64, Query, description = "Travel time in minutes (max: 120)", example = 30.0),
+        ("mode" = Option<String>, Query, description = "`driving` (default), `cycling` or `walking`", example = "driving")
+    ),
+    responses(
+        (status = 200, description = "Isochrone with the population inside it", body = IsochronePayload),
+        (status = 400, description = "Invalid coordinates, minutes or mode, start point not routable, or unsupported `include` section"),
+        (status = 404, description = "No routing engine is configured"),
+        (status = 502, description = "Routing engine unreachable or failed")
+    )
+)]
+pub(crate) async fn isochrone_exposure(
+    population: web::Data<dyn PopulationRepository>,
+    state: web::Data<AppState>,
+    query: web::Query<IsochroneQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    includes.ensure_supported(include::ISOCHRONE)?;
+    let routing = state.routing.as_ref().ok_or_else(|| {
+        AppError::NotFound("No routing engine is configured on this server".into())
+    })?;
+
+    let mut watch = includes.stopwatch();
+    let isochrone = routing.isochrone(query.lat, query.lon, query.minutes, query.mode).await?;
+    watch.mark("routing");
+    let bounds = geometry::polygon_bounds(&isochrone)
+        .map_err(|e| AppError::Upstream(format!("routing engine returned an invalid polygon: {e}")))?;
+
+    let (total, cells, area_km2) =
+        population.get_polygon_population(&isochrone.to_string(), bounds).await?;
+    watch.mark("population");
+
+    let sources = includes.has(Section::Sources).then(|| vec![sources::worldpop(), sources::valhalla()]);
+
+    Ok(ApiResponse::ok(IsochronePayload {
+        coordinate: CoordinateInfo { lat: query.lat, lon: query.lon },
+        minutes: query.minutes,
+        mode: query.mode,
+        isochrone,
+        bbox: bounds,
+        area_km2: round1(area_km2),
+        total_population: round1(total),
+        populated_cell_count: cells,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    }))
+}
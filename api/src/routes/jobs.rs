@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::API_PREFIX;
+use crate::errors::AppError;
+use crate::jobs::JobRequest;
+use crate::models::{JobPayload, JobSubmission};
+use crate::repositories::JobRepository;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+
+/// Submit an exposure or analyse request for background execution.
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    tag = "Jobs",
+    summary = "Submit a background analysis job",
+    description = "Queues an `exposure` or `analyse` request and returns immediately with `202 Accepted` \
+        and a `Location` header for `GET /jobs/{id}`. Use this for large-radius analyses that would \
+        otherwise hold an HTTP connection open until a client timeout.\n\n\
+        `params` takes the same fields as the synchronous endpoint's query string (e.g. `lat`, `lon`, \
+        `radius`, `decay`, `mode` for exposure; `lat`, `lon`, `boundaries` for analyse) and `include` \
+        the same comma-separated section list. Both are validated on submission, so an invalid job is \
+        rejected with 400 instead of failing later.\n\n\
+        Jobs are stored in the database and run by background workers with a longer query timeout \
+        than synchronous requests. Finished jobs are kept for 7 days.\n\n\
+        With `callback_url`, the finished job (as `GET /jobs/{id}` returns it) is POSTed to that URL. \
+        Requests carry `X-GeoPop-Timestamp` and `X-GeoPop-Signature: sha256=<hex>`, an HMAC-SHA256 of \
+        `\"{timestamp}.{body}\"` keyed with the server's webhook secret. Any non-2xx answer or network \
+        error is retried with exponential backoff (30 s doubling, up to 8 attempts); delivery state \
+        is reported under `callback`. Redirects are not followed, and the URL's host must resolve to \
+        public addresses only (and be listed in `WEBHOOK_ALLOWED_HOSTS` when the server sets it), \
+        both on submission and before each attempt.",
+    request_body(content = JobSubmission, description = "Analysis kind, parameters and optional sections"),
+    responses(
+        (status = 202, description = "Job queued", body = JobPayload),
+        (status = 400, description = "Invalid parameters, unsupported `include` section, or `callback_url` invalid / not enabled")
+    )
+)]
+pub(crate) async fn submit_job(
+    state: web::Data<AppState>,
+    body: web::Json<JobSubmission>,
+) -> ActixResult<HttpResponse> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if body.callback_url.is_some() && !state.jobs.callbacks_enabled() {
+        return Err(AppError::Validation(
+            "callback_url is not available: the server has no WEBHOOK_SECRET configured".into(),
+        )
+        .into());
+    }
+    if let Some(url) = &body.callback_url {
+        state.jobs.webhooks().check(url).await.map_err(|e| {
+            AppError::Validation(format!("callback_url rejected: {e}"))
+        })?;
+    }
+    JobRequest::parse(body.kind, &body.params, body.include.as_deref(), state.limits())?;
+
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let job = JobRepository::create(
+        &client,
+        body.kind,
+        &body.params,
+        body.include.as_deref(),
+        body.callback_url.as_deref(),
+    ).await?;
+    state.jobs.wake();
+
+    let location = format!("{API_PREFIX}/jobs/{}", job.id);
+    Ok(ApiResponse::accepted(job, &location))
+}
+
+/// Status and, once finished, result of a background job.
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "Jobs",
+    summary = "Get job status and result",
+    description = "Returns the job's status (`queued`, `running`, `succeeded` or `failed`). A succeeded \
+        job carries the endpoint payload in `result`, exactly as the synchronous call would have \
+        returned it; a failed job carries the reason in `error`.",
+    params(
+        ("id" = Uuid, Path, description = "Job identifier returned on submission")
+    ),
+    responses(
+        (status = 200, description = "Job state", body = JobPayload),
+        (status = 404, description = "Unknown job (or purged after 7 days)")
+    )
+)]
+pub(crate) async fn get_job(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let job = JobRepository::get(&client, *id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Job {id} not found")))?;
+    Ok(ApiResponse::ok(job))
+}
@@ -1,7 +1,97 @@
+use actix_web::web;
+
+use crate::config::Features;
+
+pub(crate) mod admin;
 pub(crate) mod analyse;
+pub(crate) mod buffer;
+pub(crate) mod cell;
+pub(crate) mod compare;
 pub(crate) mod country;
+pub(crate) mod cyclone;
+pub(crate) mod distance;
+pub(crate) mod events;
+pub(crate) mod export;
 pub(crate) mod exposure;
+pub(crate) mod flood;
 pub(crate) mod geocoding;
+pub(crate) mod graphql;
 pub(crate) mod health;
+pub(crate) mod isochrone;
+pub(crate) mod jobs;
+pub(crate) mod monitors;
 pub(crate) mod population;
 pub(crate) mod root;
+pub(crate) mod route_sample;
+pub(crate) mod shakemap;
+pub(crate) mod tagging;
+pub(crate) mod tiles;
+pub(crate) mod v2;
+
+/// Registers the API routes of every enabled feature group; mounted under
+/// `API_PREFIX`.
+pub(crate) fn configure(cfg: &mut web::ServiceConfig, features: &Features) {
+    cfg.route("/health", web::get().to(health::health))
+        .route("/population", web::get().to(population::get_population))
+        .route("/population/batch", web::post().to(population::batch_population))
+        .route("/population/rank", web::get().to(population::population_rank))
+        .route("/population/change", web::get().to(population::population_change))
+        .route("/population/grid", web::get().to(population::population_grid))
+        .route("/population/h3/{index}", web::get().to(population::h3_population))
+        .route("/population/s2/{cell}", web::get().to(population::s2_population))
+        .route("/cell/{cell_id}", web::get().to(cell::cell))
+        .route("/reverse", web::get().to(geocoding::reverse_geocode))
+        .route("/admin", web::get().to(geocoding::admin_hierarchy))
+        .route("/geocoding/nearby-countries", web::get().to(geocoding::nearby_countries))
+        .route("/geocoding/nearby-cities", web::get().to(geocoding::nearby_cities))
+        .route("/geocoding/land-check", web::get().to(geocoding::land_check))
+        .route("/cities/search", web::get().to(geocoding::search_cities))
+        .route("/facilities", web::get().to(geocoding::facilities_within))
+        .route("/facilities/nearest", web::get().to(geocoding::nearest_facilities))
+        .route("/airports/nearest", web::get().to(geocoding::nearest_airports))
+        .route("/distance", web::get().to(distance::distance))
+        .route("/country", web::get().to(country::country_lookup))
+        .route("/country/iso2/{code}", web::get().to(country::country_by_iso2))
+        .route("/country/numeric/{code}", web::get().to(country::country_by_numeric))
+        .route("/country/{iso3}", web::get().to(country::country_by_iso3))
+        .route("/country/{iso3}/population", web::get().to(country::country_population))
+        .route("/countries", web::get().to(country::countries_by_continent))
+        .route("/continents", web::get().to(country::continents))
+        .route("/eez", web::get().to(country::eez_lookup))
+        .route("/export/geopackage", web::get().to(export::export_geopackage))
+        .route("/tag/stream", web::post().to(tagging::tag_stream))
+        .route("/graphql", web::post().to(graphql::graphql))
+        .route("/graphql", web::get().to(graphql::graphiql));
+    if features.admin {
+        cfg.route("/admin/stats", web::get().to(admin::admin_stats))
+            .route("/admin/cache/flush", web::post().to(admin::flush_caches));
+    }
+    if features.exposure {
+        cfg.route("/exposure/places", web::get().to(exposure::exposure_places))
+            .route("/exposure/admin", web::get().to(exposure::exposure_admin))
+            .route("/exposure/cyclone", web::post().to(cyclone::cyclone_exposure))
+            .route("/exposure/buffer", web::post().to(buffer::buffer_exposure))
+            .route("/exposure/flood", web::post().to(flood::flood_exposure))
+            .route("/exposure/isochrone", web::get().to(isochrone::isochrone_exposure))
+            .route("/exposure/shakemap", web::post().to(shakemap::shakemap_exposure))
+            .route("/exposure", web::get().to(exposure::exposure))
+            .route("/compare", web::get().to(compare::compare))
+            .route("/route/sample", web::get().to(route_sample::route_sample))
+            .route("/analyse/stream", web::get().to(analyse::analyse_stream))
+            .route("/analyse/report", web::get().to(analyse::analyse_report))
+            .route("/analyse", web::get().to(analyse::analyse))
+            .route("/jobs", web::post().to(jobs::submit_job))
+            .route("/jobs/{id}", web::get().to(jobs::get_job))
+            .route("/events", web::get().to(events::list_events))
+            .route("/monitors", web::post().to(monitors::create_monitor))
+            .route("/monitors", web::get().to(monitors::list_monitors))
+            .route("/monitors/{id}", web::get().to(monitors::get_monitor))
+            .route("/monitors/{id}", web::put().to(monitors::update_monitor))
+            .route("/monitors/{id}", web::delete().to(monitors::delete_monitor))
+            .route("/monitors/{id}/snapshots", web::get().to(monitors::monitor_snapshots))
+            .route("/monitors/{id}/alerts", web::get().to(monitors::monitor_alerts));
+    }
+    if features.tiles {
+        cfg.route("/tiles/countries/{z}/{x}/{y}.mvt", web::get().to(tiles::country_tile));
+    }
+}
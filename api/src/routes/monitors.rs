@@ -0,0 +1,254 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::API_PREFIX;
+use crate::errors::AppError;
+use crate::geometry;
+use crate::models::{
+    MonitorAlertsPayload, MonitorListPayload, MonitorPayload, MonitorRequest, MonitorSnapshotsPayload,
+    SnapshotQuery,
+};
+use crate::repositories::{AlertRepository, MonitorArea, MonitorRepository};
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use crate::validation::MAX_MONITOR_SPAN_KM;
+
+/// Validates a monitor request and returns the area it describes.
+async fn area(body: &MonitorRequest, state: &AppState) -> Result<MonitorArea, AppError> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if body.webhook_url.is_some() && !state.jobs.callbacks_enabled() {
+        return Err(AppError::Validation(
+            "webhook_url is not available: the server has no WEBHOOK_SECRET configured".into(),
+        ));
+    }
+    if let Some(url) = &body.webhook_url {
+        state.jobs.webhooks().check(url).await.map_err(|e| {
+            AppError::Validation(format!("webhook_url rejected: {e}"))
+        })?;
+    }
+    let limits = state.limits();
+    match (&body.polygon, body.lat, body.lon) {
+        (Some(polygon), None, None) if body.radius.is_none() => {
+            let bounds = geometry::polygon_bounds(polygon)
+                .map_err(|e| AppError::Validation(format!("Invalid polygon: {e}")))?;
+            if geometry::bbox_span_km(&bounds) > MAX_MONITOR_SPAN_KM {
+                return Err(AppError::Validation(format!(
+                    "Polygon bounding box must span at most {MAX_MONITOR_SPAN_KM} km per side"
+                )));
+            }
+            Ok(MonitorArea::Polygon(polygon.clone()))
+        }
+        (None, Some(lat), Some(lon)) => {
+            let radius_km = body.radius.unwrap_or(1.0);
+            limits.check_radius(radius_km)?;
+            Ok(MonitorArea::Circle { lat, lon, radius_km })
+        }
+        _ => Err(AppError::Validation(
+            "Provide either `polygon` or `lat` and `lon` (with optional `radius`), not both".into(),
+        )),
+    }
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Monitor {id} not found"))
+}
+
+/// Register a location whose exposure is tracked over time.
+#[utoipa::path(
+    post,
+    path = "/monitors",
+    tag = "Monitors",
+    summary = "Register a monitored location",
+    description = "Registers a point of interest — a GeoJSON `polygon` (bounding box at most 1000 km \
+        per side), or `lat`/`lon` with a `radius` — whose population exposure the server recomputes \
+        every `interval_hours` (default 24) and stores as a snapshot. The first snapshot is taken \
+        shortly after registration. Circles are summed as by /exposure, polygons by cell centre.\n\n\
+        Snapshots are taken by the background workers of instances with `JOB_WORKERS` above zero.\n\n\
+        Up to 10 `thresholds` raise alerts, listed by `GET /monitors/{id}/alerts`: `population` when a \
+        snapshot first exceeds `above`, `change` when a snapshot differs from the previous one by more \
+        than `above`, and `event_exposure` when a GDACS event recorded inside the area has more than \
+        `above` people within `radius_km` (default 50) of it. With `webhook_url`, each alert is POSTed \
+        there, signed, checked and retried like job callbacks with `X-GeoPop-Event: monitor.alert`.",
+    request_body(content = MonitorRequest, description = "Monitored area, label, snapshot interval and alert thresholds"),
+    responses(
+        (status = 201, description = "Monitor registered", body = MonitorPayload),
+        (status = 400, description = "Invalid name, area, interval or thresholds, both or neither area forms given, or `webhook_url` invalid / not enabled")
+    )
+)]
+pub(crate) async fn create_monitor(
+    state: web::Data<AppState>,
+    body: web::Json<MonitorRequest>,
+) -> ActixResult<HttpResponse> {
+    let area = area(&body, &state).await?;
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let monitor = MonitorRepository::create(&client, &body, &area).await?;
+    let location = format!("{API_PREFIX}/monitors/{}", monitor.id);
+    Ok(ApiResponse::created(monitor, &location))
+}
+
+/// Every registered monitor with its latest snapshot.
+#[utoipa::path(
+    get,
+    path = "/monitors",
+    tag = "Monitors",
+    summary = "List monitored locations",
+    description = "Lists every registered monitor, oldest first, with the population of its latest \
+        snapshot in `last_population`.",
+    responses(
+        (status = 200, description = "Registered monitors", body = MonitorListPayload)
+    )
+)]
+pub(crate) async fn list_monitors(state: web::Data<AppState>) -> ActixResult<HttpResponse> {
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let monitors = MonitorRepository::list(&client).await?;
+    Ok(ApiResponse::ok(MonitorListPayload { count: monitors.len(), monitors }))
+}
+
+/// One monitor with its latest snapshot.
+#[utoipa::path(
+    get,
+    path = "/monitors/{id}",
+    tag = "Monitors",
+    summary = "Get a monitored location",
+    params(
+        ("id" = Uuid, Path, description = "Monitor identifier returned on registration")
+    ),
+    responses(
+        (status = 200, description = "Monitor", body = MonitorPayload),
+        (status = 404, description = "Unknown monitor")
+    )
+)]
+pub(crate) async fn get_monitor(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let monitor = MonitorRepository::get(&client, *id).await?.ok_or_else(|| not_found(*id))?;
+    Ok(ApiResponse::ok(monitor))
+}
+
+/// Replace a monitor's name, area, interval and thresholds.
+#[utoipa::path(
+    put,
+    path = "/monitors/{id}",
+    tag = "Monitors",
+    summary = "Update a monitored location",
+    description = "Replaces the monitor's settings with the same fields as registration. Its snapshot \
+        history is kept; when the area changes, a new snapshot is taken straight away, otherwise the \
+        next one follows the new interval.",
+    params(
+        ("id" = Uuid, Path, description = "Monitor identifier returned on registration")
+    ),
+    request_body(content = MonitorRequest, description = "Monitored area, label, snapshot interval and alert thresholds"),
+    responses(
+        (status = 200, description = "Updated monitor", body = MonitorPayload),
+        (status = 400, description = "Invalid name, area, interval or thresholds, both or neither area forms given, or `webhook_url` invalid / not enabled"),
+        (status = 404, description = "Unknown monitor")
+    )
+)]
+pub(crate) async fn update_monitor(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    body: web::Json<MonitorRequest>,
+) -> ActixResult<HttpResponse> {
+    let area = area(&body, &state).await?;
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    let monitor = MonitorRepository::update(&client, *id, &body, &area)
+        .await?
+        .ok_or_else(|| not_found(*id))?;
+    Ok(ApiResponse::ok(monitor))
+}
+
+/// Delete a monitor with its snapshots and alerts.
+#[utoipa::path(
+    delete,
+    path = "/monitors/{id}",
+    tag = "Monitors",
+    summary = "Delete a monitored location",
+    description = "Stops monitoring the location and deletes its snapshot and alert history.",
+    params(
+        ("id" = Uuid, Path, description = "Monitor identifier returned on registration")
+    ),
+    responses(
+        (status = 204, description = "Monitor deleted"),
+        (status = 404, description = "Unknown monitor")
+    )
+)]
+pub(crate) async fn delete_monitor(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> ActixResult<HttpResponse> {
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    if !MonitorRepository::delete(&client, *id).await? {
+        return Err(not_found(*id).into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Exposure history of a monitor.
+#[utoipa::path(
+    get,
+    path = "/monitors/{id}/snapshots",
+    tag = "Monitors",
+    summary = "Exposure snapshots of a monitored location",
+    description = "Returns the monitor's snapshots, most recent first, each with the population in \
+        its area and the `change` since the snapshot before.",
+    params(
+        ("id" = Uuid, Path, description = "Monitor identifier returned on registration"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of snapshots (default: 30, max: 100)", example = 30)
+    ),
+    responses(
+        (status = 200, description = "Snapshots", body = MonitorSnapshotsPayload),
+        (status = 400, description = "Invalid limit"),
+        (status = 404, description = "Unknown monitor")
+    )
+)]
+pub(crate) async fn monitor_snapshots(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    query: web::Query<SnapshotQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    MonitorRepository::get(&client, *id).await?.ok_or_else(|| not_found(*id))?;
+    let snapshots = MonitorRepository::snapshots(&client, *id, query.limit).await?;
+    Ok(ApiResponse::ok(MonitorSnapshotsPayload { monitor_id: *id, snapshots }))
+}
+
+/// Alerts raised by a monitor's thresholds.
+#[utoipa::path(
+    get,
+    path = "/monitors/{id}/alerts",
+    tag = "Monitors",
+    summary = "Alerts of a monitored location",
+    description = "Returns the alerts raised by the monitor's thresholds, most recent first, each with \
+        the breached threshold, the `value` that breached it, the GDACS `event` for `event_exposure` \
+        thresholds, and the webhook `delivery` state when the monitor has a `webhook_url`.",
+    params(
+        ("id" = Uuid, Path, description = "Monitor identifier returned on registration"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of alerts (default: 30, max: 100)", example = 30)
+    ),
+    responses(
+        (status = 200, description = "Alerts", body = MonitorAlertsPayload),
+        (status = 400, description = "Invalid limit"),
+        (status = 404, description = "Unknown monitor")
+    )
+)]
+pub(crate) async fn monitor_alerts(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    query: web::Query<SnapshotQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let client = state.pool.get().await.map_err(AppError::from)?;
+    MonitorRepository::get(&client, *id).await?.ok_or_else(|| not_found(*id))?;
+    let alerts = AlertRepository::list(&client, *id, query.limit).await?;
+    Ok(ApiResponse::ok(MonitorAlertsPayload { monitor_id: *id, alerts }))
+}
@@ -1,15 +1,24 @@
+use std::collections::HashMap;
+
 use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
 use validator::Validate;
 
 use crate::errors::AppError;
+use crate::hex;
+use crate::include::{self, sources, Includes, Section};
+use crate::kml::{self, KmlDocument};
 use crate::models::{
-    BatchPayload, BatchQuery, CoordinateInfo, PointPayload,
-    PopulationGridPayload, PopulationQuery,
+    Aggregation, BatchPayload, BatchQuery, BoundaryQuery, CellChange, CellIndexQuery, CoordinateInfo, Dataset, ExportFormat, ExportQuery,
+    DensityRankPayload, GridCell, H3CellPayload, HexGridPayload, IncludedMeta, PointPayload,
+    PointQuery, PopulationBboxPayload, PopulationChangePayload, PopulationChangeQuery,
+    PopulationGridPayload, PopulationGridQuery, PopulationQuery, S2CellPayload, S2GridPayload,
+    DEFAULT_H3_RESOLUTION, DEFAULT_S2_LEVEL,
 };
-use crate::repositories::PopulationRepository;
+use crate::repositories::{PlaceOptions, PopulationRepository, Repositories};
 use crate::response::ApiResponse;
-use crate::validation::validate_batch_size;
+use crate::s2;
+use crate::state::AppState;
+use crate::validation::parse_bbox;
 
 /// Look up population at a coordinate, optionally within a radius to get individual grid cells.
 #[utoipa::path(
@@ -18,61 +27,662 @@ use crate::validation::validate_batch_size;
     tag = "Population",
     summary = "Population lookup",
     description = "Without `radius`: returns the estimated population for the single 1 km² WorldPop \
-        grid cell at the given coordinate.\n\n\
+        grid cell at the given coordinate, with its GHS-SMOD `settlement_class` (`urban_centre`, \
+        `urban_cluster` or `rural`) once the optional settlement layer is loaded \
+        (`make ingest-settlement`), its GHSL built-up surface `built_up_m2` once that layer is \
+        loaded (`make ingest-built-up`), its VIIRS `night_light_radiance` in nW/cm²/sr once \
+        that layer is loaded (`make ingest-night-lights`), and its dominant ESA WorldCover \
+        `land_cover` class once that layer is loaded (`make ingest-land-cover`).\n\n\
         With `radius` (max 10 km): returns all non-empty 1 km² grid cells within the circle, \
         including each cell's centre point and geographic bounds — ideal for map visualisation. \
         Cells are sorted by population descending.\n\n\
-        Data source: WorldPop 2025 Unconstrained 1 km resolution.",
+        Optional sections via `include=`: `uncertainty` adds WorldPop 95% credible intervals \
+        (per cell and aggregated) where uncertainty rasters have been ingested; `sources` and \
+        `timings` add dataset attribution and a server-side timing breakdown.\n\n\
+        Data source: WorldPop 2025 1 km resolution, Unconstrained by default. \
+        `dataset=constrained` reads the Constrained grid instead, which places people only in \
+        cells with mapped buildings; it must be loaded with `make ingest-worldpop-constrained` \
+        (404 otherwise) and has no uncertainty intervals.\n\n\
+        `year` picks an earlier grid year where one has been loaded (default: the latest, \
+        echoed as `year`); uncertainty intervals exist for the latest year only.\n\n\
+        With `radius`, `aggregation=h3` sums the cells into H3 hexagons of `resolution` \
+        (default 7) by the hexagon each cell centre falls in, returning each hexagon's index, \
+        centre, population and boundary instead of the grid cells. `aggregation=s2` does the \
+        same with Google S2 cells of `level` (default 12), identified by token and by the \
+        signed 64-bit id BigQuery uses.\n\n\
+        With `radius`, `format=kml` returns the circle, its grid cells shaded by population and \
+        the nearest 20 named places as a KML document to open in Google Earth.",
     params(
         ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
         ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
-        ("radius" = Option<f64>, Query, description = "Optional search radius in km. When provided, returns all non-empty grid cells within the circle (max: 10 km).", example = 5.0)
+        ("radius" = Option<f64>, Query, description = "Optional search radius in km. When provided, returns all non-empty grid cells within the circle (max: 10 km).", example = 5.0),
+        ("dataset" = Option<Dataset>, Query, description = "WorldPop grid to read: `unconstrained` (default) or `constrained`", example = "constrained"),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020),
+        ("aggregation" = Option<Aggregation>, Query, description = "With `radius`: `grid` cells (default), `h3` hexagons or `s2` cells", example = "h3"),
+        ("resolution" = Option<u8>, Query, description = "H3 resolution for `aggregation=h3` (0–15, default: 7)", example = 7),
+        ("level" = Option<u8>, Query, description = "S2 level for `aggregation=s2` (0–30, default: 12)", example = 12),
+        ("format" = Option<ExportFormat>, Query, description = "With `radius`: `json` (default) or `kml`", example = "kml"),
+        ("boundaries" = Option<i32>, Query, description = "With `format=kml`: boundary dataset vintage for place country names (default: current)", example = 2023)
     ),
     responses(
-        (status = 200, description = "Population data — single cell (no radius) or grid cells (with radius)"),
-        (status = 400, description = "Invalid coordinates or radius out of range (0–10 km)")
+        (status = 200, description = "Population data — single cell (no radius), grid cells (with radius), hexagons (`aggregation=h3`), S2 cells (`aggregation=s2`) or a KML document (`format=kml`)"),
+        (status = 400, description = "Invalid coordinates, radius out of range (0–10 km), `include=uncertainty` with `dataset=constrained`, an earlier `year` or `aggregation=h3|s2`, `aggregation=h3|s2` or `format=kml` without `radius`, `format=shapefile`, `format=kml` with `aggregation=h3|s2`, or unsupported `include` section"),
+        (status = 404, description = "`dataset=constrained` requested but that grid is not loaded, no grid loaded for `year`, or unknown boundary vintage")
     )
 )]
 pub(crate) async fn get_population(
-    pool: web::Data<Pool>,
+    repos: web::Data<Repositories>,
     query: web::Query<PopulationQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+    export: web::Query<ExportQuery>,
+    includes: Includes,
 ) -> ActixResult<HttpResponse> {
     query.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    includes.ensure_supported(include::POPULATION)?;
+    let kml = match export.format {
+        ExportFormat::Json => false,
+        ExportFormat::Kml => true,
+        ExportFormat::Shapefile => {
+            return Err(AppError::Validation(
+                "format=shapefile is only available on /exposure".into(),
+            )
+            .into())
+        }
+    };
+    if query.dataset == Some(Dataset::Constrained) && includes.has(Section::Uncertainty) {
+        return Err(AppError::Validation(
+            "include=uncertainty is only available for dataset=unconstrained".into(),
+        )
+        .into());
+    }
+    let summed = query.aggregation.filter(|a| *a != Aggregation::Grid);
+    if let Some(aggregation) = summed {
+        if query.radius.is_none() {
+            return Err(AppError::Validation(format!(
+                "aggregation={} requires radius",
+                aggregation.as_str()
+            ))
+            .into());
+        }
+        if includes.has(Section::Uncertainty) {
+            return Err(AppError::Validation(format!(
+                "include=uncertainty is not available with aggregation={}",
+                aggregation.as_str()
+            ))
+            .into());
+        }
+    }
+    if kml && (query.radius.is_none() || summed.is_some()) {
+        return Err(AppError::Validation(
+            "format=kml requires radius and grid cells (no aggregation)".into(),
+        )
+        .into());
+    }
+    let (year, repo) = repos.population_for(query.dataset).for_year(query.year).await?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
+    let mut watch = includes.stopwatch();
 
     match query.radius {
+        Some(radius_km) if summed.is_some() => {
+            let cells = repo.get_grid_cells(query.lat, query.lon, radius_km).await?;
+            let coordinate = Some(CoordinateInfo { lat: query.lat, lon: query.lon });
+            if summed == Some(Aggregation::S2) {
+                let level = query.level.unwrap_or(DEFAULT_S2_LEVEL);
+                let s2_cells = s2::aggregate(&cells, level);
+                return Ok(ApiResponse::ok(S2GridPayload {
+                    coordinate,
+                    radius_km: Some(radius_km),
+                    bbox: None,
+                    level,
+                    total_population: summed_total(&cells),
+                    cell_count: s2_cells.len(),
+                    year: Some(year),
+                    cells: s2_cells,
+                }));
+            }
+            let resolution = query.resolution.unwrap_or(DEFAULT_H3_RESOLUTION);
+            let hexes = hex::aggregate(&cells, hex::resolution(resolution));
+            Ok(ApiResponse::ok(HexGridPayload {
+                coordinate,
+                radius_km: Some(radius_km),
+                bbox: None,
+                resolution,
+                total_population: summed_total(&cells),
+                cell_count: hexes.len(),
+                year: Some(year),
+                cells: hexes,
+            }))
+        }
+        Some(radius_km) if kml => {
+            let (lat, lon) = (query.lat, query.lon);
+            let cells = repo.get_grid_cells(lat, lon, radius_km).await?;
+            let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+            let places = repos
+                .geocoding
+                .get_exposed_places(lat, lon, radius_km, PlaceOptions::default(), include::MAX_INCLUDED_PLACES, 0, vintage)
+                .await?;
+            let summary = [
+                ("total_population", summed_total(&cells).to_string()),
+                ("cell_count", cells.len().to_string()),
+                ("year", year.to_string()),
+            ];
+            let body = KmlDocument::new(format!("GeoPop population {lat:.4}, {lon:.4}"), lat, lon, radius_km)
+                .cells(cells)
+                .places(places)
+                .render(&summary);
+            Ok(HttpResponse::Ok()
+                .content_type(kml::CONTENT_TYPE)
+                .insert_header((
+                    "Content-Disposition",
+                    format!("attachment; filename=\"geopop-population-{lat:.4}_{lon:.4}.kml\""),
+                ))
+                .body(body))
+        }
         Some(radius_km) => {
-            let cells = PopulationRepository::get_grid_cells(
-                &client, query.lat, query.lon, radius_km,
-            ).await?;
+            let mut cells = repo.get_grid_cells(query.lat, query.lon, radius_km).await?;
+            watch.mark("population");
             let total: f64 = cells.iter().map(|c| c.population as f64).sum();
+            let uncertainty = if includes.has(Section::Uncertainty) {
+                let interval = repo.attach_grid_uncertainty(&mut cells).await?;
+                watch.mark("uncertainty");
+                Some(interval)
+            } else {
+                None
+            };
 
             Ok(ApiResponse::ok(PopulationGridPayload {
                 coordinate: CoordinateInfo { lat: query.lat, lon: query.lon },
                 radius_km,
                 total_population: (total * 10.0).round() / 10.0,
                 cell_count: cells.len(),
+                year,
+                uncertainty,
                 cells,
+                meta: population_meta(includes, watch, query.dataset, year),
             }))
         }
         None => {
-            let population = PopulationRepository::get_population(
-                &client, query.lat, query.lon,
-            ).await?;
+            let (population, settlement_class, built_up_m2, night_light_radiance, land_cover) = tokio::try_join!(
+                repo.get_population(query.lat, query.lon),
+                repo.get_cell_settlement(query.lat, query.lon),
+                repo.get_cell_built_up(query.lat, query.lon),
+                repo.get_cell_night_lights(query.lat, query.lon),
+                repo.get_cell_land_cover(query.lat, query.lon),
+            )?;
+            watch.mark("population");
+            let uncertainty = if includes.has(Section::Uncertainty) {
+                let interval = repo.get_cell_uncertainty(query.lat, query.lon).await?;
+                watch.mark("uncertainty");
+                interval
+            } else {
+                None
+            };
+            let mut meta = population_meta(includes, watch, query.dataset, year);
+            if let Some(list) = &mut meta.sources {
+                if settlement_class.is_some() {
+                    list.push(sources::ghsl_smod());
+                }
+                if built_up_m2.is_some() {
+                    list.push(sources::ghsl_built_up());
+                }
+                if night_light_radiance.is_some() {
+                    list.push(sources::viirs_night_lights());
+                }
+                if land_cover.is_some() {
+                    list.push(sources::esa_worldcover());
+                }
+            }
 
             Ok(ApiResponse::ok(PointPayload {
                 lat: query.lat,
                 lon: query.lon,
                 population,
                 resolution_km: 1.0,
+                year,
+                uncertainty,
+                settlement_class,
+                built_up_m2,
+                night_light_radiance,
+                land_cover,
+                meta,
             }))
         }
     }
 }
 
+/// Most populated cells one `aggregation=h3|s2` viewport is summed from.
+const MAX_SUMMED_SOURCE_CELLS: i64 = 250_000;
+
+fn summed_total(cells: &[GridCell]) -> f64 {
+    let total: f64 = cells.iter().map(|c| c.population as f64).sum();
+    (total * 10.0).round() / 10.0
+}
+
+fn population_meta(
+    includes: Includes,
+    watch: include::Stopwatch,
+    dataset: Option<Dataset>,
+    year: i32,
+) -> IncludedMeta {
+    let sources = includes.has(Section::Sources).then(|| {
+        let mut list = vec![sources::worldpop_grid(dataset, year)];
+        if includes.has(Section::Uncertainty) {
+            list.push(sources::worldpop_uncertainty());
+        }
+        list
+    });
+    IncludedMeta { sources, timings: watch.finish() }
+}
+
+/// Population change between two grid years at a coordinate or within a radius.
+#[utoipa::path(
+    get,
+    path = "/population/change",
+    tag = "Population",
+    summary = "Population change between grid years",
+    description = "Compares two loaded WorldPop grid years and returns the absolute and \
+        percentage change from `from_year` to `to_year`.\n\n\
+        Without `radius`: compares the single 1 km² grid cell at the coordinate. With `radius` \
+        (max 10 km): compares the total of every grid cell within the circle and lists each \
+        cell's change with its bounds, largest absolute change first. Cells populated in only \
+        one of the years count as empty in the other.\n\n\
+        `percent_change` is null when the `from_year` population is zero. Both years must be \
+        loaded for `dataset` (see `make ingest-worldpop WORLDPOP_YEAR=...`).",
+    params(
+        ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180),
+        ("radius" = Option<f64>, Query, description = "Optional search radius in km; compares every grid cell within the circle (max: 10 km)", example = 5.0),
+        ("from_year" = i32, Query, description = "Grid year to compare from", example = 2020),
+        ("to_year" = i32, Query, description = "Grid year to compare to", example = 2025),
+        ("dataset" = Option<Dataset>, Query, description = "WorldPop grid to read: `unconstrained` (default) or `constrained`", example = "unconstrained")
+    ),
+    responses(
+        (status = 200, description = "Population change between the two years", body = PopulationChangePayload),
+        (status = 400, description = "Invalid coordinates, radius out of range (0–10 km), years out of range or equal"),
+        (status = 404, description = "No grid loaded for `from_year` or `to_year`")
+    )
+)]
+pub(crate) async fn population_change(
+    repos: web::Data<Repositories>,
+    query: web::Query<PopulationChangeQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    if query.from_year == query.to_year {
+        return Err(AppError::Validation("from_year and to_year must differ".into()).into());
+    }
+    let population = repos.population_for(query.dataset);
+    let (from_year, from) = population.for_year(Some(query.from_year)).await?;
+    let (to_year, to) = population.for_year(Some(query.to_year)).await?;
+
+    let (from_total, to_total, cells) = match query.radius {
+        Some(radius_km) => {
+            let before = from.get_grid_cells(query.lat, query.lon, radius_km).await?;
+            let after = to.get_grid_cells(query.lat, query.lon, radius_km).await?;
+            let from_total: f64 = before.iter().map(|c| c.population as f64).sum();
+            let to_total: f64 = after.iter().map(|c| c.population as f64).sum();
+            (from_total, to_total, Some(cell_changes(before, after)))
+        }
+        None => {
+            let before = from.get_population(query.lat, query.lon).await?;
+            let after = to.get_population(query.lat, query.lon).await?;
+            (before as f64, after as f64, None)
+        }
+    };
+
+    let round1 = |v: f64| (v * 10.0).round() / 10.0;
+    Ok(ApiResponse::ok(PopulationChangePayload {
+        coordinate: CoordinateInfo { lat: query.lat, lon: query.lon },
+        radius_km: query.radius,
+        from_year,
+        to_year,
+        from_population: round1(from_total),
+        to_population: round1(to_total),
+        change: round1(to_total - from_total),
+        percent_change: percent_change(from_total, to_total),
+        cells,
+    }))
+}
+
+fn percent_change(from: f64, to: f64) -> Option<f64> {
+    (from > 0.0).then(|| ((to - from) / from * 1000.0).round() / 10.0)
+}
+
+/// Pair up the cells of two grid years by position; a cell missing from one
+/// year was empty then.
+fn cell_changes(before: Vec<GridCell>, after: Vec<GridCell>) -> Vec<CellChange> {
+    let key = |c: &GridCell| (c.lat.to_bits(), c.lon.to_bits());
+    let mut merged: HashMap<(u64, u64), CellChange> = HashMap::new();
+    for (cell, is_after) in before.into_iter().map(|c| (c, false)).chain(after.into_iter().map(|c| (c, true))) {
+        let entry = merged.entry(key(&cell)).or_insert_with(|| CellChange {
+            lat: cell.lat,
+            lon: cell.lon,
+            from_population: 0.0,
+            to_population: 0.0,
+            change: 0.0,
+            percent_change: None,
+            bounds: cell.bounds.clone(),
+        });
+        if is_after {
+            entry.to_population = cell.population;
+        } else {
+            entry.from_population = cell.population;
+        }
+    }
+
+    let mut cells: Vec<CellChange> = merged
+        .into_values()
+        .map(|mut c| {
+            c.change = c.to_population - c.from_population;
+            c.percent_change = percent_change(c.from_population as f64, c.to_population as f64);
+            c
+        })
+        .collect();
+    cells.sort_by(|a, b| b.change.abs().total_cmp(&a.change.abs()));
+    cells
+}
+
+/// Return the populated grid cells of a map viewport.
+#[utoipa::path(
+    get,
+    path = "/population/grid",
+    tag = "Population",
+    summary = "Grid cells by bounding box",
+    description = "Returns every populated 1 km² WorldPop grid cell overlapping the `bbox` \
+        viewport (each side at most 10°), with centre point and geographic bounds for direct \
+        map rendering. Cells are sorted by population descending.\n\n\
+        When the viewport holds more than `max_cells` populated cells, cells are summed into \
+        square blocks instead: `aggregation` reports how many cells make up each block side \
+        and `cell_size_deg` its size. Blocks are aligned to the global grid, so they do not \
+        shift as the viewport pans.\n\n\
+        `aggregation=h3` sums the cells into H3 hexagons of `resolution` (default 7) by the \
+        hexagon each cell centre falls in, returning a `HexGridPayload`; `aggregation=s2` sums \
+        them into Google S2 cells of `level` (default 12), returning an `S2GridPayload`. \
+        Hexagons and S2 cells on the viewport edge only count the cells inside it. The \
+        viewport may hold at most 250,000 populated cells and yield at most `max_cells` \
+        hexagons or S2 cells; zoom in or pick a coarser `resolution` or `level` otherwise.",
+    params(
+        ("bbox" = String, Query, description = "Viewport as `min_lon,min_lat,max_lon,max_lat` (each side at most 10°)", example = "79.80,6.85,79.95,7.00"),
+        ("max_cells" = Option<i64>, Query, description = "Most cells to return before aggregating into blocks (default: 5000, max: 20000)", example = 5000),
+        ("aggregation" = Option<Aggregation>, Query, description = "`grid` cells and blocks (default), `h3` hexagons or `s2` cells", example = "h3"),
+        ("resolution" = Option<u8>, Query, description = "H3 resolution for `aggregation=h3` (0–15, default: 7)", example = 7),
+        ("level" = Option<u8>, Query, description = "S2 level for `aggregation=s2` (0–30, default: 12)", example = 12)
+    ),
+    responses(
+        (status = 200, description = "Populated cells (or blocks) within the viewport", body = PopulationBboxPayload),
+        (status = 400, description = "Malformed or oversized `bbox`, `max_cells` out of range, or too many cells, hexagons or S2 cells for `aggregation=h3|s2`")
+    )
+)]
+pub(crate) async fn population_grid(
+    repo: web::Data<dyn PopulationRepository>,
+    query: web::Query<PopulationGridQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let bbox = parse_bbox(&query.bbox)
+        .ok_or_else(|| AppError::Validation("bbox must be min_lon,min_lat,max_lon,max_lat".into()))?;
+
+    let populated = repo.count_bbox_cells(bbox).await?;
+    if let Some(summed @ (Aggregation::H3 | Aggregation::S2)) = query.aggregation {
+        if populated > MAX_SUMMED_SOURCE_CELLS {
+            return Err(AppError::Validation(format!(
+                "viewport holds {populated} populated cells; aggregation={} sums at most \
+                 {MAX_SUMMED_SOURCE_CELLS}, zoom in",
+                summed.as_str()
+            ))
+            .into());
+        }
+        let cells = repo.get_bbox_grid_cells(bbox, 1).await?;
+        return match summed {
+            Aggregation::S2 => s2_bbox(&query, bbox, &cells),
+            _ => hex_bbox(&query, bbox, &cells),
+        };
+    }
+
+    // Start from the block size that would fit if populated cells were dense,
+    // and grow it while sparse blocks still exceed the cap.
+    let mut aggregation = if populated > query.max_cells {
+        (populated as f64 / query.max_cells as f64).sqrt().ceil() as i32
+    } else {
+        1
+    };
+    let cells = loop {
+        let cells = repo.get_bbox_grid_cells(bbox, aggregation).await?;
+        if cells.len() as i64 <= query.max_cells {
+            break cells;
+        }
+        aggregation *= 2;
+    };
+
+    let total: f64 = cells.iter().map(|c| c.population as f64).sum();
+    Ok(ApiResponse::ok(PopulationBboxPayload {
+        bbox,
+        aggregation,
+        cell_size_deg: (aggregation as f64 / 120.0 * 1e6).round() / 1e6,
+        total_population: (total * 10.0).round() / 10.0,
+        cell_count: cells.len(),
+        cells,
+    }))
+}
+
+fn hex_bbox(query: &PopulationGridQuery, bbox: [f64; 4], cells: &[GridCell]) -> ActixResult<HttpResponse> {
+    let resolution = query.resolution.unwrap_or(DEFAULT_H3_RESOLUTION);
+    let hexes = hex::aggregate(cells, hex::resolution(resolution));
+    if hexes.len() as i64 > query.max_cells {
+        return Err(AppError::Validation(format!(
+            "viewport spans {} hexagons at resolution {resolution}, over max_cells={}; use a coarser resolution",
+            hexes.len(),
+            query.max_cells
+        ))
+        .into());
+    }
+
+    Ok(ApiResponse::ok(HexGridPayload {
+        coordinate: None,
+        radius_km: None,
+        bbox: Some(bbox),
+        resolution,
+        total_population: summed_total(cells),
+        cell_count: hexes.len(),
+        year: None,
+        cells: hexes,
+    }))
+}
+
+fn s2_bbox(query: &PopulationGridQuery, bbox: [f64; 4], cells: &[GridCell]) -> ActixResult<HttpResponse> {
+    let level = query.level.unwrap_or(DEFAULT_S2_LEVEL);
+    let s2_cells = s2::aggregate(cells, level);
+    if s2_cells.len() as i64 > query.max_cells {
+        return Err(AppError::Validation(format!(
+            "viewport spans {} S2 cells at level {level}, over max_cells={}; use a coarser level",
+            s2_cells.len(),
+            query.max_cells
+        ))
+        .into());
+    }
+
+    Ok(ApiResponse::ok(S2GridPayload {
+        coordinate: None,
+        radius_km: None,
+        bbox: Some(bbox),
+        level,
+        total_population: summed_total(cells),
+        cell_count: s2_cells.len(),
+        year: None,
+        cells: s2_cells,
+    }))
+}
+
+/// Population of one H3 hexagon.
+#[utoipa::path(
+    get,
+    path = "/population/h3/{index}",
+    tag = "Population",
+    summary = "Population of an H3 hexagon",
+    description = "Sums the WorldPop grid cells whose centre falls inside the H3 hexagon \
+        `index` (resolution 3 to 15), the same assignment `aggregation=h3` uses, and returns \
+        the total with the hexagon's centre, area and boundary. Hexagons finer than the 1 km \
+        grid (resolution 8 and up) often contain no cell centre and read 0.\n\n\
+        `dataset` and `year` pick the grid as for `/population`.",
+    params(
+        ("index" = String, Path, description = "H3 cell index as a hex string", example = "87611cb11ffffff"),
+        ("dataset" = Option<Dataset>, Query, description = "WorldPop grid to read: `unconstrained` (default) or `constrained`", example = "constrained"),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020)
+    ),
+    responses(
+        (status = 200, description = "Population of the hexagon", body = H3CellPayload),
+        (status = 400, description = "Malformed index, resolution below 3, or a hexagon crossing the antimeridian"),
+        (status = 404, description = "`dataset=constrained` requested but that grid is not loaded, or no grid loaded for `year`")
+    )
+)]
+pub(crate) async fn h3_population(
+    repos: web::Data<Repositories>,
+    path: web::Path<String>,
+    query: web::Query<CellIndexQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let cell: h3o::CellIndex = path
+        .trim()
+        .parse()
+        .map_err(|_| AppError::Validation(format!("'{}' is not an H3 cell index", path.as_str())))?;
+    let resolution = u8::from(cell.resolution());
+    if resolution < hex::MIN_LOOKUP_RESOLUTION {
+        return Err(AppError::Validation(format!(
+            "H3 resolution {resolution} is too coarse; use resolution {} or finer",
+            hex::MIN_LOOKUP_RESOLUTION
+        ))
+        .into());
+    }
+    let bounds = hex::bounds(cell).ok_or_else(|| {
+        AppError::Validation("hexagons crossing the antimeridian are not supported".into())
+    })?;
+
+    let (year, repo) = repos.population_for(query.dataset).for_year(query.year).await?;
+    let cells = repo.get_bbox_grid_cells(bounds, 1).await?;
+    let (population, cell_count) = hex::population_in(cell, &cells);
+    let centre = h3o::LatLng::from(cell);
+
+    Ok(ApiResponse::ok(H3CellPayload {
+        h3: cell.to_string(),
+        resolution,
+        lat: centre.lat(),
+        lon: centre.lng(),
+        population: (population * 10.0).round() / 10.0,
+        cell_count,
+        area_km2: (cell.area_km2() * 1000.0).round() / 1000.0,
+        boundary: hex::boundary(cell),
+        year,
+    }))
+}
+
+/// Population of one Google S2 cell.
+#[utoipa::path(
+    get,
+    path = "/population/s2/{cell}",
+    tag = "Population",
+    summary = "Population of an S2 cell",
+    description = "Sums the WorldPop grid cells whose centre falls inside the S2 cell `cell` \
+        (level 6 to 30), the same assignment `aggregation=s2` uses, and returns the total with \
+        the cell's centre, area and boundary. `cell` is a token (`3ae2591`) or a decimal id, \
+        signed as BigQuery's `S2_CELLIDFROMPOINT` returns it or unsigned; strings of up to 16 \
+        hex digits read as tokens. Cells finer than the 1 km grid (level 14 and up) often \
+        contain no cell centre and read 0.\n\n\
+        `dataset` and `year` pick the grid as for `/population`.",
+    params(
+        ("cell" = String, Path, description = "S2 cell token or decimal id", example = "3ae2591"),
+        ("dataset" = Option<Dataset>, Query, description = "WorldPop grid to read: `unconstrained` (default) or `constrained`", example = "constrained"),
+        ("year" = Option<i32>, Query, description = "Population grid year (default: latest loaded)", example = 2020)
+    ),
+    responses(
+        (status = 200, description = "Population of the S2 cell", body = S2CellPayload),
+        (status = 400, description = "Malformed cell, level below 6, or a cell crossing the antimeridian"),
+        (status = 404, description = "`dataset=constrained` requested but that grid is not loaded, or no grid loaded for `year`")
+    )
+)]
+pub(crate) async fn s2_population(
+    repos: web::Data<Repositories>,
+    path: web::Path<String>,
+    query: web::Query<CellIndexQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let id = s2::parse(&path)
+        .ok_or_else(|| AppError::Validation(format!("'{}' is not an S2 cell token or id", path.as_str())))?;
+    let level = s2::level(id);
+    if level < s2::MIN_LOOKUP_LEVEL {
+        return Err(AppError::Validation(format!(
+            "S2 level {level} is too coarse; use level {} or finer",
+            s2::MIN_LOOKUP_LEVEL
+        ))
+        .into());
+    }
+    let bounds = s2::bounds(id).ok_or_else(|| {
+        AppError::Validation("S2 cells crossing the antimeridian are not supported".into())
+    })?;
+
+    let (year, repo) = repos.population_for(query.dataset).for_year(query.year).await?;
+    let cells = repo.get_bbox_grid_cells(bounds, 1).await?;
+    let (population, cell_count) = s2::population_in(id, &cells);
+    let (lat, lon) = s2::centre(id);
+
+    Ok(ApiResponse::ok(S2CellPayload {
+        token: s2::token(id),
+        id: (id as i64).to_string(),
+        level,
+        lat,
+        lon,
+        population: (population * 10.0).round() / 10.0,
+        cell_count,
+        area_km2: (s2::area_km2(id) * 1000.0).round() / 1000.0,
+        boundary: s2::boundary(id),
+        year,
+    }))
+}
+
+/// Rank the population density at a coordinate against its country and the world.
+#[utoipa::path(
+    get,
+    path = "/population/rank",
+    tag = "Population",
+    summary = "Population density percentile",
+    description = "Returns the population density (people per km²) of the 1 km² grid cell at the \
+        given coordinate and its percentile among populated cells of the containing country and \
+        of the whole world. A percentile of 90 means the cell is at least as dense as 90% of \
+        populated cells; empty cells rank 0.\n\n\
+        Percentiles come from precomputed density distributions that the WorldPop loader \
+        refreshes after each load; the country is resolved against the current boundary vintage.",
+    params(
+        ("lat" = f64, Query, description = "Latitude in decimal degrees", example = 6.9271, minimum = -90, maximum = 90),
+        ("lon" = f64, Query, description = "Longitude in decimal degrees", example = 79.8612, minimum = -180, maximum = 180)
+    ),
+    responses(
+        (status = 200, description = "Cell density with country and world percentiles", body = DensityRankPayload),
+        (status = 400, description = "Invalid coordinates"),
+        (status = 404, description = "Density distributions have not been computed yet")
+    )
+)]
+pub(crate) async fn population_rank(
+    repo: web::Data<dyn PopulationRepository>,
+    query: web::Query<PointQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let rank = repo.get_density_rank(query.lat, query.lon).await?;
+    Ok(ApiResponse::ok(rank))
+}
+
 /// Look up estimated population for multiple coordinates in a single request.
 #[utoipa::path(
     post,
@@ -80,8 +690,8 @@ pub(crate) async fn get_population(
     tag = "Population",
     summary = "Batch population lookup",
     description = "Accepts an array of coordinate points (1–1000) and returns the estimated \
-        population for each 1 km² grid cell. All points are queried in a single database round-trip \
-        for optimal performance.",
+        population for each 1 km² grid cell, in request order. Points are looked up concurrently \
+        over a few pooled database connections.",
     request_body(
         content = BatchQuery,
         description = "JSON body with an array of coordinate points",
@@ -93,17 +703,18 @@ pub(crate) async fn get_population(
     )
 )]
 pub(crate) async fn batch_population(
-    pool: web::Data<Pool>,
+    repo: web::Data<dyn PopulationRepository>,
+    state: web::Data<AppState>,
     body: web::Json<BatchQuery>,
 ) -> ActixResult<HttpResponse> {
     body.validate().map_err(|e| {
         AppError::Validation(format!("Validation failed: {e}"))
     })?;
-    validate_batch_size(body.points.len())?;
+    state.limits().check_batch_size(body.points.len())?;
 
-    let client = pool.get().await.map_err(AppError::from)?;
+    let (year, repo) = repo.for_year(None).await?;
     let points: Vec<(f64, f64)> = body.points.iter().map(|p| (p.lat, p.lon)).collect();
-    let populations = PopulationRepository::get_batch_population(&client, &points).await?;
+    let populations = repo.get_batch_population(&points).await?;
 
     let results: Vec<PointPayload> = body
         .points
@@ -114,6 +725,13 @@ pub(crate) async fn batch_population(
             lon: point.lon,
             population: pop,
             resolution_km: 1.0,
+            year,
+            uncertainty: None,
+            settlement_class: None,
+            built_up_m2: None,
+            night_light_radiance: None,
+            land_cover: None,
+            meta: IncludedMeta::default(),
         })
         .collect();
 
@@ -1,10 +1,10 @@
 use actix_web::{web, HttpResponse, Result as ActixResult};
-use deadpool_postgres::Pool;
 
 use crate::config::API_PREFIX;
 use crate::models::{RootPayload, TableRowCount};
 use crate::repositories::StatsRepository;
 use crate::response::ApiResponse;
+use crate::state::AppState;
 
 /// Root endpoint: health status, Swagger docs link, and estimated table row counts.
 #[utoipa::path(
@@ -17,8 +17,8 @@ use crate::response::ApiResponse;
         (status = 200, description = "Service info with table row counts", body = RootPayload)
     )
 )]
-pub(crate) async fn root(pool: web::Data<Pool>) -> ActixResult<HttpResponse> {
-    let tables = match pool.get().await {
+pub(crate) async fn root(state: web::Data<AppState>) -> ActixResult<HttpResponse> {
+    let tables = match state.read_pool.get().await {
         Ok(client) => match StatsRepository::get_stats(&client).await {
             Ok(stats) => Some(
                 stats
@@ -0,0 +1,105 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry::{great_circle_km, great_circle_point};
+use crate::models::{BoundaryQuery, CoordinateInfo, RouteSample, RouteSamplePayload, RouteSampleQuery};
+use crate::repositories::{CountryRepository, Repositories};
+use crate::response::ApiResponse;
+use crate::validation::{parse_location, MAX_ROUTE_SAMPLES};
+
+#[inline]
+fn round3(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+/// Population and country at regular intervals along a great circle.
+#[utoipa::path(
+    get,
+    path = "/route/sample",
+    tag = "Risk Assessment",
+    summary = "Sample population along a great circle",
+    description = "Places a sample every `interval_km` along the great circle from `from` to \
+        `to` (plus one at `to` itself) and returns the population of the grid cell under each \
+        sample and the country containing it. A quick screen of what a flight path, pipeline \
+        or cable corridor passes over, without the full exposure machinery.\n\n\
+        At most 2000 samples per request: raise `interval_km` for long paths. Antipodal \
+        points are rejected, as no single great circle joins them.",
+    params(
+        ("from" = String, Query, description = "Start of the path as `lat,lon`", example = "6.9271,79.8612"),
+        ("to" = String, Query, description = "End of the path as `lat,lon`", example = "13.0827,80.2707"),
+        ("interval_km" = Option<f64>, Query, description = "Spacing between samples in km (default: 10, max: 1000)", example = 10.0),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage for `iso3` (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Samples along the path", body = RouteSamplePayload),
+        (status = 400, description = "Malformed `from` / `to`, interval out of range, too many samples or antipodal points"),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn route_sample(
+    repos: web::Data<Repositories>,
+    countries: web::Data<dyn CountryRepository>,
+    query: web::Query<RouteSampleQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    let (from_lat, from_lon) = parse_location(&query.from)?;
+    let (to_lat, to_lon) = parse_location(&query.to)?;
+
+    let distance = great_circle_km(from_lat, from_lon, to_lat, to_lon);
+    let steps = (distance / query.interval_km).ceil() as usize;
+    if steps + 1 > MAX_ROUTE_SAMPLES {
+        return Err(AppError::Validation(format!(
+            "Path of {distance:.0} km needs {} samples at {} km intervals; at most {MAX_ROUTE_SAMPLES} allowed",
+            steps + 1,
+            query.interval_km
+        ))
+        .into());
+    }
+
+    let mut offsets: Vec<f64> = (0..steps).map(|i| i as f64 * query.interval_km).collect();
+    offsets.push(distance);
+    let points = offsets
+        .iter()
+        .map(|&d| {
+            let fraction = if distance > 0.0 { d / distance } else { 0.0 };
+            great_circle_point(from_lat, from_lon, to_lat, to_lon, fraction)
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            AppError::Validation("`from` and `to` are antipodal; no single great circle joins them".into())
+        })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let tags = repos.tagging.tag_points(&points, vintage).await?;
+
+    let mut visited: Vec<String> = Vec::new();
+    let samples: Vec<RouteSample> = offsets
+        .iter()
+        .zip(points)
+        .zip(tags)
+        .map(|((&d, (lat, lon)), (iso3, population))| {
+            if let Some(code) = iso3.as_ref().filter(|c| visited.last() != Some(*c)) {
+                visited.push(code.clone());
+            }
+            RouteSample { distance_km: round3(d), lat, lon, iso3, population }
+        })
+        .collect();
+
+    Ok(ApiResponse::ok(RouteSamplePayload {
+        from: CoordinateInfo { lat: from_lat, lon: from_lon },
+        to: CoordinateInfo { lat: to_lat, lon: to_lon },
+        distance_km: round3(distance),
+        interval_km: query.interval_km,
+        sample_count: samples.len(),
+        countries: visited,
+        max_population: samples.iter().map(|s| s.population).fold(0.0, f32::max),
+        samples,
+    }))
+}
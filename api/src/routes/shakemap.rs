@@ -0,0 +1,127 @@
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use futures_util::future::try_join_all;
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::geometry;
+use crate::include::{self, sources, Includes, Section};
+use crate::models::{IncludedMeta, MmiContourExposure, ShakeMapPayload, ShakeMapQuery};
+use crate::repositories::PopulationRepository;
+use crate::response::ApiResponse;
+use crate::shakemap;
+use crate::state::AppState;
+use crate::validation::MAX_SHAKEMAP_SPAN_KM;
+
+#[inline]
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Population per MMI contour of a USGS ShakeMap.
+#[utoipa::path(
+    post,
+    path = "/exposure/shakemap",
+    tag = "Risk Assessment",
+    summary = "Population per ShakeMap intensity contour",
+    description = "Sums the population inside each Modified Mercalli intensity contour of a USGS \
+        ShakeMap, matching grid cells by their centre. Either post the contour GeoJSON \
+        (`cont_mmi.json`) as `contours`, or give the USGS `event_id` and the server fetches the \
+        event's ShakeMap from the service at `SHAKEMAP_URL`.\n\n\
+        Contours of the same level are merged; contour lines that do not close (cut off at the \
+        map edge) are skipped. Each level reports the population inside it (`population`, shaken \
+        at that intensity or more) and between it and the next higher level (`band_population`). \
+        A level's bounding box may span at most 1000 km per side.\n\n\
+        An unknown event, or one without a ShakeMap, is a 404, as is `event_id` when fetching is \
+        disabled; a failing USGS service is a 502.",
+    request_body(
+        content = ShakeMapQuery,
+        description = "ShakeMap contours, or the USGS event to fetch them for"
+    ),
+    responses(
+        (status = 200, description = "Population per MMI contour", body = ShakeMapPayload),
+        (status = 400, description = "Invalid contours or event ID, both or neither given, contour too large, or unsupported `include` section"),
+        (status = 404, description = "Unknown event, event without a ShakeMap, or fetching disabled"),
+        (status = 502, description = "USGS service unreachable or failed")
+    )
+)]
+pub(crate) async fn shakemap_exposure(
+    population: web::Data<dyn PopulationRepository>,
+    state: web::Data<AppState>,
+    body: web::Json<ShakeMapQuery>,
+    includes: Includes,
+) -> ActixResult<HttpResponse> {
+    body.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    includes.ensure_supported(include::SHAKEMAP)?;
+
+    let mut watch = includes.stopwatch();
+    let collection = match (&body.event_id, &body.contours) {
+        (Some(event_id), None) => {
+            let source = state.shakemap.as_ref().ok_or_else(|| {
+                AppError::NotFound("Fetching ShakeMaps is disabled on this server".into())
+            })?;
+            let contours = source.contours(event_id).await?;
+            watch.mark("shakemap");
+            contours
+        }
+        (None, Some(contours)) => contours.clone(),
+        _ => return Err(AppError::Validation("Provide either `event_id` or `contours`, not both".into()).into()),
+    };
+    let fetched = body.event_id.is_some();
+    let invalid = |e: String| {
+        if fetched {
+            AppError::Upstream(format!("USGS ShakeMap has invalid contours: {e}"))
+        } else {
+            AppError::Validation(format!("Invalid contours: {e}"))
+        }
+    };
+
+    let mut levels = Vec::new();
+    for (mmi, polygon) in shakemap::mmi_contours(&collection).map_err(invalid)? {
+        let bounds = geometry::polygon_bounds(&polygon).map_err(|e| invalid(format!("MMI {mmi}: {e}")))?;
+        if geometry::bbox_span_km(&bounds) > MAX_SHAKEMAP_SPAN_KM {
+            return Err(AppError::Validation(format!(
+                "MMI {mmi} contour must span at most {MAX_SHAKEMAP_SPAN_KM} km per side"
+            ))
+            .into());
+        }
+        levels.push((mmi, polygon.to_string(), bounds));
+    }
+
+    let sums = try_join_all(
+        levels.iter().map(|(_, geojson, bounds)| population.get_polygon_population(geojson, *bounds)),
+    )
+    .await?;
+    watch.mark("population");
+
+    // Levels run highest first, so each band ends at the previous contour.
+    let mut inner = 0.0;
+    let contours: Vec<MmiContourExposure> = levels
+        .into_iter()
+        .zip(sums)
+        .map(|((mmi, _, bounds), (total, cells, area_km2))| {
+            let band = (total - inner).max(0.0);
+            inner = total;
+            MmiContourExposure {
+                mmi,
+                population: round1(total),
+                band_population: round1(band),
+                populated_cell_count: cells,
+                area_km2: round1(area_km2),
+                bbox: bounds,
+            }
+        })
+        .collect();
+
+    let sources = includes
+        .has(Section::Sources)
+        .then(|| vec![sources::worldpop(), sources::usgs_shakemap()]);
+
+    Ok(ApiResponse::ok(ShakeMapPayload {
+        event_id: body.event_id.clone(),
+        total_population: contours.last().map_or(0.0, |c| c.population),
+        contours,
+        meta: IncludedMeta { sources, timings: watch.finish() },
+    }))
+}
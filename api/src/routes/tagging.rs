@@ -0,0 +1,223 @@
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::models::{BoundaryQuery, TagLineError, TagPoint, TagStreamLine, TaggedPoint};
+use crate::repositories::{CountryRepository, Repositories, TaggingRepository};
+use crate::state::AppState;
+
+/// Points tagged per database round-trip.
+const CHUNK_SIZE: usize = 2000;
+/// A partially filled chunk is flushed once the input has been idle this long,
+/// so slow producers still see results promptly.
+const FLUSH_AFTER: Duration = Duration::from_millis(50);
+/// Longest accepted input line; anything larger is not a coordinate.
+const MAX_LINE_BYTES: usize = 4096;
+/// Encoded response chunks buffered ahead of a slow reader before backpressure.
+const OUTPUT_BUFFER: usize = 8;
+
+/// Tag an unbounded NDJSON stream of coordinates with country and cell population.
+#[utoipa::path(
+    post,
+    path = "/tag/stream",
+    tag = "Enrichment",
+    summary = "Streaming country & population tagging",
+    description = "Accepts a chunked `application/x-ndjson` body with one `{\"lat\", \"lon\", \"id\"?}` \
+        object per line and streams back one tagged line per input line, in input order. \
+        Input lines are tagged in chunks of up to 2000 points per database round-trip; a partial \
+        chunk is flushed after 50 ms of input idle time, so the endpoint works for both bulk \
+        uploads and long-lived telemetry pipelines.\n\n\
+        Lines that are not valid JSON or carry out-of-range coordinates produce a \
+        `{\"line\", \"error\"}` line in their place instead of aborting the stream. \
+        `iso3` is `null` for points outside every country (at sea).\n\n\
+        Each chunk checks out a database connection only while it is tagged. An instance keeps \
+        at most `MAX_TAG_STREAMS` streams open; further ones are refused with 503.",
+    params(
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to evaluate against (default: current)", example = 2023)
+    ),
+    request_body(
+        content = TagPoint,
+        content_type = "application/x-ndjson",
+        description = "Newline-delimited JSON, one coordinate object per line"
+    ),
+    responses(
+        (status = 200, description = "Newline-delimited tagged points (or per-line errors)", body = TaggedPoint, content_type = "application/x-ndjson"),
+        (status = 404, description = "Unknown boundary vintage"),
+        (status = 503, description = "`MAX_TAG_STREAMS` streams are already open")
+    )
+)]
+pub(crate) async fn tag_stream(
+    state: web::Data<AppState>,
+    repos: web::Data<Repositories>,
+    countries: web::Data<dyn CountryRepository>,
+    payload: web::Payload,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let permit = state.tag_streams.clone().try_acquire_owned().map_err(|_| {
+        AppError::Busy(format!(
+            "{} tag streams are already open; retry later",
+            state.limits().max_tag_streams
+        ))
+    })?;
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+
+    let (tx, rx) = mpsc::channel::<Bytes>(OUTPUT_BUFFER);
+    actix_web::rt::spawn(pump(repos.tagging.clone(), permit, payload, vintage, tx));
+
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (Ok::<_, actix_web::Error>(chunk), rx))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body))
+}
+
+/// Reads request lines, tags them chunk by chunk and writes encoded results to
+/// `tx`. Returns early when the client disconnects or the stream fails; the
+/// stream's `permit` is released then.
+async fn pump(
+    tagging: Arc<TaggingRepository>,
+    _permit: OwnedSemaphorePermit,
+    mut payload: web::Payload,
+    vintage: i32,
+    tx: mpsc::Sender<Bytes>,
+) {
+    let mut pending: Vec<u8> = Vec::new();
+    let mut batch: Vec<Result<TagPoint, TagLineError>> = Vec::with_capacity(CHUNK_SIZE);
+    let mut line_no = 0usize;
+
+    loop {
+        let next = if batch.is_empty() {
+            Some(payload.next().await)
+        } else {
+            tokio::time::timeout(FLUSH_AFTER, payload.next()).await.ok()
+        };
+
+        match next {
+            // Input went idle with a partial chunk buffered.
+            None => {
+                if !flush(&tagging, vintage, &mut batch, &tx).await {
+                    return;
+                }
+            }
+            Some(None) => break,
+            Some(Some(Err(e))) => {
+                send_fatal(&tx, format!("failed to read request body: {e}")).await;
+                return;
+            }
+            Some(Some(Ok(bytes))) => {
+                pending.extend_from_slice(&bytes);
+                let mut start = 0;
+                while let Some(pos) = pending[start..].iter().position(|&b| b == b'\n') {
+                    let line = &pending[start..start + pos];
+                    start += pos + 1;
+                    line_no += 1;
+                    if line.trim_ascii().is_empty() {
+                        continue;
+                    }
+                    batch.push(parse_line(line_no, line));
+                    if batch.len() >= CHUNK_SIZE && !flush(&tagging, vintage, &mut batch, &tx).await {
+                        return;
+                    }
+                }
+                pending.drain(..start);
+
+                if pending.len() > MAX_LINE_BYTES {
+                    flush(&tagging, vintage, &mut batch, &tx).await;
+                    send_fatal(&tx, format!("line {} exceeds {MAX_LINE_BYTES} bytes", line_no + 1)).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    if !pending.trim_ascii().is_empty() {
+        batch.push(parse_line(line_no + 1, &pending));
+    }
+    flush(&tagging, vintage, &mut batch, &tx).await;
+}
+
+fn parse_line(line: usize, raw: &[u8]) -> Result<TagPoint, TagLineError> {
+    let point: TagPoint = serde_json::from_slice(raw).map_err(|e| TagLineError {
+        line,
+        error: format!("invalid JSON: {e}"),
+    })?;
+    point.validate().map_err(|e| TagLineError {
+        line,
+        error: format!("Validation failed: {e}"),
+    })?;
+    Ok(point)
+}
+
+/// Tags the buffered lines and sends them downstream. Returns `false` when the
+/// stream should stop (client gone or database failure, including a result
+/// count that does not match the points sent).
+async fn flush(
+    tagging: &TaggingRepository,
+    vintage: i32,
+    batch: &mut Vec<Result<TagPoint, TagLineError>>,
+    tx: &mpsc::Sender<Bytes>,
+) -> bool {
+    if batch.is_empty() {
+        return true;
+    }
+
+    let points: Vec<(f64, f64)> = batch
+        .iter()
+        .filter_map(|entry| entry.as_ref().ok().map(|p| (p.lat, p.lon)))
+        .collect();
+    let tags = if points.is_empty() {
+        Vec::new()
+    } else {
+        match tagging.tag_points(&points, vintage).await {
+            Ok(tags) => tags,
+            Err(e) => {
+                log::error!("tag stream: {e}");
+                send_fatal(tx, "database error".into()).await;
+                return false;
+            }
+        }
+    };
+
+    let mut tags = tags.into_iter();
+    let mut out = Vec::with_capacity(batch.len() * 96);
+    for entry in batch.drain(..) {
+        let line = match entry {
+            Ok(point) => {
+                let (iso3, population) = tags.next().expect("tag_points returns one result per point");
+                TagStreamLine::Tagged(TaggedPoint {
+                    id: point.id,
+                    lat: point.lat,
+                    lon: point.lon,
+                    iso3,
+                    population,
+                })
+            }
+            Err(err) => TagStreamLine::Error(err),
+        };
+        encode_line(&mut out, &line);
+    }
+
+    tx.send(Bytes::from(out)).await.is_ok()
+}
+
+async fn send_fatal(tx: &mpsc::Sender<Bytes>, error: String) {
+    let mut out = Vec::new();
+    encode_line(&mut out, &TagStreamLine::Error(TagLineError { line: 0, error }));
+    let _ = tx.send(Bytes::from(out)).await;
+}
+
+fn encode_line(out: &mut Vec<u8>, line: &TagStreamLine) {
+    // Serializing plain structs into a Vec cannot fail.
+    let _ = serde_json::to_writer(&mut *out, line);
+    out.push(b'\n');
+}
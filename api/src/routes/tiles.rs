@@ -0,0 +1,94 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{web, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use crate::errors::AppError;
+use crate::models::BoundaryQuery;
+use crate::repositories::CountryRepository;
+use crate::validation::validate_tile;
+
+const MVT_CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+/// Web Mercator world width in metres.
+const WORLD_M: f64 = 40_075_016.685_578_5;
+/// Boundaries are simplified to about one pixel of a 256 px tile.
+const SIMPLIFY_PX: f64 = 1.0;
+/// Boundaries change once per Natural Earth release; let clients and CDNs keep tiles a day.
+const TILE_MAX_AGE_SECS: u32 = 86_400;
+
+/// Simplification tolerance in metres for zoom `z`.
+fn tolerance_m(z: u32) -> f64 {
+    SIMPLIFY_PX * WORLD_M / (256.0 * f64::from(1u32 << z))
+}
+
+/// Country boundaries as a Mapbox vector tile.
+#[utoipa::path(
+    get,
+    path = "/tiles/countries/{z}/{x}/{y}.mvt",
+    tag = "Country",
+    summary = "Country boundary vector tiles",
+    description = "Serves the Natural Earth country boundaries as a Mapbox vector tile \
+        (Web Mercator `z/x/y`, XYZ scheme) with a single `countries` layer, so a map can draw a \
+        world layer straight from this API. Each feature carries `iso_a2`, `iso_a3`, `name`, \
+        `continent` and `sovereign`.\n\n\
+        Geometry is simplified to about one pixel at the requested zoom and clipped with a \
+        64-unit buffer. Zooms 0 to 12 are served; a tile no country touches is an empty 200. \
+        Tiles may be cached for a day.",
+    params(
+        ("z" = u32, Path, description = "Zoom level (0 to 12)", example = 3),
+        ("x" = u32, Path, description = "Tile column (0 to 2^z - 1)", example = 5),
+        ("y" = u32, Path, description = "Tile row from the north (0 to 2^z - 1)", example = 3),
+        ("boundaries" = Option<i32>, Query, description = "Boundary dataset vintage to draw (default: current)", example = 2023)
+    ),
+    responses(
+        (status = 200, description = "Vector tile", content_type = "application/vnd.mapbox-vector-tile"),
+        (status = 400, description = "Zoom above 12 or tile outside the zoom's matrix"),
+        (status = 404, description = "Unknown boundary vintage")
+    )
+)]
+pub(crate) async fn country_tile(
+    countries: web::Data<dyn CountryRepository>,
+    path: web::Path<(u32, u32, u32)>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    let (z, x, y) = path.into_inner();
+    validate_tile(z, x, y)?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let vintage = countries.resolve_vintage(boundaries.boundaries).await?;
+    let tile = countries.get_boundary_tile(z, x, y, tolerance_m(z), vintage).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(MVT_CONTENT_TYPE)
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(TILE_MAX_AGE_SECS),
+        ]))
+        .body(tile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerance_halves_per_zoom() {
+        assert!((tolerance_m(0) - 156_543.03).abs() < 0.01);
+        assert!((tolerance_m(1) * 2.0 - tolerance_m(0)).abs() < 1e-6);
+        assert!(tolerance_m(12) < 40.0);
+    }
+
+    #[actix_web::test]
+    async fn route_pattern_extracts_tile_address() {
+        use actix_web::{test, App};
+
+        let app = test::init_service(App::new().route(
+            "/tiles/countries/{z}/{x}/{y}.mvt",
+            web::get().to(|path: web::Path<(u32, u32, u32)>| async move { format!("{:?}", path.into_inner()) }),
+        ))
+        .await;
+        let req = test::TestRequest::get().uri("/tiles/countries/3/5/2.mvt").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "(3, 5, 2)");
+    }
+}
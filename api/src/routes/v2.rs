@@ -0,0 +1,86 @@
+//! `/api/v2`: the API under its revised response contract.
+//!
+//! `/api/v1` is frozen; breaking improvements land here instead. Every v2
+//! response is the bare payload (the envelope middleware strips it for these
+//! paths), coordinates are always numbers, and lists are paginated with
+//! `page`/`per_page`. Routes whose contract changed have a handler below,
+//! registered ahead of the v1 route it replaces; all others are served by the
+//! v1 handler unchanged.
+
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use validator::Validate;
+
+use super::country::matching_countries;
+use crate::config::Features;
+use crate::errors::AppError;
+use crate::language;
+use crate::models::{
+    BoundaryQuery, ContinentQuery, CountryPageV2, LangQuery, MaxDistanceQuery, PageQuery, PointQuery, ReverseModeQuery,
+    ReversePayloadV2,
+};
+use crate::repositories::{CountryRepository, Repositories};
+use crate::response::ApiResponse;
+
+/// Registers the v2 routes; mounted under `API_V2_PREFIX`.
+pub(crate) fn configure(cfg: &mut web::ServiceConfig, features: &Features) {
+    cfg.route("/reverse", web::get().to(reverse_geocode))
+        .route("/countries", web::get().to(countries_by_continent));
+    super::configure(cfg, features);
+}
+
+/// `/reverse` with numeric `lat`/`lon`.
+pub(crate) async fn reverse_geocode(
+    req: HttpRequest,
+    repos: web::Data<Repositories>,
+    query: web::Query<PointQuery>,
+    lang: web::Query<LangQuery>,
+    cap: web::Query<MaxDistanceQuery>,
+    mode: web::Query<ReverseModeQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+) -> ActixResult<HttpResponse> {
+    query.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    lang.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    cap.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+    boundaries.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let languages = language::preferred(lang.lang.as_deref(), &req);
+    let vintage = repos.countries.resolve_vintage(boundaries.boundaries).await?;
+    let result = repos
+        .geocoding
+        .reverse_geocode(query.lat, query.lon, &languages, cap.max_distance_km, mode.mode, vintage)
+        .await?;
+
+    Ok(language::vary(ApiResponse::ok(ReversePayloadV2::from(result))))
+}
+
+/// `/countries`, one page at a time.
+pub(crate) async fn countries_by_continent(
+    countries: web::Data<dyn CountryRepository>,
+    query: web::Query<ContinentQuery>,
+    boundaries: web::Query<BoundaryQuery>,
+    page: web::Query<PageQuery>,
+) -> ActixResult<HttpResponse> {
+    page.validate().map_err(|e| {
+        AppError::Validation(format!("Validation failed: {e}"))
+    })?;
+
+    let (filter, list) = matching_countries(&**countries, &query, &boundaries).await?;
+
+    Ok(ApiResponse::ok(CountryPageV2 {
+        continent: filter.continent,
+        region: filter.region,
+        subregion: filter.subregion,
+        total: list.len(),
+        page: page.page,
+        per_page: page.per_page,
+        countries: page.slice(list),
+    }))
+}
@@ -0,0 +1,113 @@
+//! Client for the optional Valhalla routing engine behind `/exposure/isochrone`.
+//!
+//! Enabled by pointing `ROUTING_URL` at a Valhalla server; the road network it
+//! serves decides which areas isochrones can be computed for. OSRM has no
+//! isochrone service, so Valhalla is the only supported engine.
+
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::errors::AppError;
+use crate::models::TravelMode;
+
+const ROUTING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Handle on the configured routing engine.
+pub(crate) struct RoutingEngine {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl RoutingEngine {
+    /// `None` when `url` is empty, i.e. the integration is disabled.
+    pub fn new(url: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        if url.is_empty() {
+            return None;
+        }
+        match reqwest::Client::builder().timeout(ROUTING_TIMEOUT).build() {
+            Ok(http) => Some(Self { http, url: url.to_string() }),
+            Err(e) => {
+                log::error!("routing disabled: failed to build HTTP client: {e}");
+                None
+            }
+        }
+    }
+
+    /// Area reachable from a point within `minutes`, as a GeoJSON Polygon or
+    /// MultiPolygon. Requests the engine rejects (typically a start point with
+    /// no road nearby) fail with `Validation`; anything else with `Upstream`.
+    pub async fn isochrone(
+        &self,
+        lat: f64,
+        lon: f64,
+        minutes: f64,
+        mode: TravelMode,
+    ) -> Result<Value, AppError> {
+        let request = json!({
+            "locations": [{ "lat": lat, "lon": lon }],
+            "costing": costing(mode),
+            "contours": [{ "time": minutes }],
+            "polygons": true,
+        });
+        let response = self
+            .http
+            .post(format!("{}/isochrone", self.url))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("routing engine: {e}")))?;
+
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("routing engine returned {status}: {e}")))?;
+        if status.is_client_error() {
+            let reason = body["error"].as_str().unwrap_or("request rejected");
+            return Err(AppError::Validation(format!("Routing engine: {reason}")));
+        }
+        if !status.is_success() {
+            return Err(AppError::Upstream(format!("routing engine returned {status}: {body}")));
+        }
+        isochrone_geometry(body)
+            .ok_or_else(|| AppError::Upstream("routing engine returned no isochrone polygon".into()))
+    }
+}
+
+/// Valhalla costing model for a travel mode.
+fn costing(mode: TravelMode) -> &'static str {
+    match mode {
+        TravelMode::Driving => "auto",
+        TravelMode::Cycling => "bicycle",
+        TravelMode::Walking => "pedestrian",
+    }
+}
+
+/// The single contour's geometry out of Valhalla's FeatureCollection.
+fn isochrone_geometry(mut body: Value) -> Option<Value> {
+    let geometry = body["features"].as_array_mut()?.first_mut()?["geometry"].take();
+    matches!(geometry["type"].as_str(), Some("Polygon" | "MultiPolygon")).then_some(geometry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_contour_polygon() {
+        let ring = json!([[79.8, 6.9], [79.9, 6.9], [79.9, 7.0], [79.8, 6.9]]);
+        let body = json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "properties": { "contour": 30, "metric": "time" },
+                "geometry": { "type": "Polygon", "coordinates": [ring] }
+            }]
+        });
+        let geometry = isochrone_geometry(body).unwrap();
+        assert_eq!(geometry["coordinates"][0], ring);
+        assert!(isochrone_geometry(json!({ "type": "FeatureCollection", "features": [] })).is_none());
+        assert!(RoutingEngine::new("  ").is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Google S2 cells over the WorldPop grid.
+//!
+//! Cell ids follow the S2 library: a cube face, then the cell's position on
+//! that face's Hilbert curve, with the quadratic projection from the sphere.
+//! Ids are written as tokens (hex with trailing zeros dropped) or as the
+//! signed 64-bit integers BigQuery's `S2_CELLIDFROMPOINT` returns. As with H3,
+//! a grid cell belongs to the S2 cell its centre falls in.
+
+use std::collections::HashMap;
+
+use crate::models::{GridCell, S2Cell};
+
+pub(crate) const MAX_LEVEL: u8 = 30;
+
+/// Coarsest level `/population/s2/{cell}` sums: a level-6 cell spans about
+/// 1.4°, coarser ones would read tens of thousands of grid cells.
+pub(crate) const MIN_LOOKUP_LEVEL: u8 = 6;
+
+const POS_BITS: u32 = 2 * MAX_LEVEL as u32 + 1;
+const SWAP_MASK: usize = 1;
+const INVERT_MASK: usize = 2;
+/// (i, j) quadrant, as `i << 1 | j`, of each Hilbert position per orientation.
+const POS_TO_IJ: [[usize; 4]; 4] = [[0, 1, 3, 2], [0, 2, 3, 1], [3, 2, 0, 1], [3, 1, 0, 2]];
+const IJ_TO_POS: [[usize; 4]; 4] = [[0, 1, 3, 2], [0, 3, 1, 2], [2, 3, 1, 0], [2, 1, 3, 0]];
+const POS_TO_ORIENTATION: [usize; 4] = [SWAP_MASK, 0, 0, INVERT_MASK | SWAP_MASK];
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+fn uv_to_st(u: f64) -> f64 {
+    if u >= 0.0 {
+        0.5 * (1.0 + 3.0 * u).sqrt()
+    } else {
+        1.0 - 0.5 * (1.0 - 3.0 * u).sqrt()
+    }
+}
+
+fn st_to_uv(s: f64) -> f64 {
+    if s >= 0.5 {
+        (4.0 * s * s - 1.0) / 3.0
+    } else {
+        (1.0 - 4.0 * (1.0 - s) * (1.0 - s)) / 3.0
+    }
+}
+
+fn face_uv_to_xyz(face: u8, u: f64, v: f64) -> [f64; 3] {
+    match face {
+        0 => [1.0, u, v],
+        1 => [-u, 1.0, v],
+        2 => [-u, -v, 1.0],
+        3 => [-1.0, -v, -u],
+        4 => [v, -1.0, -u],
+        _ => [v, u, -1.0],
+    }
+}
+
+fn xyz_to_face_uv([x, y, z]: [f64; 3]) -> (u8, f64, f64) {
+    let face = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+        if x >= 0.0 { 0 } else { 3 }
+    } else if y.abs() >= z.abs() {
+        if y >= 0.0 { 1 } else { 4 }
+    } else if z >= 0.0 {
+        2
+    } else {
+        5
+    };
+    let (u, v) = match face {
+        0 => (y / x, z / x),
+        1 => (-x / y, z / y),
+        2 => (-x / z, -y / z),
+        3 => (z / x, y / x),
+        4 => (z / y, -x / y),
+        _ => (-y / z, -x / z),
+    };
+    (face, u, v)
+}
+
+fn to_xyz(lat: f64, lon: f64) -> [f64; 3] {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn to_lat_lon([x, y, z]: [f64; 3]) -> (f64, f64) {
+    (z.atan2((x * x + y * y).sqrt()).to_degrees(), y.atan2(x).to_degrees())
+}
+
+/// Id of the level-`level` cell containing a point.
+pub(crate) fn cell_id(lat: f64, lon: f64, level: u8) -> u64 {
+    let (face, u, v) = xyz_to_face_uv(to_xyz(lat, lon));
+    let max = 1u64 << MAX_LEVEL;
+    let leaf = |s: f64| ((s * max as f64).floor() as i64).clamp(0, max as i64 - 1) as u64;
+    let (i, j) = (leaf(uv_to_st(u)), leaf(uv_to_st(v)));
+
+    let mut orientation = face as usize & SWAP_MASK;
+    let mut pos = 0u64;
+    for k in (0..MAX_LEVEL as u32).rev() {
+        let ij = (((i >> k) & 1) << 1 | ((j >> k) & 1)) as usize;
+        let p = IJ_TO_POS[orientation][ij];
+        pos |= (p as u64) << (2 * k);
+        orientation ^= POS_TO_ORIENTATION[p];
+    }
+    parent((face as u64) << POS_BITS | pos << 1 | 1, level)
+}
+
+fn lsb(level: u8) -> u64 {
+    1 << (2 * (MAX_LEVEL - level) as u32)
+}
+
+/// The ancestor of `id` at `level`.
+pub(crate) fn parent(id: u64, level: u8) -> u64 {
+    let lsb = lsb(level);
+    (id & lsb.wrapping_neg()) | lsb
+}
+
+pub(crate) fn level(id: u64) -> u8 {
+    MAX_LEVEL - (id.trailing_zeros() / 2) as u8
+}
+
+fn is_valid(id: u64) -> bool {
+    id >> POS_BITS <= 5 && id.trailing_zeros().is_multiple_of(2)
+}
+
+/// Hex token: the id with its trailing zero digits dropped.
+pub(crate) fn token(id: u64) -> String {
+    let hex = format!("{id:016x}");
+    hex.trim_end_matches('0').to_string()
+}
+
+/// A token, or a decimal id either signed (BigQuery INT64) or unsigned. Up to
+/// 16 hex digits read as a token: decimal ids that short all lie in the South
+/// Atlantic corner of face 0.
+pub(crate) fn parse(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let id = if !raw.is_empty() && raw.len() <= 16 && raw.chars().all(|c| c.is_ascii_hexdigit()) {
+        u64::from_str_radix(&format!("{raw:0<16}"), 16).ok()?
+    } else if let Ok(signed) = raw.parse::<i64>() {
+        signed as u64
+    } else {
+        raw.parse::<u64>().ok()?
+    };
+    (id != 0 && is_valid(id)).then_some(id)
+}
+
+/// Face and the `[s0, s1]`, `[t0, t1]` extent of a cell.
+fn face_st_bounds(id: u64) -> (u8, [f64; 2], [f64; 2]) {
+    let face = (id >> POS_BITS) as u8;
+    let level = level(id);
+    let mut orientation = face as usize & SWAP_MASK;
+    let (mut i, mut j) = (0u64, 0u64);
+    for k in 1..=level as u32 {
+        let p = ((id >> (POS_BITS - 2 * k)) & 3) as usize;
+        let ij = POS_TO_IJ[orientation][p];
+        i = i << 1 | (ij >> 1) as u64;
+        j = j << 1 | (ij & 1) as u64;
+        orientation ^= POS_TO_ORIENTATION[p];
+    }
+    let size = (1u64 << level) as f64;
+    (face, [i as f64 / size, (i + 1) as f64 / size], [j as f64 / size, (j + 1) as f64 / size])
+}
+
+/// Centre of a cell as `(lat, lon)`.
+pub(crate) fn centre(id: u64) -> (f64, f64) {
+    let (face, [s0, s1], [t0, t1]) = face_st_bounds(id);
+    to_lat_lon(face_uv_to_xyz(face, st_to_uv((s0 + s1) / 2.0), st_to_uv((t0 + t1) / 2.0)))
+}
+
+fn corners(id: u64) -> [[f64; 3]; 4] {
+    let (face, [s0, s1], [t0, t1]) = face_st_bounds(id);
+    [(s0, t0), (s1, t0), (s1, t1), (s0, t1)].map(|(s, t)| {
+        let [x, y, z] = face_uv_to_xyz(face, st_to_uv(s), st_to_uv(t));
+        let norm = (x * x + y * y + z * z).sqrt();
+        [x / norm, y / norm, z / norm]
+    })
+}
+
+/// Cell vertices as `[lon, lat]` pairs, counter-clockwise.
+pub(crate) fn boundary(id: u64) -> Vec<[f64; 2]> {
+    corners(id)
+        .into_iter()
+        .map(|v| {
+            let (lat, lon) = to_lat_lon(v);
+            [lon, lat]
+        })
+        .collect()
+}
+
+/// Area of a cell in km², as the spherical quadrilateral between its vertices.
+pub(crate) fn area_km2(id: u64) -> f64 {
+    let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let cross = |a: [f64; 3], b: [f64; 3]| {
+        [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+    };
+    let triangle = |a, b, c| {
+        2.0 * dot(a, cross(b, c)).abs().atan2(1.0 + dot(a, b) + dot(b, c) + dot(c, a))
+    };
+    let [a, b, c, d] = corners(id);
+    (triangle(a, b, c) + triangle(a, c, d)) * EARTH_RADIUS_KM * EARTH_RADIUS_KM
+}
+
+/// `[min_lon, min_lat, max_lon, max_lat]` of a cell padded by one grid cell,
+/// since its edges bow slightly past the vertices. `None` when it straddles
+/// the antimeridian.
+pub(crate) fn bounds(id: u64) -> Option<[f64; 4]> {
+    let (mut min_lon, mut min_lat, mut max_lon, mut max_lat) = (180.0f64, 90.0f64, -180.0f64, -90.0f64);
+    for [lon, lat] in boundary(id) {
+        min_lon = min_lon.min(lon);
+        max_lon = max_lon.max(lon);
+        min_lat = min_lat.min(lat);
+        max_lat = max_lat.max(lat);
+    }
+    if max_lon - min_lon > 180.0 {
+        return None;
+    }
+    let pad = 1.0 / 120.0;
+    Some([
+        (min_lon - pad).max(-180.0),
+        (min_lat - pad).max(-90.0),
+        (max_lon + pad).min(180.0 - 1e-9),
+        (max_lat + pad).min(90.0),
+    ])
+}
+
+/// Population and count of the grid cells whose centre falls in `id`.
+pub(crate) fn population_in(id: u64, cells: &[GridCell]) -> (f64, i64) {
+    let level = level(id);
+    cells
+        .iter()
+        .filter(|c| cell_id(c.lat, c.lon, level) == id)
+        .fold((0.0, 0), |(pop, n), c| (pop + c.population as f64, n + 1))
+}
+
+/// Sum grid cells into the S2 cells of `level`, most populous first.
+pub(crate) fn aggregate(cells: &[GridCell], level: u8) -> Vec<S2Cell> {
+    let mut sums: HashMap<u64, (f64, i64)> = HashMap::new();
+    for c in cells {
+        let entry = sums.entry(cell_id(c.lat, c.lon, level)).or_default();
+        entry.0 += c.population as f64;
+        entry.1 += 1;
+    }
+
+    let mut s2_cells: Vec<S2Cell> = sums
+        .into_iter()
+        .map(|(id, (population, cell_count))| {
+            let (lat, lon) = centre(id);
+            S2Cell {
+                token: token(id),
+                id: (id as i64).to_string(),
+                lat,
+                lon,
+                population: (population * 10.0).round() / 10.0,
+                cell_count,
+                boundary: boundary(id),
+            }
+        })
+        .collect();
+    s2_cells.sort_by(|a, b| b.population.total_cmp(&a.population).then_with(|| a.token.cmp(&b.token)));
+    s2_cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CellBounds;
+
+    #[test]
+    fn ids_match_the_s2_library() {
+        // Face cells, and well-known city prefixes.
+        assert_eq!(token(cell_id(0.0, 0.0, 0)), "1");
+        assert_eq!(token(cell_id(0.0, 90.0, 0)), "3");
+        assert_eq!(token(cell_id(90.0, 0.0, 0)), "5");
+        assert_eq!(token(cell_id(-90.0, 0.0, 0)), "b");
+        assert!(token(cell_id(40.7128, -74.0060, 12)).starts_with("89c25"));
+        assert!(token(cell_id(51.5074, -0.1278, 12)).starts_with("4876"));
+
+        let id = cell_id(6.9271, 79.8612, 12);
+        assert_eq!(level(id), 12);
+        assert_eq!(parent(cell_id(6.9271, 79.8612, 20), 12), id);
+        assert_eq!(parse(&token(id)), Some(id));
+        assert_eq!(parse(&(id as i64).to_string()), Some(id));
+        assert_eq!(parse(&id.to_string()), Some(id));
+        for raw in ["", "0", "X", "zz", "f", "18446744073709551616"] {
+            assert_eq!(parse(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn cells_contain_their_centre_and_bounds() {
+        for (lat, lon) in [(6.9271, 79.8612), (-33.8688, 151.2093), (64.1466, -21.9426), (0.0, 0.0)] {
+            for level in [MIN_LOOKUP_LEVEL, 12, 18] {
+                let id = cell_id(lat, lon, level);
+                let (c_lat, c_lon) = centre(id);
+                assert_eq!(cell_id(c_lat, c_lon, level), id);
+                let [min_lon, min_lat, max_lon, max_lat] = bounds(id).unwrap();
+                assert!((min_lon..=max_lon).contains(&lon) && (min_lat..=max_lat).contains(&lat));
+            }
+        }
+        // Level-12 cells average about 5 km².
+        let area = area_km2(cell_id(6.9271, 79.8612, 12));
+        assert!((3.0..8.0).contains(&area), "{area}");
+        // Poles sit on cell corners, so polar cells keep a bounding box.
+        let [_, _, _, max_lat] = bounds(cell_id(90.0, 0.0, 6)).unwrap();
+        assert_eq!(max_lat, 90.0);
+    }
+
+    #[test]
+    fn aggregation_keeps_every_cell_once() {
+        let cells: Vec<GridCell> = (0..10)
+            .flat_map(|r| (0..10).map(move |c| (r, c)))
+            .map(|(r, c)| {
+                let (lat, lon) = (6.9 + (r as f64 + 0.5) / 120.0, 79.8 + (c as f64 + 0.5) / 120.0);
+                GridCell {
+                    lat,
+                    lon,
+                    population: 10.0,
+                    bounds: CellBounds { min_lat: lat, max_lat: lat, min_lon: lon, max_lon: lon },
+                    uncertainty: None,
+                }
+            })
+            .collect();
+
+        for at in [10, 12, 14] {
+            let s2_cells = aggregate(&cells, at);
+            assert_eq!(s2_cells.iter().map(|c| c.cell_count).sum::<i64>(), 100, "level {at}");
+            for cell in &s2_cells {
+                let id = parse(&cell.token).unwrap();
+                assert_eq!(level(id), at);
+                assert_eq!(population_in(id, &cells), (cell.population, cell.cell_count));
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! USGS ShakeMap contours behind `/exposure/shakemap`.
+//!
+//! A ShakeMap's `cont_mmi.json` product is a FeatureCollection of Modified
+//! Mercalli intensity contours, one feature per level, drawn as closed lines or
+//! polygons. Callers either post that file or name the event, in which case it
+//! is fetched from the ComCat event service at `SHAKEMAP_URL`.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::errors::AppError;
+use crate::validation::MAX_MMI_LEVELS;
+
+const SHAKEMAP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Handle on the configured USGS event service.
+pub(crate) struct ShakeMapSource {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl ShakeMapSource {
+    /// `None` when `url` is empty, i.e. fetching by event ID is disabled.
+    pub fn new(url: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        if url.is_empty() {
+            return None;
+        }
+        match reqwest::Client::builder().timeout(SHAKEMAP_TIMEOUT).build() {
+            Ok(http) => Some(Self { http, url: url.to_string() }),
+            Err(e) => {
+                log::error!("ShakeMap fetching disabled: failed to build HTTP client: {e}");
+                None
+            }
+        }
+    }
+
+    /// The MMI contour GeoJSON of `event_id`'s preferred ShakeMap. An unknown
+    /// event, or one without a ShakeMap, is `NotFound`; anything else that
+    /// fails is `Upstream`.
+    pub async fn contours(&self, event_id: &str) -> Result<Value, AppError> {
+        let response = self
+            .http
+            .get(&self.url)
+            .query(&[("eventid", event_id), ("format", "geojson")])
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("USGS event service: {e}")))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::NO_CONTENT {
+            return Err(AppError::NotFound(format!("USGS has no event `{event_id}`")));
+        }
+        if !status.is_success() {
+            return Err(AppError::Upstream(format!("USGS event service returned {status}")));
+        }
+        let event: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("USGS event service: {e}")))?;
+
+        let url = contour_url(&event)
+            .ok_or_else(|| AppError::NotFound(format!("Event `{event_id}` has no ShakeMap MMI contours")))?;
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("USGS ShakeMap: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::Upstream(format!("USGS ShakeMap returned {status}")));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("USGS ShakeMap: {e}")))
+    }
+}
+
+/// Download URL of the preferred ShakeMap's `cont_mmi.json` in a ComCat event.
+fn contour_url(event: &Value) -> Option<&str> {
+    event["properties"]["products"]["shakemap"].get(0)?["contents"]["download/cont_mmi.json"]["url"].as_str()
+}
+
+/// The contours of a `cont_mmi.json` FeatureCollection, one MultiPolygon per
+/// MMI level, highest level first. Closed contour lines become polygon rings;
+/// open ones (cut off at the map edge) enclose nothing and are skipped.
+pub fn mmi_contours(collection: &Value) -> Result<Vec<(f64, Value)>, String> {
+    let features = collection["features"]
+        .as_array()
+        .ok_or("contours must be a GeoJSON FeatureCollection")?;
+
+    // Keyed by tenths of an MMI unit so levels sort and group exactly.
+    let mut levels: BTreeMap<i64, Vec<Value>> = BTreeMap::new();
+    for (i, feature) in features.iter().enumerate() {
+        let properties = &feature["properties"];
+        let mmi = properties["value"]
+            .as_f64()
+            .or_else(|| properties["mmi"].as_f64())
+            .filter(|m| (0.0..=12.0).contains(m))
+            .ok_or_else(|| format!("feature {i} has no MMI `value` between 0 and 12"))?;
+        let polygons = levels.entry((mmi * 10.0).round() as i64).or_default();
+        let geometry = &feature["geometry"];
+        let coordinates = &geometry["coordinates"];
+        match geometry["type"].as_str() {
+            Some("Polygon") => polygons.push(coordinates.clone()),
+            Some("MultiPolygon") => polygons.extend(coordinates.as_array().into_iter().flatten().cloned()),
+            Some("LineString") => polygons.extend(closed_ring(coordinates)),
+            Some("MultiLineString") => {
+                polygons.extend(coordinates.as_array().into_iter().flatten().filter_map(closed_ring))
+            }
+            _ => return Err(format!("feature {i} is not a contour line or polygon")),
+        }
+    }
+
+    levels.retain(|_, polygons| !polygons.is_empty());
+    if levels.is_empty() {
+        return Err("contours enclose no area".into());
+    }
+    if levels.len() > MAX_MMI_LEVELS {
+        return Err(format!("at most {MAX_MMI_LEVELS} MMI levels are supported"));
+    }
+    Ok(levels
+        .into_iter()
+        .rev()
+        .map(|(tenths, polygons)| {
+            (tenths as f64 / 10.0, json!({ "type": "MultiPolygon", "coordinates": polygons }))
+        })
+        .collect())
+}
+
+/// A line whose ends meet, as the coordinates of a one-ring polygon.
+fn closed_ring(line: &Value) -> Option<Value> {
+    let points = line.as_array()?;
+    (points.len() >= 4 && points.first() == points.last()).then(|| json!([points]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(mmi: f64, geometry: Value) -> Value {
+        json!({ "type": "Feature", "properties": { "value": mmi }, "geometry": geometry })
+    }
+
+    #[test]
+    fn groups_closed_contours_by_level() {
+        let ring = json!([[37.0, 37.0], [37.4, 37.0], [37.4, 37.4], [37.0, 37.0]]);
+        let open = json!([[36.0, 36.0], [36.5, 36.0], [36.5, 36.5]]);
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": [
+                feature(6.0, json!({ "type": "MultiLineString", "coordinates": [ring, open] })),
+                feature(8.0, json!({ "type": "Polygon", "coordinates": [ring] })),
+                feature(6.0, json!({ "type": "LineString", "coordinates": ring })),
+                feature(5.0, json!({ "type": "LineString", "coordinates": open })),
+            ]
+        });
+        let contours = mmi_contours(&collection).unwrap();
+        let levels: Vec<f64> = contours.iter().map(|(mmi, _)| *mmi).collect();
+        assert_eq!(levels, [8.0, 6.0]);
+        assert_eq!(contours[1].1["type"], "MultiPolygon");
+        assert_eq!(contours[1].1["coordinates"].as_array().unwrap().len(), 2);
+        assert_eq!(contours[1].1["coordinates"][0][0], ring);
+
+        assert!(mmi_contours(&json!({ "features": [feature(5.0, json!({ "type": "LineString", "coordinates": open }))] })).is_err());
+        assert!(mmi_contours(&json!({ "features": [feature(13.0, json!({ "type": "Polygon", "coordinates": [ring] }))] })).is_err());
+        assert!(mmi_contours(&json!({ "type": "Point" })).is_err());
+    }
+
+    #[test]
+    fn finds_contour_download() {
+        let event = json!({ "properties": { "products": { "shakemap": [{
+            "contents": { "download/cont_mmi.json": { "url": "https://example.org/cont_mmi.json" } }
+        }] } } });
+        assert_eq!(contour_url(&event), Some("https://example.org/cont_mmi.json"));
+        assert!(contour_url(&json!({ "properties": { "products": {} } })).is_none());
+        assert!(ShakeMapSource::new("").is_none());
+    }
+}
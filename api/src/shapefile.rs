@@ -0,0 +1,203 @@
+//! Zipped ESRI Shapefiles of `/exposure?format=shapefile`, for desktop GIS
+//! that cannot read our JSON.
+//!
+//! Each grid cell becomes a rectangle in the `.shp` / `.shx` pair, with its
+//! population and centre in the `.dbf` table. The `.prj` declares WGS 84, so
+//! the layer lands in the right place without asking the user for a CRS.
+
+use std::io::{Cursor, Write};
+
+use chrono::{Datelike, NaiveDate};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::errors::AppError;
+use crate::models::GridCell;
+
+pub(crate) const CONTENT_TYPE: &str = "application/zip";
+
+const SHAPE_POLYGON: i32 = 5;
+const HEADER_LEN: usize = 100;
+/// Shape type, box, part and point counts, one part index and five points.
+const CELL_RECORD_LEN: usize = 4 + 32 + 4 + 4 + 4 + 5 * 16;
+
+const WGS84_PRJ: &str = "GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",6378137.0,298.257223563]],\
+    PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]";
+
+/// `.dbf` columns: name, width and decimals of each numeric field.
+const FIELDS: [(&str, u8, u8); 3] = [("POPULATION", 14, 1), ("LAT", 10, 5), ("LON", 11, 5)];
+
+/// A zip holding `<name>.shp`, `.shx`, `.dbf` and `.prj` for `cells`, with
+/// `updated` as the table's last-update date.
+pub(crate) fn zipped(name: &str, cells: &[GridCell], updated: NaiveDate) -> Result<Vec<u8>, AppError> {
+    let (shp, shx) = shapes(cells);
+    let files: [(&str, Vec<u8>); 4] = [
+        ("shp", shp),
+        ("shx", shx),
+        ("dbf", table(cells, updated)),
+        ("prj", WGS84_PRJ.as_bytes().to_vec()),
+    ];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (ext, bytes) in files {
+        zip.start_file(format!("{name}.{ext}"), options).map_err(zip_error)?;
+        zip.write_all(&bytes).map_err(|e| zip_error(e.into()))?;
+    }
+    Ok(zip.finish().map_err(zip_error)?.into_inner())
+}
+
+fn zip_error(e: zip::result::ZipError) -> AppError {
+    AppError::Database(format!("failed to write shapefile archive: {e}"))
+}
+
+/// The `.shp` and `.shx` files: one polygon record per cell, and the offset
+/// of each record.
+fn shapes(cells: &[GridCell]) -> (Vec<u8>, Vec<u8>) {
+    let extent = cells.iter().fold(None, |acc: Option<[f64; 4]>, c| {
+        let b = &c.bounds;
+        Some(match acc {
+            None => [b.min_lon, b.min_lat, b.max_lon, b.max_lat],
+            Some([x0, y0, x1, y1]) => [x0.min(b.min_lon), y0.min(b.min_lat), x1.max(b.max_lon), y1.max(b.max_lat)],
+        })
+    });
+    let extent = extent.unwrap_or_default();
+
+    let shp_len = HEADER_LEN + cells.len() * (8 + CELL_RECORD_LEN);
+    let shx_len = HEADER_LEN + cells.len() * 8;
+    let mut shp = header(shp_len, extent);
+    let mut shx = header(shx_len, extent);
+
+    for (i, cell) in cells.iter().enumerate() {
+        let b = &cell.bounds;
+        shx.extend(((shp.len() / 2) as i32).to_be_bytes());
+        shx.extend(((CELL_RECORD_LEN / 2) as i32).to_be_bytes());
+
+        shp.extend((i as i32 + 1).to_be_bytes());
+        shp.extend(((CELL_RECORD_LEN / 2) as i32).to_be_bytes());
+        shp.extend(SHAPE_POLYGON.to_le_bytes());
+        for v in [b.min_lon, b.min_lat, b.max_lon, b.max_lat] {
+            shp.extend(v.to_le_bytes());
+        }
+        shp.extend(1i32.to_le_bytes());
+        shp.extend(5i32.to_le_bytes());
+        shp.extend(0i32.to_le_bytes());
+        // Outer rings run clockwise.
+        for [x, y] in [
+            [b.min_lon, b.min_lat],
+            [b.min_lon, b.max_lat],
+            [b.max_lon, b.max_lat],
+            [b.max_lon, b.min_lat],
+            [b.min_lon, b.min_lat],
+        ] {
+            shp.extend(x.to_le_bytes());
+            shp.extend(y.to_le_bytes());
+        }
+    }
+    (shp, shx)
+}
+
+/// The 100-byte header shared by `.shp` and `.shx`; lengths count 16-bit words.
+fn header(file_len: usize, [min_x, min_y, max_x, max_y]: [f64; 4]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(file_len);
+    out.extend(9994i32.to_be_bytes());
+    out.extend([0; 20]);
+    out.extend(((file_len / 2) as i32).to_be_bytes());
+    out.extend(1000i32.to_le_bytes());
+    out.extend(SHAPE_POLYGON.to_le_bytes());
+    for v in [min_x, min_y, max_x, max_y, 0.0, 0.0, 0.0, 0.0] {
+        out.extend(f64::to_le_bytes(v));
+    }
+    out
+}
+
+/// The dBase III `.dbf` attribute table, one row per cell in record order.
+fn table(cells: &[GridCell], updated: NaiveDate) -> Vec<u8> {
+    let header_len = 32 + FIELDS.len() * 32 + 1;
+    let record_len = 1 + FIELDS.iter().map(|f| f.1 as usize).sum::<usize>();
+
+    let mut out = Vec::with_capacity(header_len + cells.len() * record_len + 1);
+    out.push(0x03);
+    out.extend([(updated.year() - 1900) as u8, updated.month() as u8, updated.day() as u8]);
+    out.extend((cells.len() as u32).to_le_bytes());
+    out.extend((header_len as u16).to_le_bytes());
+    out.extend((record_len as u16).to_le_bytes());
+    out.extend([0; 20]);
+    for (name, width, decimals) in FIELDS {
+        let mut field = [0u8; 32];
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        field[11] = b'N';
+        field[16] = width;
+        field[17] = decimals;
+        out.extend(field);
+    }
+    out.push(0x0D);
+
+    for cell in cells {
+        out.push(b' ');
+        let values = [cell.population as f64, cell.lat, cell.lon];
+        for ((_, width, decimals), v) in FIELDS.iter().zip(values) {
+            let (width, decimals) = (*width as usize, *decimals as usize);
+            out.extend(format!("{v:>width$.decimals$}").as_bytes());
+        }
+    }
+    out.push(0x1A);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CellBounds;
+
+    fn cell(lat: f64, lon: f64, population: f32) -> GridCell {
+        let half = 1.0 / 240.0;
+        GridCell {
+            lat,
+            lon,
+            population,
+            bounds: CellBounds { min_lat: lat - half, max_lat: lat + half, min_lon: lon - half, max_lon: lon + half },
+            uncertainty: None,
+        }
+    }
+
+    fn be(bytes: &[u8], at: usize) -> i32 {
+        i32::from_be_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn shp_and_shx_lengths_and_offsets_agree() {
+        let cells = [cell(6.925, 79.858, 2_200.0), cell(6.933, 79.867, 1_500.0)];
+        let (shp, shx) = shapes(&cells);
+        assert_eq!(be(&shp, 24) as usize * 2, shp.len());
+        assert_eq!(be(&shx, 24) as usize * 2, shx.len());
+        assert_eq!(shx.len(), HEADER_LEN + 2 * 8);
+        let second = be(&shx, HEADER_LEN + 8) as usize * 2;
+        assert_eq!(be(&shp, second), 2, "record numbers start at 1");
+        let min_x = f64::from_le_bytes(shp[36..44].try_into().unwrap());
+        assert!((min_x - (79.858 - 1.0 / 240.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dbf_rows_are_fixed_width() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let dbf = table(&[cell(6.925, 79.858, 2_200.0)], date);
+        assert_eq!(&dbf[1..4], &[126, 3, 1]);
+        assert_eq!(u32::from_le_bytes(dbf[4..8].try_into().unwrap()), 1);
+        let header_len = u16::from_le_bytes([dbf[8], dbf[9]]) as usize;
+        let record_len = u16::from_le_bytes([dbf[10], dbf[11]]) as usize;
+        assert_eq!(dbf.len(), header_len + record_len + 1);
+        let row = std::str::from_utf8(&dbf[header_len..header_len + record_len]).unwrap();
+        assert_eq!(row, "         2200.0   6.92500   79.85800");
+    }
+
+    #[test]
+    fn archive_holds_the_four_files() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let bytes = zipped("cells", &[cell(6.925, 79.858, 2_200.0)], date).unwrap();
+        let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut names: Vec<_> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["cells.dbf", "cells.prj", "cells.shp", "cells.shx"]);
+    }
+}
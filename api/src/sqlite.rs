@@ -0,0 +1,476 @@
+//! A write-once SQLite database file, enough for the GeoPackages of
+//! `/export/geopackage` without linking SQLite itself.
+//!
+//! Tables are written as rowid b-trees from rows supplied in full, so there
+//! are no free pages, no journal and no balancing: leaves are packed in rowid
+//! order and interior pages built over them. Payloads too large for a page
+//! spill into overflow chains as SQLite would place them. Indexes are limited
+//! to the automatic indexes of `PRIMARY KEY` / `UNIQUE` text columns on small
+//! metadata tables, and must fit in a single leaf page.
+//!
+//! See <https://www.sqlite.org/fileformat2.html> for the layout.
+
+use crate::errors::AppError;
+
+const PAGE_SIZE: usize = 4096;
+/// Page 1 starts with the 100-byte database header.
+const DB_HEADER_LEN: usize = 100;
+const TABLE_LEAF: u8 = 0x0D;
+const TABLE_INTERIOR: u8 = 0x05;
+const INDEX_LEAF: u8 = 0x0A;
+/// Children per interior page; at most 15 bytes each, well inside a page.
+const INTERIOR_FANOUT: usize = 250;
+/// The page holding the byte range SQLite locks, which must stay unused.
+const LOCK_BYTE_PAGE: usize = (1 << 30) / PAGE_SIZE + 1;
+/// SQLite release the file claims to be written by (3.40.1).
+const WRITER_VERSION: u32 = 3_040_001;
+
+/// A column value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Integer(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Real(v)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+/// A table with its rows, keyed by rowid.
+pub(crate) struct Table {
+    name: String,
+    sql: String,
+    rows: Vec<(i64, Vec<Value>)>,
+    /// Key columns of each automatic index, numbered from 1 as SQLite names them.
+    indexes: Vec<Vec<usize>>,
+}
+
+impl Table {
+    /// A table created by `sql`. An `INTEGER PRIMARY KEY` column is the rowid:
+    /// pass `Value::Null` for it in each row.
+    pub fn new(name: &str, sql: impl Into<String>) -> Self {
+        Self { name: name.to_string(), sql: sql.into(), rows: Vec::new(), indexes: Vec::new() }
+    }
+
+    pub fn row(&mut self, rowid: i64, values: Vec<Value>) {
+        self.rows.push((rowid, values));
+    }
+
+    /// Declares the next `sqlite_autoindex_<table>_N` index, in the order its
+    /// `PRIMARY KEY` or `UNIQUE` constraint appears in the `CREATE TABLE`.
+    pub fn autoindex(mut self, columns: &[usize]) -> Self {
+        self.indexes.push(columns.to_vec());
+        self
+    }
+}
+
+/// Writes a database holding `tables`, with the given `application_id` and
+/// `user_version` header fields.
+pub(crate) fn write(tables: Vec<Table>, application_id: u32, user_version: u32) -> Result<Vec<u8>, AppError> {
+    let mut pages = Pages(vec![Vec::new()]);
+    let mut schema = Vec::new();
+    for mut table in tables {
+        table.rows.sort_by_key(|(rowid, _)| *rowid);
+        let root = pages.table_tree(&table.rows)?;
+        schema.push(vec![
+            "table".into(),
+            table.name.as_str().into(),
+            table.name.as_str().into(),
+            Value::Integer(root as i64),
+            table.sql.into(),
+        ]);
+        for (i, columns) in table.indexes.iter().enumerate() {
+            let root = pages.index_leaf(&table.rows, columns)?;
+            schema.push(vec![
+                "index".into(),
+                format!("sqlite_autoindex_{}_{}", table.name, i + 1).into(),
+                table.name.as_str().into(),
+                Value::Integer(root as i64),
+                Value::Null,
+            ]);
+        }
+    }
+
+    let schema: Vec<(i64, Vec<Value>)> = schema.into_iter().zip(1..).map(|(row, rowid)| (rowid, row)).collect();
+    let mut first = vec![0u8; DB_HEADER_LEN];
+    let cells = schema
+        .iter()
+        .map(|(rowid, values)| pages.table_cell(*rowid, values))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !fits(DB_HEADER_LEN + 8, &cells) {
+        return Err(internal("schema does not fit on the first page"));
+    }
+    first.extend(btree_page(TABLE_LEAF, DB_HEADER_LEN, &cells, None));
+    pages.0[0] = first;
+
+    let page_count = pages.0.len();
+    if page_count >= LOCK_BYTE_PAGE {
+        return Err(internal("database would exceed 1 GiB"));
+    }
+    let header = &mut pages.0[0][..DB_HEADER_LEN];
+    header[..16].copy_from_slice(b"SQLite format 3\0");
+    header[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+    header[18] = 1; // legacy (rollback journal) write version
+    header[19] = 1; // and read version
+    header[21] = 64; // maximum embedded payload fraction
+    header[22] = 32; // minimum embedded payload fraction
+    header[23] = 32; // leaf payload fraction
+    header[24..28].copy_from_slice(&1u32.to_be_bytes()); // file change counter
+    header[28..32].copy_from_slice(&(page_count as u32).to_be_bytes());
+    header[40..44].copy_from_slice(&1u32.to_be_bytes()); // schema cookie
+    header[44..48].copy_from_slice(&4u32.to_be_bytes()); // schema format
+    header[56..60].copy_from_slice(&1u32.to_be_bytes()); // UTF-8
+    header[60..64].copy_from_slice(&user_version.to_be_bytes());
+    header[68..72].copy_from_slice(&application_id.to_be_bytes());
+    header[92..96].copy_from_slice(&1u32.to_be_bytes()); // version-valid-for
+    header[96..100].copy_from_slice(&WRITER_VERSION.to_be_bytes());
+
+    let mut out = Vec::with_capacity(page_count * PAGE_SIZE);
+    for page in pages.0 {
+        out.extend_from_slice(&page);
+        out.resize(out.len().next_multiple_of(PAGE_SIZE), 0);
+    }
+    Ok(out)
+}
+
+fn internal(message: &str) -> AppError {
+    AppError::Database(format!("failed to write SQLite database: {message}"))
+}
+
+/// Pages written so far; page N is at index N - 1. Page 1 is filled last.
+struct Pages(Vec<Vec<u8>>);
+
+impl Pages {
+    fn push(&mut self, page: Vec<u8>) -> usize {
+        self.0.push(page);
+        self.0.len()
+    }
+
+    /// Writes a table b-tree and returns its root page.
+    fn table_tree(&mut self, rows: &[(i64, Vec<Value>)]) -> Result<usize, AppError> {
+        // (page, largest rowid) of each page of the current level.
+        let mut level = Vec::new();
+        let mut cells = Vec::new();
+        let mut used = 8;
+        let mut last_rowid = 0;
+        for (rowid, values) in rows {
+            let cell = self.table_cell(*rowid, values)?;
+            if !cells.is_empty() && used + cell.len() + 2 > PAGE_SIZE {
+                let page = self.push(btree_page(TABLE_LEAF, 0, &cells, None));
+                level.push((page, last_rowid));
+                cells.clear();
+                used = 8;
+            }
+            used += cell.len() + 2;
+            cells.push(cell);
+            last_rowid = *rowid;
+        }
+        if !cells.is_empty() || level.is_empty() {
+            let page = self.push(btree_page(TABLE_LEAF, 0, &cells, None));
+            level.push((page, last_rowid));
+        }
+
+        while level.len() > 1 {
+            level = level
+                .chunks(INTERIOR_FANOUT)
+                .map(|children| {
+                    let (last, rest) = children.split_last().expect("chunks are non-empty");
+                    let cells: Vec<Vec<u8>> = rest
+                        .iter()
+                        .map(|(page, rowid)| {
+                            let mut cell = (*page as u32).to_be_bytes().to_vec();
+                            put_varint(&mut cell, *rowid as u64);
+                            cell
+                        })
+                        .collect();
+                    let page = self.push(btree_page(TABLE_INTERIOR, 0, &cells, Some(last.0)));
+                    (page, last.1)
+                })
+                .collect();
+        }
+        Ok(level[0].0)
+    }
+
+    /// A table leaf cell, spilling the end of a large payload to overflow pages.
+    fn table_cell(&mut self, rowid: i64, values: &[Value]) -> Result<Vec<u8>, AppError> {
+        let payload = record(values);
+        let usable = PAGE_SIZE;
+        let max_local = usable - 35;
+        let min_local = (usable - 12) * 32 / 255 - 23;
+        let local = if payload.len() <= max_local {
+            payload.len()
+        } else {
+            let k = min_local + (payload.len() - min_local) % (usable - 4);
+            if k <= max_local { k } else { min_local }
+        };
+
+        let mut cell = Vec::with_capacity(local + 22);
+        put_varint(&mut cell, payload.len() as u64);
+        put_varint(&mut cell, rowid as u64);
+        cell.extend_from_slice(&payload[..local]);
+        if local < payload.len() {
+            let first = self.overflow_chain(&payload[local..])?;
+            cell.extend((first as u32).to_be_bytes());
+        }
+        Ok(cell)
+    }
+
+    /// Writes `data` across linked overflow pages and returns the first.
+    fn overflow_chain(&mut self, data: &[u8]) -> Result<usize, AppError> {
+        let chunks: Vec<&[u8]> = data.chunks(PAGE_SIZE - 4).collect();
+        let first = self.0.len() + 1;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let next = if i + 1 < chunks.len() { first + i + 1 } else { 0 };
+            let mut page = (next as u32).to_be_bytes().to_vec();
+            page.extend_from_slice(chunk);
+            self.push(page);
+        }
+        Ok(first)
+    }
+
+    /// Writes an index of `columns` over `rows` as one leaf page and returns it.
+    fn index_leaf(&mut self, rows: &[(i64, Vec<Value>)], columns: &[usize]) -> Result<usize, AppError> {
+        let mut keys: Vec<Vec<Value>> = rows
+            .iter()
+            .map(|(rowid, values)| {
+                let mut key: Vec<Value> = columns.iter().map(|&c| values[c].clone()).collect();
+                key.push(Value::Integer(*rowid));
+                key
+            })
+            .collect();
+        keys.sort_by(|a, b| compare_keys(a, b));
+
+        let max_local = (PAGE_SIZE - 12) * 64 / 255 - 23;
+        let mut cells = Vec::new();
+        for key in keys {
+            let payload = record(&key);
+            if payload.len() > max_local {
+                return Err(internal("index key too large"));
+            }
+            let mut cell = Vec::new();
+            put_varint(&mut cell, payload.len() as u64);
+            cell.extend(payload);
+            cells.push(cell);
+        }
+        if !fits(8, &cells) {
+            return Err(internal("index does not fit in one page"));
+        }
+        Ok(self.push(btree_page(INDEX_LEAF, 0, &cells, None)))
+    }
+}
+
+/// Whether `cells` fit on a page after `header_end` bytes of headers.
+fn fits(header_end: usize, cells: &[Vec<u8>]) -> bool {
+    header_end + cells.iter().map(|c| c.len() + 2).sum::<usize>() <= PAGE_SIZE
+}
+
+/// A b-tree page whose header starts at `offset`, with `cells` in key order
+/// packed against the end of the page. Interior pages carry `right_most`.
+fn btree_page(kind: u8, offset: usize, cells: &[Vec<u8>], right_most: Option<usize>) -> Vec<u8> {
+    let mut content = vec![0u8; PAGE_SIZE];
+    let mut start = PAGE_SIZE;
+    let mut pointers = Vec::with_capacity(cells.len() * 2);
+    for cell in cells {
+        start -= cell.len();
+        content[start..start + cell.len()].copy_from_slice(cell);
+        pointers.extend((start as u16).to_be_bytes());
+    }
+
+    let mut header = vec![kind];
+    header.extend(0u16.to_be_bytes()); // no freeblocks
+    header.extend((cells.len() as u16).to_be_bytes());
+    header.extend((start as u16).to_be_bytes());
+    header.push(0); // fragmented bytes
+    if let Some(page) = right_most {
+        header.extend((page as u32).to_be_bytes());
+    }
+    let end = offset + header.len();
+    content[offset..end].copy_from_slice(&header);
+    content[end..end + pointers.len()].copy_from_slice(&pointers);
+    content.drain(..offset);
+    content
+}
+
+/// The record format: a header of serial types, then the values.
+fn record(values: &[Value]) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+    for value in values {
+        let serial = match value {
+            Value::Null => 0,
+            Value::Integer(0) => 8,
+            Value::Integer(1) => 9,
+            Value::Integer(v) => {
+                let (serial, len) = match *v {
+                    -0x80..=0x7f => (1, 1),
+                    -0x8000..=0x7fff => (2, 2),
+                    -0x80_0000..=0x7f_ffff => (3, 3),
+                    -0x8000_0000..=0x7fff_ffff => (4, 4),
+                    -0x8000_0000_0000..=0x7fff_ffff_ffff => (5, 6),
+                    _ => (6, 8),
+                };
+                body.extend_from_slice(&v.to_be_bytes()[8 - len..]);
+                serial
+            }
+            Value::Real(v) => {
+                body.extend(v.to_be_bytes());
+                7
+            }
+            Value::Text(s) => {
+                body.extend_from_slice(s.as_bytes());
+                13 + 2 * s.len() as u64
+            }
+            Value::Blob(b) => {
+                body.extend_from_slice(b);
+                12 + 2 * b.len() as u64
+            }
+        };
+        put_varint(&mut types, serial);
+    }
+
+    let mut header_len = types.len() + 1;
+    while varint_len(header_len as u64) + types.len() != header_len {
+        header_len = varint_len(header_len as u64) + types.len();
+    }
+    let mut out = Vec::with_capacity(header_len + body.len());
+    put_varint(&mut out, header_len as u64);
+    out.extend(types);
+    out.extend(body);
+    out
+}
+
+/// Index key order for the values written here: NULL, then numbers, then
+/// text compared bytewise (the BINARY collation).
+fn compare_keys(a: &[Value], b: &[Value]) -> std::cmp::Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Real(_) => 1,
+            Value::Text(_) => 2,
+            Value::Blob(_) => 3,
+        }
+    }
+    for (x, y) in a.iter().zip(b) {
+        let order = match (x, y) {
+            (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
+            (Value::Real(x), Value::Real(y)) => x.total_cmp(y),
+            (Value::Integer(x), Value::Real(y)) => (*x as f64).total_cmp(y),
+            (Value::Real(x), Value::Integer(y)) => x.total_cmp(&(*y as f64)),
+            (Value::Text(x), Value::Text(y)) => x.as_bytes().cmp(y.as_bytes()),
+            (Value::Blob(x), Value::Blob(y)) => x.cmp(y),
+            _ => rank(x).cmp(&rank(y)),
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// SQLite's big-endian varint: 7 bits per byte, the ninth byte taking 8.
+fn put_varint(out: &mut Vec<u8>, v: u64) {
+    if v >> 56 != 0 {
+        let mut bytes = [0u8; 9];
+        bytes[8] = v as u8;
+        let mut rest = v >> 8;
+        for byte in bytes[..8].iter_mut().rev() {
+            *byte = (rest & 0x7f) as u8 | 0x80;
+            rest >>= 7;
+        }
+        out.extend(bytes);
+        return;
+    }
+    let mut groups = Vec::with_capacity(8);
+    let mut rest = v;
+    loop {
+        groups.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    groups[0] &= 0x7f;
+    out.extend(groups.iter().rev());
+}
+
+fn varint_len(v: u64) -> usize {
+    let mut out = Vec::new();
+    put_varint(&mut out, v);
+    out.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(v: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_varint(&mut out, v);
+        out
+    }
+
+    #[test]
+    fn varints_match_sqlite() {
+        assert_eq!(varint(0), [0x00]);
+        assert_eq!(varint(127), [0x7f]);
+        assert_eq!(varint(128), [0x81, 0x00]);
+        assert_eq!(varint(4326), [0xa1, 0x66]);
+        assert_eq!(varint(u64::MAX), [0xff; 9]);
+    }
+
+    #[test]
+    fn records_pick_the_smallest_serial_types() {
+        let rec = record(&[Value::Null, Value::Integer(1), Value::Integer(300), "ab".into(), Value::Real(0.5)]);
+        assert_eq!(rec[..6], [6, 0, 9, 2, 17, 7]);
+        assert_eq!(rec[6..8], 300i16.to_be_bytes());
+        assert_eq!(&rec[8..10], b"ab");
+        assert_eq!(rec.len(), 18);
+    }
+
+    #[test]
+    fn large_tables_get_interior_pages_and_overflow() {
+        let mut table = Table::new("t", "CREATE TABLE t (id INTEGER PRIMARY KEY, v BLOB)");
+        for id in 1..=2_000 {
+            table.row(id, vec![Value::Null, Value::Blob(vec![7; 40])]);
+        }
+        table.row(2_001, vec![Value::Null, Value::Blob(vec![9; 10_000])]);
+        let db = write(vec![table], 0, 0).unwrap();
+        assert_eq!(db.len() % PAGE_SIZE, 0);
+        assert_eq!(&db[..16], b"SQLite format 3\0");
+        let pages = u32::from_be_bytes(db[28..32].try_into().unwrap()) as usize;
+        assert_eq!(pages * PAGE_SIZE, db.len());
+        // The root, written after its leaves, is the last b-tree page.
+        let root = &db[(pages - 1) * PAGE_SIZE..];
+        assert_eq!(root[0], TABLE_INTERIOR);
+        assert!(db.chunks(PAGE_SIZE).filter(|p| p[0] == TABLE_LEAF).count() > 20);
+    }
+}
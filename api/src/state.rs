@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use deadpool_postgres::Pool;
+use tokio::sync::Semaphore;
+
+use crate::caches::ResponseCache;
+use crate::config::{Config, Limits};
+use crate::counters::RequestCounters;
+use crate::jobs::JobQueue;
+use crate::routing::RoutingEngine;
+use crate::shakemap::ShakeMapSource;
+
+/// Shared application state, handed to handlers as `web::Data<AppState>`.
+/// The query repositories are registered separately as trait objects so
+/// handlers can be tested against mocks.
+pub(crate) struct AppState {
+    /// Primary database; the only pool that takes writes.
+    pub pool: Pool,
+    /// Pool for read-only queries: the replica at `DATABASE_READ_URL`, or the
+    /// primary pool again when none is configured.
+    pub read_pool: Pool,
+    pub config: Config,
+    pub jobs: JobQueue,
+    /// Routing engine for `/exposure/isochrone`, when `ROUTING_URL` is set.
+    pub routing: Option<RoutingEngine>,
+    /// USGS event service for `/exposure/shakemap?event_id=`, unless
+    /// `SHAKEMAP_URL` is empty.
+    pub shakemap: Option<ShakeMapSource>,
+    /// Requests served per route, shared with the `CountRequests` middleware.
+    pub counters: Arc<RequestCounters>,
+    /// In-process caches, cleared by `/admin/cache/flush`.
+    pub caches: Vec<Arc<dyn ResponseCache>>,
+    /// One permit per open `/tag/stream` body, `Limits::max_tag_streams` in all.
+    pub tag_streams: Arc<Semaphore>,
+}
+
+impl AppState {
+    pub fn limits(&self) -> &Limits {
+        &self.config.limits
+    }
+}
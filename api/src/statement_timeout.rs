@@ -0,0 +1,71 @@
+//! Per-endpoint database statement timeouts.
+//!
+//! Point lookups should answer in milliseconds, so a plan that goes wrong
+//! there (a KNN scan over all of `geonames`, say) is cut off after a few
+//! seconds instead of holding a pooled connection for the full
+//! `REQUEST_TIMEOUT_SECS`. The middleware resolves the timeout for the
+//! request path from `Limits::statement_timeout_for` and scopes it to the
+//! handler's task; `PgDb::conn` applies it to every connection checked out
+//! there. Work that outlives the handler future — streamed bodies, jobs —
+//! keeps the repository default.
+
+use std::future::{ready, Ready};
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use tokio::task::futures::TaskLocalFuture;
+
+use crate::config::{self, Limits};
+
+tokio::task_local! {
+    /// Statement timeout in seconds for the request being handled.
+    pub(crate) static STATEMENT_TIMEOUT: u32;
+}
+
+#[derive(Clone)]
+pub(crate) struct StatementTimeout {
+    limits: Limits,
+}
+
+impl StatementTimeout {
+    pub fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for StatementTimeout
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = StatementTimeoutMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(StatementTimeoutMiddleware { service, limits: self.limits.clone() }))
+    }
+}
+
+pub(crate) struct StatementTimeoutMiddleware<S> {
+    service: S,
+    limits: Limits,
+}
+
+impl<S, B> Service<ServiceRequest> for StatementTimeoutMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = TaskLocalFuture<u32, S::Future>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let path = config::api_path(req.path());
+        let secs = self.limits.statement_timeout_for(path);
+        STATEMENT_TIMEOUT.scope(secs, self.service.call(req))
+    }
+}
@@ -0,0 +1,1397 @@
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::{test, App};
+use serde_json::{json, Value};
+
+use super::{get, post_json, send, send_raw, TestDb, ADMIN_KEY};
+use crate::audit::AuditLog;
+use crate::config::{ExposureBackend, API_PREFIX, API_V2_PREFIX};
+use crate::envelope::EnvelopeOptOut;
+use crate::models::AlertLevel;
+use crate::repositories::{EventRecord, EventRepository, Repositories};
+
+/// Colombo; its grid cell holds 2200 people and the whole 5×5 fixture block
+/// (55 000 people) lies within 5 km.
+const LAT: f64 = 6.9271;
+const LON: f64 = 79.8612;
+/// Open sea ~95 km west of the fixture block, outside both country polygons.
+const SEA_LAT: f64 = 6.9;
+const SEA_LON: f64 = 79.0;
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn system_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().wrap(EnvelopeOptOut).configure(db.configure())).await;
+
+    let (status, body) = send(&app, test::TestRequest::get().uri("/")).await;
+    assert_eq!(status, StatusCode::OK);
+    let tables = body["payload"]["tables"].as_array().expect("table stats");
+    assert!(tables.iter().any(|t| t["name"] == "population"), "{body}");
+
+    let (status, body) = send(&app, get("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["payload"]["status"], "ok");
+    assert!(body["payload"].get("pool").is_none() && body["payload"].get("database").is_none(), "{body}");
+    let (status, body) = send(&app, get("/health?detail=true")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["payload"]["database"]["reachable"], true);
+    assert!(body["payload"]["database"]["postgis_version"].as_str().is_some_and(|v| v.starts_with("3.4")), "{body}");
+    assert_eq!(body["payload"]["pool"]["max_size"], 8);
+
+    let (status, _) = send(&app, get("/admin/stats")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = send(&app, get("/admin/stats").insert_header(("X-Admin-Key", ADMIN_KEY))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let stats = &body["payload"];
+    assert!(stats["tables"].as_array().unwrap().iter().any(|t| t["name"] == "population"), "{body}");
+    assert!(stats["indexes"].as_array().is_some_and(|i| !i.is_empty()));
+    assert_eq!(stats["pool"]["max_size"], 8);
+    assert_eq!(stats["database"]["reachable"], true);
+    assert!(stats["database"]["postgis_version"].as_str().is_some_and(|v| v.starts_with("3.4")), "{body}");
+
+    let (status, _) = send(&app, get("/country/LKA")).await;
+    assert_eq!(status, StatusCode::OK);
+    let flush = |prefix: &str| {
+        test::TestRequest::post()
+            .uri(&format!("{API_PREFIX}/admin/cache/flush{prefix}"))
+            .insert_header(("X-Admin-Key", ADMIN_KEY))
+    };
+    let (status, body) = send(&app, flush("?prefix=/population")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["flushed"], 0);
+    let (status, body) = send(&app, flush("?prefix=/country")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["caches"][0]["name"], "etag");
+    assert_eq!(body["payload"]["caches"][0]["flushed"], 1);
+    let (status, _) = send(&app, flush("?prefix=country")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get("/health?envelope=false")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    let (status, body) = send(&app, get("/health").insert_header(("X-GeoPop-Envelope", "false"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn population_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let (status, body) =
+        send(&app, get(&format!("/population?lat={LAT}&lon={LON}&include=uncertainty"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"], 2200.0);
+    assert_eq!(body["payload"]["uncertainty"]["lower"], 1760.0);
+
+    let (status, body) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}&radius=5"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["cell_count"], 25);
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+
+    let points = json!({"points": [
+        {"lat": LAT, "lon": LON}, {"lat": SEA_LAT, "lon": SEA_LON}, {"lat": LAT, "lon": LON}
+    ]});
+    let (status, body) = send(&app, post_json("/population/batch", points)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["results"][0]["population"], 2200.0);
+    assert_eq!(body["payload"]["results"][1]["population"], 0.0);
+    assert_eq!(body["payload"]["results"][2]["population"], 2200.0);
+
+    let (status, body) = send(&app, get("/population/grid?bbox=79.8,6.8,79.9,7.0")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["aggregation"], 1);
+    assert_eq!(body["payload"]["cell_count"], 25);
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+
+    // 25 cells over a cap of 4: 3×3 blocks still give 6, 6×6 blocks give 2.
+    let (status, body) = send(&app, get("/population/grid?bbox=79.8,6.8,79.9,7.0&max_cells=4")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["aggregation"], 6);
+    assert_eq!(body["payload"]["cell_count"], 2);
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+
+    let (status, _) = send(&app, get("/population/grid?bbox=70,0,90,10")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Hexagons partition the cells; each one's lookup sums the same cells.
+    let uri = format!("/population?lat={LAT}&lon={LON}&radius=10&aggregation=h3&resolution=7");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["resolution"], 7);
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    let hexes = body["payload"]["cells"].as_array().unwrap();
+    assert_eq!(hexes.iter().map(|h| h["cell_count"].as_i64().unwrap()).sum::<i64>(), 25);
+    for hex in hexes {
+        let (status, lookup) = send(&app, get(&format!("/population/h3/{}", hex["h3"].as_str().unwrap()))).await;
+        assert_eq!(status, StatusCode::OK, "{lookup}");
+        assert_eq!(lookup["payload"]["population"], hex["population"]);
+        assert_eq!(lookup["payload"]["cell_count"], hex["cell_count"]);
+    }
+    let (status, body) =
+        send(&app, get("/population/grid?bbox=79.8,6.8,79.9,7.0&aggregation=h3&resolution=5")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    let (status, _) =
+        send(&app, get("/population/grid?bbox=79.8,6.8,79.9,7.0&aggregation=h3&resolution=12&max_cells=4")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}&aggregation=h3"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    for index in ["not-a-cell", "8001fffffffffff"] {
+        let (status, _) = send(&app, get(&format!("/population/h3/{index}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{index}");
+    }
+
+    // S2 cells likewise, looked up by token and by BigQuery's signed id.
+    let uri = format!("/population?lat={LAT}&lon={LON}&radius=10&aggregation=s2&level=12");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["level"], 12);
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    let s2_cells = body["payload"]["cells"].as_array().unwrap();
+    assert_eq!(s2_cells.iter().map(|c| c["cell_count"].as_i64().unwrap()).sum::<i64>(), 25);
+    for cell in s2_cells {
+        for key in ["token", "id"] {
+            let (status, lookup) =
+                send(&app, get(&format!("/population/s2/{}", cell[key].as_str().unwrap()))).await;
+            assert_eq!(status, StatusCode::OK, "{lookup}");
+            assert_eq!(lookup["payload"]["token"], cell["token"]);
+            assert_eq!(lookup["payload"]["population"], cell["population"]);
+        }
+    }
+    let (status, body) =
+        send(&app, get("/population/grid?bbox=79.8,6.8,79.9,7.0&aggregation=s2&level=10")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    for cell in ["zz", "3ae", "1"] {
+        let (status, _) = send(&app, get(&format!("/population/s2/{cell}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{cell}");
+    }
+
+    let (status, body) = send(&app, get(&format!("/cell/{}", 9968 * 43200 + 31183))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["row"], 9968);
+    assert_eq!(body["payload"]["col"], 31183);
+    assert_eq!(body["payload"]["population"], 2200.0);
+    assert_eq!(body["payload"]["country"]["iso_a3"], "LKA");
+    assert_eq!(body["payload"]["nearest_place"]["name"], "Colombo");
+    let (status, _) = send(&app, get("/cell/933120000")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The Colombo cell is the median of the 25 fixture cells.
+    let (status, body) = send(&app, get(&format!("/population/rank?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["iso_a3"], "LKA");
+    assert_eq!(body["payload"]["country_percentile"], 50);
+    assert_eq!(body["payload"]["world_percentile"], 50);
+
+    let (status, body) =
+        send(&app, get(&format!("/population/rank?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["density_per_km2"], 0.0);
+    assert_eq!(body["payload"]["country_percentile"], Value::Null);
+    assert_eq!(body["payload"]["world_percentile"], 0);
+
+    let (status, _) = send(&app, get("/population?lat=91&lon=0")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("/population?lat={LAT}&lon={LON}&radius=5&dataset=constrained");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["cell_count"], 13);
+    assert_eq!(body["payload"]["total_population"], 36400.0);
+    let uri = format!("/population?lat={LAT}&lon={LON}&dataset=constrained&include=uncertainty");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["year"], 2025);
+    assert_eq!(body["payload"]["settlement_class"], "urban_centre");
+    assert_eq!(body["payload"]["built_up_m2"], 220000.0);
+    assert_eq!(body["payload"]["night_light_radiance"], 22.0);
+    assert_eq!(body["payload"]["land_cover"], "built_up");
+    let (status, body) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}&year=2020"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["year"], 2020);
+    assert_eq!(body["payload"]["population"], 1100.0);
+    let uri = format!("/population?lat={LAT}&lon={LON}&year=2020&include=uncertainty");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}&year=2015"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    let uri = format!("/population?lat={LAT}&lon={LON}&dataset=constrained&year=2020");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uri = "/population?lat=6%C2%B055%2737.56%22N&lon=79.8612E";
+    let (status, body) = send(&app, get(uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"], 2200.0);
+    let (status, _) = send(&app, get("/population?lat=6.9271E&lon=79.8612")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = "/population?x=8890108.118&y=773006.716&crs=EPSG:3857";
+    let (status, body) = send(&app, get(uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"], 2200.0);
+    assert_eq!(body["payload"]["input_coordinate"]["crs"], "EPSG:3857");
+    assert_eq!(body["payload"]["input_coordinate"]["lat"], LAT);
+    let (status, _) = send(&app, get("/population?lat=6.9&lon=79.8&crs=EPSG:3857")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("/population/change?lat={LAT}&lon={LON}&from_year=2020&to_year=2025");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["from_population"], 1100.0);
+    assert_eq!(body["payload"]["to_population"], 2200.0);
+    assert_eq!(body["payload"]["percent_change"], 100.0);
+    assert!(body["payload"].get("cells").is_none());
+    let uri = format!("/population/change?lat={LAT}&lon={LON}&radius=5&from_year=2020&to_year=2025");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["change"], 27500.0);
+    assert_eq!(body["payload"]["percent_change"], 100.0);
+    let cells = body["payload"]["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 25);
+    assert!(cells.iter().all(|c| c["percent_change"] == 100.0), "{body}");
+    let uri = format!("/population/change?lat={LAT}&lon={LON}&from_year=2025&to_year=2025");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = format!("/population/change?lat={LAT}&lon={LON}&from_year=2015&to_year=2025");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn geocoding_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let (status, body) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["name"], "Colombo");
+    assert_eq!(body["payload"]["address"]["state"], "Western");
+    assert_eq!(body["payload"]["population"], 648034);
+    assert_eq!(body["payload"]["feature_class"], "P");
+    assert_eq!(body["payload"]["feature_code"], "PPLC");
+    let english = body["payload"]["display_name"].clone();
+
+    let req = get(&format!("/reverse?lat={LAT}&lon={LON}")).insert_header(("Accept-Language", "ta, en;q=0.5"));
+    let (status, body) = send(&app, req).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["name"], "Colombo");
+    assert_eq!(body["payload"]["display_name"], "கொழும்பு, மேல் மாகாணம், இலங்கை");
+    assert_eq!(body["payload"]["address"]["country"], "இலங்கை");
+    let (_, body) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&lang=en"))).await;
+    assert_eq!(body["payload"]["display_name"], english, "no English alternates: default names");
+    let (status, _) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&lang=tamil"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) =
+        send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&max_distance_km=10"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, body) =
+        send(&app, get(&format!("/reverse?lat={SEA_LAT}&lon={SEA_LON}&max_distance_km=10"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["payload"]["max_distance_km"], 10.0);
+    assert!(body["payload"]["nearest_distance_km"].as_f64().unwrap() > 10.0);
+    let (status, _) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&max_distance_km=0"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&boundaries=2019"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["address"]["country"], "Sri Lanka");
+    let (status, _) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&boundaries=2000"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Nearest to Colombo, but inside the Central fixture polygon.
+    let (_, body) = send(&app, get("/reverse?lat=7.0&lon=79.86")).await;
+    assert_eq!(body["payload"]["address"]["state"], "Western");
+    let (status, body) = send(&app, get("/reverse?lat=7.0&lon=79.86&mode=contains")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["name"], "Colombo");
+    assert_eq!(body["payload"]["address"]["state"], "Central");
+    assert_eq!(body["payload"]["address"]["country_code"], "lk");
+    let (_, body) = send(&app, get("/reverse?lat=7.0&lon=79.86&mode=contains&boundaries=2019")).await;
+    assert_eq!(body["payload"]["address"]["state"], "Western", "no 2019 polygons: the nearest place's");
+    let (_, body) = send(&app, get(&format!("/reverse?lat={SEA_LAT}&lon={SEA_LON}&mode=contains"))).await;
+    assert_eq!(body["payload"]["address"]["state"], "Western", "no polygon: the nearest place's");
+    let (status, _) = send(&app, get(&format!("/reverse?lat={LAT}&lon={LON}&mode=inside"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get("/admin?lat=7.0&lon=79.86")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["country"]["iso_a3"], "LKA");
+    assert_eq!(body["payload"]["admin1"], json!({"code": "LK.29", "name": "Central"}));
+    assert!(body["payload"]["admin2"].is_null(), "no level 2 layer loaded");
+    let (_, body) = send(&app, get("/admin?lat=7.0&lon=79.86&boundaries=2019")).await;
+    assert_eq!(body["payload"]["country"]["iso_a3"], "LKA");
+    assert!(body["payload"]["admin1"].is_null(), "no level 1 layer loaded for 2019");
+    let (_, body) = send(&app, get(&format!("/admin?lat={LAT}&lon={LON}&lang=ta"))).await;
+    assert_eq!(body["payload"]["country"]["name"], "இலங்கை");
+    assert_eq!(body["payload"]["admin1"]["name"], "மேல் மாகாணம்");
+    let (status, body) = send(&app, get(&format!("/admin?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body["payload"]["country"].is_null());
+    assert!(body["payload"]["admin1"].is_null());
+
+    let (status, body) =
+        send(&app, get(&format!("/geocoding/nearby-countries?lat={LAT}&lon={LON}&radius=200"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["is_land"], true);
+    let countries = body["payload"]["countries"].as_array().unwrap();
+    assert_eq!(countries.len(), 2, "{body}");
+    assert_eq!(countries[0]["iso_a3"], "LKA");
+    assert_eq!(countries[1]["iso_a3"], "IND");
+
+    let (status, body) =
+        send(&app, get(&format!("/geocoding/nearby-cities?lat={LAT}&lon={LON}&radius=20"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_places"], 2);
+    assert_eq!(body["payload"]["places"][0]["name"], "Colombo");
+
+    let (status, body) = send(&app, get(&format!("/geocoding/land-check?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["is_land"], true);
+    assert_eq!(body["payload"]["country"]["iso_a3"], "LKA");
+    let (_, body) = send(&app, get(&format!("/geocoding/land-check?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(body["payload"]["is_land"], false);
+
+    let (status, body) = send(&app, get("/cities/search?q=colo")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["results"][0]["name"], "Colombo");
+    let (_, body) = send(&app, get("/cities/search?q=kandy&country=lk")).await;
+    assert_eq!(body["payload"]["results"][0]["name"], "Kandy");
+    let (_, body) = send(&app, get("/cities/search?q=kandy&country=IN")).await;
+    assert_eq!(body["payload"]["count"], 0);
+
+    let (status, body) = send(&app, get(&format!("/facilities/nearest?lat={LAT}&lon={LON}&type=hospital"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["facility_type"], "hospital");
+    let hospitals = body["payload"]["facilities"].as_array().unwrap();
+    assert_eq!(hospitals.len(), 2, "{body}");
+    assert_eq!(hospitals[0]["name"], "General Hospital Colombo");
+    assert_eq!(hospitals[0]["feature_code"], "HSP");
+    assert_eq!(hospitals[0]["direction"], "SE");
+    assert!((hospitals[0]["distance_km"].as_f64().unwrap() - 1.27).abs() < 0.02, "{body}");
+    assert_eq!(hospitals[1]["name"], "Teaching Hospital Kandy");
+    let (_, body) = send(&app, get(&format!("/facilities/nearest?lat={LAT}&lon={LON}&type=airport&limit=1"))).await;
+    assert_eq!(body["payload"]["facilities"][0]["name"], "Bandaranaike International Airport");
+    assert_eq!(body["payload"]["facilities"][0]["direction"], "N");
+    let (_, body) = send(&app, get(&format!("/facilities/nearest?lat={LAT}&lon={LON}&type=school"))).await;
+    assert_eq!(body["payload"]["facilities"], json!([]));
+    let (status, _) = send(&app, get(&format!("/facilities/nearest?lat={LAT}&lon={LON}&type=bank"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, get(&format!("/facilities/nearest?lat={LAT}&lon={LON}&type=port&limit=51"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get(&format!("/facilities?lat={LAT}&lon={LON}&radius=30"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_facilities"], 3);
+    let groups = body["payload"]["groups"].as_array().unwrap();
+    let types: Vec<_> = groups.iter().map(|g| g["facility_type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["hospital", "airport", "port", "school"]);
+    assert_eq!(groups[0]["facilities"][0]["name"], "General Hospital Colombo");
+    assert_eq!(groups[2]["facilities"][0]["direction"], "NW");
+    assert_eq!(groups[3]["count"], 0);
+    assert_eq!(groups[3]["facilities"], json!([]));
+    let (_, body) = send(&app, get(&format!("/facilities?lat={LAT}&lon={LON}&radius=200&types=hospital&limit=1"))).await;
+    let groups = body["payload"]["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 1, "{body}");
+    assert_eq!(groups[0]["count"], 2);
+    assert_eq!(groups[0]["facilities"].as_array().unwrap().len(), 1);
+    let (status, _) = send(&app, get(&format!("/facilities?lat={LAT}&lon={LON}&types=hospital,clinic"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get(&format!("/airports/nearest?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let airports = body["payload"]["airports"].as_array().unwrap();
+    assert_eq!(airports.len(), 2, "heliport skipped: {body}");
+    assert_eq!(airports[0]["iata_code"], "RML");
+    assert_eq!(airports[0]["airport_type"], "medium_airport");
+    assert_eq!(airports[0]["direction"], "S");
+    assert_eq!(airports[0]["longest_runway_m"], 1830);
+    assert_eq!(airports[0]["runways"][0]["designator"], "04/22");
+    assert_eq!(airports[0]["runways"][1]["surface"], "GRS");
+    let (_, body) = send(&app, get(&format!("/airports/nearest?lat={LAT}&lon={LON}&min_runway_m=2000"))).await;
+    let airports = body["payload"]["airports"].as_array().unwrap();
+    assert_eq!(airports.len(), 1, "{body}");
+    assert_eq!(airports[0]["icao_code"], "VCBI");
+    assert_eq!(airports[0]["iata_code"], "CMB");
+    assert_eq!(airports[0]["elevation_m"], 9);
+    assert_eq!(airports[0]["runways"][0]["length_m"], 3350);
+    assert_eq!(airports[0]["runways"][0]["width_m"], 45);
+    let (status, _) = send(&app, get(&format!("/airports/nearest?lat={LAT}&lon={LON}&min_runway_m=7000"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get(&format!("/distance?from={LAT},{LON}&to={SEA_LAT},{SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["distance_km"], 95.113);
+    assert_eq!(body["payload"]["direction"], "W");
+    assert!(body["payload"]["geodesic_km"].as_f64().is_some());
+    let (status, _) = send(&app, get(&format!("/distance?from={LAT},{LON}&to=91,0"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn country_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let (status, body) = send(&app, get(&format!("/country?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["iso_a3"], "LKA");
+    assert_eq!(body["payload"]["boundary_vintage"], 2025);
+
+    // At sea the nearest country is returned.
+    let (_, body) = send(&app, get(&format!("/country?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(body["payload"]["iso_a3"], "LKA");
+
+    let (status, body) = send(&app, get(&format!("/eez?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["is_land"], false);
+    assert_eq!(body["payload"]["zone"]["kind"], "200NM");
+    assert_eq!(body["payload"]["zone"]["claimants"][0]["sovereign_iso3"], "LKA");
+    let (_, body) = send(&app, get("/eez?lat=0&lon=70")).await;
+    assert_eq!(body["payload"]["zone"], Value::Null);
+    let (_, body) = send(&app, get(&format!("/eez?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(body["payload"]["is_land"], true);
+
+    let res = test::call_service(&app, get("/tiles/countries/0/0/0.mvt").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "application/vnd.mapbox-vector-tile");
+    let tile = test::read_body(res).await;
+    assert!(tile.windows(9).any(|w| w == b"countries"), "layer name missing");
+    assert!(tile.windows(3).any(|w| w == b"LKA"), "Sri Lanka missing");
+    // Zoom 4 tile over the South Pacific: no country.
+    let res = test::call_service(&app, get("/tiles/countries/4/0/9.mvt").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(test::read_body(res).await.is_empty());
+    let (status, _) = send(&app, get("/tiles/countries/13/0/0.mvt")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, get("/tiles/countries/2/4/0.mvt")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A repeat with the response's ETag is a bodiless 304; another variant is not.
+    let res = test::call_service(&app, get("/country/LKA").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let etag = res.headers().get("etag").unwrap().clone();
+    let req = get("/country/LKA").insert_header(("if-none-match", etag.clone()));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    assert!(test::read_body(res).await.is_empty());
+    let uri = "/country/LKA?envelope=false";
+    let res = test::call_service(&app, get(uri).insert_header(("if-none-match", etag)).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let (status, body) = send(&app, get("/country/lka?boundaries=2019")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["boundary_vintage"], 2019);
+    assert_eq!(body["payload"]["bbox"], json!([79.7, 5.9, 81.9, 9.8]));
+    assert_eq!(body["payload"]["capital"], "Colombo");
+    assert_eq!(body["payload"]["currency_code"], "LKR");
+    assert_eq!(body["payload"]["languages"], json!(["si", "ta"]));
+    assert_eq!(body["payload"]["calling_code"], "+94");
+    assert_eq!(body["payload"]["tld"], ".lk");
+    let area = body["payload"]["area_km2"].as_f64().unwrap();
+    assert!((103_000.0..106_000.0).contains(&area), "{area}");
+    let density = body["payload"]["density_per_km2"].as_f64().unwrap();
+    assert!((density - 21_803_000.0 / area).abs() < 0.1, "{density}");
+    let (_, body) = send(&app, get("/country/IND")).await;
+    assert_eq!(body["payload"]["capital"], Value::Null);
+    assert_eq!(body["payload"]["languages"], json!([]));
+    let (status, body) = send(&app, get("/country/iso2/lk")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["iso_a3"], "LKA");
+    let (status, body) = send(&app, get("/country/numeric/356")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["iso_a3"], "IND");
+    let (status, body) = send(&app, get("/country/numeric/4")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+
+    let (status, body) = send(&app, get("/country/IND?boundaries=2019")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    let (status, body) = send(&app, get("/country/LKA?boundaries=2000")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+
+    let (status, body) = send(&app, get("/country/LKA/population")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["grid_population"], 55000.0);
+    assert_eq!(body["payload"]["populated_cells"], 25);
+    assert_eq!(body["payload"]["pop_est"], 21803000);
+    assert_eq!(body["payload"]["difference"], -21748000.0);
+    let (status, body) = send(&app, get("/country/IND/population")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["grid_population"], 0.0);
+
+    let (status, body) = send(&app, get("/countries?continent=asia")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["count"], 2);
+    assert_eq!(body["payload"]["countries"][0]["name"], "India");
+    let (status, body) = send(&app, get("/countries?subregion=southern%20asia")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["subregion"], "Southern Asia");
+    assert_eq!(body["payload"]["count"], 2);
+    let (_, body) = send(&app, get("/countries?region=Asia&subregion=Western%20Asia")).await;
+    assert_eq!(body["payload"]["count"], 0);
+    let (_, body) = send(&app, get("/countries?continent=north-america")).await;
+    assert_eq!(body["payload"]["count"], 0);
+    let (_, body) = send(&app, get("/countries?sovereign=false")).await;
+    assert_eq!(body["payload"]["countries"][0]["iso_a3"], "GRL");
+    assert_eq!(body["payload"]["countries"][0]["sovereign"], false);
+    let (_, body) = send(&app, get("/countries?sovereign=all")).await;
+    assert_eq!(body["payload"]["count"], 3);
+
+    let (status, body) = send(&app, get("/continents")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["count"], 2);
+    let asia = &body["payload"]["continents"][0];
+    assert_eq!(asia["name"], "Asia");
+    assert_eq!(asia["country_count"], 2);
+    assert_eq!(asia["pop_est"], 1438976173_i64);
+    assert_eq!(asia["bbox"], json!([76.5, 5.9, 81.9, 13.0]));
+    assert_eq!(body["payload"]["continents"][1]["country_count"], 0);
+    let (_, body) = send(&app, get("/continents?boundaries=2019")).await;
+    assert_eq!(body["payload"]["continents"][0]["country_count"], 1);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn exposure_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&rings=1,5&histogram=true&top_cells=3&include=uncertainty,places");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let payload = &body["payload"];
+    assert_eq!(payload["total_population"], 55000.0);
+    assert_eq!(payload["cell_population"], 2200.0);
+    assert_eq!(payload["place_count"], 1);
+    assert_eq!(payload["places"][0]["name"], "Colombo");
+    assert_eq!(payload["places_truncated"], false);
+    assert_eq!(payload["uncertainty"]["covered_cells"], 25);
+    assert_eq!(payload["uncertainty"]["lower"], 44000.0);
+    assert_eq!(payload["rings"][1]["cumulative_population"], 55000.0);
+    // Every fixture cell holds 1 000–3 400 people on ~0.85 km².
+    let histogram = payload["histogram"].as_array().unwrap();
+    assert_eq!(histogram.len(), 5);
+    assert_eq!(histogram[3]["min_density_per_km2"], 1000.0);
+    assert_eq!(histogram[3]["cell_count"], 25);
+    assert_eq!(histogram[3]["share_of_population"], 1.0);
+    assert_eq!(histogram[4]["max_density_per_km2"], Value::Null);
+    assert_eq!(histogram[0]["cell_count"], 0);
+    let top_cells = payload["top_cells"].as_array().unwrap();
+    assert_eq!(top_cells.len(), 3);
+    assert_eq!(top_cells[0]["population"], 3400.0);
+    assert_eq!(top_cells[2]["population"], 3200.0);
+    assert!(top_cells[0]["bounds"]["max_lat"].as_f64() > top_cells[0]["bounds"]["min_lat"].as_f64());
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&dataset=constrained&include=sources");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 36400.0);
+    assert_eq!(body["payload"]["cell_population"], 2200.0);
+    assert_eq!(body["payload"]["sources"][0]["version"], "R2024B 2025 constrained UN-adjusted, 1 km");
+
+    let (status, body) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&radius=5&year=2020"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["year"], 2020);
+    assert_eq!(body["payload"]["total_population"], 27500.0);
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&mode=coastal&coast_km=5&max_elevation_m=4");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["coastal"]["cell_count"], 15);
+    assert_eq!(body["payload"]["coastal"]["population"], 25500.0);
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&include=settlement,sources");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(
+        body["payload"]["settlement"],
+        json!({"urban_centre": 24500.0, "urban_cluster": 8500.0, "rural": 6000.0, "unclassified": 16000.0})
+    );
+    assert_eq!(body["payload"]["sources"][2]["name"], "GHSL Settlement Model");
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&include=built_up");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["built_up"], json!({"surface_m2": 5550000.0, "cell_count": 26, "share_of_area": 0.071}));
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&include=night_lights");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["night_lights"], json!({"sum_radiance": 550.0, "max_radiance": 34.0, "lit_cell_count": 25}));
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=5&include=land_cover");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(
+        body["payload"]["land_cover"],
+        json!([
+            {"class": "tree_cover", "population": 23500.0, "cell_count": 10, "share_of_population": 0.427},
+            {"class": "cropland", "population": 20500.0, "cell_count": 10, "share_of_population": 0.373},
+            {"class": "built_up", "population": 11000.0, "cell_count": 5, "share_of_population": 0.2}
+        ])
+    );
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=20&include=places&places_limit=1");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["place_count"], 2);
+    assert_eq!(body["payload"]["places"].as_array().map(Vec::len), Some(1));
+    assert_eq!(body["payload"]["places_truncated"], true);
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=20&include=places&min_place_population=300000");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["place_count"], 1);
+    assert_eq!(body["payload"]["places"][0]["name"], "Colombo");
+    assert_eq!(body["payload"]["places_truncated"], false);
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=200&group_by=admin1");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let groups = &body["payload"]["admin_groups"];
+    assert_eq!(groups[0]["admin1"], "Western");
+    assert_eq!(groups[0]["place_count"], 2);
+    assert_eq!(groups[0]["place_population"], 867861);
+    assert_eq!(groups[1]["admin1"], "Central");
+
+    let uri = format!("/compare?from={LAT},{LON}&to={SEA_LAT},{SEA_LON}&radius=5");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["from"]["total_population"], 55000.0);
+    assert_eq!(body["payload"]["to"]["total_population"], 0.0);
+    assert_eq!(body["payload"]["population_difference"], -55000.0);
+    assert_eq!(body["payload"]["distance_km"], 95.11);
+    assert_eq!(body["payload"]["direction"], "W");
+
+    let (status, _) = send(&app, get(&format!("/compare?from={LAT}&to={SEA_LAT},{SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("/route/sample?from={LAT},{LON}&to={SEA_LAT},{SEA_LON}&interval_km=10");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["sample_count"], 11);
+    assert_eq!(body["payload"]["countries"], json!(["LKA"]));
+    assert_eq!(body["payload"]["max_population"], 2200.0);
+    assert_eq!(body["payload"]["samples"][0]["population"], 2200.0);
+    assert_eq!(body["payload"]["samples"][10]["iso3"], Value::Null);
+    let uri = format!("/route/sample?from={LAT},{LON}&to=0,0&interval_km=1");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The western edge of a 20 km circle is sea beyond the Sri Lanka fixture polygon.
+    let (_, body) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&radius=20&land_only=true"))).await;
+    let land_km2 = body["payload"]["area_km2"].as_f64().unwrap();
+    assert_eq!(body["payload"]["land_only"], true);
+    assert!(land_km2 > 1100.0 && land_km2 < 1250.0, "{land_km2}");
+    let (_, body) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&radius=20"))).await;
+    assert_eq!(body["payload"]["area_km2"], 1256.64);
+    assert_eq!(body["payload"].get("land_only"), None);
+
+    assert_eq!(body["payload"].get("countries"), None, "one country: no split");
+
+    // Straddles the gap between the India and Sri Lanka fixture polygons.
+    let (status, body) = send(&app, get("/exposure?lat=8.5&lon=79.6&radius=20")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let countries = body["payload"]["countries"].as_array().unwrap();
+    let codes: Vec<_> = countries.iter().map(|c| c["iso_a3"].as_str().unwrap()).collect();
+    assert_eq!(codes, ["IND", "LKA"], "no population: by name");
+    assert_eq!(countries[0]["population"], 0.0);
+    assert_eq!(countries[0]["place_count"], 0);
+
+    let (status, _) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&radius=5&top_cells=0"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&radius=5&rings=10"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, get(&format!("/exposure?lat={LAT}&lon={LON}&places_offset=5"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("/exposure/places?lat={LAT}&lon={LON}&radius=20&per_page=1&page=2");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_places"], 2);
+    assert_eq!(body["payload"]["places"][0]["name"], "Dehiwala-Mount Lavinia");
+
+    let uri = format!("/exposure?lat={LAT}&lon={LON}&radius=20&include=places&sort=population&order=asc");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["places"][0]["name"], "Dehiwala-Mount Lavinia");
+    assert_eq!(body["payload"]["places"][0]["population"], 219827);
+    let uri = format!("/exposure/places?lat={LAT}&lon={LON}&radius=200&sort=population");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let names: Vec<_> = body["payload"]["places"].as_array().unwrap().iter().map(|p| p["name"].clone()).collect();
+    assert_eq!(names, ["Colombo", "Dehiwala-Mount Lavinia", "Kandy"]);
+
+    let (status, body) = send(&app, get("/exposure/admin?code=lk.36&places=5")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["code"], "LK.36");
+    assert_eq!(body["payload"]["name"], "Western");
+    assert_eq!(body["payload"]["level"], 1);
+    assert!(body["payload"]["total_population"].as_f64().unwrap() >= 2200.0);
+    assert!(body["payload"]["area_km2"].as_f64().unwrap() > 5_000.0);
+    let names: Vec<_> = body["payload"]["top_places"].as_array().unwrap().iter().map(|p| p["name"].clone()).collect();
+    assert_eq!(names, ["Colombo", "Dehiwala-Mount Lavinia"]);
+    let (status, _) = send(&app, get("/exposure/admin?code=LK.99")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, get("/exposure/admin?code=LK.36&boundaries=2019")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, get("/exposure/admin?code=LK")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let cyclone = json!({
+        "lat": LAT, "lon": LON, "max_wind_kt": 70.0,
+        "wind_radii": {"r34": {"ne": 20.0, "se": 20.0, "sw": 20.0, "nw": 20.0}}
+    });
+    let (status, body) = send(&app, post_json("/exposure/cyclone", cyclone)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let swaths = body["payload"]["swaths"].as_array().unwrap();
+    assert_eq!(swaths[0]["threshold_kt"], 34);
+    assert_eq!(swaths[0]["population"], 55000.0);
+
+    let flood = json!({"lat": LAT, "lon": LON, "radius": 5.0, "max_elevation_m": 4.0});
+    let (status, body) = send(&app, post_json("/exposure/flood", flood)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    assert_eq!(body["payload"]["flooded_population"], 25500.0);
+    assert_eq!(body["payload"]["flooded_cell_count"], 15);
+    assert!(body["payload"].get("parts").is_none());
+
+    // Overlapping parts each see the Colombo cell; the total counts it once.
+    let colombo = json!([[[79.86, 6.927], [79.865, 6.927], [79.865, 6.932], [79.86, 6.932], [79.86, 6.927]]]);
+    let collection = json!({"type": "GeometryCollection", "geometries": [
+        {"type": "Polygon", "coordinates": colombo},
+        {"type": "MultiPolygon", "coordinates": [colombo, [[[80.5, 7.5], [80.6, 7.5], [80.6, 7.6], [80.5, 7.5]]]]}
+    ]});
+    let flood = json!({"polygon": collection, "max_elevation_m": 4.0, "breakdown": true});
+    let (status, body) = send(&app, post_json("/exposure/flood", flood)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 2200.0);
+    let parts = body["payload"]["parts"].as_array().unwrap();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[1]["total_population"], 2200.0);
+    assert_eq!(parts[2]["total_population"], 0.0);
+    assert_eq!(parts[2]["bbox"], json!([80.5, 7.5, 80.6, 7.6]));
+    let flood = json!({"lat": LAT, "lon": LON, "max_elevation_m": 4.0, "breakdown": true});
+    let (status, _) = send(&app, post_json("/exposure/flood", flood)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let square = json!({"type": "Polygon", "coordinates": [
+        [[79.86, 6.927], [79.865, 6.927], [79.865, 6.932], [79.86, 6.932], [79.86, 6.927]]
+    ]});
+    let (status, body) = send(&app, post_json("/exposure/buffer", json!({"geometry": square, "buffer_km": 0.0}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["geometry_type"], "Polygon");
+    assert_eq!(body["payload"]["total_population"], 2200.0);
+    assert_eq!(body["payload"]["populated_cell_count"], 1);
+    let point = json!({"type": "Point", "coordinates": [LON, LAT]});
+    let (status, body) = send(&app, post_json("/exposure/buffer", json!({"geometry": point, "buffer_km": 2.0}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["buffered"]["type"], "Polygon");
+    assert!(body["payload"]["total_population"].as_f64().unwrap() >= 2200.0);
+    assert!((body["payload"]["area_km2"].as_f64().unwrap() - 12.6).abs() < 0.5);
+    let (status, _) = send(&app, post_json("/exposure/buffer", json!({"geometry": point, "buffer_km": 0.0}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let track = json!({"type": "LineString", "coordinates": [[179.9, 0.0], [179.95, 0.1]]});
+    let (status, _) = send(&app, post_json("/exposure/buffer", json!({"geometry": track, "buffer_km": 20.0}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // MMI 8 covers only the Colombo cell; the MMI 6 line closes around the block.
+    let contours = json!({"type": "FeatureCollection", "features": [
+        {"type": "Feature", "properties": {"value": 6.0}, "geometry": {"type": "MultiLineString", "coordinates": [
+            [[79.84, 6.90], [79.885, 6.90], [79.885, 6.95], [79.84, 6.95], [79.84, 6.90]]
+        ]}},
+        {"type": "Feature", "properties": {"value": 8.0}, "geometry": {"type": "Polygon", "coordinates": [
+            [[79.86, 6.927], [79.865, 6.927], [79.865, 6.932], [79.86, 6.932], [79.86, 6.927]]
+        ]}}
+    ]});
+    let (status, body) = send(&app, post_json("/exposure/shakemap", json!({"contours": contours}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["total_population"], 55000.0);
+    assert_eq!(body["payload"]["contours"][0]["mmi"], 8.0);
+    assert_eq!(body["payload"]["contours"][0]["population"], 2200.0);
+    assert_eq!(body["payload"]["contours"][1]["band_population"], 52800.0);
+    assert_eq!(body["payload"]["contours"][1]["populated_cell_count"], 25);
+    let both = json!({"event_id": "us6000jllz", "contours": contours});
+    let (status, _) = send(&app, post_json("/exposure/shakemap", both)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // The test server does not fetch from USGS.
+    let (status, _) = send(&app, post_json("/exposure/shakemap", json!({"event_id": "us6000jllz"}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The test server has no routing engine configured.
+    let uri = format!("/exposure/isochrone?lat={LAT}&lon={LON}&minutes=30");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/exposure/isochrone?lat={LAT}&lon={LON}&minutes=240");
+    let (status, _) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn large_radius_exposure_backends_agree() {
+    let db = TestDb::start().await;
+    let client = db.pool.get().await.unwrap();
+    let tiles: i32 = client
+        .query_one("SELECT refresh_population_raster('unconstrained')", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(tiles, 1);
+
+    let grid = Repositories::postgres(&db.pool, 30, ExposureBackend::Grid);
+    let raster = Repositories::postgres(&db.pool, 30, ExposureBackend::Raster);
+    for radius in [60.0, 300.0] {
+        let expected = grid.population.get_exposure_population(LAT, LON, radius).await.unwrap();
+        assert_eq!(expected, 55000.0);
+        assert_eq!(raster.population.get_exposure_population(LAT, LON, radius).await.unwrap(), expected);
+    }
+    let sea = raster.population.get_exposure_population(SEA_LAT, SEA_LON - 2.0, 60.0).await.unwrap();
+    assert_eq!(sea, 0.0);
+
+    // No constrained raster: falls back to the grid.
+    let expected = grid.population_constrained.get_exposure_population(LAT, LON, 60.0).await.unwrap();
+    let actual = raster.population_constrained.get_exposure_population(LAT, LON, 60.0).await.unwrap();
+    assert_eq!(actual, expected);
+
+    // With the rollups built, the grid backend sums pyramid blocks and edge cells.
+    client.batch_execute("SELECT refresh_population_rollups('unconstrained')").await.unwrap();
+    for (lat, lon) in [(LAT, LON), (LAT + 0.4, LON), (LAT, LON + 0.5)] {
+        let total = grid.population.get_exposure_population(lat, lon, 60.0).await.unwrap();
+        assert_eq!(total, 55000.0, "{lat},{lon}");
+    }
+    let partial = grid.population.get_exposure_population(LAT + 0.54, LON, 60.0).await.unwrap();
+    assert!(partial > 0.0 && partial < 55000.0, "{partial}");
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn analyse_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let (status, body) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let payload = &body["payload"];
+    assert_eq!(payload["is_land"], true);
+    assert_eq!(payload["country"]["iso_a3"], "LKA");
+    assert_eq!(payload["nearest_place"]["name"], "Colombo");
+    assert_eq!(payload["nearest_place"]["feature_code"], "PPLC");
+    assert_eq!(payload["population"]["epicentre_population"], 2200.0);
+    assert_eq!(payload["population"]["search_radius_km"], 5.0);
+    assert_eq!(payload["population"]["total_population"], 55000.0);
+    assert_eq!(payload["year"], 2025);
+    assert_eq!(payload.get("nearest_places"), None);
+    // The fixture India box is ~136 km away; Sri Lanka itself is `country`.
+    let nearby = payload["nearby_countries"].as_array().unwrap();
+    assert_eq!(nearby.len(), 1, "{nearby:?}");
+    assert_eq!(nearby[0]["iso_a3"], "IND");
+    assert!((100.0..200.0).contains(&nearby[0]["distance_km"].as_f64().unwrap()));
+    assert_eq!(payload["local_time"]["timezone"], "Asia/Colombo");
+    assert_eq!(payload["local_time"]["utc_offset"], "+05:30");
+    assert!(payload["local_time"]["local_time"].as_str().unwrap().ends_with("+05:30"));
+    assert_eq!(payload["terrain"], json!({"elevation_m": 4.0, "coast_km": 1.0, "class": "coastal"}));
+    assert_eq!(payload["settlement_class"], "urban_centre");
+    assert_eq!(payload["population"].get("settlement"), None);
+    assert_eq!(payload.get("hazard"), None);
+
+    // An M5 template radius is the 5 km default step, so the totals match.
+    let (status, body) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&hazard=earthquake&magnitude=5"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let hazard = &body["payload"]["hazard"];
+    assert_eq!(hazard["template"]["name"], "earthquake");
+    assert_eq!(hazard["radius_km"], 5.0);
+    assert_eq!(body["payload"]["population"]["search_radius_km"], 5.0);
+    assert_eq!(body["payload"]["population"]["total_population"], 55000.0);
+    let weighted = hazard["weighted_population"].as_f64().unwrap();
+    assert!(weighted > 0.0 && weighted < 55000.0, "{weighted}");
+
+    let (status, body) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&include=settlement"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"]["settlement"]["urban_centre"], 24500.0);
+    assert_eq!(body["payload"]["population"]["settlement"]["unclassified"], 16000.0);
+
+    let (status, body) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&nearest_places=3"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let nearest = body["payload"]["nearest_places"].as_array().unwrap();
+    let names: Vec<_> = nearest.iter().map(|p| p["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Colombo", "Dehiwala-Mount Lavinia", "Kandy"]);
+    assert!(nearest[2]["distance_km"].as_f64() > nearest[1]["distance_km"].as_f64());
+    assert_eq!(body["payload"]["nearest_place"], nearest[0]);
+    let (status, _) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&nearest_places=21"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&year=2020"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["year"], 2020);
+    assert_eq!(body["payload"]["population"]["epicentre_population"], 1100.0);
+    assert_eq!(body["payload"]["population"]["total_population"], 27500.0);
+    let (status, _) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&year=1999"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // At sea the radius search walks the tiers out to the fixture block.
+    let (status, body) = send(&app, get(&format!("/analyse?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["is_land"], false);
+    assert_eq!(body["payload"]["population"]["search_radius_km"], 100.0);
+    assert_eq!(body["payload"]["terrain"], Value::Null);
+    assert_eq!(body["payload"]["settlement_class"], Value::Null);
+    let (status, body) = send(&app, get(&format!("/analyse?lat={SEA_LAT}&lon={SEA_LON}&step_km=25"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"]["search_radius_km"], 125.0);
+    let (status, body) = send(&app, get(&format!("/analyse?lat={SEA_LAT}&lon={SEA_LON}&max_radius_km=60"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["payload"]["population"]["search_radius_km"], 60.0);
+    assert_eq!(body["payload"]["population"]["total_population"], 0.0);
+    for bad in [
+        "step_km=0.5",
+        "step_km=101",
+        "max_radius_km=1001",
+        "step_km=20&max_radius_km=10",
+        "hazard=meteor&magnitude=5",
+        "hazard=earthquake",
+        "hazard=earthquake&magnitude=10",
+        "hazard=earthquake&magnitude=6&step_km=10",
+    ] {
+        let (status, _) = send(&app, get(&format!("/analyse?lat={SEA_LAT}&lon={SEA_LON}&{bad}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{bad}");
+    }
+
+    let uri = format!("/analyse?lat={SEA_LAT}&lon={SEA_LON}&include=places&min_place_population=300000");
+    let (status, body) = send(&app, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let names: Vec<_> = body["payload"]["places"].as_array().unwrap().iter().map(|p| p["name"].clone()).collect();
+    assert_eq!(names, ["Colombo"]);
+    let (status, _) = send(&app, get(&format!("/analyse?lat={LAT}&lon={LON}&min_place_population=1"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send_raw(&app, get(&format!("/analyse/stream?lat={SEA_LAT}&lon={SEA_LON}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("event: probe\ndata: {\"radius_km\":50.0,\"populated\":false}"), "{body}");
+    assert!(body.contains("event: probe\ndata: {\"radius_km\":100.0,\"populated\":true}"), "{body}");
+    assert!(body.trim_end().rsplit("\n\n").next().unwrap().starts_with("event: summary"), "{body}");
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn analyse_report() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let (status, html) = send_raw(&app, get(&format!("/analyse/report?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{html}");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>Colombo, Sri Lanka</h1>"), "{html}");
+    assert!(html.contains("<td>55,000</td>"));
+    assert!(html.contains("<td>Kandy</td>"), "nearest places default to 5");
+    assert!(html.contains("<th>ISO codes</th><td>LK / LKA</td>"));
+    assert!(html.contains("<svg"));
+
+    let req = get(&format!("/analyse/report?lat={LAT}&lon={LON}&format=pdf"));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "application/pdf");
+    let pdf = test::read_body(res).await;
+    assert!(pdf.starts_with(b"%PDF-1.4") && pdf.ends_with(b"%%EOF\n"));
+    assert!(pdf.windows(9).any(|w| w == b"Sri Lanka"));
+
+    let (status, _) = send_raw(&app, get(&format!("/analyse/report?lat={LAT}&lon={LON}&format=docx"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send_raw(&app, get(&format!("/analyse/report?lat={LAT}&lon={LON}&include=places"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn file_exports() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let req = get(&format!("/exposure?lat={LAT}&lon={LON}&radius=5&format=kml"));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "application/vnd.google-earth.kml+xml");
+    let kml = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+    assert!(kml.contains("<Data name=\"total_population\"><value>55000</value></Data>"), "{kml}");
+    assert!(kml.contains("<name>Grid cells</name>"));
+    assert!(kml.contains("<name>Colombo</name>"), "places are implied");
+
+    let (status, kml) = send_raw(&app, get(&format!("/population?lat={LAT}&lon={LON}&radius=5&format=kml"))).await;
+    assert_eq!(status, StatusCode::OK, "{kml}");
+    assert!(kml.contains("<Data name=\"population\"><value>2200.0</value></Data>"), "{kml}");
+
+    let req = get(&format!("/exposure?lat={LAT}&lon={LON}&radius=5&format=shapefile"));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "application/zip");
+    let zip = test::read_body(res).await;
+    assert!(zip.starts_with(b"PK\x03\x04"));
+
+    for query in ["format=kml", "radius=5&aggregation=h3&format=kml", "radius=5&format=shapefile"] {
+        let (status, _) = send_raw(&app, get(&format!("/population?lat={LAT}&lon={LON}&{query}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn geopackage_export() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let req = get("/export/geopackage?bbox=79.80,6.85,79.95,7.00");
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "application/geopackage+sqlite3");
+    let file = test::read_body(res).await;
+    assert!(file.starts_with(b"SQLite format 3\0"));
+    assert_eq!(&file[68..72], b"GPKG");
+    for table in ["\"population\"", "\"places\"", "\"countries\""] {
+        assert!(file.windows(table.len()).any(|w| w == table.as_bytes()), "{table}");
+    }
+    assert!(file.windows(7).any(|w| w == b"Colombo"));
+
+    let (status, _) = send_raw(&app, get("/export/geopackage?bbox=79.80,6.85,79.95,7.00&layers=places")).await;
+    assert_eq!(status, StatusCode::OK);
+    for query in ["bbox=79.80,6.85,79.95,7.00&layers=roads", "bbox=60,0,80,20"] {
+        let (status, _) = send_raw(&app, get(&format!("/export/geopackage?{query}"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn tag_stream_route() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let lines = format!(
+        "{{\"id\":1,\"lat\":{LAT},\"lon\":{LON}}}\nnot json\n{{\"lat\":{SEA_LAT},\"lon\":{SEA_LON}}}\n"
+    );
+    let req = test::TestRequest::post()
+        .uri(&format!("{API_PREFIX}/tag/stream"))
+        .insert_header(("Content-Type", "application/x-ndjson"))
+        .set_payload(lines);
+    let (status, body) = send_raw(&app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    let out: Vec<Value> = body.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(out.len(), 3, "{body}");
+    assert_eq!(out[0], json!({"id": 1, "lat": LAT, "lon": LON, "iso3": "LKA", "population": 2200.0}));
+    assert_eq!(out[1]["line"], 2);
+    assert_eq!(out[2]["iso3"], Value::Null);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn job_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let job = json!({"kind": "exposure", "params": {"lat": LAT, "lon": LON, "radius": 5.0}});
+    let (status, body) = send(&app, post_json("/jobs", job)).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{body}");
+    let id = body["payload"]["id"].as_str().unwrap().to_owned();
+
+    let mut finished = Value::Null;
+    for _ in 0..100 {
+        let (status, body) = send(&app, get(&format!("/jobs/{id}"))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        if body["payload"]["status"] != "queued" && body["payload"]["status"] != "running" {
+            finished = body;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(finished["payload"]["status"], "succeeded", "{finished}");
+    assert_eq!(finished["payload"]["result"]["total_population"], 55000.0);
+
+    let bad = json!({"kind": "exposure", "params": {"lat": 91, "lon": LON}});
+    let (status, _) = send(&app, post_json("/jobs", bad)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn event_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let mut event = EventRecord {
+        event_type: "EQ".into(),
+        event_id: 1000001,
+        episode_id: Some(1),
+        name: "Earthquake in Sri Lanka".into(),
+        alert_level: AlertLevel::Orange,
+        severity: Some(6.0),
+        severity_text: None,
+        lat: LAT,
+        lon: LON,
+        country: Some("Sri Lanka".into()),
+        report_url: None,
+        started_at: None,
+        ended_at: None,
+    };
+    let params = json!({"lat": LAT, "lon": LON, "hazard": "earthquake", "magnitude": 6.0});
+    let client = db.pool.get().await.unwrap();
+    assert!(EventRepository::upsert(&client, &event, &params).await.unwrap());
+    event.episode_id = Some(2);
+    event.alert_level = AlertLevel::Red;
+    assert!(!EventRepository::upsert(&client, &event, &params).await.unwrap(), "a new episode is no new event");
+
+    let mut finished = Value::Null;
+    for _ in 0..100 {
+        let (status, body) = send(&app, get("/events?event_type=eq")).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let status = &body["payload"]["events"][0]["status"];
+        if status != "queued" && status != "running" {
+            finished = body;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let stored = &finished["payload"]["events"][0];
+    assert_eq!(stored["id"], "EQ1000001");
+    assert_eq!(stored["alert_level"], "red");
+    assert_eq!(stored["status"], "succeeded", "{finished}");
+    assert_eq!(stored["result"]["hazard"]["template"]["name"], "earthquake");
+
+    // Results are kept on the event once copied from the job.
+    assert_eq!(EventRepository::store_results(&client).await.unwrap(), 1);
+    client.execute("DELETE FROM jobs", &[]).await.unwrap();
+    let (_, body) = send(&app, get("/events?min_alert=red")).await;
+    assert_eq!(body["payload"]["events"][0]["status"], "succeeded");
+    assert!(body["payload"]["events"][0]["result"].is_object());
+    let (_, body) = send(&app, get("/events?event_type=TC")).await;
+    assert_eq!(body["payload"]["count"], 0);
+    let (status, _) = send(&app, get("/events?event_type=XX")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn monitor_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let monitor = json!({"name": "Colombo port", "lat": LAT, "lon": LON, "radius": 5.0});
+    let (status, body) = send(&app, post_json("/monitors", monitor)).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert_eq!(body["payload"]["interval_hours"], 24);
+    let id = body["payload"]["id"].as_str().unwrap().to_owned();
+
+    let mut snapshot = Value::Null;
+    for _ in 0..100 {
+        let (status, body) = send(&app, get(&format!("/monitors/{id}"))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        if !body["payload"]["last_population"].is_null() {
+            snapshot = body;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(snapshot["payload"]["last_population"], 55000.0, "{snapshot}");
+
+    // A new area is snapshotted straight away, with its change from the last one.
+    let polygon = json!({"type": "Polygon", "coordinates": [
+        [[79.86, 6.927], [79.865, 6.927], [79.865, 6.932], [79.86, 6.932], [79.86, 6.927]]
+    ]});
+    let update = json!({"name": "Colombo cell", "polygon": polygon, "interval_hours": 6});
+    let req = test::TestRequest::put().uri(&format!("{API_PREFIX}/monitors/{id}")).set_json(update);
+    let (status, body) = send(&app, req).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let mut snapshots = Value::Null;
+    for _ in 0..100 {
+        let (_, body) = send(&app, get(&format!("/monitors/{id}/snapshots"))).await;
+        if body["payload"]["snapshots"].as_array().is_some_and(|s| s.len() == 2) {
+            snapshots = body;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(snapshots["payload"]["snapshots"][0]["total_population"], 2200.0, "{snapshots}");
+    assert_eq!(snapshots["payload"]["snapshots"][0]["change"], -52800.0);
+    assert_eq!(snapshots["payload"]["snapshots"][1]["change"], Value::Null);
+
+    let (_, body) = send(&app, get("/monitors")).await;
+    assert_eq!(body["payload"]["count"], 1);
+    let both = json!({"name": "x", "lat": LAT, "lon": LON, "polygon": polygon});
+    let (status, _) = send(&app, post_json("/monitors", both)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::delete().uri(&format!("{API_PREFIX}/monitors/{id}"));
+    let (status, _) = send_raw(&app, req).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, get(&format!("/monitors/{id}/snapshots"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn monitor_alert_thresholds() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let thresholds = json!([
+        {"metric": "population", "above": 50000.0},
+        {"metric": "event_exposure", "above": 50000.0, "radius_km": 5.0}
+    ]);
+    let hooked = json!({"name": "x", "lat": LAT, "lon": LON, "webhook_url": "https://hooks.example.com/x"});
+    let (status, _) = send(&app, post_json("/monitors", hooked)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "webhooks need WEBHOOK_SECRET");
+    let negative = json!({"name": "x", "lat": LAT, "lon": LON, "thresholds": [{"metric": "change", "above": -1.0}]});
+    let (status, _) = send(&app, post_json("/monitors", negative)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let monitor = json!({"name": "Colombo", "lat": LAT, "lon": LON, "radius": 5.0, "thresholds": thresholds});
+    let (status, body) = send(&app, post_json("/monitors", monitor)).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert_eq!(body["payload"]["thresholds"], thresholds);
+    let id = body["payload"]["id"].as_str().unwrap().to_owned();
+
+    let alerts = |count: usize| {
+        let app = &app;
+        let path = format!("/monitors/{id}/alerts");
+        async move {
+            for _ in 0..100 {
+                let (status, body) = send(app, get(&path)).await;
+                assert_eq!(status, StatusCode::OK, "{body}");
+                if body["payload"]["alerts"].as_array().is_some_and(|a| a.len() == count) {
+                    return body;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            panic!("expected {count} alert(s)");
+        }
+    };
+    let body = alerts(1).await;
+    let alert = &body["payload"]["alerts"][0];
+    assert_eq!(alert["threshold"]["metric"], "population");
+    assert_eq!(alert["value"], 55000.0);
+    assert_eq!(alert["monitor_name"], "Colombo");
+    assert_eq!(alert["delivery"], Value::Null);
+
+    let event = EventRecord {
+        event_type: "EQ".into(),
+        event_id: 1000002,
+        episode_id: None,
+        name: "Earthquake in Sri Lanka".into(),
+        alert_level: AlertLevel::Orange,
+        severity: Some(6.0),
+        severity_text: None,
+        lat: LAT,
+        lon: LON,
+        country: None,
+        report_url: None,
+        started_at: None,
+        ended_at: None,
+    };
+    let client = db.pool.get().await.unwrap();
+    EventRepository::upsert(&client, &event, &json!({"lat": LAT, "lon": LON})).await.unwrap();
+    let body = alerts(2).await;
+    let alert = &body["payload"]["alerts"][0];
+    assert_eq!(alert["threshold"]["metric"], "event_exposure");
+    assert_eq!(alert["event"], "EQ1000002");
+    assert_eq!(alert["value"], 55000.0);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn graphql_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().configure(db.configure())).await;
+
+    let query = format!(
+        "{{ point(lat: {LAT}, lon: {LON}) {{ population country {{ isoA3 }} \
+           nearestPlace {{ name }} exposure(radius: 5) {{ totalPopulation placeCount }} }} }}"
+    );
+    let (status, body) = send(&app, post_json("/graphql", json!({"query": query}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["errors"], Value::Null, "{body}");
+    let point = &body["data"]["point"];
+    assert_eq!(point["population"], 2200.0);
+    assert_eq!(point["country"]["isoA3"], "LKA");
+    assert_eq!(point["nearestPlace"]["name"], "Colombo");
+    assert_eq!(point["exposure"]["totalPopulation"], 55000.0);
+    assert_eq!(point["exposure"]["placeCount"], 1);
+
+    let (status, body) = send_raw(&app, get("/graphql")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("graphiql"), "expected the GraphiQL page");
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn audit_log_records_requests() {
+    let db = TestDb::start().await;
+    let app = test::init_service(
+        App::new().wrap(AuditLog::spawn(db.pool.clone())).configure(db.configure()),
+    )
+    .await;
+
+    let (status, _) = send(&app, get(&format!("/population?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    send(&app, get("/health")).await;
+
+    let client = db.pool.get().await.unwrap();
+    let mut rows = Vec::new();
+    for _ in 0..50 {
+        rows = client
+            .query("SELECT method, path, params_hash, api_key, status FROM audit_log", &[])
+            .await
+            .unwrap();
+        if !rows.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(rows.len(), 1, "health checks are not audited");
+    assert_eq!(rows[0].get::<_, &str>(0), "GET");
+    assert_eq!(rows[0].get::<_, &str>(1), format!("{API_PREFIX}/population"));
+    assert_eq!(rows[0].get::<_, &str>(2).len(), 64);
+    assert_eq!(rows[0].get::<_, Option<&str>>(3), None);
+    assert_eq!(rows[0].get::<_, i16>(4), 200);
+}
+
+#[actix_web::test]
+#[ignore = "requires Docker"]
+async fn v2_routes() {
+    let db = TestDb::start().await;
+    let app = test::init_service(App::new().wrap(EnvelopeOptOut).configure(db.configure())).await;
+    let v2 = |path: &str| test::TestRequest::get().uri(&format!("{API_V2_PREFIX}{path}"));
+
+    let (status, body) = send(&app, v2(&format!("/reverse?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["name"], "Colombo");
+    assert!(body["lat"].is_f64() && body["lon"].is_f64(), "{body}");
+
+    let (status, body) = send(&app, v2("/countries?continent=asia&per_page=1")).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!((body["page"].as_i64(), body["per_page"].as_i64()), (Some(1), Some(1)));
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["countries"].as_array().map(Vec::len), Some(1));
+    let (_, page2) = send(&app, v2("/countries?continent=asia&per_page=1&page=2")).await;
+    assert_ne!(page2["countries"][0]["iso_a3"], body["countries"][0]["iso_a3"]);
+    let (status, _) = send(&app, v2("/countries?continent=asia&per_page=101")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Unchanged routes are served by the v1 handlers, bare.
+    let (status, body) = send(&app, v2(&format!("/population?lat={LAT}&lon={LON}"))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body.get("success").is_none() && body["population"].is_number(), "{body}");
+
+    // Errors keep the envelope.
+    let (status, body) = send(&app, v2("/reverse?lat=91&lon=0")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["success"], false);
+}
@@ -0,0 +1,140 @@
+-- Minimal dataset for the integration tests: a 5×5 block of grid cells centred
+-- on Colombo, a handful of GeoNames places, two boxy country polygons and an EEZ.
+-- Cell (row 9968, col 31183) contains Colombo; see grid.rs for the cell_id scheme.
+
+INSERT INTO boundary_vintages (vintage, source, is_current) VALUES
+    (2019, 'test fixture', false),
+    (2025, 'test fixture', true);
+
+INSERT INTO countries (vintage, iso_a2, iso_a3, iso_n3, name, formal_name, continent, region_un, subregion, pop_est, geom)
+SELECT v, 'LK', 'LKA', '144', 'Sri Lanka', 'Democratic Socialist Republic of Sri Lanka', 'Asia', 'Asia',
+       'Southern Asia', 21803000,
+       ST_GeomFromText('MULTIPOLYGON(((79.7 5.9, 81.9 5.9, 81.9 9.8, 79.7 9.8, 79.7 5.9)))', 4326)
+FROM unnest(ARRAY[2019, 2025]) AS v;
+
+INSERT INTO countries (vintage, iso_a2, iso_a3, iso_n3, name, formal_name, continent, region_un, subregion, pop_est, geom)
+VALUES (2025, 'IN', 'IND', '356', 'India', 'Republic of India', 'Asia', 'Asia', 'Southern Asia', 1417173173,
+        ST_GeomFromText('MULTIPOLYGON(((76.5 8.1, 79.5 8.1, 79.5 13.0, 76.5 13.0, 76.5 8.1)))', 4326));
+
+-- A dependent territory far from everything else, for `/countries?sovereign=`.
+INSERT INTO countries (vintage, iso_a2, iso_a3, iso_n3, name, formal_name, continent, region_un, subregion, sovereign, pop_est, geom)
+VALUES (2025, 'GL', 'GRL', '304', 'Greenland', NULL, 'North America', 'Americas', 'Northern America', false, 56000,
+        ST_GeomFromText('MULTIPOLYGON(((-50 60, -30 60, -30 80, -50 80, -50 60)))', 4326));
+
+-- Sri Lanka's EEZ as a box off its west coast; covers the open-sea test point.
+INSERT INTO eez (mrgid, name, pol_type, territories, territory_iso3, sovereigns, sovereign_iso3, area_km2, geom)
+VALUES (8383, 'Sri Lankan Exclusive Economic Zone', '200NM', ARRAY['Sri Lanka'], ARRAY['LKA'],
+        ARRAY['Sri Lanka'], ARRAY['LKA'], 532619,
+        ST_GeomFromText('MULTIPOLYGON(((77.5 4.5, 79.7 4.5, 79.7 8.0, 77.5 8.0, 77.5 4.5)))', 4326));
+
+-- Populations 1000, 1100, ..., 3400 row by row; the Colombo cell holds 2200.
+INSERT INTO population (cell_id, pop)
+SELECT (9966 + r) * 43200 + (31181 + c), 1000 + 100 * (r * 5 + c)
+FROM generate_series(0, 4) AS r, generate_series(0, 4) AS c;
+
+-- The constrained grid keeps only the 13 cells from Colombo's onwards (36 400 people).
+INSERT INTO population_constrained (cell_id, pop)
+SELECT cell_id, pop FROM population WHERE pop >= 2200;
+
+-- Both grids are 2025; 2020 is an earlier unconstrained year at half the population.
+INSERT INTO population_years (dataset, year)
+VALUES ('unconstrained', 2020), ('unconstrained', 2025), ('constrained', 2025);
+
+INSERT INTO population_history (dataset, year, cell_id, pop)
+SELECT 'unconstrained', 2020, cell_id, pop / 2 FROM population;
+
+INSERT INTO population_uncertainty (cell_id, pop_lower, pop_upper)
+SELECT cell_id, pop * 0.8, pop * 1.2 FROM population;
+
+-- Elevation rises 2 m per row southwards from the northern edge.
+INSERT INTO cell_elevation (cell_id, elevation_m)
+SELECT cell_id, 2 * (cell_id / 43200 - 9966) FROM population;
+
+INSERT INTO cell_terrain (cell_id, elevation_m, coast_km)
+SELECT cell_id, elevation_m, 1.0 FROM cell_elevation WHERE elevation_m <= 4;
+
+-- GHS-SMOD by row: rural (6000 people), urban cluster (8500), two urban-centre
+-- rows holding Colombo (24 500); the southern row stays unclassified (16 000).
+INSERT INTO cell_settlement (cell_id, smod)
+SELECT cell_id, (ARRAY[12, 22, 30, 30])[cell_id / 43200 - 9965]
+FROM population WHERE cell_id / 43200 < 9970;
+
+-- 100 m² of built-up surface per resident, plus a built but unpopulated cell
+-- (a port) just north of the block: 5 550 000 m² in all.
+INSERT INTO cell_built_up (cell_id, built_m2)
+SELECT cell_id, pop * 100 FROM population
+UNION ALL
+SELECT 9965 * 43200 + 31183, 50000;
+
+-- Radiance of one nW/cm²/sr per 100 residents: 550 over the block, 34 at most.
+INSERT INTO cell_night_lights (cell_id, radiance)
+SELECT cell_id, pop / 100 FROM population;
+
+-- WorldCover by column: cropland (20 500 people), built-up through Colombo
+-- (11 000), tree cover (23 500).
+INSERT INTO cell_land_cover (cell_id, class)
+SELECT cell_id, (ARRAY[40, 40, 50, 10, 10])[mod(cell_id, 43200) - 31180] FROM population;
+
+INSERT INTO country_info (iso_a3, iso_a2, name, capital, currency_code, currency_name, calling_code, tld, languages, geonameid)
+VALUES ('LKA', 'LK', 'Sri Lanka', 'Colombo', 'LKR', 'Rupee', '+94', '.lk', ARRAY['si', 'ta'], 1227603);
+
+INSERT INTO admin1_codes (code, name, geonameid) VALUES
+    ('LK.36', 'Western', 1223760),
+    ('LK.29', 'Central', 1250160);
+
+-- Province polygons for /reverse?mode=contains, in the 2025 vintage only. The
+-- border is moved south of Colombo so points just north of it lie in Central.
+INSERT INTO admin_boundaries (vintage, level, country_code, code, name, source, geom) VALUES
+    (2025, 1, 'LK', 'LK.36', 'Western Province', 'fixture',
+     ST_GeomFromText('MULTIPOLYGON(((79.7 5.9, 80.5 5.9, 80.5 6.95, 79.7 6.95, 79.7 5.9)))', 4326)),
+    (2025, 1, 'LK', 'LK.29', 'Central Province', 'fixture',
+     ST_GeomFromText('MULTIPOLYGON(((79.7 6.95, 81.0 6.95, 81.0 7.6, 79.7 7.6, 79.7 6.95)))', 4326));
+
+-- Tamil names for /reverse localisation; the historic one must never be picked.
+INSERT INTO alternate_names (alternatenameid, geonameid, isolanguage, name, is_preferred, is_historic) VALUES
+    (1, 1248991, 'ta', 'கொழும்பு', true,  false),
+    (2, 1223760, 'ta', 'மேல் மாகாணம்', false, false),
+    (3, 1227603, 'ta', 'இலங்கை', true,  false),
+    (4, 1227603, 'en', 'Ceylon', false, true);
+
+INSERT INTO geonames (geonameid, name, latitude, longitude, feature_code, country_code, admin1_code, population, timezone, geom)
+SELECT id, name, lat, lon, fc, 'LK', a1, pop, 'Asia/Colombo', ST_SetSRID(ST_MakePoint(lon, lat), 4326)
+FROM (VALUES
+    (1248991, 'Colombo',                6.93194, 79.84778, 'PPLC', '36', 648034),
+    (1246294, 'Dehiwala-Mount Lavinia', 6.84019, 79.87116, 'PPL',  '36', 219827),
+    (1241622, 'Kandy',                  7.29060, 80.63360, 'PPLA', '29', 111701)
+) AS p (id, name, lat, lon, fc, a1, pop);
+
+-- Facilities for /facilities/nearest: a hospital ~1.3 km SE of Colombo, one in
+-- Kandy, an airport ~28 km N and a port ~3 km NW.
+INSERT INTO facilities (geonameid, name, latitude, longitude, feature_code, country_code, geom)
+SELECT id, name, lat, lon, fc, 'LK', ST_SetSRID(ST_MakePoint(lon, lat), 4326)
+FROM (VALUES
+    (9000001, 'General Hospital Colombo',          6.91778, 79.86778, 'HSP'),
+    (9000002, 'Teaching Hospital Kandy',           7.28730, 80.63230, 'HSP'),
+    (9000003, 'Bandaranaike International Airport', 7.18076, 79.88412, 'AIRP'),
+    (9000004, 'Port of Colombo',                   6.94820, 79.84384, 'PRT')
+) AS f (id, name, lat, lon, fc);
+
+-- Airports for /airports/nearest: Ratmalana ~12 km S of Colombo with a 1830 m
+-- and a 610 m grass runway, Bandaranaike ~28 km N with a 3350 m runway, and a
+-- heliport next to the fixture point that the endpoint must skip.
+INSERT INTO airports (airport_id, ident, airport_type, name, latitude, longitude, elevation_ft,
+                      country_code, municipality, scheduled_service, icao_code, iata_code, geom)
+SELECT id, ident, kind, name, lat, lon, elev, 'LK', town, sched, icao, iata, ST_SetSRID(ST_MakePoint(lon, lat), 4326)
+FROM (VALUES
+    (9100001, 'VCBI',    'large_airport',  'Bandaranaike International Airport', 7.18076, 79.88412, 30, 'Colombo', true,  'VCBI', 'CMB'),
+    (9100002, 'VCCC',    'medium_airport', 'Ratmalana Airport',                  6.82199, 79.88620, 22, 'Colombo', false, 'VCCC', 'RML'),
+    (9100003, 'LK-0001', 'heliport',       'Colombo Heliport',                   6.93000, 79.86000, 10, 'Colombo', false, NULL,   NULL)
+) AS a (id, ident, kind, name, lat, lon, elev, town, sched, icao, iata);
+
+INSERT INTO airport_runways (runway_id, airport_id, length_ft, width_ft, surface, lighted, le_ident, he_ident) VALUES
+    (9200001, 9100001, 10991, 148, 'ASP', true,  '04', '22'),
+    (9200002, 9100002, 2000,  60,  'GRS', false, '14', '32'),
+    (9200003, 9100002, 6004,  150, 'ASP', true,  '04', '22');
+
+-- Density distributions for /population/rank: all 25 cells fall in Sri Lanka.
+SELECT refresh_density_percentiles();
+
+-- Country grid totals for /country/{iso3}/population, for both vintages.
+SELECT refresh_country_population();
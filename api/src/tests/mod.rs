@@ -0,0 +1,181 @@
+//! Integration tests: every route against a real PostGIS database.
+//!
+//! Each test starts its own PostGIS container, applies the embedded migrations
+//! and loads `fixtures.sql`, so tests are independent and run in parallel. They
+//! need a Docker daemon and are therefore ignored by default; run them with
+//! `cargo test -p geopop-api -- --ignored`.
+
+mod api;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::{test, web};
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use serde_json::Value;
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::{ContainerAsync, ImageExt};
+use tokio::sync::Semaphore;
+use tokio_postgres::NoTls;
+
+use crate::config::{Config, ExposureBackend, Features, Limits, API_PREFIX, API_V2_PREFIX};
+use crate::conditional::{ConditionalGet, TagCache};
+use crate::crs::CrsInput;
+use crate::statement_timeout::StatementTimeout;
+use crate::repositories::Repositories;
+use crate::state::AppState;
+use crate::webhook::Webhooks;
+use crate::{graphql, jobs, migrations, monitors, routes};
+
+/// Same image as `docker/Dockerfile.db`.
+const POSTGIS_IMAGE: &str = "imresamu/postgis";
+const POSTGIS_TAG: &str = "16-3.4";
+const FIXTURES: &str = include_str!("fixtures.sql");
+/// `ADMIN_API_KEY` of the test server.
+pub(crate) const ADMIN_KEY: &str = "test-admin-key";
+
+/// A migrated, fixture-loaded database that lives as long as the value.
+pub(crate) struct TestDb {
+    pub pool: Pool,
+    _container: ContainerAsync<Postgres>,
+}
+
+impl TestDb {
+    pub async fn start() -> Self {
+        let container = Postgres::default()
+            .with_name(POSTGIS_IMAGE)
+            .with_tag(POSTGIS_TAG)
+            .start()
+            .await
+            .expect("failed to start PostGIS container (is Docker running?)");
+        let host = container.get_host().await.expect("container host");
+        let port = container.get_host_port_ipv4(5432).await.expect("container port");
+
+        let mut pg_config = tokio_postgres::Config::new();
+        pg_config
+            .host(host.to_string())
+            .port(port)
+            .user("postgres")
+            .password("postgres")
+            .dbname("postgres");
+        let manager = Manager::from_config(
+            pg_config,
+            NoTls,
+            ManagerConfig { recycling_method: RecyclingMethod::Fast },
+        );
+        let pool = Pool::builder(manager).max_size(8).build().expect("test pool");
+
+        // The image restarts Postgres once after its init scripts; wait for
+        // the final server rather than trusting the first readiness log line.
+        let mut attempts = 0;
+        let client = loop {
+            match pool.get().await {
+                Ok(client) => break client,
+                Err(e) if attempts < 60 => {
+                    attempts += 1;
+                    log::debug!("waiting for PostGIS: {e}");
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Err(e) => panic!("PostGIS never became ready: {e}"),
+            }
+        };
+        migrations::run(&pool).await.expect("migrations");
+        client.batch_execute(FIXTURES).await.expect("fixtures");
+
+        Self { pool, _container: container }
+    }
+
+    /// Registers the app data and routes the server installs in `main`.
+    pub fn configure(&self) -> impl FnOnce(&mut web::ServiceConfig) + '_ {
+        move |cfg| {
+            let limits = Limits::default();
+            let repos = Repositories::postgres(&self.pool, limits.request_timeout_secs, ExposureBackend::Grid);
+            let jobs = jobs::spawn_workers(
+                self.pool.clone(),
+                self.pool.clone(),
+                1,
+                60,
+                Webhooks::default(),
+                ExposureBackend::Grid,
+                limits.clone(),
+            );
+            monitors::spawn_scheduler(self.pool.clone(), repos.population.clone(), Webhooks::default());
+            let etags = Arc::new(TagCache::default());
+            let state = AppState {
+                pool: self.pool.clone(),
+                read_pool: self.pool.clone(),
+                config: Config {
+                    limits: limits.clone(),
+                    admin_api_key: ADMIN_KEY.into(),
+                    features: Features::default(),
+                    ..Config::from_env()
+                },
+                jobs,
+                routing: None,
+                shakemap: None,
+                counters: Default::default(),
+                caches: vec![etags.clone()],
+                tag_streams: Arc::new(Semaphore::new(limits.max_tag_streams)),
+            };
+            cfg.app_data(web::Data::new(state))
+                .app_data(web::Data::new(repos.clone()))
+                .app_data(web::Data::from(repos.population.clone()))
+                .app_data(web::Data::from(repos.geocoding.clone()))
+                .app_data(web::Data::from(repos.countries.clone()))
+                .app_data(web::Data::new(graphql::build_schema(repos, limits.clone(), Features::default())))
+                .app_data(limits.json_config())
+                .route("/", web::get().to(routes::root::root))
+                .service(
+                    web::scope(API_PREFIX)
+                        .wrap(CrsInput)
+                        .wrap(StatementTimeout::new(limits.clone()))
+                        .wrap(ConditionalGet::new(Duration::from_secs(300), etags.clone()))
+                        .configure(|cfg| routes::configure(cfg, &Features::default())),
+                )
+                .service(
+                    web::scope(API_V2_PREFIX)
+                        .wrap(CrsInput)
+                        .wrap(StatementTimeout::new(limits.clone()))
+                        .wrap(ConditionalGet::new(Duration::from_secs(300), etags))
+                        .configure(|cfg| routes::v2::configure(cfg, &Features::default())),
+                );
+        }
+    }
+}
+
+/// Sends a request and returns the status with the body parsed as JSON
+/// (`Value::Null` for an empty or non-JSON body).
+pub(crate) async fn send<S, B>(app: &S, req: test::TestRequest) -> (StatusCode, Value)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let (status, body) = send_raw(app, req).await;
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+/// Like [`send`], for streaming (SSE / NDJSON) responses.
+pub(crate) async fn send_raw<S, B>(app: &S, req: test::TestRequest) -> (StatusCode, String)
+where
+    S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req.to_request()).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+/// `GET {API_PREFIX}{path}`.
+pub(crate) fn get(path: &str) -> test::TestRequest {
+    test::TestRequest::get().uri(&format!("{API_PREFIX}{path}"))
+}
+
+/// `POST {API_PREFIX}{path}` with a JSON body.
+pub(crate) fn post_json(path: &str, body: Value) -> test::TestRequest {
+    test::TestRequest::post().uri(&format!("{API_PREFIX}{path}")).set_json(body)
+}
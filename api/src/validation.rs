@@ -1,108 +1,124 @@
 use crate::errors::AppError;
-use validator::ValidationError;
+use crate::models::{ContinentQuery, Sovereignty};
+use crate::repositories::CountryFilter;
 
-pub(crate) const MAX_BATCH_SIZE: usize = 1000;
-pub(crate) const MAX_RADIUS_KM: f64 = 500.0;
-pub(crate) const MAX_POPULATION_RADIUS_KM: f64 = 10.0;
-pub(crate) const VALID_CONTINENTS: &[&str] = &[
-    "asia", "europe", "africa", "oceania", "americas",
-    "north-america", "south-america",
-];
+pub(crate) use geopop_types::validation::*;
 
-pub fn validate_lat(lat: f64) -> Result<(), ValidationError> {
-    if !lat.is_finite() || lat < -90.0 || lat > 90.0 {
-        return Err(ValidationError::new("latitude"));
-    }
-    Ok(())
-}
-
-pub fn validate_lon(lon: f64) -> Result<(), ValidationError> {
-    if !lon.is_finite() || lon < -180.0 || lon >= 180.0 {
-        return Err(ValidationError::new("longitude"));
-    }
-    Ok(())
+/// Parses a `lat,lon` query value, as used by the two-point endpoints.
+pub(crate) fn parse_location(raw: &str) -> Result<(f64, f64), AppError> {
+    parse_lat_lon(raw)
+        .ok_or_else(|| AppError::Validation(format!("invalid location `{raw}`, expected lat,lon")))
 }
 
-pub fn validate_population_radius(radius: f64) -> Result<(), ValidationError> {
-    if !radius.is_finite() || radius <= 0.0 || radius > MAX_POPULATION_RADIUS_KM {
-        return Err(ValidationError::new("radius"));
+pub(crate) fn validate_continent(input: &str) -> Result<String, AppError> {
+    let normalized = input.trim().to_lowercase();
+    if normalized.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Missing required parameter: continent. Valid values: {}",
+            VALID_CONTINENTS.join(", ")
+        )));
     }
-    Ok(())
-}
-
-pub fn validate_radius_field(radius: f64) -> Result<(), ValidationError> {
-    if !radius.is_finite() || radius <= 0.0 || radius > MAX_RADIUS_KM {
-        return Err(ValidationError::new("radius"));
+    if !VALID_CONTINENTS.contains(&normalized.as_str()) {
+        return Err(AppError::Validation(format!(
+            "Invalid continent '{input}'. Valid values: {}",
+            VALID_CONTINENTS.join(", ")
+        )));
     }
-    Ok(())
+    Ok(normalized)
 }
 
-pub fn validate_page(page: i64) -> Result<(), ValidationError> {
-    if page < 1 {
-        return Err(ValidationError::new("page"));
-    }
-    Ok(())
+pub(crate) fn validate_region(input: &str) -> Result<String, AppError> {
+    canonical_name(VALID_REGIONS, input).map(str::to_string).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Invalid region '{input}'. Valid values: {}",
+            VALID_REGIONS.join(", ")
+        ))
+    })
 }
 
-pub fn validate_per_page(per_page: i64) -> Result<(), ValidationError> {
-    if per_page < 1 || per_page > 100 {
-        return Err(ValidationError::new("per_page"));
-    }
-    Ok(())
+pub(crate) fn validate_subregion(input: &str) -> Result<String, AppError> {
+    canonical_name(VALID_SUBREGIONS, input).map(str::to_string).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Invalid subregion '{input}'. Valid values: {}",
+            VALID_SUBREGIONS.join(", ")
+        ))
+    })
 }
 
-pub fn validate_city_query(q: &str) -> Result<(), ValidationError> {
-    let trimmed = q.trim();
-    if trimmed.len() < 2 || trimmed.len() > 80 {
-        return Err(ValidationError::new("q"));
+/// Normalises a `/countries` filter, requiring at least one of its fields.
+pub(crate) fn validate_country_filter(query: &ContinentQuery) -> Result<CountryFilter, AppError> {
+    if query.continent.is_none()
+        && query.region.is_none()
+        && query.subregion.is_none()
+        && query.sovereign.is_none()
+    {
+        return Err(AppError::Validation(
+            "Missing filter: pass at least one of continent, region, subregion or sovereign".into(),
+        ));
     }
-    Ok(())
+    Ok(CountryFilter {
+        continent: query.continent.as_deref().map(validate_continent).transpose()?,
+        region: query.region.as_deref().map(validate_region).transpose()?,
+        subregion: query.subregion.as_deref().map(validate_subregion).transpose()?,
+        sovereign: match query.sovereign.unwrap_or_default() {
+            Sovereignty::Sovereign => Some(true),
+            Sovereignty::Dependent => Some(false),
+            Sovereignty::All => None,
+        },
+    })
 }
 
-pub fn validate_optional_iso2(code: &str) -> Result<(), ValidationError> {
-    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
-        return Err(ValidationError::new("country"));
+/// Checks a `z/x/y` tile address against `MAX_TILE_ZOOM` and the tile matrix.
+pub(crate) fn validate_tile(z: u32, x: u32, y: u32) -> Result<(), AppError> {
+    if z > MAX_TILE_ZOOM {
+        return Err(AppError::Validation(format!("zoom {z} is above the maximum of {MAX_TILE_ZOOM}")));
     }
-    Ok(())
-}
-
-pub fn validate_city_limit(limit: i64) -> Result<(), ValidationError> {
-    if limit < 1 || limit > 50 {
-        return Err(ValidationError::new("limit"));
+    let size = 1u32 << z;
+    if x >= size || y >= size {
+        return Err(AppError::Validation(format!(
+            "tile {z}/{x}/{y} is outside the {size}×{size} matrix of zoom {z}"
+        )));
     }
     Ok(())
 }
 
-pub fn validate_min_population(value: i64) -> Result<(), ValidationError> {
-    if value < 0 {
-        return Err(ValidationError::new("min_population"));
+pub(crate) fn validate_iso2(iso2: &str) -> Result<String, AppError> {
+    let normalized = iso2.to_uppercase();
+    if normalized.len() != 2 || !normalized.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(
+            "ISO-3166 alpha-2 code must be exactly 2 letters (e.g. US, IN, GB)".into(),
+        ));
     }
-    Ok(())
+    Ok(normalized)
 }
 
-pub fn validate_continent_field(continent: &str) -> Result<(), ValidationError> {
-    let normalized = continent.trim().to_lowercase();
-    if normalized.is_empty() || !VALID_CONTINENTS.contains(&normalized.as_str()) {
-        return Err(ValidationError::new("continent"));
+/// Checks a GeoNames admin code (`LK.36`, `LK.36.1234`) and upper-cases its
+/// country part.
+pub(crate) fn validate_admin_code(code: &str) -> Result<String, AppError> {
+    let mut parts = code.split('.');
+    let country = parts.next().filter(|c| c.len() == 2 && c.chars().all(|c| c.is_ascii_alphabetic()));
+    let units: Vec<&str> = parts.collect();
+    let valid_unit = |u: &&str| !u.is_empty() && u.len() <= 20 && u.chars().all(|c| c.is_ascii_alphanumeric());
+    match country {
+        Some(country) if (1..=2).contains(&units.len()) && units.iter().all(valid_unit) => {
+            Ok(format!("{}.{}", country.to_uppercase(), units.join(".")))
+        }
+        _ => Err(AppError::Validation(format!(
+            "Invalid admin code '{code}': expected a GeoNames code such as LK.36 or LK.36.1234"
+        ))),
     }
-    Ok(())
 }
 
-pub(crate) fn validate_continent(input: &str) -> Result<String, AppError> {
-    let normalized = input.trim().to_lowercase();
-    if normalized.is_empty() {
-        return Err(AppError::Validation(format!(
-            "Missing required parameter: continent. Valid values: {}",
-            VALID_CONTINENTS.join(", ")
-        )));
+/// Zero-pads an ISO 3166-1 numeric code (`4` or `004` → `004`).
+pub(crate) fn validate_iso_numeric(code: &str) -> Result<String, AppError> {
+    match code.parse::<u16>() {
+        Ok(n @ 1..=999) if code.len() <= 3 && code.bytes().all(|b| b.is_ascii_digit()) => {
+            Ok(format!("{n:03}"))
+        }
+        _ => Err(AppError::Validation(
+            "ISO-3166 numeric code must be 1 to 3 digits (e.g. 840, 356, 826)".into(),
+        )),
     }
-    if !VALID_CONTINENTS.contains(&normalized.as_str()) {
-        return Err(AppError::Validation(format!(
-            "Invalid continent '{input}'. Valid values: {}",
-            VALID_CONTINENTS.join(", ")
-        )));
-    }
-    Ok(normalized)
 }
 
 pub(crate) fn validate_iso3(iso3: &str) -> Result<String, AppError> {
@@ -114,17 +130,3 @@ pub(crate) fn validate_iso3(iso3: &str) -> Result<String, AppError> {
     }
     Ok(normalized)
 }
-
-pub(crate) fn validate_batch_size(size: usize) -> Result<(), AppError> {
-    if size == 0 {
-        return Err(AppError::Validation(
-            "Request must contain at least one point".into(),
-        ));
-    }
-    if size > MAX_BATCH_SIZE {
-        return Err(AppError::Validation(format!(
-            "Maximum {MAX_BATCH_SIZE} points per batch request"
-        )));
-    }
-    Ok(())
-}
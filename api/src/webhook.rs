@@ -0,0 +1,111 @@
+//! Outbound webhook targets: job callbacks and monitor alerts.
+//!
+//! A webhook URL is checked when it is submitted and again before every
+//! delivery: its host must resolve only to public addresses and, when
+//! `WEBHOOK_ALLOWED_HOSTS` is set, be one of the listed hosts. The delivery
+//! client also resolves through [`PublicResolver`] and never follows
+//! redirects, so a host re-pointed after the check, or a receiver answering
+//! with a redirect, still cannot reach this host or a private network.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+
+use crate::validation::is_public_ip;
+
+/// Webhook settings: the signing key (`WEBHOOK_SECRET`; empty disables
+/// webhooks) and the hosts they may be sent to (`WEBHOOK_ALLOWED_HOSTS`;
+/// empty allows any host with public addresses only).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Webhooks {
+    secret: Arc<str>,
+    allowed_hosts: Arc<[String]>,
+}
+
+impl Webhooks {
+    pub fn new(secret: &str, allowed_hosts: Vec<String>) -> Self {
+        Self { secret: secret.into(), allowed_hosts: allowed_hosts.into() }
+    }
+
+    /// Whether webhooks can be sent (a secret is configured).
+    pub fn enabled(&self) -> bool {
+        !self.secret.is_empty()
+    }
+
+    /// Key the deliveries are signed with.
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Why `url` may not receive webhooks, if it may not: an unlisted host,
+    /// or one that does not resolve or resolves to a non-public address.
+    pub async fn check(&self, url: &str) -> Result<(), String> {
+        let url = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
+        let host = url.host_str().ok_or("URL has no host")?;
+        if !self.allowed_hosts.is_empty() && !self.allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+            return Err(format!("host {host} is not in WEBHOOK_ALLOWED_HOSTS"));
+        }
+        let addrs: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+            Ok(ip) => vec![ip],
+            Err(_) => tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(443)))
+                .await
+                .map_err(|e| format!("cannot resolve {host}: {e}"))?
+                .map(|addr| addr.ip())
+                .collect(),
+        };
+        match addrs.iter().find(|ip| !is_public_ip(**ip)) {
+            Some(ip) => Err(format!("{host} resolves to non-public address {ip}")),
+            None if addrs.is_empty() => Err(format!("{host} resolves to no address")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// HTTP client for webhook deliveries: connects only to public addresses
+/// and reports redirects as the receiver's answer instead of following them.
+pub(crate) fn client(timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .redirect(Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+}
+
+/// System resolver that drops non-public addresses from every answer.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host, 0)).await?.filter(|addr| is_public_ip(addr.ip())).collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} resolves to no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn rejects_internal_and_unlisted_targets() {
+        let any = Webhooks::default();
+        assert!(any.check("http://93.184.216.34/hook").await.is_ok());
+        for url in ["http://127.0.0.1:8080/", "http://[::1]/", "http://169.254.169.254/", "http://localhost/"] {
+            assert!(any.check(url).await.is_err(), "{url}");
+        }
+
+        let listed = Webhooks::new("secret", vec!["hooks.example.com".into()]);
+        let err = listed.check("http://93.184.216.34/hook").await.unwrap_err();
+        assert!(err.contains("WEBHOOK_ALLOWED_HOSTS"), "{err}");
+    }
+}
@@ -0,0 +1,88 @@
+//! Tropical-cyclone wind-field geometry.
+//!
+//! A wind swath is described by four quadrant radii (NE, SE, SW, NW, in km)
+//! around the storm centre — the same shape as advisory wind radii. When an
+//! advisory doesn't give radii for a threshold, they are modelled from the
+//! maximum sustained wind and radius of maximum winds with a modified Rankine
+//! vortex, `V(r) = Vmax · (RMW / r)^x` for `r ≥ RMW`.
+
+use serde_json::{json, Value};
+
+/// Wind thresholds (knots) reported for every storm.
+pub const THRESHOLDS_KT: [u16; 3] = [34, 50, 64];
+
+/// Decay exponent of the modified Rankine vortex outside the RMW.
+const RANKINE_EXPONENT: f64 = 0.5;
+const KM_PER_DEG: f64 = 111.32;
+const ARC_STEPS: usize = 16;
+
+/// Radius (km) at which a modified Rankine vortex decays to `threshold_kt`.
+/// Zero when the storm never reaches the threshold.
+pub fn rankine_radius(max_wind_kt: f64, rmw_km: f64, threshold_kt: f64) -> f64 {
+    if max_wind_kt < threshold_kt {
+        return 0.0;
+    }
+    rmw_km * (max_wind_kt / threshold_kt).powf(1.0 / RANKINE_EXPONENT)
+}
+
+/// GeoJSON polygon of a quadrant swath, using the same equirectangular distance
+/// as the grid queries. `None` when every radius is zero.
+pub fn swath_polygon(lat: f64, lon: f64, radii_km: [f64; 4]) -> Option<Value> {
+    if radii_km.iter().all(|&r| r <= 0.0) {
+        return None;
+    }
+    let cos_lat = lat.to_radians().cos().max(0.01);
+
+    // Walk the quadrants clockwise (compass order), then reverse for an RFC 7946
+    // counter-clockwise exterior ring.
+    let mut ring: Vec<[f64; 2]> = Vec::with_capacity(4 * (ARC_STEPS + 1) + 1);
+    for (q, &r) in radii_km.iter().enumerate() {
+        for step in 0..=ARC_STEPS {
+            let bearing = (q as f64 * 90.0 + 90.0 * step as f64 / ARC_STEPS as f64).to_radians();
+            ring.push([
+                round6(lon + r * bearing.sin() / (KM_PER_DEG * cos_lat)),
+                round6((lat + r * bearing.cos() / KM_PER_DEG).clamp(-90.0, 90.0)),
+            ]);
+        }
+    }
+    ring.dedup();
+    ring.reverse();
+    ring.push(ring[0]);
+
+    Some(json!({ "type": "Polygon", "coordinates": [ring] }))
+}
+
+/// Area (km²) enclosed by four quarter-circles of the given radii.
+pub fn swath_area_km2(radii_km: [f64; 4]) -> f64 {
+    radii_km.iter().map(|r| std::f64::consts::PI * r * r / 4.0).sum()
+}
+
+#[inline]
+fn round6(v: f64) -> f64 {
+    (v * 1e6).round() / 1e6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rankine_radius_at_max_wind_is_rmw() {
+        assert!((rankine_radius(100.0, 30.0, 100.0) - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rankine_radius_grows_for_weaker_thresholds() {
+        // (120 / 60)^2 = 4
+        assert!((rankine_radius(120.0, 25.0, 60.0) - 100.0).abs() < 1e-9);
+        assert_eq!(rankine_radius(40.0, 25.0, 64.0), 0.0);
+    }
+
+    #[test]
+    fn swath_ring_is_closed() {
+        let polygon = swath_polygon(20.0, -75.0, [200.0, 150.0, 100.0, 120.0]).unwrap();
+        let ring: Vec<[f64; 2]> = serde_json::from_value(polygon["coordinates"][0].clone()).unwrap();
+        assert_eq!(ring.first(), ring.last());
+        assert!(swath_polygon(20.0, -75.0, [0.0; 4]).is_none());
+    }
+}
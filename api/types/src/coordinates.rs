@@ -0,0 +1,185 @@
+//! Tolerant parsing of coordinates as people write them.
+//!
+//! Besides plain decimal degrees, `lat`/`lon` query values and `lat,lon`
+//! pairs accept a hemisphere letter (`6.9271N`, `W0.1278`) and
+//! degree–minute–second notation (`6°55'38"N`, `6 55 38.5 N`, `6:55:38N`).
+//! A pair may also be written without a comma when hemisphere letters mark
+//! where one half ends (`6°55'38"N 79°51'40"E`); a longitude-first pair is
+//! recognised by its letters.
+
+use serde::de::{self, Deserializer, Visitor};
+use std::fmt;
+
+/// Which half of a coordinate a value is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Lat,
+    Lon,
+}
+
+impl Axis {
+    fn of(hemisphere: char) -> Self {
+        if matches!(hemisphere, 'N' | 'S') {
+            Self::Lat
+        } else {
+            Self::Lon
+        }
+    }
+}
+
+fn hemisphere(c: char) -> Option<char> {
+    let c = c.to_ascii_uppercase();
+    matches!(c, 'N' | 'S' | 'E' | 'W').then_some(c)
+}
+
+/// Signed decimal degrees of one angle, with its hemisphere letter if it had one.
+fn parse_angle(raw: &str) -> Option<(f64, Option<char>)> {
+    let s = raw.trim();
+    let (hemi, body) = match (s.chars().next().and_then(hemisphere), s.chars().last().and_then(hemisphere)) {
+        (Some(h), _) => (Some(h), &s[1..]),
+        (None, Some(h)) => (Some(h), &s[..s.len() - 1]),
+        (None, None) => (None, s),
+    };
+    let body = body.trim();
+    let negative = body.starts_with('-');
+    if negative && hemi.is_some() {
+        return None;
+    }
+
+    let normalised: String = body
+        .chars()
+        .map(|c| if matches!(c, '°' | 'º' | '\'' | '′' | '’' | '"' | '″' | '”' | ':') { ' ' } else { c })
+        .collect();
+    let parts = normalised
+        .split_whitespace()
+        .map(|t| t.parse::<f64>().ok().filter(|v| v.is_finite()))
+        .collect::<Option<Vec<f64>>>()?;
+    let (degrees, minutes, seconds) = match parts[..] {
+        [d] => (d, 0.0, 0.0),
+        [d, m] if d.fract() == 0.0 => (d, m, 0.0),
+        [d, m, s] if d.fract() == 0.0 && m.fract() == 0.0 => (d, m, s),
+        _ => return None,
+    };
+    if !(0.0..60.0).contains(&minutes) || !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    let magnitude = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    let value = if negative || matches!(hemi, Some('S' | 'W')) { -magnitude } else { magnitude };
+    Some((value, hemi))
+}
+
+/// One latitude or longitude in any of the accepted notations. A hemisphere
+/// letter must match `axis`. Ranges are left to the validators.
+pub fn parse_coordinate(raw: &str, axis: Axis) -> Option<f64> {
+    let (value, hemi) = parse_angle(raw)?;
+    (hemi.is_none_or(|h| Axis::of(h) == axis)).then_some(value)
+}
+
+/// A `lat,lon` pair in any of the accepted notations, as `(lat, lon)`.
+pub fn parse_pair(raw: &str) -> Option<(f64, f64)> {
+    let s = raw.trim();
+    let (first, second) = if let Some(halves) = s.split_once([',', ';']) {
+        halves
+    } else {
+        let letters: Vec<usize> = s.char_indices().filter(|(_, c)| hemisphere(*c).is_some()).map(|(i, _)| i).collect();
+        match letters[..] {
+            // Leading letters (`N6.9 E79.8`) start each half; trailing ones end it.
+            [0, second] => s.split_at(second),
+            [first, _] => s.split_at(first + 1),
+            [] => {
+                let mut tokens = s.split_whitespace();
+                match (tokens.next(), tokens.next(), tokens.next()) {
+                    (Some(a), Some(b), None) => (a, b),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    };
+
+    let (a, a_hemi) = parse_angle(first)?;
+    let (b, b_hemi) = parse_angle(second)?;
+    let (lat, lon) = match (a_hemi.map(Axis::of), b_hemi.map(Axis::of)) {
+        (Some(Axis::Lon), None | Some(Axis::Lat)) | (None, Some(Axis::Lat)) => (b, a),
+        (Some(Axis::Lat), Some(Axis::Lat)) | (Some(Axis::Lon), Some(Axis::Lon)) => return None,
+        _ => (a, b),
+    };
+    Some((lat, lon))
+}
+
+struct CoordinateVisitor(Axis);
+
+impl Visitor<'_> for CoordinateVisitor {
+    type Value = f64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Axis::Lat => f.write_str("a latitude in decimal degrees or DMS"),
+            Axis::Lon => f.write_str("a longitude in decimal degrees or DMS"),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+        parse_coordinate(v, self.0).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+/// `deserialize_with` for latitude fields: a number, or a string in any accepted notation.
+pub fn deserialize_lat<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserializer.deserialize_any(CoordinateVisitor(Axis::Lat))
+}
+
+/// `deserialize_with` for longitude fields: a number, or a string in any accepted notation.
+pub fn deserialize_lon<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserializer.deserialize_any(CoordinateVisitor(Axis::Lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: Option<f64>, expected: f64) -> bool {
+        actual.is_some_and(|v| (v - expected).abs() < 1e-4)
+    }
+
+    #[test]
+    fn parses_single_coordinates() {
+        assert_eq!(parse_coordinate("6.9271", Axis::Lat), Some(6.9271));
+        assert_eq!(parse_coordinate("6.9271N", Axis::Lat), Some(6.9271));
+        assert_eq!(parse_coordinate("w0.1278", Axis::Lon), Some(-0.1278));
+        assert!(close(parse_coordinate("6°55'38\"N", Axis::Lat), 6.9272));
+        assert!(close(parse_coordinate("6° 55′ 37.6″ S", Axis::Lat), -6.9271));
+        assert!(close(parse_coordinate("79 51 40 E", Axis::Lon), 79.8611));
+        assert!(close(parse_coordinate("-79:51.67", Axis::Lon), -79.8612));
+        for raw in ["", "N", "6.9271E", "-6.9S", "6.5 30", "6 60 0", "6 55 38 12", "abc"] {
+            assert_eq!(parse_coordinate(raw, Axis::Lat), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_pairs() {
+        assert_eq!(parse_pair("6.9271, 79.8612"), Some((6.9271, 79.8612)));
+        assert_eq!(parse_pair("6.9271N, 79.8612E"), Some((6.9271, 79.8612)));
+        assert_eq!(parse_pair("6.9271 79.8612"), Some((6.9271, 79.8612)));
+        assert_eq!(parse_pair("79.8612E 6.9271N"), Some((6.9271, 79.8612)));
+        assert_eq!(parse_pair("S33.8688 E151.2093"), Some((-33.8688, 151.2093)));
+        let (lat, lon) = parse_pair("6°55'38\"N 79°51'40\"E").unwrap();
+        assert!(close(Some(lat), 6.9272) && close(Some(lon), 79.8611));
+        for raw in ["", "6.9", "6.9N 7.1S", "6 55 38 79 51 40", "6.9,79.8,1"] {
+            assert_eq!(parse_pair(raw), None, "{raw}");
+        }
+    }
+}
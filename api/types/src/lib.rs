@@ -0,0 +1,13 @@
+//! Request and response types of the GeoPop API.
+//!
+//! Shared by the server (`geopop-api`) and the client (`geopop-client`), so the
+//! wire format is declared once. Request types carry their `validator` rules,
+//! letting clients reject bad input before it leaves the process.
+
+pub mod coordinates;
+pub mod requests;
+pub mod responses;
+pub mod validation;
+
+pub use requests::*;
+pub use responses::*;
@@ -0,0 +1,1399 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Single coordinate query for population or geocoding lookups.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612}))]
+pub struct PointQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+}
+
+/// Population query with optional radius for grid cell retrieval.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "radius": 5.0}))]
+pub struct PopulationQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Optional search radius in kilometres. When omitted, returns a single grid cell. When provided, returns all non-empty grid cells within the radius (max: 10 km).
+    #[validate(custom(function = "crate::validation::validate_population_radius"))]
+    #[schema(example = 5.0, minimum = 0, maximum = 10)]
+    pub radius: Option<f64>,
+
+    /// WorldPop grid to read: `unconstrained` (default) or `constrained`
+    #[schema(example = "constrained")]
+    pub dataset: Option<Dataset>,
+
+    /// Population grid year. Defaults to the latest year loaded.
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub year: Option<i32>,
+
+    /// How radius cells are returned: native `grid` cells (default), or summed into `h3` hexagons or `s2` cells
+    #[schema(example = "h3")]
+    pub aggregation: Option<Aggregation>,
+
+    /// H3 resolution for `aggregation=h3` (0–15, default: 7)
+    #[validate(custom(function = "crate::validation::validate_h3_resolution"))]
+    #[schema(example = 7, minimum = 0, maximum = 15)]
+    pub resolution: Option<u8>,
+
+    /// S2 level for `aggregation=s2` (0–30, default: 12)
+    #[validate(custom(function = "crate::validation::validate_s2_level"))]
+    #[schema(example = 12, minimum = 0, maximum = 30)]
+    pub level: Option<u8>,
+}
+
+/// Population change between two grid years, used by /population/change.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "radius": 5.0, "from_year": 2020, "to_year": 2025}))]
+pub struct PopulationChangeQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Optional search radius in kilometres. When provided, compares every grid cell within the radius (max: 10 km).
+    #[validate(custom(function = "crate::validation::validate_population_radius"))]
+    #[schema(example = 5.0, minimum = 0, maximum = 10)]
+    pub radius: Option<f64>,
+
+    /// Earlier grid year to compare from
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub from_year: i32,
+
+    /// Later grid year to compare to
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2025)]
+    pub to_year: i32,
+
+    /// WorldPop grid to read: `unconstrained` (default) or `constrained`
+    #[schema(example = "unconstrained")]
+    pub dataset: Option<Dataset>,
+}
+
+/// Viewport query for population grid cells, used by /population/grid.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"bbox": "79.80,6.85,79.95,7.00", "max_cells": 5000}))]
+pub struct PopulationGridQuery {
+    /// Viewport as `min_lon,min_lat,max_lon,max_lat` (each side at most 10°)
+    #[validate(custom(function = "crate::validation::validate_bbox"))]
+    #[schema(example = "79.80,6.85,79.95,7.00")]
+    pub bbox: String,
+
+    /// Most cells to return; denser viewports are aggregated into larger blocks (default: 5000, max: 20000)
+    #[serde(default = "default_max_cells")]
+    #[validate(custom(function = "crate::validation::validate_max_cells"))]
+    #[schema(example = 5000, minimum = 1, maximum = 20000, default = 5000)]
+    pub max_cells: i64,
+
+    /// How cells are returned: native `grid` cells, merged into blocks past `max_cells` (default), or summed into `h3` hexagons or `s2` cells
+    #[schema(example = "h3")]
+    pub aggregation: Option<Aggregation>,
+
+    /// H3 resolution for `aggregation=h3` (0–15, default: 7)
+    #[validate(custom(function = "crate::validation::validate_h3_resolution"))]
+    #[schema(example = 7, minimum = 0, maximum = 15)]
+    pub resolution: Option<u8>,
+
+    /// S2 level for `aggregation=s2` (0–30, default: 12)
+    #[validate(custom(function = "crate::validation::validate_s2_level"))]
+    #[schema(example = 12, minimum = 0, maximum = 30)]
+    pub level: Option<u8>,
+}
+
+/// Grid pick for the H3 and S2 cell lookups, /population/h3/{index} and
+/// /population/s2/{cell}.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"year": 2020}))]
+pub struct CellIndexQuery {
+    /// WorldPop grid to read: `unconstrained` (default) or `constrained`
+    #[schema(example = "constrained")]
+    pub dataset: Option<Dataset>,
+
+    /// Population grid year. Defaults to the latest year loaded.
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub year: Option<i32>,
+}
+
+/// Which cached responses `/admin/cache/flush` drops.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"prefix": "/country"}))]
+pub struct CacheFlushQuery {
+    /// Only drop entries for paths starting with this prefix, relative to
+    /// `/api/v1` (e.g. `/country`). Everything when omitted.
+    #[validate(custom(function = "crate::validation::validate_path_prefix"))]
+    #[schema(example = "/population/grid")]
+    pub prefix: Option<String>,
+}
+
+/// Options of `/health`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({"detail": true}))]
+pub struct HealthQuery {
+    /// Add pool usage, database latency and the PostGIS version; needs a
+    /// valid `X-API-Key` (when `API_KEY` is set) or `X-Admin-Key`
+    #[serde(default)]
+    pub detail: bool,
+}
+
+fn default_max_cells() -> i64 {
+    5000
+}
+
+/// Batch request containing multiple coordinate points (max 1000).
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"points": [{"lat": 6.9271, "lon": 79.8612}, {"lat": 7.2906, "lon": 80.6337}]}))]
+pub struct BatchQuery {
+    /// Array of coordinate points to query (1–1000 points)
+    #[validate(length(min = 1, max = 1000, message = "Must contain between 1 and 1000 points"))]
+    pub points: Vec<PointQuery>,
+}
+
+/// Population exposure query with configurable search radius.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "radius": 10.0}))]
+pub struct ExposureQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Search radius in kilometres (default: 1, max: 500)
+    #[serde(default = "default_radius")]
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 10.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: f64,
+
+    /// Optional distance-decay model for a weighted exposure figure alongside the raw total
+    #[schema(example = "linear")]
+    pub decay: Option<DecayModel>,
+
+    /// Exposure mode (default: standard). `coastal` adds a tsunami-style coastal figure.
+    #[schema(example = "coastal")]
+    pub mode: Option<ExposureMode>,
+
+    /// Coastal mode: only count cells within this distance of the coastline, km (default: 10, max: 100)
+    #[validate(custom(function = "crate::validation::validate_coast_km"))]
+    #[schema(example = 5.0, minimum = 0, maximum = 100)]
+    pub coast_km: Option<f64>,
+
+    /// Coastal mode: only count cells at or below this elevation, metres (default: 10, range: -100 to 100)
+    #[validate(custom(function = "crate::validation::validate_max_elevation"))]
+    #[schema(example = 10.0, minimum = -100, maximum = 100)]
+    pub max_elevation_m: Option<f64>,
+
+    /// Comma-separated ring radii in km for a per-band breakdown, ascending and
+    /// no larger than `radius` (max 10 rings)
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_rings"))]
+    #[schema(example = "5,10,25,50")]
+    pub rings: Option<String>,
+
+    /// Add `histogram`: grid cells and population per density bucket
+    #[schema(example = true)]
+    pub histogram: Option<bool>,
+
+    /// Count only land in `area_km2` and `density_per_km2` (country boundaries)
+    #[schema(example = true)]
+    pub land_only: Option<bool>,
+
+    /// Add `top_cells`: this many most populated grid cells, with bounds (max 100)
+    #[validate(custom(function = "crate::validation::validate_top_cells"))]
+    #[schema(example = 10, minimum = 1, maximum = 100)]
+    pub top_cells: Option<i64>,
+
+    /// With `include=places`: number of places to embed, nearest first (default: 20, max: 100)
+    #[validate(custom(function = "crate::validation::validate_per_page"))]
+    #[schema(example = 50, minimum = 1, maximum = 100)]
+    pub places_limit: Option<i64>,
+
+    /// With `include=places`: number of nearest places to skip (default: 0)
+    #[validate(custom(function = "crate::validation::validate_offset"))]
+    #[schema(example = 50, minimum = 0)]
+    pub places_offset: Option<i64>,
+
+    /// With `include=places`: sort places by `distance` (default), `population` or `name`
+    #[schema(example = "population")]
+    pub sort: Option<PlaceSort>,
+
+    /// With `include=places`: `asc` or `desc` (default: desc for population, asc otherwise)
+    #[schema(example = "desc")]
+    pub order: Option<SortOrder>,
+
+    /// Only count and list places with at least this GeoNames population (default: 0)
+    #[validate(custom(function = "crate::validation::validate_min_population"))]
+    #[schema(example = 5000, minimum = 0)]
+    pub min_place_population: Option<i64>,
+
+    /// Aggregate the places in the radius per `admin1` or `admin2` region into `admin_groups`
+    #[schema(example = "admin1")]
+    pub group_by: Option<AdminLevel>,
+
+    /// WorldPop grid to sum: `unconstrained` (default) or `constrained`
+    #[schema(example = "constrained")]
+    pub dataset: Option<Dataset>,
+
+    /// Population grid year. Defaults to the latest year loaded.
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub year: Option<i32>,
+}
+
+/// Which WorldPop population grid a query reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Dataset {
+    /// Population spread over all land, including unsettled areas
+    #[default]
+    Unconstrained,
+    /// Population placed only in cells with mapped building footprints
+    Constrained,
+}
+
+impl Dataset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unconstrained => "unconstrained",
+            Self::Constrained => "constrained",
+        }
+    }
+}
+
+/// How grid queries return their cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// WorldPop's own 30 arc-second cells
+    #[default]
+    Grid,
+    /// Cells summed into H3 hexagons by the hexagon their centre falls in
+    H3,
+    /// Cells summed into Google S2 cells by the S2 cell their centre falls in
+    S2,
+}
+
+impl Aggregation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::H3 => "h3",
+            Self::S2 => "s2",
+        }
+    }
+}
+
+/// Default H3 resolution for `aggregation=h3` (~5 km² hexagons).
+pub const DEFAULT_H3_RESOLUTION: u8 = 7;
+/// Default S2 level for `aggregation=s2` (~5 km² cells).
+pub const DEFAULT_S2_LEVEL: u8 = 12;
+
+/// Which population sum `/exposure` computes in addition to the plain radius total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExposureMode {
+    /// Every populated cell inside the radius
+    #[default]
+    Standard,
+    /// Only low-lying cells near the coast — tsunami / storm-surge reach
+    Coastal,
+}
+
+/// Default coastal band for `mode=coastal`, km.
+pub const DEFAULT_COAST_KM: f64 = 10.0;
+/// Default elevation ceiling for `mode=coastal`, metres.
+pub const DEFAULT_MAX_ELEVATION_M: f64 = 10.0;
+
+/// Distance-decay weighting applied to each cell by its distance `d` (km) from the centre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecayModel {
+    /// `w = 1 - d / radius` — falls to zero at the edge of the search area
+    Linear,
+    /// `w = 1 / (1 + d²)` — half weight at 1 km, ~1% at 10 km
+    InverseSquare,
+}
+
+impl DecayModel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::InverseSquare => "inverse_square",
+        }
+    }
+}
+
+/// Field that lists of named places are sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaceSort {
+    /// Nearest first
+    #[default]
+    Distance,
+    /// GeoNames population estimate, largest first
+    Population,
+    /// Place name, alphabetical
+    Name,
+}
+
+impl PlaceSort {
+    /// Order used when `order` is not given: descending for population,
+    /// ascending otherwise.
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            Self::Population => SortOrder::Desc,
+            Self::Distance | Self::Name => SortOrder::Asc,
+        }
+    }
+}
+
+/// Administrative level that `/exposure` aggregates named places by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminLevel {
+    /// First-order division (state / province / region)
+    Admin1,
+    /// Second-order division (district / county)
+    Admin2,
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+fn default_radius() -> f64 {
+    1.0
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+/// Page selection of a paginated `/api/v2` list.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct PageQuery {
+    #[serde(default = "default_page")]
+    #[validate(custom(function = "crate::validation::validate_page"))]
+    #[schema(example = 1, minimum = 1, default = 1)]
+    pub page: i64,
+
+    #[serde(default = "default_per_page")]
+    #[validate(custom(function = "crate::validation::validate_per_page"))]
+    #[schema(example = 20, minimum = 1, maximum = 100, default = 20)]
+    pub per_page: i64,
+}
+
+impl Default for PageQuery {
+    fn default() -> Self {
+        Self { page: default_page(), per_page: default_per_page() }
+    }
+}
+
+impl PageQuery {
+    /// The items of `all` on the selected page.
+    pub fn slice<T>(&self, all: Vec<T>) -> Vec<T> {
+        let skip = ((self.page - 1) * self.per_page) as usize;
+        all.into_iter().skip(skip).take(self.per_page as usize).collect()
+    }
+}
+
+/// Paginated places query within an exposure radius.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "radius": 10.0, "page": 1, "per_page": 20}))]
+pub struct ExposurePlacesQuery {
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    #[serde(default = "default_radius")]
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 10.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: f64,
+
+    #[serde(default = "default_page")]
+    #[validate(custom(function = "crate::validation::validate_page"))]
+    #[schema(example = 1, minimum = 1, default = 1)]
+    pub page: i64,
+
+    #[serde(default = "default_per_page")]
+    #[validate(custom(function = "crate::validation::validate_per_page"))]
+    #[schema(example = 20, minimum = 1, maximum = 100, default = 20)]
+    pub per_page: i64,
+
+    /// Sort places by `distance` (default), `population` or `name`
+    #[schema(example = "population")]
+    pub sort: Option<PlaceSort>,
+
+    /// `asc` or `desc` (default: desc for population, asc otherwise)
+    #[schema(example = "desc")]
+    pub order: Option<SortOrder>,
+}
+
+/// Two locations to compare, used by /compare.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"from": "6.9271,79.8612", "to": "7.2906,80.6337", "radius": 10.0}))]
+pub struct CompareQuery {
+    /// First location as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "6.9271,79.8612")]
+    pub from: String,
+
+    /// Second location as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "7.2906,80.6337")]
+    pub to: String,
+
+    /// Exposure radius around each location in kilometres (default: 1, max: 500)
+    #[serde(default = "default_radius")]
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 10.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: f64,
+}
+
+/// Two points to measure between, used by /distance.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"from": "6.9271,79.8612", "to": "7.2906,80.6337"}))]
+pub struct DistanceQuery {
+    /// Start point as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "6.9271,79.8612")]
+    pub from: String,
+
+    /// End point as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "7.2906,80.6337")]
+    pub to: String,
+}
+
+/// Great-circle path to sample, used by /route/sample.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"from": "6.9271,79.8612", "to": "13.0827,80.2707", "interval_km": 10.0}))]
+pub struct RouteSampleQuery {
+    /// Start of the path as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "6.9271,79.8612")]
+    pub from: String,
+
+    /// End of the path as `lat,lon`
+    #[validate(custom(function = "crate::validation::validate_lat_lon"))]
+    #[schema(example = "13.0827,80.2707")]
+    pub to: String,
+
+    /// Spacing between samples in km (default: 10, max: 1000)
+    #[serde(default = "default_interval_km")]
+    #[validate(custom(function = "crate::validation::validate_interval_km"))]
+    #[schema(example = 10.0, minimum = 0, maximum = 1000, default = 10.0)]
+    pub interval_km: f64,
+}
+
+fn default_interval_km() -> f64 {
+    10.0
+}
+
+/// Travel-time area around a point, used by /exposure/isochrone.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "minutes": 30.0, "mode": "driving"}))]
+pub struct IsochroneQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Travel time in minutes (max: 120)
+    #[validate(custom(function = "crate::validation::validate_isochrone_minutes"))]
+    #[schema(example = 30.0, minimum = 0, maximum = 120)]
+    pub minutes: f64,
+
+    /// How the area is travelled (default: driving)
+    #[serde(default)]
+    pub mode: TravelMode,
+}
+
+/// Means of travel an isochrone is computed for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TravelMode {
+    /// By car on the road network
+    #[default]
+    Driving,
+    /// By bicycle
+    Cycling,
+    /// On foot
+    Walking,
+}
+
+impl TravelMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Driving => "driving",
+            Self::Cycling => "cycling",
+            Self::Walking => "walking",
+        }
+    }
+}
+
+fn default_city_limit() -> i64 {
+    10
+}
+
+fn default_min_population() -> i64 {
+    0
+}
+
+/// Fuzzy city search query, used by /cities/search.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"q": "colom", "country": "LK", "limit": 10}))]
+pub struct CitySearchQuery {
+    /// Search term (partial name, typos tolerated). Minimum 2 characters.
+    #[validate(custom(function = "crate::validation::validate_city_query"))]
+    #[schema(example = "colom", min_length = 2, max_length = 80)]
+    pub q: String,
+
+    /// Optional ISO 3166-1 alpha-2 country code to scope the search (e.g. `LK`, `us`).
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_optional_iso2"))]
+    #[schema(example = "LK", min_length = 2, max_length = 2)]
+    pub country: Option<String>,
+
+    /// Maximum number of results to return (default: 10, max: 50).
+    #[serde(default = "default_city_limit")]
+    #[validate(custom(function = "crate::validation::validate_city_limit"))]
+    #[schema(example = 10, minimum = 1, maximum = 50, default = 10)]
+    pub limit: i64,
+
+    /// Only return places with population greater than or equal to this value.
+    /// Use this to filter out hamlets / farms. Default: 0 (no filter).
+    #[serde(default = "default_min_population")]
+    #[validate(custom(function = "crate::validation::validate_min_population"))]
+    #[schema(example = 1000, minimum = 0, default = 0)]
+    pub min_population: i64,
+}
+
+/// Nearest critical facilities of one type, used by /facilities/nearest.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "type": "hospital", "limit": 5}))]
+pub struct FacilitiesQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Kind of facility to look for
+    #[serde(rename = "type")]
+    pub facility_type: FacilityType,
+
+    /// Number of facilities to return, nearest first (default: 5, max: 50)
+    #[serde(default = "default_facility_limit")]
+    #[validate(custom(function = "crate::validation::validate_facility_limit"))]
+    #[schema(example = 5, minimum = 1, maximum = 50, default = 5)]
+    pub limit: i64,
+}
+
+fn default_facility_limit() -> i64 {
+    5
+}
+
+/// Kind of critical facility, backed by a GeoNames S-class feature code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FacilityType {
+    /// Hospitals (`HSP`)
+    Hospital,
+    /// Airports (`AIRP`)
+    Airport,
+    /// Ports and harbours (`PRT`)
+    Port,
+    /// Schools (`SCH`)
+    School,
+}
+
+impl FacilityType {
+    pub const ALL: [FacilityType; 4] = [Self::Hospital, Self::Airport, Self::Port, Self::School];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hospital => "hospital",
+            Self::Airport => "airport",
+            Self::Port => "port",
+            Self::School => "school",
+        }
+    }
+
+    /// GeoNames feature code of the facilities of this type.
+    pub fn feature_code(self) -> &'static str {
+        match self {
+            Self::Hospital => "HSP",
+            Self::Airport => "AIRP",
+            Self::Port => "PRT",
+            Self::School => "SCH",
+        }
+    }
+}
+
+/// Facilities inside a radius, used by /facilities.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "radius": 25.0, "types": "hospital,airport"}))]
+pub struct FacilitiesWithinQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Search radius in kilometres (default: 1, max: 500)
+    #[serde(default = "default_radius")]
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 25.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: f64,
+
+    /// Comma-separated facility types: `hospital`, `airport`, `port`, `school` (default: all four)
+    #[validate(custom(function = "crate::validation::validate_facility_types"))]
+    #[schema(example = "hospital,airport")]
+    pub types: Option<String>,
+
+    /// Most facilities listed per type, nearest first; counts are not capped (default: 100, max: 500)
+    #[serde(default = "default_facility_list_limit")]
+    #[validate(custom(function = "crate::validation::validate_facility_list_limit"))]
+    #[schema(example = 100, minimum = 1, maximum = 500, default = 100)]
+    pub limit: i64,
+}
+
+fn default_facility_list_limit() -> i64 {
+    100
+}
+
+/// Nearest airports, used by /airports/nearest.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "min_runway_m": 1500.0, "limit": 5}))]
+pub struct AirportsQuery {
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lat")]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    #[serde(deserialize_with = "crate::coordinates::deserialize_lon")]
+    pub lon: f64,
+
+    /// Only airports with an open runway at least this long, in metres
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_min_runway_m"))]
+    #[schema(example = 1500.0, minimum = 0, maximum = 6000)]
+    pub min_runway_m: Option<f64>,
+
+    /// Number of airports to return, nearest first (default: 5, max: 50)
+    #[serde(default = "default_facility_limit")]
+    #[validate(custom(function = "crate::validation::validate_facility_limit"))]
+    #[schema(example = 5, minimum = 1, maximum = 50, default = 5)]
+    pub limit: i64,
+}
+
+/// Query filter for listing countries by continent, UN region, UN subregion
+/// or sovereignty. At least one is required; several narrow the list together.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"subregion": "Southern Asia"}))]
+pub struct ContinentQuery {
+    /// Continent name (asia, europe, africa, oceania, americas, north-america, south-america)
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_continent_field"))]
+    #[schema(example = "asia")]
+    pub continent: Option<String>,
+
+    /// UN M49 region (Africa, Americas, Asia, Europe, Oceania), case-insensitive
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_region_field"))]
+    #[schema(example = "Asia")]
+    pub region: Option<String>,
+
+    /// UN M49 subregion such as `Southern Asia` or `Western Africa`, case-insensitive
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_subregion_field"))]
+    #[schema(example = "Southern Asia")]
+    pub subregion: Option<String>,
+
+    /// `true` (default) for sovereign countries, `false` for dependent
+    /// territories only, `all` for both
+    #[serde(default)]
+    pub sovereign: Option<Sovereignty>,
+}
+
+/// Which countries a listing covers by sovereignty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub enum Sovereignty {
+    /// Sovereign states only
+    #[default]
+    #[serde(rename = "true")]
+    Sovereign,
+    /// Dependent territories only (Greenland, Puerto Rico, Réunion, …)
+    #[serde(rename = "false")]
+    Dependent,
+    /// Both
+    #[serde(rename = "all")]
+    All,
+}
+
+/// Optional boundary-vintage pin, accepted alongside the main query by every
+/// endpoint that resolves countries.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"boundaries": 2023}))]
+pub struct BoundaryQuery {
+    /// Boundary dataset vintage (release year). Defaults to the current vintage.
+    #[validate(custom(function = "crate::validation::validate_boundary_vintage"))]
+    #[schema(example = 2023)]
+    pub boundaries: Option<i32>,
+}
+
+/// How `/reverse` resolves the country and regions of a point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReverseMode {
+    /// Those of the nearest place
+    #[default]
+    Nearest,
+    /// Those whose boundary polygons contain the point
+    Contains,
+}
+
+/// Resolution mode of `/reverse`.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, ToSchema)]
+pub struct ReverseModeQuery {
+    #[serde(default)]
+    pub mode: ReverseMode,
+}
+
+/// Distance cap of a nearest-place lookup such as `/reverse`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"max_distance_km": 50.0}))]
+pub struct MaxDistanceQuery {
+    /// Answer 404 instead when the nearest place is further than this (km)
+    #[validate(custom(function = "crate::validation::validate_max_distance_km"))]
+    #[schema(example = 50.0, maximum = 20000)]
+    pub max_distance_km: Option<f64>,
+}
+
+/// Language of localised place names, overriding `Accept-Language`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lang": "ta"}))]
+pub struct LangQuery {
+    /// Comma-separated language tags, most preferred first (`ta`, `zh-TW,en`)
+    #[validate(custom(function = "crate::validation::validate_languages"))]
+    #[schema(example = "ta")]
+    pub lang: Option<String>,
+}
+
+/// Optional population-grid year pin, accepted alongside the main query by
+/// `/analyse`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"year": 2020}))]
+pub struct YearQuery {
+    /// Population grid year. Defaults to the latest year loaded.
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub year: Option<i32>,
+}
+
+/// Place options accepted alongside the main query by `/analyse`: the
+/// population floor for `include=places` and the number of nearest places.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"min_place_population": 5000, "nearest_places": 5}))]
+pub struct PlaceFilterQuery {
+    /// Only list places with at least this GeoNames population (default: 0)
+    #[validate(custom(function = "crate::validation::validate_min_population"))]
+    #[schema(example = 5000, minimum = 0)]
+    pub min_place_population: Option<i64>,
+    /// Add `nearest_places`: this many nearest named places (max 20)
+    #[validate(custom(function = "crate::validation::validate_nearest_places"))]
+    #[schema(example = 5, minimum = 1, maximum = 20)]
+    pub nearest_places: Option<i64>,
+}
+
+/// Radius search settings of `/analyse`: the first probe radius and the
+/// radius at which the search gives up.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"step_km": 1.0, "max_radius_km": 200.0}))]
+pub struct SearchRadiusQuery {
+    /// Smallest search radius; later tiers are multiples of it (default: 5)
+    #[validate(custom(function = "crate::validation::validate_step_km"))]
+    #[schema(example = 1.0, minimum = 1, maximum = 100)]
+    pub step_km: Option<f64>,
+    /// Largest search radius, at least `step_km` (default: 1000)
+    #[validate(custom(function = "crate::validation::validate_max_radius_km"))]
+    #[schema(example = 200.0, minimum = 1, maximum = 1000)]
+    pub max_radius_km: Option<f64>,
+}
+
+/// Hazard scenario of `/analyse`: a configured template and the event's
+/// magnitude, from which the radius and decay are derived.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"hazard": "earthquake", "magnitude": 6.8}))]
+pub struct HazardQuery {
+    /// Name of a hazard template (`earthquake`, `volcano`, or one configured on the server)
+    #[schema(example = "earthquake")]
+    pub hazard: Option<String>,
+    /// Event magnitude on the template's scale (moment magnitude, VEI, …)
+    #[validate(custom(function = "crate::validation::validate_magnitude"))]
+    #[schema(example = 6.8)]
+    pub magnitude: Option<f64>,
+}
+
+/// Document format of `/analyse/report`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// Self-contained HTML page with an inline SVG map
+    #[default]
+    Html,
+    /// Single-page A4 PDF
+    Pdf,
+}
+
+/// Output settings of `/analyse/report`; the analysis takes the `/analyse`
+/// parameters.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct ReportQuery {
+    /// `html` (default) or `pdf`
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Response format of `/exposure` and `/population?radius=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The usual JSON envelope
+    #[default]
+    Json,
+    /// KML document of the search area, grid cells and places, for Google Earth
+    Kml,
+    /// Zipped ESRI Shapefile of the affected grid cells (`/exposure` only)
+    Shapefile,
+}
+
+impl ExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Kml => "kml",
+            Self::Shapefile => "shapefile",
+        }
+    }
+}
+
+/// Output format of `/exposure` and `/population`, read apart from their own
+/// parameters.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct ExportQuery {
+    /// `json` (default), `kml` or `shapefile`
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// A layer of `/export/geopackage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportLayer {
+    /// WorldPop grid cells as polygons with their population
+    Population,
+    /// GeoNames places as points
+    Places,
+    /// Country boundaries clipped to the box
+    Countries,
+}
+
+impl ExportLayer {
+    pub const ALL: [ExportLayer; 3] = [Self::Population, Self::Places, Self::Countries];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Population => "population",
+            Self::Places => "places",
+            Self::Countries => "countries",
+        }
+    }
+}
+
+/// Bounding box and layers of `/export/geopackage`.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"bbox": "79.80,6.85,79.95,7.00", "layers": "population,places"}))]
+pub struct GeoPackageQuery {
+    /// Area as `min_lon,min_lat,max_lon,max_lat` (each side at most 10°)
+    #[validate(custom(function = "crate::validation::validate_bbox"))]
+    #[schema(example = "79.80,6.85,79.95,7.00")]
+    pub bbox: String,
+
+    /// Comma-separated layers: `population`, `places`, `countries` (default: all three)
+    #[validate(custom(function = "crate::validation::validate_layers"))]
+    #[schema(example = "population,places")]
+    pub layers: Option<String>,
+
+    /// Population grid year. Defaults to the latest year loaded.
+    #[validate(custom(function = "crate::validation::validate_population_year"))]
+    #[schema(example = 2020)]
+    pub year: Option<i32>,
+
+    /// Boundary dataset vintage of the `countries` layer. Defaults to the current vintage.
+    #[validate(custom(function = "crate::validation::validate_boundary_vintage"))]
+    #[schema(example = 2023)]
+    pub boundaries: Option<i32>,
+}
+
+/// One NDJSON line of a `/tag/stream` request body.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"id": "sensor-17", "lat": 6.9271, "lon": 79.8612}))]
+pub struct TagPoint {
+    /// Optional caller-supplied identifier, echoed back unchanged on the tagged line
+    #[schema(value_type = Option<Object>, example = "sensor-17")]
+    pub id: Option<serde_json::Value>,
+
+    /// Latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.9271, minimum = -90, maximum = 90)]
+    pub lat: f64,
+
+    /// Longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.8612, minimum = -180, maximum = 180)]
+    pub lon: f64,
+}
+
+/// Tropical-cyclone wind-field exposure request.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({
+    "lat": 23.1, "lon": -75.8, "max_wind_kt": 115.0, "rmw_km": 30.0,
+    "wind_radii": {"r34": {"ne": 260.0, "se": 220.0, "sw": 150.0, "nw": 190.0}}
+}))]
+pub struct CycloneQuery {
+    /// Storm centre latitude in decimal degrees (-90 to 90)
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 23.1, minimum = -90, maximum = 90)]
+    pub lat: f64,
+
+    /// Storm centre longitude in decimal degrees (-180 to 180)
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = -75.8, minimum = -180, maximum = 180)]
+    pub lon: f64,
+
+    /// Maximum sustained wind in knots (10–250)
+    #[validate(custom(function = "crate::validation::validate_wind_speed"))]
+    #[schema(example = 115.0, minimum = 10, maximum = 250)]
+    pub max_wind_kt: f64,
+
+    /// Radius of maximum winds in km (0–300). Required for any threshold without `wind_radii`.
+    #[validate(custom(function = "crate::validation::validate_rmw"))]
+    #[schema(example = 30.0, minimum = 0, maximum = 300)]
+    pub rmw_km: Option<f64>,
+
+    /// Advisory quadrant wind radii per threshold; these override the modelled radii
+    #[validate(nested)]
+    pub wind_radii: Option<WindRadii>,
+}
+
+/// Quadrant wind radii for the 34/50/64-kt thresholds.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct WindRadii {
+    /// 34-kt (gale) radii
+    #[validate(nested)]
+    pub r34: Option<QuadrantRadii>,
+    /// 50-kt (storm) radii
+    #[validate(nested)]
+    pub r50: Option<QuadrantRadii>,
+    /// 64-kt (hurricane) radii
+    #[validate(nested)]
+    pub r64: Option<QuadrantRadii>,
+}
+
+impl WindRadii {
+    pub fn for_threshold(&self, kt: u16) -> Option<QuadrantRadii> {
+        match kt {
+            34 => self.r34,
+            50 => self.r50,
+            64 => self.r64,
+            _ => None,
+        }
+    }
+}
+
+/// Wind radii in km for each quadrant around the storm centre (0–1000).
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"ne": 260.0, "se": 220.0, "sw": 150.0, "nw": 190.0}))]
+pub struct QuadrantRadii {
+    #[validate(custom(function = "crate::validation::validate_wind_radius"))]
+    pub ne: f64,
+    #[validate(custom(function = "crate::validation::validate_wind_radius"))]
+    pub se: f64,
+    #[validate(custom(function = "crate::validation::validate_wind_radius"))]
+    pub sw: f64,
+    #[validate(custom(function = "crate::validation::validate_wind_radius"))]
+    pub nw: f64,
+}
+
+impl QuadrantRadii {
+    pub fn uniform(km: f64) -> Self {
+        Self { ne: km, se: km, sw: km, nw: km }
+    }
+
+    pub fn as_array(&self) -> [f64; 4] {
+        [self.ne, self.se, self.sw, self.nw]
+    }
+}
+
+/// Flood exposure request: an area (GeoJSON polygon, or centre + radius) and a
+/// water level expressed as a maximum ground elevation.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"lat": 23.81, "lon": 90.41, "radius": 25.0, "max_elevation_m": 6.0}))]
+pub struct FloodQuery {
+    /// GeoJSON Polygon, MultiPolygon or GeometryCollection of them giving the
+    /// flood area (WGS84 lon/lat). Mutually exclusive with `lat`/`lon`/`radius`.
+    #[schema(value_type = Option<Object>, example = json!({"type": "Polygon", "coordinates": [[[90.3, 23.7], [90.5, 23.7], [90.5, 23.9], [90.3, 23.9], [90.3, 23.7]]]}))]
+    pub polygon: Option<serde_json::Value>,
+
+    /// Centre latitude in decimal degrees (-90 to 90), when no polygon is given
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 23.81, minimum = -90, maximum = 90)]
+    pub lat: Option<f64>,
+
+    /// Centre longitude in decimal degrees (-180 to 180), when no polygon is given
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 90.41, minimum = -180, maximum = 180)]
+    pub lon: Option<f64>,
+
+    /// Search radius in kilometres around `lat`/`lon` (default: 1, max: 500)
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 25.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: Option<f64>,
+
+    /// Water level: cells at or below this ground elevation (metres) are counted (-500 to 9000)
+    #[validate(custom(function = "crate::validation::validate_flood_elevation"))]
+    #[schema(example = 6.0, minimum = -500, maximum = 9000)]
+    pub max_elevation_m: f64,
+
+    /// Also report each polygon of `polygon` separately (at most 100), e.g.
+    /// the islands of an archipelago
+    #[serde(default)]
+    pub breakdown: bool,
+}
+
+/// Administrative unit whose boundary `/exposure/admin` evaluates.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct AdminExposureQuery {
+    /// GeoNames admin code: `CC.A1` for a first-level unit, `CC.A1.A2` for a
+    /// second-level one
+    #[schema(example = "LK.36")]
+    pub code: String,
+
+    /// Number of most populous places to list (default: 10, max: 100)
+    #[validate(custom(function = "crate::validation::validate_per_page"))]
+    #[schema(example = 10, minimum = 1, maximum = 100)]
+    pub places: Option<i64>,
+}
+
+/// Buffer exposure request: any GeoJSON geometry and a distance to grow it by.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"geometry": {"type": "LineString", "coordinates": [[79.85, 6.90], [79.90, 6.95], [80.00, 7.00]]}, "buffer_km": 2.0}))]
+pub struct BufferQuery {
+    /// GeoJSON Point, MultiPoint, LineString, MultiLineString, Polygon or
+    /// MultiPolygon geometry (WGS84 lon/lat)
+    #[schema(value_type = Object, example = json!({"type": "LineString", "coordinates": [[79.85, 6.90], [79.90, 6.95], [80.00, 7.00]]}))]
+    pub geometry: serde_json::Value,
+
+    /// Distance in kilometres to buffer the geometry by (0 to 100; 0 only for polygons)
+    #[validate(custom(function = "crate::validation::validate_buffer_km"))]
+    #[schema(example = 2.0, minimum = 0, maximum = 100)]
+    pub buffer_km: f64,
+}
+
+/// ShakeMap exposure request: MMI contours given inline, or the USGS event
+/// whose ShakeMap contours the server fetches.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"event_id": "us6000jllz"}))]
+pub struct ShakeMapQuery {
+    /// USGS event ID; the server fetches the event's ShakeMap `cont_mmi.json`.
+    /// Mutually exclusive with `contours`.
+    #[validate(custom(function = "crate::validation::validate_event_id"))]
+    #[schema(example = "us6000jllz")]
+    pub event_id: Option<String>,
+
+    /// ShakeMap MMI contour GeoJSON (`cont_mmi.json`): a FeatureCollection whose
+    /// features carry the intensity in `properties.value`, as polygons or closed
+    /// contour lines (WGS84 lon/lat)
+    #[schema(value_type = Option<Object>, example = json!({"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {"value": 7.0}, "geometry": {"type": "MultiLineString", "coordinates": [[[37.0, 37.0], [37.4, 37.0], [37.4, 37.4], [37.0, 37.4], [37.0, 37.0]]]}}]}))]
+    pub contours: Option<serde_json::Value>,
+}
+
+fn default_monitor_interval() -> i32 {
+    24
+}
+
+/// A location whose exposure is recomputed periodically: a GeoJSON polygon, or
+/// a centre and radius. Used to create and replace `/monitors`.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"name": "Colombo port", "lat": 6.94, "lon": 79.84, "radius": 10.0, "interval_hours": 24}))]
+pub struct MonitorRequest {
+    /// Label for the monitored asset (1–100 characters)
+    #[validate(custom(function = "crate::validation::validate_monitor_name"))]
+    #[schema(example = "Colombo port")]
+    pub name: String,
+
+    /// GeoJSON Polygon or MultiPolygon of the monitored area (WGS84 lon/lat).
+    /// Mutually exclusive with `lat`/`lon`/`radius`.
+    #[schema(value_type = Option<Object>)]
+    pub polygon: Option<serde_json::Value>,
+
+    /// Centre latitude in decimal degrees (-90 to 90), when no polygon is given
+    #[validate(custom(function = "crate::validation::validate_lat"))]
+    #[schema(example = 6.94, minimum = -90, maximum = 90)]
+    pub lat: Option<f64>,
+
+    /// Centre longitude in decimal degrees (-180 to 180), when no polygon is given
+    #[validate(custom(function = "crate::validation::validate_lon"))]
+    #[schema(example = 79.84, minimum = -180, maximum = 180)]
+    pub lon: Option<f64>,
+
+    /// Radius in kilometres around `lat`/`lon` (default: 1, max: 500)
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 10.0, minimum = 0, maximum = 500, default = 1.0)]
+    pub radius: Option<f64>,
+
+    /// Hours between exposure snapshots (1–720, default: 24)
+    #[serde(default = "default_monitor_interval")]
+    #[validate(custom(function = "crate::validation::validate_monitor_interval"))]
+    #[schema(example = 24, minimum = 1, maximum = 720, default = 24)]
+    pub interval_hours: i32,
+
+    /// Conditions recorded as alerts, and sent to `webhook_url`, when breached (at most 10)
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::validate_thresholds"), nested)]
+    pub thresholds: Vec<AlertThreshold>,
+
+    /// http(s) URL to POST alerts to, signed with the server's webhook secret
+    #[validate(custom(function = "crate::validation::validate_callback_url"))]
+    #[schema(example = "https://hooks.example.com/geopop")]
+    pub webhook_url: Option<String>,
+}
+
+/// What a monitor threshold compares with its `above` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdMetric {
+    /// Population of a snapshot; alerts when a snapshot first exceeds the value
+    Population,
+    /// Change of a snapshot from the previous one, either way
+    Change,
+    /// Population within `radius_km` of a GDACS event recorded inside the monitored area
+    EventExposure,
+}
+
+/// A condition on a monitor's exposure that raises an alert when breached.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"metric": "event_exposure", "above": 100000.0, "radius_km": 50.0}))]
+pub struct AlertThreshold {
+    pub metric: ThresholdMetric,
+    /// Breached when the metric exceeds this value
+    #[validate(custom(function = "crate::validation::validate_threshold_value"))]
+    #[schema(example = 100000.0, minimum = 0)]
+    pub above: f64,
+    /// Radius around the event in km, for `event_exposure` (default: 50, max: 500)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "crate::validation::validate_radius_field"))]
+    #[schema(example = 50.0, minimum = 0, maximum = 500)]
+    pub radius_km: Option<f64>,
+}
+
+impl AlertThreshold {
+    /// Radius an `event_exposure` threshold sums population within.
+    pub fn event_radius_km(&self) -> f64 {
+        self.radius_km.unwrap_or(50.0)
+    }
+}
+
+fn default_snapshot_limit() -> i64 {
+    30
+}
+
+/// How many of a monitor's snapshots to return, most recent first.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct SnapshotQuery {
+    /// Maximum number of snapshots (default: 30, max: 100)
+    #[serde(default = "default_snapshot_limit")]
+    #[validate(custom(function = "crate::validation::validate_event_limit"))]
+    #[schema(example = 30, minimum = 1, maximum = 100, default = 30)]
+    pub limit: i64,
+}
+
+/// GDACS alert level of a disaster event, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertLevel {
+    Green,
+    Orange,
+    Red,
+}
+
+impl AlertLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Green => "green",
+            Self::Orange => "orange",
+            Self::Red => "red",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "green" => Some(Self::Green),
+            "orange" => Some(Self::Orange),
+            "red" => Some(Self::Red),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for AlertLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| format!("unknown alert level `{s}`"))
+    }
+}
+
+fn default_event_limit() -> i64 {
+    20
+}
+
+/// Filter for the disaster events list, used by /events.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"event_type": "EQ", "min_alert": "orange", "limit": 20}))]
+pub struct EventsQuery {
+    /// GDACS event type: `EQ` (earthquake), `TC` (tropical cyclone), `FL` (flood),
+    /// `VO` (volcano), `DR` (drought) or `WF` (wildfire)
+    #[validate(custom(function = "crate::validation::validate_event_type"))]
+    #[schema(example = "EQ")]
+    pub event_type: Option<String>,
+
+    /// Only events at this alert level or above
+    pub min_alert: Option<AlertLevel>,
+
+    /// Maximum number of events to return, most recent first (default: 20, max: 100)
+    #[serde(default = "default_event_limit")]
+    #[validate(custom(function = "crate::validation::validate_event_limit"))]
+    #[schema(example = 20, minimum = 1, maximum = 100, default = 20)]
+    pub limit: i64,
+}
+
+/// Background job submission for an analysis too slow to wait on over HTTP.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[schema(example = json!({"kind": "exposure", "params": {"lat": 6.9271, "lon": 79.8612, "radius": 400.0}, "include": "uncertainty,timings"}))]
+pub struct JobSubmission {
+    /// Analysis to run
+    pub kind: JobKind,
+    /// Parameters, exactly as the synchronous endpoint accepts them in its query string
+    #[schema(value_type = Object)]
+    pub params: serde_json::Value,
+    /// Optional response sections, as the endpoint's `include=` list
+    #[schema(example = "uncertainty,timings")]
+    pub include: Option<String>,
+    /// http(s) URL to POST the finished job to, signed with the server's webhook secret
+    #[validate(custom(function = "crate::validation::validate_callback_url"))]
+    #[schema(example = "https://hooks.example.com/geopop")]
+    pub callback_url: Option<String>,
+}
+
+/// Analyses that can run as background jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// `GET /exposure`
+    Exposure,
+    /// `GET /analyse`
+    Analyse,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Exposure => "exposure",
+            Self::Analyse => "analyse",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "exposure" => Some(Self::Exposure),
+            "analyse" => Some(Self::Analyse),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,2771 @@
+#[cfg(feature = "graphql")]
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{AlertLevel, AlertThreshold, DecayModel, FacilityType, JobKind, QuadrantRadii, TravelMode};
+
+/// Health check status.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "status": "ok",
+    "pool": {"max_size": 16, "size": 4, "available": 3, "waiting": 0},
+    "database": {"reachable": true, "latency_ms": 0.8, "postgis_version": "3.4.2"}
+}))]
+pub struct HealthPayload {
+    /// `ok`, or `degraded` when the database cannot be reached
+    #[schema(example = "ok")]
+    pub status: String,
+    /// Database connection pool usage (only with `detail=true`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolStatus>,
+    /// Read-replica pool usage (only with `detail=true` and `DATABASE_READ_URL` set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_pool: Option<PoolStatus>,
+    /// Database reachability and round-trip latency (only with `detail=true`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<DatabaseStatus>,
+}
+
+/// Connection pool usage at the time of the request.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolStatus {
+    /// Most connections the pool will open
+    #[schema(example = 16)]
+    pub max_size: usize,
+    /// Connections currently open
+    #[schema(example = 4)]
+    pub size: usize,
+    /// Open connections idle in the pool
+    #[schema(example = 3)]
+    pub available: usize,
+    /// Requests queued for a connection; a growing value means the pool is exhausted
+    #[schema(example = 0)]
+    pub waiting: usize,
+}
+
+/// Result of a round-trip to the database.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DatabaseStatus {
+    /// Whether a connection was obtained and answered within the check's time limit
+    #[schema(example = true)]
+    pub reachable: bool,
+    /// Time to obtain a connection and run a trivial query, in milliseconds (`null` when unreachable)
+    #[schema(example = 0.8)]
+    pub latency_ms: Option<f64>,
+    /// PostGIS library version (`null` when unreachable)
+    #[schema(example = "3.4.2")]
+    pub postgis_version: Option<String>,
+}
+
+/// Population data for a single coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612, "population": 28534.0, "resolution_km": 1.0}))]
+pub struct PointPayload {
+    /// Queried latitude
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Queried longitude
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Estimated population within the grid cell
+    #[schema(example = 28534.0)]
+    pub population: f32,
+    /// Grid cell resolution in kilometres (always 1.0 for WorldPop data)
+    #[schema(example = 1.0)]
+    pub resolution_km: f32,
+    /// Population grid year the figure comes from
+    #[schema(example = 2025)]
+    pub year: i32,
+    /// WorldPop 95% interval for the cell (only with `include=uncertainty`, where available)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<PopulationInterval>,
+    /// GHS-SMOD settlement class of the cell (omitted for unclassified cells and
+    /// until the settlement layer is loaded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settlement_class: Option<SettlementClass>,
+    /// GHSL built-up surface of the cell in m² (omitted until the built-up layer is loaded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 312450.0)]
+    pub built_up_m2: Option<f64>,
+    /// VIIRS nighttime-lights radiance of the cell in nW/cm²/sr; 0 for an unlit cell
+    /// (omitted until the nighttime-lights layer is loaded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 48.7)]
+    pub night_light_radiance: Option<f64>,
+    /// Dominant ESA WorldCover class of the cell (omitted for unpopulated cells and
+    /// until the land-cover layer is loaded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub land_cover: Option<LandCoverClass>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Density of the grid cell at a coordinate, ranked against populated cells.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "lat": 6.9271, "lon": 79.8612, "population": 28534.0, "cell_area_km2": 0.854,
+    "density_per_km2": 33401.6, "iso_a3": "LKA", "country_percentile": 99, "world_percentile": 99
+}))]
+pub struct DensityRankPayload {
+    /// Queried latitude
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Queried longitude
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Estimated population within the grid cell
+    #[schema(example = 28534.0)]
+    pub population: f32,
+    /// Area of the grid cell in km² (shrinks with latitude)
+    #[schema(example = 0.854)]
+    pub cell_area_km2: f64,
+    /// People per km² in the grid cell
+    #[schema(example = 33401.6)]
+    pub density_per_km2: f64,
+    /// ISO 3166-1 alpha-3 code of the country containing the coordinate (`null` at sea)
+    #[schema(example = "LKA")]
+    pub iso_a3: Option<String>,
+    /// Percentile (0–100) of the density among the country's populated cells
+    /// (`null` at sea or when the country has no populated cells)
+    #[schema(example = 99)]
+    pub country_percentile: Option<i32>,
+    /// Percentile (0–100) of the density among all populated cells worldwide
+    #[schema(example = 99)]
+    pub world_percentile: i32,
+}
+
+/// Everything known about one grid cell, for debugging grid alignment.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CellPayload {
+    /// Cell identifier (`row × 43200 + col`)
+    #[schema(example = 430648783)]
+    pub cell_id: i64,
+    /// Grid row, counted southwards from 90°N
+    #[schema(example = 9968)]
+    pub row: i64,
+    /// Grid column, counted eastwards from 180°W
+    #[schema(example = 31183)]
+    pub col: i64,
+    /// Centre latitude of the cell
+    #[schema(example = 6.929167)]
+    pub lat: f64,
+    /// Centre longitude of the cell
+    #[schema(example = 79.8625)]
+    pub lon: f64,
+    /// Geographic bounds of the cell
+    pub bounds: CellBounds,
+    /// Area of the cell in km²
+    #[schema(example = 0.854)]
+    pub area_km2: f64,
+    /// Estimated population within the cell (0 if not in the grid)
+    #[schema(example = 28534.0)]
+    pub population: f32,
+    /// Country containing the cell centre (`null` at sea)
+    pub country: Option<CountryPayload>,
+    /// Nearest named place to the cell centre
+    pub nearest_place: Option<NearestPlace>,
+}
+
+/// Sections shared by every endpoint that accepts `include=`, flattened into its payload.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Default)]
+pub struct IncludedMeta {
+    /// Datasets the response was computed from (only with `include=sources`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<DataSource>>,
+    /// Server-side processing time breakdown (only with `include=timings`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timings>,
+}
+
+/// A dataset a response was derived from.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"name": "WorldPop", "version": "R2024B 2025 unconstrained UN-adjusted, 1 km", "url": "https://www.worldpop.org/"}))]
+pub struct DataSource {
+    /// Dataset name
+    #[schema(example = "WorldPop")]
+    pub name: String,
+    /// Release / variant of the dataset in use
+    #[schema(example = "R2024B 2025 unconstrained UN-adjusted, 1 km")]
+    pub version: String,
+    /// Dataset homepage
+    #[schema(example = "https://www.worldpop.org/")]
+    pub url: String,
+}
+
+/// Server-side timing breakdown.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"total_ms": 41.87, "stages": [{"stage": "population", "ms": 38.2}, {"stage": "places", "ms": 3.61}]}))]
+pub struct Timings {
+    /// Wall-clock time from request parsing to response assembly
+    #[schema(example = 41.87)]
+    pub total_ms: f64,
+    /// Elapsed time per processing stage, in execution order
+    pub stages: Vec<StageTiming>,
+}
+
+/// Elapsed time of one processing stage.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StageTiming {
+    /// Stage name
+    #[schema(example = "population")]
+    pub stage: String,
+    /// Elapsed milliseconds
+    #[schema(example = 38.2)]
+    pub ms: f64,
+}
+
+/// 95% credible interval for a WorldPop population estimate.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Copy)]
+#[schema(example = json!({"lower": 21870.4, "upper": 35112.9}))]
+pub struct PopulationInterval {
+    /// Lower bound of the 95% interval
+    #[schema(example = 21870.4)]
+    pub lower: f64,
+    /// Upper bound of the 95% interval
+    #[schema(example = 35112.9)]
+    pub upper: f64,
+}
+
+/// Aggregate population interval over an area.
+///
+/// Bounds are summed per cell, so the range is conservative (wider than a true
+/// joint interval). Cells without uncertainty data contribute their point estimate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[schema(example = json!({"lower": 1102345.0, "upper": 1698223.5, "covered_cells": 298, "total_cells": 314}))]
+pub struct AggregateInterval {
+    /// Sum of per-cell lower bounds
+    #[schema(example = 1102345.0)]
+    pub lower: f64,
+    /// Sum of per-cell upper bounds
+    #[schema(example = 1698223.5)]
+    pub upper: f64,
+    /// Number of populated cells that have uncertainty data
+    #[schema(example = 298)]
+    pub covered_cells: i64,
+    /// Number of populated cells in the area
+    #[schema(example = 314)]
+    pub total_cells: i64,
+}
+
+/// Batch population results for multiple coordinates.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchPayload {
+    /// Array of population results for each queried point
+    pub results: Vec<PointPayload>,
+}
+
+/// Bounding box of a single population grid cell.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"min_lat": 20.4583, "max_lat": 20.4667, "min_lon": 93.9500, "max_lon": 93.9583}))]
+pub struct CellBounds {
+    /// Southern edge latitude
+    #[schema(example = 20.4583)]
+    pub min_lat: f64,
+    /// Northern edge latitude
+    #[schema(example = 20.4667)]
+    pub max_lat: f64,
+    /// Western edge longitude
+    #[schema(example = 93.9500)]
+    pub min_lon: f64,
+    /// Eastern edge longitude
+    #[schema(example = 93.9583)]
+    pub max_lon: f64,
+}
+
+/// A single 1 km² population grid cell with its bounds for map rendering.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GridCell {
+    /// Centre latitude of the grid cell
+    #[schema(example = 20.4625)]
+    pub lat: f64,
+    /// Centre longitude of the grid cell
+    #[schema(example = 93.9542)]
+    pub lon: f64,
+    /// Estimated population within this cell
+    #[schema(example = 5.16)]
+    pub population: f32,
+    /// Geographic bounds of the cell (for rendering as a rectangle on a map)
+    pub bounds: CellBounds,
+    /// WorldPop 95% interval for the cell (only with `include=uncertainty`, where available)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<PopulationInterval>,
+}
+
+/// Population grid data within a radius, suitable for map visualisation.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PopulationGridPayload {
+    /// Centre coordinate of the query
+    pub coordinate: CoordinateInfo,
+    /// Search radius in kilometres
+    #[schema(example = 5.0)]
+    pub radius_km: f64,
+    /// Total population across all cells within the radius
+    #[schema(example = 1653.2)]
+    pub total_population: f64,
+    /// Number of non-empty grid cells returned
+    #[schema(example = 42)]
+    pub cell_count: usize,
+    /// Population grid year the cells come from
+    #[schema(example = 2025)]
+    pub year: i32,
+    /// Aggregate 95% interval across all cells (only with `include=uncertainty`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<AggregateInterval>,
+    /// Individual grid cells with population > 0
+    pub cells: Vec<GridCell>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Change in a single grid cell between two grid years.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CellChange {
+    /// Centre latitude of the grid cell
+    #[schema(example = 6.9292)]
+    pub lat: f64,
+    /// Centre longitude of the grid cell
+    #[schema(example = 79.8625)]
+    pub lon: f64,
+    /// Population in `from_year`
+    #[schema(example = 1100.0)]
+    pub from_population: f32,
+    /// Population in `to_year`
+    #[schema(example = 2200.0)]
+    pub to_population: f32,
+    /// `to_population` minus `from_population`
+    #[schema(example = 1100.0)]
+    pub change: f32,
+    /// Change as a percentage of `from_population` (`null` when the cell was empty)
+    #[schema(example = 100.0)]
+    pub percent_change: Option<f64>,
+    /// Geographic bounds of the cell (for rendering as a rectangle on a map)
+    pub bounds: CellBounds,
+}
+
+/// Population change at a coordinate, or within a radius, between two grid years.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PopulationChangePayload {
+    /// Centre coordinate of the query
+    pub coordinate: CoordinateInfo,
+    /// Search radius in kilometres (absent for a single-cell comparison)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 5.0)]
+    pub radius_km: Option<f64>,
+    /// Grid year compared from
+    #[schema(example = 2020)]
+    pub from_year: i32,
+    /// Grid year compared to
+    #[schema(example = 2025)]
+    pub to_year: i32,
+    /// Population in `from_year`
+    #[schema(example = 27500.0)]
+    pub from_population: f64,
+    /// Population in `to_year`
+    #[schema(example = 55000.0)]
+    pub to_population: f64,
+    /// `to_population` minus `from_population`
+    #[schema(example = 27500.0)]
+    pub change: f64,
+    /// Change as a percentage of `from_population` (`null` when it is zero)
+    #[schema(example = 100.0)]
+    pub percent_change: Option<f64>,
+    /// Per-cell changes, largest absolute change first (radius mode only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cells: Option<Vec<CellChange>>,
+}
+
+/// Populated grid cells within a viewport, suitable for map rendering.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PopulationBboxPayload {
+    /// Requested viewport `[min_lon, min_lat, max_lon, max_lat]`
+    #[schema(example = json!([79.80, 6.85, 79.95, 7.00]))]
+    pub bbox: [f64; 4],
+    /// Grid cells merged per block side: 1 for native 30 arc-second cells, more
+    /// when the viewport held over `max_cells` populated cells
+    #[schema(example = 1)]
+    pub aggregation: i32,
+    /// Side length of each returned cell in degrees
+    #[schema(example = 0.008333)]
+    pub cell_size_deg: f64,
+    /// Total population across the returned cells
+    #[schema(example = 1653.2)]
+    pub total_population: f64,
+    /// Number of non-empty cells (or blocks) returned
+    #[schema(example = 42)]
+    pub cell_count: usize,
+    /// Cells (or blocks) with population > 0, most populous first
+    pub cells: Vec<GridCell>,
+}
+
+/// Population summed into one H3 hexagon.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HexCell {
+    /// H3 cell index, as a hex string
+    #[schema(example = "87611cb11ffffff")]
+    pub h3: String,
+    /// Centre latitude of the hexagon
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Centre longitude of the hexagon
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Population of the grid cells whose centre lies in the hexagon
+    #[schema(example = 9612.4)]
+    pub population: f64,
+    /// Number of populated grid cells summed
+    #[schema(example = 5)]
+    pub cell_count: i64,
+    /// Hexagon vertices as `[lon, lat]` pairs (for rendering as a polygon on a map)
+    #[schema(example = json!([[79.86, 6.92], [79.87, 6.93]]))]
+    pub boundary: Vec<[f64; 2]>,
+}
+
+/// Grid cells of a radius or viewport query summed into H3 hexagons.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HexGridPayload {
+    /// Centre coordinate of a radius query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate: Option<CoordinateInfo>,
+    /// Search radius of a radius query, km
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 5.0)]
+    pub radius_km: Option<f64>,
+    /// Viewport of a bbox query `[min_lon, min_lat, max_lon, max_lat]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!([79.80, 6.85, 79.95, 7.00]))]
+    pub bbox: Option<[f64; 4]>,
+    /// H3 resolution of the hexagons
+    #[schema(example = 7)]
+    pub resolution: u8,
+    /// Total population across all hexagons
+    #[schema(example = 1653.2)]
+    pub total_population: f64,
+    /// Number of hexagons returned
+    #[schema(example = 12)]
+    pub cell_count: usize,
+    /// Population grid year the cells come from (radius queries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 2025)]
+    pub year: Option<i32>,
+    /// Hexagons with population > 0, most populous first
+    pub cells: Vec<HexCell>,
+}
+
+/// Population of one H3 hexagon.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct H3CellPayload {
+    /// H3 cell index, as a hex string
+    #[schema(example = "87611cb11ffffff")]
+    pub h3: String,
+    /// H3 resolution of the hexagon
+    #[schema(example = 7)]
+    pub resolution: u8,
+    /// Centre latitude of the hexagon
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Centre longitude of the hexagon
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Population of the grid cells whose centre lies in the hexagon
+    #[schema(example = 9612.4)]
+    pub population: f64,
+    /// Number of populated grid cells summed
+    #[schema(example = 5)]
+    pub cell_count: i64,
+    /// Area of the hexagon, km²
+    #[schema(example = 5.499)]
+    pub area_km2: f64,
+    /// Hexagon vertices as `[lon, lat]` pairs
+    #[schema(example = json!([[79.86, 6.92], [79.87, 6.93]]))]
+    pub boundary: Vec<[f64; 2]>,
+    /// Population grid year the cells come from
+    #[schema(example = 2025)]
+    pub year: i32,
+}
+
+/// Population summed into one Google S2 cell.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct S2Cell {
+    /// S2 cell token (the id in hex, trailing zeros dropped)
+    #[schema(example = "3ae2591")]
+    pub token: String,
+    /// S2 cell id as a signed 64-bit integer, as BigQuery's `S2_CELLIDFROMPOINT` returns it (a string, since JSON numbers lose precision past 2^53)
+    #[schema(example = "4243051724190777344")]
+    pub id: String,
+    /// Centre latitude of the cell
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Centre longitude of the cell
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Population of the grid cells whose centre lies in the S2 cell
+    #[schema(example = 9612.4)]
+    pub population: f64,
+    /// Number of populated grid cells summed
+    #[schema(example = 5)]
+    pub cell_count: i64,
+    /// Cell vertices as `[lon, lat]` pairs (for rendering as a polygon on a map)
+    #[schema(example = json!([[79.86, 6.92], [79.87, 6.93]]))]
+    pub boundary: Vec<[f64; 2]>,
+}
+
+/// Grid cells of a radius or viewport query summed into S2 cells.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct S2GridPayload {
+    /// Centre coordinate of a radius query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate: Option<CoordinateInfo>,
+    /// Search radius of a radius query, km
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 5.0)]
+    pub radius_km: Option<f64>,
+    /// Viewport of a bbox query `[min_lon, min_lat, max_lon, max_lat]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!([79.80, 6.85, 79.95, 7.00]))]
+    pub bbox: Option<[f64; 4]>,
+    /// S2 level of the cells
+    #[schema(example = 12)]
+    pub level: u8,
+    /// Total population across all S2 cells
+    #[schema(example = 1653.2)]
+    pub total_population: f64,
+    /// Number of S2 cells returned
+    #[schema(example = 12)]
+    pub cell_count: usize,
+    /// Population grid year the cells come from (radius queries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 2025)]
+    pub year: Option<i32>,
+    /// S2 cells with population > 0, most populous first
+    pub cells: Vec<S2Cell>,
+}
+
+/// Population of one Google S2 cell.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct S2CellPayload {
+    /// S2 cell token
+    #[schema(example = "3ae2591")]
+    pub token: String,
+    /// S2 cell id as a signed 64-bit integer, in a string
+    #[schema(example = "4243051724190777344")]
+    pub id: String,
+    /// S2 level of the cell
+    #[schema(example = 12)]
+    pub level: u8,
+    /// Centre latitude of the cell
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Centre longitude of the cell
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Population of the grid cells whose centre lies in the S2 cell
+    #[schema(example = 9612.4)]
+    pub population: f64,
+    /// Number of populated grid cells summed
+    #[schema(example = 5)]
+    pub cell_count: i64,
+    /// Area of the cell, km²
+    #[schema(example = 5.824)]
+    pub area_km2: f64,
+    /// Cell vertices as `[lon, lat]` pairs
+    #[schema(example = json!([[79.86, 6.92], [79.87, 6.93]]))]
+    pub boundary: Vec<[f64; 2]>,
+    /// Population grid year the cells come from
+    #[schema(example = 2025)]
+    pub year: i32,
+}
+
+/// Reverse geocoding result — nearest named place to the queried coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "Place"))]
+#[schema(example = json!({
+    "place_id": 1234,
+    "lat": "6.9271",
+    "lon": "79.8612",
+    "name": "Colombo",
+    "display_name": "Colombo, Western Province, Sri Lanka",
+    "address": {"city": "Colombo", "state": "Western Province", "country": "Sri Lanka"},
+    "population": 648034, "feature_class": "P", "feature_code": "PPLC"
+}))]
+pub struct ReversePayload {
+    /// GeoNames place identifier
+    #[schema(example = 1234)]
+    pub place_id: i32,
+    /// Latitude of the matched place
+    #[schema(example = "6.9271")]
+    pub lat: String,
+    /// Longitude of the matched place
+    #[schema(example = "79.8612")]
+    pub lon: String,
+    /// Place name
+    #[schema(example = "Colombo")]
+    pub name: String,
+    /// Full display name including administrative hierarchy
+    #[schema(example = "Colombo, Western Province, Sri Lanka")]
+    pub display_name: String,
+    /// Structured address components (city, state, country, etc.)
+    pub address: HashMap<String, String>,
+    /// GeoNames population estimate (0 if unknown)
+    #[serde(default)]
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// GeoNames feature class (`P` = populated place)
+    #[serde(default)]
+    #[schema(example = "P")]
+    pub feature_class: Option<String>,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[serde(default)]
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+}
+
+/// `/api/v2/reverse`: [`ReversePayload`] with numeric coordinates, like every
+/// other payload.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReversePayloadV2 {
+    /// GeoNames place identifier
+    #[schema(example = 1234)]
+    pub place_id: i32,
+    /// Latitude of the matched place
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Longitude of the matched place
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// Place name
+    #[schema(example = "Colombo")]
+    pub name: String,
+    /// Full display name including administrative hierarchy
+    #[schema(example = "Colombo, Western Province, Sri Lanka")]
+    pub display_name: String,
+    /// Structured address components (city, state, country, etc.)
+    pub address: HashMap<String, String>,
+    /// GeoNames population estimate (0 if unknown)
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// GeoNames feature class (`P` = populated place)
+    #[schema(example = "P")]
+    pub feature_class: Option<String>,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+}
+
+impl From<ReversePayload> for ReversePayloadV2 {
+    fn from(p: ReversePayload) -> Self {
+        Self {
+            place_id: p.place_id,
+            // The v1 strings are formatted from the stored f64, so they parse back exactly.
+            lat: p.lat.parse().unwrap_or_default(),
+            lon: p.lon.parse().unwrap_or_default(),
+            name: p.name,
+            display_name: p.display_name,
+            address: p.address,
+            population: p.population,
+            feature_class: p.feature_class,
+            feature_code: p.feature_code,
+        }
+    }
+}
+
+/// A named place within the exposure search radius.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+pub struct ExposedPlace {
+    /// GeoNames place identifier
+    #[schema(example = 1234)]
+    pub place_id: i32,
+    /// Latitude of the place
+    #[schema(example = "6.9271")]
+    pub lat: String,
+    /// Longitude of the place
+    #[schema(example = "79.8612")]
+    pub lon: String,
+    /// Place name
+    #[schema(example = "Colombo")]
+    pub name: String,
+    /// Full display name
+    #[schema(example = "Colombo, Western Province, Sri Lanka")]
+    pub display_name: String,
+    /// Structured address components (city, district, state, country, country_code)
+    pub address: HashMap<String, String>,
+    /// Distance from the epicentre in kilometres
+    #[schema(example = 3.2)]
+    pub distance_km: f64,
+    /// Compass direction from the epicentre (N, NE, E, SE, S, SW, W, NW)
+    #[schema(example = "SW")]
+    pub direction: String,
+    /// Bearing from the epicentre in degrees (0 = North, 90 = East, 180 = South, 270 = West)
+    #[schema(example = 225.3)]
+    pub bearing_deg: f64,
+    /// GeoNames population estimate (0 if unknown)
+    #[serde(default)]
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// GeoNames feature class (`P` = populated place)
+    #[serde(default)]
+    #[schema(example = "P")]
+    pub feature_class: Option<String>,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[serde(default)]
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+}
+
+/// Coordinate pair used in exposure results.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"lat": 6.9271, "lon": 79.8612}))]
+pub struct CoordinateInfo {
+    /// Latitude
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Longitude
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+}
+
+/// A projected input coordinate and the WGS84 point it was converted to.
+/// Added to the payload of any request that sends `x`/`y` with `crs`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"crs": "EPSG:3857", "x": 8890108.118, "y": 773006.716, "lat": 6.9271, "lon": 79.8612}))]
+pub struct InputCoordinate {
+    /// Coordinate reference system of `x`/`y`
+    #[schema(example = "EPSG:3857")]
+    pub crs: String,
+    /// Easting (longitude for EPSG:4326) as sent
+    #[schema(example = 8890108.118)]
+    pub x: f64,
+    /// Northing (latitude for EPSG:4326) as sent
+    #[schema(example = 773006.716)]
+    pub y: f64,
+    /// WGS84 latitude used for the lookup
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// WGS84 longitude used for the lookup
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+}
+
+/// Comprehensive population exposure analysis for a circular area.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "Exposure"))]
+pub struct ExposurePayload {
+    /// Centre coordinate of the analysis area
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub coordinate: CoordinateInfo,
+    /// Search radius in kilometres
+    #[schema(example = 10.0)]
+    pub radius_km: f64,
+    /// Total estimated population within the radius
+    #[schema(example = 456789.0)]
+    pub total_population: f64,
+    /// Population grid year the figures come from
+    #[schema(example = 2025)]
+    pub year: i32,
+    /// Decay model used for `weighted_population` (only when `decay` was given)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub decay: Option<DecayModel>,
+    /// Population weighted by distance from the centre under `decay` (only when `decay` was given)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 98321.4)]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub weighted_population: Option<f64>,
+    /// Low-lying coastal share of `total_population` (only with `mode=coastal`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub coastal: Option<CoastalExposure>,
+    /// Population per concentric distance band (only when `rings` was given)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub rings: Option<Vec<DistanceRing>>,
+    /// Grid cells and population per density bucket, sparsest first (only with `histogram=true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub histogram: Option<Vec<DensityBucket>>,
+    /// Most populated grid cells in the radius, most populous first (only with `top_cells`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub top_cells: Option<Vec<GridCell>>,
+    /// Per-country split, largest population first (only when the radius spans
+    /// more than one country)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub countries: Option<Vec<CountryExposure>>,
+    /// Area of the search circle in km², only its land with `land_only=true`
+    #[schema(example = 314.16)]
+    pub area_km2: f64,
+    /// Whether `area_km2` and `density_per_km2` count land only
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schema(example = true)]
+    pub land_only: bool,
+    /// Average population density (people/km²) within the radius
+    #[schema(example = 1454.1)]
+    pub density_per_km2: f64,
+    /// Population in the 1km grid cell at the centre coordinate
+    #[schema(example = 28534.0)]
+    pub cell_population: f32,
+    /// Area of the centre grid cell in km²
+    #[schema(example = 0.77)]
+    pub cell_area_km2: f64,
+    /// Population density of the centre grid cell (people/km²)
+    #[schema(example = 37057.1)]
+    pub cell_density_per_km2: f64,
+    /// Number of named places within the search radius (use /exposure/places for details)
+    #[schema(example = 121)]
+    pub place_count: i64,
+    /// Aggregate 95% interval for `total_population` (only with `include=uncertainty`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<AggregateInterval>,
+    /// `total_population` split by GHS-SMOD settlement class (only with `include=settlement`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub settlement: Option<SettlementExposure>,
+    /// Built-up surface inside the radius (only with `include=built_up`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub built_up: Option<BuiltUpExposure>,
+    /// VIIRS nighttime lights inside the radius (only with `include=night_lights`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub night_lights: Option<NightLightsExposure>,
+    /// `total_population` by the dominant land-cover class of its cells, largest
+    /// first (only with `include=land_cover`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub land_cover: Option<Vec<LandCoverShare>>,
+    /// Named places within the radius, nearest first: `places_limit` of them (default 20)
+    /// after skipping `places_offset` (only with `include=places`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub places: Option<Vec<ExposedPlace>>,
+    /// Place count and summed place population per administrative region, largest
+    /// population first (only with `group_by`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub admin_groups: Option<Vec<AdminGroup>>,
+    /// Whether more places lie beyond this page of `places`; `place_count` is the total
+    /// (only with `include=places`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = true)]
+    pub places_truncated: Option<bool>,
+    /// GeoJSON FeatureCollection of the analysed area: the `search_area` outline and,
+    /// with `include=hull`, the `populated_hull` of counted cells
+    /// (only with `include=geometry` / `include_geometry=true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>, example = json!({
+        "type": "FeatureCollection",
+        "features": [{"type": "Feature", "properties": {"kind": "search_area"},
+                      "geometry": {"type": "Polygon", "coordinates": [[[79.9517, 6.9271], [79.8612, 7.0169], [79.7707, 6.9271], [79.8612, 6.8373], [79.9517, 6.9271]]]}}]
+    }))]
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub geometry: Option<serde_json::Value>,
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// The part of an exposure radius inside one country.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"iso_a2": "TR", "iso_a3": "TUR", "name": "Turkey", "population": 2814520.3, "share_of_total": 0.712, "place_count": 48}))]
+pub struct CountryExposure {
+    /// ISO 3166-1 alpha-2 code
+    #[schema(example = "TR")]
+    pub iso_a2: Option<String>,
+    /// ISO 3166-1 alpha-3 code
+    #[schema(example = "TUR")]
+    pub iso_a3: Option<String>,
+    /// Country name
+    #[schema(example = "Turkey")]
+    pub name: String,
+    /// Population of the cells in the radius whose centre lies in the country
+    #[schema(example = 2814520.3)]
+    pub population: f64,
+    /// Share of `total_population` (0–1); cells at sea belong to no country
+    #[schema(example = 0.712)]
+    pub share_of_total: f64,
+    /// Named places in the radius in the country, honouring `min_place_population`
+    #[schema(example = 48)]
+    pub place_count: i64,
+}
+
+/// Named places within an exposure radius that share an administrative region.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"country_code": "LK", "admin1_code": "36", "admin1": "Western", "place_count": 96, "place_population": 1873226}))]
+pub struct AdminGroup {
+    /// ISO 3166-1 alpha-2 country code
+    #[schema(example = "LK")]
+    pub country_code: Option<String>,
+    /// GeoNames first-order division code
+    #[schema(example = "36")]
+    pub admin1_code: Option<String>,
+    /// First-order division name (state / province / region)
+    #[schema(example = "Western")]
+    pub admin1: Option<String>,
+    /// GeoNames second-order division code (only with `group_by=admin2`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "1232783")]
+    pub admin2_code: Option<String>,
+    /// Second-order division name (only with `group_by=admin2`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Colombo District")]
+    pub admin2: Option<String>,
+    /// Named places in the region within the radius
+    #[schema(example = 96)]
+    pub place_count: i64,
+    /// Sum of the GeoNames population estimates of those places
+    #[schema(example = 1873226)]
+    pub place_population: i64,
+}
+
+/// Population in one distance band around the exposure centre.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"inner_km": 5.0, "outer_km": 10.0, "population": 512034.6, "cumulative_population": 1187220.3}))]
+pub struct DistanceRing {
+    /// Inner edge of the band, km (0 for the first ring)
+    #[schema(example = 5.0)]
+    pub inner_km: f64,
+    /// Outer edge of the band, km
+    #[schema(example = 10.0)]
+    pub outer_km: f64,
+    /// Population with cell centres in the band
+    #[schema(example = 512034.6)]
+    pub population: f64,
+    /// Population within `outer_km` — the "within N km" figure
+    #[schema(example = 1187220.3)]
+    pub cumulative_population: f64,
+}
+
+/// Grid cells within the exposure radius whose density falls in one range.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"min_density_per_km2": 1000.0, "max_density_per_km2": 10000.0, "cell_count": 212, "population": 689201.4, "share_of_population": 0.58}))]
+pub struct DensityBucket {
+    /// Lower bound of the range, people/km² (inclusive)
+    #[schema(example = 1000.0)]
+    pub min_density_per_km2: f64,
+    /// Upper bound of the range, people/km² (exclusive; `null` for the densest bucket)
+    #[schema(example = 10000.0)]
+    pub max_density_per_km2: Option<f64>,
+    /// Grid cells in the range
+    #[schema(example = 212)]
+    pub cell_count: i64,
+    /// Population of those cells
+    #[schema(example = 689201.4)]
+    pub population: f64,
+    /// Share of `total_population` living in those cells (0–1)
+    #[schema(example = 0.58)]
+    pub share_of_population: f64,
+}
+
+/// GHSL built-up surface of the cells inside an exposure radius, populated or not.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"surface_m2": 61432118.0, "cell_count": 311, "share_of_area": 0.196}))]
+pub struct BuiltUpExposure {
+    /// Total built-up surface, m²
+    #[schema(example = 61432118.0)]
+    pub surface_m2: f64,
+    /// Number of cells with any built-up surface
+    #[schema(example = 311)]
+    pub cell_count: i64,
+    /// `surface_m2` as a fraction of `area_km2` (0–1)
+    #[schema(example = 0.196)]
+    pub share_of_area: f64,
+}
+
+/// VIIRS nighttime lights of the cells inside an exposure radius.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"sum_radiance": 9021.4, "max_radiance": 112.3, "lit_cell_count": 298}))]
+pub struct NightLightsExposure {
+    /// Radiance summed over the lit cells ("sum of lights"), nW/cm²/sr
+    #[schema(example = 9021.4)]
+    pub sum_radiance: f64,
+    /// Brightest cell's radiance, nW/cm²/sr
+    #[schema(example = 112.3)]
+    pub max_radiance: f64,
+    /// Number of cells with any radiance
+    #[schema(example = 298)]
+    pub lit_cell_count: i64,
+}
+
+/// Population inside the radius that is both near the coast and low-lying —
+/// the reach of a tsunami or storm surge from the epicentre.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CoastalExposure {
+    /// Maximum distance to the coastline counted, km
+    #[schema(example = 10.0)]
+    pub coast_km: f64,
+    /// Maximum cell elevation counted, metres above sea level
+    #[schema(example = 10.0)]
+    pub max_elevation_m: f64,
+    /// Population of qualifying cells
+    #[schema(example = 84210.5)]
+    pub population: f64,
+    /// Number of populated cells that qualified
+    #[schema(example = 96)]
+    pub cell_count: i64,
+    /// `population` as a fraction of `total_population` (0–1)
+    #[schema(example = 0.184)]
+    pub share_of_total: f64,
+}
+
+/// Exposure around two locations side by side, with the distance between them.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ComparePayload {
+    /// Exposure around the `from` location
+    pub from: ExposurePayload,
+    /// Exposure around the `to` location
+    pub to: ExposurePayload,
+    /// Great-circle distance between the two locations in km
+    #[schema(example = 94.34)]
+    pub distance_km: f64,
+    /// Initial bearing from `from` to `to` in degrees (0–360)
+    #[schema(example = 64.6)]
+    pub bearing_deg: f64,
+    /// 8-point compass direction of `to` as seen from `from`
+    #[schema(example = "NE")]
+    pub direction: String,
+    /// `to.total_population` minus `from.total_population`
+    #[schema(example = -912873.4)]
+    pub population_difference: f64,
+}
+
+/// Distance and direction between two points.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DistancePayload {
+    /// Start point
+    pub from: CoordinateInfo,
+    /// End point
+    pub to: CoordinateInfo,
+    /// Great-circle (haversine) distance on a spherical Earth in km
+    #[schema(example = 94.336)]
+    pub distance_km: f64,
+    /// Ellipsoidal (WGS84, Vincenty) distance in km; null for nearly antipodal
+    /// points, where Vincenty's method does not converge
+    #[schema(example = 94.332)]
+    pub geodesic_km: Option<f64>,
+    /// Initial bearing from `from` to `to` in degrees (0–360)
+    #[schema(example = 64.6)]
+    pub bearing_deg: f64,
+    /// 8-point compass direction of `to` as seen from `from`
+    #[schema(example = "NE")]
+    pub direction: String,
+}
+
+/// One point sampled along a great-circle path.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"distance_km": 10.0, "lat": 7.016543, "lon": 79.871027, "iso3": "LKA", "population": 1843.2}))]
+pub struct RouteSample {
+    /// Distance from the start of the path in km
+    pub distance_km: f64,
+    /// Sample latitude
+    pub lat: f64,
+    /// Sample longitude
+    pub lon: f64,
+    /// ISO 3166-1 alpha-3 code of the containing country (null at sea)
+    pub iso3: Option<String>,
+    /// Estimated population of the 1 km² grid cell under the sample
+    pub population: f32,
+}
+
+/// Population and country sampled at regular intervals along a great circle.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteSamplePayload {
+    /// Start of the path
+    pub from: CoordinateInfo,
+    /// End of the path
+    pub to: CoordinateInfo,
+    /// Great-circle length of the path in km
+    #[schema(example = 700.54)]
+    pub distance_km: f64,
+    /// Spacing between samples in km; the last gap may be shorter
+    #[schema(example = 10.0)]
+    pub interval_km: f64,
+    /// Number of samples, including both ends
+    #[schema(example = 72)]
+    pub sample_count: usize,
+    /// Countries the samples fall in, in order of entry; one re-entered after
+    /// crossing another is listed again
+    #[schema(example = json!(["LKA", "IND"]))]
+    pub countries: Vec<String>,
+    /// Largest sampled cell population
+    #[schema(example = 31205.6)]
+    pub max_population: f32,
+    /// The samples, from `from` to `to`
+    pub samples: Vec<RouteSample>,
+}
+
+/// Paginated list of named places within an exposure radius.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExposurePlacesPayload {
+    /// Centre coordinate of the search
+    pub coordinate: CoordinateInfo,
+    /// Search radius in kilometres
+    #[schema(example = 10.0)]
+    pub radius_km: f64,
+    /// Total number of places within the radius
+    #[schema(example = 121)]
+    pub total_places: i64,
+    /// Current page number (1-indexed)
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Number of results per page
+    #[schema(example = 20)]
+    pub per_page: i64,
+    /// Places on this page, ordered by distance from centre
+    pub places: Vec<ExposedPlace>,
+}
+
+/// Country information from Natural Earth boundaries.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "Country"))]
+#[schema(example = json!({
+    "iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka",
+    "formal_name": "Democratic Socialist Republic of Sri Lanka",
+    "continent": "Asia", "region": "Asia", "subregion": "Southern Asia",
+    "boundary_vintage": 2025, "sovereign": true, "area_km2": 66177.6, "density_per_km2": 327.5
+}))]
+pub struct CountryPayload {
+    /// ISO 3166-1 alpha-2 code
+    #[schema(example = "LK")]
+    pub iso_a2: Option<String>,
+    /// ISO 3166-1 alpha-3 code
+    #[schema(example = "LKA")]
+    pub iso_a3: Option<String>,
+    /// Country common name
+    #[schema(example = "Sri Lanka")]
+    pub name: String,
+    /// Country formal/official name
+    #[schema(example = "Democratic Socialist Republic of Sri Lanka")]
+    pub formal_name: Option<String>,
+    /// Continent
+    #[schema(example = "Asia")]
+    pub continent: String,
+    /// World region
+    #[schema(example = "Asia")]
+    pub region: Option<String>,
+    /// World sub-region
+    #[schema(example = "Southern Asia")]
+    pub subregion: Option<String>,
+    /// Boundary dataset vintage the country record comes from
+    #[schema(example = 2025)]
+    pub boundary_vintage: i32,
+    /// False for dependent territories such as Greenland or Puerto Rico
+    #[schema(example = true)]
+    pub sovereign: bool,
+    /// Geodesic land area of the boundary polygon in km²
+    #[schema(example = 66177.6)]
+    pub area_km2: Option<f64>,
+    /// Natural Earth `pop_est` per km² of `area_km2`
+    #[schema(example = 327.5)]
+    pub density_per_km2: Option<f64>,
+}
+
+/// Detailed country information including population estimate and bounding box.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "CountryDetail"))]
+#[schema(example = json!({
+    "iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka",
+    "formal_name": "Democratic Socialist Republic of Sri Lanka",
+    "continent": "Asia", "region": "Asia", "subregion": "Southern Asia",
+    "boundary_vintage": 2025, "pop_est": 21670000, "bbox": [79.6952, 5.9169, 81.8813, 9.8354],
+    "capital": "Colombo", "currency_code": "LKR", "languages": ["si", "ta"],
+    "calling_code": "+94", "tld": ".lk", "area_km2": 66177.6, "density_per_km2": 327.5
+}))]
+pub struct CountryDetailPayload {
+    /// ISO 3166-1 alpha-2 code
+    #[schema(example = "LK")]
+    pub iso_a2: Option<String>,
+    /// ISO 3166-1 alpha-3 code
+    #[schema(example = "LKA")]
+    pub iso_a3: Option<String>,
+    /// Country common name
+    #[schema(example = "Sri Lanka")]
+    pub name: String,
+    /// Country formal/official name
+    #[schema(example = "Democratic Socialist Republic of Sri Lanka")]
+    pub formal_name: Option<String>,
+    /// Continent
+    #[schema(example = "Asia")]
+    pub continent: String,
+    /// World region
+    #[schema(example = "Asia")]
+    pub region: Option<String>,
+    /// World sub-region
+    #[schema(example = "Southern Asia")]
+    pub subregion: Option<String>,
+    /// Boundary dataset vintage the country record comes from
+    #[schema(example = 2025)]
+    pub boundary_vintage: i32,
+    /// Estimated population
+    #[schema(example = 21670000)]
+    pub pop_est: Option<i64>,
+    /// Bounding box [min_lon, min_lat, max_lon, max_lat]
+    #[schema(example = json!([79.6952, 5.9169, 81.8813, 9.8354]))]
+    pub bbox: [f64; 4],
+    /// Capital city (GeoNames)
+    #[schema(example = "Colombo")]
+    pub capital: Option<String>,
+    /// ISO 4217 currency code (GeoNames)
+    #[schema(example = "LKR")]
+    pub currency_code: Option<String>,
+    /// Spoken languages as ISO 639 / BCP 47 tags, most widely spoken first (GeoNames)
+    #[serde(default)]
+    #[schema(example = json!(["si", "ta"]))]
+    pub languages: Vec<String>,
+    /// International calling code (GeoNames)
+    #[schema(example = "+94")]
+    pub calling_code: Option<String>,
+    /// Country-code top-level domain (GeoNames)
+    #[schema(example = ".lk")]
+    pub tld: Option<String>,
+    /// Geodesic land area of the boundary polygon in km²
+    #[schema(example = 66177.6)]
+    pub area_km2: Option<f64>,
+    /// `pop_est` per km² of `area_km2`
+    #[schema(example = 327.5)]
+    pub density_per_km2: Option<f64>,
+}
+
+/// WorldPop grid total within a country polygon next to Natural Earth's estimate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "iso_a3": "LKA", "name": "Sri Lanka", "boundary_vintage": 2025,
+    "grid_population": 21893412.6, "populated_cells": 61234, "pop_est": 21803000,
+    "difference": 90412.6, "ratio": 1.004, "computed_at": "2026-01-12T03:14:07Z"
+}))]
+pub struct CountryPopulationPayload {
+    /// ISO 3166-1 alpha-3 code
+    #[schema(example = "LKA")]
+    pub iso_a3: String,
+    /// Country common name
+    #[schema(example = "Sri Lanka")]
+    pub name: String,
+    /// Boundary dataset vintage the polygon comes from
+    #[schema(example = 2025)]
+    pub boundary_vintage: i32,
+    /// Sum of WorldPop grid cells whose centre lies inside the country polygon
+    #[schema(example = 21893412.6)]
+    pub grid_population: f64,
+    /// Number of populated grid cells counted
+    #[schema(example = 61234)]
+    pub populated_cells: i64,
+    /// Natural Earth population estimate
+    #[schema(example = 21803000)]
+    pub pop_est: Option<i64>,
+    /// `grid_population` minus `pop_est` (`null` without an estimate)
+    #[schema(example = 90412.6)]
+    pub difference: Option<f64>,
+    /// `grid_population` divided by `pop_est` (`null` without an estimate)
+    #[schema(example = 1.004)]
+    pub ratio: Option<f64>,
+    /// When the rollup was last recomputed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// One territory's claim to a maritime zone.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EezClaimant {
+    /// Territory the zone belongs to
+    #[schema(example = "Sri Lanka")]
+    pub territory: String,
+    /// ISO 3166-1 alpha-3 code of the territory
+    #[schema(example = "LKA")]
+    pub territory_iso3: Option<String>,
+    /// Sovereign state of the territory
+    #[schema(example = "Sri Lanka")]
+    pub sovereign: String,
+    /// ISO 3166-1 alpha-3 code of the sovereign state
+    #[schema(example = "LKA")]
+    pub sovereign_iso3: Option<String>,
+}
+
+/// Exclusive economic zone from the Marine Regions World EEZ dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EezZone {
+    /// Marine Regions identifier
+    #[schema(example = 8383)]
+    pub mrgid: i32,
+    /// Zone name
+    #[schema(example = "Sri Lankan Exclusive Economic Zone")]
+    pub name: String,
+    /// `200NM`, `Overlapping claim` or `Joint regime`
+    #[schema(example = "200NM")]
+    pub kind: String,
+    /// Territories claiming the zone; more than one for overlapping claims and joint regimes
+    pub claimants: Vec<EezClaimant>,
+    /// Zone area in km²
+    #[schema(example = 532619.0)]
+    pub area_km2: Option<f64>,
+}
+
+/// Maritime jurisdiction of a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EezPayload {
+    pub coordinate: CoordinateInfo,
+    /// Whether the coordinate lies inside a country polygon
+    #[schema(example = false)]
+    pub is_land: bool,
+    /// Zone containing the coordinate; `null` on land and on the high seas
+    pub zone: Option<EezZone>,
+}
+
+/// A country entry with distance from a search coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NearbyCountryEntry {
+    #[serde(flatten)]
+    pub country: CountryPayload,
+    /// Distance from the search coordinate to the nearest country border in km (0 if inside)
+    #[schema(example = 0.0)]
+    pub distance_km: f64,
+}
+
+/// Countries found within a radius of a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NearbyCountriesPayload {
+    pub coordinate: CoordinateInfo,
+    #[schema(example = 50.0)]
+    pub radius_km: f64,
+    /// Whether the coordinate itself is on land (inside a country polygon)
+    #[schema(example = true)]
+    pub is_land: bool,
+    pub countries: Vec<NearbyCountryEntry>,
+}
+
+/// Land/sea check result for a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LandCheckPayload {
+    pub coordinate: CoordinateInfo,
+    /// true if the coordinate is on land, false if at sea
+    #[schema(example = true)]
+    pub is_land: bool,
+    /// The country containing the coordinate (null if at sea)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<CountryPayload>,
+}
+
+/// Paginated list of nearby cities/places within a radius.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NearbyCitiesPayload {
+    pub coordinate: CoordinateInfo,
+    #[schema(example = 10.0)]
+    pub radius_km: f64,
+    #[schema(example = 158)]
+    pub total_places: i64,
+    #[schema(example = 1)]
+    pub page: i64,
+    #[schema(example = 20)]
+    pub per_page: i64,
+    pub places: Vec<ExposedPlace>,
+}
+
+/// Nearest critical facilities of one type to a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FacilitiesPayload {
+    pub coordinate: CoordinateInfo,
+    /// Type of facility searched for
+    #[schema(example = "hospital")]
+    pub facility_type: FacilityType,
+    /// Facilities, nearest first; empty before facilities are ingested
+    pub facilities: Vec<Facility>,
+}
+
+/// A hospital, airport, port or school from GeoNames, with its distance and
+/// direction from the queried coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "facility_id": 9000001, "name": "General Hospital Colombo",
+    "feature_code": "HSP", "country_code": "LK", "lat": 6.91778, "lon": 79.86778,
+    "distance_km": 1.27, "direction": "SE", "bearing_deg": 145.0
+}))]
+pub struct Facility {
+    /// GeoNames feature identifier
+    #[schema(example = 9000001)]
+    pub facility_id: i32,
+    /// Facility name
+    #[schema(example = "General Hospital Colombo")]
+    pub name: String,
+    /// GeoNames feature code (HSP, AIRP, PRT or SCH)
+    #[schema(example = "HSP")]
+    pub feature_code: String,
+    /// ISO 3166-1 alpha-2 country code, if GeoNames has one
+    #[schema(example = "LK")]
+    pub country_code: Option<String>,
+    #[schema(example = 6.91778)]
+    pub lat: f64,
+    #[schema(example = 79.86778)]
+    pub lon: f64,
+    /// Distance from the queried coordinate in kilometres
+    #[schema(example = 1.27)]
+    pub distance_km: f64,
+    /// Compass direction from the queried coordinate (N, NE, E, SE, S, SW, W, NW)
+    #[schema(example = "SE")]
+    pub direction: String,
+    /// Bearing from the queried coordinate in degrees (0 = North, 90 = East)
+    #[schema(example = 145.0)]
+    pub bearing_deg: f64,
+}
+
+/// Facilities inside a radius, grouped by type.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FacilitiesWithinPayload {
+    pub coordinate: CoordinateInfo,
+    #[schema(example = 25.0)]
+    pub radius_km: f64,
+    /// Facilities of all requested types inside the radius
+    #[schema(example = 3)]
+    pub total_facilities: i64,
+    /// One group per requested type, in request order, including empty ones
+    pub groups: Vec<FacilityGroup>,
+}
+
+/// Facilities of one type inside a radius.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FacilityGroup {
+    #[schema(example = "hospital")]
+    pub facility_type: FacilityType,
+    /// Facilities of this type inside the radius, whether listed or not
+    #[schema(example = 1)]
+    pub count: i64,
+    /// Nearest first, at most `limit` of them
+    pub facilities: Vec<Facility>,
+}
+
+/// Nearest airports to a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AirportsPayload {
+    pub coordinate: CoordinateInfo,
+    /// Airports, nearest first; empty before OurAirports is ingested
+    pub airports: Vec<Airport>,
+}
+
+/// OurAirports size class of an airport. Heliports, seaplane bases and
+/// closed airports are not served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AirportType {
+    /// Major airport with scheduled international or high-volume service
+    LargeAirport,
+    /// Regional airport
+    MediumAirport,
+    /// Small airfield or airstrip
+    SmallAirport,
+}
+
+impl AirportType {
+    /// Class of an OurAirports `type` value; `None` for types not served.
+    pub fn from_ourairports(value: &str) -> Option<Self> {
+        match value {
+            "large_airport" => Some(Self::LargeAirport),
+            "medium_airport" => Some(Self::MediumAirport),
+            "small_airport" => Some(Self::SmallAirport),
+            _ => None,
+        }
+    }
+}
+
+/// An airport from OurAirports with its codes, open runways, and distance and
+/// direction from the queried coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "airport_id": 9100001, "ident": "VCBI", "name": "Bandaranaike International Airport",
+    "airport_type": "large_airport", "icao_code": "VCBI", "iata_code": "CMB",
+    "municipality": "Colombo", "country_code": "LK", "lat": 7.18076, "lon": 79.88412,
+    "elevation_m": 9, "scheduled_service": true,
+    "distance_km": 28.32, "direction": "N", "bearing_deg": 5.1, "longest_runway_m": 3350,
+    "runways": [{"designator": "04/22", "length_m": 3350, "width_m": 45, "surface": "ASP", "lighted": true}]
+}))]
+pub struct Airport {
+    /// OurAirports airport identifier
+    #[schema(example = 9100001)]
+    pub airport_id: i32,
+    /// OurAirports identifier code (the ICAO code where there is one)
+    #[schema(example = "VCBI")]
+    pub ident: String,
+    #[schema(example = "Bandaranaike International Airport")]
+    pub name: String,
+    pub airport_type: AirportType,
+    /// Four-letter ICAO location indicator
+    #[schema(example = "VCBI")]
+    pub icao_code: Option<String>,
+    /// Three-letter IATA code
+    #[schema(example = "CMB")]
+    pub iata_code: Option<String>,
+    /// Town or city the airport serves
+    #[schema(example = "Colombo")]
+    pub municipality: Option<String>,
+    /// ISO 3166-1 alpha-2 country code
+    #[schema(example = "LK")]
+    pub country_code: Option<String>,
+    #[schema(example = 7.18076)]
+    pub lat: f64,
+    #[schema(example = 79.88412)]
+    pub lon: f64,
+    /// Field elevation in metres
+    #[schema(example = 9)]
+    pub elevation_m: Option<i32>,
+    /// Whether the airport has scheduled airline service
+    #[schema(example = true)]
+    pub scheduled_service: bool,
+    /// Distance from the queried coordinate in kilometres
+    #[schema(example = 28.32)]
+    pub distance_km: f64,
+    /// Compass direction from the queried coordinate (N, NE, E, SE, S, SW, W, NW)
+    #[schema(example = "N")]
+    pub direction: String,
+    /// Bearing from the queried coordinate in degrees (0 = North, 90 = East)
+    #[schema(example = 5.1)]
+    pub bearing_deg: f64,
+    /// Length of the longest open runway in metres
+    #[schema(example = 3350)]
+    pub longest_runway_m: Option<i32>,
+    /// Open runways, longest first
+    pub runways: Vec<Runway>,
+}
+
+/// An open runway of an airport.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Runway {
+    /// Runway designator from both ends, e.g. `04/22`
+    #[schema(example = "04/22")]
+    pub designator: Option<String>,
+    /// Length in metres
+    #[schema(example = 3350)]
+    pub length_m: Option<i32>,
+    /// Width in metres
+    #[schema(example = 45)]
+    pub width_m: Option<i32>,
+    /// Surface code as published by OurAirports (`ASP`, `CON`, `GRS`, `TURF`, ...)
+    #[schema(example = "ASP")]
+    pub surface: Option<String>,
+    /// Whether the runway has lighting
+    #[schema(example = true)]
+    pub lighted: bool,
+}
+
+/// List of countries matching a continent, region and/or subregion filter.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CountryListPayload {
+    /// Queried continent name, if filtered by continent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "asia")]
+    pub continent: Option<String>,
+    /// Queried UN region, if filtered by region
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Asia")]
+    pub region: Option<String>,
+    /// Queried UN subregion, if filtered by subregion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Southern Asia")]
+    pub subregion: Option<String>,
+    /// Number of countries returned
+    #[schema(example = 49)]
+    pub count: usize,
+    /// Country list
+    pub countries: Vec<CountryPayload>,
+}
+
+/// `/api/v2/countries`: one page of the countries matching the filters.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CountryPageV2 {
+    /// Queried continent name, if filtered by continent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "asia")]
+    pub continent: Option<String>,
+    /// Queried UN region, if filtered by region
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Asia")]
+    pub region: Option<String>,
+    /// Queried UN subregion, if filtered by subregion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Southern Asia")]
+    pub subregion: Option<String>,
+    /// Countries matching the filters, across all pages
+    #[schema(example = 49)]
+    pub total: usize,
+    /// Current page number (1-indexed)
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Number of results per page
+    #[schema(example = 20)]
+    pub per_page: i64,
+    /// Countries on this page, by name
+    pub countries: Vec<CountryPayload>,
+}
+
+/// Aggregate figures for one Natural Earth continent.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContinentStats {
+    /// Natural Earth continent name
+    #[schema(example = "Asia")]
+    pub name: String,
+    /// Sovereign countries with ISO codes, as `/countries` lists them
+    #[schema(example = 48)]
+    pub country_count: i64,
+    /// Sum of Natural Earth `pop_est` over every polygon on the continent,
+    /// dependencies included
+    #[schema(example = 4550000000_i64)]
+    pub pop_est: i64,
+    /// Land area of those polygons in km²
+    #[schema(example = 31033131.4)]
+    pub area_km2: f64,
+    /// Bounding box as `[min_lon, min_lat, max_lon, max_lat]`; spans the whole
+    /// longitude range for continents crossing the antimeridian
+    #[schema(example = json!([-180.0, -12.56, 180.0, 81.86]))]
+    pub bbox: [f64; 4],
+}
+
+/// Every continent with its aggregate figures.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContinentListPayload {
+    /// Boundary dataset vintage the figures come from
+    #[schema(example = 2025)]
+    pub boundary_vintage: i32,
+    /// Number of continents returned
+    #[schema(example = 8)]
+    pub count: usize,
+    /// Continents in alphabetical order
+    pub continents: Vec<ContinentStats>,
+}
+
+/// Nearest named place to the epicentre with distance and direction.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[schema(example = json!({
+    "place_id": 1325189, "name": "Hetsaw",
+    "display_name": "Hetsaw, Kyaunkpyu District, Rakhine, Myanmar",
+    "address": {"city": "Hetsaw", "district": "Kyaunkpyu District", "state": "Rakhine", "country": "Myanmar", "country_code": "mm"},
+    "distance_km": 4.69, "direction": "SW", "bearing_deg": 233.3,
+    "population": 0, "feature_class": "P", "feature_code": "PPL"
+}))]
+pub struct NearestPlace {
+    /// GeoNames place identifier
+    #[schema(example = 1325189)]
+    pub place_id: i32,
+    /// Place name
+    #[schema(example = "Hetsaw")]
+    pub name: String,
+    /// Full display name including administrative hierarchy
+    #[schema(example = "Hetsaw, Kyaunkpyu District, Rakhine, Myanmar")]
+    pub display_name: String,
+    /// Structured address components (city, district, state, country, country_code)
+    pub address: HashMap<String, String>,
+    /// Distance from the epicentre in kilometres
+    #[schema(example = 4.69)]
+    pub distance_km: f64,
+    /// Compass direction from the epicentre (N, NE, E, SE, S, SW, W, NW)
+    #[schema(example = "SW")]
+    pub direction: String,
+    /// Bearing from the epicentre in degrees (0 = North, 90 = East)
+    #[schema(example = 233.3)]
+    pub bearing_deg: f64,
+    /// GeoNames population estimate (0 if unknown)
+    #[serde(default)]
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// GeoNames feature class (`P` = populated place)
+    #[serde(default)]
+    #[schema(example = "P")]
+    pub feature_class: Option<String>,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[serde(default)]
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+}
+
+/// Timezone at a coordinate and the local time there.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"timezone": "Asia/Colombo", "utc_offset": "+05:30", "local_time": "2026-03-14T09:12:45+05:30"}))]
+pub struct LocalTime {
+    /// IANA timezone name, from the nearest GeoNames place
+    #[schema(example = "Asia/Colombo")]
+    pub timezone: String,
+    /// Offset from UTC in effect at `local_time`, daylight saving included
+    #[schema(example = "+05:30")]
+    pub utc_offset: String,
+    /// Local time when the response was computed (RFC 3339)
+    #[schema(example = "2026-03-14T09:12:45+05:30")]
+    pub local_time: String,
+}
+
+/// How a hazard's affected radius follows from its magnitude:
+/// `radius_km = 10^(a + b · magnitude)`, clamped to the radius bounds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "earthquake", "a": -1.8, "b": 0.5, "min_magnitude": 3.0, "max_magnitude": 9.5,
+    "min_radius_km": 5.0, "max_radius_km": 500.0, "decay": "linear"
+}))]
+pub struct HazardTemplate {
+    /// Name callers pass as `hazard`
+    #[schema(example = "earthquake")]
+    pub name: String,
+    #[schema(example = -1.8)]
+    pub a: f64,
+    #[schema(example = 0.5)]
+    pub b: f64,
+    /// Magnitudes the template accepts
+    #[schema(example = 3.0)]
+    pub min_magnitude: f64,
+    #[schema(example = 9.5)]
+    pub max_magnitude: f64,
+    #[schema(example = 5.0)]
+    pub min_radius_km: f64,
+    #[schema(example = 500.0)]
+    pub max_radius_km: f64,
+    /// Distance decay for the scenario's `weighted_population`
+    #[serde(default)]
+    #[schema(example = "linear")]
+    pub decay: Option<DecayModel>,
+}
+
+impl HazardTemplate {
+    /// Affected radius of an event of `magnitude`, in km.
+    pub fn radius_km(&self, magnitude: f64) -> f64 {
+        10f64.powf(self.a + self.b * magnitude).clamp(self.min_radius_km, self.max_radius_km)
+    }
+}
+
+/// Hazard scenario an analysis ran, with the template it was derived from.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HazardScenario {
+    pub template: HazardTemplate,
+    #[schema(example = 6.8)]
+    pub magnitude: f64,
+    /// Radius derived from the magnitude; also `population.search_radius_km`
+    #[schema(example = 39.8)]
+    pub radius_km: f64,
+    /// Population weighted by the template's decay (only for templates with one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 412387.5)]
+    pub weighted_population: Option<f64>,
+}
+
+/// Coarse terrain class of a grid cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainClass {
+    /// Within 10 km of the coast and at most 100 m above sea level
+    Coastal,
+    /// Below 500 m
+    Lowland,
+    /// 500 m and above
+    Highland,
+}
+
+/// Elevation and terrain class of the epicentre cell.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"elevation_m": 4.0, "coast_km": 1.2, "class": "coastal"}))]
+pub struct EpicentreTerrain {
+    /// Surface elevation in metres
+    #[schema(example = 4.0)]
+    pub elevation_m: f64,
+    /// Distance to the coastline in km; only for cells within 100 km of the
+    /// coast and at most 100 m above sea level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 1.2)]
+    pub coast_km: Option<f64>,
+    pub class: TerrainClass,
+}
+
+/// Degree of urbanisation of a grid cell, from the GHSL Settlement Model
+/// (GHS-SMOD) level-2 grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementClass {
+    /// Dense, contiguous cells of at least 50 000 people (SMOD 30)
+    UrbanCentre,
+    /// Towns and suburbs of at least 5 000 people (SMOD 21–23)
+    UrbanCluster,
+    /// Villages, dispersed and mostly uninhabited areas (SMOD 11–13)
+    Rural,
+}
+
+impl SettlementClass {
+    /// Class of a GHS-SMOD level-2 code; `None` for water (10) and unknown codes.
+    pub fn from_smod(code: i16) -> Option<Self> {
+        match code {
+            30 => Some(Self::UrbanCentre),
+            21..=23 => Some(Self::UrbanCluster),
+            11..=13 => Some(Self::Rural),
+            _ => None,
+        }
+    }
+}
+
+/// ESA WorldCover land-cover class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LandCoverClass {
+    TreeCover,
+    Shrubland,
+    Grassland,
+    Cropland,
+    BuiltUp,
+    BareSparseVegetation,
+    SnowAndIce,
+    Water,
+    HerbaceousWetland,
+    Mangroves,
+    MossAndLichen,
+}
+
+impl LandCoverClass {
+    /// Class of a WorldCover map code; `None` for codes outside the legend.
+    pub fn from_worldcover(code: i16) -> Option<Self> {
+        Some(match code {
+            10 => Self::TreeCover,
+            20 => Self::Shrubland,
+            30 => Self::Grassland,
+            40 => Self::Cropland,
+            50 => Self::BuiltUp,
+            60 => Self::BareSparseVegetation,
+            70 => Self::SnowAndIce,
+            80 => Self::Water,
+            90 => Self::HerbaceousWetland,
+            95 => Self::Mangroves,
+            100 => Self::MossAndLichen,
+            _ => return None,
+        })
+    }
+}
+
+/// Population of the cells of one dominant land-cover class.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"class": "cropland", "population": 20500.0, "cell_count": 10, "share_of_population": 0.373}))]
+pub struct LandCoverShare {
+    pub class: LandCoverClass,
+    /// Population of those cells
+    #[schema(example = 20500.0)]
+    pub population: f64,
+    /// Number of populated cells of the class
+    #[schema(example = 10)]
+    pub cell_count: i64,
+    /// Share of `total_population` living in those cells (0–1)
+    #[schema(example = 0.373)]
+    pub share_of_population: f64,
+}
+
+/// Population of an area split by settlement class.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"urban_centre": 512004.3, "urban_cluster": 98120.5, "rural": 20311.9, "unclassified": 0.0}))]
+pub struct SettlementExposure {
+    /// Population of urban-centre cells
+    #[schema(example = 512004.3)]
+    pub urban_centre: f64,
+    /// Population of urban-cluster cells
+    #[schema(example = 98120.5)]
+    pub urban_cluster: f64,
+    /// Population of rural cells
+    #[schema(example = 20311.9)]
+    pub rural: f64,
+    /// Population of cells GHS-SMOD does not classify (water, or outside its coverage)
+    #[schema(example = 0.0)]
+    pub unclassified: f64,
+}
+
+/// Population summary found via auto-expanding radius search.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"search_radius_km": 5.0, "total_population": 426.0, "area_km2": 78.54, "density_per_km2": 5.4, "epicentre_population": 5.16}))]
+pub struct PopulationSummary {
+    /// Radius (km) at which population was found (indicates remoteness)
+    #[schema(example = 5.0)]
+    pub search_radius_km: f64,
+    /// Total population within the search radius
+    #[schema(example = 426.0)]
+    pub total_population: f64,
+    /// Area of the search circle in km²
+    #[schema(example = 78.54)]
+    pub area_km2: f64,
+    /// Average population density (people/km²) within the search radius
+    #[schema(example = 5.4)]
+    pub density_per_km2: f64,
+    /// Population at the exact epicentre grid cell (0 if ocean/desert)
+    #[schema(example = 5.16)]
+    pub epicentre_population: f32,
+    /// Aggregate 95% interval for `total_population` (only with `include=uncertainty`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<AggregateInterval>,
+    /// `total_population` split by GHS-SMOD settlement class (only with `include=settlement`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settlement: Option<SettlementExposure>,
+}
+
+/// Comprehensive disaster impact analysis for a coordinate.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnalysePayload {
+    /// Epicentre coordinate
+    pub coordinate: CoordinateInfo,
+    /// Whether the coordinate is on land (true) or at sea (false)
+    #[schema(example = true)]
+    pub is_land: bool,
+    /// Country the epicentre is in (or nearest to, if in ocean)
+    pub country: CountryPayload,
+    /// Nearest named place from GeoNames with distance and direction
+    pub nearest_place: NearestPlace,
+    /// The `nearest_places` nearest named places, nearest first (only with `nearest_places`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nearest_places: Option<Vec<NearestPlace>>,
+    /// Other countries within 200 km of the epicentre, nearest border first
+    pub nearby_countries: Vec<NearbyCountryEntry>,
+    /// Timezone and current local time at the epicentre; `null` when no place
+    /// with a known timezone is loaded
+    pub local_time: Option<LocalTime>,
+    /// Elevation and terrain class at the epicentre; `null` at sea, in
+    /// unpopulated cells and until the terrain layers are loaded
+    pub terrain: Option<EpicentreTerrain>,
+    /// GHS-SMOD settlement class of the epicentre cell; `null` at sea, in
+    /// unpopulated or unclassified cells and until the settlement layer is loaded
+    pub settlement_class: Option<SettlementClass>,
+    /// Hazard scenario that set the radius (only with `hazard`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hazard: Option<HazardScenario>,
+    /// Population summary from auto-expanding radius search
+    pub population: PopulationSummary,
+    /// Population grid year the summary comes from
+    #[schema(example = 2025)]
+    pub year: i32,
+    /// Nearest named places within the search radius, up to 20 (only with `include=places`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub places: Option<Vec<ExposedPlace>>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// A single city search hit returned by /cities/search.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(SimpleObject))]
+#[schema(example = json!({
+    "place_id": 1248991,
+    "name": "Colombo",
+    "display_name": "Colombo, Western Province, Sri Lanka",
+    "country_code": "LK",
+    "country": "Sri Lanka",
+    "admin1": "Western Province",
+    "admin2": "Colombo District",
+    "feature_code": "PPLC",
+    "lat": 6.9319,
+    "lon": 79.8478,
+    "population": 648034,
+    "score": 1.0,
+    "bbox": [79.8028, 6.8819, 79.8928, 6.9819]
+}))]
+pub struct CityHit {
+    /// GeoNames place identifier
+    #[schema(example = 1248991)]
+    pub place_id: i32,
+    /// Matched place name
+    #[schema(example = "Colombo")]
+    pub name: String,
+    /// Full display name including administrative hierarchy
+    #[schema(example = "Colombo, Western Province, Sri Lanka")]
+    pub display_name: String,
+    /// ISO 3166-1 alpha-2 country code
+    #[schema(example = "LK")]
+    pub country_code: Option<String>,
+    /// Country common name
+    #[schema(example = "Sri Lanka")]
+    pub country: Option<String>,
+    /// First-order administrative division (state / province / region)
+    #[schema(example = "Western Province")]
+    pub admin1: Option<String>,
+    /// Second-order administrative division (district / county)
+    #[schema(example = "Colombo District")]
+    pub admin2: Option<String>,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+    /// Latitude of the place centroid
+    #[schema(example = 6.9319)]
+    pub lat: f64,
+    /// Longitude of the place centroid
+    #[schema(example = 79.8478)]
+    pub lon: f64,
+    /// Population estimate from GeoNames (0 if unknown)
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// Match quality score in [0, 1]. 1.0 = exact, 0.9 = prefix, <0.9 = fuzzy similarity.
+    #[schema(example = 1.0)]
+    pub score: f64,
+    /// Synthesized bounding box [min_lon, min_lat, max_lon, max_lat] scaled from population.
+    /// Use this as a fallback when no true boundary polygon is available.
+    #[schema(example = json!([79.8028, 6.8819, 79.8928, 6.9819]))]
+    pub bbox: [f64; 4],
+}
+
+/// City search result set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CitySearchPayload {
+    /// Echoed search term
+    #[schema(example = "colom")]
+    pub query: String,
+    /// Echoed country filter (uppercase, if provided)
+    #[schema(example = "LK")]
+    pub country: Option<String>,
+    /// Number of results returned
+    #[schema(example = 3)]
+    pub count: usize,
+    /// Matching cities, ordered by score then population
+    pub results: Vec<CityHit>,
+}
+
+/// Root endpoint payload: health, docs link, and database stats.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RootPayload {
+    #[schema(example = "tg-geo-pop")]
+    pub service: String,
+    #[schema(example = "ok")]
+    pub status: String,
+    #[schema(example = "/api/v1/docs/")]
+    pub docs_url: String,
+    #[schema(example = "/api/v1/health")]
+    pub health_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<Vec<TableRowCount>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableRowCount {
+    pub name: String,
+    pub estimated_rows: i64,
+}
+
+/// Operational statistics for `/admin/stats`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminStatsPayload {
+    /// When this instance started
+    pub started_at: DateTime<Utc>,
+    /// Seconds since this instance started
+    #[schema(example = 86400)]
+    pub uptime_secs: u64,
+    /// Primary connection pool usage
+    pub pool: PoolStatus,
+    /// Read-replica pool usage (only when `DATABASE_READ_URL` is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_pool: Option<PoolStatus>,
+    /// Primary database reachability, round-trip latency and PostGIS version
+    pub database: DatabaseStatus,
+    /// Buffer cache hit ratios of the primary since its statistics were reset
+    pub cache: CacheHitRates,
+    /// Tables of the primary, largest first
+    pub tables: Vec<TableUsage>,
+    /// Indexes of the primary, largest first
+    pub indexes: Vec<IndexUsage>,
+    /// Requests this instance served per route since it started, busiest first
+    pub endpoints: Vec<EndpointCounter>,
+}
+
+/// Share of block reads served from Postgres' buffer cache, 0–1 (`null`
+/// before any block was read).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheHitRates {
+    #[schema(example = 0.993)]
+    pub tables: Option<f64>,
+    #[schema(example = 0.998)]
+    pub indexes: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableUsage {
+    #[schema(example = "population")]
+    pub name: String,
+    /// Live rows as tracked by the statistics collector
+    #[schema(example = 31000000)]
+    pub rows: i64,
+    /// Heap, indexes and TOAST together
+    #[schema(example = 2147483648_i64)]
+    pub total_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexUsage {
+    #[schema(example = "population_pkey")]
+    pub name: String,
+    #[schema(example = "population")]
+    pub table: String,
+    #[schema(example = 696254464_i64)]
+    pub bytes: i64,
+    /// Index scans since statistics were reset
+    #[schema(example = 120394)]
+    pub scans: i64,
+}
+
+/// Result of `/admin/cache/flush` on the instance that served it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheFlushPayload {
+    /// The requested prefix (`null` when everything was flushed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/country")]
+    pub prefix: Option<String>,
+    /// Entries dropped across all caches
+    #[schema(example = 42)]
+    pub flushed: usize,
+    /// Entries dropped per cache
+    pub caches: Vec<CacheFlushed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheFlushed {
+    /// Cache name, e.g. `etag`
+    #[schema(example = "etag")]
+    pub name: String,
+    #[schema(example = 42)]
+    pub flushed: usize,
+}
+
+/// Requests to one route, keyed by method and route pattern.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointCounter {
+    #[schema(example = "GET")]
+    pub method: String,
+    /// Route pattern, or `unmatched` for requests no route took
+    #[schema(example = "/api/v1/country/{iso3}")]
+    pub route: String,
+    #[schema(example = 1520)]
+    pub requests: u64,
+    /// Responses with a 4xx status
+    #[schema(example = 12)]
+    pub client_errors: u64,
+    /// Responses with a 5xx status
+    #[schema(example = 0)]
+    pub server_errors: u64,
+    #[schema(example = 4.2)]
+    pub mean_latency_ms: f64,
+}
+
+/// One NDJSON line of a `/tag/stream` response: a tagged point, or the error for
+/// an input line that could not be tagged.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum TagStreamLine {
+    Tagged(TaggedPoint),
+    Error(TagLineError),
+}
+
+/// A coordinate tagged with its country and grid-cell population.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"id": "sensor-17", "lat": 6.9271, "lon": 79.8612, "iso3": "LKA", "population": 28534.0}))]
+pub struct TaggedPoint {
+    /// Identifier from the input line, if one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>, example = "sensor-17")]
+    pub id: Option<serde_json::Value>,
+    /// Input latitude
+    #[schema(example = 6.9271)]
+    pub lat: f64,
+    /// Input longitude
+    #[schema(example = 79.8612)]
+    pub lon: f64,
+    /// ISO 3166-1 alpha-3 code of the containing country (null at sea)
+    #[schema(example = "LKA")]
+    pub iso3: Option<String>,
+    /// Estimated population of the 1 km² grid cell
+    #[schema(example = 28534.0)]
+    pub population: f32,
+}
+
+/// Error for a single input line of a tagging stream.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({"line": 42, "error": "invalid JSON: expected value at line 1 column 1"}))]
+pub struct TagLineError {
+    /// 1-based input line number (0 for stream-level errors)
+    #[schema(example = 42)]
+    pub line: usize,
+    /// What went wrong
+    #[schema(example = "invalid JSON: expected value at line 1 column 1")]
+    pub error: String,
+}
+
+/// Population inside one wind-speed threshold swath.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "threshold_kt": 34, "radii_source": "advisory",
+    "radii_km": {"ne": 260.0, "se": 220.0, "sw": 150.0, "nw": 190.0},
+    "area_km2": 131476.4, "population": 1245301.7
+}))]
+pub struct WindSwath {
+    /// Sustained wind threshold in knots (34, 50 or 64)
+    #[schema(example = 34)]
+    pub threshold_kt: u16,
+    /// `advisory` when taken from `wind_radii`, `rankine` when modelled from max wind and RMW
+    #[schema(example = "advisory")]
+    pub radii_source: String,
+    /// Quadrant radii of the swath in km
+    pub radii_km: QuadrantRadii,
+    /// Area of the swath in km²
+    #[schema(example = 131476.4)]
+    pub area_km2: f64,
+    /// Estimated population exposed to at least this wind speed
+    #[schema(example = 1245301.7)]
+    pub population: f64,
+}
+
+/// Population exposure to a tropical cyclone's wind field.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CyclonePayload {
+    /// Storm centre
+    pub coordinate: CoordinateInfo,
+    /// Maximum sustained wind in knots
+    #[schema(example = 115.0)]
+    pub max_wind_kt: f64,
+    /// Radius of maximum winds in km, if given
+    #[schema(example = 30.0)]
+    pub rmw_km: Option<f64>,
+    /// Nested swaths, weakest (34 kt) first
+    pub swaths: Vec<WindSwath>,
+    /// GeoJSON FeatureCollection with one polygon per non-empty swath (only with `include=geometry`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub geometry: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Population living at or below a flood level inside an area.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FloodPayload {
+    /// Centre of the area (only for point + radius requests)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate: Option<CoordinateInfo>,
+    /// Search radius in kilometres (only for point + radius requests)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 25.0)]
+    pub radius_km: Option<f64>,
+    /// Bounding box of the area as `[min_lon, min_lat, max_lon, max_lat]`
+    #[schema(example = json!([90.1306, 23.5854, 90.6894, 24.0346]))]
+    pub bbox: [f64; 4],
+    /// Ground elevation threshold in metres
+    #[schema(example = 6.0)]
+    pub max_elevation_m: f64,
+    /// Total estimated population in the area
+    #[schema(example = 14820553.2)]
+    pub total_population: f64,
+    /// Population in cells at or below `max_elevation_m`
+    #[schema(example = 3912004.8)]
+    pub flooded_population: f64,
+    /// Number of populated cells at or below `max_elevation_m`
+    #[schema(example = 412)]
+    pub flooded_cell_count: i64,
+    /// `flooded_population` as a fraction of `total_population` (0–1)
+    #[schema(example = 0.264)]
+    pub share_of_total: f64,
+    /// Each polygon of the area in input order (only with `breakdown`). Cells
+    /// under overlapping polygons count in each of them but once in the totals.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Vec<FloodPartExposure>>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Flood exposure of one polygon of a multi-part flood area.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "index": 0, "bbox": [90.3, 23.7, 90.5, 23.9], "total_population": 2104330.0,
+    "flooded_population": 512004.3, "flooded_cell_count": 88
+}))]
+pub struct FloodPartExposure {
+    /// Position of the polygon in the request, counting the polygons of
+    /// MultiPolygons and collections one by one
+    pub index: usize,
+    /// Bounding box of the polygon as `[min_lon, min_lat, max_lon, max_lat]`
+    pub bbox: [f64; 4],
+    /// Total estimated population in the polygon
+    pub total_population: f64,
+    /// Population in cells at or below `max_elevation_m`
+    pub flooded_population: f64,
+    /// Number of populated cells at or below `max_elevation_m`
+    pub flooded_cell_count: i64,
+}
+
+/// Population inside one ShakeMap MMI contour.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "mmi": 7.0, "population": 1520344.0, "band_population": 904211.5, "populated_cell_count": 3120,
+    "area_km2": 9876.5, "bbox": [36.5, 36.8, 38.1, 38.2]
+}))]
+pub struct MmiContourExposure {
+    /// Modified Mercalli intensity of the contour
+    #[schema(example = 7.0)]
+    pub mmi: f64,
+    /// Population inside the contour, i.e. shaken at this intensity or more
+    #[schema(example = 1520344.0)]
+    pub population: f64,
+    /// Population between this contour and the next higher one
+    #[schema(example = 904211.5)]
+    pub band_population: f64,
+    /// Populated grid cells inside the contour
+    #[schema(example = 3120)]
+    pub populated_cell_count: i64,
+    /// Area inside the contour in km²
+    #[schema(example = 9876.5)]
+    pub area_km2: f64,
+    /// Bounding box of the contour as `[min_lon, min_lat, max_lon, max_lat]`
+    #[schema(example = json!([36.5, 36.8, 38.1, 38.2]))]
+    pub bbox: [f64; 4],
+}
+
+/// Population per MMI contour of a ShakeMap.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShakeMapPayload {
+    /// USGS event the contours were fetched for (only with `event_id`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "us6000jllz")]
+    pub event_id: Option<String>,
+    /// Population inside the lowest contour
+    #[schema(example = 9821406.3)]
+    pub total_population: f64,
+    /// One entry per MMI level, highest intensity first
+    pub contours: Vec<MmiContourExposure>,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Population within a travel-time isochrone from the routing engine.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IsochronePayload {
+    /// Starting point of the isochrone
+    pub coordinate: CoordinateInfo,
+    /// Travel time in minutes
+    #[schema(example = 30.0)]
+    pub minutes: f64,
+    /// Means of travel
+    pub mode: TravelMode,
+    /// Area reachable within `minutes` (GeoJSON Polygon or MultiPolygon, WGS84 lon/lat)
+    #[schema(value_type = Object)]
+    pub isochrone: serde_json::Value,
+    /// Bounding box of the isochrone as `[min_lon, min_lat, max_lon, max_lat]`
+    #[schema(example = json!([79.7811, 6.8033, 80.0342, 7.0719]))]
+    pub bbox: [f64; 4],
+    /// Area of the isochrone in km²
+    #[schema(example = 412.7)]
+    pub area_km2: f64,
+    /// Total estimated population in cells whose centre lies inside the isochrone
+    #[schema(example = 1873420.6)]
+    pub total_population: f64,
+    /// Number of populated cells inside the isochrone
+    #[schema(example = 498)]
+    pub populated_cell_count: i64,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Country containing a point, as found by `GET /admin`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminCountry {
+    /// ISO 3166-1 alpha-2 code
+    #[schema(example = "LK")]
+    pub iso_a2: Option<String>,
+    /// ISO 3166-1 alpha-3 code
+    #[schema(example = "LKA")]
+    pub iso_a3: Option<String>,
+    /// Country name, localised when a `lang` name exists
+    #[schema(example = "Sri Lanka")]
+    pub name: String,
+}
+
+/// Administrative unit containing a point, as found by `GET /admin`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminArea {
+    /// GeoNames admin code, when the boundary layer carries one
+    #[schema(example = "LK.36")]
+    pub code: Option<String>,
+    /// Unit name: localised when a `lang` name exists, else GeoNames', else the boundary layer's
+    #[schema(example = "Western")]
+    pub name: String,
+}
+
+/// Administrative hierarchy of a point, as returned by `GET /admin`. A level
+/// is `null` when no loaded boundary of it contains the point.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "coordinate": {"lat": 6.9271, "lon": 79.8612},
+    "country": {"iso_a2": "LK", "iso_a3": "LKA", "name": "Sri Lanka"},
+    "admin1": {"code": "LK.36", "name": "Western"},
+    "admin2": {"code": "LK.36.1232783", "name": "Colombo District"}
+}))]
+pub struct AdminHierarchyPayload {
+    /// The looked-up coordinate
+    pub coordinate: CoordinateInfo,
+    /// Country whose boundary contains the point
+    pub country: Option<AdminCountry>,
+    /// First-level unit (state / province) containing the point
+    pub admin1: Option<AdminArea>,
+    /// Second-level unit (district / county) containing the point
+    pub admin2: Option<AdminArea>,
+}
+
+/// A named place inside an administrative unit.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminPlace {
+    /// GeoNames place identifier
+    #[schema(example = 1248991)]
+    pub place_id: i32,
+    /// Place name
+    #[schema(example = "Colombo")]
+    pub name: String,
+    /// GeoNames feature code (PPLC = capital, PPLA = admin capital, PPL = populated place, ...)
+    #[schema(example = "PPLC")]
+    pub feature_code: Option<String>,
+    /// GeoNames population estimate (0 if unknown)
+    #[schema(example = 648034)]
+    pub population: i64,
+    /// Latitude of the place
+    #[schema(example = 6.9319)]
+    pub lat: f64,
+    /// Longitude of the place
+    #[schema(example = 79.8478)]
+    pub lon: f64,
+}
+
+/// Grid population of an administrative unit, as returned by `GET /exposure/admin`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "code": "LK.36", "level": 1, "name": "Western", "country_code": "LK",
+    "bbox": [79.8106, 6.3985, 80.3372, 7.3419], "area_km2": 3684.2, "total_population": 6185306.4,
+    "populated_cell_count": 3712, "density_per_km2": 1678.8,
+    "top_places": [{"place_id": 1248991, "name": "Colombo", "feature_code": "PPLC", "population": 648034, "lat": 6.9319, "lon": 79.8478}]
+}))]
+pub struct AdminExposurePayload {
+    /// GeoNames admin code of the unit
+    #[schema(example = "LK.36")]
+    pub code: String,
+    /// Administrative level: 1 (state / province) or 2 (district / county)
+    #[schema(example = 1)]
+    pub level: i16,
+    /// Unit name, from GeoNames when it knows the code, else from the boundary layer
+    #[schema(example = "Western")]
+    pub name: String,
+    /// ISO 3166-1 alpha-2 country code
+    #[schema(example = "LK")]
+    pub country_code: Option<String>,
+    /// Bounding box of the unit as `[min_lon, min_lat, max_lon, max_lat]`
+    pub bbox: [f64; 4],
+    /// Area of the unit in km²
+    #[schema(example = 3684.2)]
+    pub area_km2: f64,
+    /// Total estimated population in cells whose centre lies inside the unit
+    #[schema(example = 6185306.4)]
+    pub total_population: f64,
+    /// Number of populated cells inside the unit
+    #[schema(example = 3712)]
+    pub populated_cell_count: i64,
+    /// `total_population` per km² of `area_km2`
+    #[schema(example = 1678.8)]
+    pub density_per_km2: f64,
+    /// Most populous named places inside the unit, largest first
+    pub top_places: Vec<AdminPlace>,
+}
+
+/// Population inside a buffered geometry, as returned by `POST /exposure/buffer`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BufferPayload {
+    /// GeoJSON type of the input geometry
+    #[schema(example = "LineString")]
+    pub geometry_type: String,
+    /// Buffer distance in kilometres
+    #[schema(example = 2.0)]
+    pub buffer_km: f64,
+    /// The buffered area (GeoJSON Polygon or MultiPolygon, WGS84 lon/lat)
+    #[schema(value_type = Object)]
+    pub buffered: serde_json::Value,
+    /// Bounding box of the buffered area as `[min_lon, min_lat, max_lon, max_lat]`
+    #[schema(example = json!([79.832, 6.882, 80.018, 7.018]))]
+    pub bbox: [f64; 4],
+    /// Area of the buffered geometry in km²
+    #[schema(example = 82.4)]
+    pub area_km2: f64,
+    /// Total estimated population in cells whose centre lies inside the buffered area
+    #[schema(example = 412830.5)]
+    pub total_population: f64,
+    /// Number of populated cells inside the buffered area
+    #[schema(example = 96)]
+    pub populated_cell_count: i64,
+    #[serde(flatten)]
+    pub meta: IncludedMeta,
+}
+
+/// Lifecycle state of a background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+    /// Being executed
+    Running,
+    /// Finished; `result` holds the endpoint payload
+    Succeeded,
+    /// Finished; `error` says why
+    Failed,
+}
+
+impl JobStatus {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(Self::Queued),
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A background job and, once finished, its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobPayload {
+    /// Job identifier
+    #[schema(example = "5f0c6f6e-2b1d-4c5e-9a51-0f5d3b7b9e21")]
+    pub id: Uuid,
+    /// Analysis the job runs
+    pub kind: JobKind,
+    /// Current state
+    pub status: JobStatus,
+    /// Execution attempts so far (a job whose worker died is retried)
+    #[schema(example = 1)]
+    pub attempts: i16,
+    /// Submission time
+    pub created_at: DateTime<Utc>,
+    /// When the latest attempt started
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job succeeded or failed
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The endpoint's payload, exactly as the synchronous call returns it (only when `succeeded`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    /// Failure reason (only when `failed`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Webhook delivery state (only when submitted with `callback_url`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback: Option<JobCallback>,
+}
+
+/// A disaster event from the GDACS feed, with the analysis run for it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventPayload {
+    /// Event type and GDACS event ID
+    #[schema(example = "EQ1402359")]
+    pub id: String,
+    /// GDACS event type (`EQ`, `TC`, `FL`, `VO`, `DR` or `WF`)
+    #[schema(example = "EQ")]
+    pub event_type: String,
+    /// GDACS event ID
+    #[schema(example = 1402359)]
+    pub event_id: i64,
+    /// Latest GDACS episode of the event
+    #[schema(example = 1559580)]
+    pub episode_id: Option<i64>,
+    #[schema(example = "Earthquake in Japan")]
+    pub name: String,
+    /// Latest GDACS alert level
+    pub alert_level: AlertLevel,
+    /// Severity in the event type's unit (magnitude, wind speed in km/h, ...)
+    #[schema(example = 7.5)]
+    pub severity: Option<f64>,
+    #[schema(example = "Magnitude 7.5M, Depth:10km")]
+    pub severity_text: Option<String>,
+    /// Location the analysis was run for
+    pub coordinate: CoordinateInfo,
+    #[schema(example = "Japan")]
+    pub country: Option<String>,
+    /// GDACS event report
+    #[schema(example = "https://www.gdacs.org/report.aspx?eventid=1402359&episodeid=1559580&eventtype=EQ")]
+    pub report_url: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// When the poller first recorded the event
+    pub first_seen_at: DateTime<Utc>,
+    /// State of the event's analysis
+    pub status: JobStatus,
+    /// The `/analyse` payload for the event's location (only when `succeeded`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    /// Failure reason (only when `failed`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Recorded disaster events, most recent first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventListPayload {
+    #[schema(example = 1)]
+    pub count: usize,
+    pub events: Vec<EventPayload>,
+}
+
+/// A monitored location and its latest exposure snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorPayload {
+    /// Monitor identifier
+    #[schema(example = "0d3c1f8e-6a54-4f0b-9a3e-2f9b6c1d7e42")]
+    pub id: Uuid,
+    #[schema(example = "Colombo port")]
+    pub name: String,
+    /// Centre of a circular monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate: Option<CoordinateInfo>,
+    /// Radius of a circular monitor in km
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 10.0)]
+    pub radius_km: Option<f64>,
+    /// Area of a polygon monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub polygon: Option<serde_json::Value>,
+    /// Hours between snapshots
+    #[schema(example = 24)]
+    pub interval_hours: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the next snapshot is due
+    pub next_run_at: DateTime<Utc>,
+    /// When the latest snapshot was taken
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Population in the latest snapshot
+    #[schema(example = 412093.5)]
+    pub last_population: Option<f64>,
+    /// Why the most recent run failed, until a later one succeeds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Conditions that raise alerts
+    pub thresholds: Vec<AlertThreshold>,
+    /// Where alerts are sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "https://hooks.example.com/geopop")]
+    pub webhook_url: Option<String>,
+}
+
+/// Registered monitors, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorListPayload {
+    #[schema(example = 1)]
+    pub count: usize,
+    pub monitors: Vec<MonitorPayload>,
+}
+
+/// Exposure of a monitored location at one point in time.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorSnapshot {
+    pub taken_at: DateTime<Utc>,
+    /// Population inside the monitored area
+    #[schema(example = 412093.5)]
+    pub total_population: f64,
+    /// Change since the previous snapshot (absent for the first)
+    #[schema(example = 1520.0)]
+    pub change: Option<f64>,
+}
+
+/// A monitor's snapshots, most recent first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorSnapshotsPayload {
+    pub monitor_id: Uuid,
+    pub snapshots: Vec<MonitorSnapshot>,
+}
+
+/// A breached monitor threshold.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorAlert {
+    /// Alert identifier
+    #[schema(example = "7c2e5b1a-93f4-4d8e-b6a0-1e4f2d9c3b57")]
+    pub id: Uuid,
+    pub monitor_id: Uuid,
+    #[schema(example = "Colombo port")]
+    pub monitor_name: String,
+    /// The threshold that was breached
+    pub threshold: AlertThreshold,
+    /// The metric's value when breached
+    #[schema(example = 134502.0)]
+    pub value: f64,
+    /// The `/events` id of the event that breached an `event_exposure` threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "EQ1402359")]
+    pub event: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Webhook delivery state (only when the monitor has a `webhook_url`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<JobCallback>,
+}
+
+/// A monitor's alerts, most recent first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitorAlertsPayload {
+    pub monitor_id: Uuid,
+    pub alerts: Vec<MonitorAlert>,
+}
+
+/// Webhook delivery of a finished job or monitor alert.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobCallback {
+    /// Target URL
+    #[schema(example = "https://hooks.example.com/geopop")]
+    pub url: String,
+    /// `pending` until the job finishes and the callback is delivered or gives up
+    pub status: CallbackStatus,
+    /// Delivery attempts so far
+    #[schema(example = 1)]
+    pub attempts: i16,
+    /// Error of the most recent failed attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Delivery state of a job's webhook callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackStatus {
+    /// Waiting for the job to finish, or for the next delivery attempt
+    Pending,
+    /// Receiver answered with a 2xx status
+    Delivered,
+    /// All delivery attempts failed
+    Failed,
+}
+
+impl CallbackStatus {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(Self::Pending),
+            "delivered" => Some(Self::Delivered),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// `epicentre` event of /analyse/stream.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EpicentreEvent {
+    /// Population of the epicentre grid cell
+    #[schema(example = 0.0)]
+    pub population: f32,
+    /// Whether the epicentre is on land
+    #[schema(example = false)]
+    pub is_land: bool,
+}
+
+/// `probe` event of /analyse/stream: one radius tier checked for population.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProbeEvent {
+    /// Radius of the tier in km
+    #[schema(example = 25.0)]
+    pub radius_km: f64,
+    /// Whether any populated cell lies within it
+    #[schema(example = false)]
+    pub populated: bool,
+}
+
+/// `error` event of a stream that failed after it started.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamError {
+    /// What went wrong
+    #[schema(example = "database connection error")]
+    pub message: String,
+}
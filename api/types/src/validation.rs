@@ -0,0 +1,672 @@
+//! Field validators referenced by the `#[validate(custom(...))]` attributes on
+//! the request types, with the limits they enforce.
+
+use std::net::IpAddr;
+
+use validator::ValidationError;
+
+use crate::coordinates::parse_pair;
+use crate::requests::{ExportLayer, FacilityType};
+
+pub const MAX_RADIUS_KM: f64 = 500.0;
+pub const MAX_POPULATION_RADIUS_KM: f64 = 10.0;
+/// Most places `/analyse?nearest_places=` returns.
+pub const MAX_NEAREST_PLACES: i64 = 20;
+/// Most facilities `/facilities/nearest` returns.
+pub const MAX_FACILITIES: i64 = 50;
+/// Most facilities of one type `/facilities` lists.
+pub const MAX_FACILITY_LIST: i64 = 500;
+/// Longest runway length `/airports/nearest?min_runway_m=` accepts; the
+/// longest paved runways in service are a little over 5 km.
+pub const MAX_RUNWAY_M: f64 = 6000.0;
+/// Most cells `/exposure?top_cells=` returns.
+pub const MAX_TOP_CELLS: i64 = 100;
+/// About half the Earth's circumference: no place is further from any point.
+pub const MAX_SEARCH_DISTANCE_KM: f64 = 20_000.0;
+/// Bounds of the `/analyse` radius search step and cap.
+pub const MIN_ANALYSE_STEP_KM: f64 = 1.0;
+pub const MAX_ANALYSE_STEP_KM: f64 = 100.0;
+pub const MAX_ANALYSE_RADIUS_KM: f64 = 1000.0;
+pub const MAX_WIND_RADIUS_KM: f64 = 1000.0;
+/// Coastal band stored by `ingest_terrain.py`; wider queries would silently truncate.
+pub const MAX_COAST_KM: f64 = 100.0;
+pub const MAX_COASTAL_ELEVATION_M: f64 = 100.0;
+/// Most distance rings one `/exposure` request may break down.
+pub const MAX_RINGS: usize = 10;
+/// Largest polygon bounding box (either side, km) accepted by /exposure/flood.
+pub const MAX_FLOOD_SPAN_KM: f64 = 2.0 * MAX_RADIUS_KM;
+/// Largest admin unit bounding box (either side, km) /exposure/admin evaluates.
+pub const MAX_ADMIN_SPAN_KM: f64 = 2500.0;
+/// Most polygons one /exposure/flood request may break down with `breakdown`.
+pub const MAX_POLYGON_PARTS: usize = 100;
+/// Widest buffer distance accepted by /exposure/buffer.
+pub const MAX_BUFFER_KM: f64 = 100.0;
+/// Largest buffered-geometry bounding box (either side, km) accepted by /exposure/buffer.
+pub const MAX_BUFFER_SPAN_KM: f64 = 2.0 * MAX_RADIUS_KM;
+/// Largest contour bounding box (either side, km) accepted by /exposure/shakemap.
+pub const MAX_SHAKEMAP_SPAN_KM: f64 = 2.0 * MAX_RADIUS_KM;
+/// Largest polygon bounding box (either side, km) a monitor may cover.
+pub const MAX_MONITOR_SPAN_KM: f64 = 2.0 * MAX_RADIUS_KM;
+/// Most distinct MMI levels one /exposure/shakemap request may break down.
+pub const MAX_MMI_LEVELS: usize = 30;
+/// Largest /population/grid viewport (either side, degrees).
+pub const MAX_GRID_BBOX_DEG: f64 = 10.0;
+/// Most cells one /population/grid response may carry before aggregating.
+pub const MAX_GRID_CELLS: i64 = 20_000;
+/// Most samples one /route/sample request may evaluate.
+pub const MAX_ROUTE_SAMPLES: usize = 2000;
+/// Deepest zoom served by /tiles/countries; Natural Earth 10m gains no detail beyond it.
+pub const MAX_TILE_ZOOM: u32 = 12;
+/// Longest /exposure/isochrone travel time, minutes (Valhalla's default contour limit).
+pub const MAX_ISOCHRONE_MINUTES: f64 = 120.0;
+pub const VALID_CONTINENTS: &[&str] = &[
+    "asia", "europe", "africa", "oceania", "americas",
+    "north-america", "south-america",
+];
+/// UN M49 regions, as stored in Natural Earth's `region_un`.
+pub const VALID_REGIONS: &[&str] = &["Africa", "Americas", "Asia", "Europe", "Oceania"];
+/// UN M49 subregions (intermediate regions in Africa and the Americas), as
+/// stored in Natural Earth's `subregion`.
+pub const VALID_SUBREGIONS: &[&str] = &[
+    "Australia and New Zealand", "Caribbean", "Central America", "Central Asia",
+    "Eastern Africa", "Eastern Asia", "Eastern Europe", "Melanesia", "Micronesia",
+    "Middle Africa", "Northern Africa", "Northern America", "Northern Europe", "Polynesia",
+    "South America", "South-Eastern Asia", "Southern Africa", "Southern Asia",
+    "Southern Europe", "Western Africa", "Western Asia", "Western Europe",
+];
+
+pub fn validate_lat(lat: f64) -> Result<(), ValidationError> {
+    if !lat.is_finite() || !(-90.0..=90.0).contains(&lat) {
+        return Err(ValidationError::new("latitude"));
+    }
+    Ok(())
+}
+
+pub fn validate_lon(lon: f64) -> Result<(), ValidationError> {
+    if !lon.is_finite() || !(-180.0..180.0).contains(&lon) {
+        return Err(ValidationError::new("longitude"));
+    }
+    Ok(())
+}
+
+pub fn validate_population_radius(radius: f64) -> Result<(), ValidationError> {
+    if !radius.is_finite() || radius <= 0.0 || radius > MAX_POPULATION_RADIUS_KM {
+        return Err(ValidationError::new("radius"));
+    }
+    Ok(())
+}
+
+pub fn validate_radius_field(radius: f64) -> Result<(), ValidationError> {
+    if !radius.is_finite() || radius <= 0.0 || radius > MAX_RADIUS_KM {
+        return Err(ValidationError::new("radius"));
+    }
+    Ok(())
+}
+
+pub fn validate_max_distance_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || km <= 0.0 || km > MAX_SEARCH_DISTANCE_KM {
+        return Err(ValidationError::new("max_distance_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_step_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || !(MIN_ANALYSE_STEP_KM..=MAX_ANALYSE_STEP_KM).contains(&km) {
+        return Err(ValidationError::new("step_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_max_radius_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || !(MIN_ANALYSE_STEP_KM..=MAX_ANALYSE_RADIUS_KM).contains(&km) {
+        return Err(ValidationError::new("max_radius_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_magnitude(magnitude: f64) -> Result<(), ValidationError> {
+    if !magnitude.is_finite() {
+        return Err(ValidationError::new("magnitude"));
+    }
+    Ok(())
+}
+
+pub fn validate_wind_speed(kt: f64) -> Result<(), ValidationError> {
+    if !kt.is_finite() || !(10.0..=250.0).contains(&kt) {
+        return Err(ValidationError::new("max_wind_kt"));
+    }
+    Ok(())
+}
+
+pub fn validate_rmw(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || km <= 0.0 || km > 300.0 {
+        return Err(ValidationError::new("rmw_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_wind_radius(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || !(0.0..=MAX_WIND_RADIUS_KM).contains(&km) {
+        return Err(ValidationError::new("wind_radius"));
+    }
+    Ok(())
+}
+
+pub fn validate_coast_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || km <= 0.0 || km > MAX_COAST_KM {
+        return Err(ValidationError::new("coast_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_max_elevation(m: f64) -> Result<(), ValidationError> {
+    if !m.is_finite() || !(-MAX_COASTAL_ELEVATION_M..=MAX_COASTAL_ELEVATION_M).contains(&m) {
+        return Err(ValidationError::new("max_elevation_m"));
+    }
+    Ok(())
+}
+
+/// Parses a `rings=5,10,25` list: at most `MAX_RINGS` strictly ascending radii
+/// in (0, `MAX_RADIUS_KM`]. `None` if malformed.
+pub fn parse_rings(raw: &str) -> Option<Vec<f64>> {
+    let rings = raw
+        .split(',')
+        .map(|t| t.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    let in_range = rings.iter().all(|&r| r.is_finite() && r > 0.0 && r <= MAX_RADIUS_KM);
+    let ascending = rings.windows(2).all(|w| w[0] < w[1]);
+    (!rings.is_empty() && rings.len() <= MAX_RINGS && in_range && ascending).then_some(rings)
+}
+
+pub fn validate_rings(raw: &str) -> Result<(), ValidationError> {
+    if parse_rings(raw).is_none() {
+        return Err(ValidationError::new("rings"));
+    }
+    Ok(())
+}
+
+/// Parses a `layers=population,places` list of distinct `/export/geopackage`
+/// layers. `None` if malformed.
+pub fn parse_layers(raw: &str) -> Option<Vec<ExportLayer>> {
+    let mut layers = Vec::new();
+    for token in raw.split(',').map(str::trim) {
+        let layer = ExportLayer::ALL.into_iter().find(|l| l.as_str() == token)?;
+        if layers.contains(&layer) {
+            return None;
+        }
+        layers.push(layer);
+    }
+    Some(layers)
+}
+
+pub fn validate_layers(raw: &str) -> Result<(), ValidationError> {
+    if parse_layers(raw).is_none() {
+        return Err(ValidationError::new("layers"));
+    }
+    Ok(())
+}
+
+pub fn parse_facility_types(raw: &str) -> Option<Vec<FacilityType>> {
+    let mut types = Vec::new();
+    for token in raw.split(',').map(str::trim) {
+        let facility_type = FacilityType::ALL.into_iter().find(|t| t.as_str() == token)?;
+        if types.contains(&facility_type) {
+            return None;
+        }
+        types.push(facility_type);
+    }
+    Some(types)
+}
+
+pub fn validate_facility_types(raw: &str) -> Result<(), ValidationError> {
+    if parse_facility_types(raw).is_none() {
+        return Err(ValidationError::new("types"));
+    }
+    Ok(())
+}
+
+/// Most languages one `lang` list may name.
+pub const MAX_LANGUAGES: usize = 8;
+
+/// Whether `tag` is a language tag as GeoNames uses them: a 2–3 letter ISO 639
+/// code, optionally followed by region or script subtags (`ta`, `zh-TW`).
+pub fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.bytes().all(|b| b.is_ascii_alphabetic())
+        && subtags.all(|s| (2..=8).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+/// Parses a `lang=ta,en` list of at most `MAX_LANGUAGES` language tags,
+/// lowercased. `None` if malformed.
+pub fn parse_languages(raw: &str) -> Option<Vec<String>> {
+    let tags: Vec<String> = raw.split(',').map(|t| t.trim().to_ascii_lowercase()).collect();
+    (tags.len() <= MAX_LANGUAGES && tags.iter().all(|t| is_language_tag(t))).then_some(tags)
+}
+
+pub fn validate_languages(raw: &str) -> Result<(), ValidationError> {
+    if parse_languages(raw).is_none() {
+        return Err(ValidationError::new("lang"));
+    }
+    Ok(())
+}
+
+/// Parses a `lat,lon` coordinate pair (in any notation [`parse_pair`] accepts)
+/// within the ranges of `validate_lat` and `validate_lon`. `None` if malformed.
+pub fn parse_lat_lon(raw: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = parse_pair(raw)?;
+    (validate_lat(lat).is_ok() && validate_lon(lon).is_ok()).then_some((lat, lon))
+}
+
+pub fn validate_lat_lon(raw: &str) -> Result<(), ValidationError> {
+    if parse_lat_lon(raw).is_none() {
+        return Err(ValidationError::new("lat_lon"));
+    }
+    Ok(())
+}
+
+/// Parses a `bbox=min_lon,min_lat,max_lon,max_lat` viewport: ordered corners
+/// within range, neither side wider than `MAX_GRID_BBOX_DEG`. `None` if malformed.
+pub fn parse_bbox(raw: &str) -> Option<[f64; 4]> {
+    let values = raw
+        .split(',')
+        .map(|t| t.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    let [min_lon, min_lat, max_lon, max_lat] = <[f64; 4]>::try_from(values).ok()?;
+    let in_range = ordered_within(min_lon, max_lon, -180.0, 180.0)
+        && ordered_within(min_lat, max_lat, -90.0, 90.0);
+    let within_span = max_lon - min_lon <= MAX_GRID_BBOX_DEG && max_lat - min_lat <= MAX_GRID_BBOX_DEG;
+    (in_range && within_span).then_some([min_lon, min_lat, max_lon, max_lat])
+}
+
+fn ordered_within(min: f64, max: f64, lower: f64, upper: f64) -> bool {
+    min.is_finite() && max.is_finite() && lower <= min && min < max && max <= upper
+}
+
+pub fn validate_bbox(raw: &str) -> Result<(), ValidationError> {
+    if parse_bbox(raw).is_none() {
+        return Err(ValidationError::new("bbox"));
+    }
+    Ok(())
+}
+
+pub fn validate_max_cells(max_cells: i64) -> Result<(), ValidationError> {
+    if !(1..=MAX_GRID_CELLS).contains(&max_cells) {
+        return Err(ValidationError::new("max_cells"));
+    }
+    Ok(())
+}
+
+pub fn validate_h3_resolution(resolution: u8) -> Result<(), ValidationError> {
+    if resolution > 15 {
+        return Err(ValidationError::new("resolution"));
+    }
+    Ok(())
+}
+
+pub fn validate_s2_level(level: u8) -> Result<(), ValidationError> {
+    if level > 30 {
+        return Err(ValidationError::new("level"));
+    }
+    Ok(())
+}
+
+pub fn validate_path_prefix(prefix: &str) -> Result<(), ValidationError> {
+    if !prefix.starts_with('/') || prefix.len() > 256 || prefix.contains(['?', '#']) {
+        return Err(ValidationError::new("prefix"));
+    }
+    Ok(())
+}
+
+pub fn validate_flood_elevation(m: f64) -> Result<(), ValidationError> {
+    if !m.is_finite() || !(-500.0..=9000.0).contains(&m) {
+        return Err(ValidationError::new("max_elevation_m"));
+    }
+    Ok(())
+}
+
+pub fn validate_isochrone_minutes(minutes: f64) -> Result<(), ValidationError> {
+    if !minutes.is_finite() || minutes <= 0.0 || minutes > MAX_ISOCHRONE_MINUTES {
+        return Err(ValidationError::new("minutes"));
+    }
+    Ok(())
+}
+
+pub fn validate_buffer_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || !(0.0..=MAX_BUFFER_KM).contains(&km) {
+        return Err(ValidationError::new("buffer_km"));
+    }
+    Ok(())
+}
+
+pub fn validate_interval_km(km: f64) -> Result<(), ValidationError> {
+    if !km.is_finite() || km <= 0.0 || km > 1000.0 {
+        return Err(ValidationError::new("interval_km"));
+    }
+    Ok(())
+}
+
+/// Webhook URLs: http(s), at most 2048 bytes, and not aimed at this host or
+/// a private network by name or literal address. Hostnames are resolved and
+/// checked again by the server before each delivery.
+pub fn validate_callback_url(url: &str) -> Result<(), ValidationError> {
+    let valid = url.len() <= 2048
+        && url::Url::parse(url).is_ok_and(|u| {
+            matches!(u.scheme(), "http" | "https")
+                && match u.host() {
+                    Some(url::Host::Domain(domain)) => {
+                        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+                        domain != "localhost" && !domain.ends_with(".localhost")
+                    }
+                    Some(url::Host::Ipv4(ip)) => is_public_ip(ip.into()),
+                    Some(url::Host::Ipv6(ip)) => is_public_ip(ip.into()),
+                    None => false,
+                }
+        });
+    if !valid {
+        return Err(ValidationError::new("callback_url"));
+    }
+    Ok(())
+}
+
+/// Whether `ip` is a public unicast address: not unspecified, loopback,
+/// private, shared (100.64/10), link-local, multicast, broadcast,
+/// documentation or an IPv6 unique-local one.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(a == 0
+                || v4.is_loopback()
+                || v4.is_private()
+                || (a == 100 && (64..128).contains(&b))
+                || v4.is_link_local()
+                || v4.is_multicast()
+                || v4.is_broadcast()
+                || v4.is_documentation())
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ip(v4.into()),
+            None => {
+                let first = v6.segments()[0];
+                !(v6.is_unspecified()
+                    || v6.is_loopback()
+                    || v6.is_multicast()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80
+                    || first == 0x2001 && v6.segments()[1] == 0x0db8)
+            }
+        },
+    }
+}
+
+/// USGS ComCat event IDs: network code and event code, e.g. `us7000abcd`.
+pub fn validate_event_id(id: &str) -> Result<(), ValidationError> {
+    if id.is_empty() || id.len() > 32 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ValidationError::new("event_id"));
+    }
+    Ok(())
+}
+
+/// GDACS event types: earthquake, tropical cyclone, flood, volcano, drought, wildfire.
+pub const EVENT_TYPES: &[&str] = &["EQ", "TC", "FL", "VO", "DR", "WF"];
+
+pub fn validate_event_type(event_type: &str) -> Result<(), ValidationError> {
+    if !EVENT_TYPES.iter().any(|t| t.eq_ignore_ascii_case(event_type)) {
+        return Err(ValidationError::new("event_type"));
+    }
+    Ok(())
+}
+
+pub fn validate_event_limit(limit: i64) -> Result<(), ValidationError> {
+    if !(1..=100).contains(&limit) {
+        return Err(ValidationError::new("limit"));
+    }
+    Ok(())
+}
+
+pub fn validate_monitor_name(name: &str) -> Result<(), ValidationError> {
+    let len = name.trim().chars().count();
+    if !(1..=100).contains(&len) {
+        return Err(ValidationError::new("name"));
+    }
+    Ok(())
+}
+
+/// Most thresholds one monitor may define.
+pub const MAX_MONITOR_THRESHOLDS: usize = 10;
+
+pub fn validate_thresholds<T>(thresholds: &[T]) -> Result<(), ValidationError> {
+    if thresholds.len() > MAX_MONITOR_THRESHOLDS {
+        return Err(ValidationError::new("thresholds"));
+    }
+    Ok(())
+}
+
+pub fn validate_threshold_value(value: f64) -> Result<(), ValidationError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(ValidationError::new("above"));
+    }
+    Ok(())
+}
+
+/// Hours between monitor snapshots: hourly up to monthly.
+pub fn validate_monitor_interval(hours: i32) -> Result<(), ValidationError> {
+    if !(1..=720).contains(&hours) {
+        return Err(ValidationError::new("interval_hours"));
+    }
+    Ok(())
+}
+
+pub fn validate_page(page: i64) -> Result<(), ValidationError> {
+    if page < 1 {
+        return Err(ValidationError::new("page"));
+    }
+    Ok(())
+}
+
+pub fn validate_nearest_places(n: i64) -> Result<(), ValidationError> {
+    if !(1..=MAX_NEAREST_PLACES).contains(&n) {
+        return Err(ValidationError::new("nearest_places"));
+    }
+    Ok(())
+}
+
+pub fn validate_facility_limit(limit: i64) -> Result<(), ValidationError> {
+    if !(1..=MAX_FACILITIES).contains(&limit) {
+        return Err(ValidationError::new("limit"));
+    }
+    Ok(())
+}
+
+pub fn validate_facility_list_limit(limit: i64) -> Result<(), ValidationError> {
+    if !(1..=MAX_FACILITY_LIST).contains(&limit) {
+        return Err(ValidationError::new("limit"));
+    }
+    Ok(())
+}
+
+pub fn validate_min_runway_m(m: f64) -> Result<(), ValidationError> {
+    if !(0.0..=MAX_RUNWAY_M).contains(&m) {
+        return Err(ValidationError::new("min_runway_m"));
+    }
+    Ok(())
+}
+
+pub fn validate_top_cells(n: i64) -> Result<(), ValidationError> {
+    if !(1..=MAX_TOP_CELLS).contains(&n) {
+        return Err(ValidationError::new("top_cells"));
+    }
+    Ok(())
+}
+
+pub fn validate_per_page(per_page: i64) -> Result<(), ValidationError> {
+    if !(1..=100).contains(&per_page) {
+        return Err(ValidationError::new("per_page"));
+    }
+    Ok(())
+}
+
+pub fn validate_offset(offset: i64) -> Result<(), ValidationError> {
+    if offset < 0 {
+        return Err(ValidationError::new("offset"));
+    }
+    Ok(())
+}
+
+pub fn validate_city_query(q: &str) -> Result<(), ValidationError> {
+    let trimmed = q.trim();
+    if trimmed.len() < 2 || trimmed.len() > 80 {
+        return Err(ValidationError::new("q"));
+    }
+    Ok(())
+}
+
+pub fn validate_optional_iso2(code: &str) -> Result<(), ValidationError> {
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(ValidationError::new("country"));
+    }
+    Ok(())
+}
+
+pub fn validate_city_limit(limit: i64) -> Result<(), ValidationError> {
+    if !(1..=50).contains(&limit) {
+        return Err(ValidationError::new("limit"));
+    }
+    Ok(())
+}
+
+pub fn validate_min_population(value: i64) -> Result<(), ValidationError> {
+    if value < 0 {
+        return Err(ValidationError::new("min_population"));
+    }
+    Ok(())
+}
+
+pub fn validate_boundary_vintage(vintage: i32) -> Result<(), ValidationError> {
+    if !(1900..=2100).contains(&vintage) {
+        return Err(ValidationError::new("boundaries"));
+    }
+    Ok(())
+}
+
+pub fn validate_population_year(year: i32) -> Result<(), ValidationError> {
+    if !(2000..=2100).contains(&year) {
+        return Err(ValidationError::new("year"));
+    }
+    Ok(())
+}
+
+pub fn validate_continent_field(continent: &str) -> Result<(), ValidationError> {
+    let normalized = continent.trim().to_lowercase();
+    if normalized.is_empty() || !VALID_CONTINENTS.contains(&normalized.as_str()) {
+        return Err(ValidationError::new("continent"));
+    }
+    Ok(())
+}
+
+/// Canonical spelling of `input` among `names`, ignoring case and surrounding
+/// whitespace.
+pub fn canonical_name(names: &[&'static str], input: &str) -> Option<&'static str> {
+    let input = input.trim();
+    names.iter().copied().find(|name| name.eq_ignore_ascii_case(input))
+}
+
+pub fn validate_region_field(region: &str) -> Result<(), ValidationError> {
+    if canonical_name(VALID_REGIONS, region).is_none() {
+        return Err(ValidationError::new("region"));
+    }
+    Ok(())
+}
+
+pub fn validate_subregion_field(subregion: &str) -> Result<(), ValidationError> {
+    if canonical_name(VALID_SUBREGIONS, subregion).is_none() {
+        return Err(ValidationError::new("subregion"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_language_lists() {
+        assert_eq!(parse_languages("ta, EN"), Some(vec!["ta".into(), "en".into()]));
+        assert_eq!(parse_languages("zh-TW"), Some(vec!["zh-tw".into()]));
+        for raw in ["", "t", "tamil", "ta,", "ta;q=1", "*", "en-", "1a", "aa,ab,ac,ad,ae,af,ag,ah,ai"] {
+            assert_eq!(parse_languages(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_ascending_rings() {
+        assert_eq!(parse_rings("5, 10,25,50"), Some(vec![5.0, 10.0, 25.0, 50.0]));
+        assert_eq!(parse_rings("2.5"), Some(vec![2.5]));
+    }
+
+    #[test]
+    fn rejects_bad_rings() {
+        for raw in ["", "10,5", "5,5", "0,10", "5,abc", "5,600", "1,2,3,4,5,6,7,8,9,10,11"] {
+            assert_eq!(parse_rings(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_bbox_viewports() {
+        assert_eq!(parse_bbox("79.8, 6.8,80.0,7.0"), Some([79.8, 6.8, 80.0, 7.0]));
+        for raw in ["", "79.8,6.8,80.0", "80.0,6.8,79.8,7.0", "0,0,20,1", "179,0,181,1", "a,0,1,1"] {
+            assert_eq!(parse_bbox(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_export_layers() {
+        assert_eq!(parse_layers("places, population"), Some(vec![ExportLayer::Places, ExportLayer::Population]));
+        for raw in ["", "places,", "people", "places,places"] {
+            assert_eq!(parse_layers(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_facility_type_lists() {
+        assert_eq!(parse_facility_types("school, hospital"), Some(vec![FacilityType::School, FacilityType::Hospital]));
+        for raw in ["", "hospital,", "clinic", "port,port"] {
+            assert_eq!(parse_facility_types(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn parses_lat_lon_pairs() {
+        assert_eq!(parse_lat_lon("6.9271, 79.8612"), Some((6.9271, 79.8612)));
+        for raw in ["", "6.9", "6.9,79.8,1", "91,0", "0,180", "a,b"] {
+            assert_eq!(parse_lat_lon(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn rejects_internal_callback_urls() {
+        for url in ["https://hooks.example.com/x", "http://93.184.216.34:8080/", "https://[2606:4700::1111]/"] {
+            assert!(validate_callback_url(url).is_ok(), "{url}");
+        }
+        for url in [
+            "ftp://hooks.example.com/",
+            "http://localhost:8080/",
+            "http://api.LOCALHOST./",
+            "http://127.0.0.1/",
+            "http://10.1.2.3/",
+            "http://172.16.0.1/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(validate_callback_url(url).is_err(), "{url}");
+        }
+    }
+}
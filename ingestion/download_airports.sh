@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+DATA_DIR="$(cd "$(dirname "$0")/.." && pwd)/data/airports"
+mkdir -p "$DATA_DIR"
+
+# OurAirports publishes nightly CSV snapshots (public domain).
+BASE_URL="https://davidmegginson.github.io/ourairports-data"
+FILES=("airports.csv" "runways.csv")
+
+for FILE in "${FILES[@]}"; do
+    if [ -f "$DATA_DIR/$FILE" ]; then
+        echo "Already exists: $DATA_DIR/$FILE"
+        continue
+    fi
+    echo "Downloading $FILE..."
+    curl -L --progress-bar --retry 3 --retry-delay 5 -o "$DATA_DIR/$FILE" "$BASE_URL/$FILE"
+    echo "Downloaded: $DATA_DIR/$FILE ($(du -h "$DATA_DIR/$FILE" | cut -f1))"
+done
+
+echo "OurAirports data ready in $DATA_DIR"
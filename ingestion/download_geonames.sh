@@ -5,7 +5,7 @@ DATA_DIR="$(cd "$(dirname "$0")/.." && pwd)/data/geonames"
 mkdir -p "$DATA_DIR"
 
 BASE_URL="https://download.geonames.org/export/dump"
-FILES=("allCountries.zip" "admin1CodesASCII.txt" "admin2Codes.txt" "countryInfo.txt")
+FILES=("allCountries.zip" "admin1CodesASCII.txt" "admin2Codes.txt" "countryInfo.txt" "alternateNamesV2.zip")
 
 for FILE in "${FILES[@]}"; do
     if [ -f "$DATA_DIR/$FILE" ]; then
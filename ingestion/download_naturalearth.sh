@@ -4,17 +4,21 @@ set -euo pipefail
 DATA_DIR="$(cd "$(dirname "$0")/.." && pwd)/data/naturalearth"
 mkdir -p "$DATA_DIR"
 
-URL="https://naciscdn.org/naturalearth/10m/cultural/ne_10m_admin_0_countries.zip"
-SHP="ne_10m_admin_0_countries.shp"
+BASE="https://naciscdn.org/naturalearth/10m/cultural"
 
-if [ -f "$DATA_DIR/$SHP" ]; then
-    echo "Already exists: $DATA_DIR/$SHP"
-    exit 0
-fi
+# fetch <layer> <description>
+fetch() {
+    local layer="$1" what="$2"
+    if [ -f "$DATA_DIR/$layer.shp" ]; then
+        echo "Already exists: $DATA_DIR/$layer.shp"
+        return
+    fi
+    echo "Downloading Natural Earth 10m $what..."
+    curl -L --progress-bar --retry 3 --retry-delay 5 -o "$DATA_DIR/$layer.zip" "$BASE/$layer.zip"
+    echo "Extracting..."
+    unzip -o "$DATA_DIR/$layer.zip" -d "$DATA_DIR"
+}
 
-echo "Downloading Natural Earth 10m countries (~5 MB)..."
-curl -L --progress-bar --retry 3 --retry-delay 5 -o "$DATA_DIR/ne_10m_admin_0_countries.zip" "$URL"
-
-echo "Extracting..."
-unzip -o "$DATA_DIR/ne_10m_admin_0_countries.zip" -d "$DATA_DIR"
+fetch ne_10m_admin_0_countries "countries (~5 MB)"
+fetch ne_10m_admin_1_states_provinces "states and provinces (~15 MB)"
 echo "Natural Earth data ready in $DATA_DIR"
@@ -4,17 +4,34 @@ set -euo pipefail
 DATA_DIR="$(cd "$(dirname "$0")/.." && pwd)/data"
 mkdir -p "$DATA_DIR"
 
-# WorldPop unconstrained + UN-adjusted 1km (~723 MB)
-# Distributes population across all land, not just detected settlement footprints.
-URL="https://data.worldpop.org/GIS/Population/Global_2015_2030/R2024B/2025/0_Mosaicked/v1/1km_ua/unconstrained/global_pop_2025_UC_1km_R2024B_UA_v1.tif"
-FILENAME="global_pop_2025_UC_1km_R2024B_UA_v1.tif"
+# Usage: download_worldpop.sh [unconstrained|constrained] [year]
+# R2024B publishes every year from 2015 to 2030.
+DATASET="${1:-unconstrained}"
+YEAR="${2:-2025}"
+BASE_URL="https://data.worldpop.org/GIS/Population/Global_2015_2030/R2024B/$YEAR/0_Mosaicked/v1/1km_ua"
+
+case "$DATASET" in
+    # WorldPop unconstrained + UN-adjusted 1km (~723 MB)
+    # Distributes population across all land, not just detected settlement footprints.
+    unconstrained) TAG="UC" ;;
+    # WorldPop constrained + UN-adjusted 1km
+    # Places population only in cells with mapped building footprints.
+    constrained) TAG="CN" ;;
+    *)
+        echo "ERROR: unknown dataset '$DATASET' (expected unconstrained or constrained)"
+        exit 1
+        ;;
+esac
+
+FILENAME="global_pop_${YEAR}_${TAG}_1km_R2024B_UA_v1.tif"
+URL="$BASE_URL/$DATASET/$FILENAME"
 
 if [ -f "$DATA_DIR/$FILENAME" ]; then
     echo "Already exists: $DATA_DIR/$FILENAME"
     exit 0
 fi
 
-echo "Downloading WorldPop R2024B 2025 unconstrained UN-adjusted 1km (~723 MB)..."
+echo "Downloading WorldPop R2024B $YEAR $DATASET UN-adjusted 1km..."
 
 MAX_RETRIES=3
 for i in $(seq 1 $MAX_RETRIES); do
@@ -3,10 +3,28 @@
 
 Reads the raster row-by-row, maps each pixel to a canonical 30 arc-second
 cell_id (matching the Rust API and SQL function), and streams to PostgreSQL
-via COPY for maximum throughput.
+via COPY for maximum throughput, then refreshes the precomputed rollups
+behind /population/rank and /country/{iso3}/population.
+
+The unconstrained grid loads into `population`. With --dataset constrained
+the constrained grid loads into `population_constrained` instead, served by
+`dataset=constrained`; the rollups are always computed from the unconstrained
+grid, so they are left alone.
+
+Loading the latest year also rebuilds the dataset's exposure rollups
+(population_rollups), which large-radius /exposure sums read.
+
+Each load is recorded in population_years. The latest year of a dataset sits
+in its main table; loading a newer year moves the previous one into
+population_history, and loading an older year writes it there directly, where
+`year=` reads it. The year comes from the file name unless --year is given:
+
+  python ingest.py                           # latest data/global_pop_*_UC_1km_*.tif
+  python ingest.py --year 2020               # data/global_pop_2020_UC_1km_*.tif
+  python ingest.py --dataset constrained     # latest data/global_pop_*_CN_1km_*.tif
 """
 
-import os, sys, time, io
+import argparse, os, re, sys, time, io
 import numpy as np
 import rasterio
 import psycopg
@@ -15,6 +33,12 @@ NCOLS = 43200   # 360° × 120
 NROWS = 21600   # 180° × 120
 BATCH_SIZE = 500_000
 
+# Target table and raster filename tag per WorldPop dataset.
+DATASETS = {
+    "unconstrained": ("population", "_UC_"),
+    "constrained": ("population_constrained", "_CN_"),
+}
+
 
 def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
     for attempt in range(retries):
@@ -38,18 +62,32 @@ def get_db_url() -> str:
     return f"postgresql://{u}:{p}@{h}:{port}/{db}"
 
 
-def find_tif() -> str:
+def tif_year(path: str) -> int | None:
+    match = re.search(r"global_pop_(\d{4})_", os.path.basename(path))
+    return int(match.group(1)) if match else None
+
+
+def find_tif(dataset: str, year: int | None = None) -> str:
+    """The dataset's raster for `year`, or its latest year when not given."""
+    _, tag = DATASETS[dataset]
     data_dir = os.path.join(os.path.dirname(__file__), "..", "data")
+    matches = []
     if os.path.isdir(data_dir):
         for f in os.listdir(data_dir):
-            if f.endswith(".tif") and "global_pop_" in f and "1km" in f:
-                return os.path.join(data_dir, f)
-    print("ERROR: No WorldPop .tif found in data/. Run: make download-worldpop")
+            if f.endswith(".tif") and "global_pop_" in f and "1km" in f and tag in f:
+                if year is None or tif_year(f) == year:
+                    matches.append(os.path.join(data_dir, f))
+    if matches:
+        return max(matches, key=lambda f: tif_year(f) or 0)
+    target = "download-worldpop" if dataset == "unconstrained" else "download-worldpop-constrained"
+    wanted = f"{dataset} {year}" if year else dataset
+    print(f"ERROR: No WorldPop {wanted} .tif found in data/. Run: make {target}")
     sys.exit(1)
 
 
-def ingest(tif_path: str, db_url: str) -> None:
-    print(f"Opening raster: {tif_path}")
+def ingest(tif_path: str, db_url: str, dataset: str = "unconstrained", year: int = 2025) -> None:
+    main_table, _ = DATASETS[dataset]
+    print(f"Opening raster: {tif_path} ({dataset} {year})")
 
     with rasterio.open(tif_path) as src:
         nodata = src.nodata
@@ -66,9 +104,28 @@ def ingest(tif_path: str, db_url: str) -> None:
         conn.autocommit = False
 
         with conn.cursor() as cur:
-            cur.execute("TRUNCATE population")
+            cur.execute("SELECT max(year) FROM population_years WHERE dataset = %s", (dataset,))
+            latest = cur.fetchone()[0]
+            if latest is None or year >= latest:
+                # Becomes (or stays) the latest year: keep the previous one queryable.
+                table, prefix = main_table, ""
+                if latest is not None and year > latest:
+                    cur.execute(
+                        f"INSERT INTO population_history (dataset, year, cell_id, pop) "
+                        f"SELECT %s, %s, cell_id, pop FROM {main_table}",
+                        (dataset, latest),
+                    )
+                    print(f"Moved {latest} into population_history.")
+                cur.execute(f"TRUNCATE {main_table}")
+            else:
+                table, prefix = "population_history", f"{dataset}\t{year}\t"
+                _register(cur, dataset, year)
+                cur.execute(
+                    "DELETE FROM population_history WHERE dataset = %s AND year = %s",
+                    (dataset, year),
+                )
         conn.commit()
-        print("Truncated population table.")
+        print(f"Loading into {table}.")
 
         total = skipped_oob = skipped_dup = 0
         start = time.time()
@@ -102,11 +159,11 @@ def ingest(tif_path: str, db_url: str) -> None:
                     continue
                 seen.add(cell_id)
 
-                buf.write(f"{cell_id}\t{pop_val:.6g}\n")
+                buf.write(f"{prefix}{cell_id}\t{pop_val:.6g}\n")
                 buf_count += 1
 
                 if buf_count >= BATCH_SIZE:
-                    _flush(conn, buf, buf_count)
+                    _flush(conn, table, buf, bool(prefix))
                     total += buf_count
                     buf, buf_count = io.StringIO(), 0
 
@@ -117,31 +174,68 @@ def ingest(tif_path: str, db_url: str) -> None:
                 print(f"  Row {row_idx+1}/{src.height} ({pct:.1f}%) — {total:,} rows — {rate:,.0f}/s")
 
         if buf_count > 0:
-            _flush(conn, buf, buf_count)
+            _flush(conn, table, buf, bool(prefix))
             total += buf_count
 
         elapsed = time.time() - start
         print(f"\nDone: {total:,} rows in {elapsed:.1f}s ({total/elapsed:,.0f}/s)")
         print(f"Skipped: {skipped_oob:,} out-of-bounds, {skipped_dup:,} duplicates")
 
+        with conn.cursor() as cur:
+            _register(cur, dataset, year)
+        conn.commit()
+
         print("Running VACUUM ANALYZE...")
         conn.autocommit = True
         with conn.cursor() as cur:
-            cur.execute("VACUUM ANALYZE population")
+            cur.execute(f"VACUUM ANALYZE {table}")
+            if table == main_table:
+                print("Refreshing exposure rollups...")
+                cur.execute("SELECT refresh_population_rollups(%s)", (dataset,))
+        if dataset != "unconstrained" or table != main_table:
+            conn.close()
+            print("Complete.")
+            return
+        # Country percentiles use the current boundary vintage, so load
+        # Natural Earth first (as `make ingest-all` does).
+        print("Refreshing density percentiles and country totals...")
+        with conn.cursor() as cur:
+            cur.execute("SELECT refresh_density_percentiles()")
+            cur.execute("SELECT refresh_country_population()")
         conn.close()
         print("Complete.")
 
 
-def _flush(conn, buf: io.StringIO, count: int) -> None:
+def _register(cur, dataset: str, year: int) -> None:
+    cur.execute(
+        "INSERT INTO population_years (dataset, year) VALUES (%s, %s) "
+        "ON CONFLICT (dataset, year) DO UPDATE SET loaded_at = now()",
+        (dataset, year),
+    )
+
+
+def _flush(conn, table: str, buf: io.StringIO, history: bool) -> None:
+    columns = "dataset, year, cell_id, pop" if history else "cell_id, pop"
     buf.seek(0)
     with conn.cursor() as cur:
-        with cur.copy("COPY population (cell_id, pop) FROM STDIN") as copy:
+        with cur.copy(f"COPY {table} ({columns}) FROM STDIN") as copy:
             copy.write(buf.read())
     conn.commit()
 
 
 if __name__ == "__main__":
-    tif = find_tif()
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--dataset", choices=sorted(DATASETS), default="unconstrained",
+                        help="WorldPop grid to load (default: unconstrained)")
+    parser.add_argument("--year", type=int,
+                        help="grid year to load (default: the latest in data/, as named by the file)")
+    args = parser.parse_args()
+
+    tif = find_tif(args.dataset, args.year)
+    year = args.year or tif_year(tif)
+    if year is None:
+        print(f"ERROR: Cannot tell the year of {tif}; pass --year.")
+        sys.exit(1)
     url = get_db_url()
     print(f"Database: {url.split('@')[1] if '@' in url else url}")
-    ingest(tif, url)
+    ingest(tif, url, args.dataset, year)
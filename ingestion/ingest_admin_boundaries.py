@@ -0,0 +1,164 @@
+#!/usr/bin/env python3
+"""Ingest admin boundary polygons into PostgreSQL.
+
+Loads first- or second-level administrative areas into the admin_boundaries
+table behind `/reverse?mode=contains`. Each load is tagged with a boundary
+vintage (default: the current one) and replaces the previous layer of the same
+level and vintage, so older vintages stay queryable via `?boundaries=YYYY`.
+The vintage must already exist, i.e. countries were loaded for it by
+ingest_naturalearth.py.
+
+Level 1 defaults to Natural Earth 10m admin-1 states and provinces, whose
+`gn_a1_code` is already the GeoNames admin1 code. Any other layer works if it
+has a field with GeoNames admin codes (`LK.36`, `LK.36.1234`); without one,
+pass only --name-field and the layer's own names are used untranslated.
+
+  python ingest_admin_boundaries.py                     # data/naturalearth/ne_10m_admin_1_states_provinces.shp
+  python ingest_admin_boundaries.py --level 2 --code-field gn_a2_code --name-field name path/to/adm2.shp
+  python ingest_admin_boundaries.py --vintage 2023 path/to/ne_2023_admin_1.shp
+"""
+
+import argparse, os, sys, time
+import fiona
+import psycopg
+from shapely.geometry import shape, MultiPolygon
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_shapefile(path: str | None, level: int) -> str:
+    if path:
+        if os.path.exists(path):
+            return path
+        print(f"ERROR: Shapefile not found: {path}")
+        sys.exit(1)
+    if level != 1:
+        print("ERROR: Level 2 has no default layer; pass the shapefile to load.")
+        sys.exit(1)
+    shp = os.path.join(os.path.dirname(__file__), "..", "data", "naturalearth",
+                       "ne_10m_admin_1_states_provinces.shp")
+    if os.path.exists(shp):
+        return shp
+    print("ERROR: Shapefile not found. Run: make download-naturalearth")
+    sys.exit(1)
+
+
+def field(p: dict, name: str | None) -> str | None:
+    """Property `name`, matched case-insensitively; None when blank or a placeholder."""
+    if not name:
+        return None
+    for key, value in p.items():
+        if key.lower() == name.lower():
+            value = None if value is None else str(value).strip()
+            return None if value in (None, "", "-99", "-1") else value
+    return None
+
+
+def resolve_vintage(conn: psycopg.Connection, requested: int | None) -> int:
+    """The vintage to tag the load with: `requested`, or the current one when omitted. Exits if it is not loaded."""
+    with conn.cursor() as cur:
+        if requested is None:
+            cur.execute("SELECT current_boundary_vintage()")
+        else:
+            cur.execute("SELECT vintage FROM boundary_vintages WHERE vintage = %s", (requested,))
+        row = cur.fetchone()
+    if row is None or row[0] is None:
+        if requested is None:
+            print("ERROR: No boundary vintage is current. Run ingest_naturalearth.py first.")
+        else:
+            print(f"ERROR: Boundary vintage {requested} is not loaded. "
+                  f"Run ingest_naturalearth.py --vintage {requested} first.")
+        sys.exit(1)
+    return row[0]
+
+
+def ingest(shp_path: str, db_url: str, level: int, code_field: str | None, name_field: str,
+           vintage: int | None) -> None:
+    conn = connect(db_url)
+    conn.autocommit = False
+    vintage = resolve_vintage(conn, vintage)
+    print(f"Opening shapefile: {shp_path} (admin level {level}, boundary vintage {vintage})")
+
+    count = skipped = uncoded = 0
+    insert_sql = """
+        INSERT INTO admin_boundaries (vintage, level, country_code, code, name, source, geom)
+        VALUES (%s, %s, %s, %s, %s, %s, ST_MakeValid(ST_GeomFromEWKT(%s)))
+    """
+    source = os.path.basename(shp_path)
+
+    with conn.cursor() as cur, fiona.open(shp_path) as src:
+        print(f"Features: {len(src)}, CRS: {src.crs}")
+        cur.execute("DELETE FROM admin_boundaries WHERE level = %s AND vintage = %s", (level, vintage))
+        for feature in src:
+            p = feature["properties"]
+            name = field(p, name_field)
+            if not name or feature["geometry"] is None:
+                skipped += 1
+                continue
+
+            geom = shape(feature["geometry"])
+            if geom.geom_type == "Polygon":
+                geom = MultiPolygon([geom])
+            elif geom.geom_type != "MultiPolygon":
+                skipped += 1
+                continue
+
+            # GeoNames codes have one dot-separated part per level below the country.
+            code = field(p, code_field)
+            if code and code.count(".") != level:
+                code = None
+            uncoded += code is None
+            country_code = code.split(".")[0] if code else field(p, "iso_a2")
+
+            cur.execute(insert_sql, (vintage, level, country_code, code, name, source, f"SRID=4326;{geom.wkt}"))
+            count += 1
+            if count % 500 == 0:
+                print(f"  {count} areas...")
+
+    # One transaction, so /reverse keeps answering from the old layer until now.
+    conn.commit()
+    print(f"Loaded {count} level-{level} areas as vintage {vintage} "
+          f"({skipped} skipped, {uncoded} without a GeoNames code).")
+
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE admin_boundaries")
+    conn.close()
+    print("Complete.")
+
+
+if __name__ == "__main__":
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("shapefile", nargs="?", help="shapefile to load (default for level 1: Natural Earth admin-1)")
+    parser.add_argument("--level", type=int, choices=(1, 2), default=1, help="admin level of the layer")
+    parser.add_argument("--code-field", help="field holding GeoNames admin codes (default for level 1: gn_a1_code)")
+    parser.add_argument("--name-field", default="name", help="field holding area names")
+    parser.add_argument("--vintage", type=int,
+                        help="boundary vintage to tag this load with (default: the current one)")
+    args = parser.parse_args()
+
+    shp = find_shapefile(args.shapefile, args.level)
+    code_field = args.code_field or ("gn_a1_code" if args.level == 1 else None)
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(shp, url, args.level, code_field, args.name_field, args.vintage)
@@ -0,0 +1,142 @@
+#!/usr/bin/env python3
+"""Ingest OurAirports airports and runways into PostgreSQL.
+
+Reads airports.csv and runways.csv from data/airports/ (run
+`make download-airports` first) and reloads the airports and airport_runways
+tables used by /airports/nearest. Closed airports and closed runways are
+skipped.
+
+Older snapshots have no icao_code column; for those the GPS code stands in
+when it is a four-letter ICAO-style code.
+"""
+
+import csv, os, re, sys, time, io
+import psycopg
+
+AIRPORT_COLUMNS = (
+    "airport_id, ident, airport_type, name, latitude, longitude, elevation_ft, "
+    "country_code, municipality, scheduled_service, icao_code, iata_code, geom"
+)
+RUNWAY_COLUMNS = "runway_id, airport_id, length_ft, width_ft, surface, lighted, le_ident, he_ident"
+ICAO_CODE = re.compile(r"^[A-Z]{4}$")
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def _text(value: str | None) -> str:
+    """COPY text field: NULL when empty, tabs and newlines flattened."""
+    value = (value or "").strip().replace("\t", " ").replace("\n", " ")
+    return value or r"\N"
+
+
+def _int(value: str | None) -> str:
+    value = (value or "").strip()
+    try:
+        return str(int(float(value)))
+    except ValueError:
+        return r"\N"
+
+
+def _airport_row(row: dict) -> str | None:
+    """Format one airports.csv record as a COPY line, or None if closed or unusable."""
+    if row["type"] == "closed":
+        return None
+    try:
+        lat, lon = float(row["latitude_deg"]), float(row["longitude_deg"])
+    except ValueError:
+        return None
+
+    icao = (row.get("icao_code") or "").strip()
+    if "icao_code" not in row and ICAO_CODE.match(row.get("gps_code") or ""):
+        icao = row["gps_code"]
+    scheduled = "t" if row.get("scheduled_service") == "yes" else "f"
+    return (
+        f"{row['id']}\t{_text(row['ident'])}\t{row['type']}\t{_text(row['name'])}\t{lat}\t{lon}\t"
+        f"{_int(row.get('elevation_ft'))}\t{_text(row.get('iso_country'))}\t{_text(row.get('municipality'))}\t"
+        f"{scheduled}\t{_text(icao)}\t{_text(row.get('iata_code'))}\tSRID=4326;POINT({lon} {lat})\n"
+    )
+
+
+def _runway_row(row: dict, airport_ids: set[str]) -> str | None:
+    """Format one runways.csv record as a COPY line, or None if closed or its airport was skipped."""
+    if row.get("closed") == "1" or row["airport_ref"] not in airport_ids:
+        return None
+    lighted = "t" if row.get("lighted") == "1" else "f"
+    return (
+        f"{row['id']}\t{row['airport_ref']}\t{_int(row.get('length_ft'))}\t{_int(row.get('width_ft'))}\t"
+        f"{_text(row.get('surface'))}\t{lighted}\t{_text(row.get('le_ident'))}\t{_text(row.get('he_ident'))}\n"
+    )
+
+
+def _copy(conn, table: str, columns: str, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        with cur.copy(f"COPY {table} ({columns}) FROM STDIN") as copy:
+            copy.write(buf.read())
+
+
+def ingest(data_dir: str, db_url: str) -> None:
+    start = time.time()
+    airports, airport_ids = io.StringIO(), set()
+    with open(os.path.join(data_dir, "airports.csv"), newline="", encoding="utf-8") as f:
+        for row in csv.DictReader(f):
+            line = _airport_row(row)
+            if line is not None:
+                airports.write(line)
+                airport_ids.add(row["id"])
+
+    runways, runway_count = io.StringIO(), 0
+    with open(os.path.join(data_dir, "runways.csv"), newline="", encoding="utf-8") as f:
+        for row in csv.DictReader(f):
+            line = _runway_row(row, airport_ids)
+            if line is not None:
+                runways.write(line)
+                runway_count += 1
+
+    conn = connect(db_url)
+    with conn.cursor() as cur:
+        cur.execute("TRUNCATE airports CASCADE")
+    _copy(conn, "airports", AIRPORT_COLUMNS, airports)
+    _copy(conn, "airport_runways", RUNWAY_COLUMNS, runways)
+    conn.commit()
+    print(f"  airports: {len(airport_ids):,} rows")
+    print(f"  airport_runways: {runway_count:,} rows")
+    print(f"\nDone in {time.time() - start:.1f}s")
+
+    print("Running VACUUM ANALYZE...")
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        for t in ("airports", "airport_runways"):
+            cur.execute(f"VACUUM ANALYZE {t}")
+    conn.close()
+    print("Complete.")
+
+
+if __name__ == "__main__":
+    data_dir = os.path.join(os.path.dirname(__file__), "..", "data", "airports")
+    if not os.path.exists(os.path.join(data_dir, "airports.csv")):
+        print(f"ERROR: {data_dir}/airports.csv not found. Run: make download-airports")
+        sys.exit(1)
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(data_dir, url)
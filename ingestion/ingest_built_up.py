@@ -0,0 +1,140 @@
+#!/usr/bin/env python3
+"""Ingest the GHSL built-up surface (GHS-BUILT-S) into PostgreSQL.
+
+Reads the global GHS-BUILT-S grid in its WGS84 30 arc-second edition (e.g.
+GHS_BUILT_S_E2020_GLOBE_R2023A_4326_30ss) from data/built_up/built_s.tif and
+stores the built-up surface, in m², of every cell that has any in
+cell_built_up, keyed by the canonical cell_id used by the population grid.
+
+Unlike ingest_settlement.py it does not filter on the population table:
+industrial estates, ports and depots are built up but often unpopulated.
+"""
+
+import os, sys, time, io
+import numpy as np
+import rasterio
+import psycopg
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+BAND_ROWS = 480
+BATCH_SIZE = 500_000
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_built_up() -> str:
+    path = os.path.join(os.path.dirname(__file__), "..", "data", "built_up", "built_s.tif")
+    if not os.path.exists(path):
+        print("ERROR: data/built_up/built_s.tif not found. Download GHS-BUILT-S (WGS84, 30 arc-second)")
+        print("       from https://ghsl.jrc.ec.europa.eu/download.php?ds=bu and place it there.")
+        sys.exit(1)
+    return path
+
+
+def ingest(built_path: str, db_url: str) -> None:
+    print(f"Opening raster: {built_path}")
+
+    with rasterio.open(built_path) as src:
+        t = src.transform
+        if abs(t.a - 1 / 120) > 1e-6 or abs(t.e + 1 / 120) > 1e-6:
+            print(f"ERROR: expected 30 arc-second pixels, got {t.a:.8f} x {t.e:.8f} degrees")
+            print("       Use the EPSG:4326 30ss edition of GHS-BUILT-S, not the Mollweide one.")
+            sys.exit(1)
+        print(f"Raster: {src.width}x{src.height}, CRS={src.crs}, NoData={src.nodata}")
+
+        row_lats = t.f + (np.arange(src.height) + 0.5) * t.e
+        canonical_rows = np.floor((90.0 - row_lats) * 120.0).astype(np.int64)
+        col_lons = t.c + (np.arange(src.width) + 0.5) * t.a
+        canonical_cols = np.floor((col_lons + 180.0) * 120.0).astype(np.int64)
+        valid_cols = (canonical_cols >= 0) & (canonical_cols < NCOLS)
+
+        conn = connect(db_url)
+        conn.autocommit = False
+        with conn.cursor() as cur:
+            cur.execute("TRUNCATE cell_built_up")
+        conn.commit()
+        print("Truncated cell_built_up table.")
+
+        total = 0
+        start = time.time()
+        buf, buf_count = io.StringIO(), 0
+
+        for band_start in range(0, src.height, BAND_ROWS):
+            band_end = min(band_start + BAND_ROWS, src.height)
+            window = rasterio.windows.Window(0, band_start, src.width, band_end - band_start)
+            built = src.read(1, window=window).astype(np.float32)
+
+            rows = canonical_rows[band_start:band_end]
+            rows_ok = (rows >= 0) & (rows < NROWS)
+            keep = rows_ok[:, np.newaxis] & valid_cols[np.newaxis, :] & np.isfinite(built) & (built > 0)
+            if src.nodata is not None:
+                keep &= built != src.nodata
+            cell_ids = rows[:, np.newaxis] * NCOLS + canonical_cols[np.newaxis, :]
+            for r, c in zip(*np.nonzero(keep)):
+                buf.write(f"{cell_ids[r, c]}\t{built[r, c]:.0f}\n")
+                buf_count += 1
+
+            if buf_count >= BATCH_SIZE:
+                _flush(conn, buf)
+                total += buf_count
+                buf, buf_count = io.StringIO(), 0
+
+            elapsed = time.time() - start
+            pct = band_end / src.height * 100
+            print(f"  Row {band_end}/{src.height} ({pct:.1f}%) — {total + buf_count:,} built-up cells — {elapsed:.0f}s")
+
+        if buf_count > 0:
+            _flush(conn, buf)
+            total += buf_count
+
+        print(f"\nDone: {total:,} built-up cells in {time.time() - start:.1f}s")
+
+        print("Running VACUUM ANALYZE...")
+        conn.autocommit = True
+        with conn.cursor() as cur:
+            cur.execute("VACUUM ANALYZE cell_built_up")
+        conn.close()
+        print("Complete.")
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    """COPY into a staging table, then upsert — rasters that wrap the antimeridian repeat cells."""
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE built_up_stage (LIKE cell_built_up) ON COMMIT DROP")
+        with cur.copy("COPY built_up_stage (cell_id, built_m2) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_built_up (cell_id, built_m2)
+            SELECT DISTINCT ON (cell_id) cell_id, built_m2 FROM built_up_stage
+            ON CONFLICT (cell_id) DO UPDATE SET built_m2 = EXCLUDED.built_m2
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    built = find_built_up()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(built, url)
@@ -0,0 +1,135 @@
+#!/usr/bin/env python3
+"""Ingest Marine Regions World EEZ boundaries into PostgreSQL.
+
+Loads the exclusive economic zones, with the territories and sovereign states
+claiming each (up to three for overlapping claims and joint regimes), into the
+eez table behind /eez. Each load replaces the previous one.
+
+Marine Regions requires accepting its licence before downloading, so fetch the
+"World EEZ" shapefile (v12 or later) from https://www.marineregions.org/downloads.php
+by hand and unzip it into data/eez/, or pass the .shp path:
+
+  python ingest_eez.py                                  # data/eez/eez_v12.shp
+  python ingest_eez.py path/to/eez_v12.shp
+"""
+
+import argparse, glob, os, sys, time
+import fiona
+import psycopg
+from shapely.geometry import shape, MultiPolygon
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_shapefile(path: str | None = None) -> str:
+    if path:
+        if os.path.exists(path):
+            return path
+        print(f"ERROR: Shapefile not found: {path}")
+        sys.exit(1)
+    data_dir = os.path.join(os.path.dirname(__file__), "..", "data", "eez")
+    matches = sorted(glob.glob(os.path.join(data_dir, "**", "eez_v*.shp"), recursive=True))
+    if matches:
+        return matches[-1]
+    print("ERROR: No eez_v*.shp under data/eez/. Download the World EEZ shapefile from")
+    print("       https://www.marineregions.org/downloads.php and unzip it there.")
+    sys.exit(1)
+
+
+def iso(value) -> str | None:
+    return None if value in (None, "", "-99", "-1") else str(value).strip()
+
+
+def claimants(p: dict) -> tuple[list, list, list, list]:
+    """Parallel territory / sovereign arrays for the (up to three) claimants."""
+    territories, territory_iso3, sovereigns, sovereign_iso3 = [], [], [], []
+    for n in (1, 2, 3):
+        territory = p.get(f"TERRITORY{n}")
+        if not territory:
+            continue
+        territories.append(territory)
+        territory_iso3.append(iso(p.get(f"ISO_TER{n}")))
+        sovereigns.append(p.get(f"SOVEREIGN{n}") or territory)
+        sovereign_iso3.append(iso(p.get(f"ISO_SOV{n}")))
+    return territories, territory_iso3, sovereigns, sovereign_iso3
+
+
+def ingest(shp_path: str, db_url: str) -> None:
+    print(f"Opening shapefile: {shp_path}")
+    conn = connect(db_url)
+    conn.autocommit = False
+
+    count = skipped = 0
+    insert_sql = """
+        INSERT INTO eez (mrgid, name, pol_type, territories, territory_iso3,
+            sovereigns, sovereign_iso3, area_km2, geom)
+        VALUES (%s, %s, %s, %s, %s, %s, %s, %s, ST_GeomFromEWKT(%s))
+    """
+
+    with conn.cursor() as cur, fiona.open(shp_path) as src:
+        print(f"Features: {len(src)}, CRS: {src.crs}")
+        cur.execute("TRUNCATE eez")
+        for feature in src:
+            p = feature["properties"]
+            mrgid, name = p.get("MRGID"), p.get("GEONAME")
+            territories, territory_iso3, sovereigns, sovereign_iso3 = claimants(p)
+            if mrgid is None or not name or not territories or feature["geometry"] is None:
+                skipped += 1
+                continue
+
+            geom = shape(feature["geometry"])
+            if geom.geom_type == "Polygon":
+                geom = MultiPolygon([geom])
+            elif geom.geom_type != "MultiPolygon":
+                skipped += 1
+                continue
+
+            cur.execute(insert_sql, (
+                int(mrgid), name, p.get("POL_TYPE") or "200NM",
+                territories, territory_iso3, sovereigns, sovereign_iso3,
+                p.get("AREA_KM2"), f"SRID=4326;{geom.wkt}",
+            ))
+            count += 1
+            if count % 50 == 0:
+                print(f"  {count} zones...")
+
+    # One transaction, so /eez keeps answering from the old load until now.
+    conn.commit()
+    print(f"Loaded {count} zones ({skipped} skipped).")
+
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE eez")
+    conn.close()
+    print("Complete.")
+
+
+if __name__ == "__main__":
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("shapefile", nargs="?", help="shapefile to load (default: data/eez/eez_v*.shp)")
+    args = parser.parse_args()
+
+    shp = find_shapefile(args.shapefile)
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(shp, url)
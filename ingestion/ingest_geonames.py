@@ -1,16 +1,48 @@
 #!/usr/bin/env python3
 """Ingest GeoNames data into PostgreSQL for reverse geocoding.
 
-Loads:
-- admin1CodesASCII.txt → admin1_codes
-- admin2Codes.txt      → admin2_codes
-- allCountries.zip     → geonames (filtered to feature_class='P' populated places)
+Subcommands:
+  full    (default) Truncate and reload everything from the dump files:
+            - admin1CodesASCII.txt → admin1_codes
+            - admin2Codes.txt      → admin2_codes
+            - countryInfo.txt      → country_info (capital, currency, languages, ...)
+            - allCountries.zip     → geonames (filtered to feature_class='P' populated places)
+                                     and facilities (hospitals, airports, ports, schools)
+            - alternateNamesV2.zip → alternate_names (names of loaded places, regions and countries)
+  update  Apply the GeoNames daily modification/deletion files incrementally.
+          Every date after the last applied one (see geonames_update_log) up to
+          yesterday is fetched into data/geonames/updates/ and applied in order.
+          Use --date YYYY-MM-DD to apply a single day.
 """
 
-import os, sys, time, io, zipfile
+import argparse, datetime, os, sys, time, io, urllib.error, urllib.request, zipfile
 import psycopg
 
 BATCH_SIZE = 100_000
+BASE_URL = "https://download.geonames.org/export/dump"
+
+GEONAMES_COLUMNS = (
+    "geonameid, name, latitude, longitude, "
+    "feature_code, country_code, admin1_code, admin2_code, "
+    "population, timezone, geom"
+)
+FACILITY_COLUMNS = "geonameid, name, latitude, longitude, feature_code, country_code, geom"
+# S-class feature codes served by /facilities/nearest: hospital, airport, port, school.
+FACILITY_CODES = {"HSP", "AIRP", "PRT", "SCH"}
+ALTERNATE_NAMES_COLUMNS = (
+    "alternatenameid, geonameid, isolanguage, name, "
+    "is_preferred, is_short, is_colloquial, is_historic"
+)
+# Whether alternate name `a` belongs to a feature whose names are kept: a loaded
+# place, or an admin region or country that /reverse names in addresses.
+NAMED_FEATURE = """
+    EXISTS (SELECT 1 FROM geonames g WHERE g.geonameid = a.geonameid)
+    OR EXISTS (SELECT 1 FROM admin1_codes r WHERE r.geonameid = a.geonameid)
+    OR EXISTS (SELECT 1 FROM admin2_codes r WHERE r.geonameid = a.geonameid)
+    OR EXISTS (SELECT 1 FROM country_info c WHERE c.geonameid = a.geonameid)
+"""
+# Pseudo-languages in alternateNames that are identifiers rather than names.
+SKIPPED_LANGUAGES = {"link", "wkdt", "post", "unlc", "iata", "icao", "faac", "tcid", "fr_1793"}
 
 
 def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
@@ -35,8 +67,8 @@ def get_db_url() -> str:
     return f"postgresql://{u}:{p}@{h}:{port}/{db}"
 
 
-def _load_tsv(conn, path: str, table: str, columns: str) -> int:
-    """Load a simple two-column TSV (code, name) into a lookup table."""
+def _load_tsv(conn, path: str, table: str) -> int:
+    """Load an admin code TSV (code, name, asciiname, geonameid) into a lookup table."""
     if not os.path.exists(path):
         print(f"  WARNING: {path} not found, skipping")
         return 0
@@ -53,86 +85,413 @@ def _load_tsv(conn, path: str, table: str, columns: str) -> int:
             code, name = parts[0].strip(), parts[1].strip()
             if not code or not name:
                 continue
-            buf.write(f"{code}\t{name.replace(chr(9), ' ').replace(chr(10), ' ')}\n")
+            gid = parts[3].strip() if len(parts) > 3 and parts[3].strip().isdigit() else r"\N"
+            buf.write(f"{code}\t{name.replace(chr(9), ' ').replace(chr(10), ' ')}\t{gid}\n")
             count += 1
 
     buf.seek(0)
     with conn.cursor() as cur:
         cur.execute(f"TRUNCATE {table}")
-        with cur.copy(f"COPY {table} ({columns}) FROM STDIN") as copy:
+        with cur.copy(f"COPY {table} (code, name, geonameid) FROM STDIN") as copy:
             copy.write(buf.read())
     conn.commit()
     print(f"  {table}: {count:,} rows")
     return count
 
 
-def _load_geonames(conn, zip_path: str) -> int:
-    """Stream allCountries.zip, filter to populated places, COPY into geonames."""
-    if not os.path.exists(zip_path):
-        print(f"ERROR: {zip_path} not found. Run: make download-geonames")
-        sys.exit(1)
+def _calling_code(raw: str) -> str | None:
+    """`94` -> `+94`; multi-code entries like `+1-809 and 1-829` get a `+` on each."""
+    codes = [c.strip().lstrip("+") for c in raw.split(" and ") if c.strip().lstrip("+")]
+    return " and ".join(f"+{c}" for c in codes) or None
+
+
+def _load_country_info(conn, path: str) -> int:
+    """Load countryInfo.txt (one row per ISO country) into country_info."""
+    if not os.path.exists(path):
+        print(f"  WARNING: {path} not found, skipping")
+        return 0
+
+    rows = []
+    with open(path, "r", encoding="utf-8") as f:
+        for line in f:
+            if line.startswith("#"):
+                continue
+            parts = line.rstrip("\n").split("\t")
+            if len(parts) < 16 or len(parts[1]) != 3:
+                continue
+            languages = [lang.strip() for lang in parts[15].split(",") if lang.strip()]
+            gid = parts[16].strip() if len(parts) > 16 else ""
+            rows.append((
+                parts[1], parts[0], parts[4], parts[5] or None, parts[10] or None,
+                parts[11] or None, _calling_code(parts[12]), parts[9] or None, languages,
+                int(gid) if gid.isdigit() else None,
+            ))
 
     with conn.cursor() as cur:
-        cur.execute("TRUNCATE geonames")
+        cur.execute("TRUNCATE country_info")
+        cur.executemany(
+            """INSERT INTO country_info (iso_a3, iso_a2, name, capital, currency_code,
+                   currency_name, calling_code, tld, languages, geonameid)
+               VALUES (%s, %s, %s, %s, %s, %s, %s, %s, %s, %s)""",
+            rows,
+        )
     conn.commit()
+    print(f"  country_info: {len(rows):,} rows")
+    return len(rows)
+
+
+def _geonames_row(parts: list[str]) -> str | None:
+    """Format one allCountries-style record as a COPY line, or None if not a populated place."""
+    if len(parts) < 19 or parts[6].strip() != "P":
+        return None
+
+    gid = parts[0].strip()
+    lat, lon = parts[4].strip(), parts[5].strip()
+    if not gid or not lat or not lon:
+        return None
+
+    name = parts[1].strip().replace("\t", " ").replace("\n", " ")
+    pop = parts[14].strip() or "0"
+    timezone = parts[17].strip() or r"\N"
+
+    return (
+        f"{gid}\t{name}\t{lat}\t{lon}\t"
+        f"{parts[7].strip()}\t{parts[8].strip()}\t{parts[10].strip()}\t{parts[11].strip()}\t"
+        f"{pop}\t{timezone}\tSRID=4326;POINT({lon} {lat})\n"
+    )
+
 
+def _facility_row(parts: list[str]) -> str | None:
+    """Format one allCountries-style record as a facilities COPY line, or None if not a facility."""
+    if len(parts) < 9 or parts[6].strip() != "S" or parts[7].strip() not in FACILITY_CODES:
+        return None
+
+    gid = parts[0].strip()
+    lat, lon = parts[4].strip(), parts[5].strip()
+    if not gid or not lat or not lon:
+        return None
+
+    name = parts[1].strip().replace("\t", " ").replace("\n", " ")
+    country = parts[8].strip() or r"\N"
+    return f"{gid}\t{name}\t{lat}\t{lon}\t{parts[7].strip()}\t{country}\tSRID=4326;POINT({lon} {lat})\n"
+
+
+def _alternate_name_row(parts: list[str]) -> str | None:
+    """Format one alternateNames record as a COPY line, or None if it is not a usable name."""
+    if len(parts) < 8:
+        return None
+
+    alt_id, gid, lang = parts[0].strip(), parts[1].strip(), parts[2].strip()
+    name = parts[3].strip().replace("\t", " ").replace("\n", " ")
+    if not alt_id or not gid or not name or lang in SKIPPED_LANGUAGES:
+        return None
+
+    lang = lang or r"\N"
+    flags = "\t".join("t" if p.strip() == "1" else "f" for p in parts[4:8])
+    return f"{alt_id}\t{gid}\t{lang}\t{name}\t{flags}\n"
+
+
+def _copy(conn, table: str, columns: str, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        with cur.copy(f"COPY {table} ({columns}) FROM STDIN") as copy:
+            copy.write(buf.read())
+    conn.commit()
+
+
+def _stream_zip(conn, zip_path: str, member: str, table: str, columns: str, to_row) -> int:
+    """Stream a zipped GeoNames dump through `to_row` and COPY it in batches."""
     total = 0
     start = time.time()
-    copy_sql = (
-        "COPY geonames (geonameid, name, latitude, longitude, "
-        "feature_code, country_code, admin1_code, admin2_code, "
-        "population, geom) FROM STDIN"
-    )
 
-    with zipfile.ZipFile(zip_path) as zf, zf.open("allCountries.txt") as raw:
+    with zipfile.ZipFile(zip_path) as zf, zf.open(member) as raw:
         buf = io.StringIO()
         buf_count = 0
 
         for line_bytes in raw:
-            parts = line_bytes.decode("utf-8", errors="replace").split("\t")
-            if len(parts) < 19 or parts[6].strip() != "P":
+            row = to_row(line_bytes.decode("utf-8", errors="replace").rstrip("\n").split("\t"))
+            if row is None:
                 continue
-
-            gid = parts[0].strip()
-            lat, lon = parts[4].strip(), parts[5].strip()
-            if not gid or not lat or not lon:
-                continue
-
-            name = parts[1].strip().replace("\t", " ").replace("\n", " ")
-            pop = parts[14].strip() or "0"
-
-            buf.write(
-                f"{gid}\t{name}\t{lat}\t{lon}\t"
-                f"{parts[7].strip()}\t{parts[8].strip()}\t{parts[10].strip()}\t{parts[11].strip()}\t"
-                f"{pop}\tSRID=4326;POINT({lon} {lat})\n"
-            )
+            buf.write(row)
             buf_count += 1
 
             if buf_count >= BATCH_SIZE:
-                buf.seek(0)
-                with conn.cursor() as cur:
-                    with cur.copy(copy_sql) as copy:
-                        copy.write(buf.read())
-                conn.commit()
+                _copy(conn, table, columns, buf)
                 total += buf_count
                 rate = total / (time.time() - start)
                 print(f"    {total:,} rows ({rate:,.0f}/s)")
                 buf, buf_count = io.StringIO(), 0
 
         if buf_count > 0:
-            buf.seek(0)
-            with conn.cursor() as cur:
-                with cur.copy(copy_sql) as copy:
-                    copy.write(buf.read())
-            conn.commit()
+            _copy(conn, table, columns, buf)
             total += buf_count
 
     elapsed = time.time() - start
-    print(f"  geonames: {total:,} rows in {elapsed:.1f}s")
+    print(f"  {table}: {total:,} rows in {elapsed:.1f}s")
     return total
 
 
+def _load_geonames(conn, zip_path: str) -> int:
+    """Stream allCountries.zip, filter to populated places, COPY into geonames."""
+    if not os.path.exists(zip_path):
+        print(f"ERROR: {zip_path} not found. Run: make download-geonames")
+        sys.exit(1)
+
+    with conn.cursor() as cur:
+        cur.execute("TRUNCATE geonames")
+    conn.commit()
+
+    return _stream_zip(conn, zip_path, "allCountries.txt", "geonames", GEONAMES_COLUMNS, _geonames_row)
+
+
+def _load_facilities(conn, zip_path: str) -> int:
+    """Stream allCountries.zip again, keeping hospitals, airports, ports and schools."""
+    with conn.cursor() as cur:
+        cur.execute("TRUNCATE facilities")
+    conn.commit()
+
+    return _stream_zip(conn, zip_path, "allCountries.txt", "facilities", FACILITY_COLUMNS, _facility_row)
+
+
+def _load_alternate_names(conn, zip_path: str) -> int:
+    """Stream alternateNamesV2.zip into alternate_names, keeping only names of loaded places."""
+    if not os.path.exists(zip_path):
+        print(f"  WARNING: {zip_path} not found, skipping")
+        return 0
+
+    with conn.cursor() as cur:
+        cur.execute("TRUNCATE alternate_names")
+    conn.commit()
+
+    total = _stream_zip(
+        conn, zip_path, "alternateNamesV2.txt", "alternate_names",
+        ALTERNATE_NAMES_COLUMNS, _alternate_name_row,
+    )
+
+    # The dump covers every feature class; keep names of the places we loaded
+    # and of the regions and countries /reverse names in addresses.
+    with conn.cursor() as cur:
+        cur.execute(f"DELETE FROM alternate_names a WHERE NOT ({NAMED_FEATURE})")
+        dropped = cur.rowcount
+    conn.commit()
+    print(f"  alternate_names: {total - dropped:,} rows kept ({dropped:,} for other features)")
+    return total - dropped
+
+
+# ── Incremental updates ──
+
+def _fetch_update_file(updates_dir: str, name: str) -> str | None:
+    """Download a daily update file unless already present. Returns None if GeoNames has none."""
+    path = os.path.join(updates_dir, name)
+    if os.path.exists(path):
+        return path
+    try:
+        with urllib.request.urlopen(f"{BASE_URL}/{name}", timeout=60) as resp:
+            data = resp.read()
+    except urllib.error.HTTPError as err:
+        if err.code == 404:
+            return None
+        raise
+    with open(path, "wb") as f:
+        f.write(data)
+    return path
+
+
+def _read_lines(path: str | None):
+    if path is None:
+        return
+    with open(path, "r", encoding="utf-8", errors="replace") as f:
+        for line in f:
+            if line.strip():
+                yield line.rstrip("\n").split("\t")
+
+
+def _apply_day(conn, updates_dir: str, day: datetime.date) -> tuple[int, int]:
+    """Apply one day of modifications/deletions inside a single transaction."""
+    stamp = day.isoformat()
+    modifications = _fetch_update_file(updates_dir, f"modifications-{stamp}.txt")
+    deletes = _fetch_update_file(updates_dir, f"deletes-{stamp}.txt")
+    alt_modifications = _fetch_update_file(updates_dir, f"alternateNamesModifications-{stamp}.txt")
+    alt_deletes = _fetch_update_file(updates_dir, f"alternateNamesDeletes-{stamp}.txt")
+
+    upserts = io.StringIO()
+    facility_upserts = io.StringIO()
+    removed_ids = []
+    removed_facilities = []
+    upsert_count = 0
+    for parts in _read_lines(modifications):
+        facility = _facility_row(parts)
+        if facility is not None:
+            facility_upserts.write(facility)
+        elif parts[0].strip():
+            removed_facilities.append(int(parts[0]))
+        row = _geonames_row(parts)
+        if row is None:
+            # A place that changed feature class away from 'P' no longer belongs in the table.
+            if parts[0].strip():
+                removed_ids.append(int(parts[0]))
+            continue
+        upserts.write(row)
+        upsert_count += 1
+    deleted_ids = [int(p[0]) for p in _read_lines(deletes) if p[0].strip()]
+    removed_ids.extend(deleted_ids)
+    removed_facilities.extend(deleted_ids)
+
+    alt_upserts = io.StringIO()
+    for parts in _read_lines(alt_modifications):
+        row = _alternate_name_row(parts)
+        if row is not None:
+            alt_upserts.write(row)
+    alt_removed = [int(p[0]) for p in _read_lines(alt_deletes) if p[0].strip()]
+
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE geonames_stage (LIKE geonames) ON COMMIT DROP")
+        upserts.seek(0)
+        with cur.copy(f"COPY geonames_stage ({GEONAMES_COLUMNS}) FROM STDIN") as copy:
+            copy.write(upserts.read())
+        cur.execute(f"""
+            INSERT INTO geonames ({GEONAMES_COLUMNS})
+            SELECT {GEONAMES_COLUMNS} FROM geonames_stage
+            ON CONFLICT (geonameid) DO UPDATE SET
+                name = EXCLUDED.name, latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude,
+                feature_code = EXCLUDED.feature_code, country_code = EXCLUDED.country_code,
+                admin1_code = EXCLUDED.admin1_code, admin2_code = EXCLUDED.admin2_code,
+                population = EXCLUDED.population, timezone = EXCLUDED.timezone, geom = EXCLUDED.geom
+        """)
+        cur.execute("DELETE FROM geonames WHERE geonameid = ANY(%s)", (removed_ids,))
+        deleted = cur.rowcount
+        cur.execute("DELETE FROM alternate_names WHERE geonameid = ANY(%s)", (removed_ids,))
+
+        cur.execute("CREATE TEMP TABLE facilities_stage (LIKE facilities) ON COMMIT DROP")
+        facility_upserts.seek(0)
+        with cur.copy(f"COPY facilities_stage ({FACILITY_COLUMNS}) FROM STDIN") as copy:
+            copy.write(facility_upserts.read())
+        cur.execute(f"""
+            INSERT INTO facilities ({FACILITY_COLUMNS})
+            SELECT {FACILITY_COLUMNS} FROM facilities_stage
+            ON CONFLICT (geonameid) DO UPDATE SET
+                name = EXCLUDED.name, latitude = EXCLUDED.latitude, longitude = EXCLUDED.longitude,
+                feature_code = EXCLUDED.feature_code, country_code = EXCLUDED.country_code, geom = EXCLUDED.geom
+        """)
+        # Features deleted, or recoded away from a facility code.
+        cur.execute("DELETE FROM facilities WHERE geonameid = ANY(%s)", (removed_facilities,))
+
+        cur.execute("CREATE TEMP TABLE alternate_names_stage (LIKE alternate_names) ON COMMIT DROP")
+        alt_upserts.seek(0)
+        with cur.copy(f"COPY alternate_names_stage ({ALTERNATE_NAMES_COLUMNS}) FROM STDIN") as copy:
+            copy.write(alt_upserts.read())
+        cur.execute(f"""
+            INSERT INTO alternate_names ({ALTERNATE_NAMES_COLUMNS})
+            SELECT {ALTERNATE_NAMES_COLUMNS} FROM alternate_names_stage a
+            WHERE {NAMED_FEATURE}
+            ON CONFLICT (alternatenameid) DO UPDATE SET
+                geonameid = EXCLUDED.geonameid, isolanguage = EXCLUDED.isolanguage,
+                name = EXCLUDED.name, is_preferred = EXCLUDED.is_preferred,
+                is_short = EXCLUDED.is_short, is_colloquial = EXCLUDED.is_colloquial,
+                is_historic = EXCLUDED.is_historic
+        """)
+        cur.execute("DELETE FROM alternate_names WHERE alternatenameid = ANY(%s)", (alt_removed,))
+
+        cur.execute(
+            """
+            INSERT INTO geonames_update_log (update_date, modified, deleted)
+            VALUES (%s, %s, %s)
+            ON CONFLICT (update_date) DO UPDATE SET
+                modified = EXCLUDED.modified, deleted = EXCLUDED.deleted, applied_at = now()
+            """,
+            (day, upsert_count, deleted),
+        )
+    conn.commit()
+    return upsert_count, deleted
+
+
+def _pending_days(conn, single: datetime.date | None) -> list[datetime.date]:
+    if single is not None:
+        return [single]
+
+    yesterday = datetime.date.today() - datetime.timedelta(days=1)
+    with conn.cursor() as cur:
+        cur.execute("SELECT MAX(update_date) FROM geonames_update_log")
+        last = cur.fetchone()[0]
+    if last is None:
+        # No history: assume the full dump is current and start with yesterday's diff.
+        last = yesterday - datetime.timedelta(days=1)
+
+    days = []
+    day = last + datetime.timedelta(days=1)
+    while day <= yesterday:
+        days.append(day)
+        day += datetime.timedelta(days=1)
+    return days
+
+
+def run_full(conn, data_dir: str) -> None:
+    print("Loading lookup tables...")
+    _load_tsv(conn, os.path.join(data_dir, "admin1CodesASCII.txt"), "admin1_codes")
+    _load_tsv(conn, os.path.join(data_dir, "admin2Codes.txt"), "admin2_codes")
+    _load_country_info(conn, os.path.join(data_dir, "countryInfo.txt"))
+
+    print("\nLoading populated places...")
+    _load_geonames(conn, os.path.join(data_dir, "allCountries.zip"))
+
+    print("\nLoading facilities...")
+    _load_facilities(conn, os.path.join(data_dir, "allCountries.zip"))
+
+    print("\nLoading alternate names...")
+    _load_alternate_names(conn, os.path.join(data_dir, "alternateNamesV2.zip"))
+
+    # The dump is a snapshot of "today"; daily diffs start from here.
+    with conn.cursor() as cur:
+        cur.execute("TRUNCATE geonames_update_log")
+        cur.execute(
+            "INSERT INTO geonames_update_log (update_date, modified, deleted) VALUES (%s, 0, 0)",
+            (datetime.date.today() - datetime.timedelta(days=1),),
+        )
+    conn.commit()
+
+
+def run_update(conn, data_dir: str, single: datetime.date | None) -> None:
+    updates_dir = os.path.join(data_dir, "updates")
+    os.makedirs(updates_dir, exist_ok=True)
+
+    days = _pending_days(conn, single)
+    if not days:
+        print("GeoNames is up to date.")
+        return
+
+    print(f"Applying {len(days)} daily update(s)...")
+    for day in days:
+        modified, deleted = _apply_day(conn, updates_dir, day)
+        print(f"  {day}: {modified:,} upserted, {deleted:,} deleted")
+
+    # admin1/admin2/countryInfo are republished in full every day and are tiny — just reload them.
+    print("\nRefreshing lookup tables...")
+    for name, table in (("admin1CodesASCII.txt", "admin1_codes"), ("admin2Codes.txt", "admin2_codes")):
+        path = os.path.join(updates_dir, name)
+        if os.path.exists(path):
+            os.remove(path)
+        if _fetch_update_file(updates_dir, name):
+            _load_tsv(conn, path, table)
+    path = os.path.join(updates_dir, "countryInfo.txt")
+    if os.path.exists(path):
+        os.remove(path)
+    if _fetch_update_file(updates_dir, "countryInfo.txt"):
+        _load_country_info(conn, path)
+
+
+def parse_args() -> argparse.Namespace:
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    sub = parser.add_subparsers(dest="command")
+    sub.add_parser("full", help="truncate and reload from the full dump files")
+    update = sub.add_parser("update", help="apply daily modification/deletion files")
+    update.add_argument("--date", type=datetime.date.fromisoformat, help="apply only this day (YYYY-MM-DD)")
+    args = parser.parse_args()
+    args.command = args.command or "full"
+    return args
+
+
 def main():
+    args = parse_args()
     db_url = get_db_url()
     data_dir = os.path.join(os.path.dirname(__file__), "..", "data", "geonames")
 
@@ -144,17 +503,15 @@ def main():
     conn = connect(db_url)
     conn.autocommit = False
 
-    print("Loading lookup tables...")
-    _load_tsv(conn, os.path.join(data_dir, "admin1CodesASCII.txt"), "admin1_codes", "code, name")
-    _load_tsv(conn, os.path.join(data_dir, "admin2Codes.txt"), "admin2_codes", "code, name")
-
-    print("\nLoading populated places...")
-    _load_geonames(conn, os.path.join(data_dir, "allCountries.zip"))
+    if args.command == "update":
+        run_update(conn, data_dir, getattr(args, "date", None))
+    else:
+        run_full(conn, data_dir)
 
     print("\nRunning VACUUM ANALYZE...")
     conn.autocommit = True
     with conn.cursor() as cur:
-        for t in ("admin1_codes", "admin2_codes", "geonames"):
+        for t in ("admin1_codes", "admin2_codes", "country_info", "geonames", "facilities", "alternate_names"):
             cur.execute(f"VACUUM ANALYZE {t}")
     conn.close()
     print("Complete.")
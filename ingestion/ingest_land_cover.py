@@ -0,0 +1,155 @@
+#!/usr/bin/env python3
+"""Ingest ESA WorldCover land cover into PostgreSQL.
+
+Reads ESA WorldCover (10 m, 3°×3° tiles) through a VRT mosaic at
+data/land_cover/worldcover.vrt — build one with
+`gdalbuildvrt data/land_cover/worldcover.vrt ESA_WorldCover_*_Map.tif` — and
+takes the most common class of each 30 arc-second cell (mode resampling). The
+class of every populated cell is stored in cell_land_cover, keyed by the
+canonical cell_id. Run `make ingest-worldpop` first; cells are matched against
+the population table band by band.
+
+WorldCover classes: 10 tree cover, 20 shrubland, 30 grassland, 40 cropland,
+50 built-up, 60 bare / sparse vegetation, 70 snow and ice, 80 permanent water
+bodies, 90 herbaceous wetland, 95 mangroves, 100 moss and lichen.
+"""
+
+import os, sys, time, io
+import numpy as np
+import rasterio
+import psycopg
+from rasterio.enums import Resampling
+from rasterio.transform import Affine
+from rasterio.vrt import WarpedVRT
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+BAND_ROWS = 120
+BATCH_SIZE = 500_000
+GRID_TRANSFORM = Affine(1 / 120, 0, -180, 0, -1 / 120, 90)
+CLASSES = np.array([10, 20, 30, 40, 50, 60, 70, 80, 90, 95, 100])
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_land_cover() -> str:
+    path = os.path.join(os.path.dirname(__file__), "..", "data", "land_cover", "worldcover.vrt")
+    if not os.path.exists(path):
+        print("ERROR: data/land_cover/worldcover.vrt not found. Download the ESA WorldCover map tiles")
+        print("       from https://esa-worldcover.org/ and mosaic them with gdalbuildvrt.")
+        sys.exit(1)
+    return path
+
+
+def ingest(land_cover_path: str, db_url: str) -> None:
+    print(f"Opening raster: {land_cover_path}")
+
+    with rasterio.open(land_cover_path) as src:
+        print(f"Raster: {src.width}x{src.height}, CRS={src.crs}, NoData={src.nodata}")
+        vrt_options = dict(
+            crs="EPSG:4326", transform=GRID_TRANSFORM, width=NCOLS, height=NROWS,
+            resampling=Resampling.mode,
+        )
+        with WarpedVRT(src, **vrt_options) as vrt:
+            conn = connect(db_url)
+            conn.autocommit = False
+            with conn.cursor() as cur:
+                cur.execute("TRUNCATE cell_land_cover")
+            conn.commit()
+            print("Truncated cell_land_cover table.")
+
+            total = 0
+            start = time.time()
+            buf, buf_count = io.StringIO(), 0
+            cols = np.arange(NCOLS, dtype=np.int64)
+
+            for band_start in range(0, NROWS, BAND_ROWS):
+                band_end = min(band_start + BAND_ROWS, NROWS)
+                rows = np.arange(band_start, band_end, dtype=np.int64)
+                populated = _populated_cells(conn, rows)
+                if populated.size:
+                    # Only read bands with people; the 10 m source is far too
+                    # large to resample wholesale.
+                    window = rasterio.windows.Window(0, band_start, NCOLS, band_end - band_start)
+                    cover = vrt.read(1, window=window)
+                    cell_ids = rows[:, np.newaxis] * NCOLS + cols[np.newaxis, :]
+                    keep = np.isin(cover, CLASSES) & np.isin(cell_ids, populated)
+                    for r, c in zip(*np.nonzero(keep)):
+                        buf.write(f"{cell_ids[r, c]}\t{cover[r, c]}\n")
+                        buf_count += 1
+
+                if buf_count >= BATCH_SIZE:
+                    _flush(conn, buf)
+                    total += buf_count
+                    buf, buf_count = io.StringIO(), 0
+
+                elapsed = time.time() - start
+                pct = band_end / NROWS * 100
+                print(f"  Row {band_end}/{NROWS} ({pct:.1f}%) — {total + buf_count:,} populated cells — {elapsed:.0f}s")
+
+            if buf_count > 0:
+                _flush(conn, buf)
+                total += buf_count
+
+    print(f"\nDone: {total:,} populated cells in {time.time() - start:.1f}s")
+    if total == 0:
+        print("WARNING: no populated cells matched — run `make ingest-worldpop` before this script.")
+
+    print("Running VACUUM ANALYZE...")
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE cell_land_cover")
+    conn.close()
+    print("Complete.")
+
+
+def _populated_cells(conn, rows: np.ndarray) -> np.ndarray:
+    """Populated cell_ids in the canonical row range covered by a band."""
+    lo, hi = int(rows.min()), int(rows.max())
+    with conn.cursor() as cur:
+        cur.execute(
+            "SELECT cell_id FROM population WHERE cell_id BETWEEN %s AND %s",
+            (lo * NCOLS, (hi + 1) * NCOLS - 1),
+        )
+        return np.fromiter((r[0] for r in cur), dtype=np.int64)
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE land_cover_stage (LIKE cell_land_cover) ON COMMIT DROP")
+        with cur.copy("COPY land_cover_stage (cell_id, class) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_land_cover (cell_id, class)
+            SELECT DISTINCT ON (cell_id) cell_id, class FROM land_cover_stage
+            ON CONFLICT (cell_id) DO UPDATE SET class = EXCLUDED.class
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    land_cover = find_land_cover()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(land_cover, url)
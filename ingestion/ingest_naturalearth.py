@@ -1,11 +1,19 @@
 #!/usr/bin/env python3
 """Ingest Natural Earth 10m country boundaries into PostgreSQL.
 
-Loads ISO codes, names, continent, region, population estimates,
-and MultiPolygon geometries into the countries table.
+Loads ISO codes (alpha-2, alpha-3, numeric), names, continent, region,
+population estimates, and MultiPolygon geometries into the countries table.
+
+Every load is tagged with a boundary vintage (default: the current year).
+Re-loading a vintage replaces only that vintage's rows, so older vintages stay
+queryable via `?boundaries=YYYY`. The loaded vintage becomes the default for
+unpinned queries unless --historical is passed.
+
+  python ingest_naturalearth.py                          # vintage = this year, current
+  python ingest_naturalearth.py --vintage 2023 --historical path/to/ne_2023.shp
 """
 
-import os, sys, time
+import argparse, datetime, os, sys, time
 import fiona
 import psycopg
 from shapely.geometry import shape, MultiPolygon
@@ -33,7 +41,12 @@ def get_db_url() -> str:
     return f"postgresql://{u}:{p}@{h}:{port}/{db}"
 
 
-def find_shapefile() -> str:
+def find_shapefile(path: str | None = None) -> str:
+    if path:
+        if os.path.exists(path):
+            return path
+        print(f"ERROR: Shapefile not found: {path}")
+        sys.exit(1)
     shp = os.path.join(os.path.dirname(__file__), "..", "data", "naturalearth", "ne_10m_admin_0_countries.shp")
     if os.path.exists(shp):
         return shp
@@ -41,20 +54,27 @@ def find_shapefile() -> str:
     sys.exit(1)
 
 
-def ingest(shp_path: str, db_url: str) -> None:
-    print(f"Opening shapefile: {shp_path}")
+def ingest(shp_path: str, db_url: str, vintage: int, historical: bool) -> None:
+    print(f"Opening shapefile: {shp_path} (boundary vintage {vintage})")
     conn = connect(db_url)
     conn.autocommit = False
 
     with conn.cursor() as cur:
-        cur.execute("TRUNCATE countries RESTART IDENTITY CASCADE")
+        cur.execute(
+            """
+            INSERT INTO boundary_vintages (vintage, source) VALUES (%s, %s)
+            ON CONFLICT (vintage) DO UPDATE SET source = EXCLUDED.source, loaded_at = now()
+            """,
+            (vintage, f"Natural Earth 10m ({os.path.basename(shp_path)})"),
+        )
+        cur.execute("DELETE FROM countries WHERE vintage = %s", (vintage,))
     conn.commit()
 
     count = skipped = 0
     insert_sql = """
-        INSERT INTO countries (iso_a2, iso_a3, name, formal_name,
+        INSERT INTO countries (vintage, iso_a2, iso_a3, iso_n3, name, formal_name,
             continent, region_un, subregion, type, sovereign, pop_est, geom)
-        VALUES (%s, %s, %s, %s, %s, %s, %s, %s, %s, %s, ST_GeomFromEWKT(%s))
+        VALUES (%s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, ST_GeomFromEWKT(%s))
     """
 
     with fiona.open(shp_path) as src:
@@ -70,6 +90,8 @@ def ingest(shp_path: str, db_url: str) -> None:
             iso_a3 = p.get("ISO_A3_EH", "")
             iso_a2 = None if iso_a2 in ("-99", "-1", "") else iso_a2
             iso_a3 = None if iso_a3 in ("-99", "-1", "") else iso_a3
+            iso_n3 = str(p.get("ISO_N3_EH") or p.get("ISO_N3") or "").strip()
+            iso_n3 = iso_n3.zfill(3) if iso_n3.isdigit() and int(iso_n3) > 0 else None
 
             ne_type = p.get("TYPE", "")
             admin = p.get("ADMIN", "")
@@ -91,7 +113,7 @@ def ingest(shp_path: str, db_url: str) -> None:
 
             with conn.cursor() as cur:
                 cur.execute(insert_sql, (
-                    iso_a2, iso_a3, name, p.get("FORMAL_EN") or None,
+                    vintage, iso_a2, iso_a3, iso_n3, name, p.get("FORMAL_EN") or None,
                     continent, p.get("REGION_UN") or None, p.get("SUBREGION") or None,
                     ne_type or None, sovereign, pop_est, f"SRID=4326;{geom.wkt}",
                 ))
@@ -99,18 +121,34 @@ def ingest(shp_path: str, db_url: str) -> None:
             if count % 50 == 0:
                 conn.commit()
 
+    if not historical:
+        with conn.cursor() as cur:
+            cur.execute("UPDATE boundary_vintages SET is_current = false WHERE is_current AND vintage <> %s", (vintage,))
+            cur.execute("UPDATE boundary_vintages SET is_current = true WHERE vintage = %s", (vintage,))
     conn.commit()
-    print(f"Loaded {count} countries ({skipped} skipped).")
+    print(f"Loaded {count} countries ({skipped} skipped) as vintage {vintage}"
+          f"{' (historical)' if historical else ' (current)'}.")
 
     conn.autocommit = True
     with conn.cursor() as cur:
         cur.execute("VACUUM ANALYZE countries")
+        # Grid totals for the new polygons; a no-op before WorldPop is loaded.
+        print(f"Refreshing country grid totals for vintage {vintage}...")
+        cur.execute("SELECT refresh_country_population(%s)", (vintage,))
     conn.close()
     print("Complete.")
 
 
 if __name__ == "__main__":
-    shp = find_shapefile()
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("shapefile", nargs="?", help="shapefile to load (default: data/naturalearth/...)")
+    parser.add_argument("--vintage", type=int, default=datetime.date.today().year,
+                        help="boundary vintage (release year) to tag this load with")
+    parser.add_argument("--historical", action="store_true",
+                        help="load without making this vintage the default for unpinned queries")
+    args = parser.parse_args()
+
+    shp = find_shapefile(args.shapefile)
     url = get_db_url()
     print(f"Database: {url.split('@')[1] if '@' in url else url}")
-    ingest(shp, url)
+    ingest(shp, url, args.vintage, args.historical)
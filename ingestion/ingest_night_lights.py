@@ -0,0 +1,138 @@
+#!/usr/bin/env python3
+"""Ingest VIIRS nighttime lights into PostgreSQL.
+
+Reads an annual VIIRS nighttime-lights composite (e.g. the Earth Observation
+Group's VNL v2 "average_masked" product, 15 arc-second GeoTIFF in nW/cm²/sr)
+from data/night_lights/viirs.tif, averages it onto the 30 arc-second population
+grid and stores the radiance of every lit cell in cell_night_lights, keyed by
+the canonical cell_id.
+
+The masked products already zero out background noise, fires and aurora;
+negative or zero radiance is dropped, so unlit cells are simply absent.
+"""
+
+import os, sys, time, io
+import numpy as np
+import rasterio
+import psycopg
+from rasterio.enums import Resampling
+from rasterio.transform import Affine
+from rasterio.vrt import WarpedVRT
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+BAND_ROWS = 480
+BATCH_SIZE = 500_000
+GRID_TRANSFORM = Affine(1 / 120, 0, -180, 0, -1 / 120, 90)
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_lights() -> str:
+    path = os.path.join(os.path.dirname(__file__), "..", "data", "night_lights", "viirs.tif")
+    if not os.path.exists(path):
+        print("ERROR: data/night_lights/viirs.tif not found. Download an annual VIIRS composite")
+        print("       (e.g. VNL v2 average_masked from https://eogdata.mines.edu/products/vnl/)")
+        print("       and place it there.")
+        sys.exit(1)
+    return path
+
+
+def ingest(lights_path: str, db_url: str) -> None:
+    print(f"Opening raster: {lights_path}")
+
+    with rasterio.open(lights_path) as src:
+        print(f"Raster: {src.width}x{src.height}, CRS={src.crs}, NoData={src.nodata}")
+        vrt_options = dict(
+            crs="EPSG:4326", transform=GRID_TRANSFORM, width=NCOLS, height=NROWS,
+            resampling=Resampling.average,
+        )
+        with WarpedVRT(src, **vrt_options) as vrt:
+            conn = connect(db_url)
+            conn.autocommit = False
+            with conn.cursor() as cur:
+                cur.execute("TRUNCATE cell_night_lights")
+            conn.commit()
+            print("Truncated cell_night_lights table.")
+
+            total = 0
+            start = time.time()
+            buf, buf_count = io.StringIO(), 0
+            cols = np.arange(NCOLS, dtype=np.int64)
+
+            for band_start in range(0, NROWS, BAND_ROWS):
+                band_end = min(band_start + BAND_ROWS, NROWS)
+                window = rasterio.windows.Window(0, band_start, NCOLS, band_end - band_start)
+                radiance = vrt.read(1, window=window).astype(np.float32)
+                keep = np.isfinite(radiance) & (radiance > 0)
+                if src.nodata is not None:
+                    keep &= radiance != src.nodata
+
+                rows = np.arange(band_start, band_end, dtype=np.int64)
+                cell_ids = rows[:, np.newaxis] * NCOLS + cols[np.newaxis, :]
+                for r, c in zip(*np.nonzero(keep)):
+                    buf.write(f"{cell_ids[r, c]}\t{radiance[r, c]:.2f}\n")
+                    buf_count += 1
+
+                if buf_count >= BATCH_SIZE:
+                    _flush(conn, buf)
+                    total += buf_count
+                    buf, buf_count = io.StringIO(), 0
+
+                elapsed = time.time() - start
+                pct = band_end / NROWS * 100
+                print(f"  Row {band_end}/{NROWS} ({pct:.1f}%) — {total + buf_count:,} lit cells — {elapsed:.0f}s")
+
+            if buf_count > 0:
+                _flush(conn, buf)
+                total += buf_count
+
+    print(f"\nDone: {total:,} lit cells in {time.time() - start:.1f}s")
+
+    print("Running VACUUM ANALYZE...")
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE cell_night_lights")
+    conn.close()
+    print("Complete.")
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE night_lights_stage (LIKE cell_night_lights) ON COMMIT DROP")
+        with cur.copy("COPY night_lights_stage (cell_id, radiance) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_night_lights (cell_id, radiance)
+            SELECT DISTINCT ON (cell_id) cell_id, radiance FROM night_lights_stage
+            ON CONFLICT (cell_id) DO UPDATE SET radiance = EXCLUDED.radiance
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    lights = find_lights()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(lights, url)
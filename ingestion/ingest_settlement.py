@@ -0,0 +1,152 @@
+#!/usr/bin/env python3
+"""Ingest the GHSL Settlement Model (GHS-SMOD) into PostgreSQL.
+
+Reads the global GHS-SMOD grid (1 km, World Mollweide, level-2 codes) from
+data/settlement/smod.tif, resamples it nearest-neighbour onto the 30 arc-second
+population grid, and stores the code of every populated cell in
+cell_settlement, keyed by the canonical cell_id. Run `make ingest-worldpop`
+first; cells are matched against the population table band by band.
+
+Level-2 codes: 30 urban centre; 23 dense, 22 semi-dense and 21 suburban urban
+cluster; 13 rural cluster, 12 low-density and 11 very-low-density rural; 10
+water. The API folds them into urban centre / urban cluster / rural.
+"""
+
+import os, sys, time, io
+import numpy as np
+import rasterio
+import psycopg
+from rasterio.enums import Resampling
+from rasterio.transform import Affine
+from rasterio.vrt import WarpedVRT
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+BAND_ROWS = 480
+BATCH_SIZE = 500_000
+GRID_TRANSFORM = Affine(1 / 120, 0, -180, 0, -1 / 120, 90)
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_smod() -> str:
+    path = os.path.join(os.path.dirname(__file__), "..", "data", "settlement", "smod.tif")
+    if not os.path.exists(path):
+        print("ERROR: data/settlement/smod.tif not found. Download GHS-SMOD (1 km, Mollweide)")
+        print("       from https://ghsl.jrc.ec.europa.eu/download.php?ds=smod and place it there.")
+        sys.exit(1)
+    return path
+
+
+def ingest(smod_path: str, db_url: str) -> None:
+    print(f"Opening raster: {smod_path}")
+
+    with rasterio.open(smod_path) as src:
+        print(f"Raster: {src.width}x{src.height}, CRS={src.crs}, NoData={src.nodata}")
+        vrt_options = dict(
+            crs="EPSG:4326", transform=GRID_TRANSFORM, width=NCOLS, height=NROWS,
+            resampling=Resampling.nearest,
+        )
+        with WarpedVRT(src, **vrt_options) as vrt:
+            conn = connect(db_url)
+            conn.autocommit = False
+            with conn.cursor() as cur:
+                cur.execute("TRUNCATE cell_settlement")
+            conn.commit()
+            print("Truncated cell_settlement table.")
+
+            total = 0
+            start = time.time()
+            buf, buf_count = io.StringIO(), 0
+            cols = np.arange(NCOLS, dtype=np.int64)
+
+            for band_start in range(0, NROWS, BAND_ROWS):
+                band_end = min(band_start + BAND_ROWS, NROWS)
+                rows = np.arange(band_start, band_end, dtype=np.int64)
+                populated = _populated_cells(conn, rows)
+                if populated.size:
+                    window = rasterio.windows.Window(0, band_start, NCOLS, band_end - band_start)
+                    smod = vrt.read(1, window=window)
+                    cell_ids = rows[:, np.newaxis] * NCOLS + cols[np.newaxis, :]
+                    keep = (smod >= 10) & (smod <= 30) & np.isin(cell_ids, populated)
+                    if src.nodata is not None:
+                        keep &= smod != src.nodata
+                    for r, c in zip(*np.nonzero(keep)):
+                        buf.write(f"{cell_ids[r, c]}\t{smod[r, c]}\n")
+                        buf_count += 1
+
+                if buf_count >= BATCH_SIZE:
+                    _flush(conn, buf)
+                    total += buf_count
+                    buf, buf_count = io.StringIO(), 0
+
+                elapsed = time.time() - start
+                pct = band_end / NROWS * 100
+                print(f"  Row {band_end}/{NROWS} ({pct:.1f}%) — {total + buf_count:,} populated cells — {elapsed:.0f}s")
+
+            if buf_count > 0:
+                _flush(conn, buf)
+                total += buf_count
+
+    print(f"\nDone: {total:,} populated cells in {time.time() - start:.1f}s")
+    if total == 0:
+        print("WARNING: no populated cells matched — run `make ingest-worldpop` before this script.")
+
+    print("Running VACUUM ANALYZE...")
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE cell_settlement")
+    conn.close()
+    print("Complete.")
+
+
+def _populated_cells(conn, rows: np.ndarray) -> np.ndarray:
+    """Populated cell_ids in the canonical row range covered by a band."""
+    lo, hi = int(rows.min()), int(rows.max())
+    with conn.cursor() as cur:
+        cur.execute(
+            "SELECT cell_id FROM population WHERE cell_id BETWEEN %s AND %s",
+            (lo * NCOLS, (hi + 1) * NCOLS - 1),
+        )
+        return np.fromiter((r[0] for r in cur), dtype=np.int64)
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE settlement_stage (LIKE cell_settlement) ON COMMIT DROP")
+        with cur.copy("COPY settlement_stage (cell_id, smod) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_settlement (cell_id, smod)
+            SELECT DISTINCT ON (cell_id) cell_id, smod FROM settlement_stage
+            ON CONFLICT (cell_id) DO UPDATE SET smod = EXCLUDED.smod
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    smod = find_smod()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(smod, url)
@@ -0,0 +1,210 @@
+#!/usr/bin/env python3
+"""Ingest terrain (elevation + distance to coast) into PostgreSQL.
+
+Reads a global 30 arc-second DEM GeoTIFF (e.g. ETOPO 2022 30s surface
+elevation) from data/terrain/dem.tif and fills two tables keyed by the
+canonical cell_id used by the population grid:
+
+  cell_elevation — elevation of every populated cell (flood exposure). Run
+                   `make ingest-worldpop` first; cells are matched against the
+                   population table band by band.
+  cell_terrain   — elevation and distance to the nearest sea cell (elevation
+                   <= 0, via a Euclidean distance transform) for land cells
+                   within MAX_COAST_KM of the coast and at most MAX_ELEVATION_M
+                   high — the envelope /exposure's coastal mode can query.
+
+Land below sea level away from the ocean (Dead Sea, Caspian depression) is
+treated as sea by the coastal mask; cell_elevation stores its true elevation.
+"""
+
+import os, sys, time, io
+import numpy as np
+import rasterio
+import psycopg
+from scipy import ndimage
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+KM_PER_DEG = 111.32
+MAX_COAST_KM = 100.0
+MAX_ELEVATION_M = 100.0
+BAND_ROWS = 480
+HALO_ROWS = int(np.ceil(MAX_COAST_KM / KM_PER_DEG * 120)) + 1
+BATCH_SIZE = 500_000
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_dem() -> str:
+    path = os.path.join(os.path.dirname(__file__), "..", "data", "terrain", "dem.tif")
+    if not os.path.exists(path):
+        print("ERROR: data/terrain/dem.tif not found. Place a global 30 arc-second DEM there")
+        print("       (e.g. ETOPO 2022 30s surface elevation, converted to GeoTIFF).")
+        sys.exit(1)
+    return path
+
+
+def ingest(dem_path: str, db_url: str) -> None:
+    print(f"Opening raster: {dem_path}")
+
+    with rasterio.open(dem_path) as src:
+        t = src.transform
+        if abs(t.a - 1 / 120) > 1e-6 or abs(t.e + 1 / 120) > 1e-6:
+            print(f"ERROR: expected 30 arc-second pixels, got {t.a:.8f} x {t.e:.8f} degrees")
+            sys.exit(1)
+        print(f"Raster: {src.width}x{src.height}, CRS={src.crs}, NoData={src.nodata}")
+
+        row_lats = t.f + (np.arange(src.height) + 0.5) * t.e
+        canonical_rows = np.floor((90.0 - row_lats) * 120.0).astype(np.int64)
+        col_lons = t.c + (np.arange(src.width) + 0.5) * t.a
+        canonical_cols = np.floor((col_lons + 180.0) * 120.0).astype(np.int64)
+        valid_cols = (canonical_cols >= 0) & (canonical_cols < NCOLS)
+
+        conn = connect(db_url)
+        conn.autocommit = False
+        with conn.cursor() as cur:
+            cur.execute("TRUNCATE cell_terrain, cell_elevation")
+        conn.commit()
+        print("Truncated cell_terrain and cell_elevation tables.")
+
+        total = total_elev = 0
+        start = time.time()
+        buf, elev_buf = io.StringIO(), io.StringIO()
+        buf_count = elev_count = 0
+
+        for band_start in range(0, src.height, BAND_ROWS):
+            band_end = min(band_start + BAND_ROWS, src.height)
+            read_start = max(band_start - HALO_ROWS, 0)
+            read_end = min(band_end + HALO_ROWS, src.height)
+
+            window = rasterio.windows.Window(0, read_start, src.width, read_end - read_start)
+            elev = src.read(1, window=window).astype(np.float32)
+            sea = ~np.isfinite(elev) | (elev <= 0)
+            if src.nodata is not None:
+                sea |= elev == src.nodata
+
+            # Distance in km to the nearest sea cell. Longitude spacing shrinks
+            # with latitude; use the band's centre latitude for the whole band.
+            mid_lat = row_lats[(band_start + band_end) // 2]
+            col_km = KM_PER_DEG * max(np.cos(np.radians(mid_lat)), 0.01) / 120
+            coast_km = ndimage.distance_transform_edt(~sea, sampling=(KM_PER_DEG / 120, col_km))
+
+            core = slice(band_start - read_start, band_end - read_start)
+            core_elev, core_coast = elev[core], coast_km[core]
+            rows_ok = (canonical_rows[band_start:band_end] >= 0) & (canonical_rows[band_start:band_end] < NROWS)
+            in_grid = rows_ok[:, np.newaxis] & valid_cols[np.newaxis, :]
+            cell_ids = canonical_rows[band_start:band_end, np.newaxis] * NCOLS + canonical_cols[np.newaxis, :]
+
+            keep = in_grid & ~sea[core] & (core_coast <= MAX_COAST_KM) & (core_elev <= MAX_ELEVATION_M)
+            for r, c in zip(*np.nonzero(keep)):
+                buf.write(f"{cell_ids[r, c]}\t{core_elev[r, c]:.1f}\t{core_coast[r, c]:.2f}\n")
+                buf_count += 1
+
+            populated = in_grid & np.isfinite(core_elev)
+            if src.nodata is not None:
+                populated &= core_elev != src.nodata
+            populated &= np.isin(cell_ids, _populated_cells(conn, canonical_rows[band_start:band_end]))
+            for r, c in zip(*np.nonzero(populated)):
+                elev_buf.write(f"{cell_ids[r, c]}\t{core_elev[r, c]:.1f}\n")
+                elev_count += 1
+
+            if buf_count >= BATCH_SIZE:
+                _flush(conn, buf)
+                total += buf_count
+                buf, buf_count = io.StringIO(), 0
+            if elev_count >= BATCH_SIZE:
+                _flush_elevation(conn, elev_buf)
+                total_elev += elev_count
+                elev_buf, elev_count = io.StringIO(), 0
+
+            elapsed = time.time() - start
+            pct = band_end / src.height * 100
+            print(f"  Row {band_end}/{src.height} ({pct:.1f}%) — {total + buf_count:,} coastal, "
+                  f"{total_elev + elev_count:,} populated cells — {elapsed:.0f}s")
+
+        if buf_count > 0:
+            _flush(conn, buf)
+            total += buf_count
+        if elev_count > 0:
+            _flush_elevation(conn, elev_buf)
+            total_elev += elev_count
+
+        print(f"\nDone: {total:,} coastal cells, {total_elev:,} populated cells in {time.time() - start:.1f}s")
+        if total_elev == 0:
+            print("WARNING: no populated cells matched — run `make ingest-worldpop` before this script.")
+
+        print("Running VACUUM ANALYZE...")
+        conn.autocommit = True
+        with conn.cursor() as cur:
+            cur.execute("VACUUM ANALYZE cell_terrain")
+            cur.execute("VACUUM ANALYZE cell_elevation")
+        conn.close()
+        print("Complete.")
+
+
+def _populated_cells(conn, rows: np.ndarray) -> np.ndarray:
+    """Populated cell_ids in the canonical row range covered by a band."""
+    lo, hi = int(rows.min()), int(rows.max())
+    with conn.cursor() as cur:
+        cur.execute(
+            "SELECT cell_id FROM population WHERE cell_id BETWEEN %s AND %s",
+            (max(lo, 0) * NCOLS, (min(hi, NROWS - 1) + 1) * NCOLS - 1),
+        )
+        return np.fromiter((r[0] for r in cur), dtype=np.int64)
+
+
+def _flush_elevation(conn, buf: io.StringIO) -> None:
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE elevation_stage (LIKE cell_elevation) ON COMMIT DROP")
+        with cur.copy("COPY elevation_stage (cell_id, elevation_m) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_elevation (cell_id, elevation_m)
+            SELECT DISTINCT ON (cell_id) cell_id, elevation_m FROM elevation_stage
+            ON CONFLICT (cell_id) DO UPDATE SET elevation_m = EXCLUDED.elevation_m
+        """)
+    conn.commit()
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    """COPY into a staging table, then upsert — rasters that wrap the antimeridian repeat cells."""
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE terrain_stage (LIKE cell_terrain) ON COMMIT DROP")
+        with cur.copy("COPY terrain_stage (cell_id, elevation_m, coast_km) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO cell_terrain (cell_id, elevation_m, coast_km)
+            SELECT DISTINCT ON (cell_id) cell_id, elevation_m, coast_km FROM terrain_stage
+            ON CONFLICT (cell_id) DO UPDATE SET
+                elevation_m = EXCLUDED.elevation_m, coast_km = EXCLUDED.coast_km
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    dem = find_dem()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+    ingest(dem, url)
@@ -0,0 +1,143 @@
+#!/usr/bin/env python3
+"""Ingest WorldPop uncertainty rasters into PostgreSQL.
+
+WorldPop publishes 95% credible-interval rasters (lower / upper bound) for the
+countries covered by its bottom-up models. Place each pair in data/uncertainty/
+named `<prefix>_lower.tif` and `<prefix>_upper.tif`; every pair found is mapped
+onto the canonical 30 arc-second cell_id (same as ingest.py) and upserted into
+population_uncertainty. Cells outside any pair simply have no interval.
+"""
+
+import os, sys, time, io, glob
+import numpy as np
+import rasterio
+import psycopg
+
+NCOLS = 43200   # 360° × 120
+NROWS = 21600   # 180° × 120
+BATCH_SIZE = 500_000
+
+
+def connect(db_url: str, retries: int = 30) -> psycopg.Connection:
+    for attempt in range(retries):
+        try:
+            return psycopg.connect(db_url, connect_timeout=5)
+        except psycopg.OperationalError:
+            if attempt == retries - 1:
+                raise
+            print(f"  DB not ready (attempt {attempt + 1}/{retries}), retrying...")
+            time.sleep(2)
+
+
+def get_db_url() -> str:
+    if url := os.environ.get("DATABASE_URL"):
+        return url
+    u = os.environ.get("POSTGRES_USER", "geopop")
+    p = os.environ.get("POSTGRES_PASSWORD", "geopop")
+    h = os.environ.get("POSTGRES_HOST", "localhost")
+    port = os.environ.get("POSTGRES_PORT", "5432")
+    db = os.environ.get("POSTGRES_DB", "geopop")
+    return f"postgresql://{u}:{p}@{h}:{port}/{db}"
+
+
+def find_pairs() -> list[tuple[str, str]]:
+    data_dir = os.path.join(os.path.dirname(__file__), "..", "data", "uncertainty")
+    pairs = []
+    for lower in sorted(glob.glob(os.path.join(data_dir, "*_lower.tif"))):
+        upper = lower[: -len("_lower.tif")] + "_upper.tif"
+        if os.path.exists(upper):
+            pairs.append((lower, upper))
+        else:
+            print(f"  WARNING: {upper} missing, skipping {lower}")
+    if not pairs:
+        print("ERROR: No *_lower.tif / *_upper.tif pairs found in data/uncertainty/")
+        sys.exit(1)
+    return pairs
+
+
+def ingest_pair(conn, lower_path: str, upper_path: str) -> int:
+    print(f"Opening rasters: {os.path.basename(lower_path)} / {os.path.basename(upper_path)}")
+
+    with rasterio.open(lower_path) as lo, rasterio.open(upper_path) as hi:
+        if lo.shape != hi.shape or lo.transform != hi.transform:
+            print("  ERROR: lower/upper rasters are not aligned, skipping")
+            return 0
+
+        t = lo.transform
+        row_lats = t.f + (np.arange(lo.height) + 0.5) * t.e
+        canonical_rows = np.floor((90.0 - row_lats) * 120.0).astype(np.int64)
+        col_lons = t.c + (np.arange(lo.width) + 0.5) * t.a
+        canonical_cols = np.floor((col_lons + 180.0) * 120.0).astype(np.int64)
+
+        total = 0
+        buf = io.StringIO()
+        buf_count = 0
+
+        for row_idx in range(lo.height):
+            crow = canonical_rows[row_idx]
+            if crow < 0 or crow >= NROWS:
+                continue
+
+            window = rasterio.windows.Window(0, row_idx, lo.width, 1)
+            lower = lo.read(1, window=window).flatten()
+            upper = hi.read(1, window=window).flatten()
+            mask = np.isfinite(lower) & np.isfinite(upper) & (upper > 0)
+            if lo.nodata is not None:
+                mask &= lower != lo.nodata
+            if hi.nodata is not None:
+                mask &= upper != hi.nodata
+
+            for idx in np.where(mask)[0]:
+                ccol = canonical_cols[idx]
+                if ccol < 0 or ccol >= NCOLS:
+                    continue
+                cell_id = int(crow) * NCOLS + int(ccol)
+                buf.write(f"{cell_id}\t{max(lower[idx], 0):.6g}\t{upper[idx]:.6g}\n")
+                buf_count += 1
+
+            if buf_count >= BATCH_SIZE:
+                _flush(conn, buf)
+                total += buf_count
+                buf, buf_count = io.StringIO(), 0
+
+        if buf_count > 0:
+            _flush(conn, buf)
+            total += buf_count
+
+    print(f"  {total:,} cells")
+    return total
+
+
+def _flush(conn, buf: io.StringIO) -> None:
+    """COPY into a staging table, then upsert — overlapping country rasters keep the last value."""
+    buf.seek(0)
+    with conn.cursor() as cur:
+        cur.execute("CREATE TEMP TABLE uncertainty_stage (LIKE population_uncertainty) ON COMMIT DROP")
+        with cur.copy("COPY uncertainty_stage (cell_id, pop_lower, pop_upper) FROM STDIN") as copy:
+            copy.write(buf.read())
+        cur.execute("""
+            INSERT INTO population_uncertainty (cell_id, pop_lower, pop_upper)
+            SELECT DISTINCT ON (cell_id) cell_id, pop_lower, pop_upper FROM uncertainty_stage
+            ON CONFLICT (cell_id) DO UPDATE SET
+                pop_lower = EXCLUDED.pop_lower, pop_upper = EXCLUDED.pop_upper
+        """)
+    conn.commit()
+
+
+if __name__ == "__main__":
+    pairs = find_pairs()
+    url = get_db_url()
+    print(f"Database: {url.split('@')[1] if '@' in url else url}")
+
+    conn = connect(url)
+    conn.autocommit = False
+    start = time.time()
+    total = sum(ingest_pair(conn, lower, upper) for lower, upper in pairs)
+    print(f"\nDone: {total:,} cells from {len(pairs)} raster pair(s) in {time.time() - start:.1f}s")
+
+    print("Running VACUUM ANALYZE...")
+    conn.autocommit = True
+    with conn.cursor() as cur:
+        cur.execute("VACUUM ANALYZE population_uncertainty")
+    conn.close()
+    print("Complete.")